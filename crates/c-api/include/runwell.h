@@ -0,0 +1,113 @@
+/*
+ * Copyright 2021 Robin Freyler
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/*
+ * C API to embed the Runwell interpreter.
+ *
+ * Fallible functions return a status code and write their results through
+ * out-pointers. Modules are reference counted and instances hold a reference
+ * to their module, so modules may be released before their instances.
+ *
+ * Function arguments and results are passed as raw 64-bit values.
+ * Integers are zero extended and floats are passed by their bit pattern.
+ */
+
+#ifndef RUNWELL_H
+#define RUNWELL_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum runwell_status {
+    RUNWELL_OK = 0,
+    RUNWELL_NULL_POINTER = 1,
+    RUNWELL_INVALID_UTF8 = 2,
+    RUNWELL_INVALID_MODULE = 3,
+    RUNWELL_INSTANTIATION_FAILED = 4,
+    RUNWELL_UNKNOWN_FUNCTION = 5,
+    RUNWELL_UNMATCHING_ARGUMENTS = 6,
+    RUNWELL_TRAP = 7,
+    RUNWELL_EVALUATION_FAILED = 8,
+    RUNWELL_MISSING_MEMORY = 9,
+    RUNWELL_MEMORY_OUT_OF_BOUNDS = 10,
+    RUNWELL_BUFFER_TOO_SMALL = 11,
+    RUNWELL_PANIC = 12,
+} runwell_status_t;
+
+typedef struct runwell_module runwell_module_t;
+typedef struct runwell_instance runwell_instance_t;
+
+/* Finalizes host data once the handle it is attached to is deleted. */
+typedef void (*runwell_finalizer_t)(void *data);
+
+/* Returns the static description of the status code. Must not be freed. */
+const char *runwell_status_message(runwell_status_t status);
+
+/* Creates a module with a reference count of one. */
+runwell_status_t runwell_module_new(
+    const uint8_t *bytes,
+    size_t len,
+    const runwell_module_t **out);
+const runwell_module_t *runwell_module_retain(const runwell_module_t *module);
+void runwell_module_release(const runwell_module_t *module);
+
+runwell_status_t runwell_instance_new(
+    const runwell_module_t *module,
+    runwell_instance_t **out);
+void runwell_instance_delete(runwell_instance_t *instance);
+
+/* Attaches host data that is finalized once the instance is deleted. */
+runwell_status_t runwell_instance_set_user_data(
+    runwell_instance_t *instance,
+    void *data,
+    runwell_finalizer_t finalizer);
+void *runwell_instance_user_data(const runwell_instance_t *instance);
+
+runwell_status_t runwell_instance_call(
+    runwell_instance_t *instance,
+    const char *name,
+    const uint64_t *args,
+    size_t args_len,
+    uint64_t *results,
+    size_t results_len,
+    size_t *out_results_len);
+
+runwell_status_t runwell_instance_memory_size(
+    const runwell_instance_t *instance,
+    uint32_t memory,
+    size_t *out_len);
+runwell_status_t runwell_instance_memory_read(
+    const runwell_instance_t *instance,
+    uint32_t memory,
+    size_t offset,
+    uint8_t *buffer,
+    size_t len);
+runwell_status_t runwell_instance_memory_write(
+    runwell_instance_t *instance,
+    uint32_t memory,
+    size_t offset,
+    const uint8_t *buffer,
+    size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RUNWELL_H */
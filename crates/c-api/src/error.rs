@@ -0,0 +1,119 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use interpreter::InterpretationError;
+use std::{
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+};
+
+/// The status code returned by the fallible functions of the C API.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RunwellStatus {
+    /// The operation succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The Wasm module could not be parsed or validated.
+    InvalidModule = 3,
+    /// The instance could not be created, e.g. since its linear memories
+    /// could not be allocated.
+    InstantiationFailed = 4,
+    /// The module has no exported function with the given name.
+    UnknownFunction = 5,
+    /// The arguments do not match the inputs of the function.
+    UnmatchingArguments = 6,
+    /// The evaluation of the function has trapped.
+    Trap = 7,
+    /// The evaluation of the function failed for another reason.
+    EvaluationFailed = 8,
+    /// The instance has no linear memory with the given index.
+    MissingMemory = 9,
+    /// The accessed range is out of bounds of the linear memory.
+    MemoryOutOfBounds = 10,
+    /// The provided buffer is too small to hold all results.
+    BufferTooSmall = 11,
+    /// The operation panicked.
+    ///
+    /// The handles involved remain valid but might be in an unexpected state.
+    Panic = 12,
+}
+
+impl From<InterpretationError> for RunwellStatus {
+    fn from(error: InterpretationError) -> Self {
+        if error.trap_code().is_some() {
+            return Self::Trap
+        }
+        match error {
+            InterpretationError::UnknownExportedFunction { .. } => {
+                Self::UnknownFunction
+            }
+            InterpretationError::UnmatchingInputValues { .. }
+            | InterpretationError::UnmatchingArgumentTypes { .. } => {
+                Self::UnmatchingArguments
+            }
+            InterpretationError::MissingMemory { .. } => Self::MissingMemory,
+            _ => Self::EvaluationFailed,
+        }
+    }
+}
+
+impl RunwellStatus {
+    /// Returns the human readable and nul-terminated description of the status.
+    fn message(self) -> &'static [u8] {
+        match self {
+            Self::Ok => b"the operation succeeded\0",
+            Self::NullPointer => b"a required pointer argument was null\0",
+            Self::InvalidUtf8 => b"a string argument was not valid UTF-8\0",
+            Self::InvalidModule => b"the Wasm module is invalid\0",
+            Self::InstantiationFailed => b"failed to instantiate the module\0",
+            Self::UnknownFunction => b"the exported function does not exist\0",
+            Self::UnmatchingArguments => {
+                b"the arguments do not match the function inputs\0"
+            }
+            Self::Trap => b"the function evaluation has trapped\0",
+            Self::EvaluationFailed => b"the function evaluation failed\0",
+            Self::MissingMemory => b"the linear memory does not exist\0",
+            Self::MemoryOutOfBounds => {
+                b"out of bounds access of the linear memory\0"
+            }
+            Self::BufferTooSmall => b"the result buffer is too small\0",
+            Self::Panic => b"the operation panicked\0",
+        }
+    }
+}
+
+/// Returns the static nul-terminated description of the status code.
+///
+/// The returned string must not be freed by the caller.
+#[no_mangle]
+pub extern "C" fn runwell_status_message(
+    status: RunwellStatus,
+) -> *const c_char {
+    status.message().as_ptr() as *const c_char
+}
+
+/// Runs the operation and converts a panic into [`RunwellStatus::Panic`].
+///
+/// Panics must not unwind across the C API boundary.
+pub(crate) fn catch_panic<F>(operation: F) -> RunwellStatus
+where
+    F: FnOnce() -> RunwellStatus,
+{
+    panic::catch_unwind(AssertUnwindSafe(operation))
+        .unwrap_or(RunwellStatus::Panic)
+}
@@ -0,0 +1,325 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    error::catch_panic,
+    module::clone_module,
+    raw::{slice_from_raw, slice_from_raw_mut},
+    user_data::UserData,
+    RunwellFinalizer,
+    RunwellModule,
+    RunwellStatus,
+};
+use entity::RawIdx;
+use interpreter::{EvaluationContext, LinearMemory, VecMemoryCreator};
+use ir::primitive::Mem;
+use module::Module;
+use std::{
+    ffi::CStr,
+    ops::Range,
+    os::raw::{c_char, c_void},
+    ptr,
+    sync::Arc,
+};
+
+/// An instance of a Wasm module with its own linear memories and globals.
+///
+/// The instance holds a reference to the module it was created from.
+#[derive(Debug)]
+pub struct RunwellInstance {
+    /// The evaluation context that borrows the module of the instance.
+    ///
+    /// Declared before `module` so that it is dropped before the module.
+    ctx: EvaluationContext<'static>,
+    /// Keeps the module of the instance alive.
+    module: Arc<RunwellModule>,
+    /// The host data attached to the instance.
+    user_data: UserData,
+}
+
+impl RunwellInstance {
+    /// Returns the linear memory at the index if any.
+    fn memory(&self, memory: u32) -> Option<&dyn LinearMemory> {
+        self.ctx.memory(mem_from_u32(memory)?)
+    }
+
+    /// Returns the mutable linear memory at the index if any.
+    fn memory_mut(&mut self, memory: u32) -> Option<&mut dyn LinearMemory> {
+        self.ctx.memory_mut(mem_from_u32(memory)?)
+    }
+}
+
+/// Returns the linear memory index for the raw index if it is valid.
+fn mem_from_u32(memory: u32) -> Option<Mem> {
+    if memory > RawIdx::MAX_U32 {
+        return None
+    }
+    Some(Mem::from_raw(RawIdx::from_u32(memory)))
+}
+
+/// Returns the byte range of the access if it is within the linear memory.
+fn memory_range(
+    memory: &dyn LinearMemory,
+    offset: usize,
+    len: usize,
+) -> Result<Range<usize>, RunwellStatus> {
+    match offset.checked_add(len) {
+        Some(end) if end <= memory.data().len() => Ok(offset..end),
+        _ => Err(RunwellStatus::MemoryOutOfBounds),
+    }
+}
+
+/// Instantiates the module.
+///
+/// Upon success the new instance is written to `out` and must be deleted
+/// with [`runwell_instance_delete`] once it is no longer in use.
+/// The instance acquires its own reference to the module.
+///
+/// # Safety
+///
+/// - `module` must be a module with at least one unreleased reference.
+/// - `out` must point to writable storage for an instance pointer.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_instance_new(
+    module: *const RunwellModule,
+    out: *mut *mut RunwellInstance,
+) -> RunwellStatus {
+    if module.is_null() || out.is_null() {
+        return RunwellStatus::NullPointer
+    }
+    catch_panic(|| {
+        let module = clone_module(module);
+        // The module is kept alive by the instance and dropped after
+        // the evaluation context that borrows it.
+        let borrowed = &*(&module.module as *const Module);
+        let ctx = match EvaluationContext::with_memory_creator(
+            borrowed,
+            &VecMemoryCreator,
+        ) {
+            Ok(ctx) => ctx,
+            Err(_) => return RunwellStatus::InstantiationFailed,
+        };
+        let instance = RunwellInstance {
+            ctx,
+            module,
+            user_data: UserData::default(),
+        };
+        *out = Box::into_raw(Box::new(instance));
+        RunwellStatus::Ok
+    })
+}
+
+/// Deletes the instance.
+///
+/// Finalizes the user data of the instance and releases its reference to
+/// the module. Does nothing if `instance` is null.
+///
+/// # Safety
+///
+/// `instance` must have been created by [`runwell_instance_new`] and must
+/// not have been deleted before.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_instance_delete(
+    instance: *mut RunwellInstance,
+) {
+    if !instance.is_null() {
+        drop(Box::from_raw(instance))
+    }
+}
+
+/// Calls the function exported under the nul-terminated `name`.
+///
+/// The `args_len` arguments are read from `args` and up to `results_len`
+/// results are written to `results`. The number of results of the function
+/// is written to `out_results_len` upon success and if `results` is too
+/// small to hold all of them.
+///
+/// # Safety
+///
+/// - `instance` must be a valid instance.
+/// - `name` must point to a nul-terminated string.
+/// - `args` must point to `args_len` readable values.
+/// - `results` must point to `results_len` writable values.
+/// - `out_results_len` must point to writable storage for a length.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_instance_call(
+    instance: *mut RunwellInstance,
+    name: *const c_char,
+    args: *const u64,
+    args_len: usize,
+    results: *mut u64,
+    results_len: usize,
+    out_results_len: *mut usize,
+) -> RunwellStatus {
+    if instance.is_null()
+        || name.is_null()
+        || out_results_len.is_null()
+        || (args.is_null() && args_len != 0)
+        || (results.is_null() && results_len != 0)
+    {
+        return RunwellStatus::NullPointer
+    }
+    catch_panic(|| {
+        let instance = &mut *instance;
+        let name = match CStr::from_ptr(name).to_str() {
+            Ok(name) => name,
+            Err(_) => return RunwellStatus::InvalidUtf8,
+        };
+        let func = match instance.module.module.get_exported_function(name) {
+            Some(func) => func,
+            None => return RunwellStatus::UnknownFunction,
+        };
+        let args = slice_from_raw(args, args_len);
+        let mut outputs = Vec::new();
+        if let Err(error) = instance.ctx.evaluate_function(
+            func,
+            args.iter().copied(),
+            |output| outputs.push(output),
+        ) {
+            return error.into()
+        }
+        *out_results_len = outputs.len();
+        if outputs.len() > results_len {
+            return RunwellStatus::BufferTooSmall
+        }
+        slice_from_raw_mut(results, outputs.len()).copy_from_slice(&outputs);
+        RunwellStatus::Ok
+    })
+}
+
+/// Writes the size in bytes of the linear memory at index `memory` to
+/// `out_len`.
+///
+/// # Safety
+///
+/// - `instance` must be a valid instance.
+/// - `out_len` must point to writable storage for a length.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_instance_memory_size(
+    instance: *const RunwellInstance,
+    memory: u32,
+    out_len: *mut usize,
+) -> RunwellStatus {
+    if instance.is_null() || out_len.is_null() {
+        return RunwellStatus::NullPointer
+    }
+    match (*instance).memory(memory) {
+        Some(memory) => {
+            *out_len = memory.data().len();
+            RunwellStatus::Ok
+        }
+        None => RunwellStatus::MissingMemory,
+    }
+}
+
+/// Copies `len` bytes at `offset` of the linear memory at index `memory`
+/// into `buffer`.
+///
+/// # Safety
+///
+/// - `instance` must be a valid instance.
+/// - `buffer` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_instance_memory_read(
+    instance: *const RunwellInstance,
+    memory: u32,
+    offset: usize,
+    buffer: *mut u8,
+    len: usize,
+) -> RunwellStatus {
+    if instance.is_null() || (buffer.is_null() && len != 0) {
+        return RunwellStatus::NullPointer
+    }
+    let memory = match (*instance).memory(memory) {
+        Some(memory) => memory,
+        None => return RunwellStatus::MissingMemory,
+    };
+    let range = match memory_range(memory, offset, len) {
+        Ok(range) => range,
+        Err(status) => return status,
+    };
+    slice_from_raw_mut(buffer, len).copy_from_slice(&memory.data()[range]);
+    RunwellStatus::Ok
+}
+
+/// Copies `len` bytes of `buffer` to `offset` of the linear memory at index
+/// `memory`.
+///
+/// # Safety
+///
+/// - `instance` must be a valid instance.
+/// - `buffer` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_instance_memory_write(
+    instance: *mut RunwellInstance,
+    memory: u32,
+    offset: usize,
+    buffer: *const u8,
+    len: usize,
+) -> RunwellStatus {
+    if instance.is_null() || (buffer.is_null() && len != 0) {
+        return RunwellStatus::NullPointer
+    }
+    let memory = match (*instance).memory_mut(memory) {
+        Some(memory) => memory,
+        None => return RunwellStatus::MissingMemory,
+    };
+    let range = match memory_range(memory, offset, len) {
+        Ok(range) => range,
+        Err(status) => return status,
+    };
+    memory.data_mut()[range].copy_from_slice(slice_from_raw(buffer, len));
+    RunwellStatus::Ok
+}
+
+/// Attaches the host data to the instance.
+///
+/// The finalizer is called with the data once the instance is deleted or
+/// once other data is attached to the instance. Previously attached data
+/// is finalized immediately.
+///
+/// # Safety
+///
+/// - `instance` must be a valid instance.
+/// - `finalizer` must be safe to call with `data` from any thread on which
+///   the instance is used.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_instance_set_user_data(
+    instance: *mut RunwellInstance,
+    data: *mut c_void,
+    finalizer: Option<RunwellFinalizer>,
+) -> RunwellStatus {
+    if instance.is_null() {
+        return RunwellStatus::NullPointer
+    }
+    (*instance).user_data = UserData::new(data, finalizer);
+    RunwellStatus::Ok
+}
+
+/// Returns the host data attached to the instance.
+///
+/// Returns null if no data is attached or if `instance` is null.
+///
+/// # Safety
+///
+/// `instance` must be a valid instance.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_instance_user_data(
+    instance: *const RunwellInstance,
+) -> *mut c_void {
+    if instance.is_null() {
+        return ptr::null_mut()
+    }
+    (*instance).user_data.data()
+}
@@ -0,0 +1,67 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! C API to embed the Runwell interpreter into non-Rust hosts.
+//!
+//! The API operates on opaque handles that are created and deleted through
+//! the exported functions. Handles never expose internal types so that
+//! language bindings can be generated from the C declarations alone. Fallible functions return a [`RunwellStatus`]
+//! code and write their results through out-pointers. The matching C
+//! declarations can be found in `include/runwell.h`.
+//!
+//! # Lifetimes
+//!
+//! - A [`RunwellModule`] is reference counted. References are acquired with
+//!   [`runwell_module_retain`] and released with [`runwell_module_release`].
+//! - A [`RunwellInstance`] is owned by the host and must be deleted with
+//!   [`runwell_instance_delete`]. It holds a reference to its module so the
+//!   module may be released before its instances.
+//! - Host data may be attached to instances together with a finalizer that
+//!   is called once the instance is deleted.
+//!
+//! # Values
+//!
+//! Function arguments and results are passed as raw 64-bit values.
+//! Integers are zero extended and floats are passed by their bit pattern.
+
+mod error;
+mod instance;
+mod module;
+mod raw;
+mod user_data;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::{
+    error::{runwell_status_message, RunwellStatus},
+    instance::{
+        runwell_instance_call,
+        runwell_instance_delete,
+        runwell_instance_memory_read,
+        runwell_instance_memory_size,
+        runwell_instance_memory_write,
+        runwell_instance_new,
+        runwell_instance_set_user_data,
+        runwell_instance_user_data,
+        RunwellInstance,
+    },
+    module::{
+        runwell_module_new,
+        runwell_module_release,
+        runwell_module_retain,
+        RunwellModule,
+    },
+    user_data::RunwellFinalizer,
+};
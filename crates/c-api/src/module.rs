@@ -0,0 +1,102 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{error::catch_panic, raw::slice_from_raw, RunwellStatus};
+use module::Module;
+use std::sync::Arc;
+
+/// A parsed and validated Wasm module.
+///
+/// Modules are reference counted. Every instance holds a reference to its
+/// module so that hosts may release modules independently of their instances.
+#[derive(Debug)]
+pub struct RunwellModule {
+    pub(crate) module: Module,
+}
+
+/// Parses and validates the Wasm module of `len` bytes at `bytes`.
+///
+/// Upon success the new module is written to `out` with a reference count
+/// of one. The reference must be released with [`runwell_module_release`].
+///
+/// # Safety
+///
+/// - `bytes` must point to `len` readable bytes.
+/// - `out` must point to writable storage for a module pointer.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_module_new(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *const RunwellModule,
+) -> RunwellStatus {
+    if bytes.is_null() || out.is_null() {
+        return RunwellStatus::NullPointer
+    }
+    catch_panic(|| {
+        let mut bytes = slice_from_raw(bytes, len);
+        let mut buffer = Vec::new();
+        let module = match wasm::parse(&mut bytes, &mut buffer) {
+            Ok(module) => module,
+            Err(_) => return RunwellStatus::InvalidModule,
+        };
+        *out = Arc::into_raw(Arc::new(RunwellModule { module }));
+        RunwellStatus::Ok
+    })
+}
+
+/// Acquires another reference to the module and returns the module.
+///
+/// Every acquired reference must be released with [`runwell_module_release`].
+///
+/// # Safety
+///
+/// `module` must be a module with at least one unreleased reference.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_module_retain(
+    module: *const RunwellModule,
+) -> *const RunwellModule {
+    if !module.is_null() {
+        Arc::increment_strong_count(module)
+    }
+    module
+}
+
+/// Releases a reference to the module.
+///
+/// The module is deleted once all references to it have been released,
+/// including the references held by its instances.
+/// Does nothing if `module` is null.
+///
+/// # Safety
+///
+/// `module` must be a module with at least one unreleased reference that
+/// is owned by the caller.
+#[no_mangle]
+pub unsafe extern "C" fn runwell_module_release(module: *const RunwellModule) {
+    if !module.is_null() {
+        drop(Arc::from_raw(module))
+    }
+}
+
+/// Returns an owned reference to the module without releasing the borrowed one.
+///
+/// # Safety
+///
+/// `module` must be a non-null module with at least one unreleased reference.
+pub(crate) unsafe fn clone_module(
+    module: *const RunwellModule,
+) -> Arc<RunwellModule> {
+    Arc::increment_strong_count(module);
+    Arc::from_raw(module)
+}
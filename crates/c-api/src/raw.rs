@@ -0,0 +1,51 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Helpers to convert raw pointers of the C API into Rust references.
+
+/// Returns the slice of `len` elements at `ptr`.
+///
+/// Unlike [`std::slice::from_raw_parts`] this allows `ptr` to be null if
+/// `len` is zero.
+///
+/// # Safety
+///
+/// If `len` is not zero `ptr` must point to `len` readable elements.
+pub(crate) unsafe fn slice_from_raw<'a, T>(
+    ptr: *const T,
+    len: usize,
+) -> &'a [T] {
+    if len == 0 {
+        return &[]
+    }
+    std::slice::from_raw_parts(ptr, len)
+}
+
+/// Returns the mutable slice of `len` elements at `ptr`.
+///
+/// Unlike [`std::slice::from_raw_parts_mut`] this allows `ptr` to be null
+/// if `len` is zero.
+///
+/// # Safety
+///
+/// If `len` is not zero `ptr` must point to `len` writable elements.
+pub(crate) unsafe fn slice_from_raw_mut<'a, T>(
+    ptr: *mut T,
+    len: usize,
+) -> &'a mut [T] {
+    if len == 0 {
+        return &mut []
+    }
+    std::slice::from_raw_parts_mut(ptr, len)
+}
@@ -0,0 +1,234 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    runwell_instance_call,
+    runwell_instance_delete,
+    runwell_instance_memory_read,
+    runwell_instance_memory_size,
+    runwell_instance_memory_write,
+    runwell_instance_new,
+    runwell_instance_set_user_data,
+    runwell_instance_user_data,
+    runwell_module_new,
+    runwell_module_release,
+    runwell_module_retain,
+    RunwellInstance,
+    RunwellModule,
+    RunwellStatus,
+};
+use std::{
+    os::raw::c_void,
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Creates a module from the Wasm text format input.
+fn new_module(wat: &str) -> *const RunwellModule {
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut module = ptr::null();
+    let status =
+        unsafe { runwell_module_new(wasm.as_ptr(), wasm.len(), &mut module) };
+    assert_eq!(status, RunwellStatus::Ok);
+    module
+}
+
+/// Calls the exported function and returns the status and results.
+fn call(
+    instance: *mut RunwellInstance,
+    name: &[u8],
+    args: &[u64],
+    results_len: usize,
+) -> (RunwellStatus, Vec<u64>) {
+    let mut results = vec![0; results_len];
+    let mut len = 0;
+    let status = unsafe {
+        runwell_instance_call(
+            instance,
+            name.as_ptr() as *const _,
+            args.as_ptr(),
+            args.len(),
+            results.as_mut_ptr(),
+            results.len(),
+            &mut len,
+        )
+    };
+    results.truncate(len);
+    (status, results)
+}
+
+#[test]
+fn call_works() {
+    let module = new_module(
+        r#"
+        (module
+            (func (export "add") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add
+            )
+            (func (export "trap")
+                unreachable
+            )
+            (func (export "div") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.div_u
+            )
+        )
+    "#,
+    );
+    let mut instance = ptr::null_mut();
+    let status = unsafe { runwell_instance_new(module, &mut instance) };
+    assert_eq!(status, RunwellStatus::Ok);
+    assert_eq!(
+        call(instance, b"add\0", &[2, 3], 1),
+        (RunwellStatus::Ok, vec![5])
+    );
+    assert_eq!(
+        call(instance, b"add\0", &[2, 3], 0),
+        (RunwellStatus::BufferTooSmall, vec![])
+    );
+    assert_eq!(
+        call(instance, b"add\0", &[2], 1).0,
+        RunwellStatus::UnmatchingArguments
+    );
+    assert_eq!(
+        call(instance, b"sub\0", &[2, 3], 1).0,
+        RunwellStatus::UnknownFunction
+    );
+    assert_eq!(call(instance, b"trap\0", &[], 0).0, RunwellStatus::Trap);
+    assert_eq!(call(instance, b"div\0", &[1, 0], 1).0, RunwellStatus::Trap);
+    // The instance remains usable after a trap.
+    assert_eq!(
+        call(instance, b"add\0", &[4, 5], 1),
+        (RunwellStatus::Ok, vec![9])
+    );
+    unsafe {
+        runwell_instance_delete(instance);
+        runwell_module_release(module);
+    }
+}
+
+#[test]
+fn memory_access_works() {
+    let module = new_module(
+        r#"
+        (module
+            (memory 1)
+            (func (export "load") (param i32) (result i32)
+                local.get 0
+                i32.load
+            )
+        )
+    "#,
+    );
+    let mut instance = ptr::null_mut();
+    let status = unsafe { runwell_instance_new(module, &mut instance) };
+    assert_eq!(status, RunwellStatus::Ok);
+    let mut len = 0;
+    let status = unsafe { runwell_instance_memory_size(instance, 0, &mut len) };
+    assert_eq!((status, len), (RunwellStatus::Ok, 65536));
+    let bytes = 0x1234_5678_u32.to_le_bytes();
+    let write = |offset: usize| unsafe {
+        runwell_instance_memory_write(
+            instance,
+            0,
+            offset,
+            bytes.as_ptr(),
+            bytes.len(),
+        )
+    };
+    assert_eq!(write(8), RunwellStatus::Ok);
+    assert_eq!(write(65533), RunwellStatus::MemoryOutOfBounds);
+    assert_eq!(
+        call(instance, b"load\0", &[8], 1),
+        (RunwellStatus::Ok, vec![0x1234_5678])
+    );
+    let mut buffer = [0x00; 4];
+    let status = unsafe {
+        runwell_instance_memory_read(instance, 0, 8, buffer.as_mut_ptr(), 4)
+    };
+    assert_eq!((status, buffer), (RunwellStatus::Ok, bytes));
+    let status = unsafe { runwell_instance_memory_size(instance, 1, &mut len) };
+    assert_eq!(status, RunwellStatus::MissingMemory);
+    unsafe {
+        runwell_instance_delete(instance);
+        runwell_module_release(module);
+    }
+}
+
+#[test]
+fn invalid_inputs_are_rejected() {
+    let mut module = ptr::null();
+    let bytes = b"\0asm";
+    let status =
+        unsafe { runwell_module_new(bytes.as_ptr(), bytes.len(), &mut module) };
+    assert_eq!(status, RunwellStatus::InvalidModule);
+    let status = unsafe { runwell_module_new(ptr::null(), 0, &mut module) };
+    assert_eq!(status, RunwellStatus::NullPointer);
+    let mut instance = ptr::null_mut();
+    let status = unsafe { runwell_instance_new(ptr::null(), &mut instance) };
+    assert_eq!(status, RunwellStatus::NullPointer);
+    // Deleting null handles does nothing.
+    unsafe {
+        runwell_instance_delete(ptr::null_mut());
+        runwell_module_release(ptr::null());
+    }
+}
+
+#[test]
+fn handles_own_their_data() {
+    unsafe extern "C" fn finalize(data: *mut c_void) {
+        (*(data as *const AtomicUsize)).fetch_add(1, Ordering::SeqCst);
+    }
+    let module = new_module(
+        r#"
+        (module
+            (func (export "answer") (result i32)
+                i32.const 42
+            )
+        )
+    "#,
+    );
+    let mut instance = ptr::null_mut();
+    let finalized = AtomicUsize::new(0);
+    let data = &finalized as *const AtomicUsize as *mut c_void;
+    unsafe {
+        assert_eq!(runwell_module_retain(module), module);
+        runwell_module_release(module);
+        let status = runwell_instance_new(module, &mut instance);
+        assert_eq!(status, RunwellStatus::Ok);
+        // The instance keeps its module alive.
+        runwell_module_release(module);
+        assert_eq!(runwell_instance_user_data(instance), ptr::null_mut());
+        let status =
+            runwell_instance_set_user_data(instance, data, Some(finalize));
+        assert_eq!(status, RunwellStatus::Ok);
+        assert_eq!(runwell_instance_user_data(instance), data);
+    }
+    assert_eq!(
+        call(instance, b"answer\0", &[], 1),
+        (RunwellStatus::Ok, vec![42])
+    );
+    unsafe {
+        // Replacing the user data finalizes the previous data.
+        let status =
+            runwell_instance_set_user_data(instance, data, Some(finalize));
+        assert_eq!(status, RunwellStatus::Ok);
+        assert_eq!(finalized.load(Ordering::SeqCst), 1);
+        runwell_instance_delete(instance);
+    }
+    assert_eq!(finalized.load(Ordering::SeqCst), 2);
+}
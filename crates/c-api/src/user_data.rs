@@ -0,0 +1,59 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{os::raw::c_void, ptr};
+
+/// Finalizes host data once the handle it is attached to is deleted.
+pub type RunwellFinalizer = unsafe extern "C" fn(data: *mut c_void);
+
+/// Host data attached to a handle.
+///
+/// Language bindings use it to associate handles with their host objects.
+/// The finalizer is called with the data once it is replaced or dropped.
+#[derive(Debug)]
+pub(crate) struct UserData {
+    data: *mut c_void,
+    finalizer: Option<RunwellFinalizer>,
+}
+
+impl Default for UserData {
+    fn default() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            finalizer: None,
+        }
+    }
+}
+
+impl UserData {
+    /// Creates new user data that is finalized with the finalizer if any.
+    pub fn new(data: *mut c_void, finalizer: Option<RunwellFinalizer>) -> Self {
+        Self { data, finalizer }
+    }
+
+    /// Returns the host data.
+    pub fn data(&self) -> *mut c_void {
+        self.data
+    }
+}
+
+impl Drop for UserData {
+    fn drop(&mut self) {
+        if let Some(finalizer) = self.finalizer {
+            // Safety: The host guarantees that the finalizer may be called
+            //         with the data when it registered them.
+            unsafe { finalizer(self.data) }
+        }
+    }
+}
@@ -193,7 +193,7 @@ impl<K, V> ComponentVec<Idx<K>, V> {
     pub fn components(&self) -> Components<V> {
         Components {
             iter: self.components.iter(),
-            remaining: 0,
+            remaining: self.len_some,
         }
     }
 
@@ -201,7 +201,7 @@ impl<K, V> ComponentVec<Idx<K>, V> {
     pub fn components_mut(&mut self) -> ComponentsMut<V> {
         ComponentsMut {
             iter: self.components.iter_mut(),
-            remaining: 0,
+            remaining: self.len_some,
         }
     }
 
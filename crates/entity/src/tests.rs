@@ -131,6 +131,29 @@ macro_rules! unit_test_secondary {
             assert_eq!(actual, expected);
         }
 
+        #[test]
+        fn components_works() {
+            let sample = some_components_with_holes();
+            let mut instance = populated_instance(sample.clone());
+
+            // Asserts that iterating over the components yields the
+            // components of all keys.
+            let expected = sample.iter().map(|(_, c)| *c).collect::<Vec<_>>();
+            let mut actual = instance.components().copied().collect::<Vec<_>>();
+            actual.sort();
+            assert_eq!(actual, expected);
+            for c in instance.components_mut() {
+                *c = c.to_ascii_lowercase();
+            }
+            let mut actual = instance.components().copied().collect::<Vec<_>>();
+            actual.sort();
+            let expected = expected
+                .iter()
+                .map(char::to_ascii_lowercase)
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+        }
+
         #[test]
         fn clear_works() {
             let sample = some_components_with_holes();
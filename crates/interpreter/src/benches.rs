@@ -0,0 +1,163 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks for the evaluation hot path.
+//!
+//! The benchmarks compare evaluation of a single shared [`Module`] from one
+//! thread with evaluation of the same `Arc<Module>` from multiple threads
+//! concurrently. Since evaluation never mutates the module and does not use
+//! interior mutability or locks on its read paths the per-thread throughput
+//! is expected to stay roughly the same.
+
+use crate::EvaluationContext;
+use entity::RawIdx;
+use ir::{
+    instr::operands::CompareIntOp,
+    primitive::{Const, Func, IntConst, IntType},
+};
+use module::{
+    primitive::{FunctionType, Variable},
+    FunctionBody,
+    Module,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        Barrier,
+    },
+    thread,
+};
+use test::Bencher;
+
+/// The number of loop iterations of the benchmarked function.
+const LOOP_ITERATIONS: i32 = 1_000;
+
+/// The number of concurrently evaluating threads.
+const NUM_THREADS: usize = 4;
+
+/// Creates a module with a single function that counts up to its input.
+fn counting_loop_module() -> (Func, Module) {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let body = (|| -> Result<FunctionBody, module::Error> {
+        let mut b = FunctionBody::build(func, res);
+        b.declare_variables(1, IntType::I32.into())?;
+        b.body()?;
+
+        let loop_head = b.create_block()?;
+        let loop_body = b.create_block()?;
+        let loop_exit = b.create_block()?;
+
+        let input = Variable::from_raw(RawIdx::from_u32(0));
+        let counter = Variable::from_raw(RawIdx::from_u32(1));
+
+        let v0 = b.ins()?.constant(IntConst::I32(0))?;
+        b.write_var(counter, v0)?;
+        b.ins()?.br(loop_head)?;
+
+        b.switch_to_block(loop_head)?;
+        let v1 = b.read_var(counter)?;
+        let v2 = b.read_var(input)?;
+        let v3 = b.ins()?.icmp(IntType::I32, CompareIntOp::Slt, v1, v2)?;
+        b.ins()?.if_then_else(v3, loop_body, loop_exit)?;
+
+        b.switch_to_block(loop_body)?;
+        let v4 = b.read_var(counter)?;
+        let v5 = b.ins()?.constant(IntConst::I32(1))?;
+        let v6 = b.ins()?.iadd(IntType::I32, v4, v5)?;
+        b.write_var(counter, v6)?;
+        b.ins()?.br(loop_head)?;
+        b.seal_block(loop_body)?;
+
+        b.seal_block(loop_head)?;
+        b.switch_to_block(loop_exit)?;
+        b.seal_block(loop_exit)?;
+        let v7 = b.read_var(counter)?;
+        b.ins()?.return_values([v7].iter().copied())?;
+        b.finalize()
+    })()
+    .unwrap();
+    body_builder.push_body(func, body).unwrap();
+    (func, builder.finalize().unwrap())
+}
+
+/// Evaluates the counting loop function once using the given context.
+fn evaluate_counting_loop(ctx: &mut EvaluationContext, func: Func) {
+    let input = Const::from(IntConst::I32(LOOP_ITERATIONS)).into_bits64();
+    let mut result = 0;
+    ctx.evaluate_function(func, vec![input], |output| result = output)
+        .unwrap();
+    assert_eq!(result, LOOP_ITERATIONS as u64);
+}
+
+#[bench]
+fn bench_counting_loop_single_thread(b: &mut Bencher) {
+    let (func, module) = counting_loop_module();
+    let mut ctx = EvaluationContext::new(&module);
+    b.iter(|| evaluate_counting_loop(&mut ctx, func))
+}
+
+/// Evaluates the counting loop from all threads concurrently.
+///
+/// The worker threads are spawned once up front and synchronized with the
+/// benchmarking thread via barriers so that only the evaluations are timed
+/// and not the spawning of threads.
+#[bench]
+fn bench_counting_loop_shared_module(b: &mut Bencher) {
+    let (func, module) = counting_loop_module();
+    let module = Arc::new(module);
+    let start = Arc::new(Barrier::new(NUM_THREADS));
+    let done = Arc::new(Barrier::new(NUM_THREADS));
+    let stop = Arc::new(AtomicBool::new(false));
+    let workers = (1..NUM_THREADS)
+        .map(|_| {
+            let module = Arc::clone(&module);
+            let start = Arc::clone(&start);
+            let done = Arc::clone(&done);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let mut ctx = EvaluationContext::new(&module);
+                loop {
+                    start.wait();
+                    if stop.load(Ordering::Acquire) {
+                        break
+                    }
+                    evaluate_counting_loop(&mut ctx, func);
+                    done.wait();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut ctx = EvaluationContext::new(&module);
+    b.iter(|| {
+        start.wait();
+        evaluate_counting_loop(&mut ctx, func);
+        done.wait();
+    });
+    stop.store(true, Ordering::Release);
+    start.wait();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Configuration of the execution semantics of the interpreter.
+
+use module::ResourceLimits;
+
+/// Configures how the interpreter executes instructions.
+///
+/// By default instructions are executed in the fastest way.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExecutionConfig {
+    /// Whether instructions avoid branching on the values they operate on.
+    constant_time: bool,
+    /// Whether NaN results of float instructions are canonicalized.
+    canonicalize_nans: bool,
+    /// Whether the types of registers are checked upon reads.
+    check_register_types: bool,
+    /// The limits on the sizes of linear memories and tables.
+    resource_limits: ResourceLimits,
+}
+
+impl ExecutionConfig {
+    /// Creates the default execution configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether instructions avoid branching on their operand values.
+    ///
+    /// In constant-time mode `select`, `fmin` and `fmax` are executed by
+    /// masking the bits of their operands instead of branching on them.
+    /// This helps code handling secrets to not leak them via timing.
+    ///
+    /// # Note
+    ///
+    /// Instructions that are not constant-time according to their
+    /// [`InstructionDescriptor::is_constant_time`] are not affected.
+    /// Also the Rust compiler is free to reintroduce branches. Therefore
+    /// this is a best-effort mode that does not replace auditing.
+    ///
+    /// [`InstructionDescriptor::is_constant_time`]:
+    /// ir::instr::InstructionDescriptor::is_constant_time
+    pub fn set_constant_time(&mut self, enable: bool) {
+        self.constant_time = enable;
+    }
+
+    /// Returns `true` if instructions avoid branching on their operand values.
+    pub fn constant_time(&self) -> bool {
+        self.constant_time
+    }
+
+    /// Sets whether NaN results of float instructions are canonicalized.
+    ///
+    /// Wasm allows float instructions to produce NaNs with arbitrary sign
+    /// and payload bits which makes results depend on the host. If enabled
+    /// every NaN produced by a float arithmetic instruction, a promotion or
+    /// a demotion is replaced by the positive quiet NaN with an empty
+    /// payload, e.g. `0x7FC0_0000` for `f32`.
+    ///
+    /// # Note
+    ///
+    /// `fabs`, `fneg` and `fcopysign` only operate on the sign bit and
+    /// therefore preserve the NaN payloads of their operands.
+    pub fn set_canonicalize_nans(&mut self, enable: bool) {
+        self.canonicalize_nans = enable;
+    }
+
+    /// Returns `true` if NaN results of float instructions are canonicalized.
+    pub fn canonicalize_nans(&self) -> bool {
+        self.canonicalize_nans
+    }
+
+    /// Sets whether the types of registers are checked upon reads.
+    ///
+    /// Registers hold raw bits so that an instruction reading a register of
+    /// an unexpected type silently operates on corrupted values. If enabled
+    /// registers additionally store the type of the value they hold and the
+    /// operands of every instruction are checked against their types before
+    /// the instruction is executed.
+    ///
+    /// This is meant for debugging miscompilations and slows down execution
+    /// considerably.
+    pub fn set_check_register_types(&mut self, enable: bool) {
+        self.check_register_types = enable;
+    }
+
+    /// Returns `true` if the types of registers are checked upon reads.
+    pub fn check_register_types(&self) -> bool {
+        self.check_register_types
+    }
+
+    /// Sets the limits on the sizes of linear memories and tables.
+    ///
+    /// Linear memories and tables whose initial sizes exceed the limits
+    /// fail to instantiate and `memory.grow` fails to grow a linear memory
+    /// beyond the limits.
+    ///
+    /// # Note
+    ///
+    /// The initial sizes are checked upon creation of an evaluation context
+    /// via [`EvaluationContext::with_config`]. Limits changed afterwards
+    /// only affect the growth of linear memories.
+    ///
+    /// [`EvaluationContext::with_config`]:
+    /// crate::EvaluationContext::with_config
+    pub fn set_resource_limits(&mut self, limits: ResourceLimits) {
+        self.resource_limits = limits;
+    }
+
+    /// Returns the limits on the sizes of linear memories and tables.
+    pub fn resource_limits(&self) -> &ResourceLimits {
+        &self.resource_limits
+    }
+}
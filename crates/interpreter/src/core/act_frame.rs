@@ -16,38 +16,87 @@ use super::{
     frame::Frame,
     stack::{Ptr, Register, Stack},
 };
-use ir::primitive::{Block, Value};
-use module::Module;
+use crate::{
+    ExecutionConfig,
+    GlobalHandle,
+    InterpretationError,
+    LinearMemory,
+    Profile,
+};
+use entity::ComponentMap;
+use ir::{
+    instr::BranchTarget,
+    primitive::{Block, Func, FuncType, Global, Mem, Table, Value},
+};
+use module::{Function, Module};
 
 /// A temporary activation frame used for instruction interpretation.
 #[derive(Debug)]
 pub struct ActivationFrame<'a> {
     pub module: &'a Module,
+    /// The function executed by the frame.
+    function: Function<'a>,
     stack: &'a mut Stack,
     frame: &'a mut Frame,
     scratch: &'a mut Vec<Register>,
+    profile: Option<&'a mut Profile>,
+    config: &'a ExecutionConfig,
+    globals: &'a ComponentMap<Global, GlobalHandle>,
+    memories: &'a mut ComponentMap<Mem, Box<dyn LinearMemory>>,
+    tables: &'a ComponentMap<Table, Vec<Option<Func>>>,
 }
 
 impl<'a> ActivationFrame<'a> {
     /// Creates a new activation frame.
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         module: &'a Module,
+        function: Function<'a>,
         stack: &'a mut Stack,
         frame: &'a mut Frame,
         scratch: &'a mut Vec<Register>,
+        profile: Option<&'a mut Profile>,
+        config: &'a ExecutionConfig,
+        globals: &'a ComponentMap<Global, GlobalHandle>,
+        memories: &'a mut ComponentMap<Mem, Box<dyn LinearMemory>>,
+        tables: &'a ComponentMap<Table, Vec<Option<Func>>>,
     ) -> Self {
         Self {
             module,
+            function,
             stack,
             frame,
             scratch,
+            profile,
+            config,
+            globals,
+            memories,
+            tables,
         }
     }
 
     /// Writes the given bits into the register for the given value.
+    ///
+    /// The register is tagged with the type of the value if register types
+    /// are checked.
     pub fn write_register(&mut self, value: Value, bits: u64) {
         let ptr = self.stack_pointer() + value;
-        self.stack.write_register(ptr, bits)
+        let ty = if self.config.check_register_types() {
+            self.function.body().value_type(value)
+        } else {
+            None
+        };
+        let register = Register::with_type(bits, ty);
+        self.stack.write_register(ptr, register)
+    }
+
+    /// Writes the given bits into the register of the output value if any.
+    ///
+    /// Does nothing if the output value is never used.
+    pub fn write_output(&mut self, value: Option<Value>, bits: u64) {
+        if let Some(value) = value {
+            self.write_register(value, bits)
+        }
     }
 
     /// Returns the bits in the register for the given value.
@@ -56,21 +105,101 @@ impl<'a> ActivationFrame<'a> {
         self.stack.read_register(ptr)
     }
 
-    /// Switches the currently executed basic block.
-    pub fn switch_to_block(&mut self, block: Block) {
+    /// Returns the configuration of the execution semantics.
+    pub fn config(&self) -> &ExecutionConfig {
+        self.config
+    }
+
+    /// Returns the global variable or an error if it has not been initialized.
+    pub fn global(
+        &self,
+        global: Global,
+    ) -> Result<&GlobalHandle, InterpretationError> {
+        self.globals
+            .get(global)
+            .ok_or(InterpretationError::UninitializedGlobal { global })
+    }
+
+    /// Returns the mutable linear memory if it has been created.
+    pub fn memory_mut(&mut self, memory: Mem) -> Option<&mut dyn LinearMemory> {
+        let memory = self.memories.get_mut(memory)?;
+        Some(&mut **memory)
+    }
+
+    /// Returns the function at the index of the table for an indirect call.
+    ///
+    /// # Errors
+    ///
+    /// - If the table has not been created or the index is out of bounds.
+    /// - If the table element at the index is uninitialized.
+    /// - If the type of the function does not match the expected function type.
+    pub fn indirect_callee(
+        &self,
+        table: Table,
+        index: u32,
+        func_type: FuncType,
+    ) -> Result<Func, InterpretationError> {
+        let (module, tables) = (self.module, self.tables);
+        super::indirect_callee(module, tables, table, index, func_type)
+    }
+
+    /// Branches to the target basic block passing the branch arguments.
+    ///
+    /// Records the taken control flow edge if profiling is enabled.
+    ///
+    /// # Note
+    ///
+    /// All branch arguments are read before any parameter of the target
+    /// basic block is written so that arguments referring to parameters of
+    /// the same basic block, e.g. in loops, observe their values before the
+    /// control flow edge was taken.
+    pub fn branch_to(&mut self, target: &BranchTarget) {
+        let func = self.frame.func();
+        let block = target.block();
+        if let Some(profile) = self.profile.as_deref_mut() {
+            profile.record_edge(func, self.frame.current_block(), block);
+        }
+        let params = self.function.body().block_params(block);
+        debug_assert_eq!(params.len(), target.args().len());
+        self.scratch.clear();
+        for &arg in target.args() {
+            self.push_scratch(arg);
+        }
+        for (n, &param) in params.iter().enumerate() {
+            let register = self.scratch[n];
+            self.stack
+                .write_register(self.frame.stack_pointer() + param, register);
+        }
+        self.scratch.clear();
         self.frame.switch_to_block(block);
     }
 
+    /// Records the outcome of the conditional branch ending the current basic block.
+    ///
+    /// Does nothing if profiling is disabled.
+    pub fn record_branch(&mut self, taken: bool) {
+        if let Some(profile) = self.profile.as_deref_mut() {
+            let func = self.frame.func();
+            profile.record_branch(func, self.frame.current_block(), taken);
+        }
+    }
+
+    /// Records the case of the branch table ending the current basic block.
+    ///
+    /// A case of `None` records that the default target was taken.
+    /// Does nothing if profiling is disabled.
+    pub fn record_branch_table(&mut self, case: Option<u32>) {
+        if let Some(profile) = self.profile.as_deref_mut() {
+            let func = self.frame.func();
+            profile.record_branch_table(func, self.frame.current_block(), case);
+        }
+    }
+
     /// Returns the currently executed basic block.
     pub fn current_block(&self) -> Block {
         self.frame.current_block()
     }
 
-    /// Returns the last executed basic block if any.
-    pub fn last_block(&self) -> Option<Block> {
-        self.frame.last_block()
-    }
-
     /// Bumps the instruction counter by one and returns its value before the bump.
     pub fn bump_instruction_counter(&mut self) -> usize {
         self.frame.bump_instruction_counter()
@@ -89,10 +218,11 @@ impl<'a> ActivationFrame<'a> {
         self.scratch.clear();
     }
 
-    /// Populates the scratch buffer with another value.
+    /// Populates the scratch buffer with the register of another value.
     ///
     /// Use this to temporarily store function call parameters or return values.
-    pub fn push_scratch(&mut self, bits: u64) {
-        self.scratch.push(Register::from_u64(bits))
+    pub fn push_scratch(&mut self, value: Value) {
+        let ptr = self.stack_pointer() + value;
+        self.scratch.push(self.stack.register(ptr))
     }
 }
@@ -0,0 +1,1021 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Baseline tier that directly interprets the operators of Wasm function bodies.
+//!
+//! Functions without a Runwell IR translation, e.g. because their translation
+//! exceeded its budget, are executed by this simple stack machine instead.
+//! Since it operates on the unmodified Wasm operators it also serves as
+//! semantics oracle for the Runwell IR interpreter in differential tests.
+
+use super::{indirect_callee, EvaluationContext, UnwindGuard};
+use crate::{
+    instr::{
+        canonicalize_nan,
+        checked_trunc,
+        ct_max_f32,
+        ct_max_f64,
+        ct_min_f32,
+        ct_min_f64,
+        grow_memory,
+        max_f32,
+        max_f64,
+        min_f32,
+        min_f64,
+        memory_range,
+        nearest_f32,
+        nearest_f64,
+        select_bits,
+    },
+    InterpretationError,
+};
+use entity::RawIdx;
+use ir::{
+    instr::TrapCode,
+    primitive::{FloatType, Func, FuncType, Mem, Table},
+};
+use module::{primitive::Global, Module};
+use std::sync::Arc;
+use wasmparser::{
+    BinaryReader,
+    MemoryImmediate,
+    Operator,
+    Type,
+    TypeOrFuncType,
+};
+
+/// Evaluates the interpreter-only function with the given inputs.
+///
+/// Returns the results of the function evaluation.
+///
+/// # Panics
+///
+/// If the function is not interpreter-only.
+pub(super) fn evaluate_function(
+    ctx: &mut EvaluationContext,
+    func: Func,
+    inputs: &[u64],
+) -> Result<Vec<u64>, InterpretationError> {
+    let module = ctx.module;
+    let function = module
+        .get_function(func)
+        .expect("encountered invalid function index");
+    let required_inputs = function.inputs().len();
+    if inputs.len() != required_inputs {
+        return Err(InterpretationError::UnmatchingInputValues {
+            given_inputs: inputs.len(),
+            required_inputs,
+        })
+    }
+    ctx.check_call_depth()?;
+    // Restores the number of baseline frames upon traps and panics.
+    let guard = UnwindGuard::new(ctx);
+    let ctx = &mut *guard.ctx;
+    ctx.baseline_frames += 1;
+    let mut func = func;
+    let mut inputs = inputs.to_vec();
+    loop {
+        let function = module
+            .get_function(func)
+            .expect("encountered invalid function index");
+        if let Some(coverage) = &mut ctx.coverage {
+            coverage.record_block(func, function.body().entry_block());
+        }
+        let body = ctx.baseline_body(func);
+        let len_outputs = function.outputs().len();
+        let mut machine = Machine::new(&body, &inputs, len_outputs);
+        let callee = loop {
+            let cost = machine.operator_cost(ctx, &body);
+            ctx.consume_fuel(cost)?;
+            match machine.execute(ctx, module, &body)? {
+                Flow::Next => machine.pc += 1,
+                Flow::Jump => continue,
+                Flow::Return => {
+                    let results = machine.stack.len() - len_outputs;
+                    return Ok(machine.stack.split_off(results))
+                }
+                Flow::TailCall(callee) => break callee,
+            }
+        };
+        // The tail called function replaces the function frame of the
+        // caller so that tail recursion does not grow the call depth.
+        let len_inputs = module
+            .get_function(callee)
+            .expect("encountered invalid function index")
+            .inputs()
+            .len();
+        inputs = machine.pop_n(len_inputs);
+        if !module.is_interpreter_only(callee) {
+            return ctx.call_function(callee, &inputs)
+        }
+        func = callee;
+    }
+}
+
+/// A decoded Wasm function body.
+#[derive(Debug)]
+pub(super) struct BaselineBody<'a> {
+    /// The number of local variables excluding the function inputs.
+    len_locals: usize,
+    /// The operators of the function body.
+    ops: Vec<Operator<'a>>,
+    /// The index of the matching `Else` operator of `If` operators.
+    ///
+    /// Indexed by the index of the `If` operator.
+    elses: Vec<Option<usize>>,
+    /// The index of the matching `End` operator of `Block`, `Loop` and `If` operators.
+    ///
+    /// Indexed by the index of the respective operator.
+    ends: Vec<usize>,
+}
+
+impl<'a> BaselineBody<'a> {
+    /// Decodes the Wasm function body from its source.
+    ///
+    /// The source is expected to be a validated code section entry
+    /// including its size prefix.
+    fn decode(source: &'a [u8]) -> Self {
+        const VALIDATED: &str = "encountered invalid function body source";
+        let mut reader = BinaryReader::new(source);
+        let _body_size = reader.read_var_u32().expect(VALIDATED);
+        let mut len_locals = 0;
+        for _ in 0..reader.read_var_u32().expect(VALIDATED) {
+            len_locals += reader.read_var_u32().expect(VALIDATED) as usize;
+            let _ty = reader.read_type().expect(VALIDATED);
+        }
+        let mut ops = Vec::new();
+        while !reader.eof() {
+            ops.push(reader.read_operator().expect(VALIDATED));
+        }
+        let mut elses = vec![None; ops.len()];
+        let mut ends = vec![0; ops.len()];
+        let mut open = Vec::new();
+        for (index, op) in ops.iter().enumerate() {
+            match op {
+                Operator::Block { .. }
+                | Operator::Loop { .. }
+                | Operator::If { .. } => open.push(index),
+                Operator::Else => {
+                    let opening = *open.last().expect(VALIDATED);
+                    elses[opening] = Some(index);
+                }
+                Operator::End => {
+                    // The last `End` operator closes the function body itself.
+                    if let Some(opening) = open.pop() {
+                        ends[opening] = index;
+                    }
+                }
+                _ => (),
+            }
+        }
+        Self {
+            len_locals,
+            ops,
+            elses,
+            ends,
+        }
+    }
+}
+
+impl<'a> EvaluationContext<'a> {
+    /// Returns the decoded body of the interpreter-only function.
+    ///
+    /// The body is decoded upon the first evaluation of the function and
+    /// reused by all later evaluations, e.g. of recursive calls.
+    ///
+    /// # Panics
+    ///
+    /// If the function is not interpreter-only.
+    fn baseline_body(&mut self, func: Func) -> Arc<BaselineBody<'a>> {
+        let module = self.module;
+        let body = self.baseline_bodies.entry(func).or_insert_with(|| {
+            let source = module
+                .get_interpreter_only_source(func)
+                .expect("encountered function that is not interpreter-only");
+            Arc::new(BaselineBody::decode(source))
+        });
+        Arc::clone(body)
+    }
+}
+
+/// A label that can be targeted by branches.
+#[derive(Debug, Copy, Clone)]
+struct Label {
+    /// `true` if branches to the label continue at its start.
+    is_loop: bool,
+    /// The index of the operator that opened the label.
+    start: usize,
+    /// The index of the `End` operator that closes the label.
+    end: usize,
+    /// The number of values that are carried by branches to the label.
+    arity: usize,
+    /// The height of the value stack below the parameters of the label.
+    height: usize,
+}
+
+/// The control flow after executing a single operator.
+#[derive(Debug, Copy, Clone)]
+enum Flow {
+    /// Continue with the next operator.
+    Next,
+    /// Continue with the operator the program counter has been set to.
+    Jump,
+    /// Return from the function with the results on top of the value stack.
+    Return,
+    /// Return from the function with the results of calling the function
+    /// with the inputs on top of the value stack.
+    TailCall(Func),
+}
+
+/// The execution state of a single interpreted function.
+#[derive(Debug)]
+struct Machine {
+    /// The index of the currently executed operator.
+    pc: usize,
+    /// The values of the function inputs and local variables.
+    locals: Vec<u64>,
+    /// The value stack of the function.
+    stack: Vec<u64>,
+    /// The currently open labels with the function body as outermost label.
+    labels: Vec<Label>,
+}
+
+impl Machine {
+    /// Creates the execution state for the body with the given inputs.
+    fn new(body: &BaselineBody, inputs: &[u64], len_outputs: usize) -> Self {
+        let mut locals = inputs.to_vec();
+        locals.resize(inputs.len() + body.len_locals, 0);
+        let function_label = Label {
+            is_loop: false,
+            start: 0,
+            end: body.ops.len() - 1,
+            arity: len_outputs,
+            height: 0,
+        };
+        Self {
+            pc: 0,
+            locals,
+            stack: Vec::new(),
+            labels: vec![function_label],
+        }
+    }
+
+    /// Pops the top most value from the value stack.
+    fn pop(&mut self) -> u64 {
+        self.stack
+            .pop()
+            .expect("encountered empty value stack due to validation")
+    }
+
+    /// Pushes the value onto the value stack.
+    fn push(&mut self, value: u64) {
+        self.stack.push(value)
+    }
+
+    /// Pops the last `len` values from the value stack.
+    fn pop_n(&mut self, len: usize) -> Vec<u64> {
+        let split = self.stack.len() - len;
+        self.stack.split_off(split)
+    }
+
+    /// Applies `f` to the top most value of the value stack.
+    fn unop<T, R, F>(&mut self, f: F)
+    where
+        T: Bits,
+        R: Bits,
+        F: FnOnce(T) -> R,
+    {
+        let source = T::from_bits64(self.pop());
+        self.push(f(source).into_bits64())
+    }
+
+    /// Applies the fallible `f` to the top most value of the value stack.
+    fn try_unop<T, R, F>(&mut self, f: F) -> Result<(), InterpretationError>
+    where
+        T: Bits,
+        R: Bits,
+        F: FnOnce(T) -> Result<R, InterpretationError>,
+    {
+        let source = T::from_bits64(self.pop());
+        self.push(f(source)?.into_bits64());
+        Ok(())
+    }
+
+    /// Applies `f` to the two top most values of the value stack.
+    fn binop<T, R, F>(&mut self, f: F)
+    where
+        T: Bits,
+        R: Bits,
+        F: FnOnce(T, T) -> R,
+    {
+        let rhs = T::from_bits64(self.pop());
+        let lhs = T::from_bits64(self.pop());
+        self.push(f(lhs, rhs).into_bits64())
+    }
+
+    /// Applies the fallible `f` to the two top most values of the value stack.
+    fn try_binop<T, R, F>(&mut self, f: F) -> Result<(), InterpretationError>
+    where
+        T: Bits,
+        R: Bits,
+        F: FnOnce(T, T) -> Result<R, InterpretationError>,
+    {
+        let rhs = T::from_bits64(self.pop());
+        let lhs = T::from_bits64(self.pop());
+        self.push(f(lhs, rhs)?.into_bits64());
+        Ok(())
+    }
+
+    /// Returns the cost of the operator at the current program counter.
+    ///
+    /// Growing a linear memory is additionally charged for the requested
+    /// pages before the memory is grown.
+    fn operator_cost(
+        &self,
+        ctx: &EvaluationContext,
+        body: &BaselineBody,
+    ) -> u64 {
+        let cost = ctx.cost_model.untranslated_operator_cost();
+        match body.ops[self.pc] {
+            Operator::MemoryGrow { .. } => {
+                let pages = self.stack.last().copied().expect("missing pages");
+                let grow_cost = ctx.cost_model.memory_grow_cost(pages as u32);
+                cost.saturating_add(grow_cost)
+            }
+            _ => cost,
+        }
+    }
+
+    /// Loads `len` bytes from linear memory at the popped address.
+    ///
+    /// Returns the loaded bytes zero extended in little-endian byte order.
+    fn load(
+        &mut self,
+        ctx: &mut EvaluationContext,
+        memarg: &MemoryImmediate,
+        len: u64,
+    ) -> Result<u64, InterpretationError> {
+        let address = self.pop() as u32;
+        let bytes = memory_bytes(ctx, memarg, address, len)?;
+        let mut buffer = [0x00; 8];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(buffer))
+    }
+
+    /// Stores the lower `len` bytes of the popped value to linear memory
+    /// at the popped address in little-endian byte order.
+    fn store(
+        &mut self,
+        ctx: &mut EvaluationContext,
+        memarg: &MemoryImmediate,
+        len: u64,
+    ) -> Result<(), InterpretationError> {
+        let value = self.pop();
+        let address = self.pop() as u32;
+        let bytes = memory_bytes(ctx, memarg, address, len)?;
+        bytes.copy_from_slice(&value.to_le_bytes()[..bytes.len()]);
+        Ok(())
+    }
+
+    /// Opens a new label for the block, loop or if operator at `pc`.
+    fn open_label(
+        &mut self,
+        module: &Module,
+        body: &BaselineBody,
+        ty: TypeOrFuncType,
+        is_loop: bool,
+    ) {
+        let (len_params, len_results) = match ty {
+            TypeOrFuncType::Type(Type::EmptyBlockType) => (0, 0),
+            TypeOrFuncType::Type(_) => (0, 1),
+            TypeOrFuncType::FuncType(index) => {
+                let func_type = module
+                    .get_type(FuncType::from_raw(RawIdx::from_u32(index)))
+                    .expect("encountered invalid block type due to validation");
+                (func_type.inputs().len(), func_type.outputs().len())
+            }
+        };
+        let arity = if is_loop { len_params } else { len_results };
+        self.labels.push(Label {
+            is_loop,
+            start: self.pc,
+            end: body.ends[self.pc],
+            arity,
+            height: self.stack.len() - len_params,
+        })
+    }
+
+    /// Pops the table index of an indirect call and returns the callee.
+    ///
+    /// # Errors
+    ///
+    /// If the table element does not reference a function of the function
+    /// type at `type_index`.
+    fn indirect_callee(
+        &mut self,
+        ctx: &EvaluationContext,
+        type_index: u32,
+        table_index: u32,
+    ) -> Result<Func, InterpretationError> {
+        let table = Table::from_raw(RawIdx::from_u32(table_index));
+        let func_type = FuncType::from_raw(RawIdx::from_u32(type_index));
+        let index = self.pop() as u32;
+        indirect_callee(ctx.module, &ctx.tables, table, index, func_type)
+    }
+
+    /// Pops the inputs of the function, calls it and pushes its results.
+    fn call(
+        &mut self,
+        ctx: &mut EvaluationContext,
+        func: Func,
+    ) -> Result<(), InterpretationError> {
+        let len_inputs = ctx
+            .module
+            .get_function(func)
+            .expect("encountered invalid function index")
+            .inputs()
+            .len();
+        let inputs = self.pop_n(len_inputs);
+        let results = ctx.call_function(func, &inputs)?;
+        self.stack.extend(results);
+        Ok(())
+    }
+
+    /// Branches to the label at the given relative depth.
+    fn branch(&mut self, relative_depth: u32) -> Flow {
+        let index = self.labels.len() - 1 - relative_depth as usize;
+        let label = self.labels[index];
+        let results = self.stack.len() - label.arity;
+        self.stack.drain(label.height..results);
+        if label.is_loop {
+            self.labels.truncate(index + 1);
+            self.pc = label.start + 1;
+        } else {
+            self.labels.truncate(index);
+            self.pc = label.end + 1;
+        }
+        if self.labels.is_empty() {
+            // Branches to the outermost label return from the function.
+            return Flow::Return
+        }
+        Flow::Jump
+    }
+
+    /// Executes the operator at the current program counter.
+    fn execute(
+        &mut self,
+        ctx: &mut EvaluationContext,
+        module: &Module,
+        body: &BaselineBody,
+    ) -> Result<Flow, InterpretationError> {
+        use InterpretationError::EvaluationHasTrapped;
+        use Operator as Op;
+        let division_by_zero = || EvaluationHasTrapped {
+            code: TrapCode::IntegerDivisionByZero,
+        };
+        let overflow = || EvaluationHasTrapped {
+            code: TrapCode::IntegerOverflow,
+        };
+        let constant_time = ctx.config.constant_time();
+        match &body.ops[self.pc] {
+            Op::Unreachable => {
+                return Err(EvaluationHasTrapped {
+                    code: TrapCode::Unreachable,
+                })
+            }
+            Op::Nop => (),
+            Op::Block { ty } => self.open_label(module, body, *ty, false),
+            Op::Loop { ty } => self.open_label(module, body, *ty, true),
+            Op::If { ty } => {
+                let condition = self.pop();
+                self.open_label(module, body, *ty, false);
+                if condition as u32 == 0 {
+                    // Continue at the else branch if any or at the `End`
+                    // operator which closes the label opened above.
+                    self.pc = match body.elses[self.pc] {
+                        Some(else_pc) => else_pc + 1,
+                        None => body.ends[self.pc],
+                    };
+                    return Ok(Flow::Jump)
+                }
+            }
+            Op::Else => {
+                // The end of the then branch has been reached.
+                let label = self.labels.last().expect("missing label for else");
+                self.pc = label.end;
+                return Ok(Flow::Jump)
+            }
+            Op::End => {
+                self.labels.pop();
+                if self.labels.is_empty() {
+                    return Ok(Flow::Return)
+                }
+            }
+            Op::Br { relative_depth } => {
+                return Ok(self.branch(*relative_depth))
+            }
+            Op::BrIf { relative_depth } => {
+                if self.pop() as u32 != 0 {
+                    return Ok(self.branch(*relative_depth))
+                }
+            }
+            Op::BrTable { table } => {
+                let index = self.pop() as u32 as usize;
+                // The default target is always yielded last.
+                let depth = table
+                    .targets()
+                    .map(|target| {
+                        target.expect(
+                            "encountered invalid br_table due to validation",
+                        )
+                    })
+                    .enumerate()
+                    .find(|&(n, (_, is_default))| n == index || is_default)
+                    .map(|(_, (depth, _))| depth)
+                    .expect("encountered branch table without default target");
+                return Ok(self.branch(depth))
+            }
+            Op::Return => return Ok(Flow::Return),
+            Op::Call { function_index } => {
+                let func = Func::from_raw(RawIdx::from_u32(*function_index));
+                self.call(ctx, func)?
+            }
+            Op::CallIndirect { index, table_index } => {
+                let func = self.indirect_callee(ctx, *index, *table_index)?;
+                self.call(ctx, func)?
+            }
+            Op::ReturnCall { function_index } => {
+                let func = Func::from_raw(RawIdx::from_u32(*function_index));
+                return Ok(Flow::TailCall(func))
+            }
+            Op::ReturnCallIndirect { index, table_index } => {
+                let func = self.indirect_callee(ctx, *index, *table_index)?;
+                return Ok(Flow::TailCall(func))
+            }
+            Op::Drop => {
+                self.pop();
+            }
+            Op::Select | Op::TypedSelect { .. } => {
+                let condition = self.pop() as u32;
+                let rhs = self.pop();
+                let lhs = self.pop();
+                if constant_time {
+                    self.push(select_bits(condition != 0, lhs, rhs));
+                } else {
+                    self.push(if condition != 0 { lhs } else { rhs });
+                }
+            }
+            Op::LocalGet { local_index } => {
+                self.push(self.locals[*local_index as usize])
+            }
+            Op::LocalSet { local_index } => {
+                self.locals[*local_index as usize] = self.pop()
+            }
+            Op::LocalTee { local_index } => {
+                let value = *self.stack.last().expect("missing value for tee");
+                self.locals[*local_index as usize] = value
+            }
+            Op::GlobalGet { global_index } => {
+                let global = Global::from_raw(RawIdx::from_u32(*global_index));
+                let handle = ctx.global(global).ok_or(
+                    InterpretationError::UninitializedGlobal { global },
+                )?;
+                self.push(handle.get_bits())
+            }
+            Op::GlobalSet { global_index } => {
+                let global = Global::from_raw(RawIdx::from_u32(*global_index));
+                let handle = ctx.global(global).ok_or(
+                    InterpretationError::UninitializedGlobal { global },
+                )?;
+                handle.set_bits(self.pop())
+            }
+            Op::I32Load { memarg } | Op::F32Load { memarg } => {
+                let bits = self.load(ctx, memarg, 4)?;
+                self.push(bits)
+            }
+            Op::I64Load { memarg } | Op::F64Load { memarg } => {
+                let bits = self.load(ctx, memarg, 8)?;
+                self.push(bits)
+            }
+            Op::I32Load8S { memarg } => {
+                let bits = self.load(ctx, memarg, 1)?;
+                self.push((bits as i8 as i32).into_bits64())
+            }
+            Op::I32Load16S { memarg } => {
+                let bits = self.load(ctx, memarg, 2)?;
+                self.push((bits as i16 as i32).into_bits64())
+            }
+            Op::I64Load8S { memarg } => {
+                let bits = self.load(ctx, memarg, 1)?;
+                self.push((bits as i8 as i64).into_bits64())
+            }
+            Op::I64Load16S { memarg } => {
+                let bits = self.load(ctx, memarg, 2)?;
+                self.push((bits as i16 as i64).into_bits64())
+            }
+            Op::I64Load32S { memarg } => {
+                let bits = self.load(ctx, memarg, 4)?;
+                self.push((bits as i32 as i64).into_bits64())
+            }
+            Op::I32Load8U { memarg } | Op::I64Load8U { memarg } => {
+                let bits = self.load(ctx, memarg, 1)?;
+                self.push(bits)
+            }
+            Op::I32Load16U { memarg } | Op::I64Load16U { memarg } => {
+                let bits = self.load(ctx, memarg, 2)?;
+                self.push(bits)
+            }
+            Op::I64Load32U { memarg } => {
+                let bits = self.load(ctx, memarg, 4)?;
+                self.push(bits)
+            }
+            Op::I32Store8 { memarg } | Op::I64Store8 { memarg } => {
+                self.store(ctx, memarg, 1)?
+            }
+            Op::I32Store16 { memarg } | Op::I64Store16 { memarg } => {
+                self.store(ctx, memarg, 2)?
+            }
+            Op::I32Store { memarg }
+            | Op::F32Store { memarg }
+            | Op::I64Store32 { memarg } => self.store(ctx, memarg, 4)?,
+            Op::I64Store { memarg } | Op::F64Store { memarg } => {
+                self.store(ctx, memarg, 8)?
+            }
+            Op::MemorySize { mem, .. } => {
+                let memory = Mem::from_raw(RawIdx::from_u32(*mem));
+                let pages = ctx
+                    .memory(memory)
+                    .ok_or(InterpretationError::MissingMemory { memory })?
+                    .pages();
+                self.push(u64::from(pages))
+            }
+            Op::MemoryGrow { mem, .. } => {
+                let memory = Mem::from_raw(RawIdx::from_u32(*mem));
+                let new_pages = self.pop() as u32;
+                let limit = ctx.config.resource_limits().max_memory_pages();
+                let memory = ctx
+                    .memory_mut(memory)
+                    .ok_or(InterpretationError::MissingMemory { memory })?;
+                let result = grow_memory(memory, new_pages, limit);
+                self.push(u64::from(result))
+            }
+            Op::I32Const { value } => self.push(*value as u32 as u64),
+            Op::I64Const { value } => self.push(*value as u64),
+            Op::F32Const { value } => self.push(value.bits() as u64),
+            Op::F64Const { value } => self.push(value.bits()),
+            Op::I32Eqz => self.unop(|a: u32| a == 0),
+            Op::I32Eq => self.binop(|a: u32, b: u32| a == b),
+            Op::I32Ne => self.binop(|a: u32, b: u32| a != b),
+            Op::I32LtS => self.binop(|a: i32, b: i32| a < b),
+            Op::I32LtU => self.binop(|a: u32, b: u32| a < b),
+            Op::I32GtS => self.binop(|a: i32, b: i32| a > b),
+            Op::I32GtU => self.binop(|a: u32, b: u32| a > b),
+            Op::I32LeS => self.binop(|a: i32, b: i32| a <= b),
+            Op::I32LeU => self.binop(|a: u32, b: u32| a <= b),
+            Op::I32GeS => self.binop(|a: i32, b: i32| a >= b),
+            Op::I32GeU => self.binop(|a: u32, b: u32| a >= b),
+            Op::I64Eqz => self.unop(|a: u64| a == 0),
+            Op::I64Eq => self.binop(|a: u64, b: u64| a == b),
+            Op::I64Ne => self.binop(|a: u64, b: u64| a != b),
+            Op::I64LtS => self.binop(|a: i64, b: i64| a < b),
+            Op::I64LtU => self.binop(|a: u64, b: u64| a < b),
+            Op::I64GtS => self.binop(|a: i64, b: i64| a > b),
+            Op::I64GtU => self.binop(|a: u64, b: u64| a > b),
+            Op::I64LeS => self.binop(|a: i64, b: i64| a <= b),
+            Op::I64LeU => self.binop(|a: u64, b: u64| a <= b),
+            Op::I64GeS => self.binop(|a: i64, b: i64| a >= b),
+            Op::I64GeU => self.binop(|a: u64, b: u64| a >= b),
+            Op::F32Eq => self.binop(|a: f32, b: f32| a == b),
+            Op::F32Ne => self.binop(|a: f32, b: f32| a != b),
+            Op::F32Lt => self.binop(|a: f32, b: f32| a < b),
+            Op::F32Gt => self.binop(|a: f32, b: f32| a > b),
+            Op::F32Le => self.binop(|a: f32, b: f32| a <= b),
+            Op::F32Ge => self.binop(|a: f32, b: f32| a >= b),
+            Op::F64Eq => self.binop(|a: f64, b: f64| a == b),
+            Op::F64Ne => self.binop(|a: f64, b: f64| a != b),
+            Op::F64Lt => self.binop(|a: f64, b: f64| a < b),
+            Op::F64Gt => self.binop(|a: f64, b: f64| a > b),
+            Op::F64Le => self.binop(|a: f64, b: f64| a <= b),
+            Op::F64Ge => self.binop(|a: f64, b: f64| a >= b),
+            Op::I32Clz => self.unop(u32::leading_zeros),
+            Op::I32Ctz => self.unop(u32::trailing_zeros),
+            Op::I32Popcnt => self.unop(u32::count_ones),
+            Op::I32Add => self.binop(u32::wrapping_add),
+            Op::I32Sub => self.binop(u32::wrapping_sub),
+            Op::I32Mul => self.binop(u32::wrapping_mul),
+            Op::I32DivS => self.try_binop(|a: i32, b: i32| match b {
+                0 => Err(division_by_zero()),
+                _ => a.checked_div(b).ok_or_else(overflow),
+            })?,
+            Op::I32DivU => self.try_binop(|a: u32, b: u32| {
+                a.checked_div(b).ok_or_else(division_by_zero)
+            })?,
+            Op::I32RemS => self.try_binop(|a: i32, b: i32| match b {
+                0 => Err(division_by_zero()),
+                _ => Ok(a.wrapping_rem(b)),
+            })?,
+            Op::I32RemU => self.try_binop(|a: u32, b: u32| {
+                a.checked_rem(b).ok_or_else(division_by_zero)
+            })?,
+            Op::I32And => self.binop(|a: u32, b: u32| a & b),
+            Op::I32Or => self.binop(|a: u32, b: u32| a | b),
+            Op::I32Xor => self.binop(|a: u32, b: u32| a ^ b),
+            Op::I32Shl => self.binop(|a: u32, b: u32| a.wrapping_shl(b)),
+            Op::I32ShrS => {
+                self.binop(|a: i32, b: i32| a.wrapping_shr(b as u32))
+            }
+            Op::I32ShrU => self.binop(|a: u32, b: u32| a.wrapping_shr(b)),
+            Op::I32Rotl => self.binop(|a: u32, b: u32| a.rotate_left(b)),
+            Op::I32Rotr => self.binop(|a: u32, b: u32| a.rotate_right(b)),
+            Op::I64Clz => self.unop(|a: u64| u64::from(a.leading_zeros())),
+            Op::I64Ctz => self.unop(|a: u64| u64::from(a.trailing_zeros())),
+            Op::I64Popcnt => self.unop(|a: u64| u64::from(a.count_ones())),
+            Op::I64Add => self.binop(u64::wrapping_add),
+            Op::I64Sub => self.binop(u64::wrapping_sub),
+            Op::I64Mul => self.binop(u64::wrapping_mul),
+            Op::I64DivS => self.try_binop(|a: i64, b: i64| match b {
+                0 => Err(division_by_zero()),
+                _ => a.checked_div(b).ok_or_else(overflow),
+            })?,
+            Op::I64DivU => self.try_binop(|a: u64, b: u64| {
+                a.checked_div(b).ok_or_else(division_by_zero)
+            })?,
+            Op::I64RemS => self.try_binop(|a: i64, b: i64| match b {
+                0 => Err(division_by_zero()),
+                _ => Ok(a.wrapping_rem(b)),
+            })?,
+            Op::I64RemU => self.try_binop(|a: u64, b: u64| {
+                a.checked_rem(b).ok_or_else(division_by_zero)
+            })?,
+            Op::I64And => self.binop(|a: u64, b: u64| a & b),
+            Op::I64Or => self.binop(|a: u64, b: u64| a | b),
+            Op::I64Xor => self.binop(|a: u64, b: u64| a ^ b),
+            Op::I64Shl => self.binop(|a: u64, b: u64| a.wrapping_shl(b as u32)),
+            Op::I64ShrS => {
+                self.binop(|a: i64, b: i64| a.wrapping_shr(b as u32))
+            }
+            Op::I64ShrU => {
+                self.binop(|a: u64, b: u64| a.wrapping_shr(b as u32))
+            }
+            Op::I64Rotl => self.binop(|a: u64, b: u64| a.rotate_left(b as u32)),
+            Op::I64Rotr => {
+                self.binop(|a: u64, b: u64| a.rotate_right(b as u32))
+            }
+            Op::F32Abs => self.unop(f32::abs),
+            Op::F32Neg => self.unop(|a: f32| -a),
+            Op::F32Ceil => self.unop(f32::ceil),
+            Op::F32Floor => self.unop(f32::floor),
+            Op::F32Trunc => self.unop(f32::trunc),
+            Op::F32Nearest => self.unop(nearest_f32),
+            Op::F32Sqrt => self.unop(f32::sqrt),
+            Op::F32Add => self.binop(|a: f32, b: f32| a + b),
+            Op::F32Sub => self.binop(|a: f32, b: f32| a - b),
+            Op::F32Mul => self.binop(|a: f32, b: f32| a * b),
+            Op::F32Div => self.binop(|a: f32, b: f32| a / b),
+            Op::F32Min if constant_time => self.binop(ct_min_f32),
+            Op::F32Max if constant_time => self.binop(ct_max_f32),
+            Op::F32Min => self.binop(min_f32),
+            Op::F32Max => self.binop(max_f32),
+            Op::F32Copysign => self.binop(f32::copysign),
+            Op::F64Abs => self.unop(f64::abs),
+            Op::F64Neg => self.unop(|a: f64| -a),
+            Op::F64Ceil => self.unop(f64::ceil),
+            Op::F64Floor => self.unop(f64::floor),
+            Op::F64Trunc => self.unop(f64::trunc),
+            Op::F64Nearest => self.unop(nearest_f64),
+            Op::F64Sqrt => self.unop(f64::sqrt),
+            Op::F64Add => self.binop(|a: f64, b: f64| a + b),
+            Op::F64Sub => self.binop(|a: f64, b: f64| a - b),
+            Op::F64Mul => self.binop(|a: f64, b: f64| a * b),
+            Op::F64Div => self.binop(|a: f64, b: f64| a / b),
+            Op::F64Min if constant_time => self.binop(ct_min_f64),
+            Op::F64Max if constant_time => self.binop(ct_max_f64),
+            Op::F64Min => self.binop(min_f64),
+            Op::F64Max => self.binop(max_f64),
+            Op::F64Copysign => self.binop(f64::copysign),
+            Op::I32WrapI64 => self.unop(|a: u64| a as u32),
+            Op::I32TruncF32S => self.try_unop(|a: f32| {
+                checked_trunc(a.into(), i32::MIN as f64, -(i32::MIN as f64))
+                    .map(|t| t as i32)
+            })?,
+            Op::I32TruncF32U => self.try_unop(|a: f32| {
+                checked_trunc(a.into(), 0.0, u32::MAX as f64 + 1.0)
+                    .map(|t| t as u32)
+            })?,
+            Op::I32TruncF64S => self.try_unop(|a: f64| {
+                checked_trunc(a, i32::MIN as f64, -(i32::MIN as f64))
+                    .map(|t| t as i32)
+            })?,
+            Op::I32TruncF64U => self.try_unop(|a: f64| {
+                checked_trunc(a, 0.0, u32::MAX as f64 + 1.0).map(|t| t as u32)
+            })?,
+            Op::I64ExtendI32S => self.unop(|a: i32| a as i64),
+            Op::I64ExtendI32U => self.unop(|a: u32| a as u64),
+            Op::I64TruncF32S => self.try_unop(|a: f32| {
+                checked_trunc(a.into(), i64::MIN as f64, -(i64::MIN as f64))
+                    .map(|t| t as i64)
+            })?,
+            // Note: `u64::MAX as f64` is rounded up to exactly 2^64.
+            Op::I64TruncF32U => self.try_unop(|a: f32| {
+                checked_trunc(a.into(), 0.0, u64::MAX as f64).map(|t| t as u64)
+            })?,
+            Op::I64TruncF64S => self.try_unop(|a: f64| {
+                checked_trunc(a, i64::MIN as f64, -(i64::MIN as f64))
+                    .map(|t| t as i64)
+            })?,
+            Op::I64TruncF64U => self.try_unop(|a: f64| {
+                checked_trunc(a, 0.0, u64::MAX as f64).map(|t| t as u64)
+            })?,
+            // Float to integer casts saturate and convert NaN to zero.
+            Op::I32TruncSatF32S => self.unop(|a: f32| a as i32),
+            Op::I32TruncSatF32U => self.unop(|a: f32| a as u32),
+            Op::I32TruncSatF64S => self.unop(|a: f64| a as i32),
+            Op::I32TruncSatF64U => self.unop(|a: f64| a as u32),
+            Op::I64TruncSatF32S => self.unop(|a: f32| a as i64),
+            Op::I64TruncSatF32U => self.unop(|a: f32| a as u64),
+            Op::I64TruncSatF64S => self.unop(|a: f64| a as i64),
+            Op::I64TruncSatF64U => self.unop(|a: f64| a as u64),
+            Op::F32ConvertI32S => self.unop(|a: i32| a as f32),
+            Op::F32ConvertI32U => self.unop(|a: u32| a as f32),
+            Op::F32ConvertI64S => self.unop(|a: i64| a as f32),
+            Op::F32ConvertI64U => self.unop(|a: u64| a as f32),
+            Op::F32DemoteF64 => self.unop(|a: f64| a as f32),
+            Op::F64ConvertI32S => self.unop(|a: i32| a as f64),
+            Op::F64ConvertI32U => self.unop(|a: u32| a as f64),
+            Op::F64ConvertI64S => self.unop(|a: i64| a as f64),
+            Op::F64ConvertI64U => self.unop(|a: u64| a as f64),
+            Op::F64PromoteF32 => self.unop(|a: f32| a as f64),
+            Op::I32ReinterpretF32
+            | Op::I64ReinterpretF64
+            | Op::F32ReinterpretI32
+            | Op::F64ReinterpretI64 => {
+                // Values of both types share the same register representation.
+            }
+            Op::I32Extend8S => self.unop(|a: u32| a as i8 as i32),
+            Op::I32Extend16S => self.unop(|a: u32| a as i16 as i32),
+            Op::I64Extend8S => self.unop(|a: u64| a as i8 as i64),
+            Op::I64Extend16S => self.unop(|a: u64| a as i16 as i64),
+            Op::I64Extend32S => self.unop(|a: u64| a as i32 as i64),
+            unsupported => {
+                return Err(InterpretationError::UnsupportedOperator {
+                    op: format!("{:?}", unsupported),
+                })
+            }
+        }
+        if ctx.config.canonicalize_nans() {
+            if let Some(ty) = nan_producing_type(&body.ops[self.pc]) {
+                let result = self.pop();
+                self.push(canonicalize_nan(ty, result));
+            }
+        }
+        Ok(Flow::Next)
+    }
+}
+
+/// Returns the bytes of the linear memory accessed by a load or store.
+///
+/// The accessed address is the sum of the dynamic `address` and the static
+/// offset of the memory immediate.
+///
+/// # Errors
+///
+/// - If the linear memory has not been created.
+/// - If the accessed bytes are out of bounds of the linear memory.
+fn memory_bytes<'c>(
+    ctx: &'c mut EvaluationContext,
+    memarg: &MemoryImmediate,
+    address: u32,
+    len: u64,
+) -> Result<&'c mut [u8], InterpretationError> {
+    let memory = Mem::from_raw(RawIdx::from_u32(memarg.memory));
+    let address = u64::from(address) + u64::from(memarg.offset);
+    let data = ctx
+        .memory_mut(memory)
+        .ok_or(InterpretationError::MissingMemory { memory })?
+        .data_mut();
+    memory_range(data, memory, address, len)
+}
+
+/// Returns the float type of the result if the operator may produce a NaN.
+///
+/// Operators that only manipulate the sign bit are excluded since they
+/// preserve the NaN payloads of their operands.
+fn nan_producing_type(op: &Operator) -> Option<FloatType> {
+    use Operator as Op;
+    match op {
+        Op::F32Ceil
+        | Op::F32Floor
+        | Op::F32Trunc
+        | Op::F32Nearest
+        | Op::F32Sqrt
+        | Op::F32Add
+        | Op::F32Sub
+        | Op::F32Mul
+        | Op::F32Div
+        | Op::F32Min
+        | Op::F32Max
+        | Op::F32DemoteF64 => Some(FloatType::F32),
+        Op::F64Ceil
+        | Op::F64Floor
+        | Op::F64Trunc
+        | Op::F64Nearest
+        | Op::F64Sqrt
+        | Op::F64Add
+        | Op::F64Sub
+        | Op::F64Mul
+        | Op::F64Div
+        | Op::F64Min
+        | Op::F64Max
+        | Op::F64PromoteF32 => Some(FloatType::F64),
+        _ => None,
+    }
+}
+
+/// Types that are stored in the 64-bit registers of the interpreter.
+trait Bits: Copy {
+    /// Creates a value from the bits of a register.
+    fn from_bits64(bits: u64) -> Self;
+    /// Converts the value into the bits of a register.
+    fn into_bits64(self) -> u64;
+}
+
+impl Bits for bool {
+    fn from_bits64(bits: u64) -> Self {
+        bits != 0
+    }
+
+    fn into_bits64(self) -> u64 {
+        self as u64
+    }
+}
+
+impl Bits for u32 {
+    fn from_bits64(bits: u64) -> Self {
+        bits as u32
+    }
+
+    fn into_bits64(self) -> u64 {
+        self as u64
+    }
+}
+
+impl Bits for u64 {
+    fn from_bits64(bits: u64) -> Self {
+        bits
+    }
+
+    fn into_bits64(self) -> u64 {
+        self
+    }
+}
+
+impl Bits for i32 {
+    fn from_bits64(bits: u64) -> Self {
+        bits as i32
+    }
+
+    fn into_bits64(self) -> u64 {
+        self as u32 as u64
+    }
+}
+
+impl Bits for i64 {
+    fn from_bits64(bits: u64) -> Self {
+        bits as i64
+    }
+
+    fn into_bits64(self) -> u64 {
+        self as u64
+    }
+}
+
+impl Bits for f32 {
+    fn from_bits64(bits: u64) -> Self {
+        f32::from_bits(bits as u32)
+    }
+
+    fn into_bits64(self) -> u64 {
+        self.to_bits() as u64
+    }
+}
+
+impl Bits for f64 {
+    fn from_bits64(bits: u64) -> Self {
+        f64::from_bits(bits)
+    }
+
+    fn into_bits64(self) -> u64 {
+        self.to_bits()
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Single-step debugging of evaluations.
+
+use super::{EvaluationContext, Register, UnwindGuard};
+use crate::InterpretationError;
+use ir::primitive::{Block, Func, Value};
+use module::{primitive::Instr, Function};
+use std::collections::BTreeSet;
+
+/// A single executed instruction of a debugged evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// The function of the executed instruction.
+    func: Func,
+    /// The basic block of the executed instruction.
+    block: Block,
+    /// The executed instruction.
+    instr: Instr,
+    /// The registers written by the executed instruction and their new bits.
+    written: Vec<(Value, u64)>,
+}
+
+impl Step {
+    /// Returns the function of the executed instruction.
+    pub fn func(&self) -> Func {
+        self.func
+    }
+
+    /// Returns the basic block of the executed instruction.
+    pub fn block(&self) -> Block {
+        self.block
+    }
+
+    /// Returns the executed instruction.
+    pub fn instr(&self) -> Instr {
+        self.instr
+    }
+
+    /// Returns the registers written by the executed instruction.
+    ///
+    /// # Note
+    ///
+    /// The results of calls to functions that are not interpreter-only are
+    /// written once the called function returns and are not reported.
+    pub fn written(&self) -> &[(Value, u64)] {
+        &self.written
+    }
+}
+
+/// Evaluates a function instruction by instruction.
+///
+/// Breakpoints can be registered on specific instructions so that
+/// [`Debugger::run`] stops before executing them.
+///
+/// The function frames of the debugged evaluation are unwound when the
+/// debugger is dropped so that the evaluation context can be reused.
+pub struct Debugger<'c, 'a> {
+    /// The evaluation context of the debugged evaluation.
+    guard: UnwindGuard<'c, 'a>,
+    /// The function of the innermost function frame.
+    ///
+    /// This is `None` once the evaluation has finished or trapped.
+    function: Option<Function<'a>>,
+    /// The outputs of the evaluation once it has finished.
+    outputs: Option<Vec<u64>>,
+    /// The instructions that stop [`Debugger::run`].
+    breakpoints: BTreeSet<(Func, Instr)>,
+}
+
+impl<'c, 'a> Debugger<'c, 'a> {
+    /// Prepares the evaluation of the function with the given inputs.
+    ///
+    /// No instruction is executed until the debugger is stepped.
+    ///
+    /// # Errors
+    ///
+    /// - If the function is an interpreter-only function.
+    /// - If the number of inputs does not match the function.
+    pub fn new<I>(
+        ctx: &'c mut EvaluationContext<'a>,
+        func: Func,
+        inputs: I,
+    ) -> Result<Self, InterpretationError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        if ctx.module.is_interpreter_only(func) {
+            return Err(InterpretationError::InterpreterOnlyFunction { func })
+        }
+        let function = ctx
+            .module
+            .get_function(func)
+            .expect("encountered invalid function index");
+        ctx.check_call_depth()?;
+        let guard = UnwindGuard::new(ctx);
+        guard.ctx.frames.push_frame(func, inputs)?;
+        Ok(Self {
+            guard,
+            function: Some(function),
+            outputs: None,
+            breakpoints: BTreeSet::new(),
+        })
+    }
+
+    /// Registers a breakpoint on the instruction of the function.
+    pub fn set_breakpoint(&mut self, func: Func, instr: Instr) {
+        self.breakpoints.insert((func, instr));
+    }
+
+    /// Removes the breakpoint on the instruction of the function.
+    ///
+    /// Returns `true` if there was such a breakpoint.
+    pub fn remove_breakpoint(&mut self, func: Func, instr: Instr) -> bool {
+        self.breakpoints.remove(&(func, instr))
+    }
+
+    /// Returns the outputs of the evaluation once it has finished.
+    pub fn outputs(&self) -> Option<&[u64]> {
+        self.outputs.as_deref()
+    }
+
+    /// Returns the function, basic block and instruction that is executed
+    /// next if the evaluation has not yet finished.
+    pub fn next_instr(&self) -> Option<(Func, Block, Instr)> {
+        let function = self.function?;
+        let frame = self.guard.ctx.frames.frames.last()?;
+        let block = frame.current_block();
+        let instr =
+            function.body().block_instrs(block)[frame.instruction_counter()];
+        Some((frame.func(), block, instr))
+    }
+
+    /// Executes the next instruction.
+    ///
+    /// Returns `None` if the evaluation has already finished.
+    ///
+    /// # Errors
+    ///
+    /// If the executed instruction traps. This ends the evaluation unless
+    /// the evaluation context ran out of fuel before executing the
+    /// instruction. In this case the evaluation can be continued after
+    /// refueling.
+    pub fn step(&mut self) -> Result<Option<Step>, InterpretationError> {
+        let mut function = match self.function {
+            Some(function) => function,
+            None => return Ok(None),
+        };
+        let depth = self.guard.depth;
+        let ctx = &mut *self.guard.ctx;
+        let len_frames = ctx.frames.len();
+        let frame = ctx
+            .frames
+            .frames
+            .last()
+            .expect("cannot execute without an activation frame");
+        let func = frame.func();
+        let block = frame.current_block();
+        let ptr = frame.stack_pointer();
+        let (output_values, _) = function
+            .body()
+            .instruction_and_value(block, frame.instruction_counter())
+            .expect("missing instruction in function");
+        let instr =
+            function.body().block_instrs(block)[frame.instruction_counter()];
+        ctx.fuel_checkpoint = false;
+        let returned = match ctx.evaluate_next_instr(&mut function, depth + 1) {
+            Ok(returned) => returned,
+            Err(error) => {
+                if error != InterpretationError::OutOfFuel
+                    || !ctx.fuel_checkpoint
+                {
+                    ctx.frames.unwind(depth);
+                    ctx.scratch.clear();
+                    self.function = None;
+                }
+                ctx.fuel_checkpoint = false;
+                return Err(error)
+            }
+        };
+        let mut written = Vec::new();
+        if ctx.frames.len() == len_frames {
+            written.extend(output_values.iter().flatten().map(|&value| {
+                (value, ctx.frames.stack.read_register(ptr + value))
+            }));
+        }
+        if returned {
+            self.function = None;
+            self.outputs =
+                Some(ctx.scratch.drain(..).map(Register::into_u64).collect());
+        } else {
+            self.function = Some(function);
+        }
+        Ok(Some(Step {
+            func,
+            block,
+            instr,
+            written,
+        }))
+    }
+
+    /// Executes instructions until the next instruction has a breakpoint
+    /// or the evaluation has finished.
+    ///
+    /// Executes at least one instruction so that repeated calls continue
+    /// past the current breakpoint.
+    ///
+    /// Returns the last executed instruction or `None` if the evaluation
+    /// has already finished.
+    ///
+    /// # Errors
+    ///
+    /// If an executed instruction traps.
+    pub fn run(&mut self) -> Result<Option<Step>, InterpretationError> {
+        let mut last = None;
+        while let Some(step) = self.step()? {
+            last = Some(step);
+            match self.next_instr() {
+                Some((func, _, instr))
+                    if self.breakpoints.contains(&(func, instr)) =>
+                {
+                    break
+                }
+                Some(_) => continue,
+                None => break,
+            }
+        }
+        Ok(last)
+    }
+}
@@ -13,7 +13,6 @@
 // limitations under the License.
 
 use super::stack::Ptr;
-use core::mem::replace;
 use ir::primitive::{Block, Func};
 
 /// A function's stack frame.
@@ -22,8 +21,6 @@ pub struct Frame {
     func: Func,
     /// The currently executing basic block.
     current_block: Block,
-    /// The last executing basic block of none.
-    last_block: Option<Block>,
     /// The currently executing instruction of the currently executing basic block.
     instruction_counter: usize,
     /// The stack pointer on the global stack for the frame.
@@ -36,12 +33,26 @@ impl Frame {
         Self {
             func,
             current_block: entry_block,
-            last_block: None,
             instruction_counter: 0,
             stack_pointer,
         }
     }
 
+    /// Returns a function frame that continues a suspended evaluation.
+    pub fn restore(
+        func: Func,
+        stack_pointer: Ptr,
+        current_block: Block,
+        instruction_counter: usize,
+    ) -> Self {
+        Self {
+            func,
+            current_block,
+            instruction_counter,
+            stack_pointer,
+        }
+    }
+
     /// Returns the function associated to this function frame.
     pub fn func(&self) -> Func {
         self.func
@@ -49,8 +60,7 @@ impl Frame {
 
     /// Switches the currently executed basic block.
     pub fn switch_to_block(&mut self, block: Block) {
-        let last_block = replace(&mut self.current_block, block);
-        self.last_block = Some(last_block);
+        self.current_block = block;
         self.instruction_counter = 0;
     }
 
@@ -59,9 +69,14 @@ impl Frame {
         self.current_block
     }
 
-    /// Returns the last executed basic block if any.
-    pub fn last_block(&self) -> Option<Block> {
-        self.last_block
+    /// Returns `true` if the current basic block has just been entered.
+    pub fn is_at_block_start(&self) -> bool {
+        self.instruction_counter == 0
+    }
+
+    /// Returns the instruction counter of the next executed instruction.
+    pub fn instruction_counter(&self) -> usize {
+        self.instruction_counter
     }
 
     /// Bumps the instruction counter by one and returns its value before the bump.
@@ -15,22 +15,67 @@
 //! Allows to interpret the Runwell IR.
 
 mod act_frame;
+mod baseline;
+mod debug;
 mod frame;
+mod snapshot;
 mod stack;
 
-pub use self::act_frame::ActivationFrame;
+pub use self::{
+    act_frame::ActivationFrame,
+    debug::{Debugger, Step},
+    snapshot::{Evaluation, Snapshot},
+};
 use self::{
+    baseline::BaselineBody,
     frame::Frame,
     stack::{Register, Stack},
 };
 pub use crate::error::InterpretationError;
-use crate::instr::{InterpretInstr, InterpretationFlow};
-use ir::primitive::Func;
-use module::{Function, Module};
+use crate::{
+    instr::{InterpretInstr, InterpretationFlow},
+    trace::TraceHook,
+    ClockProvider,
+    Coverage,
+    EntropyProvider,
+    ExecutionConfig,
+    ExecutionStats,
+    FixedClock,
+    GlobalHandle,
+    ImportRenames,
+    LinearMemory,
+    MemoryCreator,
+    Profile,
+    RegisterFile,
+    SeededEntropy,
+    TrapLocation,
+    VecMemoryCreator,
+};
+use entity::{ComponentMap, RawIdx};
+use ir::{
+    instr::{Instruction, TrapCode},
+    primitive::{Const, Func, FuncType, Mem, Table, Value},
+    CostModel,
+    UniformCostModel,
+    VisitValues,
+};
+use module::{
+    primitive::{Global, GlobalInit, ImportName, InitExpr, Instr},
+    Function,
+    Module,
+};
+use std::sync::Arc;
+
+/// The default maximum number of active function frames of an evaluation context.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1024;
 
 /// The evaluation context for the entire virtual machine.
 ///
 /// This holds all the mutable data such as the actual linear memory.
+///
+/// The referenced [`Module`] is never mutated during evaluation. Therefore
+/// multiple evaluation contexts may evaluate the same module concurrently
+/// from different threads as long as each thread owns its own context.
 #[derive(Debug)]
 pub struct EvaluationContext<'a> {
     /// The module that holds immutable data.
@@ -39,6 +84,49 @@ pub struct EvaluationContext<'a> {
     frames: Frames<'a>,
     /// A scratch buffer to store intermediate state between function executions.
     scratch: Vec<Register>,
+    /// The execution profile if profiling is enabled.
+    profile: Option<Profile>,
+    /// The basic block coverage if coverage tracking is enabled.
+    coverage: Option<Coverage>,
+    /// The configuration of the execution semantics.
+    config: ExecutionConfig,
+    /// The execution statistics if statistics collection is enabled.
+    stats: Option<ExecutionStats>,
+    /// The callback invoked before each interpreted instruction if any.
+    trace_hook: Option<TraceHook>,
+    /// The remaining fuel if fuel metering is enabled.
+    fuel: Option<u64>,
+    /// The cost model that determines the fuel consumed per instruction.
+    cost_model: Box<dyn CostModel>,
+    /// The clock queried by host functions.
+    clock: Box<dyn ClockProvider>,
+    /// The source of entropy queried by host functions.
+    entropy: Box<dyn EntropyProvider>,
+    /// The global variables that have been initialized or linked so far.
+    globals: ComponentMap<Global, GlobalHandle>,
+    /// The internal linear memories of the module.
+    memories: ComponentMap<Mem, Box<dyn LinearMemory>>,
+    /// The function references of the internal tables of the module.
+    tables: ComponentMap<Table, Vec<Option<Func>>>,
+    /// The renames applied to import names before imports are resolved.
+    import_renames: ImportRenames,
+    /// Whether the last evaluation ran out of fuel before an instruction of
+    /// the Runwell IR interpreter with its function frames left intact.
+    fuel_checkpoint: bool,
+    /// The number of active function frames of the baseline tier.
+    baseline_frames: usize,
+    /// The decoded bodies of the interpreter-only functions evaluated so far.
+    baseline_bodies: ComponentMap<Func, Arc<BaselineBody<'a>>>,
+    /// The maximum number of active function frames.
+    max_call_depth: usize,
+    /// Whether the register file of the outermost function frame is captured.
+    capture_register_file: bool,
+    /// The register file of the outermost function frame of the last
+    /// evaluation if it has been captured.
+    register_file: Option<RegisterFile>,
+    /// The location of the instruction that caused the trap of the last
+    /// evaluation if any.
+    trap_location: Option<TrapLocation>,
 }
 
 /// The value stack and the function frames.
@@ -63,6 +151,8 @@ impl<'a> Frames<'a> {
     }
 
     /// Pushes a stack frame onto the stack for the given function.
+    ///
+    /// The inputs are trusted to be of the input types of the function.
     pub fn push_frame<I>(
         &mut self,
         func: Func,
@@ -70,6 +160,29 @@ impl<'a> Frames<'a> {
     ) -> Result<(), InterpretationError>
     where
         I: IntoIterator<Item = u64>,
+    {
+        let input_types = self
+            .module
+            .get_function(func)
+            .expect("encountered invalid function index")
+            .inputs();
+        let inputs = inputs.into_iter().enumerate().map(|(n, bits)| {
+            Register::with_type(bits, input_types.get(n).copied())
+        });
+        self.push_frame_registers(func, inputs)
+    }
+
+    /// Pushes a stack frame onto the stack for the given function.
+    ///
+    /// Unlike [`Frames::push_frame`] the inputs keep the types of the
+    /// registers they have been read from.
+    fn push_frame_registers<I>(
+        &mut self,
+        func: Func,
+        inputs: I,
+    ) -> Result<(), InterpretationError>
+    where
+        I: IntoIterator<Item = Register>,
     {
         let function = self
             .module
@@ -80,6 +193,7 @@ impl<'a> Frames<'a> {
         let given_inputs = self.stack.initialize(sp, inputs);
         let required_inputs = function.inputs().len();
         if given_inputs != required_inputs {
+            self.stack.pop(sp);
             return Err(InterpretationError::UnmatchingInputValues {
                 given_inputs,
                 required_inputs,
@@ -90,12 +204,41 @@ impl<'a> Frames<'a> {
         Ok(())
     }
 
+    /// Returns the number of currently active frames.
+    fn len(&self) -> usize {
+        self.frames.len()
+    }
+
     /// Pops the last stack frame from the stack.
     pub fn pop_frame(&mut self) {
         let frame = self.frames.pop().expect("encountered missing stack frame");
         self.stack.pop(frame.stack_pointer());
     }
 
+    /// Pops all stack frames above the given depth from the stack.
+    fn unwind(&mut self, depth: usize) {
+        while self.frames.len() > depth {
+            self.pop_frame();
+        }
+    }
+
+    /// Returns the register file of the last stack frame.
+    fn last_register_file(&self, function: Function) -> RegisterFile {
+        let frame =
+            self.frames.last().expect("encountered missing stack frame");
+        let body = function.body();
+        let len = body.max_value().into_raw().into_u32() + 1;
+        let registers = (0..len)
+            .map(|n| {
+                let value = Value::from_raw(RawIdx::from_u32(n));
+                let bits =
+                    self.stack.read_register(frame.stack_pointer() + value);
+                (bits, body.value_type(value))
+            })
+            .collect();
+        RegisterFile::new(frame.func(), registers)
+    }
+
     /// Returns a mutable reference to the stack and to the last function frame.
     fn last_frame_mut(&mut self) -> Option<(&mut Stack, &mut Frame)> {
         let frame = self.frames.last_mut()?;
@@ -103,16 +246,603 @@ impl<'a> Frames<'a> {
     }
 }
 
+/// Restores the function frames of an evaluation context when dropped.
+///
+/// Evaluations that trap or panic leave their function frames behind.
+/// Unwinding them to the depth at which the evaluation started allows to
+/// keep using the evaluation context afterwards.
+struct UnwindGuard<'c, 'a> {
+    /// The guarded evaluation context.
+    ctx: &'c mut EvaluationContext<'a>,
+    /// The number of active frames before the evaluation started.
+    depth: usize,
+    /// The number of active baseline frames before the evaluation started.
+    baseline_frames: usize,
+}
+
+impl<'c, 'a> UnwindGuard<'c, 'a> {
+    /// Creates a new guard for the current frames of the evaluation context.
+    fn new(ctx: &'c mut EvaluationContext<'a>) -> Self {
+        let depth = ctx.frames.len();
+        let baseline_frames = ctx.baseline_frames;
+        Self {
+            ctx,
+            depth,
+            baseline_frames,
+        }
+    }
+}
+
+impl Drop for UnwindGuard<'_, '_> {
+    fn drop(&mut self) {
+        self.ctx.baseline_frames = self.baseline_frames;
+        if self.ctx.frames.len() > self.depth {
+            self.ctx.frames.unwind(self.depth);
+            self.ctx.scratch.clear();
+            self.ctx.fuel_checkpoint = false;
+        }
+    }
+}
+
 impl<'a> EvaluationContext<'a> {
     /// Creates a new evaluation context from the given shared reference to the store.
+    ///
+    /// The linear memories of the module are backed by `Vec<u8>`.
+    ///
+    /// # Panics
+    ///
+    /// If the linear memories of the module cannot be allocated.
     pub fn new(module: &'a Module) -> Self {
-        Self {
+        Self::with_memory_creator(module, &VecMemoryCreator)
+            .expect("failed to allocate linear memories")
+    }
+
+    /// Creates a new evaluation context that allocates its linear memories
+    /// using the given memory creator.
+    ///
+    /// # Errors
+    ///
+    /// - If the memory creator fails to create a linear memory of the module.
+    /// - If an element segment does not fit into its table.
+    pub fn with_memory_creator(
+        module: &'a Module,
+        creator: &dyn MemoryCreator,
+    ) -> Result<Self, InterpretationError> {
+        Self::with_config(module, creator, Default::default())
+    }
+
+    /// Creates a new evaluation context that allocates its linear memories
+    /// using the given memory creator and evaluates with the configuration.
+    ///
+    /// # Errors
+    ///
+    /// - If a linear memory or table of the module exceeds the resource
+    ///   limits of the configuration with its initial size.
+    /// - If the memory creator fails to create a linear memory of the module.
+    /// - If an element segment does not fit into its table.
+    pub fn with_config(
+        module: &'a Module,
+        creator: &dyn MemoryCreator,
+        config: ExecutionConfig,
+    ) -> Result<Self, InterpretationError> {
+        let limits = config.resource_limits();
+        let mut memories = ComponentMap::default();
+        for memory in module.memories() {
+            let decl = module
+                .get_memory(memory)
+                .expect("encountered missing linear memory declaration");
+            let (pages, limit) =
+                (decl.initial_pages(), limits.max_memory_pages());
+            if pages > limit {
+                return Err(InterpretationError::MemoryLimitExceeded {
+                    memory,
+                    pages,
+                    limit,
+                })
+            }
+            if module.get_memory_import(memory).is_some() {
+                continue
+            }
+            memories.insert(memory, creator.create_memory(decl)?);
+        }
+        for table in module.tables() {
+            let decl = module
+                .get_table(table)
+                .expect("encountered missing table declaration");
+            let (size, limit) = (decl.initial_size(), limits.max_table_size());
+            if size > limit {
+                return Err(InterpretationError::TableLimitExceeded {
+                    table,
+                    size,
+                    limit,
+                })
+            }
+        }
+        let mut ctx = Self {
             module,
             frames: Frames::new(module),
             scratch: Default::default(),
+            profile: None,
+            coverage: None,
+            config,
+            stats: None,
+            trace_hook: None,
+            fuel: None,
+            cost_model: Box::new(UniformCostModel::default()),
+            clock: Box::new(FixedClock::default()),
+            entropy: Box::new(SeededEntropy::default()),
+            globals: Default::default(),
+            memories,
+            tables: Default::default(),
+            import_renames: Default::default(),
+            fuel_checkpoint: false,
+            baseline_frames: 0,
+            baseline_bodies: Default::default(),
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            capture_register_file: false,
+            register_file: None,
+            trap_location: None,
+        };
+        ctx.initialize_globals();
+        ctx.initialize_tables()?;
+        Ok(ctx)
+    }
+
+    /// Initializes all internal global variables whose initializers can be resolved.
+    ///
+    /// Global variables initialized by imported global variables can only be
+    /// resolved after the imported global variable has been linked.
+    fn initialize_globals(&mut self) {
+        let module = self.module;
+        for global in module.globals() {
+            if self.globals.contains_key(global) {
+                continue
+            }
+            let (decl, init) = module
+                .get_global(global)
+                .expect("encountered invalid global variable index");
+            let init = match init {
+                GlobalInit::Define(InitExpr::Const(value)) => *value,
+                GlobalInit::Define(InitExpr::GlobalGet(source)) => {
+                    match self.globals.get(*source) {
+                        Some(source) => source.get(),
+                        None => continue,
+                    }
+                }
+                GlobalInit::Import(_) => continue,
+            };
+            self.globals
+                .insert(global, GlobalHandle::new(init, decl.is_mutable()));
+        }
+    }
+
+    /// Initializes all internal tables with their element segments.
+    ///
+    /// Element segments at offsets given by imported global variables are
+    /// only applied after the imported global variable has been linked.
+    fn initialize_tables(&mut self) -> Result<(), InterpretationError> {
+        let module = self.module;
+        for table in module.tables() {
+            if module.get_table_import(table).is_some() {
+                continue
+            }
+            let decl = module
+                .get_table(table)
+                .expect("encountered missing table declaration");
+            let init = module
+                .get_table_init(table)
+                .expect("encountered missing table initializer");
+            let mut elements = vec![None; decl.initial_size() as usize];
+            for (offset, funcs) in init {
+                let offset = match offset {
+                    InitExpr::Const(value) => *value,
+                    InitExpr::GlobalGet(source) => {
+                        match self.globals.get(*source) {
+                            Some(source) => source.get(),
+                            None => continue,
+                        }
+                    }
+                };
+                let offset = offset.into_bits64() as u32;
+                let start = offset as usize;
+                let end = start
+                    .checked_add(funcs.len())
+                    .filter(|&end| end <= elements.len())
+                    .ok_or(InterpretationError::TableElementsOutOfBounds {
+                        table,
+                        offset,
+                        len: funcs.len(),
+                        size: decl.initial_size(),
+                    })?;
+                let slots = &mut elements[start..end];
+                for (slot, &func) in slots.iter_mut().zip(funcs) {
+                    *slot = Some(func);
+                }
+            }
+            self.tables.insert(table, elements);
+        }
+        Ok(())
+    }
+
+    /// Sets the maximum number of active function frames.
+    ///
+    /// Calls that exceed the maximum call depth trap with
+    /// [`TrapCode::StackOverflow`]. This bounds the memory used for function
+    /// frames as well as the host stack used by the baseline tier.
+    ///
+    /// The default is [`DEFAULT_MAX_CALL_DEPTH`].
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Returns the maximum number of active function frames.
+    pub fn max_call_depth(&self) -> usize {
+        self.max_call_depth
+    }
+
+    /// Returns an error if another function frame exceeds the maximum call depth.
+    fn check_call_depth(&self) -> Result<(), InterpretationError> {
+        if self.call_depth() >= self.max_call_depth {
+            return Err(InterpretationError::EvaluationHasTrapped {
+                code: TrapCode::StackOverflow,
+            })
+        }
+        Ok(())
+    }
+
+    /// Sets the configuration of the execution semantics for all subsequent evaluations.
+    pub fn set_execution_config(&mut self, config: ExecutionConfig) {
+        self.config = config;
+    }
+
+    /// Returns the configuration of the execution semantics.
+    pub fn execution_config(&self) -> &ExecutionConfig {
+        &self.config
+    }
+
+    /// Sets the renames applied to import names before imports are resolved.
+    ///
+    /// Afterwards imports are resolved by their renamed import names.
+    pub fn set_import_renames(&mut self, renames: ImportRenames) {
+        self.import_renames = renames;
+    }
+
+    /// Returns the imported global variable that is resolved by the name.
+    fn get_imported_global(&self, name: &ImportName) -> Option<Global> {
+        let module = self.module;
+        module
+            .globals()
+            .find(|&global| match module.get_global(global) {
+                Some((_, GlobalInit::Import(import_name))) => {
+                    self.import_renames.resolve(import_name) == *name
+                }
+                _ => false,
+            })
+    }
+
+    /// Links the imported global variable with the given name to the handle.
+    ///
+    /// Afterwards reads and writes of the imported global variable are
+    /// shared with all other users of the handle, e.g. the evaluation
+    /// context exporting it.
+    ///
+    /// The name is matched against the import names after applying the
+    /// import renames of the evaluation context.
+    ///
+    /// # Errors
+    ///
+    /// - If the module does not import a global variable with the name.
+    /// - If the type or mutability of the handle does not match the import.
+    /// - If an element segment at an offset given by the global variable
+    ///   does not fit into its table.
+    pub fn link_global(
+        &mut self,
+        name: &ImportName,
+        handle: GlobalHandle,
+    ) -> Result<(), InterpretationError> {
+        let global = self.get_imported_global(name).ok_or_else(|| {
+            InterpretationError::UnknownImportedGlobal { name: name.clone() }
+        })?;
+        let (decl, _) = self
+            .module
+            .get_global(global)
+            .expect("encountered invalid global variable index");
+        if decl.ty() != handle.ty() || decl.is_mutable() != handle.is_mutable()
+        {
+            return Err(InterpretationError::UnmatchingGlobalImport {
+                name: name.clone(),
+                given_type: handle.ty(),
+                given_mutability: handle.is_mutable(),
+                expected_type: decl.ty(),
+                expected_mutability: decl.is_mutable(),
+            })
+        }
+        self.globals.insert(global, handle);
+        self.initialize_globals();
+        self.initialize_tables()?;
+        Ok(())
+    }
+
+    /// Returns the handle to the global variable if it is initialized or linked.
+    pub fn global(&self, global: Global) -> Option<&GlobalHandle> {
+        self.globals.get(global)
+    }
+
+    /// Returns a handle to the global variable exported under the given name.
+    ///
+    /// Returns `None` if there is no such global variable or if it is an
+    /// imported global variable that has not yet been linked.
+    pub fn exported_global(&self, name: &str) -> Option<GlobalHandle> {
+        let global = self.module.get_exported_global(name)?;
+        self.global(global).cloned()
+    }
+
+    /// Returns the current value of the global variable if it is initialized
+    /// or linked.
+    pub fn global_value(&self, global: Global) -> Option<Const> {
+        self.global(global).map(GlobalHandle::get)
+    }
+
+    /// Returns the `len` bytes of the linear memory starting at `address`.
+    ///
+    /// # Errors
+    ///
+    /// - If the linear memory has not been created.
+    /// - If the range of bytes is out of bounds of the linear memory.
+    pub fn read_memory(
+        &self,
+        memory: Mem,
+        address: u32,
+        len: usize,
+    ) -> Result<&[u8], InterpretationError> {
+        let data = self
+            .memory(memory)
+            .ok_or(InterpretationError::MissingMemory { memory })?
+            .data();
+        let start = address as usize;
+        start
+            .checked_add(len)
+            .and_then(|end| data.get(start..end))
+            .ok_or(InterpretationError::MemoryOutOfBounds {
+                memory,
+                address: u64::from(address),
+                len: len as u64,
+            })
+    }
+
+    /// Returns the linear memory if it has been created.
+    ///
+    /// Imported linear memories are not created by the evaluation context.
+    pub fn memory(&self, memory: Mem) -> Option<&dyn LinearMemory> {
+        self.memories.get(memory).map(|memory| &**memory)
+    }
+
+    /// Returns the mutable linear memory if it has been created.
+    pub fn memory_mut(
+        &mut self,
+        memory: Mem,
+    ) -> Option<&mut dyn LinearMemory> {
+        let memory = self.memories.get_mut(memory)?;
+        Some(&mut **memory)
+    }
+
+    /// Enables collection of an execution profile for all subsequent evaluations.
+    ///
+    /// Does nothing if profiling is already enabled.
+    pub fn enable_profiling(&mut self) {
+        self.profile.get_or_insert_with(Default::default);
+    }
+
+    /// Returns the execution profile collected so far if profiling is enabled.
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Takes the execution profile collected so far and disables profiling.
+    pub fn take_profile(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    /// Enables tracking of the basic block coverage for all subsequent evaluations.
+    ///
+    /// Does nothing if coverage tracking is already enabled.
+    pub fn enable_coverage(&mut self) {
+        self.coverage.get_or_insert_with(Default::default);
+    }
+
+    /// Returns the coverage tracked so far if coverage tracking is enabled.
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Takes the coverage tracked so far and disables coverage tracking.
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+
+    /// Enables collection of execution statistics for all subsequent evaluations.
+    ///
+    /// Does nothing if statistics collection is already enabled.
+    ///
+    /// # Note
+    ///
+    /// Only instructions interpreted in Runwell IR are counted.
+    pub fn enable_statistics(&mut self) {
+        self.stats.get_or_insert_with(Default::default);
+    }
+
+    /// Returns the execution statistics collected so far if enabled.
+    pub fn statistics(&self) -> Option<&ExecutionStats> {
+        self.stats.as_ref()
+    }
+
+    /// Takes the execution statistics collected so far and disables their collection.
+    pub fn take_statistics(&mut self) -> Option<ExecutionStats> {
+        self.stats.take()
+    }
+
+    /// Enables capturing the register file of the outermost function frame
+    /// when it returns for all subsequent evaluations.
+    ///
+    /// # Note
+    ///
+    /// Only functions interpreted in Runwell IR have a register file.
+    pub fn enable_register_file_capture(&mut self) {
+        self.capture_register_file = true;
+    }
+
+    /// Returns the register file of the outermost function frame of the last
+    /// evaluation if it has been captured.
+    ///
+    /// Evaluations that fail do not capture a register file.
+    pub fn register_file(&self) -> Option<&RegisterFile> {
+        self.register_file.as_ref()
+    }
+
+    /// Takes the captured register file and disables capturing register files.
+    pub fn take_register_file(&mut self) -> Option<RegisterFile> {
+        self.capture_register_file = false;
+        self.register_file.take()
+    }
+
+    /// Returns the location of the instruction that caused the trap of the
+    /// last evaluation if any.
+    ///
+    /// # Note
+    ///
+    /// Only traps of instructions interpreted in Runwell IR have a location.
+    /// Traps of interpreter-only functions do not.
+    pub fn trap_location(&self) -> Option<&TrapLocation> {
+        self.trap_location.as_ref()
+    }
+
+    /// Sets the callback that is invoked before each interpreted instruction.
+    ///
+    /// The callback receives the instruction and the bits of its operands.
+    /// Replaces the previously set callback if any.
+    ///
+    /// # Note
+    ///
+    /// Only instructions interpreted in Runwell IR are traced. The operators
+    /// of interpreter-only functions are not.
+    pub fn set_trace_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(Instr, &Instruction, &[u64]) + Send + 'static,
+    {
+        self.trace_hook = Some(TraceHook::new(hook));
+    }
+
+    /// Removes the callback that is invoked before each interpreted instruction.
+    pub fn clear_trace_hook(&mut self) {
+        self.trace_hook = None;
+    }
+
+    /// Enables fuel metering with the given amount of fuel.
+    ///
+    /// Every interpreted instruction consumes the fuel determined by the
+    /// cost model of the evaluation context which defaults to one unit of
+    /// fuel per instruction. Evaluations fail with
+    /// [`InterpretationError::OutOfFuel`] once all fuel has been consumed.
+    /// Fuel is not refilled between evaluations.
+    pub fn set_fuel(&mut self, fuel: u64) {
+        self.fuel = Some(fuel);
+    }
+
+    /// Returns the remaining fuel if fuel metering is enabled.
+    pub fn remaining_fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Disables fuel metering.
+    pub fn disable_fuel(&mut self) {
+        self.fuel = None;
+    }
+
+    /// Sets the cost model that determines the fuel consumed per instruction.
+    pub fn set_cost_model<C>(&mut self, cost_model: C)
+    where
+        C: CostModel + 'static,
+    {
+        self.cost_model = Box::new(cost_model);
+    }
+
+    /// Returns the cost model that determines the fuel consumed per instruction.
+    pub fn cost_model(&self) -> &dyn CostModel {
+        &*self.cost_model
+    }
+
+    /// Sets the clock queried by host functions.
+    ///
+    /// Defaults to a [`FixedClock`] stopped at the Unix epoch.
+    pub fn set_clock_provider<C>(&mut self, clock: C)
+    where
+        C: ClockProvider + 'static,
+    {
+        self.clock = Box::new(clock);
+    }
+
+    /// Returns the clock queried by host functions.
+    pub fn clock_provider(&self) -> &dyn ClockProvider {
+        &*self.clock
+    }
+
+    /// Sets the source of entropy queried by host functions.
+    ///
+    /// Defaults to a [`SeededEntropy`] seeded with zeros.
+    pub fn set_entropy_provider<E>(&mut self, entropy: E)
+    where
+        E: EntropyProvider + 'static,
+    {
+        self.entropy = Box::new(entropy);
+    }
+
+    /// Returns the source of entropy queried by host functions.
+    pub fn entropy_provider(&mut self) -> &mut dyn EntropyProvider {
+        &mut *self.entropy
+    }
+
+    /// Returns the cost of the instruction executed next by the last frame.
+    ///
+    /// Growing a linear memory is additionally charged for the requested
+    /// pages before the memory is grown.
+    fn next_instruction_cost(&self, function: &Function) -> u64 {
+        let frame = self
+            .frames
+            .frames
+            .last()
+            .expect("cannot execute without an activation frame");
+        let (_, instruction) = function
+            .body()
+            .instruction_and_value(
+                frame.current_block(),
+                frame.instruction_counter(),
+            )
+            .expect("missing instruction in function");
+        let cost = self.cost_model.instruction_cost(instruction);
+        match instruction {
+            Instruction::MemoryGrow(instr) => {
+                let ptr = frame.stack_pointer() + instr.new_pages();
+                let pages = self.frames.stack.read_register(ptr) as u32;
+                cost.saturating_add(self.cost_model.memory_grow_cost(pages))
+            }
+            _ => cost,
         }
     }
 
+    /// Consumes the given amount of fuel if fuel metering is enabled.
+    ///
+    /// # Errors
+    ///
+    /// If there is not enough fuel left. In this case no fuel is consumed.
+    fn consume_fuel(&mut self, amount: u64) -> Result<(), InterpretationError> {
+        if let Some(fuel) = &mut self.fuel {
+            *fuel = fuel
+                .checked_sub(amount)
+                .ok_or(InterpretationError::OutOfFuel)?;
+        }
+        Ok(())
+    }
+
     /// Evaluates the given function.
     ///
     /// This creates a new call frame for the function which can be costly.
@@ -122,6 +852,10 @@ impl<'a> EvaluationContext<'a> {
     ///
     /// This API is for use externally to the interpreter.
     /// Users call it in order to invoke the entry level function.
+    ///
+    /// Traps are propagated as errors. Upon a trap or a panic the function
+    /// frames of the evaluation are unwound so that the evaluation context
+    /// can be reused for further evaluations.
     pub fn evaluate_function<I, O>(
         &mut self,
         func: Func,
@@ -132,12 +866,129 @@ impl<'a> EvaluationContext<'a> {
         I: IntoIterator<Item = u64>,
         O: FnMut(u64),
     {
-        self.frames.push_frame(func, inputs)?;
-        let function = self
+        self.register_file = None;
+        self.trap_location = None;
+        if self.module.is_interpreter_only(func) {
+            let inputs = inputs.into_iter().collect::<Vec<_>>();
+            baseline::evaluate_function(self, func, &inputs)?
+                .into_iter()
+                .for_each(outputs);
+            return Ok(())
+        }
+        self.check_call_depth()?;
+        let guard = UnwindGuard::new(self);
+        let ctx = &mut *guard.ctx;
+        ctx.frames.push_frame(func, inputs)?;
+        let function = ctx
             .module
             .get_function(func)
             .expect("encountered invalid function index");
-        self.evaluate_function_frame(function, outputs)?;
+        let depth = ctx.frames.len();
+        ctx.evaluate_function_frame(function, depth, outputs)?;
+        Ok(())
+    }
+
+    /// Returns the number of currently active function frames.
+    ///
+    /// This is zero unless called during an evaluation, e.g. from within
+    /// a linear memory provided by the embedder. Function frames of both
+    /// the Runwell IR interpreter and the baseline tier are counted.
+    pub fn call_depth(&self) -> usize {
+        self.frames.len() + self.baseline_frames
+    }
+
+    /// Invokes the exported function with the given name and arguments.
+    ///
+    /// This is the dynamically typed counterpart to
+    /// [`EvaluationContext::evaluate_function`] that checks the arguments
+    /// against the signature of the function and converts the results
+    /// according to it.
+    ///
+    /// # Errors
+    ///
+    /// - If the module has no exported function with the given name.
+    /// - If the types of the arguments do not match the function inputs.
+    /// - If the evaluation of the function fails.
+    pub fn invoke(
+        &mut self,
+        name: &str,
+        args: &[Const],
+    ) -> Result<Vec<Const>, InterpretationError> {
+        let func = self.module.get_exported_function(name).ok_or_else(|| {
+            InterpretationError::UnknownExportedFunction {
+                name: name.to_string(),
+            }
+        })?;
+        let function = self
+            .module
+            .get_function(func)
+            .expect("encountered invalid exported function index");
+        let given_types = args.iter().map(Const::ty);
+        if !given_types.clone().eq(function.inputs().iter().copied()) {
+            return Err(InterpretationError::UnmatchingArgumentTypes {
+                given_types: given_types.collect(),
+                expected_types: function.inputs().to_vec(),
+            })
+        }
+        let mut outputs = function.outputs().iter().copied();
+        let mut results = Vec::with_capacity(outputs.len());
+        self.evaluate_function(
+            func,
+            args.iter().copied().map(Const::into_bits64),
+            |bits| {
+                let ty = outputs
+                    .next()
+                    .expect("encountered more results than function outputs");
+                results.push(Const::from_bits64(ty, bits))
+            },
+        )?;
+        Ok(results)
+    }
+
+    /// Evaluates the given function and returns its outputs.
+    ///
+    /// # Note
+    ///
+    /// This API is for use internally to the interpreter.
+    /// It is used to call back into the Runwell IR interpreter from the
+    /// baseline tier.
+    fn call_function(
+        &mut self,
+        func: Func,
+        inputs: &[u64],
+    ) -> Result<Vec<u64>, InterpretationError> {
+        let mut outputs = Vec::new();
+        self.evaluate_function(func, inputs.iter().copied(), |output| {
+            outputs.push(output)
+        })?;
+        Ok(outputs)
+    }
+
+    /// Evaluates the interpreter-only function with the inputs in the scratch buffer.
+    ///
+    /// Stores the outputs of the evaluation in the scratch buffer.
+    fn evaluate_baseline_from_scratch(
+        &mut self,
+        func: Func,
+    ) -> Result<(), InterpretationError> {
+        let inputs = self
+            .scratch
+            .drain(..)
+            .map(Register::into_u64)
+            .collect::<Vec<_>>();
+        let outputs = baseline::evaluate_function(self, func, &inputs)?;
+        let output_types = self
+            .module
+            .get_function(func)
+            .expect("encountered invalid function index")
+            .outputs();
+        self.scratch.clear();
+        self.scratch.extend(
+            outputs
+                .into_iter()
+                .zip(output_types)
+                .map(|(bits, ty)| Register::with_type(bits, Some(*ty))),
+        );
         Ok(())
     }
 
@@ -146,68 +997,205 @@ impl<'a> EvaluationContext<'a> {
     /// The function frame is expected to already be setup with the input parameters.
     /// The outputs are returned in order of their function definition appearance.
     ///
+    /// Evaluation stops once the function frame at `depth` returns, even if
+    /// there are other function frames below it. This allows to nest
+    /// evaluations, e.g. for calls from interpreter-only functions.
+    ///
     /// # Note
     ///
     /// This API is for use internally to the interpreter.
     fn evaluate_function_frame<O>(
         &mut self,
         mut function: Function<'a>,
+        depth: usize,
         mut outputs: O,
     ) -> Result<(), InterpretationError>
     where
         O: FnMut(u64),
     {
-        loop {
-            let Self {
-                module,
-                frames,
-                scratch,
-            } = self;
-            let (stack, frame) = match frames.last_frame_mut() {
-                Some(last) => last,
-                None => panic!("cannot execute without an activation frame"),
-            };
-            let act = ActivationFrame::new(module, stack, frame, scratch);
-            match function.body().interpret_instr(&[], act)? {
-                InterpretationFlow::Continue => continue,
-                InterpretationFlow::Return => {
-                    self.frames.pop_frame();
-                    if self.evaluate_return_flow(&mut function) {
-                        break
-                    }
+        while !self.evaluate_next_instr(&mut function, depth)? {}
+        for return_value in self.scratch.drain(..) {
+            outputs(return_value.into_u64())
+        }
+        Ok(())
+    }
+
+    /// Evaluates the next instruction of the innermost function frame.
+    ///
+    /// Updates the current `function` upon calls and returns.
+    ///
+    /// Returns `true` once the function frame at `depth` has returned.
+    /// The outputs of the returned function frame are then stored in the
+    /// scratch buffer.
+    ///
+    /// # Note
+    ///
+    /// This API is for use internally to the interpreter.
+    #[inline]
+    fn evaluate_next_instr(
+        &mut self,
+        function: &mut Function<'a>,
+        depth: usize,
+    ) -> Result<bool, InterpretationError> {
+        if self.fuel.is_some() {
+            let cost = self.next_instruction_cost(function);
+            if let Err(error) = self.consume_fuel(cost) {
+                self.fuel_checkpoint = true;
+                return Err(error)
+            }
+        }
+        let Self {
+            module,
+            frames,
+            scratch,
+            profile,
+            coverage,
+            config,
+            stats,
+            trace_hook,
+            globals,
+            memories,
+            tables,
+            ..
+        } = self;
+        let (stack, frame) = match frames.last_frame_mut() {
+            Some(last) => last,
+            None => panic!("cannot execute without an activation frame"),
+        };
+        if let Some(coverage) = coverage {
+            if frame.is_at_block_start() {
+                coverage.record_block(frame.func(), frame.current_block());
+            }
+        }
+        if let Some(stats) = stats {
+            let (_, instruction) = function
+                .body()
+                .instruction_and_value(
+                    frame.current_block(),
+                    frame.instruction_counter(),
+                )
+                .expect("missing instruction in function");
+            stats.record_instr(
+                frame.func(),
+                frame.current_block(),
+                instruction.descriptor(),
+                frame.is_at_block_start(),
+            );
+        }
+        if let Some(trace_hook) = trace_hook {
+            let block = frame.current_block();
+            let ic = frame.instruction_counter();
+            let instr = function.body().block_instrs(block)[ic];
+            let (_, instruction) = function
+                .body()
+                .instruction_and_value(block, ic)
+                .expect("missing instruction in function");
+            let ptr = frame.stack_pointer();
+            trace_hook.trace(instr, instruction, |value| {
+                stack.read_register(ptr + value)
+            });
+        }
+        if config.check_register_types() {
+            check_register_types(*function, stack, frame)?;
+        }
+        let act = ActivationFrame::new(
+            module,
+            *function,
+            stack,
+            frame,
+            scratch,
+            profile.as_mut(),
+            config,
+            globals,
+            memories,
+            tables,
+        );
+        let flow = function
+            .body()
+            .interpret_instr(&[], act)
+            .map_err(|error| self.record_trap_location(*function, error))?;
+        let returned = match flow {
+            InterpretationFlow::Continue => false,
+            InterpretationFlow::Return => {
+                if self.capture_register_file && self.frames.len() == depth {
+                    self.register_file =
+                        Some(self.frames.last_register_file(*function));
                 }
-                InterpretationFlow::TailCall(func) => {
-                    self.frames.pop_frame();
-                    self.update_and_push_frame(func, &mut function);
+                self.frames.pop_frame();
+                self.frames.len() < depth || self.evaluate_return_flow(function)
+            }
+            InterpretationFlow::TailCall(func) => {
+                self.frames.pop_frame();
+                if !self.module.is_interpreter_only(func) {
+                    self.update_and_push_frame(func, function)?;
+                    return Ok(false)
                 }
-                InterpretationFlow::Call(func) => {
-                    self.update_and_push_frame(func, &mut function);
+                self.evaluate_baseline_from_scratch(func)?;
+                self.frames.len() < depth || self.evaluate_return_flow(function)
+            }
+            InterpretationFlow::Call(func) => {
+                if !self.module.is_interpreter_only(func) {
+                    self.update_and_push_frame(func, function).map_err(
+                        |error| self.record_trap_location(*function, error),
+                    )?;
+                    return Ok(false)
                 }
+                self.evaluate_baseline_from_scratch(func)?;
+                self.evaluate_return_flow(function);
+                false
             }
+        };
+        Ok(returned)
+    }
+
+    /// Records the location of the last evaluated instruction of the
+    /// innermost function frame if the error is caused by a trap.
+    ///
+    /// Returns the error for convenience.
+    fn record_trap_location(
+        &mut self,
+        function: Function,
+        error: InterpretationError,
+    ) -> InterpretationError {
+        if error.trap_code().is_none() {
+            return error
         }
-        for return_value in self.scratch.drain(..) {
-            outputs(return_value.into_u64())
+        if let Some((_, frame)) = self.frames.last_frame_mut() {
+            let block = frame.current_block();
+            let ic = frame.last_instruction_counter();
+            let instr = function.body().block_instrs(block)[ic];
+            self.trap_location = Some(TrapLocation::new(
+                frame.func(),
+                instr,
+                function.body().source_span(instr),
+            ));
         }
-        Ok(())
+        error
     }
 
     /// Pushes another function frame onto the stack of frames.
     ///
     /// Initializes the new function frame with the values found in the scratch buffer.
     /// Updates the function pointer to point to the new function frame.
+    ///
+    /// # Errors
+    ///
+    /// If the new function frame exceeds the maximum call depth.
     fn update_and_push_frame(
         &mut self,
         func: Func,
         function: &mut Function<'a>,
-    ) {
+    ) -> Result<(), InterpretationError> {
+        self.check_call_depth()?;
         let called_function = self
             .module
             .get_function(func)
             .expect("encountered invalid function index");
         *function = called_function;
         self.frames
-            .push_frame(func, self.scratch.drain(..).map(Register::into_u64))
+            .push_frame_registers(func, self.scratch.drain(..))
             .expect("encountered invalid function for call");
+        Ok(())
     }
 
     /// Evaluates the control flow when an interpreted function returns to its caller.
@@ -236,7 +1224,7 @@ impl<'a> EvaluationContext<'a> {
                 for (output_value, output_result) in output_values
                     .iter()
                     .copied()
-                    .zip(self.scratch.iter().copied().map(Register::into_u64))
+                    .zip(self.scratch.iter().copied())
                 {
                     if let Some(output_value) = output_value {
                         stack.write_register(ptr + output_value, output_result);
@@ -249,3 +1237,85 @@ impl<'a> EvaluationContext<'a> {
         }
     }
 }
+
+/// Returns the function at the index of the table for an indirect call.
+///
+/// Shared by the Runwell IR interpreter and the baseline tier so that both
+/// resolve indirect calls with the same semantics.
+///
+/// # Errors
+///
+/// - If the table has not been created or the index is out of bounds.
+/// - If the table element at the index is uninitialized.
+/// - If the type of the function does not match the expected function type.
+fn indirect_callee(
+    module: &Module,
+    tables: &ComponentMap<Table, Vec<Option<Func>>>,
+    table: Table,
+    index: u32,
+    func_type: FuncType,
+) -> Result<Func, InterpretationError> {
+    let elements = tables
+        .get(table)
+        .ok_or(InterpretationError::MissingTable { table })?;
+    let func = elements
+        .get(index as usize)
+        .ok_or(InterpretationError::TableOutOfBounds { table, index })?
+        .ok_or(InterpretationError::UninitializedTableElement {
+            table,
+            index,
+        })?;
+    let matches = module
+        .get_raw_func_type(func)
+        .map(|actual| module.func_types_match(actual, func_type))
+        .unwrap_or(false);
+    if !matches {
+        return Err(InterpretationError::UnmatchingIndirectCallType {
+            func,
+            func_type,
+        })
+    }
+    Ok(func)
+}
+
+/// Checks the registers read by the next instruction of the function frame.
+///
+/// # Errors
+///
+/// If a register read by the instruction is uninitialized or holds a value
+/// of another type than the value that is read.
+fn check_register_types(
+    function: Function,
+    stack: &Stack,
+    frame: &Frame,
+) -> Result<(), InterpretationError> {
+    let body = function.body();
+    let (_, instruction) = body
+        .instruction_and_value(
+            frame.current_block(),
+            frame.instruction_counter(),
+        )
+        .expect("missing instruction in function");
+    let mut result = Ok(());
+    let check = |value: Value| {
+        let expected = body
+            .value_type(value)
+            .expect("encountered value without type");
+        let func = frame.func();
+        result = match stack.register(frame.stack_pointer() + value).ty() {
+            Some(found) if found == expected => Ok(()),
+            Some(found) => Err(InterpretationError::UnmatchingRegisterType {
+                func,
+                value,
+                expected,
+                found,
+            }),
+            None => {
+                Err(InterpretationError::UninitializedRegister { func, value })
+            }
+        };
+        result.is_ok()
+    };
+    instruction.visit_values(check);
+    result
+}
@@ -0,0 +1,350 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Suspension and resumption of evaluations that run out of fuel.
+
+use super::{frame::Frame, EvaluationContext, Frames, Register, UnwindGuard};
+use crate::InterpretationError;
+use entity::RawIdx;
+use ir::primitive::{Block, FloatType, Func, IntType, Type, Value};
+use module::Function;
+
+/// The result of a resumable evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Evaluation {
+    /// The evaluation has finished with the given outputs.
+    Finished(Vec<u64>),
+    /// The evaluation ran out of fuel and has been suspended.
+    Suspended(Snapshot),
+}
+
+/// The execution state of a suspended evaluation.
+///
+/// Holds the function frames of the evaluation including their current
+/// basic block, instruction counter and registers. The linear memories,
+/// tables and global variables remain in the evaluation context. Therefore
+/// a snapshot must be resumed by the evaluation context that created it or
+/// one that is in the same state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The suspended function frames from the outermost to the innermost.
+    frames: Vec<FrameSnapshot>,
+}
+
+/// The execution state of a single suspended function frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FrameSnapshot {
+    func: Func,
+    current_block: Block,
+    instruction_counter: usize,
+    /// The bits of the registers and their types if register types are
+    /// checked.
+    registers: Vec<(u64, Option<Type>)>,
+}
+
+impl Snapshot {
+    /// Returns the number of suspended function frames.
+    pub fn call_depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Serializes the snapshot into bytes.
+    ///
+    /// All integers are encoded in little endian. The type of each register
+    /// is encoded in a single byte following its bits.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        push_u32(&mut bytes, self.frames.len() as u32);
+        for frame in &self.frames {
+            push_u32(&mut bytes, frame.func.into_raw().into_u32());
+            push_u32(&mut bytes, frame.current_block.into_raw().into_u32());
+            push_u32(&mut bytes, frame.instruction_counter as u32);
+            push_u32(&mut bytes, frame.registers.len() as u32);
+            for (bits, ty) in &frame.registers {
+                bytes.extend_from_slice(&bits.to_le_bytes());
+                bytes.push(encode_type(*ty));
+            }
+        }
+        bytes
+    }
+
+    /// Deserializes a snapshot from the bytes created by [`Snapshot::to_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// If the bytes do not encode a snapshot.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InterpretationError> {
+        let mut reader = Reader { bytes };
+        let len_frames = reader.read_u32()?;
+        let mut frames = Vec::new();
+        for _ in 0..len_frames {
+            let func = Func::from_raw(RawIdx::from_u32(reader.read_u32()?));
+            let current_block =
+                Block::from_raw(RawIdx::from_u32(reader.read_u32()?));
+            let instruction_counter = reader.read_u32()? as usize;
+            let len_registers = reader.read_u32()?;
+            let registers = (0..len_registers)
+                .map(|_| {
+                    let bits = reader.read_u64()?;
+                    let ty = decode_type(reader.read_u8()?)?;
+                    Ok((bits, ty))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            frames.push(FrameSnapshot {
+                func,
+                current_block,
+                instruction_counter,
+                registers,
+            });
+        }
+        if !reader.bytes.is_empty() {
+            return Err(InterpretationError::InvalidSnapshot)
+        }
+        Ok(Self { frames })
+    }
+}
+
+/// Appends the little endian encoding of `value` to the bytes.
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes())
+}
+
+/// Returns the byte encoding of the optional register type.
+fn encode_type(ty: Option<Type>) -> u8 {
+    match ty {
+        None => 0x00,
+        Some(Type::Bool) => 0x01,
+        Some(Type::Ptr) => 0x02,
+        Some(Type::Int(IntType::I8)) => 0x03,
+        Some(Type::Int(IntType::I16)) => 0x04,
+        Some(Type::Int(IntType::I32)) => 0x05,
+        Some(Type::Int(IntType::I64)) => 0x06,
+        Some(Type::Float(FloatType::F32)) => 0x07,
+        Some(Type::Float(FloatType::F64)) => 0x08,
+    }
+}
+
+/// Decodes the optional register type encoded by [`encode_type`].
+fn decode_type(byte: u8) -> Result<Option<Type>, InterpretationError> {
+    let ty = match byte {
+        0x00 => None,
+        0x01 => Some(Type::Bool),
+        0x02 => Some(Type::Ptr),
+        0x03 => Some(Type::Int(IntType::I8)),
+        0x04 => Some(Type::Int(IntType::I16)),
+        0x05 => Some(Type::Int(IntType::I32)),
+        0x06 => Some(Type::Int(IntType::I64)),
+        0x07 => Some(Type::Float(FloatType::F32)),
+        0x08 => Some(Type::Float(FloatType::F64)),
+        _ => return Err(InterpretationError::InvalidSnapshot),
+    };
+    Ok(ty)
+}
+
+/// Reads the primitives of a serialized snapshot.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Reads the next `len` bytes.
+    fn read_bytes(
+        &mut self,
+        len: usize,
+    ) -> Result<&'a [u8], InterpretationError> {
+        if self.bytes.len() < len {
+            return Err(InterpretationError::InvalidSnapshot)
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    /// Reads the next byte.
+    fn read_u8(&mut self) -> Result<u8, InterpretationError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    /// Reads the next little endian encoded `u32`.
+    fn read_u32(&mut self) -> Result<u32, InterpretationError> {
+        let mut buffer = [0x00; 4];
+        buffer.copy_from_slice(self.read_bytes(4)?);
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    /// Reads the next little endian encoded `u64`.
+    fn read_u64(&mut self) -> Result<u64, InterpretationError> {
+        let mut buffer = [0x00; 8];
+        buffer.copy_from_slice(self.read_bytes(8)?);
+        Ok(u64::from_le_bytes(buffer))
+    }
+}
+
+impl<'a> EvaluationContext<'a> {
+    /// Evaluates the given function and suspends it if it runs out of fuel.
+    ///
+    /// Returns [`Evaluation::Suspended`] with a snapshot of the evaluation if
+    /// the fuel runs out before an instruction of the Runwell IR interpreter.
+    /// The snapshot can be continued via [`EvaluationContext::resume`] after
+    /// refueling the evaluation context.
+    ///
+    /// # Errors
+    ///
+    /// - If the evaluation traps.
+    /// - If the fuel runs out while evaluating an interpreter-only function.
+    ///   Those evaluations cannot be suspended.
+    pub fn evaluate_resumable<I>(
+        &mut self,
+        func: Func,
+        inputs: I,
+    ) -> Result<Evaluation, InterpretationError>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let mut outputs = Vec::new();
+        if self.module.is_interpreter_only(func) {
+            self.evaluate_function(func, inputs, |output| {
+                outputs.push(output)
+            })?;
+            return Ok(Evaluation::Finished(outputs))
+        }
+        self.check_call_depth()?;
+        let guard = UnwindGuard::new(self);
+        let ctx = &mut *guard.ctx;
+        let depth = ctx.frames.len();
+        ctx.frames.push_frame(func, inputs)?;
+        ctx.evaluate_resumable_frames(depth)
+    }
+
+    /// Resumes the suspended evaluation of the snapshot.
+    ///
+    /// Behaves the same as [`EvaluationContext::evaluate_resumable`] for the
+    /// continued evaluation.
+    ///
+    /// # Errors
+    ///
+    /// - If the snapshot does not match the functions of the module.
+    /// - If the continued evaluation fails.
+    pub fn resume(
+        &mut self,
+        snapshot: Snapshot,
+    ) -> Result<Evaluation, InterpretationError> {
+        let module = self.module;
+        let is_valid = snapshot.frames.iter().all(|frame| {
+            !module.is_interpreter_only(frame.func)
+                && module
+                    .internal_functions()
+                    .find(|function| function.idx() == frame.func)
+                    .is_some_and(|function| is_valid_frame(&function, frame))
+        });
+        if !is_valid || snapshot.frames.is_empty() {
+            return Err(InterpretationError::InvalidSnapshot)
+        }
+        let guard = UnwindGuard::new(self);
+        let ctx = &mut *guard.ctx;
+        let depth = ctx.frames.len();
+        for frame in snapshot.frames {
+            let sp = ctx.frames.stack.push(frame.registers.len() as u32);
+            let registers = frame
+                .registers
+                .into_iter()
+                .map(|(bits, ty)| Register::with_type(bits, ty));
+            ctx.frames.stack.initialize(sp, registers);
+            ctx.frames.frames.push(Frame::restore(
+                frame.func,
+                sp,
+                frame.current_block,
+                frame.instruction_counter,
+            ));
+        }
+        ctx.evaluate_resumable_frames(depth)
+    }
+
+    /// Evaluates the function frames above `depth` until they return or
+    /// run out of fuel.
+    fn evaluate_resumable_frames(
+        &mut self,
+        depth: usize,
+    ) -> Result<Evaluation, InterpretationError> {
+        let func = self
+            .frames
+            .frames
+            .last()
+            .map(Frame::func)
+            .expect("cannot execute without an activation frame");
+        let function = self
+            .module
+            .get_function(func)
+            .expect("encountered invalid function index");
+        self.fuel_checkpoint = false;
+        let mut outputs = Vec::new();
+        match self.evaluate_function_frame(function, depth + 1, |output| {
+            outputs.push(output)
+        }) {
+            Ok(()) => Ok(Evaluation::Finished(outputs)),
+            Err(InterpretationError::OutOfFuel) if self.fuel_checkpoint => {
+                self.fuel_checkpoint = false;
+                Ok(Evaluation::Suspended(self.take_snapshot(depth)))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Removes the function frames above `depth` and returns their snapshot.
+    fn take_snapshot(&mut self, depth: usize) -> Snapshot {
+        let module = self.module;
+        let Frames { stack, frames, .. } = &mut self.frames;
+        let stack_pointer = frames.get(depth).map(Frame::stack_pointer);
+        let frames = frames
+            .drain(depth..)
+            .map(|frame| {
+                let function = module
+                    .get_function(frame.func())
+                    .expect("encountered invalid function index");
+                let ptr = frame.stack_pointer();
+                let registers = (0..frame_size(&function))
+                    .map(|n| {
+                        let value = Value::from_raw(RawIdx::from_u32(n));
+                        let register = stack.register(ptr + value);
+                        (register.into_u64(), register.ty())
+                    })
+                    .collect();
+                FrameSnapshot {
+                    func: frame.func(),
+                    current_block: frame.current_block(),
+                    instruction_counter: frame.instruction_counter(),
+                    registers,
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some(stack_pointer) = stack_pointer {
+            stack.pop(stack_pointer);
+        }
+        Snapshot { frames }
+    }
+}
+
+/// Returns the number of registers of a function frame of the function.
+fn frame_size(function: &Function) -> u32 {
+    function.body().max_value().into_raw().into_u32() + 1
+}
+
+/// Returns `true` if the frame snapshot is a valid state of the function.
+fn is_valid_frame(function: &Function, frame: &FrameSnapshot) -> bool {
+    let body = function.body();
+    frame.registers.len() == frame_size(function) as usize
+        && body.contains_block(frame.current_block)
+        && frame.instruction_counter
+            < body.block_instrs(frame.current_block).len()
+}
@@ -14,7 +14,7 @@
 
 use derive_more::From;
 use entity::RawIdx;
-use ir::primitive::Value;
+use ir::primitive::{Type, Value};
 
 /// The stack.
 #[derive(Debug, Default)]
@@ -42,7 +42,7 @@ impl Stack {
     /// Returns the number of inputs initialized through this procedure.
     pub fn initialize<I>(&mut self, ptr: Ptr, inputs: I) -> usize
     where
-        I: IntoIterator<Item = u64>,
+        I: IntoIterator<Item = Register>,
     {
         let mut len_inputs = 0;
         for input in inputs.into_iter() {
@@ -56,14 +56,19 @@ impl Stack {
     }
 
     /// Writes `new_value` to the register at the given `ptr`.
-    pub fn write_register(&mut self, ptr: Ptr, new_value: u64) {
-        self.registers[ptr.into_usize()].bits = new_value;
+    pub fn write_register(&mut self, ptr: Ptr, new_value: Register) {
+        self.registers[ptr.into_usize()] = new_value;
     }
 
     /// Returns the bits from the register at the given `ptr`.
     pub fn read_register(&self, ptr: Ptr) -> u64 {
         self.registers[ptr.into_usize()].bits
     }
+
+    /// Returns the register at the given `ptr`.
+    pub fn register(&self, ptr: Ptr) -> Register {
+        self.registers[ptr.into_usize()]
+    }
 }
 
 use derive_more::Display;
@@ -94,11 +99,21 @@ impl Ptr {
 #[derive(Debug, Default, Copy, Clone)]
 pub struct Register {
     bits: u64,
+    /// The type of the value held by the register if it is known.
+    ///
+    /// This is only tracked if register types are checked.
+    ty: Option<Type>,
 }
 
 impl Register {
-    pub fn from_u64(bits: u64) -> Self {
-        Self { bits }
+    /// Creates a register holding the bits of a value of the given type.
+    pub fn with_type(bits: u64, ty: Option<Type>) -> Self {
+        Self { bits, ty }
+    }
+
+    /// Returns the type of the value held by the register if it is known.
+    pub fn ty(self) -> Option<Type> {
+        self.ty
     }
 
     /// Returns the `u64` representation of the register.
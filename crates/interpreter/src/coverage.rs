@@ -0,0 +1,260 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Coverage of the basic blocks executed during interpretation.
+//!
+//! Coverage is recorded per evaluation context and can be merged across
+//! contexts, e.g. to measure the coverage of an entire test suite. Reports
+//! list the covered and uncovered basic blocks of every internal function
+//! and can be exported in the LCOV tracefile format.
+
+use core::fmt::{self, Write as _};
+use ir::primitive::{Block, Func};
+use module::Module;
+use std::collections::BTreeMap;
+
+/// How often the basic blocks have been executed by an evaluation context.
+///
+/// Enable coverage via [`EvaluationContext::enable_coverage`].
+///
+/// [`EvaluationContext::enable_coverage`]:
+/// crate::EvaluationContext::enable_coverage
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    /// How often the basic blocks of the functions have been entered.
+    blocks: BTreeMap<(Func, Block), u64>,
+}
+
+impl Coverage {
+    /// Returns `true` if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns how often the basic block of the function has been entered.
+    pub fn block_count(&self, func: Func, block: Block) -> u64 {
+        self.blocks.get(&(func, block)).copied().unwrap_or_default()
+    }
+
+    /// Merges the counts of the other coverage into this one.
+    ///
+    /// Use this to combine the coverage of multiple evaluation contexts.
+    pub fn merge(&mut self, other: &Self) {
+        for (key, count) in &other.blocks {
+            *self.blocks.entry(*key).or_default() += count;
+        }
+    }
+
+    /// Creates a coverage report for all internal functions of the module.
+    ///
+    /// Interpreter-only functions have a single placeholder basic block
+    /// that is covered once the function has been executed.
+    pub fn report(&self, module: &Module) -> CoverageReport {
+        let funcs = module
+            .internal_functions()
+            .map(|function| {
+                let func = function.idx();
+                let blocks = function
+                    .body()
+                    .blocks()
+                    .map(|block| (block, self.block_count(func, block)))
+                    .collect();
+                FunctionCoverage {
+                    func,
+                    entry_block: function.body().entry_block(),
+                    blocks,
+                }
+            })
+            .collect();
+        CoverageReport { funcs }
+    }
+
+    /// Records that the basic block of the function has been entered.
+    pub(crate) fn record_block(&mut self, func: Func, block: Block) {
+        *self.blocks.entry((func, block)).or_default() += 1;
+    }
+}
+
+/// The coverage of the basic blocks of a single function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCoverage {
+    /// The covered function.
+    func: Func,
+    /// The entry block of the function.
+    entry_block: Block,
+    /// The basic blocks of the function and how often they have been entered.
+    blocks: Vec<(Block, u64)>,
+}
+
+impl FunctionCoverage {
+    /// Returns the covered function.
+    pub fn func(&self) -> Func {
+        self.func
+    }
+
+    /// Returns how often the function has been executed.
+    pub fn calls(&self) -> u64 {
+        self.blocks()
+            .find(|(block, _)| *block == self.entry_block)
+            .map_or(0, |(_, count)| count)
+    }
+
+    /// Returns an iterator over all basic blocks and how often they have been entered.
+    pub fn blocks(&self) -> impl Iterator<Item = (Block, u64)> + '_ {
+        self.blocks.iter().copied()
+    }
+
+    /// Returns an iterator over all basic blocks that have never been entered.
+    pub fn uncovered_blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        self.blocks()
+            .filter(|(_, count)| *count == 0)
+            .map(|(block, _)| block)
+    }
+
+    /// Returns the number of basic blocks of the function.
+    pub fn total_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns the number of basic blocks that have been entered.
+    pub fn covered_blocks(&self) -> usize {
+        self.total_blocks() - self.uncovered_blocks().count()
+    }
+
+    /// Returns the percentage of covered basic blocks.
+    pub fn percentage(&self) -> f64 {
+        percentage(self.covered_blocks(), self.total_blocks())
+    }
+}
+
+/// Returns the percentage of `covered` in `total`.
+///
+/// Returns 100% if `total` is zero since there is nothing to cover.
+fn percentage(covered: usize, total: usize) -> f64 {
+    match total {
+        0 => 100.0,
+        total => covered as f64 * 100.0 / total as f64,
+    }
+}
+
+/// The coverage of all internal functions of a module.
+///
+/// The [`Display`][fmt::Display] implementation prints a human readable
+/// summary with the per function percentages and uncovered basic blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// The coverage of the internal functions in ascending order.
+    funcs: Vec<FunctionCoverage>,
+}
+
+impl CoverageReport {
+    /// Returns the coverage of the function if it is an internal function.
+    pub fn get(&self, func: Func) -> Option<&FunctionCoverage> {
+        self.funcs.iter().find(|coverage| coverage.func == func)
+    }
+
+    /// Returns an iterator over the coverage of all internal functions.
+    pub fn funcs(&self) -> impl Iterator<Item = &FunctionCoverage> + '_ {
+        self.funcs.iter()
+    }
+
+    /// Returns the number of basic blocks of all internal functions.
+    pub fn total_blocks(&self) -> usize {
+        self.funcs.iter().map(FunctionCoverage::total_blocks).sum()
+    }
+
+    /// Returns the number of basic blocks of all internal functions that have been entered.
+    pub fn covered_blocks(&self) -> usize {
+        self.funcs
+            .iter()
+            .map(FunctionCoverage::covered_blocks)
+            .sum()
+    }
+
+    /// Returns the percentage of covered basic blocks of all internal functions.
+    pub fn percentage(&self) -> f64 {
+        percentage(self.covered_blocks(), self.total_blocks())
+    }
+
+    /// Exports the report in the LCOV tracefile format.
+    ///
+    /// The Runwell IR has no source lines. Therefore all basic blocks are
+    /// mapped to consecutive line numbers of the `source` file in the order
+    /// of the report and every function starts at the line of its first
+    /// basic block.
+    pub fn to_lcov(&self, source: &str) -> String {
+        let mut lcov = String::new();
+        self.write_lcov(&mut lcov, source)
+            .expect("writing to a string cannot fail");
+        lcov
+    }
+
+    /// Writes the report in the LCOV tracefile format.
+    fn write_lcov(&self, out: &mut String, source: &str) -> fmt::Result {
+        writeln!(out, "TN:")?;
+        writeln!(out, "SF:{}", source)?;
+        let mut line = 1;
+        for coverage in &self.funcs {
+            writeln!(out, "FN:{},{}", line, coverage.func)?;
+            line += coverage.total_blocks();
+        }
+        for coverage in &self.funcs {
+            writeln!(out, "FNDA:{},{}", coverage.calls(), coverage.func)?;
+        }
+        let called = self
+            .funcs
+            .iter()
+            .filter(|coverage| coverage.calls() > 0)
+            .count();
+        writeln!(out, "FNF:{}", self.funcs.len())?;
+        writeln!(out, "FNH:{}", called)?;
+        let blocks = self.funcs.iter().flat_map(FunctionCoverage::blocks);
+        for (line, (_, count)) in (1..).zip(blocks) {
+            writeln!(out, "DA:{},{}", line, count)?;
+        }
+        writeln!(out, "LF:{}", self.total_blocks())?;
+        writeln!(out, "LH:{}", self.covered_blocks())?;
+        writeln!(out, "end_of_record")
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for coverage in &self.funcs {
+            write!(
+                f,
+                "{}: {}/{} blocks ({:.2}%)",
+                coverage.func,
+                coverage.covered_blocks(),
+                coverage.total_blocks(),
+                coverage.percentage(),
+            )?;
+            let mut uncovered = coverage.uncovered_blocks().peekable();
+            if uncovered.peek().is_some() {
+                write!(f, ", uncovered:")?;
+                for block in uncovered {
+                    write!(f, " {}", block)?;
+                }
+            }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "total: {}/{} blocks ({:.2}%)",
+            self.covered_blocks(),
+            self.total_blocks(),
+            self.percentage(),
+        )
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Differential testing of module transformations.
+//!
+//! Transformations must not change the observable behavior of the exported
+//! functions of a module. The equivalence checker evaluates an exported
+//! function of the original and the transformed module with the same
+//! arguments and reports the first arguments for which the results, traps
+//! or fuel exhaustion of both evaluations differ.
+
+use crate::{EvaluationContext, InterpretationError};
+use core::fmt;
+use ir::{
+    instr::TrapCode,
+    primitive::{Const, Type},
+};
+use module::Module;
+
+/// The observable outcome of invoking an exported function.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The function returned the results.
+    Returned(Vec<Const>),
+    /// The evaluation trapped with the trap code.
+    Trapped(TrapCode),
+    /// The evaluation ran out of fuel.
+    OutOfFuel,
+    /// The evaluation failed without trapping.
+    ///
+    /// Boxed since interpretation errors are large compared to the other
+    /// outcomes.
+    Failed(Box<InterpretationError>),
+}
+
+impl Outcome {
+    /// Classifies the result of an invocation.
+    fn new(result: Result<Vec<Const>, InterpretationError>) -> Self {
+        match result {
+            Ok(results) => Self::Returned(results),
+            Err(InterpretationError::OutOfFuel) => Self::OutOfFuel,
+            Err(error) => match error.trap_code() {
+                Some(code) => Self::Trapped(code),
+                None => Self::Failed(Box::new(error)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Returned(results) => write!(f, "returned {:?}", results),
+            Self::Trapped(code) => write!(f, "trapped: {}", code),
+            Self::OutOfFuel => write!(f, "ran out of fuel"),
+            Self::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+/// Arguments for which the original and the transformed function differ.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The name of the exported function.
+    name: String,
+    /// The arguments of the invocations.
+    args: Vec<Const>,
+    /// The outcome of the original function.
+    original: Outcome,
+    /// The outcome of the transformed function.
+    transformed: Outcome,
+}
+
+impl Mismatch {
+    /// Returns the name of the exported function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the arguments for which the functions differ.
+    pub fn args(&self) -> &[Const] {
+        &self.args
+    }
+
+    /// Returns the outcome of the original function.
+    pub fn original(&self) -> &Outcome {
+        &self.original
+    }
+
+    /// Returns the outcome of the transformed function.
+    pub fn transformed(&self) -> &Outcome {
+        &self.transformed
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}({:?}) {} originally but {} after the transformation",
+            self.name, self.args, self.original, self.transformed
+        )
+    }
+}
+
+/// Checks that a transformation preserves the behavior of exported functions.
+///
+/// Every invocation is evaluated in a fresh evaluation context so that
+/// invocations cannot influence each other through global variables or
+/// linear memories.
+#[derive(Debug)]
+pub struct EquivalenceChecker<'a> {
+    /// The module before the transformation.
+    original: &'a Module,
+    /// The module after the transformation.
+    transformed: &'a Module,
+    /// The fuel of every evaluation if any.
+    fuel: Option<u64>,
+}
+
+impl<'a> EquivalenceChecker<'a> {
+    /// Creates an equivalence checker for the original and transformed module.
+    ///
+    /// By default evaluations have unlimited fuel.
+    pub fn new(original: &'a Module, transformed: &'a Module) -> Self {
+        Self {
+            original,
+            transformed,
+            fuel: None,
+        }
+    }
+
+    /// Sets the fuel of every evaluation.
+    ///
+    /// Both modules are given the same fuel so that transformations that
+    /// make a function run out of fuel are reported.
+    pub fn set_fuel(&mut self, fuel: u64) {
+        self.fuel = Some(fuel);
+    }
+
+    /// Checks the exported function with the given arguments.
+    ///
+    /// # Errors
+    ///
+    /// If the outcomes of the original and the transformed function differ.
+    pub fn check(&self, name: &str, args: &[Const]) -> Result<(), Mismatch> {
+        let original = self.evaluate(self.original, name, args);
+        let transformed = self.evaluate(self.transformed, name, args);
+        if original != transformed {
+            return Err(Mismatch {
+                name: name.to_string(),
+                args: args.to_vec(),
+                original,
+                transformed,
+            })
+        }
+        Ok(())
+    }
+
+    /// Checks the exported function with each of the given arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first arguments for which the outcomes differ.
+    pub fn check_all<'b, I>(
+        &self,
+        name: &str,
+        inputs: I,
+    ) -> Result<(), Mismatch>
+    where
+        I: IntoIterator<Item = &'b [Const]>,
+    {
+        for args in inputs {
+            self.check(name, args)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the exported function with generated arguments.
+    ///
+    /// The arguments are generated from boundary values of the input types
+    /// of the original function, e.g. zero, all bits set or only the sign
+    /// bit set. For floating point types these include negative zero,
+    /// subnormal numbers and NaNs.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first arguments for which the outcomes differ.
+    pub fn check_generated(&self, name: &str) -> Result<(), Mismatch> {
+        let inputs = self
+            .original
+            .get_exported_function(name)
+            .and_then(|func| self.original.get_function(func))
+            .map(|function| function.inputs().to_vec())
+            .unwrap_or_default();
+        let rounds = if inputs.is_empty() {
+            1
+        } else {
+            BOUNDARY_VALUES
+        };
+        for round in 0..rounds {
+            let args = inputs
+                .iter()
+                .enumerate()
+                .map(|(n, &ty)| boundary_value(ty, round + n))
+                .collect::<Vec<_>>();
+            self.check(name, &args)?;
+        }
+        Ok(())
+    }
+
+    /// Invokes the exported function of the module in a fresh context.
+    fn evaluate(&self, module: &Module, name: &str, args: &[Const]) -> Outcome {
+        let mut ctx = EvaluationContext::new(module);
+        if let Some(fuel) = self.fuel {
+            ctx.set_fuel(fuel);
+        }
+        Outcome::new(ctx.invoke(name, args))
+    }
+}
+
+/// The number of boundary values of every type.
+const BOUNDARY_VALUES: usize = 6;
+
+/// Returns the `n`-th boundary value of the type modulo their number.
+///
+/// These are zero, one, two, all bits set, only the sign bit set and all
+/// bits but the sign bit set.
+fn boundary_value(ty: Type, n: usize) -> Const {
+    let sign_bit = 1_u64 << (ty.bit_width() - 1);
+    let bits = [0, 1, 2, u64::MAX, sign_bit, sign_bit - 1];
+    Const::from_bits64(ty, bits[n % BOUNDARY_VALUES])
+}
+
+/// Checks the exported function of the original and the transformed module
+/// with each of the given arguments.
+///
+/// # Errors
+///
+/// Returns the first arguments for which the outcomes differ.
+pub fn check_equivalence<'b, I>(
+    original: &Module,
+    transformed: &Module,
+    name: &str,
+    inputs: I,
+) -> Result<(), Mismatch>
+where
+    I: IntoIterator<Item = &'b [Const]>,
+{
+    EquivalenceChecker::new(original, transformed).check_all(name, inputs)
+}
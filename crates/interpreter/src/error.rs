@@ -12,14 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use core::fmt;
 use derive_more::{Display, Error};
-use ir::primitive::{Const, Type, Value};
+use ir::{
+    instr::TrapCode,
+    primitive::{Const, Data, Func, FuncType, Mem, Table, Type, Value},
+    Diagnostic,
+    ToDiagnostic,
+};
+use module::primitive::{Global, ImportName, Instr, SourceSpan};
 
 /// An error that may occur while evaluating a function.
 #[derive(Debug, Display, Error, PartialEq, Eq)]
 pub enum InterpretationError {
-    #[display(fmt = "the function evaluation has trapped")]
-    EvaluationHasTrapped,
+    #[display(fmt = "the function evaluation has trapped: {}", code)]
+    EvaluationHasTrapped { code: TrapCode },
     #[display(
         fmt = "tried to initialize the non-input {} to {}",
         non_input,
@@ -57,6 +64,396 @@ pub enum InterpretationError {
         given_inputs: usize,
         required_inputs: usize,
     },
-    #[display(fmt = "encountered division by zero")]
-    DivisionByZero,
+    #[display(fmt = "ran out of fuel")]
+    OutOfFuel,
+    #[display(fmt = "encountered invalid evaluation snapshot")]
+    InvalidSnapshot,
+    #[display(fmt = "cannot debug interpreter-only function {}", func)]
+    InterpreterOnlyFunction { func: Func },
+    #[display(fmt = "the module has no exported function named {:?}", name)]
+    UnknownExportedFunction { name: String },
+    #[display(fmt = "{} is not a pure function", func)]
+    ImpureFunction { func: Func },
+    #[display(
+        fmt = "provided arguments of types {:?} but require {:?}",
+        given_types,
+        expected_types
+    )]
+    UnmatchingArgumentTypes {
+        given_types: Vec<Type>,
+        expected_types: Vec<Type>,
+    },
+    #[display(fmt = "tried to write to an immutable global variable")]
+    ImmutableGlobalWrite,
+    #[display(
+        fmt = "tried to write a value of type {} to a global variable of type {}",
+        given_type,
+        expected_type
+    )]
+    UnmatchingGlobalType {
+        given_type: Type,
+        expected_type: Type,
+    },
+    #[display(fmt = "failed to allocate linear memory of {} pages", pages)]
+    MemoryAllocationFailed { pages: u32 },
+    #[display(
+        fmt = "{} of {} initial pages exceeds the limit of {} pages",
+        memory,
+        pages,
+        limit
+    )]
+    MemoryLimitExceeded { memory: Mem, pages: u32, limit: u32 },
+    #[display(
+        fmt = "{} of {} initial elements exceeds the limit of {} elements",
+        table,
+        size,
+        limit
+    )]
+    TableLimitExceeded { table: Table, size: u32, limit: u32 },
+    #[display(
+        fmt = "out of bounds access of {} bytes at address {} of {}",
+        len,
+        address,
+        memory
+    )]
+    MemoryOutOfBounds { memory: Mem, address: u64, len: u64 },
+    #[display(fmt = "tried to access {} that has not been created", memory)]
+    MissingMemory { memory: Mem },
+    #[display(
+        fmt = "out of bounds access of {} bytes at address {} of {}",
+        len,
+        address,
+        data
+    )]
+    DataOutOfBounds { data: Data, address: u64, len: u64 },
+    #[display(fmt = "tried to store to read-only {}", data)]
+    StoreToReadOnlyData { data: Data },
+    #[display(fmt = "out of bounds access at index {} of {}", index, table)]
+    TableOutOfBounds { table: Table, index: u32 },
+    #[display(
+        fmt = "tried to call uninitialized element at index {} of {}",
+        index,
+        table
+    )]
+    UninitializedTableElement { table: Table, index: u32 },
+    #[display(
+        fmt = "indirectly called {} does not match expected {}",
+        func,
+        func_type
+    )]
+    UnmatchingIndirectCallType { func: Func, func_type: FuncType },
+    #[display(fmt = "tried to access {} that has not been created", table)]
+    MissingTable { table: Table },
+    #[display(
+        fmt = "element segment of {} elements at offset {} does not fit into {} of size {}",
+        len,
+        offset,
+        table,
+        size
+    )]
+    TableElementsOutOfBounds {
+        table: Table,
+        offset: u32,
+        len: usize,
+        size: u32,
+    },
+    #[display(
+        fmt = "tried to access uninitialized global variable {}",
+        global
+    )]
+    UninitializedGlobal { global: Global },
+    #[display(
+        fmt = "tried to read uninitialized register of {} in {}",
+        value,
+        func
+    )]
+    UninitializedRegister { func: Func, value: Value },
+    #[display(
+        fmt = "tried to read register of {} in {} holding a {} instead of a {}",
+        value,
+        func,
+        found,
+        expected
+    )]
+    UnmatchingRegisterType {
+        func: Func,
+        value: Value,
+        expected: Type,
+        found: Type,
+    },
+    #[display(fmt = "encountered unsupported operator {}", op)]
+    UnsupportedOperator { op: String },
+    #[display(fmt = "the module has no imported global variable {:?}", name)]
+    UnknownImportedGlobal { name: ImportName },
+    #[display(
+        fmt = "tried to link global {:?} of type {} (mutable: {}) to import of type {} (mutable: {})",
+        name,
+        given_type,
+        given_mutability,
+        expected_type,
+        expected_mutability
+    )]
+    UnmatchingGlobalImport {
+        name: ImportName,
+        given_type: Type,
+        given_mutability: bool,
+        expected_type: Type,
+        expected_mutability: bool,
+    },
+}
+
+impl InterpretationError {
+    /// Returns the reason of the trap if the error has been caused by a trap.
+    pub fn trap_code(&self) -> Option<TrapCode> {
+        match self {
+            Self::EvaluationHasTrapped { code } => Some(*code),
+            Self::MemoryOutOfBounds { .. } | Self::DataOutOfBounds { .. } => {
+                Some(TrapCode::MemoryOutOfBounds)
+            }
+            Self::TableOutOfBounds { .. }
+            | Self::UninitializedTableElement { .. } => {
+                Some(TrapCode::TableOutOfBounds)
+            }
+            Self::UnmatchingIndirectCallType { .. } => {
+                Some(TrapCode::IndirectCallTypeMismatch)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ToDiagnostic for InterpretationError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = |code| Diagnostic::new(code, self);
+        match self {
+            Self::EvaluationHasTrapped { code } => {
+                diagnostic("interpreter.trap").with_parameter("code", code)
+            }
+            Self::TriedToInitializeNonInput { non_input, init } => {
+                diagnostic("interpreter.initialize_non_input")
+                    .with_parameter("non_input", non_input)
+                    .with_parameter("init", init)
+            }
+            Self::UnmatchingInputTypes {
+                value,
+                given_type,
+                expected_type,
+            } => diagnostic("interpreter.unmatching_input_types")
+                .with_parameter("value", value)
+                .with_parameter("given_type", given_type)
+                .with_parameter("expected_type", expected_type),
+            Self::UninitializedInput { input } => {
+                diagnostic("interpreter.uninitialized_input")
+                    .with_parameter("input", input)
+            }
+            Self::AlreadySetReturnValue {
+                return_value,
+                prev_return_value,
+            } => diagnostic("interpreter.already_set_return_value")
+                .with_parameter("return_value", format!("{:?}", return_value))
+                .with_parameter(
+                    "prev_return_value",
+                    format!("{:?}", prev_return_value),
+                ),
+            Self::UnmatchingInputValues {
+                given_inputs,
+                required_inputs,
+            } => diagnostic("interpreter.unmatching_input_values")
+                .with_parameter("given_inputs", given_inputs)
+                .with_parameter("required_inputs", required_inputs),
+            Self::OutOfFuel => diagnostic("interpreter.out_of_fuel"),
+            Self::InvalidSnapshot => diagnostic("interpreter.invalid_snapshot"),
+            Self::InterpreterOnlyFunction { func } => {
+                diagnostic("interpreter.interpreter_only_function")
+                    .with_parameter("func", func)
+            }
+            Self::UnknownExportedFunction { name } => {
+                diagnostic("interpreter.unknown_exported_function")
+                    .with_parameter("name", name)
+            }
+            Self::ImpureFunction { func } => {
+                diagnostic("interpreter.impure_function")
+                    .with_parameter("func", func)
+            }
+            Self::UnmatchingArgumentTypes {
+                given_types,
+                expected_types,
+            } => diagnostic("interpreter.unmatching_argument_types")
+                .with_parameter("given_types", format!("{:?}", given_types))
+                .with_parameter(
+                    "expected_types",
+                    format!("{:?}", expected_types),
+                ),
+            Self::ImmutableGlobalWrite => {
+                diagnostic("interpreter.immutable_global_write")
+            }
+            Self::UnmatchingGlobalType {
+                given_type,
+                expected_type,
+            } => diagnostic("interpreter.unmatching_global_type")
+                .with_parameter("given_type", given_type)
+                .with_parameter("expected_type", expected_type),
+            Self::MemoryAllocationFailed { pages } => {
+                diagnostic("interpreter.memory_allocation_failed")
+                    .with_parameter("pages", pages)
+            }
+            Self::MemoryLimitExceeded {
+                memory,
+                pages,
+                limit,
+            } => {
+                diagnostic("interpreter.memory_limit_exceeded")
+                    .with_parameter("memory", memory)
+                    .with_parameter("pages", pages)
+                    .with_parameter("limit", limit)
+            }
+            Self::TableLimitExceeded { table, size, limit } => {
+                diagnostic("interpreter.table_limit_exceeded")
+                    .with_parameter("table", table)
+                    .with_parameter("size", size)
+                    .with_parameter("limit", limit)
+            }
+            Self::MemoryOutOfBounds {
+                memory,
+                address,
+                len,
+            } => diagnostic("interpreter.memory_out_of_bounds")
+                .with_parameter("memory", memory)
+                .with_parameter("address", address)
+                .with_parameter("len", len),
+            Self::MissingMemory { memory } => {
+                diagnostic("interpreter.missing_memory")
+                    .with_parameter("memory", memory)
+            }
+            Self::DataOutOfBounds { data, address, len } => {
+                diagnostic("interpreter.data_out_of_bounds")
+                    .with_parameter("data", data)
+                    .with_parameter("address", address)
+                    .with_parameter("len", len)
+            }
+            Self::StoreToReadOnlyData { data } => {
+                diagnostic("interpreter.store_to_read_only_data")
+                    .with_parameter("data", data)
+            }
+            Self::TableOutOfBounds { table, index } => {
+                diagnostic("interpreter.table_out_of_bounds")
+                    .with_parameter("table", table)
+                    .with_parameter("index", index)
+            }
+            Self::UninitializedTableElement { table, index } => {
+                diagnostic("interpreter.uninitialized_table_element")
+                    .with_parameter("table", table)
+                    .with_parameter("index", index)
+            }
+            Self::UnmatchingIndirectCallType { func, func_type } => {
+                diagnostic("interpreter.unmatching_indirect_call_type")
+                    .with_parameter("func", func)
+                    .with_parameter("func_type", func_type)
+            }
+            Self::MissingTable { table } => {
+                diagnostic("interpreter.missing_table")
+                    .with_parameter("table", table)
+            }
+            Self::TableElementsOutOfBounds {
+                table,
+                offset,
+                len,
+                size,
+            } => {
+                diagnostic("interpreter.table_elements_out_of_bounds")
+                    .with_parameter("table", table)
+                    .with_parameter("offset", offset)
+                    .with_parameter("len", len)
+                    .with_parameter("size", size)
+            }
+            Self::UninitializedGlobal { global } => {
+                diagnostic("interpreter.uninitialized_global")
+                    .with_parameter("global", global)
+            }
+            Self::UninitializedRegister { func, value } => {
+                diagnostic("interpreter.uninitialized_register")
+                    .with_parameter("func", func)
+                    .with_parameter("value", value)
+            }
+            Self::UnmatchingRegisterType {
+                func,
+                value,
+                expected,
+                found,
+            } => diagnostic("interpreter.unmatching_register_type")
+                .with_parameter("func", func)
+                .with_parameter("value", value)
+                .with_parameter("expected", expected)
+                .with_parameter("found", found),
+            Self::UnsupportedOperator { op } => {
+                diagnostic("interpreter.unsupported_operator")
+                    .with_parameter("op", op)
+            }
+            Self::UnknownImportedGlobal { name } => {
+                diagnostic("interpreter.unknown_imported_global")
+                    .with_parameter("module_name", name.module_name())
+                    .with_parameter("field_name", name.field_name())
+            }
+            Self::UnmatchingGlobalImport {
+                name,
+                given_type,
+                given_mutability,
+                expected_type,
+                expected_mutability,
+            } => diagnostic("interpreter.unmatching_global_import")
+                .with_parameter("module_name", name.module_name())
+                .with_parameter("field_name", name.field_name())
+                .with_parameter("given_type", given_type)
+                .with_parameter("given_mutability", given_mutability)
+                .with_parameter("expected_type", expected_type)
+                .with_parameter("expected_mutability", expected_mutability),
+        }
+    }
+}
+
+/// The location of the instruction that caused a trap.
+///
+/// Instructions translated from a source language such as Wasm also
+/// provide the source span they originate from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TrapLocation {
+    func: Func,
+    instr: Instr,
+    span: Option<SourceSpan>,
+}
+
+impl TrapLocation {
+    /// Creates a new trap location.
+    pub(crate) fn new(
+        func: Func,
+        instr: Instr,
+        span: Option<SourceSpan>,
+    ) -> Self {
+        Self { func, instr, span }
+    }
+
+    /// Returns the function containing the trapping instruction.
+    pub fn func(&self) -> Func {
+        self.func
+    }
+
+    /// Returns the trapping instruction.
+    pub fn instr(&self) -> Instr {
+        self.instr
+    }
+
+    /// Returns the source span of the trapping instruction if any.
+    pub fn source_span(&self) -> Option<SourceSpan> {
+        self.span
+    }
+}
+
+impl fmt::Display for TrapLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {}", self.instr, self.func)?;
+        if let Some(span) = self.span {
+            write!(f, " at {}", span)?;
+        }
+        Ok(())
+    }
 }
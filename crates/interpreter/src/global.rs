@@ -0,0 +1,113 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Global variables shared between evaluation contexts.
+//!
+//! Following the Wasm mutable-globals proposal, mutable global variables
+//! may be exported and imported. Writes to such a global variable through
+//! one evaluation context must be observed by all other evaluation contexts
+//! importing or exporting it. Therefore global variables are represented by
+//! shared handles to their value.
+
+use crate::InterpretationError;
+use core::sync::atomic::{AtomicU64, Ordering};
+use ir::primitive::{Const, Type};
+use std::sync::Arc;
+
+/// A shared handle to the value of a global variable.
+///
+/// Cloning a handle yields another handle to the same global variable.
+#[derive(Debug, Clone)]
+pub struct GlobalHandle {
+    inner: Arc<GlobalCell>,
+}
+
+/// The shared state of a global variable.
+#[derive(Debug)]
+struct GlobalCell {
+    /// The type of the global variable.
+    ty: Type,
+    /// `true` if the global variable is mutable.
+    is_mutable: bool,
+    /// The bits of the current value of the global variable.
+    bits: AtomicU64,
+}
+
+impl GlobalHandle {
+    /// Creates a new global variable initialized to the given value.
+    pub fn new(init: Const, is_mutable: bool) -> Self {
+        Self {
+            inner: Arc::new(GlobalCell {
+                ty: init.ty(),
+                is_mutable,
+                bits: AtomicU64::new(init.into_bits64()),
+            }),
+        }
+    }
+
+    /// Returns the type of the global variable.
+    pub fn ty(&self) -> Type {
+        self.inner.ty
+    }
+
+    /// Returns `true` if the global variable is mutable.
+    pub fn is_mutable(&self) -> bool {
+        self.inner.is_mutable
+    }
+
+    /// Returns the current value of the global variable.
+    pub fn get(&self) -> Const {
+        Const::from_bits64(self.ty(), self.get_bits())
+    }
+
+    /// Sets the value of the global variable.
+    ///
+    /// # Errors
+    ///
+    /// - If the global variable is immutable.
+    /// - If the type of the new value does not match the global variable.
+    pub fn set(&self, new_value: Const) -> Result<(), InterpretationError> {
+        if !self.is_mutable() {
+            return Err(InterpretationError::ImmutableGlobalWrite)
+        }
+        if new_value.ty() != self.ty() {
+            return Err(InterpretationError::UnmatchingGlobalType {
+                given_type: new_value.ty(),
+                expected_type: self.ty(),
+            })
+        }
+        self.set_bits(new_value.into_bits64());
+        Ok(())
+    }
+
+    /// Returns `true` if both handles refer to the same global variable.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Returns the bits of the current value of the global variable.
+    pub(crate) fn get_bits(&self) -> u64 {
+        self.inner.bits.load(Ordering::SeqCst)
+    }
+
+    /// Sets the bits of the value of the global variable.
+    ///
+    /// # Note
+    ///
+    /// Neither mutability nor type are checked since validated code is
+    /// expected to only write well typed values to mutable globals.
+    pub(crate) fn set_bits(&self, bits: u64) {
+        self.inner.bits.store(bits, Ordering::SeqCst)
+    }
+}
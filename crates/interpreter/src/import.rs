@@ -0,0 +1,71 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Renaming of import names before imports are resolved.
+//!
+//! Modules built against slightly different host conventions may import
+//! the same host entities under different names. Renames translate the
+//! import names of a module into the names provided by the host, e.g. to
+//! map `env.abort` to a host stub or to version-prefix import namespaces.
+
+use module::primitive::ImportName;
+use std::collections::HashMap;
+
+/// Rewrites the import names of a module before they are resolved.
+///
+/// Renames of individual imports take precedence over renames of the
+/// module names of imports.
+#[derive(Debug, Default, Clone)]
+pub struct ImportRenames {
+    /// The new names of individual imports by their original names.
+    imports: HashMap<ImportName, ImportName>,
+    /// The new module names of imports by their original module names.
+    modules: HashMap<String, String>,
+}
+
+impl ImportRenames {
+    /// Creates new import renames that leave all import names untouched.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renames the import with the given name.
+    pub fn rename_import(
+        &mut self,
+        from: ImportName,
+        to: ImportName,
+    ) -> &mut Self {
+        self.imports.insert(from, to);
+        self
+    }
+
+    /// Renames the module name of all imports from the given module.
+    pub fn rename_module(&mut self, from: &str, to: &str) -> &mut Self {
+        self.modules.insert(from.to_string(), to.to_string());
+        self
+    }
+
+    /// Returns the name under which the host provides the import.
+    pub fn resolve(&self, name: &ImportName) -> ImportName {
+        if let Some(renamed) = self.imports.get(name) {
+            return renamed.clone()
+        }
+        match self.modules.get(name.module_name()) {
+            Some(module_name) => {
+                ImportName::new(module_name, name.field_name())
+            }
+            None => name.clone(),
+        }
+    }
+}
@@ -29,6 +29,7 @@ use ir::{
         FloatInstr,
         FloatToIntInstr,
         PromoteFloatInstr,
+        TrapCode,
         UnaryFloatInstr,
     },
     primitive::{FloatType, IntType, Value},
@@ -73,6 +74,155 @@ fn f64_reg(float: f64) -> u64 {
     float.to_bits()
 }
 
+/// The bits of the canonical `f32` NaN.
+const CANONICAL_NAN_F32: u64 = 0x7FC0_0000;
+
+/// The bits of the canonical `f64` NaN.
+const CANONICAL_NAN_F64: u64 = 0x7FF8_0000_0000_0000;
+
+/// Returns the canonical NaN of the float type if the bits represent a NaN.
+///
+/// Otherwise returns the bits unchanged.
+pub(crate) fn canonicalize_nan(ty: FloatType, bits: u64) -> u64 {
+    match ty {
+        FloatType::F32 if reg_f32(bits).is_nan() => CANONICAL_NAN_F32,
+        FloatType::F64 if reg_f64(bits).is_nan() => CANONICAL_NAN_F64,
+        _ => bits,
+    }
+}
+
+/// Truncates the float value towards zero if it fits into `[min, max)`.
+///
+/// # Errors
+///
+/// Traps if the value is NaN or if its truncation is out of bounds.
+pub(crate) fn checked_trunc(
+    value: f64,
+    min: f64,
+    max: f64,
+) -> Result<f64, InterpretationError> {
+    let truncated = value.trunc();
+    if truncated.is_nan() {
+        return Err(InterpretationError::EvaluationHasTrapped {
+            code: TrapCode::BadConversionToInteger,
+        })
+    }
+    if truncated < min || truncated >= max {
+        return Err(InterpretationError::EvaluationHasTrapped {
+            code: TrapCode::IntegerOverflow,
+        })
+    }
+    Ok(truncated)
+}
+
+/// Rounds to the nearest integer with ties to even as required by Wasm.
+pub(crate) fn nearest_f32(value: f32) -> f32 {
+    if (value - value.trunc()).abs() == 0.5 {
+        return 2.0 * (value / 2.0).round()
+    }
+    value.round()
+}
+
+/// Rounds to the nearest integer with ties to even as required by Wasm.
+pub(crate) fn nearest_f64(value: f64) -> f64 {
+    if (value - value.trunc()).abs() == 0.5 {
+        return 2.0 * (value / 2.0).round()
+    }
+    value.round()
+}
+
+/// Returns the minimum with NaN propagation and `-0.0 < +0.0` as required by Wasm.
+pub(crate) fn min_f32(lhs: f32, rhs: f32) -> f32 {
+    if lhs.is_nan() || rhs.is_nan() {
+        return f32::NAN
+    }
+    if lhs == rhs {
+        // Only differs for zeros of different signs.
+        return f32::from_bits(lhs.to_bits() | rhs.to_bits())
+    }
+    lhs.min(rhs)
+}
+
+/// Returns the maximum with NaN propagation and `-0.0 < +0.0` as required by Wasm.
+pub(crate) fn max_f32(lhs: f32, rhs: f32) -> f32 {
+    if lhs.is_nan() || rhs.is_nan() {
+        return f32::NAN
+    }
+    if lhs == rhs {
+        // Only differs for zeros of different signs.
+        return f32::from_bits(lhs.to_bits() & rhs.to_bits())
+    }
+    lhs.max(rhs)
+}
+
+/// Returns the minimum with NaN propagation and `-0.0 < +0.0` as required by Wasm.
+pub(crate) fn min_f64(lhs: f64, rhs: f64) -> f64 {
+    if lhs.is_nan() || rhs.is_nan() {
+        return f64::NAN
+    }
+    if lhs == rhs {
+        // Only differs for zeros of different signs.
+        return f64::from_bits(lhs.to_bits() | rhs.to_bits())
+    }
+    lhs.min(rhs)
+}
+
+/// Returns the maximum with NaN propagation and `-0.0 < +0.0` as required by Wasm.
+pub(crate) fn max_f64(lhs: f64, rhs: f64) -> f64 {
+    if lhs.is_nan() || rhs.is_nan() {
+        return f64::NAN
+    }
+    if lhs == rhs {
+        // Only differs for zeros of different signs.
+        return f64::from_bits(lhs.to_bits() & rhs.to_bits())
+    }
+    lhs.max(rhs)
+}
+
+/// Returns `lhs` if `condition` holds and `rhs` otherwise without branching.
+pub(crate) fn select_bits(condition: bool, lhs: u64, rhs: u64) -> u64 {
+    let mask = 0_u64.wrapping_sub(u64::from(condition));
+    rhs ^ ((lhs ^ rhs) & mask)
+}
+
+/// Like [`min_f32`] but without branching on the operand values.
+pub(crate) fn ct_min_f32(lhs: f32, rhs: f32) -> f32 {
+    let lhs_bits = u64::from(lhs.to_bits());
+    let rhs_bits = u64::from(rhs.to_bits());
+    let min = select_bits(lhs < rhs, lhs_bits, rhs_bits);
+    let min = select_bits(lhs == rhs, lhs_bits | rhs_bits, min);
+    let nan = u64::from(f32::NAN.to_bits());
+    f32::from_bits(select_bits(lhs.is_nan() | rhs.is_nan(), nan, min) as u32)
+}
+
+/// Like [`max_f32`] but without branching on the operand values.
+pub(crate) fn ct_max_f32(lhs: f32, rhs: f32) -> f32 {
+    let lhs_bits = u64::from(lhs.to_bits());
+    let rhs_bits = u64::from(rhs.to_bits());
+    let max = select_bits(lhs > rhs, lhs_bits, rhs_bits);
+    let max = select_bits(lhs == rhs, lhs_bits & rhs_bits, max);
+    let nan = u64::from(f32::NAN.to_bits());
+    f32::from_bits(select_bits(lhs.is_nan() | rhs.is_nan(), nan, max) as u32)
+}
+
+/// Like [`min_f64`] but without branching on the operand values.
+pub(crate) fn ct_min_f64(lhs: f64, rhs: f64) -> f64 {
+    let (lhs_bits, rhs_bits) = (lhs.to_bits(), rhs.to_bits());
+    let min = select_bits(lhs < rhs, lhs_bits, rhs_bits);
+    let min = select_bits(lhs == rhs, lhs_bits | rhs_bits, min);
+    let nan = f64::NAN.to_bits();
+    f64::from_bits(select_bits(lhs.is_nan() | rhs.is_nan(), nan, min))
+}
+
+/// Like [`max_f64`] but without branching on the operand values.
+pub(crate) fn ct_max_f64(lhs: f64, rhs: f64) -> f64 {
+    let (lhs_bits, rhs_bits) = (lhs.to_bits(), rhs.to_bits());
+    let max = select_bits(lhs > rhs, lhs_bits, rhs_bits);
+    let max = select_bits(lhs == rhs, lhs_bits & rhs_bits, max);
+    let nan = f64::NAN.to_bits();
+    f64::from_bits(select_bits(lhs.is_nan() | rhs.is_nan(), nan, max))
+}
+
 impl InterpretInstr for DemoteFloatInstr {
     fn interpret_instr(
         &self,
@@ -82,11 +232,14 @@ impl InterpretInstr for DemoteFloatInstr {
         let return_value = extract_single_output(outputs);
         let source = frame.read_register(self.src());
         assert!(self.dst_type().bit_width() <= self.src_type().bit_width());
-        let result = match (self.src_type(), self.dst_type()) {
+        let mut result = match (self.src_type(), self.dst_type()) {
             (FloatType::F64, FloatType::F32) => f32_reg(reg_f64(source) as f32),
             _ => source,
         };
-        frame.write_register(return_value, result);
+        if frame.config().canonicalize_nans() {
+            result = canonicalize_nan(self.dst_type(), result);
+        }
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -100,11 +253,14 @@ impl InterpretInstr for PromoteFloatInstr {
         let return_value = extract_single_output(outputs);
         let source = frame.read_register(self.src());
         assert!(self.src_type().bit_width() <= self.dst_type().bit_width());
-        let result = match (self.src_type(), self.dst_type()) {
+        let mut result = match (self.src_type(), self.dst_type()) {
             (FloatType::F32, FloatType::F64) => f64_reg(reg_f32(source) as f64),
             _ => source,
         };
-        frame.write_register(return_value, result);
+        if frame.config().canonicalize_nans() {
+            result = canonicalize_nan(self.dst_type(), result);
+        }
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -150,7 +306,7 @@ impl InterpretInstr for CompareFloatInstr {
             (F32, Op::Gt) => operate_f32(lhs, rhs, f32::gt),
             (F64, Op::Gt) => operate_f64(lhs, rhs, f64::gt),
         };
-        frame.write_register(return_value, result);
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -183,37 +339,41 @@ impl InterpretInstr for BinaryFloatInstr {
             let rhs = reg_f64(rhs);
             f64_reg(op(lhs, rhs))
         }
-        let result = match (self.ty(), self.op()) {
+        let constant_time = frame.config().constant_time();
+        let mut result = match (self.ty(), self.op()) {
             (F32, Op::Add) => operate_f32(lhs, rhs, f32::add),
             (F64, Op::Add) => operate_f64(lhs, rhs, f64::add),
             (F32, Op::Sub) => operate_f32(lhs, rhs, f32::sub),
             (F64, Op::Sub) => operate_f64(lhs, rhs, f64::sub),
             (F32, Op::Mul) => operate_f32(lhs, rhs, f32::mul),
             (F64, Op::Mul) => operate_f64(lhs, rhs, f64::mul),
-            (F32, Op::Div) => {
-                let lhs = reg_f32(lhs);
-                let rhs = reg_f32(rhs);
-                if rhs.abs() == 0.0 {
-                    return Err(InterpretationError::DivisionByZero)
-                }
-                f32_reg(f32::div(lhs, rhs))
+            // Division by zero yields infinity or NaN and does not trap.
+            (F32, Op::Div) => operate_f32(lhs, rhs, f32::div),
+            (F64, Op::Div) => operate_f64(lhs, rhs, f64::div),
+            (F32, Op::Min) if constant_time => {
+                operate_f32(lhs, rhs, ct_min_f32)
+            }
+            (F64, Op::Min) if constant_time => {
+                operate_f64(lhs, rhs, ct_min_f64)
+            }
+            (F32, Op::Max) if constant_time => {
+                operate_f32(lhs, rhs, ct_max_f32)
             }
-            (F64, Op::Div) => {
-                let lhs = reg_f64(lhs);
-                let rhs = reg_f64(rhs);
-                if rhs.abs() == 0.0 {
-                    return Err(InterpretationError::DivisionByZero)
-                }
-                f64_reg(f64::div(lhs, rhs))
+            (F64, Op::Max) if constant_time => {
+                operate_f64(lhs, rhs, ct_max_f64)
             }
-            (F32, Op::Min) => operate_f32(lhs, rhs, f32::min),
-            (F64, Op::Min) => operate_f64(lhs, rhs, f64::min),
-            (F32, Op::Max) => operate_f32(lhs, rhs, f32::max),
-            (F64, Op::Max) => operate_f64(lhs, rhs, f64::max),
+            (F32, Op::Min) => operate_f32(lhs, rhs, min_f32),
+            (F64, Op::Min) => operate_f64(lhs, rhs, min_f64),
+            (F32, Op::Max) => operate_f32(lhs, rhs, max_f32),
+            (F64, Op::Max) => operate_f64(lhs, rhs, max_f64),
             (F32, Op::CopySign) => operate_f32(lhs, rhs, f32::copysign),
             (F64, Op::CopySign) => operate_f64(lhs, rhs, f64::copysign),
         };
-        frame.write_register(return_value, result);
+        let is_sign_op = matches!(self.op(), Op::CopySign);
+        if frame.config().canonicalize_nans() && !is_sign_op {
+            result = canonicalize_nan(self.ty(), result);
+        }
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -241,7 +401,7 @@ impl InterpretInstr for UnaryFloatInstr {
             f64_reg(op(reg_f64(reg)))
         }
         use core::ops::Neg;
-        let result = match (self.ty(), self.op()) {
+        let mut result = match (self.ty(), self.op()) {
             (F32, Op::Abs) => operate_f32(source, f32::abs),
             (F64, Op::Abs) => operate_f64(source, f64::abs),
             (F32, Op::Neg) => operate_f32(source, f32::neg),
@@ -254,28 +414,50 @@ impl InterpretInstr for UnaryFloatInstr {
             (F64, Op::Floor) => operate_f64(source, f64::floor),
             (F32, Op::Truncate) => operate_f32(source, f32::trunc),
             (F64, Op::Truncate) => operate_f64(source, f64::trunc),
-            (F32, Op::Nearest) => operate_f32(source, f32::round),
-            (F64, Op::Nearest) => operate_f64(source, f64::round),
+            (F32, Op::Nearest) => operate_f32(source, nearest_f32),
+            (F64, Op::Nearest) => operate_f64(source, nearest_f64),
         };
-        frame.write_register(return_value, result);
+        let is_sign_op = matches!(self.op(), Op::Abs | Op::Neg);
+        if frame.config().canonicalize_nans() && !is_sign_op {
+            result = canonicalize_nan(self.ty(), result);
+        }
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
 
+/// Converts the truncated float value into the integer type with saturation.
+///
+/// NaN is converted to zero and out of bounds values saturate to the
+/// minimum or maximum value of the integer type.
+fn saturating_float_to_int(value: f64, signed: bool, dst_type: IntType) -> u64 {
+    use IntType::{I16, I32, I64, I8};
+    match (signed, dst_type) {
+        (false, I8) => value as u8 as u64,
+        (false, I16) => value as u16 as u64,
+        (false, I32) => value as u32 as u64,
+        (false, I64) => value as u64,
+        (true, I8) => value as i8 as u8 as u64,
+        (true, I16) => value as i16 as u16 as u64,
+        (true, I32) => value as i32 as u32 as u64,
+        (true, I64) => value as i64 as u64,
+    }
+}
+
 impl InterpretInstr for FloatToIntInstr {
-    /// WebAssembly instructions that map to `FloatTotIntInstr`:
+    /// WebAssembly instructions that map to `FloatToIntInstr`:
     ///
-    /// `f32` conversion to `i32` and `i64`:
-    ///  - `f32.convert_i32_s`
-    ///  - `f32.convert_i32_u`
-    ///  - `f32.convert_i64_s`
-    ///  - `f32.convert_i64_u`
+    /// Trapping conversions from `f32` and `f64`:
+    ///  - `i32.trunc_f32_s`, `i32.trunc_f32_u`
+    ///  - `i32.trunc_f64_s`, `i32.trunc_f64_u`
+    ///  - `i64.trunc_f32_s`, `i64.trunc_f32_u`
+    ///  - `i64.trunc_f64_s`, `i64.trunc_f64_u`
     ///
-    /// `f64` conversion to `i32` and `i64`:
-    ///  - `f64.convert_i32_s`
-    ///  - `f64.convert_i32_u`
-    ///  - `f64.convert_i64_s`
-    ///  - `f64.convert_i64_u`
+    /// Saturating conversions from `f32` and `f64`:
+    ///  - `i32.trunc_sat_f32_s`, `i32.trunc_sat_f32_u`
+    ///  - `i32.trunc_sat_f64_s`, `i32.trunc_sat_f64_u`
+    ///  - `i64.trunc_sat_f32_s`, `i64.trunc_sat_f32_u`
+    ///  - `i64.trunc_sat_f64_s`, `i64.trunc_sat_f64_u`
     fn interpret_instr(
         &self,
         outputs: &[Option<Value>],
@@ -283,32 +465,27 @@ impl InterpretInstr for FloatToIntInstr {
     ) -> Result<InterpretationFlow, InterpretationError> {
         let return_value = extract_single_output(outputs);
         let source = frame.read_register(self.src());
-        use FloatType::{F32, F64};
-        use IntType::{I16, I32, I64, I8};
-        let result = match (self.is_signed(), self.src_type(), self.dst_type())
-        {
-            // f32 -> uN
-            (false, F32, I8) => reg_f32(source) as u8 as u64,
-            (false, F32, I16) => reg_f32(source) as u16 as u64,
-            (false, F32, I32) => reg_f32(source) as u32 as u64,
-            (false, F32, I64) => reg_f32(source) as u64,
-            // f64 -> uN
-            (false, F64, I8) => reg_f64(source) as u8 as u64,
-            (false, F64, I16) => reg_f64(source) as u16 as u64,
-            (false, F64, I32) => reg_f64(source) as u32 as u64,
-            (false, F64, I64) => reg_f64(source) as u64,
-            // f32 -> iN
-            (true, F32, I8) => reg_f32(source) as i8 as u8 as u64,
-            (true, F32, I16) => reg_f32(source) as i16 as u16 as u64,
-            (true, F32, I32) => reg_f32(source) as i32 as u32 as u64,
-            (true, F32, I64) => reg_f32(source) as i64 as u64,
-            // f64 -> iN
-            (true, F64, I8) => reg_f64(source) as i8 as u8 as u64,
-            (true, F64, I16) => reg_f64(source) as i16 as u16 as u64,
-            (true, F64, I32) => reg_f64(source) as i32 as u32 as u64,
-            (true, F64, I64) => reg_f64(source) as i64 as u64,
+        // Every `f32` value is exactly representable as `f64`.
+        let value = match self.src_type() {
+            FloatType::F32 => f64::from(reg_f32(source)),
+            FloatType::F64 => reg_f64(source),
+        };
+        let signed = self.is_signed();
+        let dst_type = self.dst_type();
+        let value = if self.is_saturating() {
+            value
+        } else {
+            let bit_width = dst_type.bit_width() as i32;
+            let (min, max) = if signed {
+                let bound = 2.0_f64.powi(bit_width - 1);
+                (-bound, bound)
+            } else {
+                (0.0, 2.0_f64.powi(bit_width))
+            };
+            checked_trunc(value, min, max)?
         };
-        frame.write_register(return_value, result as u64);
+        let result = saturating_float_to_int(value, signed, dst_type);
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
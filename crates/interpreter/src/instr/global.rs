@@ -0,0 +1,45 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::{extract_single_output, InterpretInstr, InterpretationFlow};
+use crate::{core::ActivationFrame, InterpretationError};
+use ir::{
+    instr::{GlobalGetInstr, GlobalSetInstr},
+    primitive::Value,
+};
+
+impl InterpretInstr for GlobalGetInstr {
+    fn interpret_instr(
+        &self,
+        outputs: &[Option<Value>],
+        mut frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        let return_value = extract_single_output(outputs);
+        let bits = frame.global(self.global())?.get_bits();
+        frame.write_output(return_value, bits);
+        Ok(InterpretationFlow::Continue)
+    }
+}
+
+impl InterpretInstr for GlobalSetInstr {
+    fn interpret_instr(
+        &self,
+        outputs: &[Option<Value>],
+        frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        debug_assert!(outputs.is_empty());
+        let bits = frame.read_register(self.value());
+        frame.global(self.global())?.set_bits(bits);
+        Ok(InterpretationFlow::Continue)
+    }
+}
@@ -29,6 +29,7 @@ use ir::{
         IntInstr,
         IntToFloatInstr,
         ShiftIntInstr,
+        TrapCode,
         TruncateIntInstr,
         UnaryIntInstr,
     },
@@ -61,12 +62,20 @@ impl InterpretInstr for UnaryIntInstr {
     ) -> Result<InterpretationFlow, InterpretationError> {
         let return_value = extract_single_output(outputs);
         let source = frame.read_register(self.src());
+        // Only the bits of the declared integer type are counted.
+        let bit_width = self.ty().bit_width();
+        let source = match bit_width {
+            64 => source,
+            _ => source & ((1 << bit_width) - 1),
+        };
         let result = match self.op() {
-            UnaryIntOp::LeadingZeros => source.leading_zeros(),
-            UnaryIntOp::TrailingZeros => source.trailing_zeros(),
+            UnaryIntOp::LeadingZeros => {
+                source.leading_zeros() - (64 - bit_width)
+            }
+            UnaryIntOp::TrailingZeros => source.trailing_zeros().min(bit_width),
             UnaryIntOp::PopCount => source.count_ones(),
         };
-        frame.write_register(return_value, result as u64);
+        frame.write_output(return_value, result as u64);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -91,7 +100,7 @@ impl InterpretInstr for TruncateIntInstr {
             IntType::I32 => source & mask(32),
             IntType::I64 => source,
         };
-        frame.write_register(return_value, result as u64);
+        frame.write_output(return_value, result as u64);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -108,28 +117,19 @@ impl InterpretInstr for ExtendIntInstr {
             self.src_type().bit_width() <= self.dst_type().bit_width()
         );
         let result = if self.is_signed() {
-            match (self.src_type(), self.dst_type()) {
-                (IntType::I8, IntType::I16) => {
-                    source as u8 as i8 as i16 as u16 as u64
-                }
-                (IntType::I8, IntType::I32) => {
-                    source as u8 as i8 as i32 as u32 as u64
-                }
-                (IntType::I8, IntType::I64) => source as u8 as i8 as i64 as u64,
-                (IntType::I16, IntType::I32) => {
-                    source as u16 as i16 as i32 as u32 as u64
-                }
-                (IntType::I32, IntType::I64) => {
-                    source as u32 as i32 as i64 as u64
-                }
-                (x, y) if x == y => source,
-                _ => unreachable!(),
+            // Replicate the sign bit of the source into the upper bits and
+            // clear all bits beyond the width of the destination type.
+            let shift = 64 - self.src_type().bit_width();
+            let extended = ((source << shift) as i64 >> shift) as u64;
+            match self.dst_type().bit_width() {
+                64 => extended,
+                dst_width => extended & ((0x1 << dst_width) - 1),
             }
         } else {
             // Nothing to do since interpreter registers are `u64`.
             source
         };
-        frame.write_register(return_value, result as u64);
+        frame.write_output(return_value, result as u64);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -137,17 +137,19 @@ impl InterpretInstr for ExtendIntInstr {
 impl InterpretInstr for IntToFloatInstr {
     /// WebAssembly instructions that map to `IntToFloatInstr`:
     ///
-    /// `i32` conversion to `f32` and `f64`:
-    ///  - `i32.trunc_f32_s`
-    ///  - `i32.trunc_f32_u`
-    ///  - `i32.trunc_f64_s`
-    ///  - `i32.trunc_f64_u`
+    /// `i32` and `i64` conversion to `f32`:
+    ///  - `f32.convert_i32_s`
+    ///  - `f32.convert_i32_u`
+    ///  - `f32.convert_i64_s`
+    ///  - `f32.convert_i64_u`
     ///
-    /// `i64` conversion to `f32` and `f64`:
-    ///  - `i64.trunc_f32_s`
-    ///  - `i64.trunc_f32_u`
-    ///  - `i64.trunc_f64_s`
-    ///  - `i64.trunc_f64_u`
+    /// `i32` and `i64` conversion to `f64`:
+    ///  - `f64.convert_i32_s`
+    ///  - `f64.convert_i32_u`
+    ///  - `f64.convert_i64_s`
+    ///  - `f64.convert_i64_u`
+    ///
+    /// Results that are not exactly representable are rounded to nearest.
     fn interpret_instr(
         &self,
         outputs: &[Option<Value>],
@@ -180,7 +182,7 @@ impl InterpretInstr for IntToFloatInstr {
             (true, I32, F64) => (source as u32 as i32 as f64).to_bits(),
             (true, I64, F64) => (source as u64 as i64 as f64).to_bits(),
         };
-        frame.write_register(return_value, result as u64);
+        frame.write_output(return_value, result as u64);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -234,7 +236,7 @@ impl InterpretInstr for CompareIntInstr {
             }
             IntType::I64 => cmp(self.op(), lhs, rhs, |lhs| lhs as i64),
         };
-        frame.write_register(return_value, result);
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -264,7 +266,11 @@ pub trait PrimitiveInteger: Copy {
     fn from_reg(reg: u64) -> Self;
     fn into_reg(self) -> u64;
 
+    /// Divides by `rhs` and traps upon division by zero or overflow.
     fn checked_div(self, rhs: Self) -> Result<Self, InterpretationError>;
+    /// Returns the remainder of the division by `rhs`.
+    ///
+    /// Traps upon division by zero but never overflows.
     fn checked_rem(self, rhs: Self) -> Result<Self, InterpretationError>;
 }
 macro_rules! impl_primitive_integer_for {
@@ -275,10 +281,24 @@ macro_rules! impl_primitive_integer_for {
                 fn into_reg(self) -> u64 { conv::$val_to_reg(self) }
 
                 fn checked_div(self, rhs: Self) -> Result<Self, InterpretationError> {
-                    self.checked_div(rhs).ok_or(InterpretationError::DivisionByZero)
+                    if rhs == 0 {
+                        return Err(InterpretationError::EvaluationHasTrapped {
+                            code: TrapCode::IntegerDivisionByZero,
+                        })
+                    }
+                    // Only `MIN / -1` of signed integers overflows.
+                    self.checked_div(rhs).ok_or(InterpretationError::EvaluationHasTrapped {
+                        code: TrapCode::IntegerOverflow,
+                    })
                 }
                 fn checked_rem(self, rhs: Self) -> Result<Self, InterpretationError> {
-                    self.checked_rem(rhs).ok_or(InterpretationError::DivisionByZero)
+                    if rhs == 0 {
+                        return Err(InterpretationError::EvaluationHasTrapped {
+                            code: TrapCode::IntegerDivisionByZero,
+                        })
+                    }
+                    // Wasm defines the remainder of `MIN % -1` to be zero.
+                    Ok(self.wrapping_rem(rhs))
                 }
             }
         )*
@@ -335,7 +355,7 @@ impl InterpretInstr for ShiftIntInstr {
             (Rotr, I32) => eval_shift(src, shamt, u32::rotate_right),
             (Rotr, I64) => eval_shift(src, shamt, u64::rotate_right),
         };
-        frame.write_register(return_value, result);
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -413,7 +433,7 @@ impl InterpretInstr for BinaryIntInstr {
             (Xor, I32) => eval(lhs, rhs, u32::bitxor),
             (Xor, I64) => eval(lhs, rhs, u64::bitxor),
         };
-        frame.write_register(return_value, result);
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
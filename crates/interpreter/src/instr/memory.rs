@@ -0,0 +1,266 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{extract_single_output, InterpretInstr, InterpretationFlow};
+use crate::{
+    core::ActivationFrame,
+    InterpretationError,
+    LinearMemory,
+    MAX_PAGES,
+};
+use entity::RawIdx;
+use ir::{
+    instr::{
+        DataAddrInstr,
+        HeapAddrInstr,
+        LoadInstr,
+        MemoryGrowInstr,
+        MemorySizeInstr,
+        StoreInstr,
+    },
+    primitive::{Data, Mem, Type, Value},
+};
+use module::Module;
+
+/// Marks pointers to read-only data objects in the upper 32 bits of pointers.
+const DATA_PTR_TAG: u32 = 1 << 31;
+
+/// The object a pointer points into.
+#[derive(Debug, Copy, Clone)]
+enum Pointee {
+    /// A linear memory.
+    Memory(Mem),
+    /// A read-only data object of the module.
+    Data(Data),
+}
+
+/// Encodes the linear memory and the address within it into pointer bits.
+///
+/// The lower 32 bits hold the address within the linear memory and the
+/// upper 32 bits hold the index of the linear memory.
+fn encode_ptr(memory: Mem, address: u32) -> u64 {
+    u64::from(memory.into_raw().into_u32()) << 32 | u64::from(address)
+}
+
+/// Encodes a pointer to the start of the read-only data object.
+///
+/// The upper 32 bits hold the index of the read-only data object tagged
+/// with [`DATA_PTR_TAG`] to distinguish it from linear memory indices.
+fn encode_data_ptr(data: Data) -> u64 {
+    u64::from(data.into_raw().into_u32() | DATA_PTR_TAG) << 32
+}
+
+/// Decodes the pointer bits into the pointee and the address within it.
+fn decode_ptr(bits: u64) -> (Pointee, u32) {
+    let upper = (bits >> 32) as u32;
+    let pointee = if upper & DATA_PTR_TAG != 0 {
+        Pointee::Data(Data::from_raw(RawIdx::from_u32(upper & !DATA_PTR_TAG)))
+    } else {
+        Pointee::Memory(Mem::from_raw(RawIdx::from_u32(upper)))
+    };
+    (pointee, bits as u32)
+}
+
+/// Returns the number of bytes occupied by a value of the type in linear memory.
+///
+/// Values are stored in little-endian byte order and do not have to be aligned.
+fn byte_width(ty: Type) -> u64 {
+    match ty {
+        Type::Bool => 1,
+        ty => u64::from(ty.bit_width() / 8),
+    }
+}
+
+/// Returns the linear memory or an error if it has not been created.
+fn memory_mut<'a>(
+    frame: &'a mut ActivationFrame,
+    memory: Mem,
+) -> Result<&'a mut dyn LinearMemory, InterpretationError> {
+    frame
+        .memory_mut(memory)
+        .ok_or(InterpretationError::MissingMemory { memory })
+}
+
+/// Returns the bytes of the linear memory in the range if it is in bounds.
+pub(crate) fn memory_range(
+    data: &mut [u8],
+    memory: Mem,
+    address: u64,
+    len: u64,
+) -> Result<&mut [u8], InterpretationError> {
+    let out_of_bounds = InterpretationError::MemoryOutOfBounds {
+        memory,
+        address,
+        len,
+    };
+    let end = match address.checked_add(len) {
+        Some(end) if end <= data.len() as u64 => end,
+        _ => return Err(out_of_bounds),
+    };
+    Ok(&mut data[address as usize..end as usize])
+}
+
+/// Returns the bytes of the read-only data object in the range if in bounds.
+fn data_range(
+    module: &Module,
+    data: Data,
+    address: u64,
+    len: u64,
+) -> Result<&[u8], InterpretationError> {
+    let bytes = module
+        .get_read_only_data(data)
+        .expect("encountered invalid read-only data index");
+    let out_of_bounds = InterpretationError::DataOutOfBounds {
+        data,
+        address,
+        len,
+    };
+    let end = match address.checked_add(len) {
+        Some(end) if end <= bytes.len() as u64 => end,
+        _ => return Err(out_of_bounds),
+    };
+    Ok(&bytes[address as usize..end as usize])
+}
+
+/// Grows the linear memory by `new_pages` pages if it stays within the limit.
+///
+/// Returns the previous size of the linear memory in pages or `u32::MAX`
+/// which is -1 as Wasm `i32` if the linear memory cannot grow.
+pub(crate) fn grow_memory(
+    memory: &mut dyn LinearMemory,
+    new_pages: u32,
+    limit: u32,
+) -> u32 {
+    // Enforce the limits also for embedder provided linear memories.
+    let max_pages = memory
+        .maximum_pages()
+        .map_or(MAX_PAGES, |max_pages| max_pages.min(MAX_PAGES))
+        .min(limit);
+    let result = match memory.pages().checked_add(new_pages) {
+        Some(pages) if pages <= max_pages => memory.grow(new_pages),
+        _ => None,
+    };
+    result.unwrap_or(u32::MAX)
+}
+
+impl InterpretInstr for HeapAddrInstr {
+    fn interpret_instr(
+        &self,
+        outputs: &[Option<Value>],
+        mut frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        let return_value = extract_single_output(outputs);
+        let address = frame.read_register(self.ptr()) as u32;
+        let memory = self.heap();
+        let size = u64::from(self.size().into_u32());
+        let data = memory_mut(&mut frame, memory)?.data_mut();
+        memory_range(data, memory, u64::from(address), size)?;
+        frame.write_output(return_value, encode_ptr(memory, address));
+        Ok(InterpretationFlow::Continue)
+    }
+}
+
+impl InterpretInstr for DataAddrInstr {
+    fn interpret_instr(
+        &self,
+        outputs: &[Option<Value>],
+        mut frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        let return_value = extract_single_output(outputs);
+        frame.write_output(return_value, encode_data_ptr(self.data()));
+        Ok(InterpretationFlow::Continue)
+    }
+}
+
+impl InterpretInstr for LoadInstr {
+    fn interpret_instr(
+        &self,
+        outputs: &[Option<Value>],
+        mut frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        let return_value = extract_single_output(outputs);
+        let ptr = frame.read_register(self.address());
+        let (pointee, address) = decode_ptr(ptr);
+        let address = u64::from(address) + u64::from(self.offset().into_u32());
+        let len = byte_width(self.ty());
+        let bytes = match pointee {
+            Pointee::Memory(memory) => {
+                let data = memory_mut(&mut frame, memory)?.data_mut();
+                &*memory_range(data, memory, address, len)?
+            }
+            Pointee::Data(data) => {
+                data_range(frame.module, data, address, len)?
+            }
+        };
+        let mut buffer = [0x00; 8];
+        buffer[..bytes.len()].copy_from_slice(bytes);
+        let result = u64::from_le_bytes(buffer);
+        frame.write_output(return_value, result);
+        Ok(InterpretationFlow::Continue)
+    }
+}
+
+impl InterpretInstr for StoreInstr {
+    fn interpret_instr(
+        &self,
+        outputs: &[Option<Value>],
+        mut frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        debug_assert!(outputs.is_empty());
+        let ptr = frame.read_register(self.address());
+        let (pointee, address) = decode_ptr(ptr);
+        let memory = match pointee {
+            Pointee::Memory(memory) => memory,
+            Pointee::Data(data) => {
+                return Err(InterpretationError::StoreToReadOnlyData { data })
+            }
+        };
+        let address = u64::from(address) + u64::from(self.offset().into_u32());
+        let len = byte_width(self.ty());
+        let value = frame.read_register(self.value());
+        let data = memory_mut(&mut frame, memory)?.data_mut();
+        let bytes = memory_range(data, memory, address, len)?;
+        bytes.copy_from_slice(&value.to_le_bytes()[..bytes.len()]);
+        Ok(InterpretationFlow::Continue)
+    }
+}
+
+impl InterpretInstr for MemorySizeInstr {
+    fn interpret_instr(
+        &self,
+        outputs: &[Option<Value>],
+        mut frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        let return_value = extract_single_output(outputs);
+        let pages = memory_mut(&mut frame, self.memory())?.pages();
+        frame.write_output(return_value, u64::from(pages));
+        Ok(InterpretationFlow::Continue)
+    }
+}
+
+impl InterpretInstr for MemoryGrowInstr {
+    fn interpret_instr(
+        &self,
+        outputs: &[Option<Value>],
+        mut frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        let return_value = extract_single_output(outputs);
+        let new_pages = frame.read_register(self.new_pages()) as u32;
+        let limit = frame.config().resource_limits().max_memory_pages();
+        let memory = memory_mut(&mut frame, self.memory())?;
+        let result = grow_memory(memory, new_pages, limit);
+        frame.write_output(return_value, u64::from(result));
+        Ok(InterpretationFlow::Continue)
+    }
+}
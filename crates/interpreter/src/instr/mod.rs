@@ -13,17 +13,35 @@
 // limitations under the License.
 
 mod float;
+mod global;
 mod int;
+mod memory;
 mod terminal;
 
+pub(crate) use self::float::{
+    canonicalize_nan,
+    checked_trunc,
+    ct_max_f32,
+    ct_max_f64,
+    ct_min_f32,
+    ct_min_f64,
+    max_f32,
+    max_f64,
+    min_f32,
+    min_f64,
+    nearest_f32,
+    nearest_f64,
+    select_bits,
+};
+pub(crate) use self::memory::{grow_memory, memory_range};
 use super::InterpretationError;
 use crate::core::ActivationFrame;
 use ir::{
     instr::{
+        CallIndirectInstr,
         CallInstr,
         ConstInstr,
         Instruction,
-        PhiInstr,
         ReinterpretInstr,
         SelectInstr,
     },
@@ -61,9 +79,13 @@ pub enum InterpretationFlow {
     Call(Func),
 }
 
-fn extract_single_output(outputs: &[Option<Value>]) -> Value {
+/// Returns the single output SSA value if it is in use.
+///
+/// Returns `None` if the output of the instruction is never used in which
+/// case the instruction is still evaluated for its side effects.
+fn extract_single_output(outputs: &[Option<Value>]) -> Option<Value> {
     debug_assert_eq!(outputs.len(), 1);
-    outputs[0].expect("encountered missing single output SSA value")
+    outputs[0]
 }
 
 impl InterpretInstr for FunctionBody {
@@ -90,14 +112,16 @@ impl InterpretInstr for Instruction {
     ) -> Result<InterpretationFlow, InterpretationError> {
         match self {
             Self::Call(instr) => instr.interpret_instr(outputs, frame),
-            Self::CallIndirect(_instr) => unimplemented!(),
+            Self::CallIndirect(instr) => instr.interpret_instr(outputs, frame),
             Self::Const(instr) => instr.interpret_instr(outputs, frame),
-            Self::MemoryGrow(_instr) => unimplemented!(),
-            Self::MemorySize(_instr) => unimplemented!(),
-            Self::Phi(instr) => instr.interpret_instr(outputs, frame),
-            Self::HeapAddr(_instr) => unimplemented!(),
-            Self::Load(_instr) => unimplemented!(),
-            Self::Store(_instr) => unimplemented!(),
+            Self::MemoryGrow(instr) => instr.interpret_instr(outputs, frame),
+            Self::MemorySize(instr) => instr.interpret_instr(outputs, frame),
+            Self::HeapAddr(instr) => instr.interpret_instr(outputs, frame),
+            Self::DataAddr(instr) => instr.interpret_instr(outputs, frame),
+            Self::GlobalGet(instr) => instr.interpret_instr(outputs, frame),
+            Self::GlobalSet(instr) => instr.interpret_instr(outputs, frame),
+            Self::Load(instr) => instr.interpret_instr(outputs, frame),
+            Self::Store(instr) => instr.interpret_instr(outputs, frame),
             Self::Select(instr) => instr.interpret_instr(outputs, frame),
             Self::Reinterpret(instr) => instr.interpret_instr(outputs, frame),
             Self::Terminal(instr) => instr.interpret_instr(outputs, frame),
@@ -107,25 +131,6 @@ impl InterpretInstr for Instruction {
     }
 }
 
-impl InterpretInstr for PhiInstr {
-    fn interpret_instr(
-        &self,
-        outputs: &[Option<Value>],
-        mut frame: ActivationFrame,
-    ) -> Result<InterpretationFlow, InterpretationError> {
-        let return_value = extract_single_output(outputs);
-        let last_block = frame
-            .last_block()
-            .expect("phi instruction is missing predecessor");
-        let result = self
-            .operand_for(last_block)
-            .expect("phi instruction missing value for predecessor");
-        let result = frame.read_register(result);
-        frame.write_register(return_value, result);
-        Ok(InterpretationFlow::Continue)
-    }
-}
-
 impl InterpretInstr for ConstInstr {
     fn interpret_instr(
         &self,
@@ -133,7 +138,7 @@ impl InterpretInstr for ConstInstr {
         mut frame: ActivationFrame,
     ) -> Result<InterpretationFlow, InterpretationError> {
         let return_value = extract_single_output(outputs);
-        frame.write_register(return_value, self.const_value().into_bits64());
+        frame.write_output(return_value, self.const_value().into_bits64());
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -146,13 +151,19 @@ impl InterpretInstr for SelectInstr {
     ) -> Result<InterpretationFlow, InterpretationError> {
         let return_value = extract_single_output(outputs);
         let condition = frame.read_register(self.condition());
-        let result_value = if condition != 0 {
-            self.true_value()
+        let result = if frame.config().constant_time() {
+            let true_bits = frame.read_register(self.true_value());
+            let false_bits = frame.read_register(self.false_value());
+            select_bits(condition != 0, true_bits, false_bits)
         } else {
-            self.false_value()
+            let result_value = if condition != 0 {
+                self.true_value()
+            } else {
+                self.false_value()
+            };
+            frame.read_register(result_value)
         };
-        let result = frame.read_register(result_value);
-        frame.write_register(return_value, result);
+        frame.write_output(return_value, result);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -165,13 +176,29 @@ impl InterpretInstr for CallInstr {
     ) -> Result<InterpretationFlow, InterpretationError> {
         frame.clear_scratch();
         for param in self.params().iter().copied() {
-            let bits = frame.read_register(param);
-            frame.push_scratch(bits);
+            frame.push_scratch(param);
         }
         Ok(InterpretationFlow::Call(self.func()))
     }
 }
 
+impl InterpretInstr for CallIndirectInstr {
+    fn interpret_instr(
+        &self,
+        _outputs: &[Option<Value>],
+        mut frame: ActivationFrame,
+    ) -> Result<InterpretationFlow, InterpretationError> {
+        let index = frame.read_register(self.index()) as u32;
+        let func =
+            frame.indirect_callee(self.table(), index, self.func_type())?;
+        frame.clear_scratch();
+        for param in self.params().iter().copied() {
+            frame.push_scratch(param);
+        }
+        Ok(InterpretationFlow::Call(func))
+    }
+}
+
 impl InterpretInstr for ReinterpretInstr {
     fn interpret_instr(
         &self,
@@ -185,7 +212,7 @@ impl InterpretInstr for ReinterpretInstr {
             self.dst_type().bit_width()
         );
         // Reinterpretation just moves from one register to the other.
-        frame.write_register(return_value, source);
+        frame.write_output(return_value, source);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -35,7 +35,9 @@ impl InterpretInstr for TerminalInstr {
         frame: ActivationFrame,
     ) -> Result<InterpretationFlow, InterpretationError> {
         match self {
-            Self::Trap => Err(InterpretationError::EvaluationHasTrapped),
+            Self::Trap(code) => {
+                Err(InterpretationError::EvaluationHasTrapped { code: *code })
+            }
             Self::Return(instr) => instr.interpret_instr(outputs, frame),
             Self::Br(instr) => instr.interpret_instr(outputs, frame),
             Self::Ite(instr) => instr.interpret_instr(outputs, frame),
@@ -56,8 +58,7 @@ impl InterpretInstr for ReturnInstr {
     ) -> Result<InterpretationFlow, InterpretationError> {
         frame.clear_scratch();
         for param in self.return_values().iter().copied() {
-            let bits = frame.read_register(param);
-            frame.push_scratch(bits);
+            frame.push_scratch(param);
         }
         Ok(InterpretationFlow::Return)
     }
@@ -69,7 +70,7 @@ impl InterpretInstr for BranchInstr {
         _outputs: &[Option<Value>],
         mut frame: ActivationFrame,
     ) -> Result<InterpretationFlow, InterpretationError> {
-        frame.switch_to_block(self.target());
+        frame.branch_to(self.target());
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -80,13 +81,14 @@ impl InterpretInstr for IfThenElseInstr {
         _outputs: &[Option<Value>],
         mut frame: ActivationFrame,
     ) -> Result<InterpretationFlow, InterpretationError> {
-        let condition = frame.read_register(self.condition());
-        let target = if condition != 0 {
+        let condition = frame.read_register(self.condition()) != 0;
+        let target = if condition {
             self.true_target()
         } else {
             self.false_target()
         };
-        frame.switch_to_block(target);
+        frame.record_branch(condition);
+        frame.branch_to(target);
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -99,8 +101,7 @@ impl InterpretInstr for TailCallInstr {
     ) -> Result<InterpretationFlow, InterpretationError> {
         frame.clear_scratch();
         for param in self.params().iter().copied() {
-            let bits = frame.read_register(param);
-            frame.push_scratch(bits);
+            frame.push_scratch(param);
         }
         Ok(InterpretationFlow::TailCall(self.func()))
     }
@@ -110,9 +111,16 @@ impl InterpretInstr for TailCallIndirectInstr {
     fn interpret_instr(
         &self,
         _outputs: &[Option<Value>],
-        _frame: ActivationFrame,
+        mut frame: ActivationFrame,
     ) -> Result<InterpretationFlow, InterpretationError> {
-        unimplemented!()
+        let index = frame.read_register(self.index()) as u32;
+        let func =
+            frame.indirect_callee(self.table(), index, self.func_type())?;
+        frame.clear_scratch();
+        for param in self.params().iter().copied() {
+            frame.push_scratch(param);
+        }
+        Ok(InterpretationFlow::TailCall(func))
     }
 }
 
@@ -122,13 +130,10 @@ impl InterpretInstr for BranchTableInstr {
         _outputs: &[Option<Value>],
         mut frame: ActivationFrame,
     ) -> Result<InterpretationFlow, InterpretationError> {
-        let case = frame.read_register(self.case());
-        let target = self
-            .targets()
-            .get(case as usize)
-            .copied()
-            .unwrap_or_else(|| self.default_target());
-        frame.switch_to_block(target);
+        let case = frame.read_register(self.case()) as u32;
+        let target = self.case_target(case);
+        frame.record_branch_table(target.map(|_| case));
+        frame.branch_to(target.unwrap_or_else(|| self.default_target()));
         Ok(InterpretationFlow::Continue)
     }
 }
@@ -15,12 +15,63 @@
 //! Allows to interpret the Runwell IR.
 
 #![forbid(unsafe_code)]
+#![cfg_attr(feature = "bench", feature(test))]
 
+#[cfg(all(test, feature = "bench"))]
+extern crate test;
+
+mod config;
 mod core;
+mod coverage;
+mod equivalence;
 mod error;
+mod global;
+mod import;
 mod instr;
+mod memory;
+mod profile;
+mod provider;
+mod pure;
+mod register_file;
+mod stats;
+mod trace;
+mod value;
 
 #[cfg(test)]
 mod tests;
 
-pub use self::{core::EvaluationContext, error::InterpretationError};
+#[cfg(all(test, feature = "bench"))]
+mod benches;
+
+pub use self::{
+    config::ExecutionConfig,
+    core::{
+        Debugger,
+        Evaluation,
+        EvaluationContext,
+        Snapshot,
+        Step,
+        DEFAULT_MAX_CALL_DEPTH,
+    },
+    coverage::{Coverage, CoverageReport, FunctionCoverage},
+    equivalence::{check_equivalence, EquivalenceChecker, Mismatch, Outcome},
+    error::{InterpretationError, TrapLocation},
+    global::GlobalHandle,
+    import::ImportRenames,
+    memory::{
+        LinearMemory,
+        MemoryCreator,
+        VecMemory,
+        VecMemoryCreator,
+        MAX_PAGES,
+        PAGE_SIZE,
+    },
+    profile::{BranchProfile, BranchTableProfile, Profile},
+    provider::{ClockProvider, EntropyProvider, FixedClock, SeededEntropy},
+    pure::EvaluatePure,
+    register_file::RegisterFile,
+    stats::{BlockStats, ExecutionStats},
+    value::DisplayValue,
+};
+#[cfg(feature = "std")]
+pub use self::provider::{OsClock, OsEntropy};
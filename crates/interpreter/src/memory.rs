@@ -0,0 +1,148 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Linear memories and the strategies to allocate them.
+//!
+//! Embedders choose how the linear memories of an evaluation context are
+//! allocated by providing a [`MemoryCreator`]. The default [`VecMemoryCreator`]
+//! allocates plain `Vec<u8>` backed memories which are portable and do not
+//! require any platform support. Embedders may implement [`MemoryCreator`]
+//! themselves, e.g. to use custom allocators or memory pools.
+
+use crate::InterpretationError;
+use core::fmt;
+use module::primitive::LinearMemoryDecl;
+
+/// The size of a linear memory page in bytes.
+pub const PAGE_SIZE: usize = 65536;
+
+/// The maximum number of pages of a linear memory with 32-bit addresses.
+pub const MAX_PAGES: u32 = 65536;
+
+/// A linear memory of an evaluation context.
+pub trait LinearMemory: fmt::Debug + Send {
+    /// Returns the current size of the linear memory in pages.
+    fn pages(&self) -> u32;
+
+    /// Returns the maximum size of the linear memory in pages if any.
+    fn maximum_pages(&self) -> Option<u32>;
+
+    /// Grows the linear memory by `delta` pages.
+    ///
+    /// Returns the previous size of the linear memory in pages or `None`
+    /// if the linear memory cannot grow by the requested amount of pages.
+    /// The contents of new pages are zero initialized.
+    fn grow(&mut self, delta: u32) -> Option<u32>;
+
+    /// Returns the bytes of the linear memory.
+    fn data(&self) -> &[u8];
+
+    /// Returns the mutable bytes of the linear memory.
+    fn data_mut(&mut self) -> &mut [u8];
+}
+
+/// Creates the linear memories of evaluation contexts.
+pub trait MemoryCreator: fmt::Debug {
+    /// Creates a zero initialized linear memory for the declaration.
+    ///
+    /// # Errors
+    ///
+    /// If the linear memory cannot be allocated.
+    fn create_memory(
+        &self,
+        decl: &LinearMemoryDecl,
+    ) -> Result<Box<dyn LinearMemory>, InterpretationError>;
+}
+
+/// Creates `Vec<u8>` backed linear memories.
+///
+/// This is the default memory creator of evaluation contexts.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct VecMemoryCreator;
+
+impl MemoryCreator for VecMemoryCreator {
+    fn create_memory(
+        &self,
+        decl: &LinearMemoryDecl,
+    ) -> Result<Box<dyn LinearMemory>, InterpretationError> {
+        let memory =
+            VecMemory::new(decl.initial_pages(), decl.maximum_pages())?;
+        Ok(Box::new(memory))
+    }
+}
+
+/// A linear memory backed by a `Vec<u8>`.
+#[derive(Debug)]
+pub struct VecMemory {
+    /// The bytes of the linear memory.
+    data: Vec<u8>,
+    /// The maximum size of the linear memory in pages if any.
+    maximum_pages: Option<u32>,
+}
+
+impl VecMemory {
+    /// Creates a new zero initialized linear memory.
+    ///
+    /// # Errors
+    ///
+    /// If the initial pages exceed the maximum pages or cannot be allocated.
+    pub fn new(
+        initial_pages: u32,
+        maximum_pages: Option<u32>,
+    ) -> Result<Self, InterpretationError> {
+        let mut memory = Self {
+            data: Vec::new(),
+            maximum_pages,
+        };
+        memory.grow(initial_pages).ok_or(
+            InterpretationError::MemoryAllocationFailed {
+                pages: initial_pages,
+            },
+        )?;
+        Ok(memory)
+    }
+}
+
+impl LinearMemory for VecMemory {
+    fn pages(&self) -> u32 {
+        (self.data.len() / PAGE_SIZE) as u32
+    }
+
+    fn maximum_pages(&self) -> Option<u32> {
+        self.maximum_pages
+    }
+
+    fn grow(&mut self, delta: u32) -> Option<u32> {
+        let pages = self.pages();
+        let new_pages = pages.checked_add(delta)?;
+        let max_pages = self.maximum_pages.unwrap_or(MAX_PAGES).min(MAX_PAGES);
+        if new_pages > max_pages {
+            return None
+        }
+        let new_len = (new_pages as usize).checked_mul(PAGE_SIZE)?;
+        self.data
+            .try_reserve_exact(new_len - self.data.len())
+            .ok()?;
+        self.data.resize(new_len, 0);
+        Some(pages)
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Execution profiles collected during interpretation.
+//!
+//! Profiles record how often control flow edges, conditional branches and
+//! branch table cases have been taken. They serve as input for profile guided
+//! optimizations such as block layout, inlining and outlining.
+
+use ir::primitive::{Block, Func};
+use std::collections::BTreeMap;
+
+/// The taken and not-taken counts of a conditional branch.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct BranchProfile {
+    /// How often the `then` target was taken.
+    taken: u64,
+    /// How often the `else` target was taken.
+    not_taken: u64,
+}
+
+impl BranchProfile {
+    /// Returns how often the `then` target was taken.
+    pub fn taken(&self) -> u64 {
+        self.taken
+    }
+
+    /// Returns how often the `else` target was taken.
+    pub fn not_taken(&self) -> u64 {
+        self.not_taken
+    }
+
+    /// Returns how often the conditional branch was executed.
+    pub fn total(&self) -> u64 {
+        self.taken + self.not_taken
+    }
+
+    /// Returns the probability that the `then` target is taken.
+    ///
+    /// Returns `None` if the conditional branch has never been executed.
+    pub fn probability(&self) -> Option<f64> {
+        match self.total() {
+            0 => None,
+            total => Some(self.taken as f64 / total as f64),
+        }
+    }
+
+    /// Merges the counts of the other branch profile into this one.
+    fn merge(&mut self, other: &Self) {
+        self.taken += other.taken;
+        self.not_taken += other.not_taken;
+    }
+}
+
+/// The case frequencies of a branch table.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BranchTableProfile {
+    /// How often each case key led to a non-default target.
+    cases: BTreeMap<u32, u64>,
+    /// How often the default target was taken.
+    default: u64,
+}
+
+impl BranchTableProfile {
+    /// Returns how often the case key led to a non-default target.
+    pub fn case_count(&self, key: u32) -> u64 {
+        self.cases.get(&key).copied().unwrap_or_default()
+    }
+
+    /// Returns how often the default target was taken.
+    pub fn default_count(&self) -> u64 {
+        self.default
+    }
+
+    /// Returns an iterator over all taken case keys and their counts in ascending key order.
+    pub fn cases(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.cases.iter().map(|(key, count)| (*key, *count))
+    }
+
+    /// Returns how often the branch table was executed.
+    pub fn total(&self) -> u64 {
+        self.cases.values().sum::<u64>() + self.default
+    }
+
+    /// Merges the counts of the other branch table profile into this one.
+    fn merge(&mut self, other: &Self) {
+        for (key, count) in other.cases() {
+            *self.cases.entry(key).or_default() += count;
+        }
+        self.default += other.default;
+    }
+}
+
+/// An execution profile of all functions evaluated by an evaluation context.
+///
+/// Enable profiling via [`EvaluationContext::enable_profiling`].
+///
+/// [`EvaluationContext::enable_profiling`]:
+/// crate::EvaluationContext::enable_profiling
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// How often control flow went from one basic block to another.
+    edges: BTreeMap<(Func, Block, Block), u64>,
+    /// The profiles of the conditional branches ending a basic block.
+    branches: BTreeMap<(Func, Block), BranchProfile>,
+    /// The profiles of the branch tables ending a basic block.
+    branch_tables: BTreeMap<(Func, Block), BranchTableProfile>,
+}
+
+impl Profile {
+    /// Returns `true` if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// Returns how often control flow went from `from` to `to` in the function.
+    pub fn edge_count(&self, func: Func, from: Block, to: Block) -> u64 {
+        self.edges
+            .get(&(func, from, to))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns an iterator over all taken edges of the function and their counts.
+    ///
+    /// The edges are yielded in ascending order.
+    pub fn edges(
+        &self,
+        func: Func,
+    ) -> impl Iterator<Item = (Block, Block, u64)> + '_ {
+        self.edges
+            .iter()
+            .filter(move |((edge_func, _, _), _)| *edge_func == func)
+            .map(|((_, from, to), count)| (*from, *to, *count))
+    }
+
+    /// Returns the profile of the conditional branch ending the basic block if executed.
+    pub fn branch(&self, func: Func, block: Block) -> Option<&BranchProfile> {
+        self.branches.get(&(func, block))
+    }
+
+    /// Returns the profile of the branch table ending the basic block if executed.
+    pub fn branch_table(
+        &self,
+        func: Func,
+        block: Block,
+    ) -> Option<&BranchTableProfile> {
+        self.branch_tables.get(&(func, block))
+    }
+
+    /// Merges the counts of the other profile into this one.
+    ///
+    /// Use this to combine profiles of multiple evaluation contexts.
+    pub fn merge(&mut self, other: &Self) {
+        for (edge, count) in &other.edges {
+            *self.edges.entry(*edge).or_default() += count;
+        }
+        for (key, branch) in &other.branches {
+            self.branches.entry(*key).or_default().merge(branch);
+        }
+        for (key, table) in &other.branch_tables {
+            self.branch_tables.entry(*key).or_default().merge(table);
+        }
+    }
+
+    /// Records that control flow went from `from` to `to` in the function.
+    pub(crate) fn record_edge(&mut self, func: Func, from: Block, to: Block) {
+        *self.edges.entry((func, from, to)).or_default() += 1;
+    }
+
+    /// Records the outcome of the conditional branch ending the basic block.
+    pub(crate) fn record_branch(
+        &mut self,
+        func: Func,
+        block: Block,
+        taken: bool,
+    ) {
+        let branch = self.branches.entry((func, block)).or_default();
+        if taken {
+            branch.taken += 1;
+        } else {
+            branch.not_taken += 1;
+        }
+    }
+
+    /// Records the case of the branch table ending the basic block.
+    ///
+    /// A case of `None` records that the default target was taken.
+    pub(crate) fn record_branch_table(
+        &mut self,
+        func: Func,
+        block: Block,
+        case: Option<u32>,
+    ) {
+        let table = self.branch_tables.entry((func, block)).or_default();
+        match case {
+            Some(key) => *table.cases.entry(key).or_default() += 1,
+            None => table.default += 1,
+        }
+    }
+}
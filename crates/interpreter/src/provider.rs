@@ -0,0 +1,168 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Clocks and sources of entropy of evaluation contexts.
+//!
+//! Host functions such as WASI shims query the time and random bytes from
+//! the providers of their evaluation context instead of the operating system.
+//! The default [`FixedClock`] and [`SeededEntropy`] providers are fully
+//! deterministic so that evaluations can be replayed, e.g. for consensus
+//! execution. Embedders opt into the clock and entropy of the operating
+//! system with [`OsClock`] and [`OsEntropy`].
+
+use core::fmt;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+
+/// Provides the time to host functions of an evaluation context.
+pub trait ClockProvider: fmt::Debug + Send {
+    /// Returns the wall clock time in nanoseconds since the Unix epoch.
+    fn realtime(&self) -> u64;
+
+    /// Returns the time of a monotonic clock in nanoseconds.
+    ///
+    /// The time is relative to an unspecified point in time and never
+    /// decreases between queries.
+    fn monotonic(&self) -> u64;
+}
+
+/// Provides random bytes to host functions of an evaluation context.
+pub trait EntropyProvider: fmt::Debug + Send {
+    /// Fills `dest` with random bytes.
+    fn fill_bytes(&mut self, dest: &mut [u8]);
+}
+
+/// A clock that is stopped at a fixed epoch.
+///
+/// Both the wall clock and the monotonic clock always report the epoch.
+/// This is the default clock of evaluation contexts.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FixedClock {
+    /// The epoch in nanoseconds since the Unix epoch.
+    epoch: u64,
+}
+
+impl FixedClock {
+    /// Creates a clock that is stopped at the epoch.
+    ///
+    /// The epoch is given in nanoseconds since the Unix epoch.
+    pub fn new(epoch: u64) -> Self {
+        Self { epoch }
+    }
+
+    /// Returns the epoch in nanoseconds since the Unix epoch.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+}
+
+impl ClockProvider for FixedClock {
+    fn realtime(&self) -> u64 {
+        self.epoch
+    }
+
+    fn monotonic(&self) -> u64 {
+        self.epoch
+    }
+}
+
+/// Deterministic entropy generated by ChaCha20 from a fixed seed.
+///
+/// Providers created from the same seed generate the same random bytes.
+/// The default provider is seeded with zeros and is the default source of
+/// entropy of evaluation contexts.
+#[derive(Debug, Clone)]
+pub struct SeededEntropy {
+    /// The ChaCha20 stream generating the random bytes.
+    rng: ChaCha20Rng,
+}
+
+impl SeededEntropy {
+    /// Creates a deterministic source of entropy from the seed.
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self {
+            rng: ChaCha20Rng::from_seed(seed),
+        }
+    }
+
+    /// Creates a deterministic source of entropy from the 64-bit seed.
+    pub fn from_u64(seed: u64) -> Self {
+        Self {
+            rng: ChaCha20Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for SeededEntropy {
+    fn default() -> Self {
+        Self::new([0x00; 32])
+    }
+}
+
+impl EntropyProvider for SeededEntropy {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+}
+
+/// The clock of the operating system.
+///
+/// # Note
+///
+/// Evaluations that query this clock are not deterministic.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone)]
+pub struct OsClock {
+    /// The point in time from which the monotonic clock is measured.
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl Default for OsClock {
+    fn default() -> Self {
+        Self {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ClockProvider for OsClock {
+    fn realtime(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or_default()
+    }
+
+    fn monotonic(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+}
+
+/// The entropy of the operating system.
+///
+/// # Note
+///
+/// Evaluations that query this source of entropy are not deterministic.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct OsEntropy;
+
+#[cfg(feature = "std")]
+impl EntropyProvider for OsEntropy {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand_core::OsRng.fill_bytes(dest)
+    }
+}
@@ -0,0 +1,61 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Evaluation of pure functions at compile time.
+//!
+//! Pure functions only depend on their inputs. Therefore their results
+//! can be precomputed for constant arguments without instantiating the
+//! module, e.g. in order to fold constants of smart contracts.
+
+use crate::{EvaluationContext, InterpretationError};
+use ir::primitive::Const;
+use module::Module;
+
+/// Evaluates pure exported functions of a module.
+///
+/// Bring this trait into scope in order to call
+/// [`eval_pure`][EvaluatePure::eval_pure] on a [`Module`].
+pub trait EvaluatePure {
+    /// Evaluates the pure exported function with the given name and arguments.
+    ///
+    /// # Errors
+    ///
+    /// - If the module has no exported function with the given name.
+    /// - If the exported function is not pure.
+    /// - If the types of the arguments do not match the function inputs.
+    /// - If the evaluation of the function fails, e.g. if it traps.
+    fn eval_pure(
+        &self,
+        name: &str,
+        args: &[Const],
+    ) -> Result<Vec<Const>, InterpretationError>;
+}
+
+impl EvaluatePure for Module {
+    fn eval_pure(
+        &self,
+        name: &str,
+        args: &[Const],
+    ) -> Result<Vec<Const>, InterpretationError> {
+        let func = self.get_exported_function(name).ok_or_else(|| {
+            InterpretationError::UnknownExportedFunction {
+                name: name.to_string(),
+            }
+        })?;
+        if !self.is_pure(func) {
+            return Err(InterpretationError::ImpureFunction { func })
+        }
+        EvaluationContext::new(self).invoke(name, args)
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Register files captured for post-execution inspection.
+
+use crate::DisplayValue;
+use core::fmt;
+use entity::RawIdx;
+use ir::primitive::{Const, Func, Type, Value};
+
+/// The registers of a function frame at the time the function returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterFile {
+    /// The function that owned the function frame.
+    func: Func,
+    /// The bits and types of the registers indexed by their values.
+    ///
+    /// The type is `None` for values that are not defined by the function.
+    registers: Vec<(u64, Option<Type>)>,
+}
+
+impl RegisterFile {
+    /// Creates a new register file of the function.
+    pub(crate) fn new(func: Func, registers: Vec<(u64, Option<Type>)>) -> Self {
+        Self { func, registers }
+    }
+
+    /// Returns the function that owned the function frame.
+    pub fn func(&self) -> Func {
+        self.func
+    }
+
+    /// Returns the number of registers.
+    pub fn len(&self) -> usize {
+        self.registers.len()
+    }
+
+    /// Returns `true` if there are no registers.
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty()
+    }
+
+    /// Returns the bits of the register of the value if any.
+    pub fn get(&self, value: Value) -> Option<u64> {
+        self.registers
+            .get(value.into_raw().into_u32() as usize)
+            .map(|&(bits, _)| bits)
+    }
+
+    /// Returns the bits of the register of the value as a constant of its type.
+    ///
+    /// Returns `None` if the value is not defined by the function.
+    pub fn get_const(&self, value: Value) -> Option<Const> {
+        let &(bits, ty) =
+            self.registers.get(value.into_raw().into_u32() as usize)?;
+        ty.map(|ty| Const::from_bits64(ty, bits))
+    }
+
+    /// Iterates over the values, bits and types of all registers.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (Value, u64, Option<Type>)> + '_ {
+        self.registers.iter().enumerate().map(|(n, &(bits, ty))| {
+            (Value::from_raw(RawIdx::from_u32(n as u32)), bits, ty)
+        })
+    }
+}
+
+impl fmt::Display for RegisterFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "registers of {}:", self.func)?;
+        for (value, bits, ty) in self.iter() {
+            match ty {
+                Some(ty) => {
+                    let typed = DisplayValue::new(ty, bits);
+                    writeln!(f, "    {}: {} = {}", value, ty, typed)?
+                }
+                None => writeln!(f, "    {} = 0x{:X}", value, bits)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Statistics about the instructions executed during interpretation.
+//!
+//! Statistics count the executions per instruction kind and per basic block.
+//! They help to identify hot basic blocks, e.g. as candidates for further
+//! compilation.
+
+use core::{cmp::Reverse, fmt};
+use ir::{
+    instr::InstructionDescriptor,
+    primitive::{Block, Func},
+};
+use std::collections::BTreeMap;
+
+/// How often a single basic block has been executed.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct BlockStats {
+    /// How often the basic block has been entered.
+    entries: u64,
+    /// How many instructions of the basic block have been executed.
+    instrs: u64,
+}
+
+impl BlockStats {
+    /// Returns how often the basic block has been entered.
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns how many instructions of the basic block have been executed.
+    pub fn instrs(&self) -> u64 {
+        self.instrs
+    }
+
+    /// Merges the counts of the other block statistics into this one.
+    fn merge(&mut self, other: &Self) {
+        self.entries += other.entries;
+        self.instrs += other.instrs;
+    }
+}
+
+/// Execution statistics of all functions evaluated by an evaluation context.
+///
+/// Enable statistics via [`EvaluationContext::enable_statistics`].
+///
+/// The [`Display`][fmt::Display] implementation prints a human readable
+/// report of the instruction kinds and basic blocks ordered by their
+/// number of executed instructions.
+///
+/// [`EvaluationContext::enable_statistics`]:
+/// crate::EvaluationContext::enable_statistics
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExecutionStats {
+    /// How often instructions of each kind have been executed.
+    kinds: BTreeMap<&'static str, u64>,
+    /// How often the basic blocks have been executed.
+    blocks: BTreeMap<(Func, Block), BlockStats>,
+}
+
+impl ExecutionStats {
+    /// Returns `true` if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Returns the total number of executed instructions.
+    pub fn total_instrs(&self) -> u64 {
+        self.kinds.values().sum()
+    }
+
+    /// Returns how often instructions with the given mnemonic have been executed.
+    pub fn kind_count(&self, name: &str) -> u64 {
+        self.kinds.get(name).copied().unwrap_or_default()
+    }
+
+    /// Returns an iterator over all executed instruction kinds and their counts.
+    ///
+    /// The kinds are yielded in descending order of their counts.
+    pub fn kinds(&self) -> impl Iterator<Item = (&'static str, u64)> {
+        let mut kinds = self
+            .kinds
+            .iter()
+            .map(|(name, count)| (*name, *count))
+            .collect::<Vec<_>>();
+        kinds.sort_by(|(_, lhs), (_, rhs)| rhs.cmp(lhs));
+        kinds.into_iter()
+    }
+
+    /// Returns the statistics of the basic block of the function if executed.
+    pub fn block(&self, func: Func, block: Block) -> Option<&BlockStats> {
+        self.blocks.get(&(func, block))
+    }
+
+    /// Returns the `n` basic blocks with the most executed instructions.
+    ///
+    /// The basic blocks are yielded in descending order of their executed
+    /// instructions.
+    pub fn hot_blocks(&self, n: usize) -> Vec<(Func, Block, BlockStats)> {
+        let mut blocks = self
+            .blocks
+            .iter()
+            .map(|((func, block), stats)| (*func, *block, *stats))
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|(_, _, stats)| Reverse(stats.instrs));
+        blocks.truncate(n);
+        blocks
+    }
+
+    /// Merges the counts of the other statistics into this one.
+    ///
+    /// Use this to combine the statistics of multiple evaluation contexts.
+    pub fn merge(&mut self, other: &Self) {
+        for (name, count) in &other.kinds {
+            *self.kinds.entry(name).or_default() += count;
+        }
+        for (key, stats) in &other.blocks {
+            self.blocks.entry(*key).or_default().merge(stats);
+        }
+    }
+
+    /// Records the execution of an instruction of the basic block.
+    ///
+    /// `entered` is `true` if the instruction is the first executed
+    /// instruction of the basic block since it has been entered.
+    pub(crate) fn record_instr(
+        &mut self,
+        func: Func,
+        block: Block,
+        descriptor: &InstructionDescriptor,
+        entered: bool,
+    ) {
+        *self.kinds.entry(descriptor.name()).or_default() += 1;
+        let stats = self.blocks.entry((func, block)).or_default();
+        stats.instrs += 1;
+        if entered {
+            stats.entries += 1;
+        }
+    }
+}
+
+impl fmt::Display for ExecutionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "executed instructions: {}", self.total_instrs())?;
+        writeln!(f, "instruction kinds:")?;
+        for (name, count) in self.kinds() {
+            writeln!(f, "    {}: {}", name, count)?;
+        }
+        writeln!(f, "basic blocks:")?;
+        for (func, block, stats) in self.hot_blocks(self.blocks.len()) {
+            writeln!(
+                f,
+                "    {} {}: {} instructions, {} entries",
+                func, block, stats.instrs, stats.entries,
+            )?;
+        }
+        Ok(())
+    }
+}
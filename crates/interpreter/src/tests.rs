@@ -14,33 +14,97 @@
 
 //! The tests build up a Runwell IR function and print an output of the function.
 //!
-//! Note that currently the tests do not automatically test if the constructed
-//! function is equal to the tests expectation.
+//! Some tests additionally check the printed output against `CHECK` patterns
+//! using [`module::filecheck`].
 //! Also note that currently no optimizations are performed.
-//!
-//! Automated checks to verify that the constructed functions match expectations
-//! are planned after an API for that has been designed.
 
-use crate::EvaluationContext;
+mod baseline;
+mod conformance;
+mod float;
+mod provider;
+mod random;
+
+use crate::{
+    Debugger,
+    DisplayValue,
+    EvaluationContext,
+    ExecutionConfig,
+    GlobalHandle,
+    ImportRenames,
+    InterpretationError,
+    LinearMemory,
+    MemoryCreator,
+    VecMemory,
+    VecMemoryCreator,
+    MAX_PAGES,
+    PAGE_SIZE,
+};
+use core::cell::Cell;
 use entity::RawIdx;
 use ir::{
-    instr::operands::CompareIntOp,
+    instr::{
+        operands::{BinaryIntOp, CompareFloatOp, CompareIntOp, ShiftIntOp},
+        BinaryIntInstr,
+        BranchInstr,
+        ConstInstr,
+        Instruction,
+        OperandKind,
+        TrapCode,
+        TypeConstraint,
+    },
     primitive::{
+        Block,
         Const,
+        Data,
         FloatConst,
         FloatType,
         Func,
         IntConst,
         IntType,
+        Mem,
         Type,
         Value,
     },
+    ImmU32,
+    ToDiagnostic,
+    VisitValues,
 };
 use module::{
-    builder::{FunctionBuilder, InstructionBuilder},
-    primitive::{FunctionType, Instr, Variable},
+    builder::{FunctionBuilder, InstructionBuilder, IrArena, SwitchLowering},
+    primitive::{
+        FunctionType,
+        GlobalVariable,
+        ImportName,
+        InitExpr,
+        Instr,
+        LinearMemoryDecl,
+        SourceSpan,
+        TableDecl,
+        Variable,
+    },
+    filecheck::filecheck,
+    CheckedArithmetic,
+    DisplayConfig,
     FunctionBody,
+    InstrumentationHooks,
     Module,
+    ModulePolicy,
+    Overflow,
+    PolicyViolation,
+    ResourceLimits,
+    ShadowStack,
+    StartFuncPolicy,
+    TargetConstraints,
+    VerifierViolation,
+};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+        Mutex,
+    },
 };
 
 /// Evaluates the function given the inputs and returns the results.
@@ -51,6 +115,39 @@ fn module_with_func<F>(
 ) -> (Func, Module)
 where
     F: FnOnce(&mut FunctionBuilder) -> Result<(), module::Error>,
+{
+    module_with_edited_func(inputs, outputs, f, |_| Ok(()))
+}
+
+/// Like [`module_with_func`] but edits the function body before it is pushed.
+fn module_with_edited_func<F, E>(
+    inputs: &[Type],
+    outputs: &[Type],
+    f: F,
+    edit: E,
+) -> (Func, Module)
+where
+    F: FnOnce(&mut FunctionBuilder) -> Result<(), module::Error>,
+    E: FnOnce(&mut FunctionBody) -> Result<(), module::Error>,
+{
+    let (func, module) = module_with_unverified_func(inputs, outputs, f, edit);
+    module.verify().unwrap();
+
+    println!("{}", module);
+
+    (func, module)
+}
+
+/// Like [`module_with_edited_func`] but does not verify the resulting module.
+fn module_with_unverified_func<F, E>(
+    inputs: &[Type],
+    outputs: &[Type],
+    f: F,
+    edit: E,
+) -> (Func, Module)
+where
+    F: FnOnce(&mut FunctionBuilder) -> Result<(), module::Error>,
+    E: FnOnce(&mut FunctionBody) -> Result<(), module::Error>,
 {
     let mut builder = Module::build();
     let mut type_builder = builder.type_section().unwrap();
@@ -69,12 +166,10 @@ where
     let (res, mut body_builder) = builder.code_section().unwrap();
     let mut func_builder = FunctionBody::build(func, res);
     f(&mut func_builder).unwrap();
-    let func_body = func_builder.finalize().unwrap();
+    let mut func_body = func_builder.finalize().unwrap();
+    edit(&mut func_body).unwrap();
     body_builder.push_body(func, func_body).unwrap();
     let module = builder.finalize().unwrap();
-
-    println!("{}", module);
-
     (func, module)
 }
 
@@ -378,6 +473,27 @@ fn counting_loop_works() {
 
             Ok(())
         });
+    filecheck(
+        &module.to_string(),
+        "
+        // CHECK: fn func0(v0: i32) -> i32 {
+        // CHECK-NEXT: block {
+        // CHECK: const<i32> 0
+        // CHECK: br bb1(v1)
+        // CHECK-NEXT: }
+        // CHECK-NEXT: block bb1(v3: i32) {
+        // CHECK-NEXT: icmp<i32> -slt v3 v0
+        // CHECK-NEXT: if
+        // CHECK-SAME: then bb2 else bb3
+        // CHECK-NEXT: }
+        // CHECK-NEXT: block bb2 {
+        // CHECK: iadd<i32> v3
+        // CHECK-NEXT: br bb1(v5)
+        // CHECK: block bb3 {
+        // CHECK-NEXT: return
+        ",
+    )
+    .unwrap();
     let mut ctx = EvaluationContext::new(&module);
     for count_until in 0..10 {
         let count_until = IntConst::I32(count_until).into();
@@ -387,6 +503,132 @@ fn counting_loop_works() {
     }
 }
 
+/// Constructs a counting loop without sealing any of its basic blocks.
+///
+/// Returns the loop header, body and exit blocks.
+fn construct_unsealed_counting_loop(
+    b: &mut FunctionBuilder,
+) -> Result<[Block; 3], module::Error> {
+    b.declare_variables(1, IntType::I32.into())?;
+    b.body()?;
+    let loop_head = b.create_block()?;
+    let loop_body = b.create_block()?;
+    let loop_exit = b.create_block()?;
+    let input = Variable::from_raw(RawIdx::from_u32(0));
+    let counter = Variable::from_raw(RawIdx::from_u32(1));
+    let v0 = b.ins()?.constant(IntConst::I32(0))?;
+    b.write_var(counter, v0)?;
+    b.ins()?.br(loop_head)?;
+
+    b.switch_to_block(loop_head)?;
+    let v1 = b.read_var(counter)?;
+    let v2 = b.read_var(input)?;
+    let v3 = b.ins()?.icmp(IntType::I32, CompareIntOp::Slt, v1, v2)?;
+    b.ins()?.if_then_else(v3, loop_body, loop_exit)?;
+
+    b.switch_to_block(loop_body)?;
+    let v4 = b.read_var(counter)?;
+    let v5 = b.ins()?.constant(IntConst::I32(1))?;
+    let v6 = b.ins()?.iadd(IntType::I32, v4, v5)?;
+    b.write_var(counter, v6)?;
+    b.ins()?.br(loop_head)?;
+
+    b.switch_to_block(loop_exit)?;
+    let v7 = b.read_var(counter)?;
+    b.ins()?.return_values([v7].iter().copied())?;
+    Ok([loop_head, loop_body, loop_exit])
+}
+
+#[test]
+fn blocks_can_be_sealed_all_at_once() {
+    let check_counting = |module: &Module, func: Func| {
+        let mut ctx = EvaluationContext::new(module);
+        for count_until in 0..5 {
+            let count_until = IntConst::I32(count_until).into();
+            let result = evaluate_func_in_ctx(&mut ctx, func, &[count_until]);
+            let result = bits_into_const(module, func, result);
+            assert_eq!(result, vec![count_until]);
+        }
+    };
+    let inputs = &[IntType::I32.into()];
+    let outputs = &[IntType::I32.into()];
+    // Sealing all blocks explicitly before finalization.
+    let (func, module) = module_with_func(inputs, outputs, |b| {
+        let blocks = construct_unsealed_counting_loop(b)?;
+        assert_eq!(b.seal_all_blocks()?, blocks.to_vec());
+        assert_eq!(b.seal_all_blocks()?, vec![]);
+        Ok(())
+    });
+    check_counting(&module, func);
+    // Plain finalization reports the unsealed blocks.
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let mut b = FunctionBody::build(func, res);
+    construct_unsealed_counting_loop(&mut b).unwrap();
+    let error = b.finalize().unwrap_err();
+    assert_eq!(
+        error.to_diagnostic().code(),
+        "builder.unsealed_blocks_upon_finalize"
+    );
+    // Automatic sealing upon finalization reports the force-sealed blocks.
+    let mut b = FunctionBody::build(func, res);
+    let blocks = construct_unsealed_counting_loop(&mut b).unwrap();
+    let (body, force_sealed) = b.finalize_auto_sealed().unwrap();
+    assert_eq!(force_sealed, blocks.to_vec());
+    body_builder.push_body(func, body).unwrap();
+    let module = builder.finalize().unwrap();
+    check_counting(&module, func);
+}
+
+#[test]
+fn verbose_display_works() {
+    let (func, module) =
+        module_with_func(&[IntType::I32.into()], &[IntType::I32.into()], |b| {
+            construct_unsealed_counting_loop(b)?;
+            b.seal_all_blocks()?;
+            Ok(())
+        });
+    let function = module.get_function(func).unwrap();
+    // The default configuration matches the regular display.
+    let display = function.display_with(DisplayConfig::default());
+    assert_eq!(display.to_string(), function.to_string());
+    filecheck(
+        &function.display_with(DisplayConfig::verbose()).to_string(),
+        "
+        // CHECK: block {
+        // CHECK-NEXT: instr(0): let v1: i32 = const<i32> 0 // v1 = var(1)
+        // CHECK-NEXT: instr(1): br bb1(v1)
+        // CHECK: block bb1(v2: i32) { // preds: bb0, bb2; v2 = var(1)
+        // CHECK-NEXT: instr(2): let v3: bool = icmp<i32> -slt v2 v0
+        // CHECK: block bb2 { // preds: bb1
+        // CHECK: instr(5): let v5: i32 = iadd<i32> v2 v4 // v5 = var(1)
+        // CHECK: block bb3 { // preds: bb1
+        // CHECK-NEXT: instr(7): return v2
+        ",
+    )
+    .unwrap();
+    let mut config = DisplayConfig::new();
+    config.set_show_value_types(false);
+    filecheck(
+        &function.display_with(config).to_string(),
+        "
+        // CHECK: fn func0(v0: i32) -> i32 {
+        // CHECK: block bb1(v2) {
+        // CHECK-NEXT: let v3 = icmp<i32> -slt v2 v0
+        ",
+    )
+    .unwrap();
+}
+
 fn construct_is_even_and_is_odd<F>(
     mut f: F,
 ) -> Result<(Module, Func, Func), module::Error>
@@ -534,10 +776,771 @@ fn ping_pong_tail_calls() -> Result<(), module::Error> {
         let is_odd_result = bits_into_const(&module, is_odd, is_odd_result);
         assert_eq!(is_odd_result, vec![Const::Bool(x % 2 == 1)]);
     }
+    // Tail calls replace the frame of the caller so deep recursions work.
+    let input = IntConst::I32(100_001).into();
+    let result = evaluate_func_in_ctx(&mut ctx, is_even, &[input]);
+    assert_eq!(result, vec![0]);
 
     Ok(())
 }
 
+#[test]
+fn tail_call_requires_matching_outputs() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let caller_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let callee_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(Type::Bool);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let caller = function_builder.push_function(caller_type).unwrap();
+    let callee = function_builder.push_function(callee_type).unwrap();
+    let (res, _) = builder.code_section().unwrap();
+    let mut b = FunctionBody::build(caller, res);
+    b.body().unwrap();
+    let input = b.input_var(0).unwrap();
+    let v0 = b.read_var(input).unwrap();
+    assert_eq!(
+        b.ins().unwrap().tail_call(callee, vec![v0]),
+        Err(module::Error::from(
+            module::FunctionBuilderError::UnmatchingFunctionReturnType {
+                returned_types: vec![Type::Bool],
+                expected_types: vec![IntType::I32.into()],
+            }
+        ))
+    );
+    // The failed tail call does not terminate the basic block.
+    b.ins().unwrap().tail_call(caller, vec![v0]).unwrap();
+    b.finalize().unwrap();
+}
+
+#[test]
+fn calls_require_matching_arguments() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut table_builder = builder.table_section().unwrap();
+    let table = table_builder.push_table(TableDecl::new(1, None)).unwrap();
+    let (res, _) = builder.code_section().unwrap();
+    let mut b = FunctionBody::build(func, res);
+    b.body().unwrap();
+    let input = b.input_var(0).unwrap();
+    let v0 = b.read_var(input).unwrap();
+    let v1 = b.ins().unwrap().constant(IntConst::I64(1)).unwrap();
+    let unmatching = |given_types: Vec<Type>| -> Result<Instr, _> {
+        Err(module::Error::from(
+            module::FunctionBuilderError::UnmatchingCallArgumentTypes {
+                given_types,
+                expected_types: vec![IntType::I32.into()],
+            },
+        ))
+    };
+    let i32_type = Type::from(IntType::I32);
+    let i64_type = Type::from(IntType::I64);
+    assert_eq!(b.ins().unwrap().call(func, vec![]), unmatching(vec![]));
+    assert_eq!(
+        b.ins().unwrap().call(func, vec![v0, v0]),
+        unmatching(vec![i32_type, i32_type])
+    );
+    assert_eq!(
+        b.ins().unwrap().call(func, vec![v1]),
+        unmatching(vec![i64_type])
+    );
+    assert_eq!(
+        b.ins()
+            .unwrap()
+            .call_indirect(table, func_type, v0, vec![v1]),
+        unmatching(vec![i64_type])
+    );
+    assert_eq!(
+        b.ins().unwrap().tail_call(func, vec![v1]),
+        unmatching(vec![i64_type])
+    );
+    // The outputs of calls are allocated from the signature of the callee.
+    let instr = b.ins().unwrap().call(func, vec![v0]).unwrap();
+    let v2 = b.instr_values(instr).unwrap()[0];
+    let instr = b
+        .ins()
+        .unwrap()
+        .call_indirect(table, func_type, v0, vec![v2])
+        .unwrap();
+    let v3 = b.instr_values(instr).unwrap()[0];
+    b.ins().unwrap().tail_call(func, vec![v3]).unwrap();
+    b.finalize().unwrap();
+}
+
+#[test]
+fn with_signature_declares_input_variables() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let signature = {
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_input(IntType::I64);
+        b.push_output(IntType::I64);
+        b.finalize()
+    };
+    let func_type = type_builder.push_type(signature.clone());
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let mut b = FunctionBody::with_signature(func, res, &signature).unwrap();
+    assert_eq!(b.input_vars().len(), 2);
+    b.body().unwrap();
+    let lhs = b.input_var(0).unwrap();
+    let rhs = b.input_var(1).unwrap();
+    assert_eq!(b.var_type(lhs).unwrap(), IntType::I32.into());
+    assert_eq!(b.var_type(rhs).unwrap(), IntType::I64.into());
+    let v0 = b.read_var(lhs).unwrap();
+    let v1 = b.read_var(rhs).unwrap();
+    let v2 = b
+        .ins()
+        .unwrap()
+        .iextend(IntType::I32, IntType::I64, v0, false)
+        .unwrap();
+    let v3 = b.ins().unwrap().iadd(IntType::I64, v2, v1).unwrap();
+    b.ins().unwrap().return_values([v3]).unwrap();
+    let body = b.finalize().unwrap();
+    body_builder.push_body(func, body).unwrap();
+    let module = builder.finalize().unwrap();
+    module.verify().unwrap();
+    let results = evaluate_func(
+        &module,
+        func,
+        &[IntConst::I32(40).into(), IntConst::I64(2).into()],
+    );
+    assert_eq!(results, vec![42]);
+}
+
+#[test]
+fn with_signature_requires_matching_signature() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let (res, _) = builder.code_section().unwrap();
+    let signature = {
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I64);
+        b.push_output(IntType::I32);
+        b.finalize()
+    };
+    let expected = || {
+        Some(module::Error::from(
+            module::FunctionBuilderError::UnmatchingFunctionSignature {
+                func,
+                signature: signature.clone(),
+            },
+        ))
+    };
+    assert_eq!(
+        FunctionBody::with_signature(func, res, &signature).err(),
+        expected()
+    );
+    let mut arena = IrArena::new();
+    assert_eq!(
+        FunctionBody::with_signature_in(func, res, &signature, &mut arena)
+            .err(),
+        expected()
+    );
+}
+
+#[test]
+fn binary_integer_instructions_work() {
+    let i32_type = Type::from(IntType::I32);
+    let (func, module) =
+        module_with_func(&[i32_type, i32_type], &[i32_type; 8], |b| {
+            b.body()?;
+            let lhs = b.read_var(b.input_var(0).unwrap())?;
+            let rhs = b.read_var(b.input_var(1).unwrap())?;
+            let ty = IntType::I32;
+            let results = [
+                b.ins()?.isub(ty, lhs, rhs)?,
+                b.ins()?.sdiv(ty, lhs, rhs)?,
+                b.ins()?.udiv(ty, lhs, rhs)?,
+                b.ins()?.srem(ty, lhs, rhs)?,
+                b.ins()?.urem(ty, lhs, rhs)?,
+                b.ins()?.iand(ty, lhs, rhs)?,
+                b.ins()?.ior(ty, lhs, rhs)?,
+                b.ins()?.ixor(ty, lhs, rhs)?,
+            ];
+            b.ins()?.return_values(results.iter().copied())?;
+            Ok(())
+        });
+    let mut ctx = EvaluationContext::new(&module);
+    for &lhs in &[0_i32, 1, -1, 7, -8, i32::MAX] {
+        for &rhs in &[1_i32, -1, 3, -7] {
+            let inputs = [IntConst::I32(lhs).into(), IntConst::I32(rhs).into()];
+            let result = evaluate_func_in_ctx(&mut ctx, func, &inputs);
+            let result = bits_into_const(&module, func, result);
+            let (ulhs, urhs) = (lhs as u32, rhs as u32);
+            let expected = [
+                lhs.wrapping_sub(rhs),
+                lhs / rhs,
+                (ulhs / urhs) as i32,
+                lhs % rhs,
+                (ulhs % urhs) as i32,
+                lhs & rhs,
+                lhs | rhs,
+                lhs ^ rhs,
+            ];
+            let expected = expected
+                .iter()
+                .map(|&value| Const::from(IntConst::I32(value)))
+                .collect::<Vec<_>>();
+            assert_eq!(result, expected, "mismatch for {} and {}", lhs, rhs);
+        }
+    }
+}
+
+#[test]
+fn unary_integer_instructions_respect_their_width() {
+    /// Returns the leading zeros, trailing zeros and ones of the bits.
+    fn expected(ty: IntType, bits: u64) -> [u64; 3] {
+        let counts = |clz: u32, ctz: u32, popcnt: u32| {
+            [u64::from(clz), u64::from(ctz), u64::from(popcnt)]
+        };
+        match ty {
+            IntType::I8 => {
+                let x = bits as u8;
+                counts(x.leading_zeros(), x.trailing_zeros(), x.count_ones())
+            }
+            IntType::I16 => {
+                let x = bits as u16;
+                counts(x.leading_zeros(), x.trailing_zeros(), x.count_ones())
+            }
+            IntType::I32 => {
+                let x = bits as u32;
+                counts(x.leading_zeros(), x.trailing_zeros(), x.count_ones())
+            }
+            IntType::I64 => {
+                let x = bits;
+                counts(x.leading_zeros(), x.trailing_zeros(), x.count_ones())
+            }
+        }
+    }
+    let int_types = [IntType::I8, IntType::I16, IntType::I32, IntType::I64];
+    for ty in int_types.iter().copied() {
+        let (func, module) =
+            module_with_func(&[ty.into()], &[ty.into(); 3], |b| {
+                b.body()?;
+                let x = b.read_var(b.input_var(0).unwrap())?;
+                let results = [
+                    b.ins()?.iclz(ty, x)?,
+                    b.ins()?.ictz(ty, x)?,
+                    b.ins()?.ipopcnt(ty, x)?,
+                ];
+                b.ins()?.return_values(results.iter().copied())?;
+                Ok(())
+            });
+        let mut ctx = EvaluationContext::new(&module);
+        for &bits in &[0, 1, 0x80, 0x8000, 0x8000_0000, 0xF0F0, u64::MAX] {
+            let input = Const::from_bits64(ty.into(), bits);
+            let result = evaluate_func_in_ctx(&mut ctx, func, &[input]);
+            let result = bits_into_const(&module, func, result);
+            let expected = expected(ty, bits)
+                .iter()
+                .map(|&count| Const::from_bits64(ty.into(), count))
+                .collect::<Vec<_>>();
+            assert_eq!(result, expected, "mismatch for {} of {}", bits, ty);
+        }
+    }
+}
+
+#[test]
+fn conversion_instructions_work() {
+    let i32_type = Type::from(IntType::I32);
+    let f32_type = Type::from(FloatType::F32);
+    let f64_type = Type::from(FloatType::F64);
+    let inputs = [i32_type, f32_type, f64_type];
+    let outputs = [
+        IntType::I64.into(),
+        IntType::I8.into(),
+        f64_type,
+        f32_type,
+        f64_type,
+        i32_type,
+        i32_type,
+    ];
+    let (func, module) = module_with_func(&inputs, &outputs, |b| {
+        b.body()?;
+        let x = b.read_var(b.input_var(0).unwrap())?;
+        let y = b.read_var(b.input_var(1).unwrap())?;
+        let z = b.read_var(b.input_var(2).unwrap())?;
+        use module::FunctionBuilderError as BuilderError;
+        let (i8_type, i32_type, i64_type) =
+            (IntType::I8, IntType::I32, IntType::I64);
+        let (f32_type, f64_type) = (FloatType::F32, FloatType::F64);
+        let error = |error: BuilderError| Err(module::Error::from(error));
+        assert_eq!(
+            b.ins()?.iextend(i64_type, i32_type, x, true),
+            error(BuilderError::InvalidExtension {
+                from_type: i64_type,
+                to_type: i32_type,
+            })
+        );
+        assert_eq!(
+            b.ins()?.itruncate(i32_type, i64_type, x),
+            error(BuilderError::InvalidTruncation {
+                from_type: i32_type,
+                to_type: i64_type,
+            })
+        );
+        assert_eq!(
+            b.ins()?.promote(f64_type, f32_type, z),
+            error(BuilderError::InvalidPromotion {
+                from_type: f64_type,
+                to_type: f32_type,
+            })
+        );
+        assert_eq!(
+            b.ins()?.demote(f32_type, f64_type, y),
+            error(BuilderError::InvalidDemotion {
+                from_type: f32_type,
+                to_type: f64_type,
+            })
+        );
+        assert_eq!(
+            b.ins()?.reinterpret(i32_type.into(), f64_type.into(), x),
+            error(BuilderError::UnmatchingReinterpretBitwidths {
+                from_bitwidth: 32,
+                to_bitwidth: 64,
+                src: x,
+            })
+        );
+        // The source value must be of the source type.
+        assert_eq!(
+            b.ins()?.int_to_float(true, i32_type, f32_type, y),
+            error(BuilderError::UnmatchingValueType {
+                value: y,
+                value_type: f32_type.into(),
+                expected_type: i32_type.into(),
+            })
+        );
+        assert!(b.ins()?.iextend(i32_type, i64_type, z, true).is_err());
+        assert!(b.ins()?.promote(f32_type, f64_type, z).is_err());
+        let results = [
+            b.ins()?.iextend(i32_type, i64_type, x, true)?,
+            b.ins()?.itruncate(i32_type, i8_type, x)?,
+            b.ins()?.promote(f32_type, f64_type, y)?,
+            b.ins()?.demote(f64_type, f32_type, z)?,
+            b.ins()?.int_to_float(false, i32_type, f64_type, x)?,
+            b.ins()?.float_to_int(f64_type, i32_type, true, z, true)?,
+            b.ins()?.reinterpret(f32_type.into(), i32_type.into(), y)?,
+        ];
+        b.ins()?.return_values(results.iter().copied())?;
+        Ok(())
+    });
+    let f32 = |value: f32| Const::from(FloatConst::F32(value.into()));
+    let f64 = |value: f64| Const::from(FloatConst::F64(value.into()));
+    let inputs = [IntConst::I32(-2).into(), f32(1.5), f64(-1e10)];
+    let result = evaluate_func(&module, func, &inputs);
+    let result = bits_into_const(&module, func, result);
+    assert_eq!(
+        result,
+        vec![
+            IntConst::I64(-2).into(),
+            IntConst::I8(-2).into(),
+            f64(1.5),
+            f32(-1e10),
+            f64(f64::from(u32::MAX - 1)),
+            IntConst::I32(i32::MIN).into(),
+            IntConst::I32(1.5_f32.to_bits() as i32).into(),
+        ]
+    );
+}
+
+#[test]
+fn integer_extensions_work_for_all_widths() {
+    let types = [IntType::I8, IntType::I16, IntType::I32, IntType::I64];
+    let mask = |ty: IntType| u64::MAX >> (64 - ty.bit_width());
+    for (n, &from_type) in types.iter().enumerate() {
+        for &to_type in &types[n + 1..] {
+            for &signed in &[true, false] {
+                let (func, module) = module_with_func(
+                    &[from_type.into()],
+                    &[to_type.into()],
+                    |b| {
+                        b.body()?;
+                        let x = b.read_var(b.input_var(0).unwrap())?;
+                        let y =
+                            b.ins()?.iextend(from_type, to_type, x, signed)?;
+                        b.ins()?.return_values([y].iter().copied())?;
+                        Ok(())
+                    },
+                );
+                // The most significant bit of the source value is set.
+                let bits = 0x1 << (from_type.bit_width() - 1) | 0x1;
+                let input = Const::from_bits64(from_type.into(), bits);
+                let expected = match signed {
+                    true => (!mask(from_type) | bits) & mask(to_type),
+                    false => bits,
+                };
+                assert_eq!(
+                    evaluate_func(&module, func, &[input]),
+                    vec![expected],
+                    "{} extension from {} to {}",
+                    if signed { "signed" } else { "unsigned" },
+                    from_type,
+                    to_type,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn select_works() {
+    let f64_type = Type::from(FloatType::F64);
+    let (func, module) = module_with_func(
+        &[f64_type, f64_type, IntType::I32.into()],
+        &[f64_type, f64_type],
+        |b| {
+            b.body()?;
+            let x = b.read_var(b.input_var(0).unwrap())?;
+            let y = b.read_var(b.input_var(1).unwrap())?;
+            let flag = b.read_var(b.input_var(2).unwrap())?;
+            // The condition must be either of type `bool` or `i32`.
+            assert!(b.ins()?.select(f64_type, x, x, y).is_err());
+            let less =
+                b.ins()?.fcmp(FloatType::F64, CompareFloatOp::Lt, x, y)?;
+            let neg_x = b.ins()?.fneg(FloatType::F64, x)?;
+            let v0 = b.ins()?.select(f64_type, less, neg_x, y)?;
+            let v1 = b.ins()?.select(f64_type, flag, x, y)?;
+            b.ins()?.return_values([v0, v1].iter().copied())?;
+            Ok(())
+        },
+    );
+    let f64 = |value: f64| Const::from(FloatConst::F64(value.into()));
+    let i32 = |value: i32| Const::from(IntConst::I32(value));
+    let mut ctx = EvaluationContext::new(&module);
+    let mut select = |inputs: &[Const]| {
+        let result = evaluate_func_in_ctx(&mut ctx, func, inputs);
+        bits_into_const(&module, func, result)
+    };
+    assert_eq!(
+        select(&[f64(1.5), f64(2.5), i32(0)]),
+        vec![f64(-1.5), f64(2.5)]
+    );
+    assert_eq!(
+        select(&[f64(2.5), f64(1.5), i32(-1)]),
+        vec![f64(1.5), f64(2.5)]
+    );
+}
+
+#[test]
+fn memory_instructions_are_validated() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let mem = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, None))
+        .unwrap();
+    let (res, _) = builder.code_section().unwrap();
+    let mut b = FunctionBody::build(func, res);
+    b.body().unwrap();
+    let input = b.input_var(0).unwrap();
+    let v0 = b.read_var(input).unwrap();
+    let invalid_mem = Mem::from_raw(RawIdx::from_u32(1));
+    let invalid_memory = || {
+        module::Error::from(module::FunctionBuilderError::InvalidMemory {
+            mem: invalid_mem,
+        })
+    };
+    assert_eq!(
+        b.ins().unwrap().heap_addr(invalid_mem, v0, 4.into()),
+        Err(invalid_memory())
+    );
+    assert_eq!(
+        b.ins().unwrap().memory_size(invalid_mem),
+        Err(invalid_memory())
+    );
+    assert_eq!(
+        b.ins().unwrap().memory_grow(invalid_mem, v0),
+        Err(invalid_memory())
+    );
+    let i32_type = Type::from(IntType::I32);
+    let i64_type = Type::from(IntType::I64);
+    let ptr = b.ins().unwrap().heap_addr(mem, v0, 8.into()).unwrap();
+    assert_eq!(
+        b.ins().unwrap().store(ptr, 0.into(), v0, i64_type),
+        Err(module::Error::from(
+            module::FunctionBuilderError::UnmatchingValueType {
+                value: v0,
+                value_type: i32_type,
+                expected_type: i64_type,
+            }
+        ))
+    );
+    assert!(b.ins().unwrap().load(v0, 0.into(), i32_type).is_err());
+    b.ins().unwrap().store(ptr, 0.into(), v0, i32_type).unwrap();
+    let v1 = b.ins().unwrap().load(ptr, 4.into(), i32_type).unwrap();
+    let v2 = b.ins().unwrap().memory_grow(mem, v1).unwrap();
+    let v3 = b.ins().unwrap().memory_size(mem).unwrap();
+    let v4 = b.ins().unwrap().iadd(IntType::I32, v2, v3).unwrap();
+    b.ins().unwrap().return_values(vec![v4]).unwrap();
+    b.finalize().unwrap();
+}
+
+/// Asserts that the SSA values of all instructions of the function body
+/// satisfy the type constraints of the instruction descriptors.
+///
+/// Returns the names of the checked instructions.
+fn assert_type_constraints(body: &FunctionBody) -> Vec<&'static str> {
+    let satisfies = |instruction: &Instruction, constraint, ty| {
+        match constraint {
+            TypeConstraint::Condition => {
+                ty == Type::Bool || ty == IntType::I32.into()
+            }
+            TypeConstraint::Referenced => true,
+            _ => instruction.constrained_type(constraint) == Some(ty),
+        }
+    };
+    let mut names = Vec::new();
+    for block in body.blocks() {
+        for n in 0..body.block_instrs(block).len() {
+            let (results, instruction) =
+                body.instruction_and_value(block, n).unwrap();
+            let descriptor = instruction.descriptor();
+            let mut operands = Vec::new();
+            instruction.visit_values(|value| {
+                operands.push(value);
+                true
+            });
+            let mut operands = operands.into_iter();
+            let kinds = descriptor.operands().iter();
+            for (kind, &constraint) in kinds.zip(descriptor.operand_types()) {
+                match kind {
+                    OperandKind::Value => {
+                        let value = operands.next().unwrap();
+                        let ty = body.value_type(value).unwrap();
+                        assert!(
+                            satisfies(instruction, constraint, ty),
+                            "{} operand {} of type {}",
+                            descriptor,
+                            value,
+                            ty,
+                        );
+                    }
+                    // The types of all remaining values are given by the
+                    // referenced function or basic blocks.
+                    OperandKind::Values
+                    | OperandKind::Block
+                    | OperandKind::Blocks => break,
+                    _ => (),
+                }
+            }
+            for value in results.iter().copied().flatten() {
+                let ty = body.value_type(value).unwrap();
+                assert!(
+                    satisfies(instruction, descriptor.result_type(), ty),
+                    "{} result {} of type {}",
+                    descriptor,
+                    value,
+                    ty,
+                );
+            }
+            names.push(descriptor.name());
+        }
+    }
+    names
+}
+
+#[test]
+fn built_instructions_satisfy_type_constraints() {
+    let i32_type = Type::from(IntType::I32);
+    let i64_type = Type::from(IntType::I64);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_input(IntType::I64);
+        b.push_input(FloatType::F32);
+        b.push_input(FloatType::F64);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let mem = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, None))
+        .unwrap();
+    let mut global_builder = builder.global_section().unwrap();
+    let global = global_builder
+        .push_global(
+            GlobalVariable::new(i64_type, true),
+            InitExpr::Const(IntConst::I64(0).into()),
+        )
+        .unwrap();
+    let (res, _) = builder.code_section().unwrap();
+    let mut b = FunctionBody::build(func, res);
+    b.body().unwrap();
+    let then_block = b.create_block().unwrap();
+    let else_block = b.create_block().unwrap();
+    let exit_block = b.create_block().unwrap();
+    let inputs = b.input_vars().collect::<Vec<_>>();
+    let x = b.read_var(inputs[0]).unwrap();
+    let y = b.read_var(inputs[1]).unwrap();
+    let f = b.read_var(inputs[2]).unwrap();
+    let d = b.read_var(inputs[3]).unwrap();
+    let one = b.ins().unwrap().constant(IntConst::I32(1)).unwrap();
+    let v0 = b.ins().unwrap().iadd(IntType::I32, x, one).unwrap();
+    let v1 = b.ins().unwrap().ishl(IntType::I64, y, v0).unwrap();
+    let v2 = b.ins().unwrap().iclz(IntType::I64, v1).unwrap();
+    let v3 = b
+        .ins()
+        .unwrap()
+        .iextend(IntType::I32, IntType::I64, v0, true)
+        .unwrap();
+    let v4 = b
+        .ins()
+        .unwrap()
+        .itruncate(IntType::I64, IntType::I32, v2)
+        .unwrap();
+    let v5 = b
+        .ins()
+        .unwrap()
+        .int_to_float(true, IntType::I32, FloatType::F64, v4)
+        .unwrap();
+    let v6 = b.ins().unwrap().fadd(FloatType::F64, v5, d).unwrap();
+    let v7 = b.ins().unwrap().fneg(FloatType::F32, f).unwrap();
+    let v8 = b
+        .ins()
+        .unwrap()
+        .promote(FloatType::F32, FloatType::F64, v7)
+        .unwrap();
+    let v9 = b
+        .ins()
+        .unwrap()
+        .demote(FloatType::F64, FloatType::F32, v6)
+        .unwrap();
+    let v10 = b
+        .ins()
+        .unwrap()
+        .float_to_int(FloatType::F32, IntType::I32, true, v9, true)
+        .unwrap();
+    let v11 = b
+        .ins()
+        .unwrap()
+        .reinterpret(FloatType::F64.into(), i64_type, v8)
+        .unwrap();
+    let v12 = b
+        .ins()
+        .unwrap()
+        .icmp(IntType::I64, CompareIntOp::Slt, v3, v11)
+        .unwrap();
+    let v13 = b
+        .ins()
+        .unwrap()
+        .fcmp(FloatType::F64, CompareFloatOp::Lt, v6, v8)
+        .unwrap();
+    let v14 = b.ins().unwrap().select(i64_type, v12, v3, v11).unwrap();
+    let v15 = b.ins().unwrap().select(i32_type, v10, v0, v4).unwrap();
+    b.ins().unwrap().global_set(global, v14).unwrap();
+    let v16 = b.ins().unwrap().global_get(global).unwrap();
+    let ptr = b.ins().unwrap().heap_addr(mem, v15, 16.into()).unwrap();
+    b.ins().unwrap().store(ptr, 0.into(), v16, i64_type).unwrap();
+    let v17 = b.ins().unwrap().load(ptr, 8.into(), i32_type).unwrap();
+    let v18 = b.ins().unwrap().memory_grow(mem, v17).unwrap();
+    let v19 = b.ins().unwrap().memory_size(mem).unwrap();
+    let instr = b.ins().unwrap().call(func, vec![v19, v16, f, d]).unwrap();
+    let v20 = b.instr_values(instr).unwrap()[0];
+    b.ins().unwrap().if_then_else(v13, then_block, else_block).unwrap();
+    b.switch_to_block(then_block).unwrap();
+    b.seal_block(then_block).unwrap();
+    b.ins().unwrap().br_table(v18, vec![else_block], exit_block).unwrap();
+    b.switch_to_block(else_block).unwrap();
+    b.seal_block(else_block).unwrap();
+    b.ins().unwrap().return_values(vec![v20]).unwrap();
+    b.switch_to_block(exit_block).unwrap();
+    b.seal_block(exit_block).unwrap();
+    b.ins().unwrap().tail_call(func, vec![x, y, f, d]).unwrap();
+    let body = b.finalize().unwrap();
+    let names = assert_type_constraints(&body);
+    assert_eq!(names.len(), 29);
+    // The builder rejects operands that violate the type constraints.
+    let (res, _) = builder.code_section().unwrap();
+    let mut b = FunctionBody::build(func, res);
+    b.body().unwrap();
+    let x = b.read_var(b.input_var(0).unwrap()).unwrap();
+    let y = b.read_var(b.input_var(1).unwrap()).unwrap();
+    let unmatching = |value, value_type, expected_type| {
+        Some(module::Error::from(
+            module::FunctionBuilderError::UnmatchingValueType {
+                value,
+                value_type,
+                expected_type,
+            },
+        ))
+    };
+    // The type of the instruction.
+    assert_eq!(
+        b.ins().unwrap().iadd(IntType::I32, x, y).err(),
+        unmatching(y, i64_type, i32_type)
+    );
+    // The source type of a conversion.
+    assert_eq!(
+        b.ins()
+            .unwrap()
+            .iextend(IntType::I32, IntType::I64, y, false)
+            .err(),
+        unmatching(y, i64_type, i32_type)
+    );
+    // Exactly `i32` shift amounts.
+    assert_eq!(
+        b.ins().unwrap().ishl(IntType::I64, y, y).err(),
+        unmatching(y, i64_type, i32_type)
+    );
+    // Exactly `ptr` addresses.
+    assert_eq!(
+        b.ins().unwrap().load(x, 0.into(), i32_type).err(),
+        unmatching(x, i32_type, Type::Ptr)
+    );
+    // Either `bool` or `i32` conditions.
+    assert_eq!(
+        b.ins().unwrap().select(i32_type, y, x, x).err(),
+        unmatching(y, i64_type, Type::Bool)
+    );
+    assert!(b.ins().unwrap().select(i32_type, x, x, x).is_ok());
+    // Exactly `bool` conditions of branches.
+    let block = b.create_block().unwrap();
+    assert_eq!(
+        b.ins().unwrap().if_then_else(x, block, block).err(),
+        unmatching(x, i32_type, Type::Bool)
+    );
+}
+
 #[test]
 fn multi_value_div_rem_works() -> Result<(), module::Error> {
     // Setup module.
@@ -662,3 +1665,2992 @@ fn multi_value_div_rem_works() -> Result<(), module::Error> {
 
     Ok(())
 }
+
+#[test]
+fn return_values_require_matching_outputs() {
+    let i32_type = Type::from(IntType::I32);
+    let outputs = [i32_type, i32_type];
+    let (func, module) = module_with_func(&[i32_type], &outputs, |b| {
+        let input = b.input_var(0).unwrap();
+        let v0 = b.read_var(input)?;
+        let v1 = b.ins()?.constant(IntConst::I64(1))?;
+        let i64_type = Type::from(IntType::I64);
+        let mismatches = [
+            (vec![v0], vec![i32_type]),
+            (vec![v0, v0, v0], vec![i32_type, i32_type, i32_type]),
+            (vec![v0, v1], vec![i32_type, i64_type]),
+        ];
+        for (returned, returned_types) in &mismatches {
+            assert_eq!(
+                b.ins()?.return_values(returned.iter().copied()),
+                Err(module::Error::from(
+                    module::FunctionBuilderError::UnmatchingFunctionReturnType {
+                        returned_types: returned_types.clone(),
+                        expected_types: outputs.to_vec(),
+                    }
+                ))
+            );
+        }
+        // Failed returns do not terminate the basic block.
+        b.ins()?.return_values([v0, v0].iter().copied())?;
+        Ok(())
+    });
+    let results = evaluate_func(&module, func, &[IntConst::I32(42).into()]);
+    assert_eq!(results, vec![42, 42]);
+}
+
+#[test]
+fn concurrent_evaluation_works() {
+    use std::{sync::Arc, thread};
+    let (func, module) =
+        module_with_func(&[IntType::I32.into()], &[IntType::I32.into()], |b| {
+            b.body()?;
+            let input = Variable::from_raw(RawIdx::from_u32(0));
+            let v0 = b.read_var(input)?;
+            let v1 = b.ins()?.iadd(IntType::I32, v0, v0)?;
+            b.ins()?.return_values([v1].iter().copied())?;
+            Ok(())
+        });
+    let module = Arc::new(module);
+    let handles = (0..4)
+        .map(|n| {
+            let module = Arc::clone(&module);
+            thread::spawn(move || {
+                let mut ctx = EvaluationContext::new(&module);
+                for x in (n * 100)..(n * 100 + 100) {
+                    let input = IntConst::I32(x).into();
+                    let result = evaluate_func_in_ctx(&mut ctx, func, &[input]);
+                    let result = bits_into_const(&module, func, result);
+                    assert_eq!(result, vec![IntConst::I32(x * 2).into()]);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn variable_metadata_is_kept() {
+    let (func, module) =
+        module_with_func(&[IntType::I32.into()], &[IntType::I32.into()], |b| {
+            b.declare_variables(1, IntType::I64.into())?;
+            b.body()?;
+            let input = b.input_var(0).unwrap();
+            let local = Variable::from_raw(RawIdx::from_u32(1));
+            let v0 = b.read_var(input)?;
+            let v1 = b.ins()?.iextend(IntType::I32, IntType::I64, v0, true)?;
+            b.write_var(local, v1)?;
+            b.ins()?.return_values([v0].iter().copied())?;
+            Ok(())
+        });
+    let function = module.get_function(func).unwrap();
+    let entry = function.body().entry_block();
+    let input = Variable::from_raw(RawIdx::from_u32(0));
+    let local = Variable::from_raw(RawIdx::from_u32(1));
+    assert_eq!(function.vars().collect::<Vec<_>>(), vec![input, local]);
+    assert_eq!(function.var_type(input), Some(IntType::I32.into()));
+    assert_eq!(function.var_type(local), Some(IntType::I64.into()));
+    assert_eq!(
+        function.var_definition(input, entry),
+        Some(Value::from_raw(RawIdx::from_u32(0)))
+    );
+    // The value written to `local` is never used and therefore eliminated.
+    assert_eq!(function.var_definition(local, entry), None);
+}
+
+#[test]
+fn instruction_stats_work() {
+    let (func, module) = module_with_func(&[], &[IntType::I32.into()], |b| {
+        b.body()?;
+        let v1 = b.ins()?.constant(IntConst::I32(1))?;
+        let v2 = b.ins()?.constant(IntConst::I32(2))?;
+        let v3 = b.ins()?.iadd(IntType::I32, v1, v2)?;
+        let v3 = b.ins()?.imul(IntType::I32, v3, v3)?;
+        b.ins()?.return_values([v3].iter().copied())?;
+        Ok(())
+    });
+    let stats = module.stats();
+    let func_stats = stats.get(func).unwrap();
+    assert_eq!(func_stats, stats.total());
+    assert_eq!(func_stats.len_blocks(), 1);
+    assert_eq!(func_stats.len_instrs(), 5);
+    assert_eq!(func_stats.len_block_params(), 0);
+    assert_eq!(func_stats.len_operands(), 5);
+    assert_eq!(func_stats.len_kind("const"), 2);
+    assert_eq!(func_stats.len_kind("iadd"), 1);
+    assert_eq!(func_stats.len_kind("imul"), 1);
+    assert_eq!(func_stats.len_kind("return"), 1);
+    assert!(func_stats.memory_usage() > 0);
+}
+
+#[test]
+fn module_policy_works() {
+    let i32_type = || -> Type { IntType::I32.into() };
+    let func_type = |inputs: &[Type], outputs: &[Type]| {
+        let mut b = FunctionType::build();
+        for input in inputs {
+            b.push_input(*input);
+        }
+        for output in outputs {
+            b.push_output(*output);
+        }
+        b.finalize()
+    };
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let call_type = type_builder.push_type(func_type(
+        &[i32_type(), i32_type()],
+        &[i32_type(), i32_type()],
+    ));
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(call_type).unwrap();
+    let extra = function_builder.push_function(call_type).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let memory = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, 100))
+        .unwrap();
+    let mut export_builder = builder.export_section().unwrap();
+    export_builder.export_function(func, "call").unwrap();
+    export_builder.export_function(extra, "extra").unwrap();
+    builder.set_start_func(func).unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    for &func in &[func, extra] {
+        let mut func_builder = FunctionBody::build(func, res);
+        func_builder.body().unwrap();
+        let inputs = func_builder.input_vars().collect::<Vec<_>>();
+        let v0 = func_builder.read_var(inputs[0]).unwrap();
+        let v1 = func_builder.read_var(inputs[1]).unwrap();
+        func_builder
+            .ins()
+            .unwrap()
+            .return_values([v0, v1].iter().copied())
+            .unwrap();
+        let func_body = func_builder.finalize().unwrap();
+        body_builder.push_body(func, func_body).unwrap();
+    }
+    let module = builder.finalize().unwrap();
+
+    // The default policy accepts all modules.
+    assert_eq!(ModulePolicy::default().check(&module), Ok(()));
+
+    let mut policy = ModulePolicy::default();
+    policy.require_export(
+        "call",
+        func_type(&[i32_type(), i32_type()], &[i32_type(), i32_type()]),
+    );
+    policy.set_max_memory_pages(100);
+    assert_eq!(policy.check(&module), Ok(()));
+
+    let mut policy = ModulePolicy::default();
+    policy.set_start_func(StartFuncPolicy::Forbid);
+    policy.require_export("call", func_type(&[i32_type()], &[i32_type()]));
+    policy.require_export("missing", func_type(&[], &[]));
+    policy.forbid_other_exports();
+    policy.set_max_memory_pages(16);
+    let violations = policy.check(&module).unwrap_err();
+    assert_eq!(
+        violations,
+        vec![
+            PolicyViolation::ForbiddenStartFunc { func },
+            PolicyViolation::UnmatchingExportSignature {
+                name: "call".to_string(),
+                expected: func_type(&[i32_type()], &[i32_type()]),
+                found: func_type(
+                    &[i32_type(), i32_type()],
+                    &[i32_type(), i32_type()]
+                ),
+            },
+            PolicyViolation::MissingExport {
+                name: "missing".to_string(),
+            },
+            PolicyViolation::ForbiddenExport {
+                name: "extra".to_string(),
+            },
+            PolicyViolation::MemoryLimitExceeded {
+                memory,
+                pages: 100,
+                limit: 16,
+            },
+        ]
+    );
+
+    // The policy limits can be enforced at runtime as well.
+    assert_eq!(policy.resource_limits().max_memory_pages(), 16);
+    let mut limits = ResourceLimits::new();
+    limits.set_max_memory_pages(0);
+    policy.set_resource_limits(limits);
+    let mut config = ExecutionConfig::new();
+    config.set_resource_limits(*policy.resource_limits());
+    assert_eq!(
+        EvaluationContext::with_config(&module, &VecMemoryCreator, config)
+            .err(),
+        Some(InterpretationError::MemoryLimitExceeded {
+            memory,
+            pages: 1,
+            limit: 0,
+        })
+    );
+}
+
+#[test]
+fn switch_lowering_works() {
+    let sparse_cases = [(3, 0), (7, 1), (100, 2), (1000, 3), (7, 4), (5, 5)];
+    let dense_cases = [(2, 0), (3, 1), (5, 2), (6, 3), (7, 4)];
+    let lowerings = [
+        None,
+        Some(SwitchLowering::JumpTable),
+        Some(SwitchLowering::SparseTable),
+        Some(SwitchLowering::BinarySearch),
+        Some(SwitchLowering::IfChain),
+    ];
+    let inputs = (0..=10).chain([99, 100, 101, 1000, u32::MAX].iter().copied());
+    for cases in [&sparse_cases[..], &dense_cases[..]] {
+        for lowering in lowerings.iter().copied() {
+            let (func, module) = module_with_func(
+                &[IntType::I32.into()],
+                &[IntType::I32.into()],
+                |b| {
+                    b.body()?;
+                    let targets = (0..cases.len())
+                        .map(|_| b.create_block())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let default = b.create_block()?;
+                    let input = b.input_var(0).unwrap();
+                    let case = b.read_var(input)?;
+                    let cases = cases
+                        .iter()
+                        .map(|(key, n)| (*key, targets[*n]))
+                        .collect::<Vec<_>>();
+                    match lowering {
+                        Some(lowering) => {
+                            b.switch_with(lowering, case, default, &cases)?
+                        }
+                        None => b.switch(case, default, &cases)?,
+                    }
+                    for (n, target) in targets.iter().copied().enumerate() {
+                        b.switch_to_block(target)?;
+                        b.seal_block(target)?;
+                        let v0 = b.ins()?.constant(IntConst::I32(n as i32))?;
+                        b.ins()?.return_values([v0].iter().copied())?;
+                    }
+                    b.switch_to_block(default)?;
+                    b.seal_block(default)?;
+                    let v0 = b.ins()?.constant(IntConst::I32(-1))?;
+                    b.ins()?.return_values([v0].iter().copied())?;
+                    Ok(())
+                },
+            );
+            let mut ctx = EvaluationContext::new(&module);
+            for input in inputs.clone() {
+                // The first case with a matching key wins.
+                let expected = cases
+                    .iter()
+                    .find(|(key, _)| *key == input)
+                    .map(|(_, n)| *n as i32)
+                    .unwrap_or(-1);
+                let result = evaluate_func_in_ctx(
+                    &mut ctx,
+                    func,
+                    &[IntConst::I32(input as i32).into()],
+                );
+                let result = bits_into_const(&module, func, result);
+                assert_eq!(
+                    result,
+                    vec![IntConst::I32(expected).into()],
+                    "mismatch for input {} with lowering {:?}",
+                    input,
+                    lowering,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn br_table_works() {
+    let (func, module) =
+        module_with_func(&[IntType::I32.into()], &[IntType::I32.into()], |b| {
+            b.body()?;
+            let first = b.create_block()?;
+            let second = b.create_block()?;
+            let default = b.create_block()?;
+            let input = b.input_var(0).unwrap();
+            let case = b.read_var(input)?;
+            // Targets may appear multiple times in the branching table.
+            let targets = vec![first, second, first];
+            b.ins()?.br_table(case, targets, default)?;
+            // The branching table fills the current block.
+            assert!(b.ins().is_err());
+            let blocks = [first, second, default];
+            for (n, block) in blocks.iter().copied().enumerate() {
+                b.switch_to_block(block)?;
+                b.seal_block(block)?;
+                let v0 = b.ins()?.constant(IntConst::I32(n as i32))?;
+                b.ins()?.return_values([v0].iter().copied())?;
+            }
+            Ok(())
+        });
+    let mut ctx = EvaluationContext::new(&module);
+    let expected = [(0, 0), (1, 1), (2, 0), (3, 2), (u32::MAX, 2)];
+    for (input, expected) in expected.iter().copied() {
+        let result = evaluate_func_in_ctx(
+            &mut ctx,
+            func,
+            &[IntConst::I32(input as i32).into()],
+        );
+        let result = bits_into_const(&module, func, result);
+        assert_eq!(result, vec![IntConst::I32(expected).into()]);
+    }
+}
+
+#[test]
+fn invoke_exported_function_works() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_input(IntType::I64);
+        b.push_output(IntType::I64);
+        b.push_output(FloatType::F32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut export_builder = builder.export_section().unwrap();
+    export_builder.export_function(func, "extend_add").unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let body = (|| -> Result<FunctionBody, module::Error> {
+        let mut b = FunctionBody::build(func, res);
+        b.body()?;
+        let lhs = b.read_var(b.input_var(0).unwrap())?;
+        let rhs = b.read_var(b.input_var(1).unwrap())?;
+        let lhs = b.ins()?.iextend(IntType::I32, IntType::I64, lhs, true)?;
+        let sum = b.ins()?.iadd(IntType::I64, lhs, rhs)?;
+        let half = b.ins()?.constant(FloatConst::F32(0.5_f32.into()))?;
+        b.ins()?.return_values([sum, half].iter().copied())?;
+        b.finalize()
+    })()
+    .unwrap();
+    body_builder.push_body(func, body).unwrap();
+    let module = builder.finalize().unwrap();
+
+    let mut ctx = EvaluationContext::new(&module);
+    assert_eq!(
+        ctx.invoke(
+            "extend_add",
+            &[IntConst::I32(-1).into(), IntConst::I64(10).into()]
+        ),
+        Ok(vec![
+            IntConst::I64(9).into(),
+            FloatConst::F32(0.5_f32.into()).into()
+        ]),
+    );
+    assert_eq!(
+        ctx.invoke("missing", &[]),
+        Err(InterpretationError::UnknownExportedFunction {
+            name: "missing".to_string()
+        }),
+    );
+    assert_eq!(
+        ctx.invoke("extend_add", &[IntConst::I32(1).into()]),
+        Err(InterpretationError::UnmatchingArgumentTypes {
+            given_types: vec![IntType::I32.into()],
+            expected_types: vec![IntType::I32.into(), IntType::I64.into()],
+        }),
+    );
+}
+
+#[test]
+fn cfg_editing_works() {
+    let input = Variable::from_raw(RawIdx::from_u32(0));
+    let x = Variable::from_raw(RawIdx::from_u32(1));
+    let (func, module) = module_with_edited_func(
+        &[IntType::I32.into()],
+        &[IntType::I32.into()],
+        |b| {
+            b.declare_variables(1, IntType::I32.into())?;
+            b.body()?;
+            let then_block = b.create_block()?;
+            let exit_block = b.create_block()?;
+            let v1 = b.ins()?.constant(IntConst::I32(10))?;
+            b.write_var(x, v1)?;
+            let v2 = b.read_var(input)?;
+            let v3 = b.ins()?.constant(IntConst::I32(0))?;
+            let v4 = b.ins()?.icmp(IntType::I32, CompareIntOp::Ne, v2, v3)?;
+            b.ins()?.if_then_else(v4, then_block, exit_block)?;
+            b.switch_to_block(then_block)?;
+            b.seal_block(then_block)?;
+            let v5 = b.ins()?.constant(IntConst::I32(20))?;
+            b.write_var(x, v5)?;
+            b.ins()?.br(exit_block)?;
+            b.switch_to_block(exit_block)?;
+            b.seal_block(exit_block)?;
+            let v6 = b.read_var(x)?;
+            b.ins()?.return_values([v6].iter().copied())?;
+            Ok(())
+        },
+        |body| {
+            let entry = body.entry_block();
+            let then_block = body.successors(entry)[0];
+            let exit_block = body.successors(entry)[1];
+            // Split off the branch of the then block into its own block.
+            let br = *body.block_instrs(then_block).last().unwrap();
+            let mut editor = body.edit();
+            let forward_block = editor.split_block(br)?;
+            assert_eq!(
+                editor.body().predecessors(exit_block),
+                vec![entry, forward_block]
+            );
+            assert!(editor.remove_block(entry).is_err());
+            assert!(editor.remove_block(forward_block).is_err());
+            // Bypass the forwarding block and remove it afterwards.
+            editor.redirect_edge(then_block, forward_block, exit_block)?;
+            assert!(editor
+                .redirect_edge(then_block, forward_block, exit_block)
+                .is_err());
+            editor.remove_block(forward_block)?;
+            assert!(!body.contains_block(forward_block));
+            assert_eq!(body.predecessors(exit_block), vec![entry, then_block]);
+            Ok(())
+        },
+    );
+    filecheck(
+        &module.to_string(),
+        "
+        // CHECK: if v3 then bb1 else bb2(v1)
+        // CHECK: block bb1 {
+        // CHECK: const<i32> 20
+        // CHECK-NEXT: br bb2(v4)
+        // CHECK-NEXT: }
+        // CHECK-NEXT: block bb2(v5: i32) {
+        // CHECK-NEXT: return v5
+        // CHECK-NOT: bb3
+        ",
+    )
+    .unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    for (value, expected) in [(0, 10), (1, 20), (-5, 20)].iter().copied() {
+        let inputs = [IntConst::I32(value).into()];
+        let result = evaluate_func_in_ctx(&mut ctx, func, &inputs);
+        assert_eq!(result, vec![expected as u64]);
+    }
+}
+
+#[test]
+fn instr_editing_works() {
+    let input = Variable::from_raw(RawIdx::from_u32(0));
+    let x = Variable::from_raw(RawIdx::from_u32(1));
+    let (func, module) = module_with_edited_func(
+        &[IntType::I32.into()],
+        &[IntType::I32.into()],
+        |b| {
+            b.declare_variables(1, IntType::I32.into())?;
+            b.body()?;
+            let then_block = b.create_block()?;
+            let exit_block = b.create_block()?;
+            let v1 = b.ins()?.constant(IntConst::I32(10))?;
+            b.write_var(x, v1)?;
+            b.ins()?.constant(IntConst::I32(99))?;
+            let v2 = b.read_var(input)?;
+            let v3 = b.ins()?.constant(IntConst::I32(0))?;
+            let v4 = b.ins()?.icmp(IntType::I32, CompareIntOp::Ne, v2, v3)?;
+            b.ins()?.if_then_else(v4, then_block, exit_block)?;
+            b.switch_to_block(then_block)?;
+            b.seal_block(then_block)?;
+            let v5 = b.ins()?.constant(IntConst::I32(20))?;
+            b.write_var(x, v5)?;
+            b.ins()?.br(exit_block)?;
+            b.switch_to_block(exit_block)?;
+            b.seal_block(exit_block)?;
+            let v6 = b.read_var(x)?;
+            b.ins()?.return_values([v6].iter().copied())?;
+            Ok(())
+        },
+        |body| {
+            let entry = body.entry_block();
+            let then_block = body.successors(entry)[0];
+            let exit_block = body.successors(entry)[1];
+            let unused = body.block_instrs(entry)[1];
+            let zero = body.block_instrs(entry)[2];
+            let then_const = body.block_instrs(then_block)[0];
+            let then_br = body.block_instrs(then_block)[1];
+            let mut editor = body.edit();
+            // Replace the constant of the then block in place.
+            let c = ConstInstr::new(IntConst::I32(30).into());
+            editor.replace_instr(then_const, c.into())?;
+            let br = BranchInstr::new(exit_block);
+            let error = editor
+                .replace_instr(then_const, br.clone().into())
+                .unwrap_err();
+            assert_eq!(
+                error.to_diagnostic().code(),
+                "editor.incompatible_replacement"
+            );
+            let error = editor.replace_instr(then_br, br.into()).unwrap_err();
+            assert_eq!(
+                error.to_diagnostic().code(),
+                "editor.mismatching_block_args"
+            );
+            // Only unused non-terminal instructions can be removed.
+            let error = editor.remove_instr(zero).unwrap_err();
+            assert_eq!(
+                error.to_diagnostic().code(),
+                "editor.remove_used_instr"
+            );
+            let error = editor.remove_instr(then_br).unwrap_err();
+            assert_eq!(error.to_diagnostic().code(), "editor.remove_terminal");
+            editor.remove_instr(unused)?;
+            assert!(editor.remove_instr(unused).is_err());
+            // Insert a basic block on the edge from the entry to the exit.
+            let new_block = editor.insert_block_between(entry, exit_block)?;
+            assert_eq!(
+                editor.body().predecessors(exit_block),
+                vec![then_block, new_block]
+            );
+            assert_eq!(editor.body().predecessors(new_block), vec![entry]);
+            assert!(editor.insert_block_between(then_block, entry).is_err());
+            Ok(())
+        },
+    );
+    filecheck(
+        &module.to_string(),
+        "
+        // CHECK: block {
+        // CHECK-NOT: const<i32> 99
+        // CHECK: if v3 then bb1 else bb3
+        // CHECK: block bb1 {
+        // CHECK-NEXT: const<i32> 30
+        // CHECK-NEXT: br bb2(v4)
+        // CHECK: block bb2(v5: i32) {
+        // CHECK: block bb3 {
+        // CHECK-NEXT: br bb2(v1)
+        ",
+    )
+    .unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    for (value, expected) in [(0, 10), (1, 30), (-5, 30)].iter().copied() {
+        let inputs = [IntConst::I32(value).into()];
+        let result = evaluate_func_in_ctx(&mut ctx, func, &inputs);
+        assert_eq!(result, vec![expected as u64]);
+    }
+}
+
+#[test]
+fn cursor_inserts_instructions() {
+    let (func, module) = module_with_edited_func(
+        &[IntType::I32.into()],
+        &[IntType::I32.into()],
+        |b| {
+            b.body()?;
+            let input = b.input_var(0).unwrap();
+            let v0 = b.read_var(input)?;
+            let v1 = b.ins()?.constant(IntConst::I32(1))?;
+            let v2 = b.ins()?.iadd(IntType::I32, v0, v1)?;
+            b.ins()?.return_values([v2].iter().copied())?;
+            Ok(())
+        },
+        |body| {
+            let entry = body.entry_block();
+            let iadd = body.block_instrs(entry)[1];
+            let ret = body.block_instrs(entry)[2];
+            let mut cursor = body.cursor();
+            assert_eq!(cursor.current_block(), entry);
+            // Insert two instructions before the addition in order.
+            cursor.goto_before(iadd)?;
+            let c = ConstInstr::new(IntConst::I32(10).into());
+            let v3 = cursor.insert_value(c.into(), IntType::I32.into())?;
+            let v0 = Value::from_raw(RawIdx::from_u32(0));
+            let mul =
+                BinaryIntInstr::new(BinaryIntOp::Mul, IntType::I32, v0, v3);
+            let (_, outputs) =
+                cursor.insert(mul.into(), &[IntType::I32.into()])?;
+            assert_eq!(cursor.next_instr(), Some(iadd));
+            assert_eq!(cursor.current_instr(), Some(ret));
+            // Insert after an existing instruction.
+            cursor.goto_after(iadd)?;
+            let sub = BinaryIntInstr::new(
+                BinaryIntOp::Sub,
+                IntType::I32,
+                outputs[0],
+                v3,
+            );
+            cursor.insert(sub.into(), &[IntType::I32.into()])?;
+            assert_eq!(cursor.current_instr(), Some(ret));
+            // Terminals and positions after the terminal are rejected.
+            let br = BranchInstr::new(entry);
+            assert!(cursor.insert(br.into(), &[]).is_err());
+            cursor.goto_after(ret)?;
+            assert_eq!(cursor.current_instr(), None);
+            let c = ConstInstr::new(IntConst::I32(0).into());
+            let error = cursor.insert(c.into(), &[]).unwrap_err();
+            assert_eq!(
+                error.to_diagnostic().code(),
+                "editor.insert_after_terminal"
+            );
+            // Instructions must only use existing values.
+            cursor.goto_top(entry)?;
+            let invalid = Value::from_raw(RawIdx::from_u32(100));
+            let add = BinaryIntInstr::new(
+                BinaryIntOp::Add,
+                IntType::I32,
+                invalid,
+                invalid,
+            );
+            let error = cursor.insert(add.into(), &[]).unwrap_err();
+            assert_eq!(error.to_diagnostic().code(), "editor.invalid_value");
+            Ok(())
+        },
+    );
+    filecheck(
+        &module.to_string(),
+        "
+        // CHECK: let v1: i32 = const<i32> 1
+        // CHECK-NEXT: let v3: i32 = const<i32> 10
+        // CHECK-NEXT: let v4: i32 = imul<i32> v0 v3
+        // CHECK-NEXT: let v2: i32 = iadd<i32> v0 v1
+        // CHECK-NEXT: let v5: i32 = isub<i32> v4 v3
+        // CHECK-NEXT: return v2
+        ",
+    )
+    .unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    for value in [0, 1, 41].iter().copied() {
+        let inputs = [IntConst::I32(value).into()];
+        let result = evaluate_func_in_ctx(&mut ctx, func, &inputs);
+        assert_eq!(result, vec![value as u64 + 1]);
+    }
+}
+
+#[test]
+fn dominator_trees_work() {
+    let (func, module) = module_with_func(
+        &[IntType::I32.into()],
+        &[IntType::I32.into()],
+        |b| {
+            b.body()?;
+            let input = b.input_var(0).unwrap();
+            let entry = b.current_block()?;
+            let then_block = b.create_block()?;
+            let else_block = b.create_block()?;
+            let exit_block = b.create_block()?;
+            let v0 = b.read_var(input)?;
+            let v1 = b.ins()?.constant(IntConst::I32(0))?;
+            let v2 = b.ins()?.icmp(IntType::I32, CompareIntOp::Ne, v0, v1)?;
+            b.ins()?.if_then_else(v2, then_block, else_block)?;
+            b.seal_block(then_block)?;
+            b.seal_block(else_block)?;
+            b.switch_to_block(then_block)?;
+            b.ins()?.br(exit_block)?;
+            // The branch from the else block has not yet been constructed.
+            let tree = b.dominator_tree()?;
+            assert_eq!(tree.idom(exit_block), Some(then_block));
+            assert_eq!(tree.idom(else_block), Some(entry));
+            b.switch_to_block(else_block)?;
+            b.ins()?.br(exit_block)?;
+            b.seal_block(exit_block)?;
+            let tree = b.dominator_tree()?;
+            assert_eq!(tree.idom(exit_block), Some(entry));
+            assert_eq!(tree.idom(else_block), Some(entry));
+            assert!(!tree.dominates(then_block, exit_block));
+            b.switch_to_block(exit_block)?;
+            b.ins()?.return_values([v0].iter().copied())?;
+            Ok(())
+        },
+    );
+    let body = module.get_function(func).unwrap().body();
+    let tree = body.dominator_tree();
+    let entry = body.entry_block();
+    assert_eq!(tree.reverse_postorder().len(), 4);
+    assert_eq!(tree.reverse_postorder()[0], entry);
+    for &block in &tree.reverse_postorder()[1..] {
+        assert_eq!(tree.idom(block), Some(entry));
+        assert!(tree.dominates(entry, block));
+    }
+}
+
+#[test]
+fn verifier_reports_violations() {
+    let (func, module) = module_with_unverified_func(
+        &[IntType::I32.into()],
+        &[IntType::I32.into()],
+        |b| {
+            b.body()?;
+            let input = b.input_var(0).unwrap();
+            let v0 = b.read_var(input)?;
+            let v1 = b.ins()?.constant(IntConst::I32(1))?;
+            let v2 = b.ins()?.iadd(IntType::I32, v0, v1)?;
+            b.ins()?.return_values([v2].iter().copied())?;
+            Ok(())
+        },
+        |body| {
+            let entry = body.entry_block();
+            let constant = body.block_instrs(entry)[0];
+            let iadd = body.block_instrs(entry)[1];
+            let v0 = Value::from_raw(RawIdx::from_u32(0));
+            let v1 = Value::from_raw(RawIdx::from_u32(1));
+            let v2 = Value::from_raw(RawIdx::from_u32(2));
+            // Use `v2` before its definition.
+            let mut cursor = body.cursor();
+            cursor.goto_before(constant)?;
+            let mul =
+                BinaryIntInstr::new(BinaryIntOp::Mul, IntType::I32, v0, v2);
+            cursor.insert_value(mul.into(), IntType::I32.into())?;
+            // Add the `i32` operands as if they were of type `i64`.
+            let add =
+                BinaryIntInstr::new(BinaryIntOp::Add, IntType::I64, v0, v1);
+            body.edit().replace_instr(iadd, add.into())?;
+            Ok(())
+        },
+    );
+    let violations = module.verify().unwrap_err();
+    assert_eq!(violations.len(), 3);
+    assert!(violations.iter().all(|(f, _)| *f == func));
+    let codes = violations
+        .iter()
+        .map(|(_, violation)| violation.to_diagnostic().code())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        codes,
+        vec![
+            "verifier.non_dominating_use",
+            "verifier.unmatching_operand_type",
+            "verifier.unmatching_operand_type",
+        ]
+    );
+    assert!(matches!(
+        violations[0].1,
+        VerifierViolation::NonDominatingUse { value, .. }
+            if value == Value::from_raw(RawIdx::from_u32(2))
+    ));
+    assert!(matches!(
+        violations[1].1,
+        VerifierViolation::UnmatchingOperandType { expected, found, .. }
+            if expected == IntType::I64.into() && found == IntType::I32.into()
+    ));
+}
+
+#[test]
+fn finalize_reports_verifier_violations() {
+    let i32_type = Type::from(IntType::I32);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_output(i32_type);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let (res, _) = builder.code_section().unwrap();
+    let mut b = FunctionBody::build(func, res);
+    b.body().unwrap();
+    let then_block = b.create_block().unwrap();
+    let else_block = b.create_block().unwrap();
+    let v0 = b.ins().unwrap().constant(Const::Bool(true)).unwrap();
+    b.ins()
+        .unwrap()
+        .if_then_else(v0, then_block, else_block)
+        .unwrap();
+    b.seal_block(then_block).unwrap();
+    b.seal_block(else_block).unwrap();
+    b.switch_to_block(then_block).unwrap();
+    let v1 = b.ins().unwrap().constant(IntConst::I32(1)).unwrap();
+    b.ins().unwrap().return_values(Some(v1)).unwrap();
+    // Uses `v1` that is only defined within the other branch.
+    b.switch_to_block(else_block).unwrap();
+    b.ins().unwrap().return_values(Some(v1)).unwrap();
+    match b.finalize().unwrap_err().kind() {
+        module::ErrorKind::FunctionBuilder(
+            module::FunctionBuilderError::InvalidFunctionBody {
+                func: invalid,
+                violations,
+            },
+        ) => {
+            assert_eq!(*invalid, func);
+            assert!(matches!(
+                violations[..],
+                [VerifierViolation::NonDominatingUse { value, .. }]
+                    if value == v1
+            ));
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+}
+
+#[test]
+fn edge_profiling_works() {
+    let mut blocks = Vec::new();
+    let (func, module) = module_with_func(
+        &[IntType::I32.into()],
+        &[IntType::I32.into()],
+        |b| {
+            b.body()?;
+            let table_block = b.create_block()?;
+            let other_block = b.create_block()?;
+            let targets = [b.create_block()?, b.create_block()?];
+            let default = b.create_block()?;
+            let input = b.input_var(0).unwrap();
+            let v0 = b.read_var(input)?;
+            let v1 = b.ins()?.constant(IntConst::I32(2))?;
+            let v2 = b.ins()?.icmp(IntType::I32, CompareIntOp::Slt, v0, v1)?;
+            b.ins()?.if_then_else(v2, table_block, other_block)?;
+            b.switch_to_block(table_block)?;
+            b.seal_block(table_block)?;
+            let cases = [(0, targets[0]), (1, targets[1])];
+            b.switch_with(SwitchLowering::SparseTable, v0, default, &cases)?;
+            for (n, block) in [other_block, targets[0], targets[1], default]
+                .iter()
+                .copied()
+                .enumerate()
+            {
+                b.switch_to_block(block)?;
+                b.seal_block(block)?;
+                let v3 = b.ins()?.constant(IntConst::I32(n as i32))?;
+                b.ins()?.return_values([v3].iter().copied())?;
+            }
+            blocks.extend(
+                [table_block, other_block, targets[0], targets[1]]
+                    .iter()
+                    .copied(),
+            );
+            Ok(())
+        },
+    );
+    let (table_block, other_block, case0, case1) =
+        (blocks[0], blocks[1], blocks[2], blocks[3]);
+    let entry = module.get_function(func).unwrap().body().entry_block();
+    let mut ctx = EvaluationContext::new(&module);
+    evaluate_func_in_ctx(&mut ctx, func, &[IntConst::I32(0).into()]);
+    assert!(ctx.profile().is_none());
+    ctx.enable_profiling();
+    for input in [-1, 0, 1, 1, 5, 7].iter().copied() {
+        evaluate_func_in_ctx(&mut ctx, func, &[IntConst::I32(input).into()]);
+    }
+    let profile = ctx.take_profile().unwrap();
+    assert!(ctx.profile().is_none());
+    let branch = profile.branch(func, entry).unwrap();
+    assert_eq!((branch.taken(), branch.not_taken()), (4, 2));
+    assert_eq!(profile.edge_count(func, entry, table_block), 4);
+    assert_eq!(profile.edge_count(func, entry, other_block), 2);
+    assert_eq!(profile.edge_count(func, table_block, case1), 2);
+    let table = profile.branch_table(func, table_block).unwrap();
+    assert_eq!(table.cases().collect::<Vec<_>>(), vec![(0, 1), (1, 2)]);
+    assert_eq!(table.case_count(0), 1);
+    assert_eq!(table.default_count(), 1);
+    assert_eq!(table.total(), 4);
+    assert!(profile.branch(func, case0).is_none());
+    let mut merged = profile.clone();
+    merged.merge(&profile);
+    assert_eq!(merged.edge_count(func, entry, table_block), 8);
+    assert_eq!(merged.branch(func, entry).unwrap().total(), 12);
+}
+
+#[test]
+fn coverage_works() {
+    let mut blocks = Vec::new();
+    let (func, module) = module_with_func(
+        &[IntType::I32.into()],
+        &[IntType::I32.into()],
+        |b| {
+            b.body()?;
+            let then_block = b.create_block()?;
+            let else_block = b.create_block()?;
+            let input = b.input_var(0).unwrap();
+            let v0 = b.read_var(input)?;
+            let v1 = b.ins()?.constant(IntConst::I32(0))?;
+            let v2 = b.ins()?.icmp(IntType::I32, CompareIntOp::Ne, v0, v1)?;
+            b.ins()?.if_then_else(v2, then_block, else_block)?;
+            for (n, block) in
+                [then_block, else_block].iter().copied().enumerate()
+            {
+                b.switch_to_block(block)?;
+                b.seal_block(block)?;
+                let v3 = b.ins()?.constant(IntConst::I32(n as i32))?;
+                b.ins()?.return_values([v3].iter().copied())?;
+            }
+            blocks.extend([then_block, else_block].iter().copied());
+            Ok(())
+        },
+    );
+    let (then_block, else_block) = (blocks[0], blocks[1]);
+    let entry = module.get_function(func).unwrap().body().entry_block();
+    let mut ctx = EvaluationContext::new(&module);
+    assert!(ctx.coverage().is_none());
+    ctx.enable_coverage();
+    for _ in 0..3 {
+        evaluate_func_in_ctx(&mut ctx, func, &[IntConst::I32(1).into()]);
+    }
+    let coverage = ctx.take_coverage().unwrap();
+    assert!(ctx.coverage().is_none());
+    assert_eq!(coverage.block_count(func, entry), 3);
+    assert_eq!(coverage.block_count(func, then_block), 3);
+    assert_eq!(coverage.block_count(func, else_block), 0);
+    let report = coverage.report(&module);
+    let func_coverage = report.get(func).unwrap();
+    assert_eq!(func_coverage.calls(), 3);
+    assert_eq!(func_coverage.total_blocks(), 3);
+    assert_eq!(func_coverage.covered_blocks(), 2);
+    assert_eq!(
+        func_coverage.uncovered_blocks().collect::<Vec<_>>(),
+        vec![else_block]
+    );
+    assert_eq!(
+        report.to_string(),
+        format!(
+            "{}: 2/3 blocks (66.67%), uncovered: {}\n\
+             total: 2/3 blocks (66.67%)\n",
+            func, else_block,
+        )
+    );
+    assert_eq!(
+        report.to_lcov("module.wasm"),
+        format!(
+            "TN:\nSF:module.wasm\nFN:1,{0}\nFNDA:3,{0}\nFNF:1\nFNH:1\n\
+             DA:1,3\nDA:2,3\nDA:3,0\nLF:3\nLH:2\nend_of_record\n",
+            func,
+        )
+    );
+    // Merging the coverage of another context covers the remaining block.
+    let mut ctx = EvaluationContext::new(&module);
+    ctx.enable_coverage();
+    evaluate_func_in_ctx(&mut ctx, func, &[IntConst::I32(0).into()]);
+    let mut merged = coverage.clone();
+    merged.merge(ctx.coverage().unwrap());
+    let report = merged.report(&module);
+    assert_eq!(report.covered_blocks(), 3);
+    assert_eq!(report.percentage(), 100.0);
+}
+
+#[test]
+fn mutable_global_is_shared_between_contexts() {
+    let i32 = Type::from(IntType::I32);
+    let exporter = {
+        let mut builder = Module::build();
+        let mut globals = builder.global_section().unwrap();
+        let init = InitExpr::Const(IntConst::I32(1).into());
+        let global = globals
+            .push_global(GlobalVariable::new(i32, true), init)
+            .unwrap();
+        let mut exports = builder.export_section().unwrap();
+        exports.export_global(global, "counter").unwrap();
+        builder.finalize().unwrap()
+    };
+    let name = ImportName::new("env", "counter");
+    let (importer, imported, derived) = {
+        let mut builder = Module::build();
+        let mut imports = builder.import_section().unwrap();
+        let imported = imports
+            .import_global(name.clone(), GlobalVariable::new(i32, true));
+        let mut globals = builder.global_section().unwrap();
+        let derived = globals
+            .push_global(
+                GlobalVariable::new(i32, false),
+                InitExpr::GlobalGet(imported),
+            )
+            .unwrap();
+        (builder.finalize().unwrap(), imported, derived)
+    };
+    let exporter_ctx = EvaluationContext::new(&exporter);
+    let mut importer_ctx = EvaluationContext::new(&importer);
+    assert!(importer_ctx.global(imported).is_none());
+    assert!(importer_ctx.global(derived).is_none());
+    let handle = exporter_ctx.exported_global("counter").unwrap();
+    // Mutability must match the import.
+    let immutable = GlobalHandle::new(IntConst::I32(0).into(), false);
+    assert!(matches!(
+        importer_ctx.link_global(&name, immutable),
+        Err(InterpretationError::UnmatchingGlobalImport { .. })
+    ));
+    assert!(matches!(
+        importer_ctx
+            .link_global(&ImportName::new("env", "other"), handle.clone()),
+        Err(InterpretationError::UnknownImportedGlobal { .. })
+    ));
+    importer_ctx.link_global(&name, handle.clone()).unwrap();
+    let derived = importer_ctx.global(derived).unwrap();
+    assert_eq!(derived.get(), IntConst::I32(1).into());
+    // Writes through either context are observed by the other.
+    handle.set(IntConst::I32(42).into()).unwrap();
+    let imported = importer_ctx.global(imported).unwrap();
+    assert!(imported.ptr_eq(&handle));
+    assert_eq!(imported.get(), IntConst::I32(42).into());
+    imported.set(IntConst::I32(7).into()).unwrap();
+    assert_eq!(
+        exporter_ctx.exported_global("counter").unwrap().get(),
+        IntConst::I32(7).into()
+    );
+    // Derived immutable globals keep their initial value.
+    assert_eq!(derived.get(), IntConst::I32(1).into());
+    assert_eq!(
+        derived.set(IntConst::I32(5).into()),
+        Err(InterpretationError::ImmutableGlobalWrite)
+    );
+    assert_eq!(
+        imported.set(IntConst::I64(5).into()),
+        Err(InterpretationError::UnmatchingGlobalType {
+            given_type: IntType::I64.into(),
+            expected_type: i32,
+        })
+    );
+}
+
+#[test]
+fn function_types_are_interned() {
+    let func_type = |inputs: &[Type], outputs: &[Type]| {
+        let mut b = FunctionType::build();
+        for input in inputs {
+            b.push_input(*input);
+        }
+        for output in outputs {
+            b.push_output(*output);
+        }
+        b.finalize()
+    };
+    let i32 = Type::from(IntType::I32);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let t0 = type_builder.push_type(func_type(&[i32], &[i32]));
+    let t1 = type_builder.push_type(func_type(&[], &[]));
+    let t2 = type_builder.push_type(func_type(&[i32], &[i32]));
+    assert_ne!(t0, t2);
+    let mut function_builder = builder.function_section().unwrap();
+    let f0 = function_builder.push_function(t2).unwrap();
+    let (res, _) = builder.code_section().unwrap();
+    assert_eq!(res.get_canonical_type(t0), Some(t0));
+    assert_eq!(res.get_canonical_type(t1), Some(t1));
+    assert_eq!(res.get_canonical_type(t2), Some(t0));
+    assert!(res.func_types_match(t0, t2));
+    assert!(!res.func_types_match(t0, t1));
+    assert_eq!(res.get_raw_func_type(f0), Some(t0));
+    assert_eq!(res.get_type(t2), res.get_func_type(f0));
+}
+
+#[test]
+fn display_value_works() {
+    fn display(value: Const) -> (String, String) {
+        let value = DisplayValue::from(value);
+        (format!("{}", value), format!("{:#}", value))
+    }
+    let f32 = |value: f32| Const::from(FloatConst::F32(value.into()));
+    let f64 = |value: f64| Const::from(FloatConst::F64(value.into()));
+    let same = |s: &str| (s.to_string(), s.to_string());
+    assert_eq!(display(Const::Bool(true)), same("true"));
+    assert_eq!(display(Const::Ptr(0x10)), same("0x10"));
+    assert_eq!(
+        display(IntConst::I8(-1).into()),
+        ("-1".to_string(), "255".to_string())
+    );
+    assert_eq!(
+        display(IntConst::I32(-2).into()),
+        ("-2".to_string(), "4294967294".to_string())
+    );
+    assert_eq!(display(IntConst::I64(42).into()), same("42"));
+    assert_eq!(display(f32(1.0)), same("0x1p+0"));
+    assert_eq!(display(f32(3.0)), same("0x1.8p+1"));
+    assert_eq!(display(f32(-0.1)), same("-0x1.99999ap-4"));
+    assert_eq!(display(f32(0.0)), same("0x0p+0"));
+    assert_eq!(display(f32(f32::from_bits(1))), same("0x0.000002p-126"));
+    assert_eq!(display(f32(f32::INFINITY)), same("inf"));
+    assert_eq!(display(f32(f32::from_bits(0x7FC0_0000))), same("nan"));
+    assert_eq!(
+        display(f32(f32::from_bits(0xFFA0_0001))),
+        same("-nan:0x200001")
+    );
+    assert_eq!(display(f64(0.5)), same("0x1p-1"));
+    assert_eq!(display(f64(0.1)), same("0x1.999999999999ap-4"));
+    assert_eq!(display(f64(f64::NEG_INFINITY)), same("-inf"));
+    // Register bits of narrower types are truncated.
+    let value = DisplayValue::new(IntType::I16.into(), 0x1_FFFF);
+    assert_eq!(value.to_string(), "-1");
+}
+
+#[test]
+fn memory_creator_works() {
+    #[derive(Debug, Default)]
+    struct CountingCreator {
+        created: Cell<usize>,
+    }
+    impl MemoryCreator for CountingCreator {
+        fn create_memory(
+            &self,
+            decl: &LinearMemoryDecl,
+        ) -> Result<Box<dyn LinearMemory>, InterpretationError> {
+            self.created.set(self.created.get() + 1);
+            VecMemoryCreator.create_memory(decl)
+        }
+    }
+    #[derive(Debug)]
+    struct FailingCreator;
+    impl MemoryCreator for FailingCreator {
+        fn create_memory(
+            &self,
+            decl: &LinearMemoryDecl,
+        ) -> Result<Box<dyn LinearMemory>, InterpretationError> {
+            Err(InterpretationError::MemoryAllocationFailed {
+                pages: decl.initial_pages(),
+            })
+        }
+    }
+    let (module, imported, internal) = {
+        let mut builder = Module::build();
+        let mut imports = builder.import_section().unwrap();
+        let imported = imports.import_memory(
+            ImportName::new("env", "memory"),
+            LinearMemoryDecl::new(1, None),
+        );
+        let mut memories = builder.memory_section().unwrap();
+        let internal =
+            memories.push_memory(LinearMemoryDecl::new(1, 3)).unwrap();
+        (builder.finalize().unwrap(), imported, internal)
+    };
+    let creator = CountingCreator::default();
+    let mut ctx =
+        EvaluationContext::with_memory_creator(&module, &creator).unwrap();
+    // Imported linear memories are not created by the context.
+    assert_eq!(creator.created.get(), 1);
+    assert!(ctx.memory(imported).is_none());
+    let memory = ctx.memory_mut(internal).unwrap();
+    assert_eq!(memory.pages(), 1);
+    assert_eq!(memory.maximum_pages(), Some(3));
+    assert_eq!(memory.data().len(), PAGE_SIZE);
+    assert!(memory.data().iter().all(|&byte| byte == 0));
+    memory.data_mut()[42] = 0xFF;
+    assert_eq!(memory.grow(2), Some(1));
+    assert_eq!(memory.grow(1), None);
+    assert_eq!(memory.pages(), 3);
+    assert_eq!(memory.data().len(), 3 * PAGE_SIZE);
+    assert_eq!(memory.data()[42], 0xFF);
+    assert_eq!(
+        EvaluationContext::with_memory_creator(&module, &FailingCreator)
+            .unwrap_err(),
+        InterpretationError::MemoryAllocationFailed { pages: 1 },
+    );
+    // Vec backed memories reject initial pages exceeding their maximum.
+    assert!(VecMemory::new(2, Some(1)).is_err());
+    assert!(VecMemory::new(0, None).unwrap().grow(MAX_PAGES + 1).is_none());
+}
+
+#[test]
+fn ir_arena_reuse_works() {
+    let i32_type = Type::from(IntType::I32);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.push_output(i32_type);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let inc = function_builder.push_function(func_type).unwrap();
+    let double = function_builder.push_function(func_type).unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let mut arena = IrArena::new();
+    assert!(!arena.is_retaining());
+    let body = {
+        let mut b = FunctionBody::build_in(inc, res, &mut arena);
+        b.body().unwrap();
+        let input = b.input_var(0).unwrap();
+        let v0 = b.read_var(input).unwrap();
+        let v1 = b.ins().unwrap().constant(IntConst::I32(1)).unwrap();
+        let v2 = b.ins().unwrap().iadd(IntType::I32, v0, v1).unwrap();
+        b.ins().unwrap().return_values([v2].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(inc, body).unwrap();
+    assert!(arena.is_retaining());
+    // The second construction reuses the cleared state of the first one.
+    let body = {
+        let mut b = FunctionBody::build_in(double, res, &mut arena);
+        b.body().unwrap();
+        let input = b.input_var(0).unwrap();
+        let v0 = b.read_var(input).unwrap();
+        let v1 = b.ins().unwrap().iadd(IntType::I32, v0, v0).unwrap();
+        b.ins().unwrap().return_values([v1].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(double, body).unwrap();
+    arena.release();
+    assert!(!arena.is_retaining());
+    let module = builder.finalize().unwrap();
+    for x in -10..10 {
+        let input = [IntConst::I32(x).into()];
+        let result = evaluate_func(&module, inc, &input);
+        let result = bits_into_const(&module, inc, result);
+        assert_eq!(result, vec![IntConst::I32(x + 1).into()]);
+        let result = evaluate_func(&module, double, &input);
+        let result = bits_into_const(&module, double, result);
+        assert_eq!(result, vec![IntConst::I32(x * 2).into()]);
+    }
+}
+
+#[test]
+fn read_only_data_works() {
+    let i32_type = Type::from(IntType::I32);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_output(i32_type);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut data_builder = builder.read_only_data_section().unwrap();
+    let hello = data_builder.push_data(b"hello\n".iter().copied());
+    let table = data_builder.push_data(vec![1, 2, 3, 4]);
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    assert_eq!(res.get_read_only_data(hello), Some(&b"hello\n"[..]));
+    let body = {
+        let mut b = FunctionBody::build(func, res);
+        b.body().unwrap();
+        let invalid = Data::from_raw(RawIdx::from_u32(2));
+        assert_eq!(
+            b.ins().unwrap().data_addr(invalid),
+            Err(module::Error::from(
+                module::FunctionBuilderError::InvalidReadOnlyData {
+                    data: invalid
+                }
+            ))
+        );
+        b.ins().unwrap().data_addr(hello).unwrap();
+        let ptr = b.ins().unwrap().data_addr(table).unwrap();
+        let offset = ImmU32::from(0);
+        let v0 = b.ins().unwrap().load(ptr, offset, i32_type).unwrap();
+        b.ins().unwrap().return_values([v0].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(func, body).unwrap();
+    let module = builder.finalize().unwrap();
+    assert_eq!(
+        module.read_only_data().collect::<Vec<_>>(),
+        vec![hello, table]
+    );
+    assert_eq!(module.get_read_only_data(table), Some(&[1, 2, 3, 4][..]));
+    filecheck(
+        &module.to_string(),
+        r#"
+        // CHECK: mod {
+        // CHECK-NEXT: data(0) = "hello\n"
+        // CHECK-NEXT: data(1) = "\x01\x02\x03\x04"
+        // CHECK: data_addr data(0)
+        // CHECK: data_addr data(1)
+        "#,
+    )
+    .unwrap();
+    // Read-only data is stored in little-endian byte order.
+    assert_eq!(evaluate_func(&module, func, &[]), vec![0x0403_0201]);
+}
+
+#[test]
+fn shift_amount_is_masked() {
+    let i32_type = Type::from(IntType::I32);
+    let ops = [
+        ShiftIntOp::Shl,
+        ShiftIntOp::Sshr,
+        ShiftIntOp::Ushr,
+        ShiftIntOp::Rotl,
+        ShiftIntOp::Rotr,
+    ];
+    for &op in &ops {
+        let build = |int_type: IntType| {
+            let ty = Type::from(int_type);
+            module_with_func(&[ty, i32_type], &[ty], |b| {
+                b.body()?;
+                let source = b.input_var(0).unwrap();
+                let amount = b.input_var(1).unwrap();
+                let source = b.read_var(source)?;
+                let amount = b.read_var(amount)?;
+                let ins = b.ins()?;
+                let result = match op {
+                    ShiftIntOp::Shl => ins.ishl(int_type, source, amount)?,
+                    ShiftIntOp::Sshr => ins.isshr(int_type, source, amount)?,
+                    ShiftIntOp::Ushr => ins.iushr(int_type, source, amount)?,
+                    ShiftIntOp::Rotl => ins.irotl(int_type, source, amount)?,
+                    ShiftIntOp::Rotr => ins.irotr(int_type, source, amount)?,
+                };
+                b.ins()?.return_values([result].iter().copied())?;
+                Ok(())
+            })
+        };
+        let (func32, module32) = build(IntType::I32);
+        let (func64, module64) = build(IntType::I64);
+        let source32 = 0x8765_4321_u32 as i32;
+        let source64 = 0x8765_4321_0FED_CBA9_u64 as i64;
+        for &amount in &[0, 1, 5, 31, 32, 33, 63, 64, 65, -1] {
+            let expected32 = match op {
+                ShiftIntOp::Shl => source32.wrapping_shl(amount as u32),
+                ShiftIntOp::Sshr => source32.wrapping_shr(amount as u32),
+                ShiftIntOp::Ushr => {
+                    (source32 as u32).wrapping_shr(amount as u32) as i32
+                }
+                ShiftIntOp::Rotl => source32.rotate_left(amount as u32 % 32),
+                ShiftIntOp::Rotr => source32.rotate_right(amount as u32 % 32),
+            };
+            let expected64 = match op {
+                ShiftIntOp::Shl => source64.wrapping_shl(amount as u32),
+                ShiftIntOp::Sshr => source64.wrapping_shr(amount as u32),
+                ShiftIntOp::Ushr => {
+                    (source64 as u64).wrapping_shr(amount as u32) as i64
+                }
+                ShiftIntOp::Rotl => source64.rotate_left(amount as u32 % 64),
+                ShiftIntOp::Rotr => source64.rotate_right(amount as u32 % 64),
+            };
+            let amount = IntConst::I32(amount).into();
+            let inputs = [IntConst::I32(source32).into(), amount];
+            let result = evaluate_func(&module32, func32, &inputs);
+            let result = bits_into_const(&module32, func32, result);
+            assert_eq!(result, vec![IntConst::I32(expected32).into()]);
+            let inputs = [IntConst::I64(source64).into(), amount];
+            let result = evaluate_func(&module64, func64, &inputs);
+            let result = bits_into_const(&module64, func64, result);
+            assert_eq!(result, vec![IntConst::I64(expected64).into()]);
+        }
+    }
+}
+
+#[test]
+fn legalization_works() {
+    fn build(b: &mut FunctionBuilder) -> Result<(), module::Error> {
+        b.body()?;
+        let lhs = b.input_var(0).unwrap();
+        let rhs = b.input_var(1).unwrap();
+        let amount = b.input_var(2).unwrap();
+        let lhs = b.read_var(lhs)?;
+        let rhs = b.read_var(rhs)?;
+        let amount = b.read_var(amount)?;
+        let results = [
+            b.ins()?.iadd(IntType::I8, lhs, rhs)?,
+            b.ins()?.imul(IntType::I8, lhs, rhs)?,
+            b.ins()?.udiv(IntType::I8, lhs, rhs)?,
+            b.ins()?.iclz(IntType::I8, lhs)?,
+            b.ins()?.ictz(IntType::I8, lhs)?,
+            b.ins()?.ipopcnt(IntType::I8, lhs)?,
+            b.ins()?.ishl(IntType::I8, lhs, amount)?,
+            b.ins()?.isshr(IntType::I8, lhs, amount)?,
+            b.ins()?.icmp(IntType::I8, CompareIntOp::Slt, lhs, rhs)?,
+            b.ins()?
+                .int_to_float(true, IntType::I8, FloatType::F32, lhs)?,
+        ];
+        b.ins()?.return_values(results.iter().copied())?;
+        Ok(())
+    }
+    let i8_type = Type::from(IntType::I8);
+    let inputs = [i8_type, i8_type, IntType::I32.into()];
+    let outputs = [
+        i8_type,
+        i8_type,
+        i8_type,
+        i8_type,
+        i8_type,
+        i8_type,
+        i8_type,
+        i8_type,
+        Type::Bool,
+        FloatType::F32.into(),
+    ];
+    let mut constraints = TargetConstraints::default();
+    constraints.set_min_int_type(IntType::I32);
+    let (func, module) = module_with_func(&inputs, &outputs, build);
+    let (legal_func, legal_module) =
+        module_with_edited_func(&inputs, &outputs, build, |body| {
+            assert_eq!(body.legalize(&constraints)?, outputs.len());
+            // Legalized function bodies are already legal.
+            assert_eq!(body.legalize(&constraints)?, 0);
+            Ok(())
+        });
+    for &lhs in &[0_i8, 1, -1, 7, 64, i8::MIN, i8::MAX] {
+        for &rhs in &[1_i8, -1, 3, i8::MIN] {
+            for &amount in &[0, 1, 7, 8, 9, -1] {
+                let inputs = [
+                    IntConst::I8(lhs).into(),
+                    IntConst::I8(rhs).into(),
+                    IntConst::I32(amount).into(),
+                ];
+                let expected = evaluate_func(&module, func, &inputs);
+                let expected = bits_into_const(&module, func, expected);
+                let result = evaluate_func(&legal_module, legal_func, &inputs);
+                let result = bits_into_const(&legal_module, legal_func, result);
+                assert_eq!(result, expected);
+            }
+        }
+    }
+    // Narrow rotations have no legalization.
+    module_with_edited_func(
+        &[i8_type, IntType::I32.into()],
+        &[i8_type],
+        |b| {
+            b.body()?;
+            let source = b.input_var(0).unwrap();
+            let amount = b.input_var(1).unwrap();
+            let source = b.read_var(source)?;
+            let amount = b.read_var(amount)?;
+            let result = b.ins()?.irotl(IntType::I8, source, amount)?;
+            b.ins()?.return_values([result].iter().copied())?;
+            Ok(())
+        },
+        |body| {
+            let instr = body.block_instrs(body.entry_block())[0];
+            assert_eq!(
+                body.legalize(&constraints),
+                Err(module::Error::from(
+                    module::FunctionEditorError::IllegalInstruction { instr }
+                ))
+            );
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn checked_arithmetic_works() {
+    type Signed = fn(i8, i8) -> Option<i8>;
+    type Unsigned = fn(u8, u8) -> Option<u8>;
+    let ops: [(BinaryIntOp, Signed, Unsigned); 3] = [
+        (BinaryIntOp::Add, i8::checked_add, u8::checked_add),
+        (BinaryIntOp::Sub, i8::checked_sub, u8::checked_sub),
+        (BinaryIntOp::Mul, i8::checked_mul, u8::checked_mul),
+    ];
+    let i8_type = Type::from(IntType::I8);
+    let values = [0_i8, 1, 2, 3, 11, 16, 64, 127, -1, -2, -16, -64, -127, -128];
+    let mut checks = CheckedArithmetic::default();
+    for &(op, signed, unsigned) in &ops {
+        for &overflow in &[Overflow::Signed, Overflow::Unsigned] {
+            checks.set_check(IntType::I8, overflow);
+            let (func, module) = module_with_edited_func(
+                &[i8_type, i8_type],
+                &[i8_type],
+                |b| {
+                    b.body()?;
+                    let lhs = b.input_var(0).unwrap();
+                    let rhs = b.input_var(1).unwrap();
+                    let lhs = b.read_var(lhs)?;
+                    let rhs = b.read_var(rhs)?;
+                    let result = match op {
+                        BinaryIntOp::Add => {
+                            b.ins()?.iadd(IntType::I8, lhs, rhs)?
+                        }
+                        BinaryIntOp::Sub => {
+                            b.ins()?.isub(IntType::I8, lhs, rhs)?
+                        }
+                        _ => b.ins()?.imul(IntType::I8, lhs, rhs)?,
+                    };
+                    // Arithmetic of unchecked integer types is not checked.
+                    let wide = b.ins()?.iextend(
+                        IntType::I8,
+                        IntType::I32,
+                        lhs,
+                        true,
+                    )?;
+                    b.ins()?.iadd(IntType::I32, wide, wide)?;
+                    b.ins()?.return_values([result].iter().copied())?;
+                    Ok(())
+                },
+                |body| {
+                    assert_eq!(body.check_arithmetic(&checks)?, 1);
+                    Ok(())
+                },
+            );
+            for &lhs in &values {
+                for &rhs in &values {
+                    let expected = match overflow {
+                        Overflow::Signed => signed(lhs, rhs),
+                        Overflow::Unsigned => {
+                            unsigned(lhs as u8, rhs as u8).map(|r| r as i8)
+                        }
+                    };
+                    let inputs = [IntConst::I8(lhs), IntConst::I8(rhs)];
+                    let mut ctx = EvaluationContext::new(&module);
+                    let mut results = Vec::new();
+                    let result = ctx.evaluate_function(
+                        func,
+                        inputs
+                            .iter()
+                            .map(|input| Const::from(*input).into_bits64()),
+                        |result| results.push(result),
+                    );
+                    match expected {
+                        Some(expected) => {
+                            assert_eq!(
+                                bits_into_const(&module, func, results),
+                                vec![IntConst::I8(expected).into()]
+                            );
+                        }
+                        None => {
+                            assert_eq!(
+                                result.unwrap_err().trap_code(),
+                                Some(TrapCode::IntegerOverflow)
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn instrumentation_hooks_work() {
+    let i32_type = Type::from(IntType::I32);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let hook_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.finalize()
+    });
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.push_output(i32_type);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let entry_hook = function_builder.push_function(hook_type).unwrap();
+    let exit_hook = function_builder.push_function(hook_type).unwrap();
+    let outer = function_builder.push_function(func_type).unwrap();
+    let inner = function_builder.push_function(func_type).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let mem = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, None))
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    // The hooks count the entries and exits of every function in memory.
+    let offsets = [(entry_hook, 0_u32), (exit_hook, 32)];
+    for (hook, offset) in offsets.iter().copied() {
+        let mut b = FunctionBody::build(hook, res);
+        b.body().unwrap();
+        let func = b.input_var(0).unwrap();
+        let func = b.read_var(func).unwrap();
+        let v0 = b.ins().unwrap().constant(IntConst::I32(2)).unwrap();
+        let pos = b.ins().unwrap().ishl(IntType::I32, func, v0).unwrap();
+        let ptr = b.ins().unwrap().heap_addr(mem, pos, 36.into()).unwrap();
+        let v1 = b.ins().unwrap().load(ptr, offset.into(), i32_type).unwrap();
+        let v2 = b.ins().unwrap().constant(IntConst::I32(1)).unwrap();
+        let v3 = b.ins().unwrap().iadd(IntType::I32, v1, v2).unwrap();
+        b.ins()
+            .unwrap()
+            .store(ptr, offset.into(), v3, i32_type)
+            .unwrap();
+        b.ins().unwrap().return_values(None).unwrap();
+        body_builder.push_body(hook, b.finalize().unwrap()).unwrap();
+    }
+    // outer(x) = if x != 0 { inner(x) + 1 } else { 0 }
+    let body = {
+        let mut b = FunctionBody::build(outer, res);
+        b.body().unwrap();
+        let then_block = b.create_block().unwrap();
+        let else_block = b.create_block().unwrap();
+        let x = b.input_var(0).unwrap();
+        let x = b.read_var(x).unwrap();
+        let v0 = b.ins().unwrap().constant(IntConst::I32(0)).unwrap();
+        let v1 = b
+            .ins()
+            .unwrap()
+            .icmp(IntType::I32, CompareIntOp::Ne, x, v0)
+            .unwrap();
+        b.ins()
+            .unwrap()
+            .if_then_else(v1, then_block, else_block)
+            .unwrap();
+        b.switch_to_block(then_block).unwrap();
+        b.seal_block(then_block).unwrap();
+        let instr = b.ins().unwrap().call(inner, vec![x]).unwrap();
+        let v2 = b.instr_values(instr).unwrap()[0];
+        let v3 = b.ins().unwrap().constant(IntConst::I32(1)).unwrap();
+        let v4 = b.ins().unwrap().iadd(IntType::I32, v2, v3).unwrap();
+        b.ins()
+            .unwrap()
+            .return_values([v4].iter().copied())
+            .unwrap();
+        b.switch_to_block(else_block).unwrap();
+        b.seal_block(else_block).unwrap();
+        b.ins()
+            .unwrap()
+            .return_values([v0].iter().copied())
+            .unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(outer, body).unwrap();
+    // inner(x) = x
+    let body = {
+        let mut b = FunctionBody::build(inner, res);
+        b.body().unwrap();
+        let x = b.input_var(0).unwrap();
+        let x = b.read_var(x).unwrap();
+        b.ins().unwrap().return_values([x].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(inner, body).unwrap();
+    let mut module = builder.finalize().unwrap();
+    // Hooks must be of type `fn(i32)`.
+    let mut hooks = InstrumentationHooks::default();
+    hooks.set_entry_hook(outer);
+    assert_eq!(
+        module.instrument(&hooks),
+        Err(module::Error::from(
+            module::FunctionEditorError::InvalidHook { hook: outer }
+        ))
+    );
+    let mut hooks = InstrumentationHooks::default();
+    hooks.set_entry_hook(entry_hook);
+    hooks.set_exit_hook(exit_hook);
+    // One entry for both functions, two exits of outer and one of inner.
+    assert_eq!(module.instrument(&hooks), Ok(5));
+    filecheck(
+        &module.to_string(),
+        "
+        // CHECK: fn func2(v0: i32) -> i32 {
+        // CHECK-NEXT: block {
+        // CHECK-NEXT: const<i32> 2
+        // CHECK-NEXT: call func0(
+        // CHECK: call func1(
+        // CHECK-NEXT: return
+        // CHECK: call func1(
+        // CHECK-NEXT: return
+        // CHECK: fn func3(v0: i32) -> i32 {
+        // CHECK-NEXT: block {
+        // CHECK-NEXT: const<i32> 3
+        // CHECK-NEXT: call func0(
+        // CHECK-NEXT: const<i32> 3
+        // CHECK-NEXT: call func1(
+        // CHECK-NEXT: return
+        ",
+    )
+    .unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    let mut evaluate = |input: i32| {
+        let inputs = [IntConst::I32(input).into()];
+        evaluate_func_in_ctx(&mut ctx, outer, &inputs)
+    };
+    assert_eq!(evaluate(5), vec![6]);
+    assert_eq!(evaluate(0), vec![0]);
+    let counts = |offset: usize| {
+        let data = ctx.memory(mem).unwrap().data();
+        (0..4)
+            .map(|n| {
+                let pos = offset + 4 * n;
+                let mut bytes = [0x00; 4];
+                bytes.copy_from_slice(&data[pos..pos + 4]);
+                u32::from_le_bytes(bytes)
+            })
+            .collect::<Vec<_>>()
+    };
+    // The hooks themselves are not instrumented.
+    assert_eq!(counts(0), vec![0, 0, 2, 1]);
+    assert_eq!(counts(32), vec![0, 0, 2, 1]);
+}
+
+#[test]
+fn memory_access_hook_works() {
+    let i32_type = Type::from(IntType::I32);
+    let i64_type = Type::from(IntType::I64);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let hook_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.push_input(i32_type);
+        b.push_input(i32_type);
+        b.finalize()
+    });
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.push_input(i64_type);
+        b.push_output(IntType::I8);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let hook = function_builder.push_function(hook_type).unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let mem = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, None))
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    // Stores the last accessed address and sums up the accessed bytes and
+    // the number of writes.
+    let body = {
+        let mut b = FunctionBody::build(hook, res);
+        b.body().unwrap();
+        let inputs = (0..3)
+            .map(|n| {
+                let input = b.input_var(n).unwrap();
+                b.read_var(input).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let v0 = b.ins().unwrap().constant(IntConst::I32(0)).unwrap();
+        let ptr = b.ins().unwrap().heap_addr(mem, v0, 12.into()).unwrap();
+        b.ins()
+            .unwrap()
+            .store(ptr, 0.into(), inputs[0], i32_type)
+            .unwrap();
+        let sums = [(4_u32, inputs[1]), (8, inputs[2])];
+        for (offset, input) in sums.iter().copied() {
+            let v1 =
+                b.ins().unwrap().load(ptr, offset.into(), i32_type).unwrap();
+            let v2 = b.ins().unwrap().iadd(IntType::I32, v1, input).unwrap();
+            b.ins()
+                .unwrap()
+                .store(ptr, offset.into(), v2, i32_type)
+                .unwrap();
+        }
+        b.ins().unwrap().return_values(None).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(hook, body).unwrap();
+    // Stores the value at `pos + 4` and loads its second byte.
+    let body = {
+        let mut b = FunctionBody::build(func, res);
+        b.body().unwrap();
+        let pos = b.input_var(0).unwrap();
+        let value = b.input_var(1).unwrap();
+        let pos = b.read_var(pos).unwrap();
+        let value = b.read_var(value).unwrap();
+        let ptr = b.ins().unwrap().heap_addr(mem, pos, 12.into()).unwrap();
+        b.ins()
+            .unwrap()
+            .store(ptr, 4.into(), value, i64_type)
+            .unwrap();
+        let v0 = b
+            .ins()
+            .unwrap()
+            .load(ptr, 5.into(), IntType::I8.into())
+            .unwrap();
+        b.ins().unwrap().return_values(Some(v0)).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(func, body).unwrap();
+    let mut module = builder.finalize().unwrap();
+    let mut hooks = InstrumentationHooks::default();
+    hooks.set_memory_access_hook(func);
+    assert_eq!(
+        module.instrument(&hooks),
+        Err(module::Error::from(
+            module::FunctionEditorError::InvalidHook { hook: func }
+        ))
+    );
+    let mut hooks = InstrumentationHooks::default();
+    hooks.set_memory_access_hook(hook);
+    assert_eq!(module.instrument(&hooks), Ok(2));
+    let mut ctx = EvaluationContext::new(&module);
+    let inputs = [IntConst::I32(100).into(), IntConst::I64(0x1234).into()];
+    assert_eq!(evaluate_func_in_ctx(&mut ctx, func, &inputs), vec![0x12]);
+    let data = ctx.memory(mem).unwrap().data();
+    let read = |pos: usize| {
+        let mut bytes = [0x00; 4];
+        bytes.copy_from_slice(&data[pos..pos + 4]);
+        u32::from_le_bytes(bytes)
+    };
+    assert_eq!((read(0), read(4), read(8)), (105, 9, 1));
+}
+
+#[test]
+fn shadow_stack_overflow_traps() {
+    let i32_type = Type::from(IntType::I32);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.push_output(i32_type);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut globals = builder.global_section().unwrap();
+    let init = InitExpr::Const(IntConst::I32(1024).into());
+    let stack_pointer = globals
+        .push_global(GlobalVariable::new(i32_type, true), init)
+        .unwrap();
+    let init = InitExpr::Const(IntConst::I32(0).into());
+    let immutable = globals
+        .push_global(GlobalVariable::new(i32_type, false), init)
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    // Allocates the given number of bytes on the shadow stack and returns
+    // the new stack pointer.
+    let body = {
+        let mut b = FunctionBody::build(func, res);
+        b.body().unwrap();
+        let len = b.input_var(0).unwrap();
+        let len = b.read_var(len).unwrap();
+        let sp = b.ins().unwrap().global_get(stack_pointer).unwrap();
+        let sp = b.ins().unwrap().isub(IntType::I32, sp, len).unwrap();
+        b.ins().unwrap().global_set(stack_pointer, sp).unwrap();
+        b.ins().unwrap().return_values(Some(sp)).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(func, body).unwrap();
+    let mut module = builder.finalize().unwrap();
+    assert_eq!(
+        module.check_shadow_stack(&ShadowStack::new(immutable, 512)),
+        Err(module::Error::from(
+            module::FunctionEditorError::InvalidStackPointer {
+                global: immutable
+            }
+        ))
+    );
+    let stack = ShadowStack::new(stack_pointer, 512);
+    assert_eq!(module.check_shadow_stack(&stack), Ok(1));
+    assert_eq!(module.verify(), Ok(()));
+    filecheck(
+        &module.to_string(),
+        "
+        // CHECK: const<i32> 512
+        // CHECK-NEXT: icmp
+        // CHECK-NEXT: if
+        // CHECK: global.set
+        // CHECK: trap stack_overflow
+        ",
+    )
+    .unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    let mut evaluate = |len: i32| {
+        let mut results = Vec::new();
+        ctx.evaluate_function(
+            func,
+            Some(Const::from(IntConst::I32(len)).into_bits64()),
+            |result| results.push(result),
+        )
+        .map(|_| results)
+    };
+    assert_eq!(evaluate(256).unwrap(), vec![768]);
+    assert_eq!(evaluate(256).unwrap(), vec![512]);
+    assert_eq!(
+        evaluate(1).unwrap_err().trap_code(),
+        Some(TrapCode::StackOverflow)
+    );
+    // The stack pointer is left untouched upon overflow.
+    assert_eq!(
+        ctx.global(stack_pointer).unwrap().get(),
+        Const::from(IntConst::I32(512))
+    );
+}
+
+#[test]
+fn load_and_store_work() {
+    let i32_type = Type::from(IntType::I32);
+    let i64_type = Type::from(IntType::I64);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.push_input(i64_type);
+        b.push_output(i64_type);
+        b.push_output(IntType::I8);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let mem = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, None))
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let body = {
+        let mut b = FunctionBody::build(func, res);
+        b.body().unwrap();
+        let pos = b.input_var(0).unwrap();
+        let value = b.input_var(1).unwrap();
+        let pos = b.read_var(pos).unwrap();
+        let value = b.read_var(value).unwrap();
+        // Stores the value at `pos + 4` and loads it and its second byte.
+        let ptr = b.ins().unwrap().heap_addr(mem, pos, 12.into()).unwrap();
+        b.ins().unwrap().store(ptr, 4.into(), value, i64_type).unwrap();
+        let v0 = b.ins().unwrap().load(ptr, 4.into(), i64_type).unwrap();
+        let v1 = b
+            .ins()
+            .unwrap()
+            .load(ptr, 5.into(), IntType::I8.into())
+            .unwrap();
+        b.ins()
+            .unwrap()
+            .return_values([v0, v1].iter().copied())
+            .unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(func, body).unwrap();
+    let module = builder.finalize().unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    let value = 0x0123_4567_89AB_CDEF_u64;
+    let evaluate = |ctx: &mut EvaluationContext, pos: u32| {
+        let mut results = Vec::new();
+        ctx.evaluate_function(
+            func,
+            [u64::from(pos), value].iter().copied(),
+            |result| results.push(result),
+        )
+        .map(|_| results)
+    };
+    // Unaligned accesses are allowed.
+    assert_eq!(evaluate(&mut ctx, 3), Ok(vec![value, 0xCD]));
+    let data = ctx.memory(mem).unwrap().data();
+    assert_eq!(data[7..15], value.to_le_bytes());
+    let last = PAGE_SIZE as u32 - 12;
+    assert_eq!(evaluate(&mut ctx, last), Ok(vec![value, 0xCD]));
+    assert_eq!(
+        evaluate(&mut ctx, last + 1),
+        Err(InterpretationError::MemoryOutOfBounds {
+            memory: mem,
+            address: u64::from(last + 1),
+            len: 12,
+        })
+    );
+}
+
+#[test]
+fn memory_grow_and_size_work() {
+    let i32_type = Type::from(IntType::I32);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.push_output(i32_type);
+        b.push_output(i32_type);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let mem = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, 3))
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let body = {
+        let mut b = FunctionBody::build(func, res);
+        b.body().unwrap();
+        let delta = b.input_var(0).unwrap();
+        let delta = b.read_var(delta).unwrap();
+        let v0 = b.ins().unwrap().memory_grow(mem, delta).unwrap();
+        let v1 = b.ins().unwrap().memory_size(mem).unwrap();
+        b.ins()
+            .unwrap()
+            .return_values([v0, v1].iter().copied())
+            .unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(func, body).unwrap();
+    let module = builder.finalize().unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    let mut grow = |delta: u32| {
+        let mut results = Vec::new();
+        ctx.evaluate_function(
+            func,
+            [u64::from(delta)].iter().copied(),
+            |result| results.push(result),
+        )
+        .unwrap();
+        results
+    };
+    let failed = u64::from(u32::MAX);
+    assert_eq!(grow(0), vec![1, 1]);
+    assert_eq!(grow(1), vec![1, 2]);
+    // Growing beyond the maximum size fails and leaves the size unchanged.
+    assert_eq!(grow(2), vec![failed, 2]);
+    assert_eq!(grow(u32::MAX), vec![failed, 2]);
+    assert_eq!(grow(1), vec![2, 3]);
+    assert_eq!(grow(1), vec![failed, 3]);
+    assert_eq!(ctx.memory(mem).unwrap().data().len(), 3 * PAGE_SIZE);
+}
+
+#[test]
+fn resource_limits_are_enforced() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(func_type).unwrap();
+    let mut table_builder = builder.table_section().unwrap();
+    let table = table_builder.push_table(TableDecl::new(4, None)).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let mem = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, None))
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let body = {
+        let mut b = FunctionBody::build(func, res);
+        b.body().unwrap();
+        let delta = b.input_var(0).unwrap();
+        let delta = b.read_var(delta).unwrap();
+        let v0 = b.ins().unwrap().memory_grow(mem, delta).unwrap();
+        b.ins().unwrap().return_values([v0].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(func, body).unwrap();
+    let module = builder.finalize().unwrap();
+    let config = |max_memory_pages, max_table_size| {
+        let mut limits = ResourceLimits::new();
+        limits.set_max_memory_pages(max_memory_pages);
+        limits.set_max_table_size(max_table_size);
+        let mut config = ExecutionConfig::new();
+        config.set_resource_limits(limits);
+        config
+    };
+    let create = |config| {
+        EvaluationContext::with_config(&module, &VecMemoryCreator, config)
+    };
+    // Initial sizes exceeding the limits fail to instantiate.
+    assert_eq!(
+        create(config(0, 4)).err(),
+        Some(InterpretationError::MemoryLimitExceeded {
+            memory: mem,
+            pages: 1,
+            limit: 0,
+        })
+    );
+    assert_eq!(
+        create(config(1, 3)).err(),
+        Some(InterpretationError::TableLimitExceeded {
+            table,
+            size: 4,
+            limit: 3,
+        })
+    );
+    // Linear memories cannot grow beyond the limits.
+    let mut ctx = create(config(2, 4)).unwrap();
+    let mut grow = |delta: u64| {
+        let mut results = Vec::new();
+        ctx.evaluate_function(func, [delta].iter().copied(), |result| {
+            results.push(result)
+        })
+        .unwrap();
+        results
+    };
+    assert_eq!(grow(1), vec![1]);
+    assert_eq!(grow(1), vec![u64::from(u32::MAX)]);
+}
+
+#[test]
+fn traps_and_panics_unwind_frames() {
+    #[derive(Debug)]
+    struct PanickingMemory {
+        memory: VecMemory,
+        armed: Arc<AtomicBool>,
+    }
+    impl LinearMemory for PanickingMemory {
+        fn pages(&self) -> u32 {
+            self.memory.pages()
+        }
+        fn maximum_pages(&self) -> Option<u32> {
+            self.memory.maximum_pages()
+        }
+        fn grow(&mut self, delta: u32) -> Option<u32> {
+            self.memory.grow(delta)
+        }
+        fn data(&self) -> &[u8] {
+            self.memory.data()
+        }
+        fn data_mut(&mut self) -> &mut [u8] {
+            assert!(!self.armed.load(Ordering::SeqCst), "host panic");
+            self.memory.data_mut()
+        }
+    }
+    #[derive(Debug, Default)]
+    struct PanickingCreator {
+        armed: Arc<AtomicBool>,
+    }
+    impl MemoryCreator for PanickingCreator {
+        fn create_memory(
+            &self,
+            decl: &LinearMemoryDecl,
+        ) -> Result<Box<dyn LinearMemory>, InterpretationError> {
+            Ok(Box::new(PanickingMemory {
+                memory: VecMemory::new(
+                    decl.initial_pages(),
+                    decl.maximum_pages(),
+                )?,
+                armed: self.armed.clone(),
+            }))
+        }
+    }
+    let i32_type = Type::from(IntType::I32);
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let func_type = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(i32_type);
+        b.push_output(i32_type);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let outer = function_builder.push_function(func_type).unwrap();
+    let inner = function_builder.push_function(func_type).unwrap();
+    let mut memory_builder = builder.memory_section().unwrap();
+    let mem = memory_builder
+        .push_memory(LinearMemoryDecl::new(1, None))
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    // outer(pos) = inner(pos) + 1
+    let body = {
+        let mut b = FunctionBody::build(outer, res);
+        b.body().unwrap();
+        let pos = b.input_var(0).unwrap();
+        let pos = b.read_var(pos).unwrap();
+        let instr = b.ins().unwrap().call(inner, vec![pos]).unwrap();
+        let v0 = b.instr_values(instr).unwrap()[0];
+        let v1 = b.ins().unwrap().constant(IntConst::I32(1)).unwrap();
+        let v2 = b.ins().unwrap().iadd(IntType::I32, v0, v1).unwrap();
+        b.ins().unwrap().return_values([v2].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(outer, body).unwrap();
+    // inner(pos) = load(heap_addr(pos))
+    let body = {
+        let mut b = FunctionBody::build(inner, res);
+        b.body().unwrap();
+        let pos = b.input_var(0).unwrap();
+        let pos = b.read_var(pos).unwrap();
+        let ptr = b.ins().unwrap().heap_addr(mem, pos, 4.into()).unwrap();
+        let v0 = b.ins().unwrap().load(ptr, 0.into(), i32_type).unwrap();
+        b.ins().unwrap().return_values([v0].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(inner, body).unwrap();
+    let module = builder.finalize().unwrap();
+    let creator = PanickingCreator::default();
+    let mut ctx =
+        EvaluationContext::with_memory_creator(&module, &creator).unwrap();
+    ctx.memory_mut(mem).unwrap().data_mut()[8] = 41;
+    let evaluate = |ctx: &mut EvaluationContext, pos: u32| {
+        let mut results = Vec::new();
+        ctx.evaluate_function(
+            outer,
+            [u64::from(pos)].iter().copied(),
+            |result| results.push(result),
+        )
+        .map(|_| results)
+    };
+    assert_eq!(evaluate(&mut ctx, 8), Ok(vec![42]));
+    // A trap in the callee unwinds the frames of the caller and callee.
+    let last = PAGE_SIZE as u32 - 3;
+    assert_eq!(
+        evaluate(&mut ctx, last),
+        Err(InterpretationError::MemoryOutOfBounds {
+            memory: mem,
+            address: u64::from(last),
+            len: 4,
+        })
+    );
+    assert_eq!(ctx.call_depth(), 0);
+    assert_eq!(evaluate(&mut ctx, 8), Ok(vec![42]));
+    // A panic of host code propagates to the caller of the evaluation as is.
+    // Destructors of host values run and the frames are unwound as well.
+    let token = Rc::new(());
+    creator.armed.store(true, Ordering::SeqCst);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let token = token.clone();
+        ctx.evaluate_function(outer, [8].iter().copied(), move |_| {
+            let _ = &token;
+        })
+    }));
+    assert!(result.is_err());
+    assert_eq!(Rc::strong_count(&token), 1);
+    creator.armed.store(false, Ordering::SeqCst);
+    assert_eq!(ctx.call_depth(), 0);
+    assert_eq!(evaluate(&mut ctx, 8), Ok(vec![42]));
+}
+
+#[test]
+fn call_indirect_works() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let unary_type = || {
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    };
+    let t0 = type_builder.push_type(unary_type());
+    // Structurally identical function types match each other.
+    let t1 = type_builder.push_type(unary_type());
+    let t2 = type_builder.push_type({
+        let mut b = FunctionType::build();
+        b.push_input(IntType::I32);
+        b.push_input(IntType::I32);
+        b.push_output(IntType::I32);
+        b.finalize()
+    });
+    let mut function_builder = builder.function_section().unwrap();
+    let inc = function_builder.push_function(t0).unwrap();
+    let dec = function_builder.push_function(t1).unwrap();
+    let dispatch = function_builder.push_function(t2).unwrap();
+    let mut table_builder = builder.table_section().unwrap();
+    let table = table_builder.push_table(TableDecl::new(4, None)).unwrap();
+    let mut element_builder = builder.table_element_section().unwrap();
+    element_builder
+        .push_element(
+            table,
+            InitExpr::Const(IntConst::I32(0).into()),
+            [inc, dec, dispatch].iter().copied(),
+        )
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    for &(func, delta) in &[(inc, 1), (dec, -1)] {
+        let mut b = FunctionBody::build(func, res);
+        b.body().unwrap();
+        let x = b.input_var(0).unwrap();
+        let v0 = b.read_var(x).unwrap();
+        let v1 = b.ins().unwrap().constant(IntConst::I32(delta)).unwrap();
+        let v2 = b.ins().unwrap().iadd(IntType::I32, v0, v1).unwrap();
+        b.ins().unwrap().return_values([v2].iter().copied()).unwrap();
+        body_builder.push_body(func, b.finalize().unwrap()).unwrap();
+    }
+    // dispatch(index, x) = table[index](x)
+    let body = {
+        let mut b = FunctionBody::build(dispatch, res);
+        b.body().unwrap();
+        let index = b.input_var(0).unwrap();
+        let x = b.input_var(1).unwrap();
+        let index = b.read_var(index).unwrap();
+        let x = b.read_var(x).unwrap();
+        let instr = b
+            .ins()
+            .unwrap()
+            .call_indirect(table, t1, index, vec![x])
+            .unwrap();
+        let v0 = b.instr_values(instr).unwrap()[0];
+        b.ins().unwrap().return_values([v0].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(dispatch, body).unwrap();
+    let module = builder.finalize().unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    let mut evaluate = |index: u64| {
+        let mut results = Vec::new();
+        ctx.evaluate_function(dispatch, [index, 5].iter().copied(), |result| {
+            results.push(result)
+        })
+        .map(|_| results)
+    };
+    assert_eq!(evaluate(0), Ok(vec![6]));
+    assert_eq!(evaluate(1), Ok(vec![4]));
+    assert_eq!(
+        evaluate(2),
+        Err(InterpretationError::UnmatchingIndirectCallType {
+            func: dispatch,
+            func_type: t1,
+        })
+    );
+    assert_eq!(
+        evaluate(3),
+        Err(InterpretationError::UninitializedTableElement { table, index: 3 })
+    );
+    assert_eq!(
+        evaluate(4),
+        Err(InterpretationError::TableOutOfBounds { table, index: 4 })
+    );
+}
+
+#[test]
+fn element_segments_are_validated() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let t0 = type_builder.push_type(FunctionType::build().finalize());
+    let mut function_builder = builder.function_section().unwrap();
+    let func = function_builder.push_function(t0).unwrap();
+    let mut table_builder = builder.table_section().unwrap();
+    let table = table_builder.push_table(TableDecl::new(2, None)).unwrap();
+    let mut element_builder = builder.table_element_section().unwrap();
+    let offset = |offset| InitExpr::Const(IntConst::I32(offset).into());
+    // Functions outside of the function space are rejected upfront.
+    let invalid = Func::from_raw(RawIdx::from_u32(5));
+    assert!(element_builder
+        .push_element(table, offset(0), [func, invalid].iter().copied())
+        .is_err());
+    // Offsets are validated against the table size at instantiation.
+    element_builder
+        .push_element(table, offset(1), [func, func].iter().copied())
+        .unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    let mut b = FunctionBody::build(func, res);
+    b.body().unwrap();
+    b.ins().unwrap().return_values(None).unwrap();
+    body_builder.push_body(func, b.finalize().unwrap()).unwrap();
+    let module = builder.finalize().unwrap();
+    let ctx =
+        EvaluationContext::with_memory_creator(&module, &VecMemoryCreator);
+    assert_eq!(
+        ctx.err(),
+        Some(InterpretationError::TableElementsOutOfBounds {
+            table,
+            offset: 1,
+            len: 2,
+            size: 2,
+        })
+    );
+}
+
+#[test]
+fn float_instructions_follow_wasm_semantics() {
+    let f32_type = Type::from(FloatType::F32);
+    let f64_type = Type::from(FloatType::F64);
+    type BinaryOp =
+        fn(InstructionBuilder, Value, Value) -> Result<Value, module::Error>;
+    let binary = |op: BinaryOp| {
+        module_with_func(&[f64_type, f64_type], &[f64_type], |b| {
+            let lhs = b.read_var(b.input_var(0).unwrap())?;
+            let rhs = b.read_var(b.input_var(1).unwrap())?;
+            let result = op(b.ins()?, lhs, rhs)?;
+            b.ins()?.return_values([result].iter().copied())?;
+            Ok(())
+        })
+    };
+    let eval = |(func, module): &(Func, Module), inputs: &[f64]| {
+        let inputs = inputs
+            .iter()
+            .map(|&input| Const::from(FloatConst::F64(input.into())))
+            .collect::<Vec<_>>();
+        f64::from_bits(evaluate_func(module, *func, &inputs)[0])
+    };
+    // Division by zero does not trap.
+    let div = binary(|ins, lhs, rhs| ins.fdiv(FloatType::F64, lhs, rhs));
+    assert_eq!(eval(&div, &[1.0, 0.0]), f64::INFINITY);
+    assert_eq!(eval(&div, &[1.0, -0.0]), f64::NEG_INFINITY);
+    assert!(eval(&div, &[0.0, 0.0]).is_nan());
+    // Minimum and maximum propagate NaN and order `-0.0 < +0.0`.
+    let min = binary(|ins, lhs, rhs| ins.fmin(FloatType::F64, lhs, rhs));
+    let max = binary(|ins, lhs, rhs| ins.fmax(FloatType::F64, lhs, rhs));
+    assert!(eval(&min, &[f64::NAN, 1.0]).is_nan());
+    assert!(eval(&max, &[1.0, f64::NAN]).is_nan());
+    assert!(eval(&min, &[0.0, -0.0]).is_sign_negative());
+    assert!(eval(&max, &[-0.0, 0.0]).is_sign_positive());
+    assert_eq!(eval(&min, &[-1.0, 2.0]), -1.0);
+    assert_eq!(eval(&max, &[-1.0, 2.0]), 2.0);
+    // Rounding to nearest resolves ties to even.
+    let (nearest, module) = module_with_func(&[f32_type], &[f32_type], |b| {
+        let source = b.read_var(b.input_var(0).unwrap())?;
+        let result = b.ins()?.fnearest(FloatType::F32, source)?;
+        b.ins()?.return_values([result].iter().copied())?;
+        Ok(())
+    });
+    for &(input, expected) in &[
+        (0.5_f32, 0.0),
+        (1.5, 2.0),
+        (2.5, 2.0),
+        (-2.5, -2.0),
+        (2.6, 3.0),
+    ] {
+        let input = Const::from(FloatConst::F32(input.into()));
+        let result = evaluate_func(&module, nearest, &[input]);
+        assert_eq!(f32::from_bits(result[0] as u32), expected);
+    }
+    // Conversions to integers either trap or saturate.
+    let float_to_int = |saturating: bool| {
+        module_with_func(&[f64_type], &[IntType::I32.into()], |b| {
+            let source = b.read_var(b.input_var(0).unwrap())?;
+            let result = b.ins()?.float_to_int(
+                FloatType::F64,
+                IntType::I32,
+                true,
+                source,
+                saturating,
+            )?;
+            b.ins()?.return_values([result].iter().copied())?;
+            Ok(())
+        })
+    };
+    let convert = |(func, module): &(Func, Module), input: f64| {
+        let mut ctx = EvaluationContext::new(module);
+        let mut results = Vec::new();
+        ctx.evaluate_function(
+            *func,
+            [input.to_bits()].iter().copied(),
+            |result| results.push(result),
+        )
+        .map(|_| results[0] as u32 as i32)
+    };
+    let trunc = float_to_int(false);
+    let trunc_sat = float_to_int(true);
+    for &(input, expected) in &[
+        (-1.9, -1),
+        (2_147_483_647.9, i32::MAX),
+        (-2_147_483_648.9, i32::MIN),
+    ] {
+        assert_eq!(convert(&trunc, input), Ok(expected));
+        assert_eq!(convert(&trunc_sat, input), Ok(expected));
+    }
+    for &(input, saturated, code) in &[
+        (f64::NAN, 0, TrapCode::BadConversionToInteger),
+        (2_147_483_648.0, i32::MAX, TrapCode::IntegerOverflow),
+        (-2_147_483_649.0, i32::MIN, TrapCode::IntegerOverflow),
+        (f64::NEG_INFINITY, i32::MIN, TrapCode::IntegerOverflow),
+    ] {
+        assert_eq!(
+            convert(&trunc, input),
+            Err(InterpretationError::EvaluationHasTrapped { code })
+        );
+        assert_eq!(convert(&trunc_sat, input), Ok(saturated));
+    }
+    // Conversions from integers round to nearest.
+    let int_to_float = |signed: bool, src_type: IntType| {
+        module_with_func(&[src_type.into()], &[f32_type], |b| {
+            let source = b.read_var(b.input_var(0).unwrap())?;
+            let result = b.ins()?.int_to_float(
+                signed,
+                src_type,
+                FloatType::F32,
+                source,
+            )?;
+            b.ins()?.return_values([result].iter().copied())?;
+            Ok(())
+        })
+    };
+    let (i32_type, i64_type) = (IntType::I32, IntType::I64);
+    for &(signed, src_type, input, expected) in &[
+        (true, i32_type, IntConst::I32(-1), -1.0_f32),
+        (false, i32_type, IntConst::I32(-1), 4_294_967_296.0),
+        (true, i32_type, IntConst::I32(16_777_217), 16_777_216.0),
+        (true, i32_type, IntConst::I32(16_777_219), 16_777_220.0),
+        (true, i64_type, IntConst::I64(i64::MIN), -9.223_372e18),
+        (false, i64_type, IntConst::I64(-1), 1.844_674_4e19),
+    ] {
+        let (func, module) = int_to_float(signed, src_type);
+        let result = evaluate_func(&module, func, &[input.into()]);
+        assert_eq!(f32::from_bits(result[0] as u32), expected);
+    }
+}
+
+#[test]
+fn trap_codes_work() {
+    let i32_type = Type::from(IntType::I32);
+    let (func, module) = module_with_func(&[], &[], |b| {
+        b.ins()?.trap(TrapCode::IndirectCallTypeMismatch)?;
+        Ok(())
+    });
+    let mut ctx = EvaluationContext::new(&module);
+    let error = ctx.evaluate_function(func, Vec::new(), |_| ()).unwrap_err();
+    assert_eq!(
+        error,
+        InterpretationError::EvaluationHasTrapped {
+            code: TrapCode::IndirectCallTypeMismatch
+        }
+    );
+    assert_eq!(error.trap_code(), Some(TrapCode::IndirectCallTypeMismatch));
+    // Runtime errors of trapping instructions report their trap code.
+    let (func, module) =
+        module_with_func(&[i32_type, i32_type], &[i32_type], |b| {
+            let lhs = b.read_var(b.input_var(0).unwrap())?;
+            let rhs = b.read_var(b.input_var(1).unwrap())?;
+            let result = b.ins()?.sdiv(IntType::I32, lhs, rhs)?;
+            b.ins()?.return_values([result].iter().copied())?;
+            Ok(())
+        });
+    let mut ctx = EvaluationContext::new(&module);
+    let error = ctx.evaluate_function(func, vec![1, 0], |_| ()).unwrap_err();
+    assert_eq!(error.trap_code(), Some(TrapCode::IntegerDivisionByZero));
+    assert_eq!(
+        InterpretationError::UnknownExportedFunction {
+            name: "f".to_string()
+        }
+        .trap_code(),
+        None
+    );
+}
+
+#[test]
+fn traps_report_source_spans() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func (export "div") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.div_u
+            )
+        )
+    "#,
+    )
+    .unwrap();
+    let mut buffer = Vec::new();
+    let module = wasm::parse(&mut &wasm[..], &mut buffer).unwrap();
+    // The `i32.div_u` operator directly follows `local.get 0; local.get 1`.
+    let operators = [0x20, 0x00, 0x20, 0x01, 0x6E];
+    let start = wasm
+        .windows(operators.len())
+        .position(|window| window == operators)
+        .unwrap()
+        + 4;
+    let span = SourceSpan::new(start, start + 1);
+    let func = module.get_exported_function("div").unwrap();
+    let function = module.get_function(func).unwrap();
+    let mut config = DisplayConfig::default();
+    config.set_show_source_spans(true);
+    filecheck(
+        &function.display_with(config).to_string(),
+        &format!("// CHECK: udiv<i32> v0 v1 // at {}", span),
+    )
+    .unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    let args = [IntConst::I32(7).into(), IntConst::I32(2).into()];
+    assert_eq!(ctx.invoke("div", &args), Ok(vec![IntConst::I32(3).into()]));
+    assert_eq!(ctx.trap_location(), None);
+    let args = [IntConst::I32(1).into(), IntConst::I32(0).into()];
+    let error = ctx.invoke("div", &args).unwrap_err();
+    assert_eq!(error.trap_code(), Some(TrapCode::IntegerDivisionByZero));
+    let location = *ctx.trap_location().unwrap();
+    assert_eq!(location.func(), func);
+    assert_eq!(location.source_span(), Some(span));
+    assert_eq!(
+        function.body().source_span(location.instr()),
+        Some(span)
+    );
+    // Instructions built without a source span have no source span.
+    let (func, module) = module_with_func(&[], &[], |b| {
+        b.ins()?.trap(TrapCode::Unreachable)?;
+        Ok(())
+    });
+    let mut ctx = EvaluationContext::new(&module);
+    assert!(ctx.evaluate_function(func, Vec::new(), |_| ()).is_err());
+    let location = ctx.trap_location().unwrap();
+    assert_eq!(location.func(), func);
+    assert_eq!(location.source_span(), None);
+}
+
+#[test]
+fn import_renames_work() {
+    let i32 = Type::from(IntType::I32);
+    let (module, a, b) = {
+        let mut builder = Module::build();
+        let mut imports = builder.import_section().unwrap();
+        let a = imports.import_global(
+            ImportName::new("env", "a"),
+            GlobalVariable::new(i32, false),
+        );
+        let b = imports.import_global(
+            ImportName::new("env", "b"),
+            GlobalVariable::new(i32, false),
+        );
+        (builder.finalize().unwrap(), a, b)
+    };
+    let mut renames = ImportRenames::new();
+    renames.rename_module("env", "host_v2").rename_import(
+        ImportName::new("env", "b"),
+        ImportName::new("stub", "b"),
+    );
+    assert_eq!(
+        renames.resolve(&ImportName::new("env", "a")),
+        ImportName::new("host_v2", "a")
+    );
+    assert_eq!(
+        renames.resolve(&ImportName::new("other", "a")),
+        ImportName::new("other", "a")
+    );
+    let mut ctx = EvaluationContext::new(&module);
+    ctx.set_import_renames(renames);
+    let handle = |value| GlobalHandle::new(IntConst::I32(value).into(), false);
+    // Imports are only resolved by their renamed import names.
+    assert!(matches!(
+        ctx.link_global(&ImportName::new("env", "a"), handle(1)),
+        Err(InterpretationError::UnknownImportedGlobal { .. })
+    ));
+    ctx.link_global(&ImportName::new("host_v2", "a"), handle(1))
+        .unwrap();
+    // Renames of individual imports take precedence over module renames.
+    assert!(matches!(
+        ctx.link_global(&ImportName::new("host_v2", "b"), handle(2)),
+        Err(InterpretationError::UnknownImportedGlobal { .. })
+    ));
+    ctx.link_global(&ImportName::new("stub", "b"), handle(2))
+        .unwrap();
+    assert_eq!(ctx.global(a).unwrap().get(), IntConst::I32(1).into());
+    assert_eq!(ctx.global(b).unwrap().get(), IntConst::I32(2).into());
+}
+
+#[test]
+fn debugger_works() {
+    let i32_type = Type::from(IntType::I32);
+    let (func, module) = module_with_func(&[i32_type], &[i32_type], |b| {
+        let input = b.read_var(b.input_var(0).unwrap())?;
+        let one = b.ins()?.constant(IntConst::I32(1))?;
+        let sum = b.ins()?.iadd(IntType::I32, input, one)?;
+        let product = b.ins()?.imul(IntType::I32, sum, sum)?;
+        b.ins()?.return_values([product].iter().copied())?;
+        Ok(())
+    });
+    let mut ctx = EvaluationContext::new(&module);
+    let mut debugger = Debugger::new(&mut ctx, func, vec![2]).unwrap();
+    let mut steps = Vec::new();
+    while let Some(step) = debugger.step().unwrap() {
+        assert_eq!(step.func(), func);
+        steps.push(step);
+    }
+    assert_eq!(debugger.outputs(), Some(&[9][..]));
+    let written = steps
+        .iter()
+        .map(|step| step.written().iter().map(|(_, bits)| *bits).collect())
+        .collect::<Vec<Vec<u64>>>();
+    assert_eq!(written, vec![vec![1], vec![3], vec![9], vec![]]);
+    drop(debugger);
+    // Breakpoints stop the evaluation before the instruction is executed.
+    let mut debugger = Debugger::new(&mut ctx, func, vec![4]).unwrap();
+    debugger.set_breakpoint(func, steps[2].instr());
+    let last = debugger.run().unwrap().unwrap();
+    assert_eq!(last.instr(), steps[1].instr());
+    assert_eq!(
+        debugger.next_instr(),
+        Some((func, steps[2].block(), steps[2].instr()))
+    );
+    assert_eq!(debugger.run().unwrap().unwrap().instr(), steps[3].instr());
+    assert_eq!(debugger.outputs(), Some(&[25][..]));
+    assert_eq!(debugger.step(), Ok(None));
+    drop(debugger);
+    // Dropping an unfinished debugger unwinds its function frames.
+    let mut debugger = Debugger::new(&mut ctx, func, vec![4]).unwrap();
+    debugger.step().unwrap();
+    drop(debugger);
+    assert_eq!(ctx.call_depth(), 0);
+}
+
+#[test]
+fn trace_hook_works() {
+    let i32_type = Type::from(IntType::I32);
+    let (func, module) = module_with_func(&[i32_type], &[i32_type], |b| {
+        let input = b.read_var(b.input_var(0).unwrap())?;
+        let one = b.ins()?.constant(IntConst::I32(1))?;
+        let sum = b.ins()?.iadd(IntType::I32, input, one)?;
+        let product = b.ins()?.imul(IntType::I32, sum, sum)?;
+        b.ins()?.return_values([product].iter().copied())?;
+        Ok(())
+    });
+    let trace = Arc::new(Mutex::new(Vec::new()));
+    let mut ctx = EvaluationContext::new(&module);
+    ctx.set_trace_hook({
+        let trace = trace.clone();
+        move |_, instruction, operands| {
+            let name = instruction.descriptor().name();
+            trace.lock().unwrap().push((name, operands.to_vec()))
+        }
+    });
+    let inputs = [IntConst::I32(2).into()];
+    assert_eq!(evaluate_func_in_ctx(&mut ctx, func, &inputs), [9]);
+    assert_eq!(
+        *trace.lock().unwrap(),
+        vec![
+            ("const", vec![]),
+            ("iadd", vec![2, 1]),
+            ("imul", vec![3, 3]),
+            ("return", vec![9]),
+        ]
+    );
+    // No instructions are traced once the hook has been cleared.
+    ctx.clear_trace_hook();
+    evaluate_func_in_ctx(&mut ctx, func, &inputs);
+    assert_eq!(trace.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn execution_statistics_work() {
+    let i32_type = Type::from(IntType::I32);
+    let (func, module) = module_with_func(&[i32_type], &[i32_type], |b| {
+        let input = b.read_var(b.input_var(0).unwrap())?;
+        let one = b.ins()?.constant(IntConst::I32(1))?;
+        let sum = b.ins()?.iadd(IntType::I32, input, one)?;
+        let product = b.ins()?.imul(IntType::I32, sum, sum)?;
+        b.ins()?.return_values([product].iter().copied())?;
+        Ok(())
+    });
+    let entry_block = module.get_function(func).unwrap().body().entry_block();
+    let mut ctx = EvaluationContext::new(&module);
+    let inputs = [IntConst::I32(2).into()];
+    evaluate_func_in_ctx(&mut ctx, func, &inputs);
+    assert!(ctx.statistics().is_none());
+    ctx.enable_statistics();
+    evaluate_func_in_ctx(&mut ctx, func, &inputs);
+    evaluate_func_in_ctx(&mut ctx, func, &inputs);
+    let stats = ctx.take_statistics().unwrap();
+    assert_eq!(stats.total_instrs(), 8);
+    assert_eq!(stats.kind_count("iadd"), 2);
+    assert_eq!(stats.kind_count("sdiv"), 0);
+    assert_eq!(stats.kinds().count(), 4);
+    let block = stats.block(func, entry_block).unwrap();
+    assert_eq!((block.entries(), block.instrs()), (2, 8));
+    assert_eq!(stats.hot_blocks(1), vec![(func, entry_block, *block)]);
+    let mut merged = stats.clone();
+    merged.merge(&stats);
+    assert_eq!(merged.total_instrs(), 16);
+    assert!(ctx.statistics().is_none());
+}
+
+#[test]
+fn constant_time_works() {
+    let i32_type = Type::from(IntType::I32);
+    let secret = Cell::new(None);
+    let leaked = Cell::new(None);
+    let (func, module) = module_with_edited_func(
+        &[i32_type, i32_type],
+        &[i32_type],
+        |b| {
+            let input = b.read_var(b.input_var(0).unwrap())?;
+            let public = b.read_var(b.input_var(1).unwrap())?;
+            let sum = b.ins()?.iadd(IntType::I32, input, public)?;
+            let quotient = b.ins()?.sdiv(IntType::I32, sum, public)?;
+            let square = b.ins()?.imul(IntType::I32, public, public)?;
+            let less = b.ins()?.icmp(
+                IntType::I32,
+                CompareIntOp::Slt,
+                input,
+                public,
+            )?;
+            let result = b.ins()?.select(i32_type, less, quotient, square)?;
+            b.ins()?.return_values([result].iter().copied())?;
+            secret.set(Some(input));
+            leaked.set(Some(sum));
+            Ok(())
+        },
+        |body| {
+            // Only the division by a secret dividend leaks the secret.
+            let leaks = body.check_constant_time(secret.get());
+            assert_eq!(leaks.len(), 1);
+            assert_eq!(Some(leaks[0].value()), leaked.get());
+            assert!(body.check_constant_time(None).is_empty());
+            Ok(())
+        },
+    );
+    let mut config = ExecutionConfig::new();
+    config.set_constant_time(true);
+    let cases = [(5, 12, 1), (20, 12, 144), (-7, 3, -1)];
+    for &(input, public, expected) in &cases {
+        let inputs =
+            [IntConst::I32(input).into(), IntConst::I32(public).into()];
+        let mut ctx = EvaluationContext::new(&module);
+        let regular = evaluate_func_in_ctx(&mut ctx, func, &inputs);
+        ctx.set_execution_config(config.clone());
+        assert!(ctx.execution_config().constant_time());
+        let constant_time = evaluate_func_in_ctx(&mut ctx, func, &inputs);
+        assert_eq!(regular, constant_time);
+        assert_eq!(
+            bits_into_const(&module, func, constant_time),
+            vec![IntConst::I32(expected).into()]
+        );
+    }
+}
+
+#[test]
+fn constant_time_tracks_block_params() {
+    let i32_type = Type::from(IntType::I32);
+    let x = Variable::from_raw(RawIdx::from_u32(2));
+    let secret = Cell::new(None);
+    module_with_edited_func(
+        &[i32_type, i32_type],
+        &[i32_type],
+        |b| {
+            b.declare_variables(1, i32_type)?;
+            b.body()?;
+            let then_block = b.create_block()?;
+            let exit_block = b.create_block()?;
+            let input = b.read_var(b.input_var(0).unwrap())?;
+            let public = b.read_var(b.input_var(1).unwrap())?;
+            b.write_var(x, public)?;
+            let zero = b.ins()?.constant(IntConst::I32(0))?;
+            let is_nonzero =
+                b.ins()?.icmp(IntType::I32, CompareIntOp::Ne, public, zero)?;
+            b.ins()?.if_then_else(is_nonzero, then_block, exit_block)?;
+            b.switch_to_block(then_block)?;
+            b.seal_block(then_block)?;
+            b.write_var(x, input)?;
+            b.ins()?.br(exit_block)?;
+            b.switch_to_block(exit_block)?;
+            b.seal_block(exit_block)?;
+            let divisor = b.read_var(x)?;
+            let quotient = b.ins()?.sdiv(IntType::I32, public, divisor)?;
+            b.ins()?.return_values([quotient].iter().copied())?;
+            secret.set(Some(input));
+            Ok(())
+        },
+        |body| {
+            // Passing the secret as branch argument does not leak it but
+            // dividing by the parameter receiving it does.
+            let exit_block = body.successors(body.entry_block())[1];
+            let param = body.block_params(exit_block)[0];
+            let leaks = body.check_constant_time(secret.get());
+            assert_eq!(leaks.len(), 1);
+            assert_eq!(leaks[0].value(), param);
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn register_type_checks_work() {
+    let mut builder = Module::build();
+    let mut type_builder = builder.type_section().unwrap();
+    let unary_type = |ty: IntType| {
+        let mut b = FunctionType::build();
+        b.push_input(ty);
+        b.push_output(ty);
+        b.finalize()
+    };
+    let narrow_type = type_builder.push_type(unary_type(IntType::I32));
+    let wide_type = type_builder.push_type(unary_type(IntType::I64));
+    let mut function_builder = builder.function_section().unwrap();
+    let narrow = function_builder.push_function(narrow_type).unwrap();
+    let wide = function_builder.push_function(wide_type).unwrap();
+    let caller = function_builder.push_function(wide_type).unwrap();
+    let (res, mut body_builder) = builder.code_section().unwrap();
+    // double(x) = x + x
+    let double = |func: Func| {
+        let mut b = FunctionBody::build(func, res);
+        b.body().unwrap();
+        let x = b.input_var(0).unwrap();
+        let v0 = b.read_var(x).unwrap();
+        let v1 = b.ins().unwrap().iadd(IntType::I64, v0, v0).unwrap();
+        b.ins().unwrap().return_values([v1].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(wide, double(wide)).unwrap();
+    // Simulates a miscompilation that reads an `i32` input as `i64`.
+    body_builder.push_body(narrow, double(wide)).unwrap();
+    // caller(x) = wide(x)
+    let body = {
+        let mut b = FunctionBody::build(caller, res);
+        b.body().unwrap();
+        let x = b.input_var(0).unwrap();
+        let x = b.read_var(x).unwrap();
+        let instr = b.ins().unwrap().call(wide, vec![x]).unwrap();
+        let v0 = b.instr_values(instr).unwrap()[0];
+        b.ins().unwrap().return_values([v0].iter().copied()).unwrap();
+        b.finalize().unwrap()
+    };
+    body_builder.push_body(caller, body).unwrap();
+    let module = builder.finalize().unwrap();
+    let mut ctx = EvaluationContext::new(&module);
+    let mut evaluate = |func: Func| {
+        let mut results = Vec::new();
+        ctx.evaluate_function(func, Some(21), |result| results.push(result))
+            .map(|_| results)
+    };
+    // Without checks the miscompilation silently operates on the bits.
+    assert_eq!(evaluate(narrow), Ok(vec![42]));
+    let mut config = ExecutionConfig::new();
+    config.set_check_register_types(true);
+    ctx.set_execution_config(config);
+    // Correctly typed registers pass the checks across calls.
+    let mut results = Vec::new();
+    ctx.evaluate_function(caller, Some(21), |result| results.push(result))
+        .unwrap();
+    assert_eq!(results, vec![42]);
+    let error = ctx
+        .evaluate_function(narrow, Some(21), |_| panic!("unexpected result"))
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        InterpretationError::UnmatchingRegisterType {
+            func,
+            expected: Type::Int(IntType::I64),
+            found: Type::Int(IntType::I32),
+            ..
+        } if func == narrow
+    ));
+    assert_eq!(ctx.call_depth(), 0);
+}
+
+#[test]
+fn integer_division_traps_follow_wasm_semantics() {
+    for &ty in &[IntType::I8, IntType::I16, IntType::I32, IntType::I64] {
+        let min = Const::from(match ty {
+            IntType::I8 => IntConst::I8(i8::MIN),
+            IntType::I16 => IntConst::I16(i16::MIN),
+            IntType::I32 => IntConst::I32(i32::MIN),
+            IntType::I64 => IntConst::I64(i64::MIN),
+        });
+        let minus_one = Const::from_bits64(ty.into(), u64::MAX);
+        let zero = Const::from_bits64(ty.into(), 0);
+        let ops = [
+            BinaryIntOp::Sdiv,
+            BinaryIntOp::Udiv,
+            BinaryIntOp::Srem,
+            BinaryIntOp::Urem,
+        ];
+        for &op in &ops {
+            let (func, module) =
+                module_with_func(&[ty.into(), ty.into()], &[ty.into()], |b| {
+                    let lhs = b.read_var(b.input_var(0).unwrap())?;
+                    let rhs = b.read_var(b.input_var(1).unwrap())?;
+                    let ins = b.ins()?;
+                    let result = match op {
+                        BinaryIntOp::Sdiv => ins.sdiv(ty, lhs, rhs)?,
+                        BinaryIntOp::Udiv => ins.udiv(ty, lhs, rhs)?,
+                        BinaryIntOp::Srem => ins.srem(ty, lhs, rhs)?,
+                        _ => ins.urem(ty, lhs, rhs)?,
+                    };
+                    b.ins()?.return_values([result].iter().copied())?;
+                    Ok(())
+                });
+            let mut ctx = EvaluationContext::new(&module);
+            let mut evaluate = |lhs: Const, rhs: Const| {
+                let mut results = Vec::new();
+                ctx.evaluate_function(
+                    func,
+                    vec![lhs.into_bits64(), rhs.into_bits64()],
+                    |result| results.push(result),
+                )
+                .map(|_| bits_into_const(&module, func, results))
+            };
+            assert_eq!(
+                evaluate(min, zero),
+                Err(InterpretationError::EvaluationHasTrapped {
+                    code: TrapCode::IntegerDivisionByZero,
+                })
+            );
+            // Unsigned the operands are `2^(n-1)` and the maximum `2^n - 1`.
+            let expected = match op {
+                BinaryIntOp::Sdiv => {
+                    Err(InterpretationError::EvaluationHasTrapped {
+                        code: TrapCode::IntegerOverflow,
+                    })
+                }
+                BinaryIntOp::Udiv | BinaryIntOp::Srem => Ok(vec![zero]),
+                _ => Ok(vec![min]),
+            };
+            assert_eq!(evaluate(min, minus_one), expected);
+        }
+    }
+}
+
+#[test]
+fn errors_provide_structured_diagnostics() {
+    let trap = InterpretationError::EvaluationHasTrapped {
+        code: TrapCode::StackOverflow,
+    };
+    let diagnostic = trap.to_diagnostic();
+    assert_eq!(diagnostic.code(), "interpreter.trap");
+    assert_eq!(diagnostic.parameter("code"), Some("stack_overflow"));
+    assert_eq!(diagnostic.message(), trap.to_string());
+    let import = InterpretationError::UnknownImportedGlobal {
+        name: ImportName::new("env", "counter"),
+    };
+    let diagnostic = import.to_diagnostic();
+    assert_eq!(diagnostic.code(), "interpreter.unknown_imported_global");
+    assert_eq!(
+        diagnostic.parameters(),
+        &[
+            ("module_name", "env".to_string()),
+            ("field_name", "counter".to_string()),
+        ]
+    );
+    // Errors of the module builder keep their code and add their context.
+    let phi = module::FunctionBuilderError::UnreachablePhi {
+        value: Value::from_raw(RawIdx::from_u32(3)),
+    };
+    let error =
+        module::Error::from(phi).with_context("while building the loop");
+    let diagnostic = error.to_diagnostic();
+    assert_eq!(diagnostic.code(), "builder.unreachable_phi");
+    assert_eq!(diagnostic.parameter("value"), Some("v3"));
+    assert_eq!(
+        diagnostic.parameter("context"),
+        Some("while building the loop")
+    );
+}
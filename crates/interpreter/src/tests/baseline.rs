@@ -0,0 +1,1381 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the baseline tier that directly interprets Wasm function bodies.
+//!
+//! Functions are parsed with an exhausted translation budget so that all of
+//! them are interpreter-only. Where the Wasm translator supports all used
+//! operators the results are also compared against the Runwell IR translation.
+
+use crate::{
+    check_equivalence,
+    EquivalenceChecker,
+    EvaluatePure,
+    Evaluation,
+    EvaluationContext,
+    ExecutionConfig,
+    InterpretationError,
+    Outcome,
+    Snapshot,
+    VecMemoryCreator,
+    DEFAULT_MAX_CALL_DEPTH,
+    PAGE_SIZE,
+};
+use entity::RawIdx;
+use ir::{
+    instr::{Instruction, InstructionDescriptor, TrapCode},
+    primitive::{
+        Const,
+        Func,
+        FuncType,
+        Global,
+        IntConst,
+        IntType,
+        Mem,
+        Table,
+        Type,
+        Value,
+    },
+    CostModel,
+    GasTable,
+    UniformCostModel,
+};
+use module::{CheckedArithmetic, Module, Overflow, ResourceLimits};
+use wasm::TranslationBudget;
+
+/// Parses the Wasm text format input using the given translation budget.
+fn parse_wat(wat: &str, budget: TranslationBudget) -> Module {
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut buffer = Vec::new();
+    wasm::parse_with_budget(&mut &wasm[..], &mut buffer, budget).unwrap()
+}
+
+/// Parses the Wasm text format input with only interpreter-only functions.
+fn parse_baseline(wat: &str) -> Module {
+    let mut budget = TranslationBudget::default();
+    budget.set_max_operators(0);
+    parse_wat(wat, budget)
+}
+
+/// Evaluates the function at index `func` with the given inputs.
+fn evaluate(
+    module: &Module,
+    func: u32,
+    inputs: &[u64],
+) -> Result<Vec<u64>, InterpretationError> {
+    let func = Func::from_raw(RawIdx::from_u32(func));
+    let mut ctx = EvaluationContext::new(module);
+    let mut results = Vec::new();
+    ctx.evaluate_function(func, inputs.iter().copied(), |result| {
+        results.push(result)
+    })?;
+    Ok(results)
+}
+
+#[test]
+fn loop_factorial_works() {
+    let module = parse_baseline(
+        r#"
+        (module
+            (func (param i64) (result i64) (local i64)
+                i64.const 1
+                local.set 1
+                block
+                    loop
+                        local.get 0
+                        i64.eqz
+                        br_if 1
+                        local.get 1
+                        local.get 0
+                        i64.mul
+                        local.set 1
+                        local.get 0
+                        i64.const 1
+                        i64.sub
+                        local.set 0
+                        br 0
+                    end
+                end
+                local.get 1
+            )
+        )
+    "#,
+    );
+    assert!(module.is_interpreter_only(Func::from_raw(RawIdx::from_u32(0))));
+    assert_eq!(evaluate(&module, 0, &[0]), Ok(vec![1]));
+    assert_eq!(evaluate(&module, 0, &[5]), Ok(vec![120]));
+    assert_eq!(evaluate(&module, 0, &[20]), Ok(vec![2432902008176640000]));
+}
+
+#[test]
+fn recursive_if_else_works() {
+    let module = parse_baseline(
+        r#"
+        (module
+            (func $fib (param i32) (result i32)
+                local.get 0
+                i32.const 2
+                i32.lt_u
+                if (result i32)
+                    local.get 0
+                else
+                    local.get 0
+                    i32.const 1
+                    i32.sub
+                    call $fib
+                    local.get 0
+                    i32.const 2
+                    i32.sub
+                    call $fib
+                    i32.add
+                end
+            )
+        )
+    "#,
+    );
+    let expected = [0, 1, 1, 2, 3, 5, 8, 13, 21, 34, 55];
+    for (n, &fib) in expected.iter().enumerate() {
+        assert_eq!(evaluate(&module, 0, &[n as u64]), Ok(vec![fib]));
+    }
+}
+
+#[test]
+fn branches_carrying_values_work() {
+    let module = parse_baseline(
+        r#"
+        (module
+            (func (param i32) (result i32)
+                block (result i32)
+                    i32.const 7
+                    local.get 0
+                    br_if 0
+                    drop
+                    i32.const 9
+                end
+            )
+            (func (param i32) (result i32)
+                block
+                    block
+                        block
+                            local.get 0
+                            br_table 0 1 2
+                        end
+                        i32.const 100
+                        return
+                    end
+                    i32.const 200
+                    return
+                end
+                i32.const 300
+            )
+        )
+    "#,
+    );
+    assert_eq!(evaluate(&module, 0, &[1]), Ok(vec![7]));
+    assert_eq!(evaluate(&module, 0, &[0]), Ok(vec![9]));
+    assert_eq!(evaluate(&module, 1, &[0]), Ok(vec![100]));
+    assert_eq!(evaluate(&module, 1, &[1]), Ok(vec![200]));
+    assert_eq!(evaluate(&module, 1, &[2]), Ok(vec![300]));
+    assert_eq!(evaluate(&module, 1, &[3]), Ok(vec![300]));
+    assert_eq!(evaluate(&module, 1, &[u32::MAX as u64]), Ok(vec![300]));
+}
+
+#[test]
+fn traps_work() {
+    let module = parse_baseline(
+        r#"
+        (module
+            (func
+                unreachable
+            )
+            (func (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.div_s
+            )
+            (func (param f32) (result i32)
+                local.get 0
+                i32.trunc_f32_s
+            )
+        )
+    "#,
+    );
+    let int_min = i32::MIN as u32 as u64;
+    let minus_one = -1_i32 as u32 as u64;
+    let trapped =
+        |code| Err(InterpretationError::EvaluationHasTrapped { code });
+    assert_eq!(evaluate(&module, 0, &[]), trapped(TrapCode::Unreachable));
+    assert_eq!(evaluate(&module, 1, &[7, 2]), Ok(vec![3]));
+    assert_eq!(
+        evaluate(&module, 1, &[1, 0]),
+        trapped(TrapCode::IntegerDivisionByZero)
+    );
+    assert_eq!(
+        evaluate(&module, 1, &[int_min, minus_one]),
+        trapped(TrapCode::IntegerOverflow)
+    );
+    let f32_bits = |value: f32| value.to_bits() as u64;
+    assert_eq!(
+        evaluate(&module, 2, &[f32_bits(-3.7)]),
+        Ok(vec![minus_one - 2])
+    );
+    assert_eq!(
+        evaluate(&module, 2, &[f32_bits(f32::NAN)]),
+        trapped(TrapCode::BadConversionToInteger)
+    );
+    assert_eq!(
+        evaluate(&module, 2, &[f32_bits(3e9)]),
+        trapped(TrapCode::IntegerOverflow)
+    );
+}
+
+#[test]
+fn baseline_matches_translation() {
+    let wat = r#"
+        (module
+            (func (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add
+                local.get 0
+                local.get 1
+                i32.sub
+                local.get 0
+                local.get 1
+                i32.lt_s
+                select
+                i32.const 3
+                i32.shl
+                local.get 1
+                i32.xor
+            )
+        )
+    "#;
+    let baseline = parse_baseline(wat);
+    let translated = parse_wat(wat, TranslationBudget::default());
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    assert!(baseline.is_interpreter_only(func));
+    assert!(!translated.is_interpreter_only(func));
+    let values = [0, 1, 2, 42, i32::MAX as u32, i32::MIN as u32, u32::MAX];
+    for &lhs in &values {
+        for &rhs in &values {
+            let inputs = [lhs as u64, rhs as u64];
+            assert_eq!(
+                evaluate(&baseline, 0, &inputs),
+                evaluate(&translated, 0, &inputs),
+            );
+        }
+    }
+}
+
+#[test]
+fn control_flow_matches_translation() {
+    let wat = r#"
+        (module
+            (func (param i32) (result i32)
+                block (result i32)
+                    i32.const 7
+                    local.get 0
+                    br_if 0
+                    drop
+                    i32.const 9
+                end
+            )
+            (func (param i32) (result i32)
+                block
+                    block
+                        block
+                            local.get 0
+                            br_table 0 1 2
+                        end
+                        i32.const 100
+                        return
+                    end
+                    i32.const 200
+                    br 1
+                    unreachable
+                end
+                i32.const 300
+            )
+            (func (param i32) (result i32) (local i32)
+                loop
+                    local.get 1
+                    local.get 0
+                    i32.add
+                    local.set 1
+                    local.get 0
+                    i32.const 1
+                    i32.sub
+                    local.tee 0
+                    i32.const 0
+                    i32.gt_s
+                    br_if 0
+                end
+                local.get 1
+                local.get 1
+                i32.const 10
+                i32.gt_u
+                if (param i32) (result i32)
+                    i32.const 1
+                    i32.add
+                end
+            )
+        )
+    "#;
+    let baseline = parse_baseline(wat);
+    let translated = parse_wat(wat, TranslationBudget::default());
+    for n in 0..3 {
+        let func = Func::from_raw(RawIdx::from_u32(n));
+        assert!(!translated.is_interpreter_only(func));
+        for &input in &[0, 1, 2, 3, 4, 5, u32::MAX as u64] {
+            assert_eq!(
+                evaluate(&baseline, n, &[input]),
+                evaluate(&translated, n, &[input]),
+            );
+        }
+    }
+}
+
+#[test]
+fn calls_between_tiers_work() {
+    let wat = r#"
+        (module
+            (func $inc (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add
+            )
+            (func $double_inc_twice (param i32) (result i32)
+                local.get 0
+                call $inc
+                call $inc
+                i32.const 2
+                i32.mul
+            )
+            (func (param i32) (result i32)
+                local.get 0
+                call $double_inc_twice
+            )
+        )
+    "#;
+    let mut budget = TranslationBudget::default();
+    budget.set_max_operators(4);
+    let module = parse_wat(wat, budget);
+    let func = |n| Func::from_raw(RawIdx::from_u32(n));
+    assert!(!module.is_interpreter_only(func(0)));
+    assert!(module.is_interpreter_only(func(1)));
+    assert!(!module.is_interpreter_only(func(2)));
+    for input in 0..10 {
+        let expected = (input + 2) * 2;
+        assert_eq!(evaluate(&module, 1, &[input]), Ok(vec![expected]));
+        assert_eq!(evaluate(&module, 2, &[input]), Ok(vec![expected]));
+    }
+}
+
+#[test]
+fn indirect_calls_work_in_baseline_tier() {
+    let wat = r#"
+        (module
+            (type $unop (func (param i32) (result i32)))
+            (table 4 funcref)
+            (elem (i32.const 0) $inc $dec $id)
+            (func $inc (type $unop)
+                local.get 0
+                i32.const 1
+                i32.add
+            )
+            (func $dec (type $unop)
+                local.get 0
+                i32.const 1
+                i32.sub
+            )
+            (func $id (param i64) (result i64)
+                local.get 0
+            )
+            (func (param i32 i32) (result i32)
+                nop
+                nop
+                nop
+                local.get 1
+                local.get 0
+                call_indirect (type $unop)
+            )
+        )
+    "#;
+    let mut budget = TranslationBudget::default();
+    budget.set_max_operators(4);
+    let mixed = parse_wat(wat, budget.clone());
+    let func = |n| Func::from_raw(RawIdx::from_u32(n));
+    assert!(!mixed.is_interpreter_only(func(0)));
+    assert!(mixed.is_interpreter_only(func(3)));
+    let table = Table::from_raw(RawIdx::from_u32(0));
+    let func_type = FuncType::from_raw(RawIdx::from_u32(0));
+    for module in &[parse_baseline(wat), parse_wat(wat, budget)] {
+        // Indirect calls behave the same as direct calls of the callees.
+        for &input in &[0, 1, u64::from(u32::MAX)] {
+            assert_eq!(
+                evaluate(module, 3, &[0, input]),
+                evaluate(&mixed, 0, &[input])
+            );
+            assert_eq!(
+                evaluate(module, 3, &[1, input]),
+                evaluate(&mixed, 1, &[input])
+            );
+        }
+        assert_eq!(
+            evaluate(module, 3, &[2, 0]),
+            Err(InterpretationError::UnmatchingIndirectCallType {
+                func: func(2),
+                func_type,
+            })
+        );
+        assert_eq!(
+            evaluate(module, 3, &[3, 0]),
+            Err(InterpretationError::UninitializedTableElement {
+                table,
+                index: 3,
+            })
+        );
+        assert_eq!(
+            evaluate(module, 3, &[4, 0]),
+            Err(InterpretationError::TableOutOfBounds { table, index: 4 })
+        );
+    }
+}
+
+#[test]
+fn fuel_metering_works() {
+    let wat = r#"
+        (module
+            (func (param i32) (result i32)
+                block
+                    loop
+                        local.get 0
+                        i32.eqz
+                        br_if 1
+                        local.get 0
+                        i32.const 1
+                        i32.sub
+                        local.set 0
+                        br 0
+                    end
+                end
+                local.get 0
+            )
+        )
+    "#;
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    for module in &[
+        parse_baseline(wat),
+        parse_wat(wat, TranslationBudget::default()),
+    ] {
+        let mut ctx = EvaluationContext::new(module);
+        let evaluate = |ctx: &mut EvaluationContext, input: u64| {
+            let mut results = Vec::new();
+            ctx.evaluate_function(func, [input].iter().copied(), |result| {
+                results.push(result)
+            })
+            .map(|_| results)
+        };
+        assert_eq!(ctx.remaining_fuel(), None);
+        ctx.set_fuel(1_000);
+        assert_eq!(evaluate(&mut ctx, 1), Ok(vec![0]));
+        let consumed_once = 1_000 - ctx.remaining_fuel().unwrap();
+        assert!(consumed_once > 0);
+        // Longer loops consume more fuel.
+        assert_eq!(evaluate(&mut ctx, 2), Ok(vec![0]));
+        let consumed_twice =
+            1_000 - consumed_once - ctx.remaining_fuel().unwrap();
+        assert!(consumed_twice > consumed_once);
+        // Running out of fuel fails the evaluation but keeps the context usable.
+        assert_eq!(
+            evaluate(&mut ctx, 1_000),
+            Err(InterpretationError::OutOfFuel)
+        );
+        assert_eq!(ctx.remaining_fuel(), Some(0));
+        ctx.set_fuel(consumed_once);
+        assert_eq!(evaluate(&mut ctx, 1), Ok(vec![0]));
+        assert_eq!(ctx.remaining_fuel(), Some(0));
+        ctx.disable_fuel();
+        assert_eq!(evaluate(&mut ctx, 1_000), Ok(vec![0]));
+    }
+}
+
+#[test]
+fn cost_model_determines_fuel_consumption() {
+    /// Charges control flow instructions ten times as much as others.
+    #[derive(Debug)]
+    struct ControlFlowCostModel;
+
+    impl CostModel for ControlFlowCostModel {
+        fn instruction_cost(&self, instruction: &Instruction) -> u64 {
+            if instruction.descriptor().effects().is_control_flow() {
+                return 10
+            }
+            1
+        }
+    }
+
+    /// Returns the fuel consumed by evaluating the function with the input.
+    fn consumed_fuel<C>(module: &Module, cost_model: C, input: u64) -> u64
+    where
+        C: CostModel + 'static,
+    {
+        let func = Func::from_raw(RawIdx::from_u32(0));
+        let mut ctx = EvaluationContext::new(module);
+        ctx.set_cost_model(cost_model);
+        ctx.set_fuel(1_000_000);
+        ctx.evaluate_function(func, [input].iter().copied(), |_| ())
+            .unwrap();
+        1_000_000 - ctx.remaining_fuel().unwrap()
+    }
+
+    let wat = r#"
+        (module
+            (func (param i32) (result i32)
+                block
+                    loop
+                        local.get 0
+                        i32.eqz
+                        br_if 1
+                        local.get 0
+                        i32.const 1
+                        i32.sub
+                        local.set 0
+                        br 0
+                    end
+                end
+                local.get 0
+            )
+        )
+    "#;
+    let baseline = parse_baseline(wat);
+    let translated = parse_wat(wat, TranslationBudget::default());
+    for module in &[&baseline, &translated] {
+        let uniform = consumed_fuel(module, UniformCostModel::default(), 3);
+        assert_eq!(
+            consumed_fuel(module, UniformCostModel::new(3), 3),
+            3 * uniform
+        );
+    }
+    // Untranslated operators of the baseline tier cost a single unit by default.
+    assert_eq!(
+        consumed_fuel(&baseline, ControlFlowCostModel, 3),
+        consumed_fuel(&baseline, UniformCostModel::default(), 3),
+    );
+    assert!(
+        consumed_fuel(&translated, ControlFlowCostModel, 3)
+            > consumed_fuel(&translated, UniformCostModel::default(), 3)
+    );
+}
+
+#[test]
+fn gas_table_charges_memory_growth() {
+    let module = parse_wat(
+        r#"
+        (module
+            (memory 1 10)
+            (func (param i32) (result i32)
+                local.get 0
+                memory.grow
+            )
+        )
+    "#,
+        TranslationBudget::default(),
+    );
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    let mem = Mem::from_raw(RawIdx::from_u32(0));
+    let mut table = GasTable::new(2);
+    table.set_memory_page_cost(100);
+    // Returns are free so that memory growth is the last charged instruction.
+    let ret = InstructionDescriptor::from_name("return").unwrap();
+    table.set_cost(ret, 0);
+    let mut ctx = EvaluationContext::new(&module);
+    ctx.set_cost_model(table);
+    ctx.set_fuel(1_000_000);
+    let grow = |ctx: &mut EvaluationContext, pages: u64| {
+        let fuel = ctx.remaining_fuel().unwrap();
+        let mut results = Vec::new();
+        ctx.evaluate_function(func, [pages].iter().copied(), |result| {
+            results.push(result)
+        })
+        .map(|_| (results, fuel - ctx.remaining_fuel().unwrap()))
+    };
+    let (results, consumed_none) = grow(&mut ctx, 0).unwrap();
+    assert_eq!(results, vec![1]);
+    let (results, consumed_three) = grow(&mut ctx, 3).unwrap();
+    assert_eq!(results, vec![1]);
+    assert_eq!(consumed_three, consumed_none + 300);
+    // Memory growth is charged before the linear memory is grown.
+    ctx.set_fuel(consumed_none + 299);
+    assert_eq!(grow(&mut ctx, 3), Err(InterpretationError::OutOfFuel));
+    assert_eq!(ctx.memory(mem).unwrap().pages(), 4);
+}
+
+#[test]
+fn state_can_be_inspected_after_evaluation() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (global (mut i32) (i32.const 0))
+            (func (param i32) (result i32) (local i32)
+                local.get 0
+                i32.const 2
+                i32.mul
+                local.set 1
+                i32.const 8
+                local.get 1
+                i32.store
+                local.get 1
+                global.set 0
+                local.get 1
+            )
+        )
+    "#;
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    let mem = Mem::from_raw(RawIdx::from_u32(0));
+    let global = Global::from_raw(RawIdx::from_u32(0));
+    let module = parse_wat(wat, TranslationBudget::default());
+    let mut ctx = EvaluationContext::new(&module);
+    ctx.enable_register_file_capture();
+    ctx.evaluate_function(func, Some(21), |_| ()).unwrap();
+    assert_eq!(ctx.read_memory(mem, 8, 4), Ok(&42_u32.to_le_bytes()[..]));
+    assert_eq!(
+        ctx.global_value(global),
+        Some(Const::from(IntConst::I32(42)))
+    );
+    assert_eq!(
+        ctx.read_memory(mem, PAGE_SIZE as u32 - 2, 4),
+        Err(InterpretationError::MemoryOutOfBounds {
+            memory: mem,
+            address: PAGE_SIZE as u64 - 2,
+            len: 4,
+        })
+    );
+    let missing = Mem::from_raw(RawIdx::from_u32(1));
+    assert_eq!(
+        ctx.read_memory(missing, 0, 1),
+        Err(InterpretationError::MissingMemory { memory: missing })
+    );
+    let registers = ctx.take_register_file().unwrap();
+    assert_eq!(registers.func(), func);
+    let input = Value::from_raw(RawIdx::from_u32(0));
+    assert_eq!(
+        registers.get_const(input),
+        Some(Const::from(IntConst::I32(21)))
+    );
+    let i32_type = Some(Type::from(IntType::I32));
+    assert!(registers
+        .iter()
+        .any(|(_, bits, ty)| bits == 42 && ty == i32_type));
+    // Capturing is disabled after taking the register file.
+    ctx.evaluate_function(func, Some(1), |_| ()).unwrap();
+    assert_eq!(ctx.register_file(), None);
+}
+
+#[test]
+fn pure_functions_can_be_evaluated() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "square") (param i32) (result i32)
+                local.get 0
+                local.get 0
+                i32.mul
+            )
+            (func (export "div") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.div_s
+            )
+            (func (export "load") (param i32) (result i32)
+                local.get 0
+                i32.load
+            )
+            (func (export "call") (param i32) (result i32)
+                local.get 0
+                call 0
+            )
+        )
+    "#;
+    let module = parse_wat(wat, TranslationBudget::default());
+    let arg = |value: i32| Const::from(IntConst::I32(value));
+    assert_eq!(module.eval_pure("square", &[arg(-7)]), Ok(vec![arg(49)]));
+    // Pure functions might still trap.
+    assert_eq!(
+        module
+            .eval_pure("div", &[arg(1), arg(0)])
+            .unwrap_err()
+            .trap_code(),
+        Some(TrapCode::IntegerDivisionByZero)
+    );
+    for &(name, func) in &[("load", 2), ("call", 3)] {
+        let func = Func::from_raw(RawIdx::from_u32(func));
+        assert!(!module.is_pure(func));
+        assert_eq!(
+            module.eval_pure(name, &[arg(0)]),
+            Err(InterpretationError::ImpureFunction { func })
+        );
+    }
+    assert_eq!(
+        module.eval_pure("missing", &[]),
+        Err(InterpretationError::UnknownExportedFunction {
+            name: "missing".to_string()
+        })
+    );
+    // Interpreter-only functions are never considered pure.
+    let module = parse_baseline(wat);
+    let square = Func::from_raw(RawIdx::from_u32(0));
+    assert!(!module.is_pure(square));
+}
+
+#[test]
+fn globals_work_in_both_tiers() {
+    let wat = r#"
+        (module
+            (global $counter (mut i32) (i32.const 10))
+            (global $step i32 (i32.const 3))
+            (func (result i32)
+                global.get $counter
+                global.get $step
+                i32.add
+                global.set $counter
+                global.get $counter
+            )
+        )
+    "#;
+    let baseline = parse_baseline(wat);
+    let translated = parse_wat(wat, TranslationBudget::default());
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    let counter = Global::from_raw(RawIdx::from_u32(0));
+    assert!(baseline.is_interpreter_only(func));
+    assert!(!translated.is_interpreter_only(func));
+    assert!(!translated.is_pure(func));
+    for module in &[baseline, translated] {
+        let mut ctx = EvaluationContext::new(module);
+        for expected in &[13, 16, 19] {
+            let mut results = Vec::new();
+            ctx.evaluate_function(func, None, |result| results.push(result))
+                .unwrap();
+            assert_eq!(results, vec![*expected]);
+        }
+        assert_eq!(
+            ctx.global(counter).unwrap().get(),
+            IntConst::I32(19).into()
+        );
+        // Each evaluation context starts from the global initializers.
+        assert_eq!(evaluate(module, 0, &[]), Ok(vec![13]));
+    }
+}
+
+#[test]
+fn memory_operators_work_in_both_tiers() {
+    let wat = r#"
+        (module
+            (memory 1 3)
+            (func (param i32 i64) (result i64)
+                local.get 0
+                local.get 1
+                i64.store offset=4
+                local.get 0
+                i64.load8_s offset=4
+                local.get 0
+                i64.load16_u offset=4
+                i64.add
+                local.get 0
+                i32.load8_s offset=5
+                i64.extend_i32_s
+                i64.add
+                local.get 0
+                i32.load offset=4
+                i64.extend_i32_u
+                i64.add
+                local.get 0
+                i64.load32_s offset=8
+                i64.add
+            )
+            (func (param i32) (result i32)
+                local.get 0
+                memory.grow
+                i32.const 0xFFFF
+                i32.and
+                memory.size
+                i32.const 16
+                i32.shl
+                i32.or
+            )
+        )
+    "#;
+    let baseline = parse_baseline(wat);
+    let translated = parse_wat(wat, TranslationBudget::default());
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    assert!(baseline.is_interpreter_only(func));
+    assert!(!translated.is_interpreter_only(func));
+    let values = [0, 1, 0x80, 0xFFFF_FF80, u64::MAX, 0x0123_4567_89AB_CDEF];
+    let last = PAGE_SIZE as u64 - 12;
+    for &address in &[0, 3, 100, last] {
+        for &value in &values {
+            let inputs = [address, value];
+            let result = evaluate(&baseline, 0, &inputs);
+            assert!(result.is_ok());
+            assert_eq!(result, evaluate(&translated, 0, &inputs));
+        }
+    }
+    assert!(evaluate(&baseline, 0, &[last + 1, 0]).is_err());
+    assert!(evaluate(&translated, 0, &[last + 1, 0]).is_err());
+    // The previous size is in the lower and the new size in the upper bits.
+    let grow = |ctx: &mut EvaluationContext, pages: u64| {
+        let func = Func::from_raw(RawIdx::from_u32(1));
+        let mut results = Vec::new();
+        ctx.evaluate_function(func, [pages].iter().copied(), |result| {
+            results.push(result)
+        })
+        .unwrap();
+        results[0] as u32
+    };
+    for module in &[baseline, translated] {
+        let mut ctx = EvaluationContext::new(module);
+        assert_eq!(grow(&mut ctx, 0), 1 << 16 | 1);
+        assert_eq!(grow(&mut ctx, 1), 2 << 16 | 1);
+        assert_eq!(grow(&mut ctx, 2), 2 << 16 | 0xFFFF);
+        assert_eq!(grow(&mut ctx, 1), 3 << 16 | 2);
+        // The resource limits of the configuration apply to both tiers.
+        let mut limits = ResourceLimits::new();
+        limits.set_max_memory_pages(2);
+        let mut config = ExecutionConfig::new();
+        config.set_resource_limits(limits);
+        let mut ctx =
+            EvaluationContext::with_config(module, &VecMemoryCreator, config)
+                .unwrap();
+        assert_eq!(grow(&mut ctx, 1), 2 << 16 | 1);
+        assert_eq!(grow(&mut ctx, 1), 2 << 16 | 0xFFFF);
+    }
+}
+
+#[test]
+fn suspended_evaluations_resume_from_snapshots() {
+    let wat = r#"
+        (module
+            (func (param i32) (result i32) (local i32)
+                block
+                    loop
+                        local.get 0
+                        i32.eqz
+                        br_if 1
+                        local.get 1
+                        local.get 0
+                        call 1
+                        local.set 1
+                        local.get 0
+                        i32.const 1
+                        i32.sub
+                        local.set 0
+                        br 0
+                    end
+                end
+                local.get 1
+            )
+            (func (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add
+            )
+        )
+    "#;
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    let module = parse_wat(wat, TranslationBudget::default());
+    let mut ctx = EvaluationContext::new(&module);
+    ctx.set_fuel(10);
+    let mut evaluation = ctx.evaluate_resumable(func, vec![10]).unwrap();
+    let mut suspensions = 0;
+    while let Evaluation::Suspended(snapshot) = evaluation {
+        suspensions += 1;
+        assert_eq!(ctx.call_depth(), 0);
+        let snapshot = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+        ctx.set_fuel(10);
+        evaluation = ctx.resume(snapshot).unwrap();
+    }
+    assert!(suspensions > 1);
+    assert_eq!(evaluation, Evaluation::Finished(vec![55]));
+    // Interpreter-only functions cannot be suspended.
+    let baseline = parse_baseline(wat);
+    let mut ctx = EvaluationContext::new(&baseline);
+    ctx.set_fuel(10);
+    assert_eq!(
+        ctx.evaluate_resumable(func, vec![10]),
+        Err(InterpretationError::OutOfFuel)
+    );
+    assert_eq!(
+        Snapshot::from_bytes(&[0x01]),
+        Err(InterpretationError::InvalidSnapshot)
+    );
+}
+
+#[test]
+fn suspended_evaluations_keep_register_types() {
+    let wat = r#"
+        (module
+            (func (param i64) (result i64) (local i64)
+                block
+                    loop
+                        local.get 0
+                        i64.eqz
+                        br_if 1
+                        local.get 1
+                        local.get 0
+                        i64.add
+                        local.set 1
+                        local.get 0
+                        i64.const 1
+                        i64.sub
+                        local.set 0
+                        br 0
+                    end
+                end
+                local.get 1
+            )
+        )
+    "#;
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    let module = parse_wat(wat, TranslationBudget::default());
+    let mut ctx = EvaluationContext::new(&module);
+    let mut config = ExecutionConfig::new();
+    config.set_check_register_types(true);
+    ctx.set_execution_config(config);
+    ctx.set_fuel(10);
+    let mut evaluation = ctx.evaluate_resumable(func, vec![10]).unwrap();
+    let mut suspensions = 0;
+    while let Evaluation::Suspended(snapshot) = evaluation {
+        suspensions += 1;
+        let snapshot = Snapshot::from_bytes(&snapshot.to_bytes()).unwrap();
+        ctx.set_fuel(10);
+        evaluation = ctx.resume(snapshot).unwrap();
+    }
+    assert!(suspensions > 1);
+    assert_eq!(evaluation, Evaluation::Finished(vec![55]));
+}
+
+#[test]
+fn nan_canonicalization_works_in_both_tiers() {
+    let wat = r#"
+        (module
+            (func (param f32 f32) (result f32)
+                local.get 0
+                local.get 1
+                f32.add
+            )
+            (func (param f32) (result f32)
+                local.get 0
+                f32.neg
+            )
+            (func (param f32) (result f64)
+                local.get 0
+                f64.promote_f32
+            )
+            (func (param f64) (result f64)
+                local.get 0
+                f64.sqrt
+            )
+        )
+    "#;
+    let mut config = ExecutionConfig::new();
+    config.set_canonicalize_nans(true);
+    let one = u64::from(1.0_f32.to_bits());
+    let minus_one = (-1.0_f64).to_bits();
+    let cases: [(u32, &[u64], u64); 6] = [
+        (0, &[0x7FA0_0000, one], 0x7FC0_0000),
+        (0, &[one, 0xFFC0_0001], 0x7FC0_0000),
+        (0, &[one, one], u64::from(2.0_f32.to_bits())),
+        // Sign bit operations preserve NaN payloads.
+        (1, &[0xFFC0_0001], 0x7FC0_0001),
+        (2, &[0x7FA0_0000], 0x7FF8_0000_0000_0000),
+        (3, &[minus_one], 0x7FF8_0000_0000_0000),
+    ];
+    let translated = parse_wat(wat, TranslationBudget::default());
+    for module in &[parse_baseline(wat), translated] {
+        let mut ctx = EvaluationContext::new(module);
+        ctx.set_execution_config(config.clone());
+        let mut run = |func: u32, inputs: &[u64]| {
+            let func = Func::from_raw(RawIdx::from_u32(func));
+            let mut results = Vec::new();
+            ctx.evaluate_function(func, inputs.iter().copied(), |result| {
+                results.push(result)
+            })
+            .unwrap();
+            results
+        };
+        for &(func, inputs, expected) in &cases {
+            assert_eq!(run(func, inputs), vec![expected]);
+        }
+    }
+}
+
+#[test]
+fn call_depth_limit_works_in_both_tiers() {
+    let wat = r#"
+        (module
+            (func $countdown (param i32) (result i32)
+                local.get 0
+                if (result i32)
+                    local.get 0
+                    i32.const 1
+                    i32.sub
+                    call $countdown
+                else
+                    i32.const 0
+                end
+            )
+        )
+    "#;
+    let stack_overflow = Err(InterpretationError::EvaluationHasTrapped {
+        code: TrapCode::StackOverflow,
+    });
+    let translated = parse_wat(wat, TranslationBudget::default());
+    for module in &[parse_baseline(wat), translated] {
+        let func = Func::from_raw(RawIdx::from_u32(0));
+        let mut ctx = EvaluationContext::new(module);
+        assert_eq!(ctx.max_call_depth(), DEFAULT_MAX_CALL_DEPTH);
+        ctx.set_max_call_depth(10);
+        let mut countdown = |n: u64| {
+            let mut results = Vec::new();
+            ctx.evaluate_function(func, Some(n), |result| results.push(result))
+                .map(|_| results)
+        };
+        // The evaluation with input `n` requires `n + 1` function frames.
+        assert_eq!(countdown(9), Ok(vec![0]));
+        assert_eq!(countdown(10), stack_overflow);
+        assert_eq!(countdown(u32::MAX as u64), stack_overflow);
+        // The evaluation context can be reused after the trap.
+        assert_eq!(countdown(3), Ok(vec![0]));
+        assert_eq!(ctx.call_depth(), 0);
+    }
+}
+
+#[test]
+fn equivalence_checks_detect_changed_behavior() {
+    let wat = r#"
+        (module
+            (func (export "add") (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add
+            )
+        )
+    "#;
+    let original = parse_wat(wat, TranslationBudget::default());
+    // Both tiers behave the same.
+    let baseline = parse_baseline(wat);
+    let checker = EquivalenceChecker::new(&original, &baseline);
+    assert_eq!(checker.check_generated("add"), Ok(()));
+    let mut checked = parse_wat(wat, TranslationBudget::default());
+    let mut checks = CheckedArithmetic::default();
+    checks.set_check(IntType::I32, Overflow::Signed);
+    checked.check_arithmetic(&checks).unwrap();
+    let i32_const = |value| Const::from(IntConst::I32(value));
+    let inputs = [
+        vec![i32_const(1), i32_const(2)],
+        vec![i32_const(-1), i32_const(-2)],
+    ];
+    assert_eq!(
+        check_equivalence(
+            &original,
+            &checked,
+            "add",
+            inputs.iter().map(Vec::as_slice)
+        ),
+        Ok(())
+    );
+    // Overflow checks trap where the original function wraps around.
+    let mismatch = EquivalenceChecker::new(&original, &checked)
+        .check_generated("add")
+        .unwrap_err();
+    assert_eq!(mismatch.name(), "add");
+    assert_eq!(mismatch.args(), &[i32_const(-1), i32_const(i32::MIN)]);
+    assert_eq!(
+        mismatch.original(),
+        &Outcome::Returned(vec![i32_const(i32::MAX)])
+    );
+    assert_eq!(
+        mismatch.transformed(),
+        &Outcome::Trapped(TrapCode::IntegerOverflow)
+    );
+}
+
+#[test]
+fn integer_width_conversions_work_in_both_tiers() {
+    let wat = r#"
+        (module
+            (func (param i64) (result i32)
+                local.get 0
+                i32.wrap_i64
+            )
+            (func (param i32) (result i64)
+                local.get 0
+                i64.extend_i32_s
+            )
+            (func (param i32) (result i64)
+                local.get 0
+                i64.extend_i32_u
+            )
+            (func (param i32 i64) (result i64)
+                local.get 0
+                i64.extend_i32_u
+                local.get 1
+                i64.add
+                i32.wrap_i64
+                i32.const 1
+                i32.sub
+                i64.extend_i32_s
+            )
+            (func (param i32) (result i32)
+                local.get 0
+                i32.extend8_s
+            )
+            (func (param i32) (result i32)
+                local.get 0
+                i32.extend16_s
+            )
+            (func (param i64) (result i64)
+                local.get 0
+                i64.extend8_s
+            )
+            (func (param i64) (result i64)
+                local.get 0
+                i64.extend16_s
+            )
+            (func (param i64) (result i64)
+                local.get 0
+                i64.extend32_s
+            )
+        )
+    "#;
+    let translated = parse_wat(wat, TranslationBudget::default());
+    for module in &[parse_baseline(wat), translated] {
+        let i32_bits = |value: i32| value as u32 as u64;
+        let i64_bits = |value: i64| value as u64;
+        assert_eq!(
+            evaluate(module, 0, &[0x1_2345_6789]),
+            Ok(vec![0x2345_6789])
+        );
+        assert_eq!(
+            evaluate(module, 0, &[i64_bits(i64::MIN | 0x8000_0000)]),
+            Ok(vec![i32_bits(i32::MIN)])
+        );
+        assert_eq!(
+            evaluate(module, 1, &[i32_bits(-2)]),
+            Ok(vec![i64_bits(-2)])
+        );
+        assert_eq!(evaluate(module, 2, &[i32_bits(-2)]), Ok(vec![0xFFFF_FFFE]));
+        // `u32::MAX + 1` wraps to zero before the subtraction.
+        assert_eq!(
+            evaluate(module, 3, &[i32_bits(-1), 1]),
+            Ok(vec![i64_bits(-1)])
+        );
+        assert_eq!(
+            evaluate(module, 3, &[i32_bits(5), i64_bits(-3)]),
+            Ok(vec![1])
+        );
+        // Sign extensions ignore the bits above the extended width.
+        assert_eq!(evaluate(module, 4, &[0x1234_5680]), Ok(vec![0xFFFF_FF80]));
+        assert_eq!(evaluate(module, 4, &[0x1234_567F]), Ok(vec![0x7F]));
+        assert_eq!(evaluate(module, 5, &[0x1234_8001]), Ok(vec![0xFFFF_8001]));
+        assert_eq!(evaluate(module, 5, &[0x1234_7FFF]), Ok(vec![0x7FFF]));
+        assert_eq!(
+            evaluate(module, 6, &[0x1234_5678_9ABC_DE80]),
+            Ok(vec![i64_bits(-128)])
+        );
+        assert_eq!(
+            evaluate(module, 6, &[0x1234_5678_9ABC_DE7F]),
+            Ok(vec![0x7F])
+        );
+        assert_eq!(
+            evaluate(module, 7, &[0x1234_5678_9ABC_8001]),
+            Ok(vec![0xFFFF_FFFF_FFFF_8001])
+        );
+        assert_eq!(
+            evaluate(module, 8, &[0x1234_5678_8000_0001]),
+            Ok(vec![0xFFFF_FFFF_8000_0001])
+        );
+        assert_eq!(
+            evaluate(module, 8, &[0x1234_5678_7FFF_FFFF]),
+            Ok(vec![0x7FFF_FFFF])
+        );
+    }
+}
+
+#[test]
+fn saturating_truncations_work_in_both_tiers() {
+    let ops = [
+        "i32.trunc_sat_f32_s",
+        "i32.trunc_sat_f32_u",
+        "i32.trunc_sat_f64_s",
+        "i32.trunc_sat_f64_u",
+        "i64.trunc_sat_f32_s",
+        "i64.trunc_sat_f32_u",
+        "i64.trunc_sat_f64_s",
+        "i64.trunc_sat_f64_u",
+    ];
+    let funcs = ops
+        .iter()
+        .map(|op| {
+            let (result, param) = (&op[..3], &op[op.len() - 5..op.len() - 2]);
+            format!(
+                "(func (param {}) (result {}) local.get 0 {})",
+                param, result, op
+            )
+        })
+        .collect::<String>();
+    let wat = format!("(module {})", funcs);
+    let baseline = parse_baseline(&wat);
+    let translated = parse_wat(&wat, TranslationBudget::default());
+    let floats = [
+        f64::NAN,
+        f64::NEG_INFINITY,
+        f64::INFINITY,
+        -1.5,
+        1.5,
+        -3e9,
+        3e9,
+        1e20,
+    ];
+    for (n, op) in ops.iter().enumerate() {
+        for &float in &floats {
+            let input = match op.contains("f32") {
+                true => u64::from((float as f32).to_bits()),
+                false => float.to_bits(),
+            };
+            assert_eq!(
+                evaluate(&baseline, n as u32, &[input]),
+                evaluate(&translated, n as u32, &[input]),
+                "{} of {}",
+                op,
+                float,
+            );
+        }
+    }
+    let f32_bits = |value: f32| u64::from(value.to_bits());
+    let i32_bits = |value: i32| value as u32 as u64;
+    for module in &[baseline, translated] {
+        assert_eq!(evaluate(module, 0, &[f32_bits(f32::NAN)]), Ok(vec![0]));
+        assert_eq!(
+            evaluate(module, 0, &[f32_bits(f32::NEG_INFINITY)]),
+            Ok(vec![i32_bits(i32::MIN)])
+        );
+        assert_eq!(
+            evaluate(module, 0, &[f32_bits(3e9)]),
+            Ok(vec![i32_bits(i32::MAX)])
+        );
+        assert_eq!(evaluate(module, 1, &[f32_bits(-1.5)]), Ok(vec![0]));
+        assert_eq!(
+            evaluate(module, 7, &[1e20_f64.to_bits()]),
+            Ok(vec![u64::MAX])
+        );
+    }
+}
+
+#[test]
+fn multi_value_results_work_in_both_tiers() {
+    let wat = r#"
+        (module
+            (func $swap (param i32 i64) (result i64 i32)
+                local.get 1
+                local.get 0
+            )
+            (func (param i32 i64) (result i64 i32 i32)
+                local.get 0
+                local.get 1
+                call $swap
+                i32.const 1
+            )
+            (func (param i32) (result i32 i32)
+                local.get 0
+                if
+                    i32.const 1
+                    i32.const 2
+                    return
+                end
+                block (result i32 i32)
+                    i32.const 7
+                    i32.const 2
+                end
+            )
+            (func (param i32) (result i32)
+                local.get 0
+                call 2
+                i32.sub
+            )
+        )
+    "#;
+    let translated = parse_wat(wat, TranslationBudget::default());
+    for module in &[parse_baseline(wat), translated] {
+        assert_eq!(evaluate(module, 0, &[1, 2]), Ok(vec![2, 1]));
+        assert_eq!(evaluate(module, 1, &[3, 4]), Ok(vec![4, 3, 1]));
+        assert_eq!(evaluate(module, 2, &[1]), Ok(vec![1, 2]));
+        assert_eq!(evaluate(module, 2, &[0]), Ok(vec![7, 2]));
+        assert_eq!(evaluate(module, 3, &[0]), Ok(vec![5]));
+        assert_eq!(evaluate(module, 3, &[1]), Ok(vec![0xFFFF_FFFF]));
+    }
+}
+
+#[test]
+fn comparisons_produce_wasm_booleans_in_both_tiers() {
+    let wat = r#"
+        (module
+            (func (param i32) (result i32)
+                local.get 0
+                i32.eqz
+            )
+            (func (param i64) (result i32)
+                local.get 0
+                i64.eqz
+            )
+            (func (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.lt_s
+                i32.const 10
+                i32.mul
+                local.get 0
+                i32.eqz
+                i32.eqz
+                i32.add
+            )
+            (func (param f64 f64) (result i32)
+                local.get 0
+                local.get 1
+                f64.lt
+            )
+        )
+    "#;
+    let translated = parse_wat(wat, TranslationBudget::default());
+    for module in &[parse_baseline(wat), translated] {
+        assert_eq!(evaluate(module, 0, &[0]), Ok(vec![1]));
+        assert_eq!(evaluate(module, 0, &[7]), Ok(vec![0]));
+        assert_eq!(evaluate(module, 1, &[0]), Ok(vec![1]));
+        assert_eq!(evaluate(module, 1, &[1 << 32]), Ok(vec![0]));
+        // Comparison results flow into integer arithmetic as `0` or `1`.
+        assert_eq!(evaluate(module, 2, &[1, 2]), Ok(vec![11]));
+        assert_eq!(evaluate(module, 2, &[0, 2]), Ok(vec![10]));
+        assert_eq!(evaluate(module, 2, &[2, 1]), Ok(vec![1]));
+        let f64_bits = |value: f64| value.to_bits();
+        assert_eq!(
+            evaluate(module, 3, &[f64_bits(1.0), f64_bits(2.0)]),
+            Ok(vec![1])
+        );
+        assert_eq!(
+            evaluate(module, 3, &[f64_bits(f64::NAN), f64_bits(2.0)]),
+            Ok(vec![0])
+        );
+    }
+}
@@ -0,0 +1,2119 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance tests for the interpretation of numeric instructions.
+//!
+//! The test vectors are vendored from the `assert_return` and `assert_trap`
+//! cases of the `i32.wast`, `i64.wast`, `f32.wast`, `f64.wast` and
+//! `conversions.wast` scripts of the Wasm specification test suite.
+//! Every vector is evaluated against a single Runwell IR instruction so that
+//! arithmetic is checked independently of the Wasm translation.
+//!
+//! Inputs and results are given as bit patterns. NaN results are accepted
+//! regardless of their payload since the specification only requires
+//! canonical or arithmetic NaNs which both tiers treat alike.
+
+use self::Expected::{Bits, Nan, Trap};
+use super::module_with_func;
+use crate::EvaluationContext;
+use ir::{
+    instr::{
+        operands::{
+            BinaryFloatOp,
+            BinaryIntOp,
+            CompareFloatOp,
+            CompareIntOp,
+            ShiftIntOp,
+            UnaryFloatOp,
+            UnaryIntOp,
+        },
+        TrapCode::{
+            self,
+            BadConversionToInteger,
+            IntegerDivisionByZero,
+            IntegerOverflow,
+        },
+    },
+    primitive::{FloatType, IntType, Type, Value},
+};
+use module::builder::InstructionBuilder;
+
+/// The expected outcome of evaluating a test vector.
+#[derive(Debug, Copy, Clone)]
+enum Expected {
+    /// The instruction returns the bits.
+    Bits(u64),
+    /// The instruction returns a NaN with an arbitrary payload.
+    Nan,
+    /// The instruction traps with the trap code.
+    Trap(TrapCode),
+}
+
+/// The input bits of a test vector and its expected outcome.
+type Vector = (&'static [u64], Expected);
+
+/// The instruction under test.
+#[derive(Debug, Copy, Clone)]
+enum Op {
+    IntUnary(IntType, UnaryIntOp),
+    IntBinary(IntType, BinaryIntOp),
+    IntShift(IntType, ShiftIntOp),
+    IntCompare(IntType, CompareIntOp),
+    FloatUnary(FloatType, UnaryFloatOp),
+    FloatBinary(FloatType, BinaryFloatOp),
+    FloatCompare(FloatType, CompareFloatOp),
+    /// Extends from the first to the second type, signed if `true`.
+    Extend(IntType, IntType, bool),
+    /// Wraps from the first to the second type.
+    Truncate(IntType, IntType),
+    /// Converts with the signedness and saturation flags in this order.
+    FloatToInt(FloatType, IntType, bool, bool),
+    /// Converts from the first to the second type, signed if `true`.
+    IntToFloat(IntType, FloatType, bool),
+    /// Promotes from `f32` to `f64`.
+    Promote,
+    /// Demotes from `f64` to `f32`.
+    Demote,
+    Reinterpret(Type, Type),
+}
+
+impl Op {
+    /// Returns the input types of the instruction.
+    fn inputs(self) -> Vec<Type> {
+        match self {
+            Self::IntUnary(ty, _) => vec![ty.into()],
+            Self::IntBinary(ty, _) | Self::IntCompare(ty, _) => {
+                vec![ty.into(), ty.into()]
+            }
+            Self::IntShift(ty, _) => vec![ty.into(), IntType::I32.into()],
+            Self::FloatUnary(ty, _) => vec![ty.into()],
+            Self::FloatBinary(ty, _) | Self::FloatCompare(ty, _) => {
+                vec![ty.into(), ty.into()]
+            }
+            Self::Extend(from, _, _) | Self::Truncate(from, _) => {
+                vec![from.into()]
+            }
+            Self::FloatToInt(from, _, _, _) => vec![from.into()],
+            Self::IntToFloat(from, _, _) => vec![from.into()],
+            Self::Promote => vec![FloatType::F32.into()],
+            Self::Demote => vec![FloatType::F64.into()],
+            Self::Reinterpret(from, _) => vec![from],
+        }
+    }
+
+    /// Returns the output type of the instruction.
+    fn output(self) -> Type {
+        match self {
+            Self::IntUnary(ty, _)
+            | Self::IntBinary(ty, _)
+            | Self::IntShift(ty, _) => ty.into(),
+            Self::IntCompare(_, _) | Self::FloatCompare(_, _) => Type::Bool,
+            Self::FloatUnary(ty, _) | Self::FloatBinary(ty, _) => ty.into(),
+            Self::Extend(_, to, _) | Self::Truncate(_, to) => to.into(),
+            Self::FloatToInt(_, to, _, _) => to.into(),
+            Self::IntToFloat(_, to, _) => to.into(),
+            Self::Promote => FloatType::F64.into(),
+            Self::Demote => FloatType::F32.into(),
+            Self::Reinterpret(_, to) => to,
+        }
+    }
+
+    /// Appends the instruction operating on the inputs.
+    fn build(
+        self,
+        ins: InstructionBuilder,
+        inputs: &[Value],
+    ) -> Result<Value, module::Error> {
+        let x = inputs[0];
+        let y = || inputs[1];
+        match self {
+            Self::IntUnary(ty, op) => match op {
+                UnaryIntOp::LeadingZeros => ins.iclz(ty, x),
+                UnaryIntOp::TrailingZeros => ins.ictz(ty, x),
+                UnaryIntOp::PopCount => ins.ipopcnt(ty, x),
+            },
+            Self::IntBinary(ty, op) => match op {
+                BinaryIntOp::Add => ins.iadd(ty, x, y()),
+                BinaryIntOp::Sub => ins.isub(ty, x, y()),
+                BinaryIntOp::Mul => ins.imul(ty, x, y()),
+                BinaryIntOp::Sdiv => ins.sdiv(ty, x, y()),
+                BinaryIntOp::Udiv => ins.udiv(ty, x, y()),
+                BinaryIntOp::Srem => ins.srem(ty, x, y()),
+                BinaryIntOp::Urem => ins.urem(ty, x, y()),
+                BinaryIntOp::And => ins.iand(ty, x, y()),
+                BinaryIntOp::Or => ins.ior(ty, x, y()),
+                BinaryIntOp::Xor => ins.ixor(ty, x, y()),
+            },
+            Self::IntShift(ty, op) => match op {
+                ShiftIntOp::Shl => ins.ishl(ty, x, y()),
+                ShiftIntOp::Ushr => ins.iushr(ty, x, y()),
+                ShiftIntOp::Sshr => ins.isshr(ty, x, y()),
+                ShiftIntOp::Rotl => ins.irotl(ty, x, y()),
+                ShiftIntOp::Rotr => ins.irotr(ty, x, y()),
+            },
+            Self::IntCompare(ty, op) => ins.icmp(ty, op, x, y()),
+            Self::FloatUnary(ty, op) => match op {
+                UnaryFloatOp::Abs => ins.fabs(ty, x),
+                UnaryFloatOp::Neg => ins.fneg(ty, x),
+                UnaryFloatOp::Sqrt => ins.fsqrt(ty, x),
+                UnaryFloatOp::Ceil => ins.fceil(ty, x),
+                UnaryFloatOp::Floor => ins.ffloor(ty, x),
+                UnaryFloatOp::Truncate => ins.ftruncate(ty, x),
+                UnaryFloatOp::Nearest => ins.fnearest(ty, x),
+            },
+            Self::FloatBinary(ty, op) => match op {
+                BinaryFloatOp::Add => ins.fadd(ty, x, y()),
+                BinaryFloatOp::Sub => ins.fsub(ty, x, y()),
+                BinaryFloatOp::Mul => ins.fmul(ty, x, y()),
+                BinaryFloatOp::Div => ins.fdiv(ty, x, y()),
+                BinaryFloatOp::Min => ins.fmin(ty, x, y()),
+                BinaryFloatOp::Max => ins.fmax(ty, x, y()),
+                BinaryFloatOp::CopySign => ins.fcopysign(ty, x, y()),
+            },
+            Self::FloatCompare(ty, op) => ins.fcmp(ty, op, x, y()),
+            Self::Extend(from, to, signed) => ins.iextend(from, to, x, signed),
+            Self::Truncate(from, to) => ins.itruncate(from, to, x),
+            Self::FloatToInt(from, to, signed, saturating) => {
+                ins.float_to_int(from, to, signed, x, saturating)
+            }
+            Self::IntToFloat(from, to, signed) => {
+                ins.int_to_float(signed, from, to, x)
+            }
+            Self::Promote => ins.promote(FloatType::F32, FloatType::F64, x),
+            Self::Demote => ins.demote(FloatType::F64, FloatType::F32, x),
+            Self::Reinterpret(from, to) => ins.reinterpret(from, to, x),
+        }
+    }
+}
+
+/// Returns `true` if the bits represent a NaN value of the type.
+fn is_nan(ty: Type, bits: u64) -> bool {
+    match ty {
+        Type::Float(FloatType::F32) => f32::from_bits(bits as u32).is_nan(),
+        Type::Float(FloatType::F64) => f64::from_bits(bits).is_nan(),
+        _ => false,
+    }
+}
+
+/// Evaluates every test vector against its instruction.
+///
+/// # Panics
+///
+/// If the outcome of a test vector does not match its expected outcome.
+fn assert_conformance(suite: &[(&str, Op, &[Vector])]) {
+    for &(name, op, vectors) in suite {
+        let output = op.output();
+        let (func, module) = module_with_func(&op.inputs(), &[output], |b| {
+            let inputs = (0..op.inputs().len() as u32)
+                .map(|n| {
+                    let var = b.input_var(n).unwrap();
+                    b.read_var(var)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let result = op.build(b.ins()?, &inputs)?;
+            b.ins()?.return_values([result].iter().copied())?;
+            Ok(())
+        });
+        for &(inputs, expected) in vectors {
+            // Use a fresh context so that traps cannot affect later vectors.
+            let mut ctx = EvaluationContext::new(&module);
+            let mut results = Vec::new();
+            let outcome = ctx
+                .evaluate_function(func, inputs.iter().copied(), |result| {
+                    results.push(result)
+                })
+                .map(|_| results);
+            match (expected, outcome) {
+                (Bits(bits), Ok(results)) => {
+                    assert_eq!(results, [bits], "{} {:X?}", name, inputs)
+                }
+                (Nan, Ok(results))
+                    if results.len() == 1 && is_nan(output, results[0]) => {}
+                (Trap(code), Err(error)) => {
+                    assert_eq!(
+                        error.trap_code(),
+                        Some(code),
+                        "{} {:X?}",
+                        name,
+                        inputs
+                    )
+                }
+                (expected, outcome) => {
+                    panic!(
+                        "{} {:X?}: expected {:?} but found {:X?}",
+                        name, inputs, expected, outcome
+                    )
+                }
+            }
+        }
+    }
+}
+
+const I32_ADD: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0002)),
+    (&[0x0000_0001, 0x0000_0000], Bits(0x0000_0001)),
+    (&[0xFFFF_FFFF, 0xFFFF_FFFF], Bits(0xFFFF_FFFE)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0x7FFF_FFFF, 0x0000_0001], Bits(0x8000_0000)),
+    (&[0x8000_0000, 0xFFFF_FFFF], Bits(0x7FFF_FFFF)),
+    (&[0x8000_0000, 0x8000_0000], Bits(0x0000_0000)),
+    (&[0x3FFF_FFFF, 0x0000_0001], Bits(0x4000_0000)),
+];
+
+const I32_SUB: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0x0000_0001, 0x0000_0000], Bits(0x0000_0001)),
+    (&[0xFFFF_FFFF, 0xFFFF_FFFF], Bits(0x0000_0000)),
+    (&[0x7FFF_FFFF, 0xFFFF_FFFF], Bits(0x8000_0000)),
+    (&[0x8000_0000, 0x0000_0001], Bits(0x7FFF_FFFF)),
+    (&[0x8000_0000, 0x8000_0000], Bits(0x0000_0000)),
+    (&[0x3FFF_FFFF, 0xFFFF_FFFF], Bits(0x4000_0000)),
+];
+
+const I32_MUL: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x0000_0001, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0xFFFF_FFFF, 0xFFFF_FFFF], Bits(0x0000_0001)),
+    (&[0x1000_0000, 0x0000_1000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0xFFFF_FFFF], Bits(0x8000_0000)),
+    (&[0x7FFF_FFFF, 0xFFFF_FFFF], Bits(0x8000_0001)),
+    (&[0x0123_4567, 0x7654_3210], Bits(0x358E_7470)),
+    (&[0x7FFF_FFFF, 0x7FFF_FFFF], Bits(0x0000_0001)),
+];
+
+const I32_DIV_S: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0000], Trap(IntegerDivisionByZero)),
+    (&[0x0000_0000, 0x0000_0000], Trap(IntegerDivisionByZero)),
+    (&[0x8000_0000, 0xFFFF_FFFF], Trap(IntegerOverflow)),
+    (&[0x8000_0000, 0x0000_0002], Bits(0xC000_0000)),
+    (&[0x8000_0001, 0x0000_03E8], Bits(0xFFDF_3B65)),
+    (&[0x0000_0005, 0x0000_0002], Bits(0x0000_0002)),
+    (&[0xFFFF_FFFB, 0x0000_0002], Bits(0xFFFF_FFFE)),
+    (&[0x0000_0005, 0xFFFF_FFFE], Bits(0xFFFF_FFFE)),
+    (&[0xFFFF_FFFB, 0xFFFF_FFFE], Bits(0x0000_0002)),
+    (&[0x0000_0007, 0x0000_0003], Bits(0x0000_0002)),
+    (&[0xFFFF_FFF9, 0x0000_0003], Bits(0xFFFF_FFFE)),
+    (&[0x0000_0011, 0x0000_0007], Bits(0x0000_0002)),
+];
+
+const I32_DIV_U: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0000], Trap(IntegerDivisionByZero)),
+    (&[0x0000_0000, 0x0000_0000], Trap(IntegerDivisionByZero)),
+    (&[0x8000_0000, 0xFFFF_FFFF], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x0000_0002], Bits(0x4000_0000)),
+    (&[0x8FF0_0FF0, 0x0001_0001], Bits(0x0000_8FEF)),
+    (&[0x8000_0001, 0x0000_03E8], Bits(0x0020_C49B)),
+    (&[0x0000_0005, 0x0000_0002], Bits(0x0000_0002)),
+    (&[0xFFFF_FFFB, 0x0000_0002], Bits(0x7FFF_FFFD)),
+    (&[0x0000_0005, 0xFFFF_FFFE], Bits(0x0000_0000)),
+    (&[0xFFFF_FFFB, 0xFFFF_FFFE], Bits(0x0000_0000)),
+    (&[0x0000_0011, 0x0000_0007], Bits(0x0000_0002)),
+];
+
+const I32_REM_S: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0000], Trap(IntegerDivisionByZero)),
+    (&[0x7FFF_FFFF, 0xFFFF_FFFF], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0xFFFF_FFFF], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x0000_0002], Bits(0x0000_0000)),
+    (&[0x8000_0001, 0x0000_03E8], Bits(0xFFFF_FD79)),
+    (&[0x0000_0005, 0x0000_0002], Bits(0x0000_0001)),
+    (&[0xFFFF_FFFB, 0x0000_0002], Bits(0xFFFF_FFFF)),
+    (&[0x0000_0005, 0xFFFF_FFFE], Bits(0x0000_0001)),
+    (&[0xFFFF_FFFB, 0xFFFF_FFFE], Bits(0xFFFF_FFFF)),
+    (&[0xFFFF_FFF9, 0x0000_0003], Bits(0xFFFF_FFFF)),
+    (&[0x0000_0011, 0x0000_0007], Bits(0x0000_0003)),
+];
+
+const I32_REM_U: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0000], Trap(IntegerDivisionByZero)),
+    (&[0x8000_0000, 0xFFFF_FFFF], Bits(0x8000_0000)),
+    (&[0x8000_0000, 0x0000_0002], Bits(0x0000_0000)),
+    (&[0x8FF0_0FF0, 0x0001_0001], Bits(0x0000_8001)),
+    (&[0x8000_0001, 0x0000_03E8], Bits(0x0000_0289)),
+    (&[0xFFFF_FFFB, 0x0000_0002], Bits(0x0000_0001)),
+    (&[0x0000_0005, 0xFFFF_FFFE], Bits(0x0000_0005)),
+    (&[0xFFFF_FFFB, 0xFFFF_FFFE], Bits(0xFFFF_FFFB)),
+    (&[0x0000_0011, 0x0000_0007], Bits(0x0000_0003)),
+];
+
+const I32_AND: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x7FFF_FFFF, 0x8000_0000], Bits(0x0000_0000)),
+    (&[0x7FFF_FFFF, 0xFFFF_FFFF], Bits(0x7FFF_FFFF)),
+    (&[0xF0F0_FFFF, 0xFFFF_F0F0], Bits(0xF0F0_F0F0)),
+    (&[0xFFFF_FFFF, 0xFFFF_FFFF], Bits(0xFFFF_FFFF)),
+];
+
+const I32_OR: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0000], Bits(0x0000_0001)),
+    (&[0x0000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x7FFF_FFFF, 0x8000_0000], Bits(0xFFFF_FFFF)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0x8000_0000)),
+    (&[0xF0F0_FFFF, 0xFFFF_F0F0], Bits(0xFFFF_FFFF)),
+];
+
+const I32_XOR: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0x7FFF_FFFF, 0x8000_0000], Bits(0xFFFF_FFFF)),
+    (&[0xFFFF_FFFF, 0x8000_0000], Bits(0x7FFF_FFFF)),
+    (&[0xFFFF_FFFF, 0x7FFF_FFFF], Bits(0x8000_0000)),
+    (&[0xF0F0_FFFF, 0xFFFF_F0F0], Bits(0x0F0F_0F0F)),
+    (&[0xFFFF_FFFF, 0xFFFF_FFFF], Bits(0x0000_0000)),
+];
+
+const I32_SHL: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0002)),
+    (&[0x0000_0001, 0x0000_0000], Bits(0x0000_0001)),
+    (&[0x7FFF_FFFF, 0x0000_0001], Bits(0xFFFF_FFFE)),
+    (&[0x8000_0000, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0x4000_0000, 0x0000_0001], Bits(0x8000_0000)),
+    (&[0x0000_0001, 0x0000_001F], Bits(0x8000_0000)),
+    (&[0x0000_0001, 0x0000_0020], Bits(0x0000_0001)),
+    (&[0x0000_0001, 0x0000_0021], Bits(0x0000_0002)),
+    (&[0x0000_0001, 0xFFFF_FFFF], Bits(0x8000_0000)),
+    (&[0x0000_0001, 0x7FFF_FFFF], Bits(0x8000_0000)),
+];
+
+const I32_SHR_S: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(0xFFFF_FFFF)),
+    (&[0x7FFF_FFFF, 0x0000_0001], Bits(0x3FFF_FFFF)),
+    (&[0x8000_0000, 0x0000_0001], Bits(0xC000_0000)),
+    (&[0x0000_0001, 0x0000_0020], Bits(0x0000_0001)),
+    (&[0x0000_0001, 0x0000_0021], Bits(0x0000_0000)),
+    (&[0x0000_0001, 0xFFFF_FFFF], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x0000_001F], Bits(0xFFFF_FFFF)),
+    (&[0xFFFF_FFFF, 0x0000_0020], Bits(0xFFFF_FFFF)),
+    (&[0xFFFF_FFFF, 0xFFFF_FFFF], Bits(0xFFFF_FFFF)),
+];
+
+const I32_SHR_U: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(0x7FFF_FFFF)),
+    (&[0x8000_0000, 0x0000_0001], Bits(0x4000_0000)),
+    (&[0x0000_0001, 0x0000_0020], Bits(0x0000_0001)),
+    (&[0x0000_0001, 0xFFFF_FFFF], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x0000_001F], Bits(0x0000_0001)),
+    (&[0xFFFF_FFFF, 0x0000_0020], Bits(0xFFFF_FFFF)),
+    (&[0xFFFF_FFFF, 0xFFFF_FFFF], Bits(0x0000_0001)),
+];
+
+const I32_ROTL: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0002)),
+    (&[0x0000_0001, 0x0000_0000], Bits(0x0000_0001)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(0xFFFF_FFFF)),
+    (&[0x0000_0001, 0x0000_0020], Bits(0x0000_0001)),
+    (&[0xABCD_9876, 0x0000_0001], Bits(0x579B_30ED)),
+    (&[0xFE00_DC00, 0x0000_0004], Bits(0xE00D_C00F)),
+    (&[0xB0C1_D2E3, 0x0000_0005], Bits(0x183A_5C76)),
+    (&[0x0000_8000, 0x0000_0025], Bits(0x0010_0000)),
+    (&[0xB0C1_D2E3, 0x0000_FF05], Bits(0x183A_5C76)),
+    (&[0x769A_BCDF, 0xFFFF_FFED], Bits(0x579B_EED3)),
+    (&[0x769A_BCDF, 0x8000_000D], Bits(0x579B_EED3)),
+    (&[0x8000_0000, 0x0000_0001], Bits(0x0000_0001)),
+];
+
+const I32_ROTR: &[Vector] = &[
+    (&[0x0000_0001, 0x0000_0001], Bits(0x8000_0000)),
+    (&[0x0000_0001, 0x0000_0000], Bits(0x0000_0001)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(0xFFFF_FFFF)),
+    (&[0x0000_0001, 0x0000_0020], Bits(0x0000_0001)),
+    (&[0xFF00_CC00, 0x0000_0001], Bits(0x7F80_6600)),
+    (&[0x0008_0000, 0x0000_0004], Bits(0x0000_8000)),
+    (&[0xB0C1_D2E3, 0x0000_0005], Bits(0x1D86_0E97)),
+    (&[0x0000_8000, 0x0000_0025], Bits(0x0000_0400)),
+    (&[0xB0C1_D2E3, 0x0000_FF05], Bits(0x1D86_0E97)),
+    (&[0x769A_BCDF, 0xFFFF_FFED], Bits(0xE6FB_B4D5)),
+    (&[0x769A_BCDF, 0x8000_000D], Bits(0xE6FB_B4D5)),
+    (&[0x0000_0001, 0x0000_001F], Bits(0x0000_0002)),
+    (&[0x8000_0000, 0x0000_001F], Bits(0x0000_0001)),
+];
+
+const I32_CLZ: &[Vector] = &[
+    (&[0xFFFF_FFFF], Bits(0)),
+    (&[0x0000_0000], Bits(32)),
+    (&[0x0000_8000], Bits(16)),
+    (&[0x0000_00FF], Bits(24)),
+    (&[0x8000_0000], Bits(0)),
+    (&[0x0000_0001], Bits(31)),
+    (&[0x0000_0002], Bits(30)),
+    (&[0x7FFF_FFFF], Bits(1)),
+];
+
+const I32_CTZ: &[Vector] = &[
+    (&[0xFFFF_FFFF], Bits(0)),
+    (&[0x0000_0000], Bits(32)),
+    (&[0x0000_8000], Bits(15)),
+    (&[0x0001_0000], Bits(16)),
+    (&[0x8000_0000], Bits(31)),
+    (&[0x7FFF_FFFF], Bits(0)),
+];
+
+const I32_POPCNT: &[Vector] = &[
+    (&[0xFFFF_FFFF], Bits(32)),
+    (&[0x0000_0000], Bits(0)),
+    (&[0x0000_8000], Bits(1)),
+    (&[0x8000_8000], Bits(2)),
+    (&[0x7FFF_FFFF], Bits(31)),
+    (&[0xAAAA_AAAA], Bits(16)),
+    (&[0x5555_5555], Bits(16)),
+    (&[0xDEAD_BEEF], Bits(24)),
+];
+
+const I32_EQ: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(1)),
+    (&[0x8000_0000, 0x8000_0000], Bits(1)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(0)),
+    (&[0x8000_0000, 0x7FFF_FFFF], Bits(0)),
+];
+
+const I32_LT_S: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(0)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(1)),
+    (&[0x8000_0000, 0x0000_0000], Bits(1)),
+    (&[0x8000_0000, 0x7FFF_FFFF], Bits(1)),
+    (&[0x7FFF_FFFF, 0x8000_0000], Bits(0)),
+];
+
+const I32_LT_U: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(0)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(0)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0)),
+    (&[0x8000_0000, 0x7FFF_FFFF], Bits(0)),
+    (&[0x7FFF_FFFF, 0x8000_0000], Bits(1)),
+];
+
+const I32_GE_S: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(1)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(0)),
+    (&[0x8000_0000, 0x7FFF_FFFF], Bits(0)),
+    (&[0x7FFF_FFFF, 0x8000_0000], Bits(1)),
+];
+
+const I32_GE_U: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(1)),
+    (&[0xFFFF_FFFF, 0x0000_0001], Bits(1)),
+    (&[0x8000_0000, 0x7FFF_FFFF], Bits(1)),
+    (&[0x7FFF_FFFF, 0x8000_0000], Bits(0)),
+];
+
+const I64_ADD: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0002),
+    ),
+    (
+        &[0xFFFF_FFFF_FFFF_FFFF, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x7FFF_FFFF_FFFF_FFFF, 0x0000_0000_0000_0001],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x7FFF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_3FFF_FFFF, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_4000_0000),
+    ),
+];
+
+const I64_SUB: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x7FFF_FFFF_FFFF_FFFF, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0001],
+        Bits(0x7FFF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_3FFF_FFFF, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x0000_0000_4000_0000),
+    ),
+];
+
+const I64_MUL: &[Vector] = &[
+    (
+        &[0xFFFF_FFFF_FFFF_FFFF, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x1000_0000_0000_0000, 0x0000_0000_0000_1000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x7FFF_FFFF_FFFF_FFFF, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x8000_0000_0000_0001),
+    ),
+    (
+        &[0x0123_4567_89AB_CDEF, 0xFEDC_BA98_7654_3210],
+        Bits(0x2236_D88F_E561_8CF0),
+    ),
+    (
+        &[0x7FFF_FFFF_FFFF_FFFF, 0x7FFF_FFFF_FFFF_FFFF],
+        Bits(0x0000_0000_0000_0001),
+    ),
+];
+
+const I64_DIV_S: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0000],
+        Trap(IntegerDivisionByZero),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0xFFFF_FFFF_FFFF_FFFF],
+        Trap(IntegerOverflow),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0002],
+        Bits(0xC000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_03E8],
+        Bits(0xFFDF_3B64_5A1C_AC09),
+    ),
+    (
+        &[0xFFFF_FFFF_FFFF_FFFB, 0x0000_0000_0000_0002],
+        Bits(0xFFFF_FFFF_FFFF_FFFE),
+    ),
+    (
+        &[0xFFFF_FFFF_FFFF_FFF9, 0xFFFF_FFFF_FFFF_FFFD],
+        Bits(0x0000_0000_0000_0002),
+    ),
+];
+
+const I64_DIV_U: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0000],
+        Trap(IntegerDivisionByZero),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0002],
+        Bits(0x4000_0000_0000_0000),
+    ),
+    (
+        &[0x8FF0_0FF0_0FF0_0FF0, 0x0000_0001_0000_0001],
+        Bits(0x0000_0000_8FF0_0FEF),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_03E8],
+        Bits(0x0020_C49B_A5E3_53F7),
+    ),
+    (
+        &[0xFFFF_FFFF_FFFF_FFFB, 0x0000_0000_0000_0002],
+        Bits(0x7FFF_FFFF_FFFF_FFFD),
+    ),
+];
+
+const I64_REM_S: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0000],
+        Trap(IntegerDivisionByZero),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_03E8],
+        Bits(0xFFFF_FFFF_FFFF_FCD9),
+    ),
+    (
+        &[0xFFFF_FFFF_FFFF_FFFB, 0x0000_0000_0000_0002],
+        Bits(0xFFFF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0x0000_0000_0000_0005, 0xFFFF_FFFF_FFFF_FFFE],
+        Bits(0x0000_0000_0000_0001),
+    ),
+];
+
+const I64_REM_U: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0000],
+        Trap(IntegerDivisionByZero),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0xFFFF_FFFF_FFFF_FFFF],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x8FF0_0FF0_0FF0_0FF0, 0x0000_0001_0000_0001],
+        Bits(0x0000_0000_8000_0001),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_03E8],
+        Bits(0x0000_0000_0000_0329),
+    ),
+    (
+        &[0xFFFF_FFFF_FFFF_FFFB, 0xFFFF_FFFF_FFFF_FFFE],
+        Bits(0xFFFF_FFFF_FFFF_FFFB),
+    ),
+];
+
+const I64_AND: &[Vector] = &[
+    (
+        &[0x7FFF_FFFF_FFFF_FFFF, 0x8000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0xF0F0_FFFF_F0F0_FFFF, 0xFFFF_F0F0_FFFF_F0F0],
+        Bits(0xF0F0_F0F0_F0F0_F0F0),
+    ),
+];
+
+const I64_OR: &[Vector] = &[
+    (
+        &[0x7FFF_FFFF_FFFF_FFFF, 0x8000_0000_0000_0000],
+        Bits(0xFFFF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0xF0F0_FFFF_F0F0_FFFF, 0xFFFF_F0F0_FFFF_F0F0],
+        Bits(0xFFFF_FFFF_FFFF_FFFF),
+    ),
+];
+
+const I64_XOR: &[Vector] = &[
+    (
+        &[0xFFFF_FFFF_FFFF_FFFF, 0x8000_0000_0000_0000],
+        Bits(0x7FFF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0xF0F0_FFFF_F0F0_FFFF, 0xFFFF_F0F0_FFFF_F0F0],
+        Bits(0x0F0F_0F0F_0F0F_0F0F),
+    ),
+];
+
+const I64_SHL: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0001],
+        Bits(0x0000_0000_0000_0002),
+    ),
+    (
+        &[0x7FFF_FFFF_FFFF_FFFF, 0x0000_0001],
+        Bits(0xFFFF_FFFF_FFFF_FFFE),
+    ),
+    (
+        &[0x4000_0000_0000_0000, 0x0000_0001],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_003F],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0040],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0041],
+        Bits(0x0000_0000_0000_0002),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0xFFFF_FFFF],
+        Bits(0x8000_0000_0000_0000),
+    ),
+];
+
+const I64_SHR_S: &[Vector] = &[
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0001],
+        Bits(0xC000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0040],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_003F],
+        Bits(0xFFFF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0xFFFF_FFFF_FFFF_FFFF, 0xFFFF_FFFF],
+        Bits(0xFFFF_FFFF_FFFF_FFFF),
+    ),
+];
+
+const I64_SHR_U: &[Vector] = &[
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0001],
+        Bits(0x4000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0040],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_003F],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0xFFFF_FFFF_FFFF_FFFF, 0xFFFF_FFFF],
+        Bits(0x0000_0000_0000_0001),
+    ),
+];
+
+const I64_ROTL: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0040],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0xABCD_9876_0246_8ACE, 0x0000_0001],
+        Bits(0x579B_30EC_048D_159D),
+    ),
+    (
+        &[0xFE00_0000_DC00_0000, 0x0000_0004],
+        Bits(0xE000_000D_C000_000F),
+    ),
+    (
+        &[0xABCD_1234_EF56_7809, 0x0000_0035],
+        Bits(0x0135_79A2_469D_EACF),
+    ),
+    (
+        &[0xABD1_234E_F567_809C, 0x0000_003F],
+        Bits(0x55E8_91A7_7AB3_C04E),
+    ),
+];
+
+const I64_ROTR: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0001],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0xABCD_9876_0246_8ACE, 0x0000_0001],
+        Bits(0x55E6_CC3B_0123_4567),
+    ),
+    (
+        &[0xFE00_0000_DC00_0000, 0x0000_0004],
+        Bits(0x0FE0_0000_0DC0_0000),
+    ),
+    (
+        &[0xABCD_1234_EF56_7809, 0x0000_0035],
+        Bits(0x6891_A77A_B3C0_4D5E),
+    ),
+    (
+        &[0xABD1_234E_F567_809C, 0x0000_003F],
+        Bits(0x57A2_469D_EACF_0139),
+    ),
+];
+
+const I64_CLZ: &[Vector] = &[
+    (&[0xFFFF_FFFF_FFFF_FFFF], Bits(0)),
+    (&[0x0000_0000_0000_0000], Bits(64)),
+    (&[0x0000_0000_0000_8000], Bits(48)),
+    (&[0x0000_0000_0000_00FF], Bits(56)),
+    (&[0x0000_0000_0000_0001], Bits(63)),
+    (&[0x7FFF_FFFF_FFFF_FFFF], Bits(1)),
+];
+
+const I64_CTZ: &[Vector] = &[
+    (&[0x0000_0000_0000_0000], Bits(64)),
+    (&[0x0000_0000_0000_8000], Bits(15)),
+    (&[0x0000_0001_0000_0000], Bits(32)),
+    (&[0x8000_0000_0000_0000], Bits(63)),
+];
+
+const I64_POPCNT: &[Vector] = &[
+    (&[0xFFFF_FFFF_FFFF_FFFF], Bits(64)),
+    (&[0x8000_8000_8000_8000], Bits(4)),
+    (&[0xAAAA_AAAA_5555_5555], Bits(32)),
+    (&[0xDEAD_BEEF_DEAD_BEEF], Bits(48)),
+];
+
+const I64_LT_S: &[Vector] = &[
+    (&[0x8000_0000_0000_0000, 0x7FFF_FFFF_FFFF_FFFF], Bits(1)),
+    (&[0xFFFF_FFFF_FFFF_FFFF, 0x0000_0000_0000_0000], Bits(1)),
+    (&[0x0000_0001_0000_0000, 0x0000_0000_FFFF_FFFF], Bits(0)),
+];
+
+const I64_LT_U: &[Vector] = &[
+    (&[0x8000_0000_0000_0000, 0x7FFF_FFFF_FFFF_FFFF], Bits(0)),
+    (&[0xFFFF_FFFF_FFFF_FFFF, 0x0000_0000_0000_0000], Bits(0)),
+    (&[0x0000_0000_FFFF_FFFF, 0x0000_0001_0000_0000], Bits(1)),
+];
+
+const F32_ADD: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x8000_0000], Bits(0x8000_0000)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0002)),
+    (&[0x8000_0001, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0x3F00_0000, 0x3F00_0000], Bits(0x3F80_0000)),
+    (&[0x3F80_0000, 0x0000_0001], Bits(0x3F80_0000)),
+    (&[0x40C9_0FDB, 0x3F80_0000], Bits(0x40E9_0FDB)),
+    (&[0x7F7F_FFFF, 0x7F7F_FFFF], Bits(0x7F80_0000)),
+    (&[0x7F80_0000, 0xFF80_0000], Nan),
+    (&[0x7F80_0000, 0x3F80_0000], Bits(0x7F80_0000)),
+    (&[0x0000_0000, 0x8000_0000], Bits(0x0000_0000)),
+    (&[0x3F80_0000, 0x7FC0_0000], Nan),
+];
+
+const F32_SUB: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x8000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0x8000_0001, 0x0000_0001], Bits(0x8000_0002)),
+    (&[0x3F00_0000, 0x3F00_0000], Bits(0x0000_0000)),
+    (&[0x3F80_0000, 0x0000_0001], Bits(0x3F80_0000)),
+    (&[0x40C9_0FDB, 0x3F80_0000], Bits(0x40A9_0FDB)),
+    (&[0x7F7F_FFFF, 0x7F7F_FFFF], Bits(0x0000_0000)),
+    (&[0x7F80_0000, 0xFF80_0000], Bits(0x7F80_0000)),
+    (&[0x7F80_0000, 0x3F80_0000], Bits(0x7F80_0000)),
+    (&[0x0000_0000, 0x8000_0000], Bits(0x0000_0000)),
+    (&[0x3F80_0000, 0x7FC0_0000], Nan),
+];
+
+const F32_MUL: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x8000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0000)),
+    (&[0x8000_0001, 0x0000_0001], Bits(0x8000_0000)),
+    (&[0x3F00_0000, 0x3F00_0000], Bits(0x3E80_0000)),
+    (&[0x3F80_0000, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x40C9_0FDB, 0x3F80_0000], Bits(0x40C9_0FDB)),
+    (&[0x7F7F_FFFF, 0x7F7F_FFFF], Bits(0x7F80_0000)),
+    (&[0x7F80_0000, 0xFF80_0000], Bits(0xFF80_0000)),
+    (&[0x7F80_0000, 0x3F80_0000], Bits(0x7F80_0000)),
+    (&[0x0000_0000, 0x8000_0000], Bits(0x8000_0000)),
+    (&[0x3F80_0000, 0x7FC0_0000], Nan),
+];
+
+const F32_DIV: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Nan),
+    (&[0x8000_0000, 0x8000_0000], Nan),
+    (&[0x8000_0000, 0x0000_0000], Nan),
+    (&[0x0000_0001, 0x0000_0001], Bits(0x3F80_0000)),
+    (&[0x8000_0001, 0x0000_0001], Bits(0xBF80_0000)),
+    (&[0x3F00_0000, 0x3F00_0000], Bits(0x3F80_0000)),
+    (&[0x3F80_0000, 0x0000_0001], Bits(0x7F80_0000)),
+    (&[0x40C9_0FDB, 0x3F80_0000], Bits(0x40C9_0FDB)),
+    (&[0x7F7F_FFFF, 0x7F7F_FFFF], Bits(0x3F80_0000)),
+    (&[0x7F80_0000, 0xFF80_0000], Nan),
+    (&[0x7F80_0000, 0x3F80_0000], Bits(0x7F80_0000)),
+    (&[0x0000_0000, 0x8000_0000], Nan),
+    (&[0x3F80_0000, 0x7FC0_0000], Nan),
+];
+
+const F32_MIN: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x8000_0000], Bits(0x8000_0000)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x8000_0001, 0x0000_0001], Bits(0x8000_0001)),
+    (&[0x3F00_0000, 0x3F00_0000], Bits(0x3F00_0000)),
+    (&[0x3F80_0000, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x40C9_0FDB, 0x3F80_0000], Bits(0x3F80_0000)),
+    (&[0x7F7F_FFFF, 0x7F7F_FFFF], Bits(0x7F7F_FFFF)),
+    (&[0x7F80_0000, 0xFF80_0000], Bits(0xFF80_0000)),
+    (&[0x7F80_0000, 0x3F80_0000], Bits(0x3F80_0000)),
+    (&[0x0000_0000, 0x8000_0000], Bits(0x8000_0000)),
+    (&[0x3F80_0000, 0x7FC0_0000], Nan),
+];
+
+const F32_MAX: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x8000_0000], Bits(0x8000_0000)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x8000_0001, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x3F00_0000, 0x3F00_0000], Bits(0x3F00_0000)),
+    (&[0x3F80_0000, 0x0000_0001], Bits(0x3F80_0000)),
+    (&[0x40C9_0FDB, 0x3F80_0000], Bits(0x40C9_0FDB)),
+    (&[0x7F7F_FFFF, 0x7F7F_FFFF], Bits(0x7F7F_FFFF)),
+    (&[0x7F80_0000, 0xFF80_0000], Bits(0x7F80_0000)),
+    (&[0x7F80_0000, 0x3F80_0000], Bits(0x7F80_0000)),
+    (&[0x0000_0000, 0x8000_0000], Bits(0x0000_0000)),
+    (&[0x3F80_0000, 0x7FC0_0000], Nan),
+];
+
+const F32_COPYSIGN: &[Vector] = &[
+    (&[0x0000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000, 0x8000_0000], Bits(0x8000_0000)),
+    (&[0x8000_0000, 0x0000_0000], Bits(0x0000_0000)),
+    (&[0x0000_0001, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x8000_0001, 0x0000_0001], Bits(0x0000_0001)),
+    (&[0x3F00_0000, 0x3F00_0000], Bits(0x3F00_0000)),
+    (&[0x3F80_0000, 0x0000_0001], Bits(0x3F80_0000)),
+    (&[0x40C9_0FDB, 0x3F80_0000], Bits(0x40C9_0FDB)),
+    (&[0x7F7F_FFFF, 0x7F7F_FFFF], Bits(0x7F7F_FFFF)),
+    (&[0x7F80_0000, 0xFF80_0000], Bits(0xFF80_0000)),
+    (&[0x7F80_0000, 0x3F80_0000], Bits(0x7F80_0000)),
+    (&[0x0000_0000, 0x8000_0000], Bits(0x8000_0000)),
+    (&[0x3F80_0000, 0x7FC0_0000], Bits(0x3F80_0000)),
+];
+
+const F32_ABS: &[Vector] = &[
+    (&[0x8000_0000], Bits(0x0000_0000)),
+    (&[0xBF80_0000], Bits(0x3F80_0000)),
+    (&[0xFF80_0000], Bits(0x7F80_0000)),
+    (&[0xFFC0_0000], Bits(0x7FC0_0000)),
+    (&[0xFFA0_0000], Bits(0x7FA0_0000)),
+];
+
+const F32_NEG: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x8000_0000)),
+    (&[0xBF80_0000], Bits(0x3F80_0000)),
+    (&[0x7F80_0000], Bits(0xFF80_0000)),
+    (&[0x7FC0_0000], Bits(0xFFC0_0000)),
+    (&[0xFFA0_0000], Bits(0x7FA0_0000)),
+];
+
+const F32_SQRT: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0001], Bits(0x1A35_04F3)),
+    (&[0x3F00_0000], Bits(0x3F35_04F3)),
+    (&[0xBF00_0000], Nan),
+    (&[0x3FC0_0000], Bits(0x3F9C_C471)),
+    (&[0xC020_0000], Nan),
+    (&[0x4060_0000], Bits(0x3FEF_7751)),
+    (&[0x40C9_0FDB], Bits(0x4020_6C99)),
+    (&[0x7F80_0000], Bits(0x7F80_0000)),
+    (&[0xFF80_0000], Nan),
+    (&[0x7FC0_0000], Nan),
+    (&[0xBF80_0000], Nan),
+];
+
+const F32_CEIL: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0001], Bits(0x3F80_0000)),
+    (&[0x3F00_0000], Bits(0x3F80_0000)),
+    (&[0xBF00_0000], Bits(0x8000_0000)),
+    (&[0x3FC0_0000], Bits(0x4000_0000)),
+    (&[0xC020_0000], Bits(0xC000_0000)),
+    (&[0x4060_0000], Bits(0x4080_0000)),
+    (&[0x40C9_0FDB], Bits(0x40E0_0000)),
+    (&[0x7F80_0000], Bits(0x7F80_0000)),
+    (&[0xFF80_0000], Bits(0xFF80_0000)),
+    (&[0x7FC0_0000], Nan),
+    (&[0xBF80_0000], Bits(0xBF80_0000)),
+];
+
+const F32_FLOOR: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0001], Bits(0x0000_0000)),
+    (&[0x3F00_0000], Bits(0x0000_0000)),
+    (&[0xBF00_0000], Bits(0xBF80_0000)),
+    (&[0x3FC0_0000], Bits(0x3F80_0000)),
+    (&[0xC020_0000], Bits(0xC040_0000)),
+    (&[0x4060_0000], Bits(0x4040_0000)),
+    (&[0x40C9_0FDB], Bits(0x40C0_0000)),
+    (&[0x7F80_0000], Bits(0x7F80_0000)),
+    (&[0xFF80_0000], Bits(0xFF80_0000)),
+    (&[0x7FC0_0000], Nan),
+    (&[0xBF80_0000], Bits(0xBF80_0000)),
+];
+
+const F32_TRUNC: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0001], Bits(0x0000_0000)),
+    (&[0x3F00_0000], Bits(0x0000_0000)),
+    (&[0xBF00_0000], Bits(0x8000_0000)),
+    (&[0x3FC0_0000], Bits(0x3F80_0000)),
+    (&[0xC020_0000], Bits(0xC000_0000)),
+    (&[0x4060_0000], Bits(0x4040_0000)),
+    (&[0x40C9_0FDB], Bits(0x40C0_0000)),
+    (&[0x7F80_0000], Bits(0x7F80_0000)),
+    (&[0xFF80_0000], Bits(0xFF80_0000)),
+    (&[0x7FC0_0000], Nan),
+    (&[0xBF80_0000], Bits(0xBF80_0000)),
+];
+
+const F32_NEAREST: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0001], Bits(0x0000_0000)),
+    (&[0x3F00_0000], Bits(0x0000_0000)),
+    (&[0xBF00_0000], Bits(0x8000_0000)),
+    (&[0x3FC0_0000], Bits(0x4000_0000)),
+    (&[0xC020_0000], Bits(0xC000_0000)),
+    (&[0x4060_0000], Bits(0x4080_0000)),
+    (&[0x40C9_0FDB], Bits(0x40C0_0000)),
+    (&[0x7F80_0000], Bits(0x7F80_0000)),
+    (&[0xFF80_0000], Bits(0xFF80_0000)),
+    (&[0x7FC0_0000], Nan),
+    (&[0xBF80_0000], Bits(0xBF80_0000)),
+];
+
+const F32_EQ: &[Vector] = &[
+    (&[0x0000_0000, 0x8000_0000], Bits(1)),
+    (&[0x3F80_0000, 0x3F80_0000], Bits(1)),
+    (&[0x7F80_0000, 0x7F80_0000], Bits(1)),
+    (&[0x0000_0001, 0x0000_0000], Bits(0)),
+    (&[0x7FC0_0000, 0x7FC0_0000], Bits(0)),
+];
+
+const F32_NE: &[Vector] = &[
+    (&[0x0000_0000, 0x8000_0000], Bits(0)),
+    (&[0x7FC0_0000, 0x7FC0_0000], Bits(1)),
+    (&[0x3F80_0000, 0x7FC0_0000], Bits(1)),
+];
+
+const F32_LT: &[Vector] = &[
+    (&[0x8000_0000, 0x0000_0000], Bits(0)),
+    (&[0xFF80_0000, 0x7F7F_FFFF], Bits(1)),
+    (&[0x8000_0001, 0x0000_0001], Bits(1)),
+    (&[0x7FC0_0000, 0x3F80_0000], Bits(0)),
+];
+
+const F32_GE: &[Vector] = &[
+    (&[0x8000_0000, 0x0000_0000], Bits(1)),
+    (&[0x7F80_0000, 0x7F7F_FFFF], Bits(1)),
+    (&[0x3F80_0000, 0x7FC0_0000], Bits(0)),
+];
+
+const F64_ADD: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0002),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x3FE0_0000_0000_0000, 0x3FE0_0000_0000_0000],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x3FF0_0000_0000_0000, 0x0000_0000_0000_0001],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x4019_21FB_5444_2D18, 0x3FF0_0000_0000_0000],
+        Bits(0x401D_21FB_5444_2D18),
+    ),
+    (
+        &[0x7FEF_FFFF_FFFF_FFFF, 0x7FEF_FFFF_FFFF_FFFF],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (&[0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000], Nan),
+    (
+        &[0x7FF0_0000_0000_0000, 0x3FF0_0000_0000_0000],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (&[0x3FF0_0000_0000_0000, 0x7FF8_0000_0000_0000], Nan),
+];
+
+const F64_SUB: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x8000_0000_0000_0002),
+    ),
+    (
+        &[0x3FE0_0000_0000_0000, 0x3FE0_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x3FF0_0000_0000_0000, 0x0000_0000_0000_0001],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x4019_21FB_5444_2D18, 0x3FF0_0000_0000_0000],
+        Bits(0x4015_21FB_5444_2D18),
+    ),
+    (
+        &[0x7FEF_FFFF_FFFF_FFFF, 0x7FEF_FFFF_FFFF_FFFF],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0x3FF0_0000_0000_0000],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (&[0x3FF0_0000_0000_0000, 0x7FF8_0000_0000_0000], Nan),
+];
+
+const F64_MUL: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x3FE0_0000_0000_0000, 0x3FE0_0000_0000_0000],
+        Bits(0x3FD0_0000_0000_0000),
+    ),
+    (
+        &[0x3FF0_0000_0000_0000, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x4019_21FB_5444_2D18, 0x3FF0_0000_0000_0000],
+        Bits(0x4019_21FB_5444_2D18),
+    ),
+    (
+        &[0x7FEF_FFFF_FFFF_FFFF, 0x7FEF_FFFF_FFFF_FFFF],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000],
+        Bits(0xFFF0_0000_0000_0000),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0x3FF0_0000_0000_0000],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (&[0x3FF0_0000_0000_0000, 0x7FF8_0000_0000_0000], Nan),
+];
+
+const F64_DIV: &[Vector] = &[
+    (&[0x0000_0000_0000_0000, 0x0000_0000_0000_0000], Nan),
+    (&[0x8000_0000_0000_0000, 0x8000_0000_0000_0000], Nan),
+    (&[0x8000_0000_0000_0000, 0x0000_0000_0000_0000], Nan),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0xBFF0_0000_0000_0000),
+    ),
+    (
+        &[0x3FE0_0000_0000_0000, 0x3FE0_0000_0000_0000],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x3FF0_0000_0000_0000, 0x0000_0000_0000_0001],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x4019_21FB_5444_2D18, 0x3FF0_0000_0000_0000],
+        Bits(0x4019_21FB_5444_2D18),
+    ),
+    (
+        &[0x7FEF_FFFF_FFFF_FFFF, 0x7FEF_FFFF_FFFF_FFFF],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (&[0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000], Nan),
+    (
+        &[0x7FF0_0000_0000_0000, 0x3FF0_0000_0000_0000],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (&[0x0000_0000_0000_0000, 0x8000_0000_0000_0000], Nan),
+    (&[0x3FF0_0000_0000_0000, 0x7FF8_0000_0000_0000], Nan),
+];
+
+const F64_MIN: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x8000_0000_0000_0001),
+    ),
+    (
+        &[0x3FE0_0000_0000_0000, 0x3FE0_0000_0000_0000],
+        Bits(0x3FE0_0000_0000_0000),
+    ),
+    (
+        &[0x3FF0_0000_0000_0000, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x4019_21FB_5444_2D18, 0x3FF0_0000_0000_0000],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x7FEF_FFFF_FFFF_FFFF, 0x7FEF_FFFF_FFFF_FFFF],
+        Bits(0x7FEF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000],
+        Bits(0xFFF0_0000_0000_0000),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0x3FF0_0000_0000_0000],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (&[0x3FF0_0000_0000_0000, 0x7FF8_0000_0000_0000], Nan),
+];
+
+const F64_MAX: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x3FE0_0000_0000_0000, 0x3FE0_0000_0000_0000],
+        Bits(0x3FE0_0000_0000_0000),
+    ),
+    (
+        &[0x3FF0_0000_0000_0000, 0x0000_0000_0000_0001],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x4019_21FB_5444_2D18, 0x3FF0_0000_0000_0000],
+        Bits(0x4019_21FB_5444_2D18),
+    ),
+    (
+        &[0x7FEF_FFFF_FFFF_FFFF, 0x7FEF_FFFF_FFFF_FFFF],
+        Bits(0x7FEF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0x3FF0_0000_0000_0000],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (&[0x3FF0_0000_0000_0000, 0x7FF8_0000_0000_0000], Nan),
+];
+
+const F64_COPYSIGN: &[Vector] = &[
+    (
+        &[0x0000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x8000_0000_0000_0000, 0x0000_0000_0000_0000],
+        Bits(0x0000_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x8000_0000_0000_0001, 0x0000_0000_0000_0001],
+        Bits(0x0000_0000_0000_0001),
+    ),
+    (
+        &[0x3FE0_0000_0000_0000, 0x3FE0_0000_0000_0000],
+        Bits(0x3FE0_0000_0000_0000),
+    ),
+    (
+        &[0x3FF0_0000_0000_0000, 0x0000_0000_0000_0001],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+    (
+        &[0x4019_21FB_5444_2D18, 0x3FF0_0000_0000_0000],
+        Bits(0x4019_21FB_5444_2D18),
+    ),
+    (
+        &[0x7FEF_FFFF_FFFF_FFFF, 0x7FEF_FFFF_FFFF_FFFF],
+        Bits(0x7FEF_FFFF_FFFF_FFFF),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000],
+        Bits(0xFFF0_0000_0000_0000),
+    ),
+    (
+        &[0x7FF0_0000_0000_0000, 0x3FF0_0000_0000_0000],
+        Bits(0x7FF0_0000_0000_0000),
+    ),
+    (
+        &[0x0000_0000_0000_0000, 0x8000_0000_0000_0000],
+        Bits(0x8000_0000_0000_0000),
+    ),
+    (
+        &[0x3FF0_0000_0000_0000, 0x7FF8_0000_0000_0000],
+        Bits(0x3FF0_0000_0000_0000),
+    ),
+];
+
+const F64_ABS: &[Vector] = &[
+    (&[0x8000_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0xBFF0_0000_0000_0000], Bits(0x3FF0_0000_0000_0000)),
+    (&[0xFFF0_0000_0000_0000], Bits(0x7FF0_0000_0000_0000)),
+    (&[0xFFF8_0000_0000_0000], Bits(0x7FF8_0000_0000_0000)),
+    (&[0xFFF4_0000_0000_0000], Bits(0x7FF4_0000_0000_0000)),
+];
+
+const F64_NEG: &[Vector] = &[
+    (&[0x0000_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0xBFF0_0000_0000_0000], Bits(0x3FF0_0000_0000_0000)),
+    (&[0x7FF0_0000_0000_0000], Bits(0xFFF0_0000_0000_0000)),
+    (&[0x7FF8_0000_0000_0000], Bits(0xFFF8_0000_0000_0000)),
+    (&[0xFFF4_0000_0000_0000], Bits(0x7FF4_0000_0000_0000)),
+];
+
+const F64_SQRT: &[Vector] = &[
+    (&[0x0000_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x0000_0000_0000_0001], Bits(0x1E60_0000_0000_0000)),
+    (&[0x3FE0_0000_0000_0000], Bits(0x3FE6_A09E_667F_3BCD)),
+    (&[0xBFE0_0000_0000_0000], Nan),
+    (&[0x3FF8_0000_0000_0000], Bits(0x3FF3_988E_1409_212E)),
+    (&[0xC004_0000_0000_0000], Nan),
+    (&[0x400C_0000_0000_0000], Bits(0x3FFD_EEEA_1168_3F49)),
+    (&[0x4019_21FB_5444_2D18], Bits(0x4004_0D93_1FF6_2705)),
+    (&[0x7FF0_0000_0000_0000], Bits(0x7FF0_0000_0000_0000)),
+    (&[0xFFF0_0000_0000_0000], Nan),
+    (&[0x7FF8_0000_0000_0000], Nan),
+    (&[0xBFF0_0000_0000_0000], Nan),
+];
+
+const F64_CEIL: &[Vector] = &[
+    (&[0x0000_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x0000_0000_0000_0001], Bits(0x3FF0_0000_0000_0000)),
+    (&[0x3FE0_0000_0000_0000], Bits(0x3FF0_0000_0000_0000)),
+    (&[0xBFE0_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x3FF8_0000_0000_0000], Bits(0x4000_0000_0000_0000)),
+    (&[0xC004_0000_0000_0000], Bits(0xC000_0000_0000_0000)),
+    (&[0x400C_0000_0000_0000], Bits(0x4010_0000_0000_0000)),
+    (&[0x4019_21FB_5444_2D18], Bits(0x401C_0000_0000_0000)),
+    (&[0x7FF0_0000_0000_0000], Bits(0x7FF0_0000_0000_0000)),
+    (&[0xFFF0_0000_0000_0000], Bits(0xFFF0_0000_0000_0000)),
+    (&[0x7FF8_0000_0000_0000], Nan),
+    (&[0xBFF0_0000_0000_0000], Bits(0xBFF0_0000_0000_0000)),
+];
+
+const F64_FLOOR: &[Vector] = &[
+    (&[0x0000_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x0000_0000_0000_0001], Bits(0x0000_0000_0000_0000)),
+    (&[0x3FE0_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0xBFE0_0000_0000_0000], Bits(0xBFF0_0000_0000_0000)),
+    (&[0x3FF8_0000_0000_0000], Bits(0x3FF0_0000_0000_0000)),
+    (&[0xC004_0000_0000_0000], Bits(0xC008_0000_0000_0000)),
+    (&[0x400C_0000_0000_0000], Bits(0x4008_0000_0000_0000)),
+    (&[0x4019_21FB_5444_2D18], Bits(0x4018_0000_0000_0000)),
+    (&[0x7FF0_0000_0000_0000], Bits(0x7FF0_0000_0000_0000)),
+    (&[0xFFF0_0000_0000_0000], Bits(0xFFF0_0000_0000_0000)),
+    (&[0x7FF8_0000_0000_0000], Nan),
+    (&[0xBFF0_0000_0000_0000], Bits(0xBFF0_0000_0000_0000)),
+];
+
+const F64_TRUNC: &[Vector] = &[
+    (&[0x0000_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x0000_0000_0000_0001], Bits(0x0000_0000_0000_0000)),
+    (&[0x3FE0_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0xBFE0_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x3FF8_0000_0000_0000], Bits(0x3FF0_0000_0000_0000)),
+    (&[0xC004_0000_0000_0000], Bits(0xC000_0000_0000_0000)),
+    (&[0x400C_0000_0000_0000], Bits(0x4008_0000_0000_0000)),
+    (&[0x4019_21FB_5444_2D18], Bits(0x4018_0000_0000_0000)),
+    (&[0x7FF0_0000_0000_0000], Bits(0x7FF0_0000_0000_0000)),
+    (&[0xFFF0_0000_0000_0000], Bits(0xFFF0_0000_0000_0000)),
+    (&[0x7FF8_0000_0000_0000], Nan),
+    (&[0xBFF0_0000_0000_0000], Bits(0xBFF0_0000_0000_0000)),
+];
+
+const F64_NEAREST: &[Vector] = &[
+    (&[0x0000_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x0000_0000_0000_0001], Bits(0x0000_0000_0000_0000)),
+    (&[0x3FE0_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0xBFE0_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x3FF8_0000_0000_0000], Bits(0x4000_0000_0000_0000)),
+    (&[0xC004_0000_0000_0000], Bits(0xC000_0000_0000_0000)),
+    (&[0x400C_0000_0000_0000], Bits(0x4010_0000_0000_0000)),
+    (&[0x4019_21FB_5444_2D18], Bits(0x4018_0000_0000_0000)),
+    (&[0x7FF0_0000_0000_0000], Bits(0x7FF0_0000_0000_0000)),
+    (&[0xFFF0_0000_0000_0000], Bits(0xFFF0_0000_0000_0000)),
+    (&[0x7FF8_0000_0000_0000], Nan),
+    (&[0xBFF0_0000_0000_0000], Bits(0xBFF0_0000_0000_0000)),
+];
+
+const F64_EQ: &[Vector] = &[
+    (&[0x0000_0000_0000_0000, 0x8000_0000_0000_0000], Bits(1)),
+    (&[0x3FF0_0000_0000_0000, 0x3FF0_0000_0000_0000], Bits(1)),
+    (&[0x0000_0000_0000_0001, 0x0000_0000_0000_0000], Bits(0)),
+    (&[0x7FF8_0000_0000_0000, 0x7FF8_0000_0000_0000], Bits(0)),
+];
+
+const F64_NE: &[Vector] = &[
+    (&[0x0000_0000_0000_0000, 0x8000_0000_0000_0000], Bits(0)),
+    (&[0x7FF8_0000_0000_0000, 0x7FF8_0000_0000_0000], Bits(1)),
+];
+
+const F64_LE: &[Vector] = &[
+    (&[0x8000_0000_0000_0000, 0x0000_0000_0000_0000], Bits(1)),
+    (&[0xFFF0_0000_0000_0000, 0x7FEF_FFFF_FFFF_FFFF], Bits(1)),
+    (&[0x0000_0000_0000_0001, 0x8000_0000_0000_0001], Bits(0)),
+    (&[0x7FF8_0000_0000_0000, 0x3FF0_0000_0000_0000], Bits(0)),
+];
+
+const F64_GT: &[Vector] = &[
+    (&[0x8000_0000_0000_0000, 0x0000_0000_0000_0000], Bits(0)),
+    (&[0x7FF0_0000_0000_0000, 0x7FEF_FFFF_FFFF_FFFF], Bits(1)),
+    (&[0x3FF0_0000_0000_0000, 0x7FF8_0000_0000_0000], Bits(0)),
+];
+
+const I64_EXTEND_I32_S: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0x0000_2710], Bits(0x0000_0000_0000_2710)),
+    (&[0xFFFF_D8F0], Bits(0xFFFF_FFFF_FFFF_D8F0)),
+    (&[0xFFFF_FFFF], Bits(0xFFFF_FFFF_FFFF_FFFF)),
+    (&[0x7FFF_FFFF], Bits(0x0000_0000_7FFF_FFFF)),
+    (&[0x8000_0000], Bits(0xFFFF_FFFF_8000_0000)),
+];
+
+const I64_EXTEND_I32_U: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0xFFFF_D8F0], Bits(0x0000_0000_FFFF_D8F0)),
+    (&[0xFFFF_FFFF], Bits(0x0000_0000_FFFF_FFFF)),
+    (&[0x8000_0000], Bits(0x0000_0000_8000_0000)),
+];
+
+const I32_WRAP_I64: &[Vector] = &[
+    (&[0xFFFF_FFFF_FFFF_FFFF], Bits(0xFFFF_FFFF)),
+    (&[0xFFFF_FFFF_FFFE_7960], Bits(0xFFFE_7960)),
+    (&[0x0000_0000_8000_0000], Bits(0x8000_0000)),
+    (&[0xFFFF_FFFF_7FFF_FFFF], Bits(0x7FFF_FFFF)),
+    (&[0xFFFF_FFFF_0000_0000], Bits(0x0000_0000)),
+    (&[0xFFFF_FFFE_FFFF_FFFF], Bits(0xFFFF_FFFF)),
+    (&[0x0000_0001_0000_0001], Bits(0x0000_0001)),
+    (&[0x0000_0002_540B_E3FF], Bits(0x540B_E3FF)),
+];
+
+const I32_TRUNC_F32_S: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000], Bits(0x0000_0000)),
+    (&[0x0000_0001], Bits(0x0000_0000)),
+    (&[0x3F80_0000], Bits(0x0000_0001)),
+    (&[0x3FC0_0000], Bits(0x0000_0001)),
+    (&[0xBFC0_0000], Bits(0xFFFF_FFFF)),
+    (&[0xBFF3_3333], Bits(0xFFFF_FFFF)),
+    (&[0xC000_0000], Bits(0xFFFF_FFFE)),
+    (&[0x4EFF_FFFF], Bits(0x7FFF_FF80)),
+    (&[0xCF00_0000], Bits(0x8000_0000)),
+    (&[0x4F00_0000], Trap(IntegerOverflow)),
+    (&[0xCF00_0001], Trap(IntegerOverflow)),
+    (&[0x7F80_0000], Trap(IntegerOverflow)),
+    (&[0x7FC0_0000], Trap(BadConversionToInteger)),
+];
+
+const I32_TRUNC_F32_U: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000], Bits(0x0000_0000)),
+    (&[0x3F80_0000], Bits(0x0000_0001)),
+    (&[0x3FC0_0000], Bits(0x0000_0001)),
+    (&[0x3FF3_3333], Bits(0x0000_0001)),
+    (&[0x4F00_0000], Bits(0x8000_0000)),
+    (&[0x4F7F_FFFF], Bits(0xFFFF_FF00)),
+    (&[0xBF66_6666], Bits(0x0000_0000)),
+    (&[0xBF7F_FFFF], Bits(0x0000_0000)),
+    (&[0xBF80_0000], Trap(IntegerOverflow)),
+    (&[0x4F80_0000], Trap(IntegerOverflow)),
+    (&[0x7FC0_0000], Trap(BadConversionToInteger)),
+];
+
+const I32_TRUNC_F64_S: &[Vector] = &[
+    (&[0x41DF_FFFF_FFC0_0000], Bits(0x7FFF_FFFF)),
+    (&[0xC1E0_0000_0000_0000], Bits(0x8000_0000)),
+    (&[0x41E0_0000_0000_0000], Trap(IntegerOverflow)),
+    (&[0xC1E0_0000_0020_0000], Trap(IntegerOverflow)),
+    (&[0xC1E0_0000_001C_CCCD], Bits(0x8000_0000)),
+    (&[0x3FF8_0000_0000_0000], Bits(0x0000_0001)),
+    (&[0xFFF0_0000_0000_0000], Trap(IntegerOverflow)),
+];
+
+const I64_TRUNC_F64_S: &[Vector] = &[
+    (&[0x43DF_FFFF_FFFF_FFFF], Bits(0x7FFF_FFFF_FFFF_FC00)),
+    (&[0xC3E0_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x43E0_0000_0000_0000], Trap(IntegerOverflow)),
+    (&[0xC3E0_0000_0000_0001], Trap(IntegerOverflow)),
+    (&[0xBFF8_0000_0000_0000], Bits(0xFFFF_FFFF_FFFF_FFFF)),
+    (&[0x7FF8_0000_0000_0000], Trap(BadConversionToInteger)),
+];
+
+const I64_TRUNC_F64_U: &[Vector] = &[
+    (&[0x43EF_FFFF_FFFF_FFFF], Bits(0xFFFF_FFFF_FFFF_F800)),
+    (&[0x43F0_0000_0000_0000], Trap(IntegerOverflow)),
+    (&[0xBFEC_CCCC_CCCC_CCCD], Bits(0x0000_0000_0000_0000)),
+    (&[0xBFF0_0000_0000_0000], Trap(IntegerOverflow)),
+    (&[0x41EF_FFFF_FFE0_0000], Bits(0x0000_0000_FFFF_FFFF)),
+];
+
+const I32_TRUNC_SAT_F32_S: &[Vector] = &[
+    (&[0x3FC0_0000], Bits(0x0000_0001)),
+    (&[0x4F00_0000], Bits(0x7FFF_FFFF)),
+    (&[0xCF00_0001], Bits(0x8000_0000)),
+    (&[0x7F80_0000], Bits(0x7FFF_FFFF)),
+    (&[0xFF80_0000], Bits(0x8000_0000)),
+    (&[0x7FC0_0000], Bits(0x0000_0000)),
+    (&[0xFFC0_0000], Bits(0x0000_0000)),
+];
+
+const I32_TRUNC_SAT_F32_U: &[Vector] = &[
+    (&[0x3FC0_0000], Bits(0x0000_0001)),
+    (&[0x4F80_0000], Bits(0xFFFF_FFFF)),
+    (&[0xBF80_0000], Bits(0x0000_0000)),
+    (&[0x7F80_0000], Bits(0xFFFF_FFFF)),
+    (&[0x7FC0_0000], Bits(0x0000_0000)),
+];
+
+const I64_TRUNC_SAT_F64_S: &[Vector] = &[
+    (&[0x43E0_0000_0000_0000], Bits(0x7FFF_FFFF_FFFF_FFFF)),
+    (&[0xC3E0_0000_0000_0001], Bits(0x8000_0000_0000_0000)),
+    (&[0x7FF8_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0xBFF8_0000_0000_0000], Bits(0xFFFF_FFFF_FFFF_FFFF)),
+];
+
+const I64_TRUNC_SAT_F64_U: &[Vector] = &[
+    (&[0x43F0_0000_0000_0000], Bits(0xFFFF_FFFF_FFFF_FFFF)),
+    (&[0xBFF0_0000_0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0x43EF_FFFF_FFFF_FFFF], Bits(0xFFFF_FFFF_FFFF_F800)),
+];
+
+const F32_CONVERT_I32_S: &[Vector] = &[
+    (&[0x0000_0001], Bits(0x3F80_0000)),
+    (&[0xFFFF_FFFF], Bits(0xBF80_0000)),
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x7FFF_FFFF], Bits(0x4F00_0000)),
+    (&[0x8000_0000], Bits(0xCF00_0000)),
+    (&[0x4996_02D2], Bits(0x4E93_2C06)),
+    (&[0x0100_0001], Bits(0x4B80_0000)),
+    (&[0xFEFF_FFFF], Bits(0xCB80_0000)),
+];
+
+const F32_CONVERT_I32_U: &[Vector] = &[
+    (&[0x0000_0001], Bits(0x3F80_0000)),
+    (&[0x0000_0000], Bits(0x0000_0000)),
+    (&[0x7FFF_FFFF], Bits(0x4F00_0000)),
+    (&[0x8000_0000], Bits(0x4F00_0000)),
+    (&[0xFFFF_FFFF], Bits(0x4F80_0000)),
+    (&[0x0100_0001], Bits(0x4B80_0000)),
+    (&[0x8000_0080], Bits(0x4F00_0000)),
+    (&[0x8000_0081], Bits(0x4F00_0001)),
+];
+
+const F32_CONVERT_I64_S: &[Vector] = &[
+    (&[0x7FFF_FFFF_FFFF_FFFF], Bits(0x5F00_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0xDF00_0000)),
+    (&[0x0020_0000_2000_0001], Bits(0x5A00_0001)),
+    (&[0xFFDF_FFFF_DFFF_FFFF], Bits(0xDA00_0001)),
+];
+
+const F32_CONVERT_I64_U: &[Vector] = &[
+    (&[0xFFFF_FFFF_FFFF_FFFF], Bits(0x5F80_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0x5F00_0000)),
+    (&[0x0020_0000_2000_0001], Bits(0x5A00_0001)),
+];
+
+const F64_CONVERT_I32_S: &[Vector] = &[
+    (&[0x8000_0000], Bits(0xC1E0_0000_0000_0000)),
+    (&[0xFFFF_FFFF], Bits(0xBFF0_0000_0000_0000)),
+];
+
+const F64_CONVERT_I32_U: &[Vector] = &[
+    (&[0x8000_0000], Bits(0x41E0_0000_0000_0000)),
+    (&[0xFFFF_FFFF], Bits(0x41EF_FFFF_FFE0_0000)),
+];
+
+const F64_CONVERT_I64_S: &[Vector] = &[
+    (&[0x7FFF_FFFF_FFFF_FFFF], Bits(0x43E0_0000_0000_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0xC3E0_0000_0000_0000)),
+    (&[0x0020_0000_0000_0001], Bits(0x4340_0000_0000_0000)),
+    (&[0x0020_0000_0000_0003], Bits(0x4340_0000_0000_0002)),
+    (&[0xFFDF_FFFF_FFFF_FFFF], Bits(0xC340_0000_0000_0000)),
+];
+
+const F64_CONVERT_I64_U: &[Vector] = &[
+    (&[0xFFFF_FFFF_FFFF_FFFF], Bits(0x43F0_0000_0000_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0x43E0_0000_0000_0000)),
+    (&[0x8000_0000_0000_0401], Bits(0x43E0_0000_0000_0001)),
+    (&[0x8000_0000_0000_0400], Bits(0x43E0_0000_0000_0000)),
+    (&[0x0020_0000_0000_0001], Bits(0x4340_0000_0000_0000)),
+];
+
+const F64_PROMOTE_F32: &[Vector] = &[
+    (&[0x0000_0000], Bits(0x0000_0000_0000_0000)),
+    (&[0x8000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x0000_0001], Bits(0x36A0_0000_0000_0000)),
+    (&[0x7F7F_FFFF], Bits(0x47EF_FFFF_E000_0000)),
+    (&[0x3F80_0000], Bits(0x3FF0_0000_0000_0000)),
+    (&[0xFF80_0000], Bits(0xFFF0_0000_0000_0000)),
+    (&[0x7FC0_0000], Nan),
+];
+
+const F32_DEMOTE_F64: &[Vector] = &[
+    (&[0x0000_0000_0000_0000], Bits(0x0000_0000)),
+    (&[0x8000_0000_0000_0000], Bits(0x8000_0000)),
+    (&[0x0000_0000_0000_0001], Bits(0x0000_0000)),
+    (&[0x36A0_0000_0000_0000], Bits(0x0000_0001)),
+    (&[0x3690_0000_0000_0000], Bits(0x0000_0000)),
+    (&[0x3690_0000_0000_0001], Bits(0x0000_0001)),
+    (&[0x47EF_FFFF_E000_0000], Bits(0x7F7F_FFFF)),
+    (&[0x47EF_FFFF_EFFF_FFFF], Bits(0x7F7F_FFFF)),
+    (&[0x47EF_FFFF_F000_0000], Bits(0x7F80_0000)),
+    (&[0x3FF0_0000_1000_0000], Bits(0x3F80_0000)),
+    (&[0x3FF0_0000_3000_0000], Bits(0x3F80_0002)),
+    (&[0x7FF0_0000_0000_0000], Bits(0x7F80_0000)),
+    (&[0x7FF8_0000_0000_0000], Nan),
+];
+
+const I32_REINTERPRET_F32: &[Vector] = &[
+    (&[0x8000_0000], Bits(0x8000_0000)),
+    (&[0x7F80_0000], Bits(0x7F80_0000)),
+    (&[0x7FA0_0000], Bits(0x7FA0_0000)),
+];
+
+const F64_REINTERPRET_I64: &[Vector] = &[
+    (&[0x8000_0000_0000_0000], Bits(0x8000_0000_0000_0000)),
+    (&[0x7FF4_0000_0000_0000], Bits(0x7FF4_0000_0000_0000)),
+    (&[0xFFF8_0000_0000_0001], Bits(0xFFF8_0000_0000_0001)),
+];
+
+#[test]
+fn i32_vectors_conform() {
+    use IntType::I32;
+    assert_conformance(&[
+        ("i32.add", Op::IntBinary(I32, BinaryIntOp::Add), I32_ADD),
+        ("i32.sub", Op::IntBinary(I32, BinaryIntOp::Sub), I32_SUB),
+        ("i32.mul", Op::IntBinary(I32, BinaryIntOp::Mul), I32_MUL),
+        (
+            "i32.div_s",
+            Op::IntBinary(I32, BinaryIntOp::Sdiv),
+            I32_DIV_S,
+        ),
+        (
+            "i32.div_u",
+            Op::IntBinary(I32, BinaryIntOp::Udiv),
+            I32_DIV_U,
+        ),
+        (
+            "i32.rem_s",
+            Op::IntBinary(I32, BinaryIntOp::Srem),
+            I32_REM_S,
+        ),
+        (
+            "i32.rem_u",
+            Op::IntBinary(I32, BinaryIntOp::Urem),
+            I32_REM_U,
+        ),
+        ("i32.and", Op::IntBinary(I32, BinaryIntOp::And), I32_AND),
+        ("i32.or", Op::IntBinary(I32, BinaryIntOp::Or), I32_OR),
+        ("i32.xor", Op::IntBinary(I32, BinaryIntOp::Xor), I32_XOR),
+        ("i32.shl", Op::IntShift(I32, ShiftIntOp::Shl), I32_SHL),
+        ("i32.shr_s", Op::IntShift(I32, ShiftIntOp::Sshr), I32_SHR_S),
+        ("i32.shr_u", Op::IntShift(I32, ShiftIntOp::Ushr), I32_SHR_U),
+        ("i32.rotl", Op::IntShift(I32, ShiftIntOp::Rotl), I32_ROTL),
+        ("i32.rotr", Op::IntShift(I32, ShiftIntOp::Rotr), I32_ROTR),
+        (
+            "i32.clz",
+            Op::IntUnary(I32, UnaryIntOp::LeadingZeros),
+            I32_CLZ,
+        ),
+        (
+            "i32.ctz",
+            Op::IntUnary(I32, UnaryIntOp::TrailingZeros),
+            I32_CTZ,
+        ),
+        (
+            "i32.popcnt",
+            Op::IntUnary(I32, UnaryIntOp::PopCount),
+            I32_POPCNT,
+        ),
+        ("i32.eq", Op::IntCompare(I32, CompareIntOp::Eq), I32_EQ),
+        ("i32.lt_s", Op::IntCompare(I32, CompareIntOp::Slt), I32_LT_S),
+        ("i32.lt_u", Op::IntCompare(I32, CompareIntOp::Ult), I32_LT_U),
+        ("i32.ge_s", Op::IntCompare(I32, CompareIntOp::Sge), I32_GE_S),
+        ("i32.ge_u", Op::IntCompare(I32, CompareIntOp::Uge), I32_GE_U),
+    ]);
+}
+
+#[test]
+fn i64_vectors_conform() {
+    use IntType::I64;
+    assert_conformance(&[
+        ("i64.add", Op::IntBinary(I64, BinaryIntOp::Add), I64_ADD),
+        ("i64.sub", Op::IntBinary(I64, BinaryIntOp::Sub), I64_SUB),
+        ("i64.mul", Op::IntBinary(I64, BinaryIntOp::Mul), I64_MUL),
+        (
+            "i64.div_s",
+            Op::IntBinary(I64, BinaryIntOp::Sdiv),
+            I64_DIV_S,
+        ),
+        (
+            "i64.div_u",
+            Op::IntBinary(I64, BinaryIntOp::Udiv),
+            I64_DIV_U,
+        ),
+        (
+            "i64.rem_s",
+            Op::IntBinary(I64, BinaryIntOp::Srem),
+            I64_REM_S,
+        ),
+        (
+            "i64.rem_u",
+            Op::IntBinary(I64, BinaryIntOp::Urem),
+            I64_REM_U,
+        ),
+        ("i64.and", Op::IntBinary(I64, BinaryIntOp::And), I64_AND),
+        ("i64.or", Op::IntBinary(I64, BinaryIntOp::Or), I64_OR),
+        ("i64.xor", Op::IntBinary(I64, BinaryIntOp::Xor), I64_XOR),
+        ("i64.shl", Op::IntShift(I64, ShiftIntOp::Shl), I64_SHL),
+        ("i64.shr_s", Op::IntShift(I64, ShiftIntOp::Sshr), I64_SHR_S),
+        ("i64.shr_u", Op::IntShift(I64, ShiftIntOp::Ushr), I64_SHR_U),
+        ("i64.rotl", Op::IntShift(I64, ShiftIntOp::Rotl), I64_ROTL),
+        ("i64.rotr", Op::IntShift(I64, ShiftIntOp::Rotr), I64_ROTR),
+        (
+            "i64.clz",
+            Op::IntUnary(I64, UnaryIntOp::LeadingZeros),
+            I64_CLZ,
+        ),
+        (
+            "i64.ctz",
+            Op::IntUnary(I64, UnaryIntOp::TrailingZeros),
+            I64_CTZ,
+        ),
+        (
+            "i64.popcnt",
+            Op::IntUnary(I64, UnaryIntOp::PopCount),
+            I64_POPCNT,
+        ),
+        ("i64.lt_s", Op::IntCompare(I64, CompareIntOp::Slt), I64_LT_S),
+        ("i64.lt_u", Op::IntCompare(I64, CompareIntOp::Ult), I64_LT_U),
+    ]);
+}
+
+#[test]
+fn f32_vectors_conform() {
+    use FloatType::F32;
+    assert_conformance(&[
+        ("f32.add", Op::FloatBinary(F32, BinaryFloatOp::Add), F32_ADD),
+        ("f32.sub", Op::FloatBinary(F32, BinaryFloatOp::Sub), F32_SUB),
+        ("f32.mul", Op::FloatBinary(F32, BinaryFloatOp::Mul), F32_MUL),
+        ("f32.div", Op::FloatBinary(F32, BinaryFloatOp::Div), F32_DIV),
+        ("f32.min", Op::FloatBinary(F32, BinaryFloatOp::Min), F32_MIN),
+        ("f32.max", Op::FloatBinary(F32, BinaryFloatOp::Max), F32_MAX),
+        (
+            "f32.copysign",
+            Op::FloatBinary(F32, BinaryFloatOp::CopySign),
+            F32_COPYSIGN,
+        ),
+        ("f32.abs", Op::FloatUnary(F32, UnaryFloatOp::Abs), F32_ABS),
+        ("f32.neg", Op::FloatUnary(F32, UnaryFloatOp::Neg), F32_NEG),
+        (
+            "f32.sqrt",
+            Op::FloatUnary(F32, UnaryFloatOp::Sqrt),
+            F32_SQRT,
+        ),
+        (
+            "f32.ceil",
+            Op::FloatUnary(F32, UnaryFloatOp::Ceil),
+            F32_CEIL,
+        ),
+        (
+            "f32.floor",
+            Op::FloatUnary(F32, UnaryFloatOp::Floor),
+            F32_FLOOR,
+        ),
+        (
+            "f32.trunc",
+            Op::FloatUnary(F32, UnaryFloatOp::Truncate),
+            F32_TRUNC,
+        ),
+        (
+            "f32.nearest",
+            Op::FloatUnary(F32, UnaryFloatOp::Nearest),
+            F32_NEAREST,
+        ),
+        ("f32.eq", Op::FloatCompare(F32, CompareFloatOp::Eq), F32_EQ),
+        ("f32.ne", Op::FloatCompare(F32, CompareFloatOp::Ne), F32_NE),
+        ("f32.lt", Op::FloatCompare(F32, CompareFloatOp::Lt), F32_LT),
+        ("f32.ge", Op::FloatCompare(F32, CompareFloatOp::Ge), F32_GE),
+    ]);
+}
+
+#[test]
+fn f64_vectors_conform() {
+    use FloatType::F64;
+    assert_conformance(&[
+        ("f64.add", Op::FloatBinary(F64, BinaryFloatOp::Add), F64_ADD),
+        ("f64.sub", Op::FloatBinary(F64, BinaryFloatOp::Sub), F64_SUB),
+        ("f64.mul", Op::FloatBinary(F64, BinaryFloatOp::Mul), F64_MUL),
+        ("f64.div", Op::FloatBinary(F64, BinaryFloatOp::Div), F64_DIV),
+        ("f64.min", Op::FloatBinary(F64, BinaryFloatOp::Min), F64_MIN),
+        ("f64.max", Op::FloatBinary(F64, BinaryFloatOp::Max), F64_MAX),
+        (
+            "f64.copysign",
+            Op::FloatBinary(F64, BinaryFloatOp::CopySign),
+            F64_COPYSIGN,
+        ),
+        ("f64.abs", Op::FloatUnary(F64, UnaryFloatOp::Abs), F64_ABS),
+        ("f64.neg", Op::FloatUnary(F64, UnaryFloatOp::Neg), F64_NEG),
+        (
+            "f64.sqrt",
+            Op::FloatUnary(F64, UnaryFloatOp::Sqrt),
+            F64_SQRT,
+        ),
+        (
+            "f64.ceil",
+            Op::FloatUnary(F64, UnaryFloatOp::Ceil),
+            F64_CEIL,
+        ),
+        (
+            "f64.floor",
+            Op::FloatUnary(F64, UnaryFloatOp::Floor),
+            F64_FLOOR,
+        ),
+        (
+            "f64.trunc",
+            Op::FloatUnary(F64, UnaryFloatOp::Truncate),
+            F64_TRUNC,
+        ),
+        (
+            "f64.nearest",
+            Op::FloatUnary(F64, UnaryFloatOp::Nearest),
+            F64_NEAREST,
+        ),
+        ("f64.eq", Op::FloatCompare(F64, CompareFloatOp::Eq), F64_EQ),
+        ("f64.ne", Op::FloatCompare(F64, CompareFloatOp::Ne), F64_NE),
+        ("f64.le", Op::FloatCompare(F64, CompareFloatOp::Le), F64_LE),
+        ("f64.gt", Op::FloatCompare(F64, CompareFloatOp::Gt), F64_GT),
+    ]);
+}
+
+#[test]
+fn conversion_vectors_conform() {
+    use FloatType::{F32, F64};
+    use IntType::{I32, I64};
+    assert_conformance(&[
+        (
+            "i64.extend_i32_s",
+            Op::Extend(I32, I64, true),
+            I64_EXTEND_I32_S,
+        ),
+        (
+            "i64.extend_i32_u",
+            Op::Extend(I32, I64, false),
+            I64_EXTEND_I32_U,
+        ),
+        ("i32.wrap_i64", Op::Truncate(I64, I32), I32_WRAP_I64),
+        (
+            "i32.trunc_f32_s",
+            Op::FloatToInt(F32, I32, true, false),
+            I32_TRUNC_F32_S,
+        ),
+        (
+            "i32.trunc_f32_u",
+            Op::FloatToInt(F32, I32, false, false),
+            I32_TRUNC_F32_U,
+        ),
+        (
+            "i32.trunc_f64_s",
+            Op::FloatToInt(F64, I32, true, false),
+            I32_TRUNC_F64_S,
+        ),
+        (
+            "i64.trunc_f64_s",
+            Op::FloatToInt(F64, I64, true, false),
+            I64_TRUNC_F64_S,
+        ),
+        (
+            "i64.trunc_f64_u",
+            Op::FloatToInt(F64, I64, false, false),
+            I64_TRUNC_F64_U,
+        ),
+        (
+            "i32.trunc_sat_f32_s",
+            Op::FloatToInt(F32, I32, true, true),
+            I32_TRUNC_SAT_F32_S,
+        ),
+        (
+            "i32.trunc_sat_f32_u",
+            Op::FloatToInt(F32, I32, false, true),
+            I32_TRUNC_SAT_F32_U,
+        ),
+        (
+            "i64.trunc_sat_f64_s",
+            Op::FloatToInt(F64, I64, true, true),
+            I64_TRUNC_SAT_F64_S,
+        ),
+        (
+            "i64.trunc_sat_f64_u",
+            Op::FloatToInt(F64, I64, false, true),
+            I64_TRUNC_SAT_F64_U,
+        ),
+        (
+            "f32.convert_i32_s",
+            Op::IntToFloat(I32, F32, true),
+            F32_CONVERT_I32_S,
+        ),
+        (
+            "f32.convert_i32_u",
+            Op::IntToFloat(I32, F32, false),
+            F32_CONVERT_I32_U,
+        ),
+        (
+            "f32.convert_i64_s",
+            Op::IntToFloat(I64, F32, true),
+            F32_CONVERT_I64_S,
+        ),
+        (
+            "f32.convert_i64_u",
+            Op::IntToFloat(I64, F32, false),
+            F32_CONVERT_I64_U,
+        ),
+        (
+            "f64.convert_i32_s",
+            Op::IntToFloat(I32, F64, true),
+            F64_CONVERT_I32_S,
+        ),
+        (
+            "f64.convert_i32_u",
+            Op::IntToFloat(I32, F64, false),
+            F64_CONVERT_I32_U,
+        ),
+        (
+            "f64.convert_i64_s",
+            Op::IntToFloat(I64, F64, true),
+            F64_CONVERT_I64_S,
+        ),
+        (
+            "f64.convert_i64_u",
+            Op::IntToFloat(I64, F64, false),
+            F64_CONVERT_I64_U,
+        ),
+        ("f64.promote_f32", Op::Promote, F64_PROMOTE_F32),
+        ("f32.demote_f64", Op::Demote, F32_DEMOTE_F64),
+        (
+            "i32.reinterpret_f32",
+            Op::Reinterpret(F32.into(), I32.into()),
+            I32_REINTERPRET_F32,
+        ),
+        (
+            "f64.reinterpret_i64",
+            Op::Reinterpret(I64.into(), F64.into()),
+            F64_REINTERPRET_I64,
+        ),
+    ]);
+}
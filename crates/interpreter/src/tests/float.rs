@@ -0,0 +1,389 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Determinism tests for the interpretation of float instructions.
+//!
+//! Evaluates every float instruction on inputs that are known to expose
+//! platform dependent behavior, namely signaling and quiet NaNs with
+//! payloads, subnormals, signed zeros, infinities and rounding ties.
+//! Results must be bit-identical between repeated evaluations, both within
+//! the same and within fresh evaluation contexts.
+//!
+//! Additionally asserts properties that the Wasm specification guarantees
+//! regardless of the NaN payloads chosen by an implementation.
+
+use super::module_with_func;
+use crate::{EvaluationContext, ExecutionConfig, InterpretationError};
+use ir::{
+    instr::operands::{BinaryFloatOp, CompareFloatOp, UnaryFloatOp},
+    primitive::{FloatType, Func, IntType, Type},
+};
+use module::Module;
+
+/// The results of a sweep by the inputs given to the function.
+type Sweep = Vec<(Vec<u64>, Result<Vec<u64>, InterpretationError>)>;
+
+/// Interesting `f32` input bit patterns.
+const F32_INPUTS: &[u64] = &[
+    0x0000_0000, // +0.0
+    0x8000_0000, // -0.0
+    0x0000_0001, // smallest positive subnormal
+    0x807F_FFFF, // largest negative subnormal
+    0x0080_0000, // smallest positive normal
+    0x3DCC_CCCD, // 0.1
+    0x3F80_0000, // 1.0
+    0x3FC0_0000, // 1.5
+    0x4020_0000, // 2.5
+    0xC020_0000, // -2.5
+    0x7F7F_FFFF, // largest finite
+    0x7F80_0000, // +inf
+    0xFF80_0000, // -inf
+    0x7FC0_0000, // canonical quiet NaN
+    0xFFC0_0001, // negative quiet NaN with payload
+    0x7F80_0001, // signaling NaN
+    0x7FA0_0000, // signaling NaN with payload
+];
+
+/// Interesting `f64` input bit patterns.
+const F64_INPUTS: &[u64] = &[
+    0x0000_0000_0000_0000, // +0.0
+    0x8000_0000_0000_0000, // -0.0
+    0x0000_0000_0000_0001, // smallest positive subnormal
+    0x800F_FFFF_FFFF_FFFF, // largest negative subnormal
+    0x0010_0000_0000_0000, // smallest positive normal
+    0x3FB9_9999_9999_999A, // 0.1
+    0x3FF0_0000_0000_0000, // 1.0
+    0x3FF8_0000_0000_0000, // 1.5
+    0x4004_0000_0000_0000, // 2.5
+    0xC004_0000_0000_0000, // -2.5
+    0x7FEF_FFFF_FFFF_FFFF, // largest finite
+    0x7FF0_0000_0000_0000, // +inf
+    0xFFF0_0000_0000_0000, // -inf
+    0x7FF8_0000_0000_0000, // canonical quiet NaN
+    0xFFF8_0000_0000_0001, // negative quiet NaN with payload
+    0x7FF0_0000_0000_0001, // signaling NaN
+    0x7FF4_0000_0000_0000, // signaling NaN with payload
+];
+
+const FLOAT_TYPES: &[FloatType] = &[FloatType::F32, FloatType::F64];
+
+const UNARY_OPS: &[UnaryFloatOp] = &[
+    UnaryFloatOp::Abs,
+    UnaryFloatOp::Neg,
+    UnaryFloatOp::Sqrt,
+    UnaryFloatOp::Ceil,
+    UnaryFloatOp::Floor,
+    UnaryFloatOp::Truncate,
+    UnaryFloatOp::Nearest,
+];
+
+const BINARY_OPS: &[BinaryFloatOp] = &[
+    BinaryFloatOp::Add,
+    BinaryFloatOp::Sub,
+    BinaryFloatOp::Mul,
+    BinaryFloatOp::Div,
+    BinaryFloatOp::Min,
+    BinaryFloatOp::Max,
+    BinaryFloatOp::CopySign,
+];
+
+const COMPARE_OPS: &[CompareFloatOp] = &[
+    CompareFloatOp::Eq,
+    CompareFloatOp::Ne,
+    CompareFloatOp::Lt,
+    CompareFloatOp::Le,
+    CompareFloatOp::Gt,
+    CompareFloatOp::Ge,
+];
+
+/// Returns the interesting input bit patterns for the float type.
+fn inputs(ty: FloatType) -> &'static [u64] {
+    match ty {
+        FloatType::F32 => F32_INPUTS,
+        FloatType::F64 => F64_INPUTS,
+    }
+}
+
+/// Returns the bit mask of the sign bit of the float type.
+fn sign_mask(ty: FloatType) -> u64 {
+    1 << (ty.bit_width() - 1)
+}
+
+/// Returns `true` if the bits represent a NaN value of the float type.
+fn is_nan(ty: FloatType, bits: u64) -> bool {
+    match ty {
+        FloatType::F32 => f32::from_bits(bits as u32).is_nan(),
+        FloatType::F64 => f64::from_bits(bits).is_nan(),
+    }
+}
+
+/// Evaluates the function for all combinations of `arity` many inputs.
+fn sweep(module: &Module, func: Func, arity: usize, inputs: &[u64]) -> Sweep {
+    let mut ctx = EvaluationContext::new(module);
+    sweep_in(&mut ctx, func, arity, inputs)
+}
+
+/// Like [`sweep`] but evaluates the function within the given context.
+fn sweep_in(
+    ctx: &mut EvaluationContext,
+    func: Func,
+    arity: usize,
+    inputs: &[u64],
+) -> Sweep {
+    let args: Vec<Vec<u64>> = match arity {
+        1 => inputs.iter().map(|&x| vec![x]).collect(),
+        2 => inputs
+            .iter()
+            .flat_map(|&x| inputs.iter().map(move |&y| vec![x, y]))
+            .collect(),
+        _ => unreachable!("encountered unsupported arity {}", arity),
+    };
+    args.into_iter()
+        .map(|args| {
+            let mut results = Vec::new();
+            let result = ctx
+                .evaluate_function(func, args.iter().copied(), |result| {
+                    results.push(result)
+                })
+                .map(|_| results);
+            (args, result)
+        })
+        .collect()
+}
+
+/// Sweeps the function twice and asserts that the results are bit-identical.
+fn deterministic_sweep(
+    module: &Module,
+    func: Func,
+    arity: usize,
+    inputs: &[u64],
+) -> Sweep {
+    let first = sweep(module, func, arity, inputs);
+    let second = sweep(module, func, arity, inputs);
+    assert_eq!(first, second);
+    first
+}
+
+/// Returns the single output of a successful evaluation.
+fn single_output(result: &Result<Vec<u64>, InterpretationError>) -> u64 {
+    match result {
+        Ok(outputs) if outputs.len() == 1 => outputs[0],
+        unexpected => panic!("unexpected evaluation result: {:?}", unexpected),
+    }
+}
+
+#[test]
+fn unary_float_instructions_are_deterministic() {
+    for &ty in FLOAT_TYPES {
+        for &op in UNARY_OPS {
+            let (func, module) =
+                module_with_func(&[ty.into()], &[ty.into()], |b| {
+                    let x = b.input_var(0).unwrap();
+                    let x = b.read_var(x)?;
+                    let ins = b.ins()?;
+                    let result = match op {
+                        UnaryFloatOp::Abs => ins.fabs(ty, x)?,
+                        UnaryFloatOp::Neg => ins.fneg(ty, x)?,
+                        UnaryFloatOp::Sqrt => ins.fsqrt(ty, x)?,
+                        UnaryFloatOp::Ceil => ins.fceil(ty, x)?,
+                        UnaryFloatOp::Floor => ins.ffloor(ty, x)?,
+                        UnaryFloatOp::Truncate => ins.ftruncate(ty, x)?,
+                        UnaryFloatOp::Nearest => ins.fnearest(ty, x)?,
+                    };
+                    b.ins()?.return_values([result].iter().copied())?;
+                    Ok(())
+                });
+            let sign = sign_mask(ty);
+            for (args, result) in
+                deterministic_sweep(&module, func, 1, inputs(ty))
+            {
+                let x = args[0];
+                let result = single_output(&result);
+                match op {
+                    // Sign bit operations must preserve NaN payloads.
+                    UnaryFloatOp::Abs => assert_eq!(result, x & !sign),
+                    UnaryFloatOp::Neg => assert_eq!(result, x ^ sign),
+                    _ => {
+                        if is_nan(ty, x) {
+                            assert!(is_nan(ty, result), "{} {:X}", op, x);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn binary_float_instructions_are_deterministic() {
+    for &ty in FLOAT_TYPES {
+        for &op in BINARY_OPS {
+            let (func, module) =
+                module_with_func(&[ty.into(), ty.into()], &[ty.into()], |b| {
+                    let lhs = b.input_var(0).unwrap();
+                    let rhs = b.input_var(1).unwrap();
+                    let lhs = b.read_var(lhs)?;
+                    let rhs = b.read_var(rhs)?;
+                    let ins = b.ins()?;
+                    let result = match op {
+                        BinaryFloatOp::Add => ins.fadd(ty, lhs, rhs)?,
+                        BinaryFloatOp::Sub => ins.fsub(ty, lhs, rhs)?,
+                        BinaryFloatOp::Mul => ins.fmul(ty, lhs, rhs)?,
+                        BinaryFloatOp::Div => ins.fdiv(ty, lhs, rhs)?,
+                        BinaryFloatOp::Min => ins.fmin(ty, lhs, rhs)?,
+                        BinaryFloatOp::Max => ins.fmax(ty, lhs, rhs)?,
+                        BinaryFloatOp::CopySign => {
+                            ins.fcopysign(ty, lhs, rhs)?
+                        }
+                    };
+                    b.ins()?.return_values([result].iter().copied())?;
+                    Ok(())
+                });
+            let sign = sign_mask(ty);
+            for (args, result) in
+                deterministic_sweep(&module, func, 2, inputs(ty))
+            {
+                let (lhs, rhs) = (args[0], args[1]);
+                match op {
+                    // Sign bit operations must preserve NaN payloads.
+                    BinaryFloatOp::CopySign => {
+                        let expected = (lhs & !sign) | (rhs & sign);
+                        assert_eq!(single_output(&result), expected);
+                    }
+                    BinaryFloatOp::Add
+                    | BinaryFloatOp::Sub
+                    | BinaryFloatOp::Mul
+                        if is_nan(ty, lhs) || is_nan(ty, rhs) =>
+                    {
+                        let result = single_output(&result);
+                        assert!(
+                            is_nan(ty, result),
+                            "{} {:X} {:X}",
+                            op,
+                            lhs,
+                            rhs
+                        );
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn compare_float_instructions_are_deterministic() {
+    for &ty in FLOAT_TYPES {
+        for &op in COMPARE_OPS {
+            let (func, module) =
+                module_with_func(&[ty.into(), ty.into()], &[Type::Bool], |b| {
+                    let lhs = b.input_var(0).unwrap();
+                    let rhs = b.input_var(1).unwrap();
+                    let lhs = b.read_var(lhs)?;
+                    let rhs = b.read_var(rhs)?;
+                    let result = b.ins()?.fcmp(ty, op, lhs, rhs)?;
+                    b.ins()?.return_values([result].iter().copied())?;
+                    Ok(())
+                });
+            for (args, result) in
+                deterministic_sweep(&module, func, 2, inputs(ty))
+            {
+                let result = single_output(&result);
+                // Comparisons with NaN are unordered.
+                if is_nan(ty, args[0]) || is_nan(ty, args[1]) {
+                    let expected = (op == CompareFloatOp::Ne) as u64;
+                    assert_eq!(result, expected, "{} {:X?}", op, args);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn float_conversions_are_deterministic() {
+    let conversions = [
+        (FloatType::F32, FloatType::F64),
+        (FloatType::F64, FloatType::F32),
+    ];
+    for &(from_type, to_type) in &conversions {
+        let (func, module) =
+            module_with_func(&[from_type.into()], &[to_type.into()], |b| {
+                let x = b.input_var(0).unwrap();
+                let x = b.read_var(x)?;
+                let ins = b.ins()?;
+                let result = if from_type.bit_width() < to_type.bit_width() {
+                    ins.promote(from_type, to_type, x)?
+                } else {
+                    ins.demote(from_type, to_type, x)?
+                };
+                b.ins()?.return_values([result].iter().copied())?;
+                Ok(())
+            });
+        for (args, result) in
+            deterministic_sweep(&module, func, 1, inputs(from_type))
+        {
+            if is_nan(from_type, args[0]) {
+                assert!(is_nan(to_type, single_output(&result)));
+            }
+        }
+    }
+    for &from_type in FLOAT_TYPES {
+        for &to_type in &[IntType::I32, IntType::I64] {
+            for &signed in &[false, true] {
+                let (func, module) = module_with_func(
+                    &[from_type.into()],
+                    &[to_type.into()],
+                    |b| {
+                        let x = b.input_var(0).unwrap();
+                        let x = b.read_var(x)?;
+                        let result = b.ins()?.float_to_int(
+                            from_type, to_type, signed, x, false,
+                        )?;
+                        b.ins()?.return_values([result].iter().copied())?;
+                        Ok(())
+                    },
+                );
+                deterministic_sweep(&module, func, 1, inputs(from_type));
+            }
+        }
+    }
+}
+
+#[test]
+fn constant_time_min_and_max_are_bit_identical() {
+    let mut config = ExecutionConfig::new();
+    config.set_constant_time(true);
+    for &ty in FLOAT_TYPES {
+        for &op in &[BinaryFloatOp::Min, BinaryFloatOp::Max] {
+            let (func, module) =
+                module_with_func(&[ty.into(), ty.into()], &[ty.into()], |b| {
+                    let lhs = b.input_var(0).unwrap();
+                    let rhs = b.input_var(1).unwrap();
+                    let lhs = b.read_var(lhs)?;
+                    let rhs = b.read_var(rhs)?;
+                    let ins = b.ins()?;
+                    let result = match op {
+                        BinaryFloatOp::Min => ins.fmin(ty, lhs, rhs)?,
+                        _ => ins.fmax(ty, lhs, rhs)?,
+                    };
+                    b.ins()?.return_values([result].iter().copied())?;
+                    Ok(())
+                });
+            let expected = sweep(&module, func, 2, inputs(ty));
+            let mut ctx = EvaluationContext::new(&module);
+            ctx.set_execution_config(config.clone());
+            assert_eq!(sweep_in(&mut ctx, func, 2, inputs(ty)), expected);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the clock and entropy providers of evaluation contexts.
+//!
+//! The default providers must be deterministic across evaluation contexts
+//! while the operating system providers are only available on request.
+
+use super::{evaluate_func_in_ctx, module_with_func};
+use crate::{
+    EntropyProvider,
+    EvaluationContext,
+    FixedClock,
+    SeededEntropy,
+};
+use ir::primitive::{Func, IntConst, IntType};
+use module::Module;
+
+/// Returns a module with a function that returns a constant.
+fn constant_module() -> (Func, Module) {
+    module_with_func(&[], &[IntType::I32.into()], |b| {
+        b.body()?;
+        let v0 = b.ins()?.constant(IntConst::I32(1))?;
+        b.ins()?.return_values([v0])?;
+        Ok(())
+    })
+}
+
+/// Returns the first `len` random bytes of the entropy provider.
+fn random_bytes(entropy: &mut dyn EntropyProvider, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0x00; len];
+    entropy.fill_bytes(&mut bytes);
+    bytes
+}
+
+#[test]
+fn default_providers_are_deterministic() {
+    let (_, module) = constant_module();
+    let mut lhs = EvaluationContext::new(&module);
+    let mut rhs = EvaluationContext::new(&module);
+    assert_eq!(lhs.clock_provider().realtime(), 0);
+    assert_eq!(lhs.clock_provider().monotonic(), 0);
+    assert_eq!(rhs.clock_provider().realtime(), 0);
+    let first = random_bytes(lhs.entropy_provider(), 64);
+    assert_eq!(first, random_bytes(rhs.entropy_provider(), 64));
+    // Subsequent queries continue the random stream.
+    let second = random_bytes(lhs.entropy_provider(), 64);
+    assert_ne!(first, second);
+    assert_eq!(second, random_bytes(rhs.entropy_provider(), 64));
+}
+
+#[test]
+fn seeded_entropy_generates_chacha20_stream() {
+    // The first block of the ChaCha20 key stream for the all zero key and
+    // nonce as given by RFC 7539 test vector #1 of section A.1.
+    let expected = [
+        0x76, 0xb8, 0xe0, 0xad, 0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5,
+        0x53, 0x86, 0xbd, 0x28, 0xbd, 0xd2, 0x19, 0xb8, 0xa0, 0x8d, 0xed, 0x1a,
+        0xa8, 0x36, 0xef, 0xcc, 0x8b, 0x77, 0x0d, 0xc7,
+    ];
+    let mut entropy = SeededEntropy::default();
+    assert_eq!(random_bytes(&mut entropy, 32), expected);
+    let mut entropy = SeededEntropy::new([0x00; 32]);
+    assert_eq!(random_bytes(&mut entropy, 32), expected);
+    assert_eq!(
+        random_bytes(&mut SeededEntropy::from_u64(42), 32),
+        random_bytes(&mut SeededEntropy::from_u64(42), 32),
+    );
+    assert_ne!(
+        random_bytes(&mut SeededEntropy::from_u64(1), 32),
+        random_bytes(&mut SeededEntropy::from_u64(2), 32),
+    );
+}
+
+#[test]
+fn providers_are_configurable() {
+    let (func, module) = constant_module();
+    let mut ctx = EvaluationContext::new(&module);
+    let epoch = 1_600_000_000_000_000_000;
+    ctx.set_clock_provider(FixedClock::new(epoch));
+    ctx.set_entropy_provider(SeededEntropy::from_u64(42));
+    assert_eq!(ctx.clock_provider().realtime(), epoch);
+    assert_eq!(ctx.clock_provider().monotonic(), epoch);
+    assert_eq!(
+        random_bytes(ctx.entropy_provider(), 32),
+        random_bytes(&mut SeededEntropy::from_u64(42), 32),
+    );
+    // The providers do not influence evaluations without host functions.
+    assert_eq!(evaluate_func_in_ctx(&mut ctx, func, &[]), vec![1]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn os_providers_are_opt_in() {
+    use crate::{OsClock, OsEntropy};
+    let (_, module) = constant_module();
+    let mut ctx = EvaluationContext::new(&module);
+    ctx.set_clock_provider(OsClock::default());
+    ctx.set_entropy_provider(OsEntropy);
+    // The clock of the operating system is past the default fixed epoch.
+    assert!(ctx.clock_provider().realtime() > 1_600_000_000_000_000_000);
+    let before = ctx.clock_provider().monotonic();
+    assert!(ctx.clock_provider().monotonic() >= before);
+    assert_ne!(random_bytes(ctx.entropy_provider(), 64), vec![0x00; 64]);
+}
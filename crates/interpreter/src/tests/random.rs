@@ -0,0 +1,414 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic randomized tests for the SSA construction of the function builder.
+//!
+//! Generates random programs with structured control flow (nested `if` and
+//! counting loops) as well as random variable reads and writes, translates
+//! them into Runwell IR via the [`FunctionBuilder`] and compares the results
+//! of interpreting the constructed functions against a simple reference
+//! evaluator operating on the generated program directly.
+//!
+//! This is meant to shake out phi placement bugs for control flow shapes that
+//! are cumbersome to cover with manually written tests.
+
+use super::{evaluate_func_in_ctx, module_with_func};
+use crate::EvaluationContext;
+use entity::RawIdx;
+use ir::{
+    instr::operands::CompareIntOp,
+    primitive::{IntConst, IntType, Type, Value},
+};
+use module::{builder::FunctionBuilder, primitive::Variable};
+
+/// The number of function inputs of every generated program.
+const LEN_INPUTS: u32 = 2;
+/// The number of function local variables of every generated program.
+const LEN_LOCALS: u32 = 3;
+/// The maximum nesting depth of control flow statements.
+const MAX_DEPTH: u32 = 3;
+/// The maximum number of statements within a single sequence.
+const MAX_STMTS: u32 = 4;
+/// The maximum number of iterations of a single loop.
+const MAX_ITERATIONS: u32 = 3;
+/// The number of randomly generated programs to test.
+const LEN_PROGRAMS: u64 = 200;
+
+/// A simple deterministic xorshift pseudo random number generator.
+///
+/// Used instead of an external crate so that failing seeds are reproducible.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new generator from the given seed.
+    fn new(seed: u64) -> Self {
+        // Avoid the degenerate all-zero state of xorshift generators.
+        Self {
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    /// Returns the next pseudo random number.
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// Returns a pseudo random number in the range `0..bound`.
+    fn below(&mut self, bound: u32) -> u32 {
+        (self.next_u64() % bound as u64) as u32
+    }
+}
+
+/// A pure expression of a generated program.
+#[derive(Debug)]
+enum Expr {
+    Const(i32),
+    Var(u32),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Xor(Box<Expr>, Box<Expr>),
+}
+
+/// A statement of a generated program.
+#[derive(Debug)]
+enum Stmt {
+    /// Assigns the result of the expression to the variable.
+    Assign { var: u32, expr: Expr },
+    /// Executes `then_stmts` if `lhs < rhs` and `else_stmts` otherwise.
+    If {
+        lhs: Expr,
+        rhs: Expr,
+        then_stmts: Vec<Stmt>,
+        else_stmts: Vec<Stmt>,
+    },
+    /// Executes `body` for `iterations` times.
+    ///
+    /// The loop counter is stored in its own hidden variable `counter`.
+    Loop {
+        counter: u32,
+        iterations: u32,
+        body: Vec<Stmt>,
+    },
+}
+
+/// A randomly generated program.
+///
+/// The program returns the values of all inputs and locals.
+#[derive(Debug)]
+struct Program {
+    /// The number of hidden loop counter variables.
+    len_counters: u32,
+    /// The statements of the program body.
+    body: Vec<Stmt>,
+}
+
+impl Program {
+    /// The number of variables that are returned by the program.
+    const LEN_RESULTS: u32 = LEN_INPUTS + LEN_LOCALS;
+
+    /// Generates a new random program.
+    fn generate(rng: &mut Rng) -> Self {
+        let mut len_counters = 0;
+        let body = generate_stmts(rng, 0, &mut len_counters);
+        Self { len_counters, body }
+    }
+
+    /// Evaluates the program with the given inputs using the reference evaluator.
+    fn evaluate(&self, inputs: &[i32]) -> Vec<i32> {
+        let len_vars = Self::LEN_RESULTS + self.len_counters;
+        let mut vars = vec![0; len_vars as usize];
+        vars[..inputs.len()].copy_from_slice(inputs);
+        eval_stmts(&self.body, &mut vars);
+        vars.truncate(Self::LEN_RESULTS as usize);
+        vars
+    }
+
+    /// Translates the program into Runwell IR using the function builder.
+    fn build(&self, b: &mut FunctionBuilder) -> Result<(), module::Error> {
+        let len_vars = LEN_LOCALS + self.len_counters;
+        b.declare_variables(len_vars, IntType::I32.into())?;
+        b.body()?;
+        let zero = b.ins()?.constant(IntConst::I32(0))?;
+        for n in LEN_INPUTS..(LEN_INPUTS + len_vars) {
+            b.write_var(var(n), zero)?;
+        }
+        build_stmts(b, &self.body)?;
+        let results = (0..Self::LEN_RESULTS)
+            .map(|n| b.read_var(var(n)))
+            .collect::<Result<Vec<_>, _>>()?;
+        b.ins()?.return_values(results)?;
+        Ok(())
+    }
+}
+
+/// Returns the variable with the given index.
+fn var(n: u32) -> Variable {
+    Variable::from_raw(RawIdx::from_u32(n))
+}
+
+/// Generates a random variable that can be read or written by the program.
+fn generate_var(rng: &mut Rng) -> u32 {
+    rng.below(LEN_INPUTS + LEN_LOCALS)
+}
+
+fn generate_expr(rng: &mut Rng, depth: u32) -> Expr {
+    let choice = if depth >= 2 { rng.below(2) } else { rng.below(5) };
+    let operand = |rng: &mut Rng| Box::new(generate_expr(rng, depth + 1));
+    match choice {
+        0 => Expr::Const(rng.below(21) as i32 - 10),
+        1 => Expr::Var(generate_var(rng)),
+        2 => Expr::Add(operand(rng), operand(rng)),
+        3 => Expr::Sub(operand(rng), operand(rng)),
+        _ => Expr::Xor(operand(rng), operand(rng)),
+    }
+}
+
+fn generate_stmts(
+    rng: &mut Rng,
+    depth: u32,
+    len_counters: &mut u32,
+) -> Vec<Stmt> {
+    let len_stmts = rng.below(MAX_STMTS + 1);
+    (0..len_stmts)
+        .map(|_| generate_stmt(rng, depth, len_counters))
+        .collect()
+}
+
+fn generate_stmt(
+    rng: &mut Rng,
+    depth: u32,
+    len_counters: &mut u32,
+) -> Stmt {
+    let choice = if depth >= MAX_DEPTH { 0 } else { rng.below(4) };
+    match choice {
+        0 | 1 => {
+            Stmt::Assign {
+                var: LEN_INPUTS + rng.below(LEN_LOCALS),
+                expr: generate_expr(rng, 0),
+            }
+        }
+        2 => {
+            Stmt::If {
+                lhs: generate_expr(rng, 0),
+                rhs: generate_expr(rng, 0),
+                then_stmts: generate_stmts(rng, depth + 1, len_counters),
+                else_stmts: generate_stmts(rng, depth + 1, len_counters),
+            }
+        }
+        _ => {
+            let counter = Program::LEN_RESULTS + *len_counters;
+            *len_counters += 1;
+            Stmt::Loop {
+                counter,
+                iterations: rng.below(MAX_ITERATIONS + 1),
+                body: generate_stmts(rng, depth + 1, len_counters),
+            }
+        }
+    }
+}
+
+fn eval_expr(expr: &Expr, vars: &[i32]) -> i32 {
+    match expr {
+        Expr::Const(value) => *value,
+        Expr::Var(n) => vars[*n as usize],
+        Expr::Add(lhs, rhs) => {
+            eval_expr(lhs, vars).wrapping_add(eval_expr(rhs, vars))
+        }
+        Expr::Sub(lhs, rhs) => {
+            eval_expr(lhs, vars).wrapping_sub(eval_expr(rhs, vars))
+        }
+        Expr::Xor(lhs, rhs) => eval_expr(lhs, vars) ^ eval_expr(rhs, vars),
+    }
+}
+
+fn eval_stmts(stmts: &[Stmt], vars: &mut [i32]) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Assign { var, expr } => {
+                let value = eval_expr(expr, vars);
+                vars[*var as usize] = value;
+            }
+            Stmt::If {
+                lhs,
+                rhs,
+                then_stmts,
+                else_stmts,
+            } => {
+                if eval_expr(lhs, vars) < eval_expr(rhs, vars) {
+                    eval_stmts(then_stmts, vars)
+                } else {
+                    eval_stmts(else_stmts, vars)
+                }
+            }
+            Stmt::Loop {
+                counter,
+                iterations,
+                body,
+            } => {
+                vars[*counter as usize] = 0;
+                while vars[*counter as usize] < *iterations as i32 {
+                    eval_stmts(body, vars);
+                    vars[*counter as usize] += 1;
+                }
+            }
+        }
+    }
+}
+
+fn build_expr(
+    b: &mut FunctionBuilder,
+    expr: &Expr,
+) -> Result<Value, module::Error> {
+    let ty = IntType::I32;
+    let value = match expr {
+        Expr::Const(value) => b.ins()?.constant(IntConst::I32(*value))?,
+        Expr::Var(n) => b.read_var(var(*n))?,
+        Expr::Add(lhs, rhs) => {
+            let lhs = build_expr(b, lhs)?;
+            let rhs = build_expr(b, rhs)?;
+            b.ins()?.iadd(ty, lhs, rhs)?
+        }
+        Expr::Sub(lhs, rhs) => {
+            let lhs = build_expr(b, lhs)?;
+            let rhs = build_expr(b, rhs)?;
+            b.ins()?.isub(ty, lhs, rhs)?
+        }
+        Expr::Xor(lhs, rhs) => {
+            let lhs = build_expr(b, lhs)?;
+            let rhs = build_expr(b, rhs)?;
+            b.ins()?.ixor(ty, lhs, rhs)?
+        }
+    };
+    Ok(value)
+}
+
+fn build_stmts(
+    b: &mut FunctionBuilder,
+    stmts: &[Stmt],
+) -> Result<(), module::Error> {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Assign { var: n, expr } => {
+                let value = build_expr(b, expr)?;
+                b.write_var(var(*n), value)?;
+            }
+            Stmt::If {
+                lhs,
+                rhs,
+                then_stmts,
+                else_stmts,
+            } => {
+                let then_block = b.create_block()?;
+                let else_block = b.create_block()?;
+                let exit_block = b.create_block()?;
+                let lhs = build_expr(b, lhs)?;
+                let rhs = build_expr(b, rhs)?;
+                let condition =
+                    b.ins()?.icmp(IntType::I32, CompareIntOp::Slt, lhs, rhs)?;
+                b.ins()?.if_then_else(condition, then_block, else_block)?;
+
+                b.switch_to_block(then_block)?;
+                b.seal_block(then_block)?;
+                build_stmts(b, then_stmts)?;
+                b.ins()?.br(exit_block)?;
+
+                b.switch_to_block(else_block)?;
+                b.seal_block(else_block)?;
+                build_stmts(b, else_stmts)?;
+                b.ins()?.br(exit_block)?;
+
+                b.switch_to_block(exit_block)?;
+                b.seal_block(exit_block)?;
+            }
+            Stmt::Loop {
+                counter,
+                iterations,
+                body,
+            } => {
+                let loop_head = b.create_block()?;
+                let loop_body = b.create_block()?;
+                let loop_exit = b.create_block()?;
+                let counter = var(*counter);
+                let zero = b.ins()?.constant(IntConst::I32(0))?;
+                b.write_var(counter, zero)?;
+                b.ins()?.br(loop_head)?;
+
+                b.switch_to_block(loop_head)?;
+                let count = b.read_var(counter)?;
+                let limit =
+                    b.ins()?.constant(IntConst::I32(*iterations as i32))?;
+                let condition = b.ins()?.icmp(
+                    IntType::I32,
+                    CompareIntOp::Slt,
+                    count,
+                    limit,
+                )?;
+                b.ins()?.if_then_else(condition, loop_body, loop_exit)?;
+
+                b.switch_to_block(loop_body)?;
+                b.seal_block(loop_body)?;
+                build_stmts(b, body)?;
+                let count = b.read_var(counter)?;
+                let one = b.ins()?.constant(IntConst::I32(1))?;
+                let count = b.ins()?.iadd(IntType::I32, count, one)?;
+                b.write_var(counter, count)?;
+                b.ins()?.br(loop_head)?;
+                b.seal_block(loop_head)?;
+
+                b.switch_to_block(loop_exit)?;
+                b.seal_block(loop_exit)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn random_programs_match_reference() {
+    let inputs = [(0, 0), (1, -1), (-7, 3), (42, 42), (i32::MAX, i32::MIN)];
+    for seed in 0..LEN_PROGRAMS {
+        let mut rng = Rng::new(seed);
+        let program = Program::generate(&mut rng);
+        let i32_type: Type = IntType::I32.into();
+        let (func, module) = module_with_func(
+            &[i32_type; LEN_INPUTS as usize],
+            &[i32_type; Program::LEN_RESULTS as usize],
+            |b| program.build(b),
+        );
+        let mut ctx = EvaluationContext::new(&module);
+        for &(x, y) in &inputs {
+            let expected = program.evaluate(&[x, y]);
+            let results = evaluate_func_in_ctx(
+                &mut ctx,
+                func,
+                &[IntConst::I32(x).into(), IntConst::I32(y).into()],
+            )
+            .into_iter()
+            .map(|bits| bits as u32 as i32)
+            .collect::<Vec<_>>();
+            assert_eq!(
+                results, expected,
+                "mismatch for seed {} with inputs ({}, {}) of program {:#?}",
+                seed, x, y, program,
+            );
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracing of the instructions executed during interpretation.
+
+use core::fmt;
+use ir::{instr::Instruction, primitive::Value, VisitValues};
+use module::primitive::Instr;
+
+/// The callback of a trace hook.
+type TraceCallback = dyn FnMut(Instr, &Instruction, &[u64]) + Send;
+
+/// A callback invoked before each instruction executed by the Runwell IR
+/// interpreter.
+///
+/// Set the hook via [`EvaluationContext::set_trace_hook`].
+///
+/// [`EvaluationContext::set_trace_hook`]:
+/// crate::EvaluationContext::set_trace_hook
+pub(crate) struct TraceHook {
+    /// The callback of the embedder.
+    hook: Box<TraceCallback>,
+    /// Buffer for the bits of the operands of the traced instruction.
+    operands: Vec<u64>,
+}
+
+impl fmt::Debug for TraceHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceHook").finish()
+    }
+}
+
+impl TraceHook {
+    /// Creates a trace hook from the callback.
+    pub fn new<F>(hook: F) -> Self
+    where
+        F: FnMut(Instr, &Instruction, &[u64]) + Send + 'static,
+    {
+        Self {
+            hook: Box::new(hook),
+            operands: Vec::new(),
+        }
+    }
+
+    /// Invokes the callback for the instruction.
+    ///
+    /// The bits of the operands of the instruction are read via `read`.
+    pub fn trace<R>(&mut self, instr: Instr, instruction: &Instruction, read: R)
+    where
+        R: Fn(Value) -> u64,
+    {
+        let operands = &mut self.operands;
+        operands.clear();
+        instruction.visit_values(|value| {
+            operands.push(read(value));
+            true
+        });
+        (self.hook)(instr, instruction, operands)
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Human readable rendering of register values.
+
+use core::fmt;
+use ir::primitive::{Const, FloatType, IntType, Type};
+
+/// Renders the bits of a register value according to its static type.
+///
+/// # Formatting
+///
+/// - Integers are rendered as signed decimals.
+///   The alternate flag `{:#}` renders them as unsigned decimals instead.
+/// - Floating point numbers are rendered as exact hexadecimal floats,
+///   e.g. `0x1.8p+1` for `3.0`, including `inf` and `nan` payloads.
+/// - Booleans are rendered as `true` or `false`.
+/// - Pointers are rendered as hexadecimal addresses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DisplayValue {
+    ty: Type,
+    bits: u64,
+}
+
+impl DisplayValue {
+    /// Creates a new displayable value from its type and register bits.
+    pub fn new(ty: Type, bits: u64) -> Self {
+        Self { ty, bits }
+    }
+
+    /// Returns the static type of the value.
+    pub fn ty(&self) -> Type {
+        self.ty
+    }
+}
+
+impl From<Const> for DisplayValue {
+    fn from(value: Const) -> Self {
+        Self::new(value.ty(), value.into_bits64())
+    }
+}
+
+impl fmt::Display for DisplayValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = self.bits;
+        let unsigned = f.alternate();
+        match self.ty {
+            Type::Bool => write!(f, "{}", bits != 0),
+            Type::Ptr => write!(f, "0x{:x}", bits as u32),
+            Type::Int(IntType::I8) if unsigned => write!(f, "{}", bits as u8),
+            Type::Int(IntType::I16) if unsigned => write!(f, "{}", bits as u16),
+            Type::Int(IntType::I32) if unsigned => write!(f, "{}", bits as u32),
+            Type::Int(IntType::I64) if unsigned => write!(f, "{}", bits),
+            Type::Int(IntType::I8) => write!(f, "{}", bits as i8),
+            Type::Int(IntType::I16) => write!(f, "{}", bits as i16),
+            Type::Int(IntType::I32) => write!(f, "{}", bits as i32),
+            Type::Int(IntType::I64) => write!(f, "{}", bits as i64),
+            Type::Float(FloatType::F32) => {
+                fmt_hex_float(f, bits & u64::from(u32::MAX), 8, 23)
+            }
+            Type::Float(FloatType::F64) => fmt_hex_float(f, bits, 11, 52),
+        }
+    }
+}
+
+/// Writes the IEEE 754 floating point number bits as hexadecimal float.
+///
+/// The `exp_bits` and `mant_bits` denote the number of exponent and mantissa
+/// bits of the floating point number format respectively.
+fn fmt_hex_float(
+    f: &mut fmt::Formatter<'_>,
+    bits: u64,
+    exp_bits: u32,
+    mant_bits: u32,
+) -> fmt::Result {
+    let exp_mask = (1_u64 << exp_bits) - 1;
+    let mant_mask = (1_u64 << mant_bits) - 1;
+    let sign = (bits >> (exp_bits + mant_bits)) & 1;
+    let exp = (bits >> mant_bits) & exp_mask;
+    let mant = bits & mant_mask;
+    if sign == 1 {
+        write!(f, "-")?;
+    }
+    if exp == exp_mask {
+        if mant == 0 {
+            return write!(f, "inf")
+        }
+        if mant == 1 << (mant_bits - 1) {
+            return write!(f, "nan")
+        }
+        return write!(f, "nan:0x{:x}", mant)
+    }
+    if exp == 0 && mant == 0 {
+        return write!(f, "0x0p+0")
+    }
+    let bias = (1_i64 << (exp_bits - 1)) - 1;
+    let (lead, exponent) = if exp == 0 {
+        // Subnormal numbers have no implicit leading one.
+        (0, 1 - bias)
+    } else {
+        (1, exp as i64 - bias)
+    };
+    // Align the mantissa to whole hexadecimal digits and trim trailing zeros.
+    let mut digits = mant_bits.div_ceil(4);
+    let mut mant = mant << (digits * 4 - mant_bits);
+    while digits > 0 && mant & 0xF == 0 {
+        mant >>= 4;
+        digits -= 1;
+    }
+    write!(f, "0x{}", lead)?;
+    if digits > 0 {
+        write!(f, ".{:0width$x}", mant, width = digits as usize)?;
+    }
+    write!(f, "p{:+}", exponent)
+}
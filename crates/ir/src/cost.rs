@@ -0,0 +1,329 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cost models that assign execution costs to Runwell IR instructions.
+//!
+//! Fuel metering of interpreters and size heuristics of transformations
+//! query the same cost model so that embedders can plug in their own cost
+//! schedule in a single place.
+//!
+//! Timing tables provide per-target latency and throughput estimates so
+//! that heuristics can be tuned per backend. Gas tables let embedders price
+//! instructions and linear memory growth without patching the interpreter.
+
+use crate::instr::{Instruction, InstructionDescriptor};
+use core::fmt;
+use std::collections::BTreeMap;
+
+/// Assigns an execution cost to every Runwell IR instruction.
+pub trait CostModel: fmt::Debug + Send + Sync {
+    /// Returns the cost of executing the instruction once.
+    fn instruction_cost(&self, instruction: &Instruction) -> u64;
+
+    /// Returns the cost of executing an operator of a function body that
+    /// has not been translated into the Runwell IR.
+    ///
+    /// Defaults to the cost of a single unit.
+    fn untranslated_operator_cost(&self) -> u64 {
+        1
+    }
+
+    /// Returns the additional cost of growing a linear memory by `pages`.
+    ///
+    /// This is charged on top of the cost of the `memory.grow` instruction
+    /// for the requested pages, regardless of whether the growth succeeds.
+    ///
+    /// Defaults to no additional cost.
+    fn memory_grow_cost(&self, _pages: u32) -> u64 {
+        0
+    }
+}
+
+/// A cost model that assigns the same cost to every instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct UniformCostModel {
+    /// The cost of every instruction.
+    cost: u64,
+}
+
+impl UniformCostModel {
+    /// Creates a cost model that assigns the given cost to every instruction.
+    pub fn new(cost: u64) -> Self {
+        Self { cost }
+    }
+}
+
+impl Default for UniformCostModel {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl CostModel for UniformCostModel {
+    fn instruction_cost(&self, _instruction: &Instruction) -> u64 {
+        self.cost
+    }
+
+    fn untranslated_operator_cost(&self) -> u64 {
+        self.cost
+    }
+}
+
+/// The estimated timing of an instruction on a target.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InstructionTiming {
+    /// The cycles until the results of the instruction are available.
+    latency: u32,
+    /// The cycles between the issue of two independent instructions.
+    ///
+    /// This is the reciprocal throughput of the instruction.
+    throughput: u32,
+}
+
+impl InstructionTiming {
+    /// Creates a new instruction timing.
+    ///
+    /// The `throughput` is the reciprocal throughput in cycles.
+    pub const fn new(latency: u32, throughput: u32) -> Self {
+        Self {
+            latency,
+            throughput,
+        }
+    }
+
+    /// Returns the cycles until the results of the instruction are available.
+    pub fn latency(&self) -> u32 {
+        self.latency
+    }
+
+    /// Returns the cycles between the issue of two independent instructions.
+    pub fn throughput(&self) -> u32 {
+        self.throughput
+    }
+}
+
+impl Default for InstructionTiming {
+    fn default() -> Self {
+        Self::new(1, 1)
+    }
+}
+
+/// The instruction timings of a target.
+///
+/// Instructions without an explicit timing have the default timing of the
+/// table. As a cost model the table assigns the latency of an instruction
+/// as its cost.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TimingTable {
+    /// The timings of the instructions keyed by their mnemonic.
+    timings: BTreeMap<&'static str, InstructionTiming>,
+    /// The timing of instructions without an explicit timing.
+    default: InstructionTiming,
+}
+
+impl TimingTable {
+    /// Creates a timing table that assigns the default timing to every
+    /// instruction.
+    pub fn new(default: InstructionTiming) -> Self {
+        Self {
+            timings: BTreeMap::new(),
+            default,
+        }
+    }
+
+    /// Creates a timing table with rough estimates for a generic 64-bit
+    /// out-of-order processor.
+    pub fn generic() -> Self {
+        let mut table = Self::default();
+        for &(name, latency, throughput) in &[
+            ("call", 5, 2),
+            ("call_indirect", 7, 2),
+            ("memory.grow", 100, 100),
+            ("load", 4, 1),
+            ("tail_call", 5, 2),
+            ("tail_call_indirect", 7, 2),
+            ("imul", 3, 1),
+            ("sdiv", 26, 6),
+            ("udiv", 26, 6),
+            ("srem", 26, 6),
+            ("urem", 26, 6),
+            ("fadd", 4, 1),
+            ("fsub", 4, 1),
+            ("fmul", 4, 1),
+            ("fdiv", 13, 4),
+            ("fsqrt", 15, 4),
+            ("fconvert_s", 6, 1),
+            ("fconvert_u", 6, 1),
+            ("sconvert", 5, 1),
+            ("uconvert", 5, 1),
+        ] {
+            let descriptor = InstructionDescriptor::from_name(name)
+                .expect("encountered unknown instruction mnemonic");
+            table.set_timing(
+                descriptor,
+                InstructionTiming::new(latency, throughput),
+            );
+        }
+        table
+    }
+
+    /// Sets the timing of the described instruction.
+    pub fn set_timing(
+        &mut self,
+        descriptor: &InstructionDescriptor,
+        timing: InstructionTiming,
+    ) {
+        self.timings.insert(descriptor.name(), timing);
+    }
+
+    /// Returns the timing of the described instruction.
+    pub fn timing(
+        &self,
+        descriptor: &InstructionDescriptor,
+    ) -> InstructionTiming {
+        self.timings
+            .get(descriptor.name())
+            .copied()
+            .unwrap_or(self.default)
+    }
+}
+
+impl CostModel for TimingTable {
+    fn instruction_cost(&self, instruction: &Instruction) -> u64 {
+        u64::from(self.timing(instruction.descriptor()).latency())
+    }
+
+    fn untranslated_operator_cost(&self) -> u64 {
+        u64::from(self.default.latency())
+    }
+}
+
+/// A gas schedule that assigns costs to instruction kinds.
+///
+/// Instructions without an explicit cost are charged the default cost of
+/// the table which is also charged for untranslated operators. Growing a
+/// linear memory is additionally charged per requested page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasTable {
+    /// The costs of the instructions keyed by their mnemonic.
+    costs: BTreeMap<&'static str, u64>,
+    /// The cost of instructions without an explicit cost.
+    default: u64,
+    /// The cost per page of linear memory growth.
+    memory_page_cost: u64,
+}
+
+impl Default for GasTable {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl GasTable {
+    /// Creates a gas table that assigns the default cost to every
+    /// instruction and does not charge for linear memory growth.
+    pub fn new(default: u64) -> Self {
+        Self {
+            costs: BTreeMap::new(),
+            default,
+            memory_page_cost: 0,
+        }
+    }
+
+    /// Sets the cost of the described instruction.
+    pub fn set_cost(&mut self, descriptor: &InstructionDescriptor, cost: u64) {
+        self.costs.insert(descriptor.name(), cost);
+    }
+
+    /// Returns the cost of the described instruction.
+    pub fn cost(&self, descriptor: &InstructionDescriptor) -> u64 {
+        self.costs
+            .get(descriptor.name())
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Sets the cost per page of linear memory growth.
+    pub fn set_memory_page_cost(&mut self, cost: u64) {
+        self.memory_page_cost = cost;
+    }
+
+    /// Returns the cost per page of linear memory growth.
+    pub fn memory_page_cost(&self) -> u64 {
+        self.memory_page_cost
+    }
+}
+
+impl CostModel for GasTable {
+    fn instruction_cost(&self, instruction: &Instruction) -> u64 {
+        self.cost(instruction.descriptor())
+    }
+
+    fn untranslated_operator_cost(&self) -> u64 {
+        self.default
+    }
+
+    fn memory_grow_cost(&self, pages: u32) -> u64 {
+        self.memory_page_cost.saturating_mul(u64::from(pages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_timings_are_valid() {
+        let table = TimingTable::generic();
+        for descriptor in InstructionDescriptor::all() {
+            let timing = table.timing(descriptor);
+            assert!(timing.latency() >= 1);
+            assert!(timing.throughput() >= 1);
+        }
+        let sdiv = InstructionDescriptor::from_name("sdiv").unwrap();
+        let iadd = InstructionDescriptor::from_name("iadd").unwrap();
+        assert!(table.timing(sdiv).latency() > table.timing(iadd).latency());
+    }
+
+    #[test]
+    fn set_timing_works() {
+        let default = InstructionTiming::new(2, 1);
+        let mut table = TimingTable::new(default);
+        let fdiv = InstructionDescriptor::from_name("fdiv").unwrap();
+        let fadd = InstructionDescriptor::from_name("fadd").unwrap();
+        assert_eq!(table.timing(fdiv), default);
+        table.set_timing(fdiv, InstructionTiming::new(20, 8));
+        assert_eq!(table.timing(fdiv), InstructionTiming::new(20, 8));
+        assert_eq!(table.timing(fadd), default);
+        assert_eq!(table.untranslated_operator_cost(), 2);
+    }
+
+    #[test]
+    fn gas_table_works() {
+        let mut table = GasTable::new(3);
+        let sdiv = InstructionDescriptor::from_name("sdiv").unwrap();
+        let iadd = InstructionDescriptor::from_name("iadd").unwrap();
+        assert_eq!(table.cost(sdiv), 3);
+        table.set_cost(sdiv, 40);
+        assert_eq!(table.cost(sdiv), 40);
+        assert_eq!(table.cost(iadd), 3);
+        assert_eq!(table.untranslated_operator_cost(), 3);
+        assert_eq!(table.memory_grow_cost(5), 0);
+        table.set_memory_page_cost(1_000);
+        assert_eq!(table.memory_page_cost(), 1_000);
+        assert_eq!(table.memory_grow_cost(5), 5_000);
+        table.set_memory_page_cost(u64::MAX);
+        assert_eq!(table.memory_grow_cost(2), u64::MAX);
+    }
+}
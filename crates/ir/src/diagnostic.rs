@@ -0,0 +1,145 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured diagnostics of user-facing errors.
+//!
+//! Errors of the Runwell crates can be turned into diagnostics that consist
+//! of a stable machine-readable code, the named parameters of the error and
+//! the default English message. Embedders render diagnostics in their own
+//! language or map their codes to documentation links while the codes stay
+//! stable across releases.
+
+use core::fmt;
+
+/// The structured data of a user-facing error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The stable code identifying the kind of the error.
+    code: &'static str,
+    /// The named parameters of the error in their order of appearance.
+    parameters: Vec<(&'static str, String)>,
+    /// The default English message of the error.
+    message: String,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic without parameters.
+    pub fn new<M>(code: &'static str, message: M) -> Self
+    where
+        M: fmt::Display,
+    {
+        Self {
+            code,
+            parameters: Vec::new(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Adds a named parameter to the diagnostic and returns `self`.
+    pub fn with_parameter<T>(mut self, name: &'static str, value: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        self.parameters.push((name, value.to_string()));
+        self
+    }
+
+    /// Returns the stable code identifying the kind of the error.
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    /// Returns the named parameters of the error.
+    pub fn parameters(&self) -> &[(&'static str, String)] {
+        &self.parameters
+    }
+
+    /// Returns the value of the named parameter if any.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| *parameter == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the default English message of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Renders the diagnostic with the given renderer.
+    ///
+    /// Falls back to the default message if the renderer does not support
+    /// the code of the diagnostic.
+    pub fn render(&self, renderer: &dyn DiagnosticRenderer) -> String {
+        renderer
+            .render(self)
+            .unwrap_or_else(|| self.message.clone())
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Errors that can be described by a structured diagnostic.
+pub trait ToDiagnostic {
+    /// Returns the diagnostic describing the error.
+    fn to_diagnostic(&self) -> Diagnostic;
+}
+
+/// Renders diagnostics into user-facing messages.
+pub trait DiagnosticRenderer {
+    /// Returns the rendered message of the diagnostic.
+    ///
+    /// Returns `None` if the renderer does not support the diagnostic.
+    fn render(&self, diagnostic: &Diagnostic) -> Option<String>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders out of fuel diagnostics in German.
+    struct GermanRenderer;
+
+    impl DiagnosticRenderer for GermanRenderer {
+        fn render(&self, diagnostic: &Diagnostic) -> Option<String> {
+            match diagnostic.code() {
+                "example.out_of_fuel" => Some(format!(
+                    "Treibstoff nach {} Schritten aufgebraucht",
+                    diagnostic.parameter("steps")?
+                )),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn render_works() {
+        let out_of_fuel = Diagnostic::new("example.out_of_fuel", "ran out")
+            .with_parameter("steps", 42);
+        assert_eq!(out_of_fuel.parameter("steps"), Some("42"));
+        assert_eq!(out_of_fuel.parameter("fuel"), None);
+        assert_eq!(
+            out_of_fuel.render(&GermanRenderer),
+            "Treibstoff nach 42 Schritten aufgebraucht"
+        );
+        let unknown = Diagnostic::new("example.unknown", "unknown error");
+        assert_eq!(unknown.render(&GermanRenderer), "unknown error");
+        assert_eq!(unknown.to_string(), "unknown error");
+    }
+}
@@ -54,7 +54,7 @@ impl ReinterpretInstr {
 
     /// Returns the destination type of the reinterpret instruction.
     pub fn dst_type(&self) -> Type {
-        self.src_type
+        self.dst_type
     }
 
     /// Returns the source value of the reinterpret instruction.
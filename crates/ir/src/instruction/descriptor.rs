@@ -0,0 +1,706 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine readable descriptions of all Runwell IR instructions.
+//!
+//! Tooling such as text parsers, fuzzers or documentation generators can
+//! query the descriptor of any instruction via [`Instruction::descriptor`]
+//! or iterate over all known descriptors via [`InstructionDescriptor::all`].
+//!
+//! # Constant-Time Instructions
+//!
+//! Descriptors also document whether the execution time of an instruction
+//! is independent of its operand values. Instructions that are marked as
+//! `VariableTime` below are not constant-time:
+//!
+//! - Branches and indirect calls whose target depends on an operand.
+//! - Memory accesses whose timing depends on the accessed address.
+//! - Integer divisions and remainders as well as floating point divisions
+//!   and square roots whose latency depends on the operands on most targets.
+//!
+//! Code handling secrets must not pass secret values to those instructions.
+//!
+//! # Type Constraints
+//!
+//! Descriptors also state the types of the SSA values that instructions take
+//! as operands and produce as results. The types of most instructions depend
+//! on the type of the concrete instruction, e.g. `iadd` on `i32` values
+//! produces an `i32` value, which is queried via
+//! [`Instruction::constrained_type`].
+
+use super::{
+    BinaryFloatOp,
+    BinaryIntOp,
+    FloatInstr,
+    Instruction,
+    IntInstr,
+    ShiftIntOp,
+    TerminalInstr,
+    UnaryFloatOp,
+    UnaryIntOp,
+};
+use crate::primitive::{IntType, Type};
+use core::fmt;
+
+/// The kind of an operand of an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum OperandKind {
+    /// A single SSA value.
+    Value,
+    /// A variable amount of SSA values.
+    Values,
+    /// A single basic block and the arguments passed to its parameters.
+    Block,
+    /// A variable amount of basic blocks and the arguments passed to their parameters.
+    Blocks,
+    /// A function reference.
+    Func,
+    /// A function type reference.
+    FuncType,
+    /// A linear memory reference.
+    Memory,
+    /// A table reference.
+    Table,
+    /// A read-only data object reference.
+    Data,
+    /// A global variable reference.
+    Global,
+    /// An immediate value such as a constant or a memory offset.
+    Imm,
+}
+
+/// The amount of SSA values produced by an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ResultKind {
+    /// The instruction does not produce any SSA values.
+    None,
+    /// The instruction produces exactly one SSA value.
+    Single,
+    /// The instruction produces as many SSA values as the signature
+    /// of the referenced function defines.
+    Multiple,
+}
+
+/// The constraint on the type of an operand or result of an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TypeConstraint {
+    /// The operand or result is not an SSA value and therefore untyped.
+    Untyped,
+    /// The type of the instruction, e.g. the integer type of `iadd`.
+    InstrType,
+    /// The source type of a conversion instruction.
+    SourceType,
+    /// The destination type of a conversion instruction.
+    DestinationType,
+    /// Exactly the given type, e.g. `i32` for the index of an indirect call.
+    Exact(Type),
+    /// Either `bool` or `i32` as for the condition of `select`.
+    Condition,
+    /// The types declared by the referenced function, function type or
+    /// basic block.
+    Referenced,
+}
+
+/// The side effects of an instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InstructionEffects {
+    bits: u8,
+}
+
+impl InstructionEffects {
+    /// The instruction has no side effects.
+    pub const PURE: Self = Self { bits: 0 };
+    /// The instruction reads from a linear memory.
+    pub const READS_MEMORY: Self = Self { bits: 1 << 0 };
+    /// The instruction writes to a linear memory.
+    pub const WRITES_MEMORY: Self = Self { bits: 1 << 1 };
+    /// The instruction calls another function.
+    pub const CALLS: Self = Self { bits: 1 << 2 };
+    /// The instruction alters the control flow of the function.
+    pub const CONTROL_FLOW: Self = Self { bits: 1 << 3 };
+    /// The instruction might trap during execution.
+    pub const MAY_TRAP: Self = Self { bits: 1 << 4 };
+    /// The instruction reads from a global variable.
+    pub const READS_GLOBAL: Self = Self { bits: 1 << 5 };
+    /// The instruction writes to a global variable.
+    pub const WRITES_GLOBAL: Self = Self { bits: 1 << 6 };
+
+    /// Returns the union of both effects.
+    pub const fn union(self, other: Self) -> Self {
+        Self {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Returns `true` if all effects of `other` are contained in `self`.
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Returns `true` if the instruction has no side effects.
+    pub const fn is_pure(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns `true` if the instruction reads from a linear memory.
+    pub const fn reads_memory(self) -> bool {
+        self.contains(Self::READS_MEMORY)
+    }
+
+    /// Returns `true` if the instruction writes to a linear memory.
+    pub const fn writes_memory(self) -> bool {
+        self.contains(Self::WRITES_MEMORY)
+    }
+
+    /// Returns `true` if the instruction reads from a global variable.
+    pub const fn reads_global(self) -> bool {
+        self.contains(Self::READS_GLOBAL)
+    }
+
+    /// Returns `true` if the instruction writes to a global variable.
+    pub const fn writes_global(self) -> bool {
+        self.contains(Self::WRITES_GLOBAL)
+    }
+
+    /// Returns `true` if the instruction calls another function.
+    pub const fn calls(self) -> bool {
+        self.contains(Self::CALLS)
+    }
+
+    /// Returns `true` if the instruction alters the control flow.
+    pub const fn is_control_flow(self) -> bool {
+        self.contains(Self::CONTROL_FLOW)
+    }
+
+    /// Returns `true` if the instruction might trap.
+    pub const fn may_trap(self) -> bool {
+        self.contains(Self::MAY_TRAP)
+    }
+}
+
+/// Describes the static properties of a Runwell IR instruction.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct InstructionDescriptor {
+    /// The unique mnemonic of the instruction.
+    name: &'static str,
+    /// The kinds of the instruction operands in order.
+    operands: &'static [OperandKind],
+    /// The type constraints of the instruction operands in order.
+    operand_types: &'static [TypeConstraint],
+    /// The amount of SSA values produced by the instruction.
+    results: ResultKind,
+    /// The type constraint of the SSA values produced by the instruction.
+    result_type: TypeConstraint,
+    /// The side effects of the instruction.
+    effects: InstructionEffects,
+    /// Whether the execution time is independent of the operand values.
+    constant_time: bool,
+}
+
+impl InstructionDescriptor {
+    /// Returns the unique mnemonic of the instruction.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the kinds of the instruction operands in order.
+    pub fn operands(&self) -> &'static [OperandKind] {
+        self.operands
+    }
+
+    /// Returns the type constraints of the instruction operands in order.
+    ///
+    /// Operands that are not SSA values are [`TypeConstraint::Untyped`].
+    pub fn operand_types(&self) -> &'static [TypeConstraint] {
+        self.operand_types
+    }
+
+    /// Returns the amount of SSA values produced by the instruction.
+    pub fn results(&self) -> ResultKind {
+        self.results
+    }
+
+    /// Returns the type constraint of the values produced by the instruction.
+    pub fn result_type(&self) -> TypeConstraint {
+        self.result_type
+    }
+
+    /// Returns the side effects of the instruction.
+    pub fn effects(&self) -> InstructionEffects {
+        self.effects
+    }
+
+    /// Returns `true` if the execution time of the instruction is
+    /// independent of its operand values.
+    ///
+    /// Backends and interpreters might still execute `select`, `fmin` and
+    /// `fmax` with value-dependent branches unless they are configured to
+    /// run in a constant-time mode.
+    pub fn is_constant_time(&self) -> bool {
+        self.constant_time
+    }
+
+    /// Returns the descriptors of all Runwell IR instructions.
+    pub fn all() -> &'static [&'static InstructionDescriptor] {
+        ALL_DESCRIPTORS
+    }
+
+    /// Returns the descriptor of the instruction with the given mnemonic if any.
+    pub fn from_name(name: &str) -> Option<&'static InstructionDescriptor> {
+        ALL_DESCRIPTORS
+            .iter()
+            .copied()
+            .find(|descriptor| descriptor.name == name)
+    }
+}
+
+impl fmt::Display for InstructionDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.name)?;
+        if let Some((first, rest)) = self.operands.split_first() {
+            write!(f, "{:?}", first)?;
+            for operand in rest {
+                write!(f, ", {:?}", operand)?;
+            }
+        }
+        write!(f, ") -> {:?}", self.results)?;
+        Ok(())
+    }
+}
+
+/// Returns `false` for instructions that are marked as `VariableTime`.
+macro_rules! is_constant_time {
+    () => {
+        true
+    };
+    (VariableTime) => {
+        false
+    };
+}
+
+/// Returns the type constraint for the given shorthand.
+///
+/// Operands and results without a shorthand are untyped. `Ty`, `Src`, `Dst`
+/// and `Refs` are short for the instruction, source, destination and
+/// referenced types respectively.
+macro_rules! type_constraint {
+    () => {
+        TypeConstraint::Untyped
+    };
+    (Ty) => {
+        TypeConstraint::InstrType
+    };
+    (Src) => {
+        TypeConstraint::SourceType
+    };
+    (Dst) => {
+        TypeConstraint::DestinationType
+    };
+    (Refs) => {
+        TypeConstraint::Referenced
+    };
+    (Bool) => {
+        TypeConstraint::Exact(Type::Bool)
+    };
+    (Ptr) => {
+        TypeConstraint::Exact(Type::Ptr)
+    };
+    (I32) => {
+        TypeConstraint::Exact(Type::Int(IntType::I32))
+    };
+    ($constraint:ident) => {
+        TypeConstraint::$constraint
+    };
+}
+
+macro_rules! define_descriptors {
+    (
+        $(
+            $ident:ident = $name:literal
+                ( $( $operand:ident $( : $operand_type:ident )? ),* )
+                -> $results:ident $( : $result_type:ident )?
+                $( | $effect:ident )*
+                $( @ $timing:ident )?;
+        )*
+    ) => {
+        $(
+            static $ident: InstructionDescriptor = InstructionDescriptor {
+                name: $name,
+                operands: &[ $( OperandKind::$operand ),* ],
+                operand_types: &[
+                    $( type_constraint!($( $operand_type )?) ),*
+                ],
+                results: ResultKind::$results,
+                result_type: type_constraint!($( $result_type )?),
+                effects: InstructionEffects::PURE
+                    $( .union(InstructionEffects::$effect) )*,
+                constant_time: is_constant_time!($( $timing )?),
+            };
+        )*
+
+        /// The descriptors of all Runwell IR instructions.
+        static ALL_DESCRIPTORS: &[&InstructionDescriptor] = &[
+            $( &$ident ),*
+        ];
+    };
+}
+
+define_descriptors! {
+    CALL = "call"(Func, Values: Refs) -> Multiple: Refs | CALLS | MAY_TRAP;
+    CALL_INDIRECT = "call_indirect"(Table, FuncType, Value: I32, Values: Refs)
+        -> Multiple: Refs | CALLS | MAY_TRAP @ VariableTime;
+    CONST = "const"(Imm) -> Single: Ty;
+    MEMORY_GROW = "memory.grow"(Memory, Value: I32) -> Single: I32
+        | READS_MEMORY | WRITES_MEMORY @ VariableTime;
+    MEMORY_SIZE = "memory.size"(Memory) -> Single: I32 | READS_MEMORY;
+    HEAP_ADDR = "heap_addr"(Memory, Value: I32, Imm) -> Single: Ptr
+        | MAY_TRAP @ VariableTime;
+    DATA_ADDR = "data_addr"(Data) -> Single: Ptr;
+    GLOBAL_GET = "global.get"(Global) -> Single: Ty | READS_GLOBAL;
+    GLOBAL_SET = "global.set"(Global, Value: Ty) -> None | WRITES_GLOBAL;
+    LOAD = "load"(Value: Ptr, Imm) -> Single: Ty
+        | READS_MEMORY | MAY_TRAP @ VariableTime;
+    STORE = "store"(Value: Ptr, Imm, Value: Ty) -> None
+        | WRITES_MEMORY | MAY_TRAP @ VariableTime;
+    SELECT = "select"(Value: Condition, Value: Ty, Value: Ty) -> Single: Ty;
+    REINTERPRET = "reinterpret"(Value: Src) -> Single: Dst;
+
+    TRAP = "trap"() -> None | CONTROL_FLOW | MAY_TRAP;
+    RETURN = "return"(Values: Refs) -> None | CONTROL_FLOW;
+    BR = "br"(Block: Refs) -> None | CONTROL_FLOW;
+    IF_THEN_ELSE = "if"(Value: Bool, Block: Refs, Block: Refs)
+        -> None | CONTROL_FLOW @ VariableTime;
+    TAIL_CALL = "tail_call"(Func, Values: Refs) -> None
+        | CONTROL_FLOW | CALLS | MAY_TRAP;
+    TAIL_CALL_INDIRECT = "tail_call_indirect"(
+        Table, FuncType, Value: I32, Values: Refs
+    ) -> None | CONTROL_FLOW | CALLS | MAY_TRAP @ VariableTime;
+    BR_TABLE = "br_table"(Value: I32, Blocks: Refs, Block: Refs) -> None
+        | CONTROL_FLOW @ VariableTime;
+
+    IADD = "iadd"(Value: Ty, Value: Ty) -> Single: Ty;
+    ISUB = "isub"(Value: Ty, Value: Ty) -> Single: Ty;
+    IMUL = "imul"(Value: Ty, Value: Ty) -> Single: Ty;
+    SDIV = "sdiv"(Value: Ty, Value: Ty) -> Single: Ty | MAY_TRAP @ VariableTime;
+    UDIV = "udiv"(Value: Ty, Value: Ty) -> Single: Ty | MAY_TRAP @ VariableTime;
+    SREM = "srem"(Value: Ty, Value: Ty) -> Single: Ty | MAY_TRAP @ VariableTime;
+    UREM = "urem"(Value: Ty, Value: Ty) -> Single: Ty | MAY_TRAP @ VariableTime;
+    IAND = "iand"(Value: Ty, Value: Ty) -> Single: Ty;
+    IOR = "ior"(Value: Ty, Value: Ty) -> Single: Ty;
+    IXOR = "ixor"(Value: Ty, Value: Ty) -> Single: Ty;
+    ISHL = "ishl"(Value: Ty, Value: I32) -> Single: Ty;
+    SSHR = "sshr"(Value: Ty, Value: I32) -> Single: Ty;
+    USHR = "ushr"(Value: Ty, Value: I32) -> Single: Ty;
+    IROTL = "irotl"(Value: Ty, Value: I32) -> Single: Ty;
+    IROTR = "irotr"(Value: Ty, Value: I32) -> Single: Ty;
+    ILEADING_ZEROS = "ileading_zeros"(Value: Ty) -> Single: Ty;
+    ITRAILING_ZEROS = "itrailing_zeros"(Value: Ty) -> Single: Ty;
+    IPOPCOUNT = "ipopcount"(Value: Ty) -> Single: Ty;
+    ICMP = "icmp"(Value: Ty, Value: Ty) -> Single: Bool;
+    SEXTEND = "sextend"(Value: Src) -> Single: Dst;
+    UEXTEND = "uextend"(Value: Src) -> Single: Dst;
+    ITRUNCATE = "itruncate"(Value: Src) -> Single: Dst;
+    SCONVERT = "sconvert"(Value: Src) -> Single: Dst;
+    UCONVERT = "uconvert"(Value: Src) -> Single: Dst;
+
+    FADD = "fadd"(Value: Ty, Value: Ty) -> Single: Ty;
+    FSUB = "fsub"(Value: Ty, Value: Ty) -> Single: Ty;
+    FMUL = "fmul"(Value: Ty, Value: Ty) -> Single: Ty;
+    FDIV = "fdiv"(Value: Ty, Value: Ty) -> Single: Ty @ VariableTime;
+    FMIN = "fmin"(Value: Ty, Value: Ty) -> Single: Ty;
+    FMAX = "fmax"(Value: Ty, Value: Ty) -> Single: Ty;
+    FCOPYSIGN = "fcopysign"(Value: Ty, Value: Ty) -> Single: Ty;
+    FABS = "fabs"(Value: Ty) -> Single: Ty;
+    FNEG = "fneg"(Value: Ty) -> Single: Ty;
+    FSQRT = "fsqrt"(Value: Ty) -> Single: Ty @ VariableTime;
+    FCEIL = "fceil"(Value: Ty) -> Single: Ty;
+    FFLOOR = "ffloor"(Value: Ty) -> Single: Ty;
+    FTRUNC = "ftrunc"(Value: Ty) -> Single: Ty;
+    FNEAREST = "fnearest"(Value: Ty) -> Single: Ty;
+    FCMP = "fcmp"(Value: Ty, Value: Ty) -> Single: Bool;
+    FDEMOTE = "fdemote"(Value: Src) -> Single: Dst;
+    FPROMOTE = "fpromote"(Value: Src) -> Single: Dst;
+    FCONVERT_S = "fconvert_s"(Value: Src) -> Single: Dst | MAY_TRAP;
+    FCONVERT_U = "fconvert_u"(Value: Src) -> Single: Dst | MAY_TRAP;
+    FCONVERT_S_SAT = "fconvert_s_sat"(Value: Src) -> Single: Dst;
+    FCONVERT_U_SAT = "fconvert_u_sat"(Value: Src) -> Single: Dst;
+}
+
+impl Instruction {
+    /// Returns the static descriptor of the instruction.
+    pub fn descriptor(&self) -> &'static InstructionDescriptor {
+        match self {
+            Self::Call(_) => &CALL,
+            Self::CallIndirect(_) => &CALL_INDIRECT,
+            Self::Const(_) => &CONST,
+            Self::MemoryGrow(_) => &MEMORY_GROW,
+            Self::MemorySize(_) => &MEMORY_SIZE,
+            Self::HeapAddr(_) => &HEAP_ADDR,
+            Self::DataAddr(_) => &DATA_ADDR,
+            Self::GlobalGet(_) => &GLOBAL_GET,
+            Self::GlobalSet(_) => &GLOBAL_SET,
+            Self::Load(_) => &LOAD,
+            Self::Store(_) => &STORE,
+            Self::Select(_) => &SELECT,
+            Self::Reinterpret(_) => &REINTERPRET,
+            Self::Terminal(instr) => instr.descriptor(),
+            Self::Int(instr) => instr.descriptor(),
+            Self::Float(instr) => instr.descriptor(),
+        }
+    }
+
+    /// Returns the type that the type constraint denotes for the instruction.
+    ///
+    /// Returns `None` if the constraint does not denote a single type or if
+    /// the instruction has no such type, e.g. no source type for `iadd`.
+    pub fn constrained_type(&self, constraint: TypeConstraint) -> Option<Type> {
+        match constraint {
+            TypeConstraint::Exact(ty) => Some(ty),
+            TypeConstraint::InstrType => self.instr_type(),
+            TypeConstraint::SourceType => {
+                self.conversion_types().map(|(src_type, _)| src_type)
+            }
+            TypeConstraint::DestinationType => {
+                self.conversion_types().map(|(_, dst_type)| dst_type)
+            }
+            TypeConstraint::Untyped
+            | TypeConstraint::Condition
+            | TypeConstraint::Referenced => None,
+        }
+    }
+
+    /// Returns the type of the instruction if any.
+    fn instr_type(&self) -> Option<Type> {
+        let ty = match self {
+            Self::Const(instr) => instr.ty(),
+            Self::GlobalGet(instr) => instr.ty(),
+            Self::GlobalSet(instr) => instr.ty(),
+            Self::Load(instr) => instr.ty(),
+            Self::Store(instr) => instr.ty(),
+            Self::Select(instr) => instr.ty(),
+            Self::Int(IntInstr::Binary(instr)) => instr.ty().into(),
+            Self::Int(IntInstr::Shift(instr)) => instr.ty().into(),
+            Self::Int(IntInstr::Unary(instr)) => instr.ty().into(),
+            Self::Int(IntInstr::Compare(instr)) => instr.ty().into(),
+            Self::Float(FloatInstr::Binary(instr)) => instr.ty().into(),
+            Self::Float(FloatInstr::Unary(instr)) => instr.ty().into(),
+            Self::Float(FloatInstr::Compare(instr)) => instr.ty().into(),
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    /// Returns the source and destination types of a conversion instruction.
+    fn conversion_types(&self) -> Option<(Type, Type)> {
+        let types = match self {
+            Self::Reinterpret(instr) => (instr.src_type(), instr.dst_type()),
+            Self::Int(IntInstr::Extend(instr)) => {
+                (instr.src_type().into(), instr.dst_type().into())
+            }
+            Self::Int(IntInstr::Truncate(instr)) => {
+                (instr.src_type().into(), instr.dst_type().into())
+            }
+            Self::Int(IntInstr::IntToFloat(instr)) => {
+                (instr.src_type().into(), instr.dst_type().into())
+            }
+            Self::Float(FloatInstr::Demote(instr)) => {
+                (instr.src_type().into(), instr.dst_type().into())
+            }
+            Self::Float(FloatInstr::Promote(instr)) => {
+                (instr.src_type().into(), instr.dst_type().into())
+            }
+            Self::Float(FloatInstr::FloatToInt(instr)) => {
+                (instr.src_type().into(), instr.dst_type().into())
+            }
+            _ => return None,
+        };
+        Some(types)
+    }
+}
+
+impl TerminalInstr {
+    /// Returns the static descriptor of the terminal instruction.
+    pub fn descriptor(&self) -> &'static InstructionDescriptor {
+        match self {
+            Self::Trap(_) => &TRAP,
+            Self::Return(_) => &RETURN,
+            Self::Br(_) => &BR,
+            Self::Ite(_) => &IF_THEN_ELSE,
+            Self::TailCall(_) => &TAIL_CALL,
+            Self::TailCallIndirect(_) => &TAIL_CALL_INDIRECT,
+            Self::BranchTable(_) => &BR_TABLE,
+        }
+    }
+}
+
+impl IntInstr {
+    /// Returns the static descriptor of the integer instruction.
+    pub fn descriptor(&self) -> &'static InstructionDescriptor {
+        match self {
+            Self::Binary(instr) => {
+                match instr.op() {
+                    BinaryIntOp::Add => &IADD,
+                    BinaryIntOp::Sub => &ISUB,
+                    BinaryIntOp::Mul => &IMUL,
+                    BinaryIntOp::Sdiv => &SDIV,
+                    BinaryIntOp::Udiv => &UDIV,
+                    BinaryIntOp::Srem => &SREM,
+                    BinaryIntOp::Urem => &UREM,
+                    BinaryIntOp::And => &IAND,
+                    BinaryIntOp::Or => &IOR,
+                    BinaryIntOp::Xor => &IXOR,
+                }
+            }
+            Self::Shift(instr) => {
+                match instr.op() {
+                    ShiftIntOp::Shl => &ISHL,
+                    ShiftIntOp::Sshr => &SSHR,
+                    ShiftIntOp::Ushr => &USHR,
+                    ShiftIntOp::Rotl => &IROTL,
+                    ShiftIntOp::Rotr => &IROTR,
+                }
+            }
+            Self::Unary(instr) => {
+                match instr.op() {
+                    UnaryIntOp::LeadingZeros => &ILEADING_ZEROS,
+                    UnaryIntOp::TrailingZeros => &ITRAILING_ZEROS,
+                    UnaryIntOp::PopCount => &IPOPCOUNT,
+                }
+            }
+            Self::Compare(_) => &ICMP,
+            Self::Extend(instr) if instr.is_signed() => &SEXTEND,
+            Self::Extend(_) => &UEXTEND,
+            Self::Truncate(_) => &ITRUNCATE,
+            Self::IntToFloat(instr) if instr.is_signed() => &SCONVERT,
+            Self::IntToFloat(_) => &UCONVERT,
+        }
+    }
+}
+
+impl FloatInstr {
+    /// Returns the static descriptor of the floating point instruction.
+    pub fn descriptor(&self) -> &'static InstructionDescriptor {
+        match self {
+            Self::Binary(instr) => {
+                match instr.op() {
+                    BinaryFloatOp::Add => &FADD,
+                    BinaryFloatOp::Sub => &FSUB,
+                    BinaryFloatOp::Mul => &FMUL,
+                    BinaryFloatOp::Div => &FDIV,
+                    BinaryFloatOp::Min => &FMIN,
+                    BinaryFloatOp::Max => &FMAX,
+                    BinaryFloatOp::CopySign => &FCOPYSIGN,
+                }
+            }
+            Self::Unary(instr) => {
+                match instr.op() {
+                    UnaryFloatOp::Abs => &FABS,
+                    UnaryFloatOp::Neg => &FNEG,
+                    UnaryFloatOp::Sqrt => &FSQRT,
+                    UnaryFloatOp::Ceil => &FCEIL,
+                    UnaryFloatOp::Floor => &FFLOOR,
+                    UnaryFloatOp::Truncate => &FTRUNC,
+                    UnaryFloatOp::Nearest => &FNEAREST,
+                }
+            }
+            Self::Compare(_) => &FCMP,
+            Self::Demote(_) => &FDEMOTE,
+            Self::Promote(_) => &FPROMOTE,
+            Self::FloatToInt(instr) => {
+                match (instr.is_signed(), instr.is_saturating()) {
+                    (true, false) => &FCONVERT_S,
+                    (false, false) => &FCONVERT_U,
+                    (true, true) => &FCONVERT_S_SAT,
+                    (false, true) => &FCONVERT_U_SAT,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn descriptor_names_are_unique() {
+        let all = InstructionDescriptor::all();
+        for (n, lhs) in all.iter().enumerate() {
+            for rhs in &all[(n + 1)..] {
+                assert_ne!(lhs.name(), rhs.name());
+            }
+        }
+    }
+
+    #[test]
+    fn from_name_works() {
+        for descriptor in InstructionDescriptor::all() {
+            assert_eq!(
+                InstructionDescriptor::from_name(descriptor.name()),
+                Some(*descriptor)
+            );
+        }
+        assert_eq!(InstructionDescriptor::from_name("unknown"), None);
+    }
+
+    #[test]
+    fn constant_time_works() {
+        let is_constant_time = |name| {
+            InstructionDescriptor::from_name(name)
+                .unwrap()
+                .is_constant_time()
+        };
+        assert!(is_constant_time("iadd"));
+        assert!(is_constant_time("select"));
+        assert!(is_constant_time("fmin"));
+        assert!(!is_constant_time("sdiv"));
+        assert!(!is_constant_time("if"));
+        assert!(!is_constant_time("load"));
+    }
+
+    #[test]
+    fn type_constraints_match_kinds() {
+        for descriptor in InstructionDescriptor::all() {
+            let operands = descriptor.operands();
+            let operand_types = descriptor.operand_types();
+            assert_eq!(operands.len(), operand_types.len());
+            for (kind, constraint) in operands.iter().zip(operand_types) {
+                let is_untyped = *constraint == TypeConstraint::Untyped;
+                let is_referenced = *constraint == TypeConstraint::Referenced;
+                match kind {
+                    OperandKind::Value => {
+                        assert!(!is_untyped && !is_referenced, "{}", descriptor)
+                    }
+                    OperandKind::Values
+                    | OperandKind::Block
+                    | OperandKind::Blocks => assert!(is_referenced),
+                    _ => assert!(is_untyped),
+                }
+            }
+            let result_type = descriptor.result_type();
+            match descriptor.results() {
+                ResultKind::None => {
+                    assert_eq!(result_type, TypeConstraint::Untyped)
+                }
+                ResultKind::Single => {
+                    assert_ne!(result_type, TypeConstraint::Untyped);
+                    assert_ne!(result_type, TypeConstraint::Referenced);
+                }
+                ResultKind::Multiple => {
+                    assert_eq!(result_type, TypeConstraint::Referenced)
+                }
+            }
+        }
+    }
+}
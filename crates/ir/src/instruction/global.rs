@@ -0,0 +1,93 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+    primitive::{Global, Type, Value},
+    VisitValues,
+    VisitValuesMut,
+};
+use derive_more::Display;
+
+/// Returns the current value of the indexed global variable.
+#[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[display(fmt = "global.get {} {}", ty, global)]
+pub struct GlobalGetInstr {
+    global: Global,
+    ty: Type,
+}
+
+impl GlobalGetInstr {
+    /// Creates a new global variable read instruction.
+    pub fn new(global: Global, ty: Type) -> Self {
+        Self { global, ty }
+    }
+
+    /// Returns the global variable that is read.
+    pub fn global(&self) -> Global {
+        self.global
+    }
+
+    /// Returns the type of the global variable.
+    pub fn ty(&self) -> Type {
+        self.ty
+    }
+}
+
+/// Sets the value of the indexed mutable global variable.
+#[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[display(fmt = "global.set {} {} {}", ty, global, value)]
+pub struct GlobalSetInstr {
+    global: Global,
+    value: Value,
+    ty: Type,
+}
+
+impl GlobalSetInstr {
+    /// Creates a new global variable write instruction.
+    pub fn new(global: Global, value: Value, ty: Type) -> Self {
+        Self { global, value, ty }
+    }
+
+    /// Returns the global variable that is written.
+    pub fn global(&self) -> Global {
+        self.global
+    }
+
+    /// Returns the value that is written to the global variable.
+    pub fn value(&self) -> Value {
+        self.value
+    }
+
+    /// Returns the type of the global variable.
+    pub fn ty(&self) -> Type {
+        self.ty
+    }
+}
+
+impl VisitValues for GlobalSetInstr {
+    fn visit_values<V>(&self, mut visitor: V)
+    where
+        V: FnMut(Value) -> bool,
+    {
+        visitor(self.value);
+    }
+}
+
+impl VisitValuesMut for GlobalSetInstr {
+    fn visit_values_mut<V>(&mut self, mut visitor: V)
+    where
+        V: FnMut(&mut Value) -> bool,
+    {
+        visitor(&mut self.value);
+    }
+}
@@ -27,6 +27,8 @@ use core::fmt::Display;
 ///
 /// - The source and result values are of integer type `ty` whereas
 ///   the shift amount is always of type `I32`.
+/// - The shift amount is taken modulo the bit width of `ty` which
+///   matches the semantics of the Wasm shift and rotate operators.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct ShiftIntInstr {
     op: ShiftIntOp,
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::{
-    primitive::{Mem, Type, Value},
+    primitive::{Data, Mem, Type, Value},
     VisitValues,
     VisitValuesMut,
 };
@@ -28,6 +28,13 @@ pub struct ImmU32 {
     value: u32,
 }
 
+impl ImmU32 {
+    /// Returns the underlying `u32` value.
+    pub fn into_u32(self) -> u32 {
+        self.value
+    }
+}
+
 /// Returns a pointer from the heap at the given location.
 ///
 /// Checks if the range `ptr..ptr+size` is within bounds of the target heap memory.
@@ -83,6 +90,29 @@ impl VisitValuesMut for HeapAddrInstr {
     }
 }
 
+/// Returns a pointer to the start of the indexed read-only data object.
+///
+/// Read-only data objects are byte blobs owned by the module that are not
+/// part of any linear memory. Therefore the optimizer may freely assume that
+/// their contents never change.
+#[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[display(fmt = "data_addr {}", data)]
+pub struct DataAddrInstr {
+    data: Data,
+}
+
+impl DataAddrInstr {
+    /// Creates a new read-only data addressing instruction.
+    pub fn new(data: Data) -> Self {
+        Self { data }
+    }
+
+    /// Returns the index of the referenced read-only data object.
+    pub fn data(&self) -> Data {
+        self.data
+    }
+}
+
 /// Loads a value of type `ty` from the given memory at the given address with given alignment.
 #[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 #[display(fmt = "load {} from {}+{}", ty, address, offset)]
@@ -211,6 +241,16 @@ impl MemoryGrowInstr {
     pub fn new(memory: Mem, new_pages: Value) -> Self {
         Self { memory, new_pages }
     }
+
+    /// Returns the linear memory that is grown.
+    pub fn memory(&self) -> Mem {
+        self.memory
+    }
+
+    /// Returns the value holding the amount of new pages.
+    pub fn new_pages(&self) -> Value {
+        self.new_pages
+    }
 }
 
 impl VisitValues for MemoryGrowInstr {
@@ -243,4 +283,9 @@ impl MemorySizeInstr {
     pub fn new(memory: Mem) -> Self {
         Self { memory }
     }
+
+    /// Returns the linear memory that is queried.
+    pub fn memory(&self) -> Mem {
+        self.memory
+    }
 }
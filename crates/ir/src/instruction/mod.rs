@@ -15,10 +15,11 @@
 mod call;
 mod constant;
 mod conv;
+mod descriptor;
 mod float;
+mod global;
 mod int;
 mod memory;
-mod phi;
 mod select;
 mod terminal;
 
@@ -26,6 +27,13 @@ pub use self::{
     call::{CallIndirectInstr, CallInstr},
     constant::ConstInstr,
     conv::ReinterpretInstr,
+    descriptor::{
+        InstructionDescriptor,
+        InstructionEffects,
+        OperandKind,
+        ResultKind,
+        TypeConstraint,
+    },
     float::{
         BinaryFloatInstr,
         BinaryFloatOp,
@@ -38,6 +46,7 @@ pub use self::{
         UnaryFloatInstr,
         UnaryFloatOp,
     },
+    global::{GlobalGetInstr, GlobalSetInstr},
     int::{
         BinaryIntInstr,
         BinaryIntOp,
@@ -53,6 +62,7 @@ pub use self::{
         UnaryIntOp,
     },
     memory::{
+        DataAddrInstr,
         HeapAddrInstr,
         ImmU32,
         LoadInstr,
@@ -60,16 +70,17 @@ pub use self::{
         MemorySizeInstr,
         StoreInstr,
     },
-    phi::PhiInstr,
     select::SelectInstr,
     terminal::{
         BranchInstr,
         BranchTableInstr,
+        BranchTarget,
         IfThenElseInstr,
         ReturnInstr,
         TailCallIndirectInstr,
         TailCallInstr,
         TerminalInstr,
+        TrapCode,
     },
 };
 use super::primitive::Value;
@@ -98,7 +109,9 @@ pub enum Instruction {
     MemoryGrow(MemoryGrowInstr),
     MemorySize(MemorySizeInstr),
     HeapAddr(HeapAddrInstr),
-    Phi(PhiInstr),
+    DataAddr(DataAddrInstr),
+    GlobalGet(GlobalGetInstr),
+    GlobalSet(GlobalSetInstr),
     Load(LoadInstr),
     Store(StoreInstr),
     Select(SelectInstr),
@@ -113,11 +126,6 @@ impl Instruction {
     pub fn is_terminal(&self) -> bool {
         matches!(self, Self::Terminal(_))
     }
-
-    /// Returns `true` if the instruction is a ϕ-instruction.
-    pub fn is_phi(&self) -> bool {
-        matches!(self, Self::Phi(_))
-    }
 }
 
 impl VisitValues for Instruction {
@@ -131,8 +139,10 @@ impl VisitValues for Instruction {
             Self::Const(instr) => instr.visit_values(visitor),
             Self::MemoryGrow(instr) => instr.visit_values(visitor),
             Self::MemorySize(__instr) => (),
-            Self::Phi(instr) => instr.visit_values(visitor),
             Self::HeapAddr(instr) => instr.visit_values(visitor),
+            Self::DataAddr(__instr) => (),
+            Self::GlobalGet(__instr) => (),
+            Self::GlobalSet(instr) => instr.visit_values(visitor),
             Self::Load(instr) => instr.visit_values(visitor),
             Self::Store(instr) => instr.visit_values(visitor),
             Self::Select(instr) => instr.visit_values(visitor),
@@ -155,8 +165,10 @@ impl VisitValuesMut for Instruction {
             Self::Const(instr) => instr.visit_values_mut(visitor),
             Self::MemoryGrow(instr) => instr.visit_values_mut(visitor),
             Self::MemorySize(__instr) => (),
-            Self::Phi(instr) => instr.visit_values_mut(visitor),
             Self::HeapAddr(instr) => instr.visit_values_mut(visitor),
+            Self::DataAddr(__instr) => (),
+            Self::GlobalGet(__instr) => (),
+            Self::GlobalSet(instr) => instr.visit_values_mut(visitor),
             Self::Load(instr) => instr.visit_values_mut(visitor),
             Self::Store(instr) => instr.visit_values_mut(visitor),
             Self::Select(instr) => instr.visit_values_mut(visitor),
@@ -176,11 +188,15 @@ mod tests {
     fn size_of_instruction_is_kept_small() {
         use core::mem::size_of;
         // Ideally we keep the size of generic instructions as small as possible.
-        assert_eq!(size_of::<Instruction>(), 48);
+        assert_eq!(size_of::<Instruction>(), 56);
         // Also assert the sizes of the biggest known concrete instructions.
-        assert_eq!(size_of::<TerminalInstr>(), 40);
-        assert_eq!(size_of::<BranchTableInstr>(), 32);
-        assert_eq!(size_of::<PhiInstr>(), 24);
+        //
+        // Branch tables are the biggest since they also refer to the keys
+        // of sparse branch tables and their targets carry the arguments
+        // passed to the basic block parameters.
+        assert_eq!(size_of::<TerminalInstr>(), 56);
+        assert_eq!(size_of::<BranchTableInstr>(), 48);
+        assert_eq!(size_of::<IfThenElseInstr>(), 40);
         assert_eq!(size_of::<CallIndirectInstr>(), 32);
     }
 }
@@ -20,6 +20,36 @@ use crate::{
 };
 use core::fmt::{self, Display};
 use derive_more::{Display, From};
+use smallvec::SmallVec;
+
+/// The reason for a trap.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TrapCode {
+    /// Unreachable code has been executed.
+    #[display(fmt = "unreachable")]
+    Unreachable,
+    /// An integer has been divided by zero.
+    #[display(fmt = "integer_division_by_zero")]
+    IntegerDivisionByZero,
+    /// An integer operation or conversion has overflowed.
+    #[display(fmt = "integer_overflow")]
+    IntegerOverflow,
+    /// A NaN has been converted to an integer.
+    #[display(fmt = "bad_conversion_to_integer")]
+    BadConversionToInteger,
+    /// A linear memory has been accessed out of bounds.
+    #[display(fmt = "memory_out_of_bounds")]
+    MemoryOutOfBounds,
+    /// An indirect call did not match the signature of the called function.
+    #[display(fmt = "indirect_call_type_mismatch")]
+    IndirectCallTypeMismatch,
+    /// A table has been accessed out of bounds or at an uninitialized element.
+    #[display(fmt = "table_out_of_bounds")]
+    TableOutOfBounds,
+    /// The maximum call depth has been exceeded.
+    #[display(fmt = "stack_overflow")]
+    StackOverflow,
+}
 
 /// A terminal SSA instruction.
 ///
@@ -27,8 +57,8 @@ use derive_more::{Display, From};
 /// as its last instruction.
 #[derive(Debug, Display, From, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub enum TerminalInstr {
-    #[display(fmt = "trap")]
-    Trap,
+    #[display(fmt = "trap {}", _0)]
+    Trap(TrapCode),
     Return(ReturnInstr),
     Br(BranchInstr),
     Ite(IfThenElseInstr),
@@ -37,15 +67,108 @@ pub enum TerminalInstr {
     BranchTable(BranchTableInstr),
 }
 
+impl TerminalInstr {
+    /// Returns the basic blocks that the terminal instruction may branch to.
+    ///
+    /// # Note
+    ///
+    /// The same basic block might be returned multiple times,
+    /// e.g. for branching tables with multiple cases jumping to it.
+    pub fn successors(&self) -> SmallBlockVec {
+        match self {
+            Self::Trap(_)
+            | Self::Return(_)
+            | Self::TailCall(_)
+            | Self::TailCallIndirect(_) => SmallBlockVec::new(),
+            _ => {
+                self.branch_targets()
+                    .into_iter()
+                    .map(BranchTarget::block)
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns the branch targets of the terminal instruction.
+    ///
+    /// # Note
+    ///
+    /// For branching tables the default target comes last.
+    pub fn branch_targets(&self) -> SmallVec<[&BranchTarget; 2]> {
+        match self {
+            Self::Trap(_)
+            | Self::Return(_)
+            | Self::TailCall(_)
+            | Self::TailCallIndirect(_) => SmallVec::new(),
+            Self::Br(instr) => [&instr.target].iter().copied().collect(),
+            Self::Ite(instr) => {
+                [&instr.br_then, &instr.br_else].iter().copied().collect()
+            }
+            Self::BranchTable(instr) => {
+                instr.targets.iter().chain(Some(&instr.default)).collect()
+            }
+        }
+    }
+
+    /// Returns exclusive references to the branch targets of the terminal instruction.
+    ///
+    /// # Note
+    ///
+    /// For branching tables the default target comes last.
+    pub fn branch_targets_mut(&mut self) -> SmallVec<[&mut BranchTarget; 2]> {
+        match self {
+            Self::Trap(_)
+            | Self::Return(_)
+            | Self::TailCall(_)
+            | Self::TailCallIndirect(_) => SmallVec::new(),
+            Self::Br(instr) => Some(&mut instr.target).into_iter().collect(),
+            Self::Ite(instr) => {
+                Some(&mut instr.br_then)
+                    .into_iter()
+                    .chain(Some(&mut instr.br_else))
+                    .collect()
+            }
+            Self::BranchTable(instr) => {
+                instr
+                    .targets
+                    .iter_mut()
+                    .chain(Some(&mut instr.default))
+                    .collect()
+            }
+        }
+    }
+
+    /// Replaces all branches to the `old` basic block with branches to the `new` basic block.
+    ///
+    /// The replaced branches pass the given arguments to the `new` basic block.
+    ///
+    /// Returns `true` if at least one branch has been replaced.
+    pub fn replace_successor(
+        &mut self,
+        old: Block,
+        new: Block,
+        args: &[Value],
+    ) -> bool {
+        let mut replaced = false;
+        for target in self.branch_targets_mut() {
+            if target.block == old {
+                *target = BranchTarget::new(new, args.iter().copied());
+                replaced = true;
+            }
+        }
+        replaced
+    }
+}
+
 impl VisitValues for TerminalInstr {
     fn visit_values<V>(&self, visitor: V)
     where
         V: FnMut(Value) -> bool,
     {
         match self {
-            Self::Trap => (),
+            Self::Trap(_) => (),
             Self::Return(instr) => instr.visit_values(visitor),
-            Self::Br(_instr) => (),
+            Self::Br(instr) => instr.visit_values(visitor),
             Self::Ite(instr) => instr.visit_values(visitor),
             Self::TailCall(instr) => instr.visit_values(visitor),
             Self::TailCallIndirect(instr) => instr.visit_values(visitor),
@@ -60,9 +183,9 @@ impl VisitValuesMut for TerminalInstr {
         V: FnMut(&mut Value) -> bool,
     {
         match self {
-            Self::Trap => (),
+            Self::Trap(_) => (),
             Self::Return(instr) => instr.visit_values_mut(visitor),
-            Self::Br(_instr) => (),
+            Self::Br(instr) => instr.visit_values_mut(visitor),
             Self::Ite(instr) => instr.visit_values_mut(visitor),
             Self::TailCall(instr) => instr.visit_values_mut(visitor),
             Self::TailCallIndirect(instr) => instr.visit_values_mut(visitor),
@@ -141,23 +264,156 @@ impl VisitValuesMut for ReturnInstr {
     }
 }
 
+/// The basic block a branch jumps to and the arguments passed to its parameters.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct BranchTarget {
+    block: Block,
+    /// The arguments passed to the basic block parameters.
+    ///
+    /// `None` if there are no arguments. Boxed twice to keep the size of
+    /// branching instructions small since most branches pass no arguments.
+    args: Option<Box<Box<[Value]>>>,
+}
+
+impl From<Block> for BranchTarget {
+    fn from(block: Block) -> Self {
+        Self::new(block, None)
+    }
+}
+
+impl BranchTarget {
+    /// Creates a new branch target passing the arguments to the basic block.
+    pub fn new<I>(block: Block, args: I) -> Self
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        Self {
+            block,
+            args: Self::collect_args(args),
+        }
+    }
+
+    /// Collects the arguments without allocating if there are none.
+    fn collect_args<I>(args: I) -> Option<Box<Box<[Value]>>>
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        let args = args.into_iter().collect::<Box<[Value]>>();
+        if args.is_empty() {
+            return None
+        }
+        Some(Box::new(args))
+    }
+
+    /// Returns the basic block to jump to.
+    #[inline]
+    pub fn block(&self) -> Block {
+        self.block
+    }
+
+    /// Returns the arguments passed to the parameters of the basic block.
+    #[inline]
+    pub fn args(&self) -> &[Value] {
+        self.args.as_deref().map(|args| &args[..]).unwrap_or_default()
+    }
+
+    /// Returns the mutable arguments passed to the parameters of the basic block.
+    fn args_mut(&mut self) -> &mut [Value] {
+        self.args
+            .as_deref_mut()
+            .map(|args| &mut args[..])
+            .unwrap_or_default()
+    }
+
+    /// Replaces the arguments passed to the parameters of the basic block.
+    pub fn set_args<I>(&mut self, args: I)
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        self.args = Self::collect_args(args);
+    }
+}
+
+impl VisitValues for BranchTarget {
+    fn visit_values<V>(&self, mut visitor: V)
+    where
+        V: FnMut(Value) -> bool,
+    {
+        for &value in self.args() {
+            if !visitor(value) {
+                break
+            }
+        }
+    }
+}
+
+impl VisitValuesMut for BranchTarget {
+    fn visit_values_mut<V>(&mut self, mut visitor: V)
+    where
+        V: FnMut(&mut Value) -> bool,
+    {
+        for value in self.args_mut() {
+            if !visitor(value) {
+                break
+            }
+        }
+    }
+}
+
+impl Display for BranchTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.block)?;
+        if let Some((first, rest)) = self.args().split_first() {
+            write!(f, "({}", first)?;
+            for arg in rest {
+                write!(f, ", {}", arg)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
 /// Unconditionally branches to another basic block.
 #[derive(Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 #[display(fmt = "br {}", target)]
 pub struct BranchInstr {
-    target: Block,
+    target: BranchTarget,
 }
 
 impl BranchInstr {
     /// Creates a new branch instruction branching to the given basic block.
-    pub fn new(target: Block) -> Self {
-        Self { target }
+    pub fn new<T>(target: T) -> Self
+    where
+        T: Into<BranchTarget>,
+    {
+        Self {
+            target: target.into(),
+        }
     }
 
     /// Returns the target block to jump to.
     #[inline]
-    pub fn target(&self) -> Block {
-        self.target
+    pub fn target(&self) -> &BranchTarget {
+        &self.target
+    }
+}
+
+impl VisitValues for BranchInstr {
+    fn visit_values<V>(&self, visitor: V)
+    where
+        V: FnMut(Value) -> bool,
+    {
+        self.target.visit_values(visitor)
+    }
+}
+
+impl VisitValuesMut for BranchInstr {
+    fn visit_values_mut<V>(&mut self, visitor: V)
+    where
+        V: FnMut(&mut Value) -> bool,
+    {
+        self.target.visit_values_mut(visitor)
     }
 }
 
@@ -166,17 +422,21 @@ impl BranchInstr {
 #[display(fmt = "if {} then {} else {}", condition, br_then, br_else)]
 pub struct IfThenElseInstr {
     condition: Value,
-    br_then: Block,
-    br_else: Block,
+    br_then: BranchTarget,
+    br_else: BranchTarget,
 }
 
 impl IfThenElseInstr {
     /// Creates a new if-then-else instruction branching to either `then` or `else` depending on `condition`.
-    pub fn new(condition: Value, br_then: Block, br_else: Block) -> Self {
+    pub fn new<T, E>(condition: Value, br_then: T, br_else: E) -> Self
+    where
+        T: Into<BranchTarget>,
+        E: Into<BranchTarget>,
+    {
         Self {
             condition,
-            br_then,
-            br_else,
+            br_then: br_then.into(),
+            br_else: br_else.into(),
         }
     }
 
@@ -188,14 +448,14 @@ impl IfThenElseInstr {
 
     /// Returns the block to jump to in case the condition evaluates to `true`.
     #[inline]
-    pub fn true_target(&self) -> Block {
-        self.br_then
+    pub fn true_target(&self) -> &BranchTarget {
+        &self.br_then
     }
 
     /// Returns the block to jump to in case the condition evaluates to `false`.
     #[inline]
-    pub fn false_target(&self) -> Block {
-        self.br_else
+    pub fn false_target(&self) -> &BranchTarget {
+        &self.br_else
     }
 }
 
@@ -204,7 +464,13 @@ impl VisitValues for IfThenElseInstr {
     where
         V: FnMut(Value) -> bool,
     {
-        visitor(self.condition);
+        let mut proceed = visitor(self.condition);
+        for target in &[&self.br_then, &self.br_else] {
+            target.visit_values(|value| {
+                proceed = proceed && visitor(value);
+                proceed
+            });
+        }
     }
 }
 
@@ -213,7 +479,13 @@ impl VisitValuesMut for IfThenElseInstr {
     where
         V: FnMut(&mut Value) -> bool,
     {
-        visitor(&mut self.condition);
+        let mut proceed = visitor(&mut self.condition);
+        for target in &mut [&mut self.br_then, &mut self.br_else] {
+            target.visit_values_mut(|value| {
+                proceed = proceed && visitor(value);
+                proceed
+            });
+        }
     }
 }
 
@@ -339,23 +611,72 @@ impl VisitValuesMut for TailCallIndirectInstr {
 }
 
 /// A branching table mapping indices to branching targets.
+///
+/// # Dense and Sparse Tables
+///
+/// A dense branching table maps the indices `0..n` to its `n` targets.
+/// A sparse branching table instead maps arbitrary case keys to its targets.
+/// In both cases all indices without target jump to the default target.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct BranchTableInstr {
     case: Value,
-    default: Block,
-    targets: SmallBlockVec,
+    default: BranchTarget,
+    targets: Box<[BranchTarget]>,
+    /// The sorted case keys of a sparse branching table.
+    ///
+    /// `None` for dense branching tables.
+    /// Otherwise the `n`th key belongs to the `n`th target.
+    ///
+    /// Boxed twice to keep the size of instructions small since sparse
+    /// branching tables are rare.
+    keys: Option<Box<Box<[u32]>>>,
 }
 
 impl BranchTableInstr {
     /// Creates a new branching table with the given case, default target and targets.
-    pub fn new<I>(case: Value, default: Block, targets: I) -> Self
+    pub fn new<D, I, T>(case: Value, default: D, targets: I) -> Self
+    where
+        D: Into<BranchTarget>,
+        I: IntoIterator<Item = T>,
+        T: Into<BranchTarget>,
+    {
+        Self {
+            case,
+            default: default.into(),
+            targets: targets.into_iter().map(Into::into).collect(),
+            keys: None,
+        }
+    }
+
+    /// Creates a new sparse branching table with the given case, default target
+    /// and pairs of case keys and their targets.
+    ///
+    /// # Panics
+    ///
+    /// If the same case key is used for more than one target.
+    pub fn new_sparse<D, I, T>(case: Value, default: D, cases: I) -> Self
     where
-        I: IntoIterator<Item = Block>,
+        D: Into<BranchTarget>,
+        I: IntoIterator<Item = (u32, T)>,
+        T: Into<BranchTarget>,
     {
+        let mut cases = cases.into_iter().collect::<Vec<_>>();
+        cases.sort_by_key(|(key, _)| *key);
+        for window in cases.windows(2) {
+            assert_ne!(
+                window[0].0, window[1].0,
+                "encountered duplicate case key in sparse branching table"
+            );
+        }
+        let keys = cases.iter().map(|(key, _)| *key).collect();
         Self {
             case,
-            default,
-            targets: targets.into_iter().collect(),
+            default: default.into(),
+            targets: cases
+                .into_iter()
+                .map(|(_, target)| target.into())
+                .collect(),
+            keys: Some(Box::new(keys)),
         }
     }
 
@@ -365,13 +686,52 @@ impl BranchTableInstr {
     }
 
     /// Returns a slice over all target jumps.
-    pub fn targets(&self) -> &[Block] {
+    ///
+    /// # Note
+    ///
+    /// For sparse branching tables the targets are ordered by their case keys.
+    pub fn targets(&self) -> &[BranchTarget] {
         &self.targets
     }
 
     /// Returns the default target to jump to.
-    pub fn default_target(&self) -> Block {
-        self.default
+    pub fn default_target(&self) -> &BranchTarget {
+        &self.default
+    }
+
+    /// Returns `true` if the branching table is sparse.
+    pub fn is_sparse(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    /// Returns an iterator over the case keys and their targets in ascending key order.
+    pub fn cases(&self) -> impl Iterator<Item = (u32, &BranchTarget)> + '_ {
+        let dense_keys = if self.is_sparse() {
+            0..0
+        } else {
+            0..self.targets.len() as u32
+        };
+        let sparse_keys = self.keys.as_deref().map(|keys| &keys[..]);
+        sparse_keys
+            .unwrap_or_default()
+            .iter()
+            .copied()
+            .chain(dense_keys)
+            .zip(self.targets.iter())
+    }
+
+    /// Returns the non-default target to jump to for the given case index if any.
+    pub fn case_target(&self, index: u32) -> Option<&BranchTarget> {
+        let position = match &self.keys {
+            Some(keys) => keys.binary_search(&index).ok(),
+            None => Some(index as usize),
+        };
+        position.and_then(|position| self.targets.get(position))
+    }
+
+    /// Returns the target to jump to for the given case index.
+    pub fn target(&self, index: u32) -> &BranchTarget {
+        self.case_target(index).unwrap_or(&self.default)
     }
 }
 
@@ -380,7 +740,13 @@ impl VisitValues for BranchTableInstr {
     where
         V: FnMut(Value) -> bool,
     {
-        visitor(self.case);
+        let mut proceed = visitor(self.case);
+        for target in self.targets.iter().chain(Some(&self.default)) {
+            target.visit_values(|value| {
+                proceed = proceed && visitor(value);
+                proceed
+            });
+        }
     }
 }
 
@@ -389,18 +755,21 @@ impl VisitValuesMut for BranchTableInstr {
     where
         V: FnMut(&mut Value) -> bool,
     {
-        visitor(&mut self.case);
+        let mut proceed = visitor(&mut self.case);
+        for target in self.targets.iter_mut().chain(Some(&mut self.default)) {
+            target.visit_values_mut(|value| {
+                proceed = proceed && visitor(value);
+                proceed
+            });
+        }
     }
 }
 
 impl Display for BranchTableInstr {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "br_table {} [ ", self.case)?;
-        if let Some((first, rest)) = self.targets.split_first() {
-            write!(f, "0 🠖 {}", first)?;
-            for (n, target) in rest.iter().enumerate() {
-                write!(f, ", {} 🠖 {}", n + 1, target)?;
-            }
+        for (key, target) in self.cases() {
+            write!(f, "{} 🠖 {}, ", key, target)?;
         }
         write!(f, "_ 🠖 {}", self.default_target())?;
         write!(f, " ]")?;
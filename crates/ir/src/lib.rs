@@ -16,11 +16,21 @@
 
 #![forbid(unsafe_code)]
 
+mod cost;
+mod diagnostic;
 mod instruction;
 pub mod primitive;
 mod value_visitor;
 
 pub use self::{
+    cost::{
+        CostModel,
+        GasTable,
+        InstructionTiming,
+        TimingTable,
+        UniformCostModel,
+    },
+    diagnostic::{Diagnostic, DiagnosticRenderer, ToDiagnostic},
     instruction::ImmU32,
     value_visitor::{VisitValues, VisitValuesMut},
 };
@@ -46,26 +56,33 @@ pub mod instr {
         BinaryIntInstr,
         BranchInstr,
         BranchTableInstr,
+        BranchTarget,
         CallIndirectInstr,
         CallInstr,
         CompareFloatInstr,
         CompareIntInstr,
         ConstInstr,
+        DataAddrInstr,
         DemoteFloatInstr,
         ExtendIntInstr,
         FloatInstr,
         FloatToIntInstr,
+        GlobalGetInstr,
+        GlobalSetInstr,
         HeapAddrInstr,
         IfThenElseInstr,
         Instruction,
+        InstructionDescriptor,
+        InstructionEffects,
         IntInstr,
         IntToFloatInstr,
         LoadInstr,
         MemoryGrowInstr,
         MemorySizeInstr,
-        PhiInstr,
+        OperandKind,
         PromoteFloatInstr,
         ReinterpretInstr,
+        ResultKind,
         ReturnInstr,
         SelectInstr,
         ShiftIntInstr,
@@ -73,7 +90,9 @@ pub mod instr {
         TailCallIndirectInstr,
         TailCallInstr,
         TerminalInstr,
+        TrapCode,
         TruncateIntInstr,
+        TypeConstraint,
         UnaryFloatInstr,
         UnaryIntInstr,
     };
@@ -85,6 +85,32 @@ impl DisplayHook for TableEntity {
     }
 }
 
+/// A global variable entity of the Runwell IR.
+#[derive(Debug, Default)]
+pub struct GlobalVariableEntity;
+
+/// The unique index of a global variable entity of the Runwell IR.
+pub type Global = Idx<GlobalVariableEntity>;
+
+impl DisplayHook for GlobalVariableEntity {
+    fn fmt(idx: Global, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "global({})", idx.into_raw())
+    }
+}
+
+/// A read-only data entity of the Runwell IR.
+#[derive(Debug, Default)]
+pub struct ReadOnlyDataEntity;
+
+/// The unique index of a read-only data entity of the Runwell IR.
+pub type Data = Idx<ReadOnlyDataEntity>;
+
+impl DisplayHook for ReadOnlyDataEntity {
+    fn fmt(idx: Data, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "data({})", idx.into_raw())
+    }
+}
+
 /// A basic block entity of the Runwell IR.
 #[derive(Debug, Default, Copy, Clone)]
 pub struct BlockEntity;
@@ -239,6 +265,25 @@ impl Const {
             Self::Float(float_const) => float_const.into_bits64(),
         }
     }
+
+    /// Creates a constant of the given type from its underlying 64-bits.
+    ///
+    /// # Note
+    ///
+    /// This is the inverse of [`Const::into_bits64`].
+    /// Bits that do not fit into the given type are ignored.
+    pub fn from_bits64(ty: Type, bits: u64) -> Self {
+        match ty {
+            Type::Bool => Self::Bool(bits != 0),
+            Type::Ptr => Self::Ptr(bits as u32),
+            Type::Int(IntType::I8) => IntConst::I8(bits as i8).into(),
+            Type::Int(IntType::I16) => IntConst::I16(bits as i16).into(),
+            Type::Int(IntType::I32) => IntConst::I32(bits as i32).into(),
+            Type::Int(IntType::I64) => IntConst::I64(bits as i64).into(),
+            Type::Float(FloatType::F32) => F32::from_bits(bits as u32).into(),
+            Type::Float(FloatType::F64) => F64::from_bits(bits).into(),
+        }
+    }
 }
 
 /// A constant fixed-size integer value.
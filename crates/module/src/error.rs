@@ -12,9 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::FunctionBuilderError;
+use super::{FunctionBuilderError, FunctionEditorError};
 use core::fmt;
 use derive_more::{Display, Error, From};
+use ir::{Diagnostic, ToDiagnostic};
 
 /// An error that occurred while translating from Wasm to Runwell IR.
 #[derive(Debug, Error, From, PartialEq, Eq)]
@@ -60,14 +61,43 @@ impl Error {
     }
 }
 
+impl ToDiagnostic for Error {
+    /// Returns the diagnostic of the error kind.
+    ///
+    /// The context information is joined into the `context` parameter.
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = self.kind.to_diagnostic();
+        if self.context.is_empty() {
+            return diagnostic
+        }
+        diagnostic.with_parameter("context", self.context.join("\n"))
+    }
+}
+
 impl From<FunctionBuilderError> for Error {
     fn from(error: FunctionBuilderError) -> Self {
         Self::from_kind(error.into())
     }
 }
 
+impl From<FunctionEditorError> for Error {
+    fn from(error: FunctionEditorError) -> Self {
+        Self::from_kind(error.into())
+    }
+}
+
 /// An error kind that occurred while translating from Wasm to Runwell IR.
 #[derive(Debug, Display, Error, From, PartialEq, Eq)]
 pub enum ErrorKind {
     FunctionBuilder(FunctionBuilderError),
+    FunctionEditor(FunctionEditorError),
+}
+
+impl ToDiagnostic for ErrorKind {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::FunctionBuilder(error) => error.to_diagnostic(),
+            Self::FunctionEditor(error) => error.to_diagnostic(),
+        }
+    }
 }
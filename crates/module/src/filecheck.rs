@@ -0,0 +1,329 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Assertion helpers that match printed Runwell IR against check patterns.
+//!
+//! The supported directives are inspired by LLVM's `FileCheck` tool:
+//!
+//! - `CHECK: <pattern>`: The pattern must occur somewhere after the previous
+//!   match.
+//! - `CHECK-NEXT: <pattern>`: The pattern must occur on the line directly
+//!   following the line of the previous match.
+//! - `CHECK-SAME: <pattern>`: The pattern must occur on the same line as the
+//!   previous match after the matched text.
+//! - `CHECK-NOT: <pattern>`: The pattern must not occur between the previous
+//!   match and the next match or the end of the input.
+//!
+//! Patterns are matched as plain substrings. Consecutive whitespace in both
+//! the patterns and the input are treated as a single space. Lines of the
+//! check file without directive are ignored, so directives can be embedded
+//! into comments, e.g. `// CHECK: iadd<i32>`.
+
+use derive_more::Display;
+
+/// A check directive.
+#[derive(Debug, Display, Copy, Clone, PartialEq, Eq)]
+pub enum Directive {
+    #[display(fmt = "CHECK")]
+    Check,
+    #[display(fmt = "CHECK-NEXT")]
+    CheckNext,
+    #[display(fmt = "CHECK-SAME")]
+    CheckSame,
+    #[display(fmt = "CHECK-NOT")]
+    CheckNot,
+}
+
+impl Directive {
+    /// All directives and their textual prefixes.
+    ///
+    /// Ordered so that longer prefixes are tried first.
+    const PREFIXES: [(&'static str, Self); 4] = [
+        ("CHECK-NEXT:", Self::CheckNext),
+        ("CHECK-SAME:", Self::CheckSame),
+        ("CHECK-NOT:", Self::CheckNot),
+        ("CHECK:", Self::Check),
+    ];
+}
+
+/// An error that occurs if the input does not match the check patterns.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum FileCheckError {
+    #[display(fmt = "the check file does not contain any CHECK directives")]
+    NoDirectives,
+    #[display(
+        fmt = "{} on line {} of the check file has an empty pattern",
+        directive,
+        check_line
+    )]
+    EmptyPattern {
+        directive: Directive,
+        check_line: usize,
+    },
+    #[display(
+        fmt = "{} on line {} of the check file cannot follow a CHECK-NOT",
+        directive,
+        check_line
+    )]
+    InvalidDirectiveAfterNot {
+        directive: Directive,
+        check_line: usize,
+    },
+    #[display(
+        fmt = "{}: {:?} on line {} of the check file did not match the input",
+        directive,
+        pattern,
+        check_line
+    )]
+    NoMatch {
+        directive: Directive,
+        pattern: String,
+        check_line: usize,
+    },
+    #[display(
+        fmt = "CHECK-NOT: {:?} on line {} of the check file matched line {} of the input: {:?}",
+        pattern,
+        check_line,
+        input_line,
+        line
+    )]
+    ForbiddenMatch {
+        pattern: String,
+        check_line: usize,
+        input_line: usize,
+        line: String,
+    },
+}
+
+/// A single parsed check directive with its pattern.
+#[derive(Debug)]
+struct CheckLine {
+    directive: Directive,
+    pattern: String,
+    /// The 1-based line number within the check file.
+    line: usize,
+}
+
+/// Collapses all consecutive whitespace into a single space and trims the ends.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parses all directives of the check file.
+fn parse_checks(checks: &str) -> Result<Vec<CheckLine>, FileCheckError> {
+    let mut parsed = Vec::new();
+    for (n, line) in checks.lines().enumerate() {
+        let directive = Directive::PREFIXES.iter().find_map(|(prefix, dir)| {
+            line.find(prefix).map(|pos| (*dir, &line[pos + prefix.len()..]))
+        });
+        if let Some((directive, pattern)) = directive {
+            let pattern = normalize(pattern);
+            if pattern.is_empty() {
+                return Err(FileCheckError::EmptyPattern {
+                    directive,
+                    check_line: n + 1,
+                })
+            }
+            parsed.push(CheckLine {
+                directive,
+                pattern,
+                line: n + 1,
+            });
+        }
+    }
+    if parsed.is_empty() {
+        return Err(FileCheckError::NoDirectives)
+    }
+    Ok(parsed)
+}
+
+/// Matches the input, e.g. printed Runwell IR, against the check patterns.
+///
+/// # Errors
+///
+/// If the input does not match the check patterns or if the check file is malformed.
+pub fn filecheck(input: &str, checks: &str) -> Result<(), FileCheckError> {
+    let checks = parse_checks(checks)?;
+    let lines = input.lines().map(normalize).collect::<Vec<_>>();
+    // The line and column right after the previous match.
+    let mut line = 0;
+    let mut column = 0;
+    // The pending CHECK-NOT patterns that must not match before the next match.
+    let mut pending_nots = Vec::<&CheckLine>::new();
+    let no_match = |check: &CheckLine| {
+        FileCheckError::NoMatch {
+            directive: check.directive,
+            pattern: check.pattern.clone(),
+            check_line: check.line,
+        }
+    };
+    for check in &checks {
+        let position = match check.directive {
+            Directive::CheckNot => {
+                pending_nots.push(check);
+                continue
+            }
+            Directive::CheckNext | Directive::CheckSame
+                if !pending_nots.is_empty() =>
+            {
+                return Err(FileCheckError::InvalidDirectiveAfterNot {
+                    directive: check.directive,
+                    check_line: check.line,
+                })
+            }
+            Directive::Check => {
+                // Continue on the line of the previous match, after the matched text.
+                let rest = lines.get(line).map(|text| {
+                    text.get(column..)
+                        .and_then(|rest| rest.find(&check.pattern))
+                        .map(|pos| (line, column + pos))
+                });
+                rest.flatten().or_else(|| {
+                    lines
+                        .iter()
+                        .enumerate()
+                        .skip(line + 1)
+                        .find_map(|(n, text)| {
+                            text.find(&check.pattern).map(|pos| (n, pos))
+                        })
+                })
+            }
+            Directive::CheckNext => {
+                let next = line + 1;
+                lines
+                    .get(next)
+                    .and_then(|text| text.find(&check.pattern))
+                    .map(|pos| (next, pos))
+            }
+            Directive::CheckSame => {
+                lines
+                    .get(line)
+                    .and_then(|text| text.get(column..))
+                    .and_then(|rest| rest.find(&check.pattern))
+                    .map(|pos| (line, column + pos))
+            }
+        };
+        let (match_line, match_column) =
+            position.ok_or_else(|| no_match(check))?;
+        check_nots(&pending_nots, &lines, (line, column), match_line)?;
+        pending_nots.clear();
+        line = match_line;
+        column = match_column + check.pattern.len();
+    }
+    check_nots(&pending_nots, &lines, (line, column), lines.len())?;
+    Ok(())
+}
+
+/// Returns an error if any of the CHECK-NOT patterns occurs in the input lines
+/// starting at `start` up to but excluding line `end`.
+fn check_nots(
+    nots: &[&CheckLine],
+    lines: &[String],
+    start: (usize, usize),
+    end: usize,
+) -> Result<(), FileCheckError> {
+    let (start_line, start_column) = start;
+    for (n, text) in lines.iter().enumerate().take(end).skip(start_line) {
+        let text = if n == start_line {
+            text.get(start_column..).unwrap_or("")
+        } else {
+            text.as_str()
+        };
+        if let Some(check) = nots.iter().find(|not| text.contains(&not.pattern))
+        {
+            return Err(FileCheckError::ForbiddenMatch {
+                pattern: check.pattern.clone(),
+                check_line: check.line,
+                input_line: n + 1,
+                line: lines[n].clone(),
+            })
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "\
+fn func0(v0: i32) -> i32 {
+    block {
+        let v1: i32 = const<i32> 1
+        let v2: i32 =   iadd<i32> v0 v1
+        return v2
+    }
+}
+";
+
+    #[test]
+    fn matching_checks_work() {
+        assert_eq!(
+            filecheck(
+                INPUT,
+                "
+                // CHECK: fn func0
+                // CHECK-SAME: -> i32
+                // CHECK: const<i32> 1
+                // CHECK-NEXT: let v2: i32 = iadd<i32>
+                // CHECK-NOT: imul
+                // CHECK: return
+                // CHECK-NOT: block
+                "
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn mismatching_checks_fail() {
+        assert_eq!(
+            filecheck(INPUT, "CHECK: imul"),
+            Err(FileCheckError::NoMatch {
+                directive: Directive::Check,
+                pattern: "imul".to_string(),
+                check_line: 1,
+            })
+        );
+        assert_eq!(
+            filecheck(INPUT, "CHECK: const\nCHECK-NEXT: return"),
+            Err(FileCheckError::NoMatch {
+                directive: Directive::CheckNext,
+                pattern: "return".to_string(),
+                check_line: 2,
+            })
+        );
+        assert_eq!(
+            filecheck(INPUT, "CHECK: iadd\nCHECK: const"),
+            Err(FileCheckError::NoMatch {
+                directive: Directive::Check,
+                pattern: "const".to_string(),
+                check_line: 2,
+            })
+        );
+        assert_eq!(
+            filecheck(INPUT, "CHECK: block\nCHECK-NOT: iadd\nCHECK: return"),
+            Err(FileCheckError::ForbiddenMatch {
+                pattern: "iadd".to_string(),
+                check_line: 2,
+                input_line: 4,
+                line: "let v2: i32 = iadd<i32> v0 v1".to_string(),
+            })
+        );
+        assert_eq!(
+            filecheck(INPUT, "no directives"),
+            Err(FileCheckError::NoDirectives)
+        );
+    }
+}
@@ -22,14 +22,16 @@
 //! its predecessors as all predecessors are known.
 
 use super::{
+    dominators::DominatorTree,
     incomplete_phi::IncompletePhi,
     instruction::{Instr, InstructionBuilder},
     variable::Variable,
     FunctionBody,
     FunctionBuilderError,
+    SourceSpan,
     VariableTranslator,
 };
-use crate::{Error, ModuleResources};
+use crate::{primitive::FunctionType, Error, ModuleResources};
 use core::{
     fmt::Debug,
     hash::Hash,
@@ -47,7 +49,7 @@ use entity::{
     RawIdx,
 };
 use ir::{
-    instr::{Instruction, PhiInstr},
+    instr::{Instruction, TerminalInstr, TrapCode},
     primitive::{Block, BlockEntity, Func, Type, Value, ValueEntity},
     VisitValuesMut,
 };
@@ -60,7 +62,79 @@ type HashSet<T> = std::collections::HashSet<T, ahash::RandomState>;
 impl FunctionBody {
     /// Creates a function builder to incrementally construct the function.
     pub fn build(func: Func, res: &ModuleResources) -> FunctionBuilder {
-        let mut ctx = Default::default();
+        Self::build_with(func, res, Default::default(), None)
+    }
+
+    /// Creates a function builder for the function with the given signature.
+    ///
+    /// Callers that already hold the signature of `func`, e.g. a parsed Wasm
+    /// function type, get it checked against the declared function type.
+    ///
+    /// # Errors
+    ///
+    /// If `func` is not a function with the given signature.
+    pub fn with_signature<'a>(
+        func: Func,
+        res: &'a ModuleResources,
+        signature: &FunctionType,
+    ) -> Result<FunctionBuilder<'a>, Error> {
+        Self::check_signature(func, res, signature)?;
+        Ok(Self::build(func, res))
+    }
+
+    /// Creates a function builder for the function with the given signature
+    /// that reuses the memory of the arena.
+    ///
+    /// # Errors
+    ///
+    /// If `func` is not a function with the given signature.
+    pub fn with_signature_in<'a>(
+        func: Func,
+        res: &'a ModuleResources,
+        signature: &FunctionType,
+        arena: &'a mut IrArena,
+    ) -> Result<FunctionBuilder<'a>, Error> {
+        Self::check_signature(func, res, signature)?;
+        Ok(Self::build_in(func, res, arena))
+    }
+
+    /// Checks that `func` is a function with the given signature.
+    fn check_signature(
+        func: Func,
+        res: &ModuleResources,
+        signature: &FunctionType,
+    ) -> Result<(), Error> {
+        if res.get_func_type(func) != Some(signature) {
+            return Err(FunctionBuilderError::UnmatchingFunctionSignature {
+                func,
+                signature: signature.clone(),
+            }
+            .into())
+        }
+        Ok(())
+    }
+
+    /// Creates a function builder that reuses the memory of the arena.
+    ///
+    /// The construction state of the function builder is returned to the
+    /// arena upon successful finalization so that subsequent constructions
+    /// can reuse its memory.
+    pub fn build_in<'a>(
+        func: Func,
+        res: &'a ModuleResources,
+        arena: &'a mut IrArena,
+    ) -> FunctionBuilder<'a> {
+        let ctx = arena.ctx.take().unwrap_or_default();
+        Self::build_with(func, res, ctx, Some(arena))
+    }
+
+    /// Creates a function builder operating on the given construction state.
+    fn build_with<'a>(
+        func: Func,
+        res: &'a ModuleResources,
+        mut ctx: FunctionBuilderContext,
+        arena: Option<&'a mut IrArena>,
+    ) -> FunctionBuilder<'a> {
         let func_type = res.get_func_type(func).unwrap_or_else(|| {
             panic!(
                 "tried to build function {} that is missing from module resources",
@@ -73,10 +147,47 @@ impl FunctionBody {
             func,
             res,
             state: FunctionBuilderState::LocalVariables,
+            arena,
         }
     }
 }
 
+/// Reusable memory for the construction of many function bodies.
+///
+/// Constructing a function body allocates lots of intermediate data that is
+/// dropped upon finalization. Function builders created via
+/// [`FunctionBody::build_in`] instead return this data to the arena which
+/// clears it but retains its memory for the next construction. All retained
+/// memory is freed at once when the arena is dropped or released.
+#[derive(Debug, Default)]
+pub struct IrArena {
+    /// The cleared construction state of the last finalized function body.
+    ctx: Option<FunctionBuilderContext>,
+}
+
+impl IrArena {
+    /// Creates a new empty arena.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the arena retains memory of a previous construction.
+    pub fn is_retaining(&self) -> bool {
+        self.ctx.is_some()
+    }
+
+    /// Frees all memory retained by the arena.
+    pub fn release(&mut self) {
+        self.ctx = None;
+    }
+
+    /// Clears and retains the construction state for later reuse.
+    fn recycle(&mut self, mut ctx: FunctionBuilderContext) {
+        ctx.clear();
+        self.ctx = Some(ctx);
+    }
+}
+
 /// Incrementally guides the construction process to build a Runwell IR function.
 #[derive(Debug)]
 pub struct FunctionBuilder<'a> {
@@ -84,6 +195,8 @@ pub struct FunctionBuilder<'a> {
     pub(super) func: Func,
     pub(super) res: &'a ModuleResources,
     state: FunctionBuilderState,
+    /// The arena to return the construction state to upon finalization if any.
+    arena: Option<&'a mut IrArena>,
 }
 
 /// The context that is built during IR function construction.
@@ -101,8 +214,10 @@ pub struct FunctionBuilderContext {
     pub block_preds: DefaultComponentVec<Block, HashSet<Block>>,
     /// The phi functions of every basic block.
     ///
-    /// Every basic block can have up to one phi instruction per variable in use.
-    pub block_phis: DefaultComponentMap<Block, ComponentMap<Variable, Instr>>,
+    /// Every basic block can have up to one phi per variable in use.
+    /// Upon finalization the remaining phis of a basic block become its
+    /// parameters and the branches to it pass the phi operands as arguments.
+    pub block_phis: DefaultComponentMap<Block, ComponentMap<Variable, Value>>,
     /// Is `true` if block is sealed.
     ///
     /// A sealed block knows all its predecessors.
@@ -131,11 +246,15 @@ pub struct FunctionBuilderContext {
     /// For example `store` is not in pure SSA form and therefore
     /// has no SSA value association.
     pub instr_values: DefaultComponentMap<Instr, SmallVec<[Value; 4]>>,
+    /// The source spans of all instructions constructed while a source span was set.
+    pub instr_spans: ComponentMap<Instr, SourceSpan>,
+    /// The source span attached to all subsequently constructed instructions if any.
+    pub source_span: Option<SourceSpan>,
     /// The incomplete phi instruction in case the value represents one.
     ///
     /// Incomplete phis are use throughout the function body construction
-    /// instead of actually creating phi instructions. Phi instructions are
-    /// first created upon finalization of the function body.
+    /// instead of actually creating basic block parameters. Those are first
+    /// created upon finalization of the function body.
     ///
     /// The construction algorithm works solely on incomplete phis, even
     /// though a phi instruction might be deemed complete during function
@@ -148,16 +267,20 @@ pub struct FunctionBuilderContext {
     /// Every SSA value has an association to either an IR instruction
     /// or to an input parameter of the IR function under construction.
     pub value_assoc: ComponentVec<Value, ValueAssoc>,
-    /// Stores all users of all values.
+    /// Stores all instruction users of all values.
     ///
     /// This information is required to replace an unnecessary phi
     /// phi instruction with its single operand. Users of the unnecessary
-    /// phi instruction are updated accordingly and phi instruction
-    /// users are checked for triviality.
+    /// phi instruction are updated accordingly.
     ///
     /// Also this information can be used for optimizations when replacing
     /// one instruction with another.
     pub value_users: DefaultComponentMap<Value, HashSet<Instr>>,
+    /// Stores all phi users of all values.
+    ///
+    /// Phi users of an unnecessary phi instruction are updated upon its
+    /// replacement and checked for triviality.
+    pub value_phi_users: DefaultComponentMap<Value, HashSet<Value>>,
     /// The current basic block that is being operated on.
     pub current: Block,
     /// The variable translator.
@@ -167,6 +290,33 @@ pub struct FunctionBuilderContext {
     pub vars: VariableTranslator,
 }
 
+impl FunctionBuilderContext {
+    /// Clears the context for reusing its memory.
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.values.clear();
+        self.instrs.clear();
+        self.block_preds.clear();
+        self.block_phis.clear();
+        self.block_sealed.clear();
+        self.block_filled.clear();
+        self.block_instrs.clear();
+        self.block_incomplete_phis.clear();
+        self.phi_block.clear();
+        self.phi_var.clear();
+        self.instr_values.clear();
+        self.instr_spans.clear();
+        self.source_span = None;
+        self.value_incomplete_phi.clear();
+        self.value_type.clear();
+        self.value_assoc.clear();
+        self.value_users.clear();
+        self.value_phi_users.clear();
+        self.current = Block::from_raw(RawIdx::from_u32(0));
+        self.vars.clear();
+    }
+}
+
 impl Default for FunctionBuilderContext {
     fn default() -> Self {
         Self {
@@ -182,10 +332,13 @@ impl Default for FunctionBuilderContext {
             phi_block: Default::default(),
             phi_var: Default::default(),
             instr_values: Default::default(),
+            instr_spans: Default::default(),
+            source_span: None,
             value_incomplete_phi: Default::default(),
             value_type: Default::default(),
             value_assoc: Default::default(),
             value_users: Default::default(),
+            value_phi_users: Default::default(),
             current: Block::from_raw(RawIdx::from_u32(0)),
             vars: Default::default(),
         }
@@ -194,14 +347,22 @@ impl Default for FunctionBuilderContext {
 
 /// The association of the SSA value.
 ///
-/// Every SSA value has an association to either an IR instruction
-/// or to an input parameter of the IR function under construction.
+/// Every SSA value has an association to either an IR instruction,
+/// to a basic block parameter or to an input parameter of the IR function
+/// under construction.
 #[derive(Debug, Copy, Clone)]
 pub enum ValueAssoc {
     /// The value is associated to the nth input of the function.
     Input(u32),
     /// The value is associated to the nth output of the instruction.
     Instr(Instr, u32),
+    /// The value is associated to the nth parameter of the basic block.
+    ///
+    /// # Note
+    ///
+    /// During function body construction the index is not yet determined
+    /// since phis might still be removed. It is assigned upon finalization.
+    Param(Block, u32),
 }
 
 impl ValueAssoc {
@@ -314,6 +475,66 @@ impl<'a> FunctionBuilder<'a> {
         Ok(self.ctx.current)
     }
 
+    /// Returns the number of instructions constructed so far.
+    ///
+    /// # Note
+    ///
+    /// This includes instructions that might be removed again upon
+    /// finalization, e.g. instructions of unreachable basic blocks.
+    pub fn len_instrs(&self) -> usize {
+        self.ctx.instrs.len()
+    }
+
+    /// Returns the number of SSA values created so far.
+    ///
+    /// # Note
+    ///
+    /// This includes the basic block parameters created while resolving
+    /// variables into SSA form.
+    pub fn len_values(&self) -> usize {
+        self.ctx.values.len()
+    }
+
+    /// Computes the dominator tree of the basic blocks constructed so far.
+    ///
+    /// # Note
+    ///
+    /// The dominator tree is computed from the known predecessors of all
+    /// basic blocks. It might change as further branches are constructed.
+    ///
+    /// # Errors
+    ///
+    /// If no basic blocks exist.
+    pub fn dominator_tree(&mut self) -> Result<DominatorTree, Error> {
+        self.ensure_construction_in_order(FunctionBuilderState::Body)?;
+        let mut successors =
+            <DefaultComponentMap<Block, Vec<Block>>>::default();
+        for block in self.ctx.blocks.indices() {
+            for &pred in &self.ctx.block_preds[block] {
+                successors[pred].push(block);
+            }
+        }
+        let entry = Block::from_raw(RawIdx::from_u32(0));
+        let successors = &successors;
+        Ok(DominatorTree::new(entry, move |block| {
+            successors[block].iter().copied()
+        }))
+    }
+
+    /// Sets the source span attached to all subsequently constructed instructions.
+    ///
+    /// Translators of source languages use this to remember for every
+    /// constructed instruction where it originates from in the source input.
+    /// Setting `None` stops attaching source spans to new instructions.
+    pub fn set_source_span(&mut self, span: Option<SourceSpan>) {
+        self.ctx.source_span = span;
+    }
+
+    /// Returns the source span attached to newly constructed instructions if any.
+    pub fn source_span(&self) -> Option<SourceSpan> {
+        self.ctx.source_span
+    }
+
     /// Switches the current block to the given basic block.
     ///
     /// # Errors
@@ -353,6 +574,30 @@ impl<'a> FunctionBuilder<'a> {
         Ok(())
     }
 
+    /// Seals all basic blocks that have not yet been sealed.
+    ///
+    /// Returns the basic blocks that have been sealed by this call in the
+    /// order in which they were sealed.
+    ///
+    /// # Note
+    ///
+    /// This should only be used once no further branches to any of the
+    /// unsealed basic blocks are going to be constructed since sealing a
+    /// basic block fixes its set of predecessors.
+    pub fn seal_all_blocks(&mut self) -> Result<Vec<Block>, Error> {
+        self.ensure_construction_in_order(FunctionBuilderState::Body)?;
+        let unsealed = self
+            .ctx
+            .blocks
+            .indices()
+            .filter(|&block| !self.ctx.block_sealed.get(block))
+            .collect::<Vec<_>>();
+        for &block in &unsealed {
+            self.seal_block(block)?;
+        }
+        Ok(unsealed)
+    }
+
     /// Returns an instruction builder to appends instructions to the current basic block.
     ///
     /// # Errors
@@ -392,27 +637,28 @@ impl<'a> FunctionBuilder<'a> {
     }
 
     /// Creates a new phi instruction.
+    ///
+    /// The phi is represented by the value of the basic block parameter
+    /// it is going to become upon finalization.
     fn create_phi_instruction(
         &mut self,
         var: Variable,
         var_type: Type,
         block: Block,
     ) -> Result<Value, Error> {
-        let instr = self.ctx.instrs.alloc(PhiInstr::default().into());
         let value = self.ctx.values.alloc_some(1);
         self.ctx
             .value_incomplete_phi
             .insert(value, Default::default());
         self.ctx
             .value_assoc
-            .insert(value, ValueAssoc::Instr(instr, 0));
+            .insert(value, ValueAssoc::Param(block, 0));
         self.ctx.value_type.insert(value, var_type);
         self.ctx.phi_var.insert(value, var);
         self.ctx.phi_block.insert(value, block);
-        self.ctx.block_phis[block].insert(var, instr);
+        self.ctx.block_phis[block].insert(var, value);
         self.ctx.block_incomplete_phis[block].insert(var, value);
         self.ctx.vars.write_var(var, value, block, var_type)?;
-        self.ctx.instr_values[instr].push(value);
         Ok(value)
     }
 
@@ -469,8 +715,7 @@ impl<'a> FunctionBuilder<'a> {
             let value = self.read_var_in_block(var, pred)?;
             let incomplete_phi = &mut self.ctx.value_incomplete_phi[phi];
             incomplete_phi.append_operand(pred, value);
-            let phi_instr = self.phi_value_to_instr(phi);
-            self.ctx.value_users[value].insert(phi_instr);
+            self.ctx.value_phi_users[value].insert(phi);
         }
         self.try_remove_trivial_phi(phi)
     }
@@ -502,23 +747,21 @@ impl<'a> FunctionBuilder<'a> {
         //
         // Remove phi from its own users in case it was using itself.
         let same = equivalent_value;
-        let phi_instr = self.phi_value_to_instr(phi_value);
-        self.ctx.value_users[phi_value].remove(&phi_instr);
+        self.ctx.value_phi_users[phi_value].remove(&phi_value);
         let users = take(&mut self.ctx.value_users[phi_value]);
+        let phi_users = take(&mut self.ctx.value_phi_users[phi_value]);
         let phi_block = self.ctx.phi_block[phi_value];
         let phi_var = self.ctx.phi_var[phi_value];
-        let phi_value = self.phi_instr_to_value(phi_instr);
-        let phi_type = self.ctx.value_type[phi_value];
         self.ctx.block_phis[phi_block].remove(phi_var);
-        self.ctx
-            .vars
-            .replace_var(phi_var, phi_block, phi_value, same, phi_type)?;
+        self.ctx.vars.replace_value(phi_value, same);
         for user in users {
-            let got_replaced = self.replace_user_values(user, phi_value, same);
-            if got_replaced && self.ctx.instrs[user].is_phi() {
-                // If the user was an incomplete phi and there was an actual replacement
-                // we have to check if the phi is now trivial as well.
-                self.try_remove_trivial_phi(phi_value)?;
+            self.replace_user_values(user, phi_value, same);
+        }
+        for user in phi_users {
+            if self.replace_phi_user_values(user, phi_value, same) {
+                // If there was an actual replacement we have to check
+                // if the phi user is now trivial as well.
+                self.try_remove_trivial_phi(user)?;
             }
         }
         Ok(same)
@@ -536,28 +779,15 @@ impl<'a> FunctionBuilder<'a> {
         replace_value: Value,
         with_value: Value,
     ) -> bool {
-        let user_instr = &mut self.ctx.instrs[user];
-        let got_replaced = match user_instr.is_phi() {
-            false => {
-                // Returns `true` if a value actually got replaced.
-                let mut replaced = false;
-                user_instr.visit_values_mut(|value| {
-                    if *value == replace_value {
-                        *value = with_value;
-                        replaced = true;
-                    }
-                    true
-                });
-                replaced
+        // Returns `true` if a value actually got replaced.
+        let mut got_replaced = false;
+        self.ctx.instrs[user].visit_values_mut(|value| {
+            if *value == replace_value {
+                *value = with_value;
+                got_replaced = true;
             }
-            true => {
-                // Due to incomplete phi instruction we need to treat them differently.
-                let phi_value = self.phi_instr_to_value(user);
-                let inc_phi = &mut self.ctx.value_incomplete_phi[phi_value];
-                // Returns `true` if a value actually got replaced.
-                inc_phi.replace_value(replace_value, with_value)
-            }
-        };
+            true
+        });
         if got_replaced {
             // Register the instruction as user if there was an actual replacement.
             self.ctx.value_users[with_value].insert(user);
@@ -565,40 +795,25 @@ impl<'a> FunctionBuilder<'a> {
         got_replaced
     }
 
-    /// Returns the associated value of the phi instruction.
-    ///
-    /// # Panics
-    ///
-    /// If the given `Instr` is not a phi instruction.
-    fn phi_instr_to_value(&self, phi_instr: Instr) -> Value {
-        assert!(self.ctx.instrs[phi_instr].is_phi());
-        assert_eq!(
-            self.ctx.instr_values[phi_instr].len(),
-            1,
-            "phi instructions must have exactly one output value"
-        );
-        self.ctx.instr_values[phi_instr][0]
-    }
-
-    /// Returns the associated `Instr` for the phi value.
+    /// Replaces occurrences of `replace_value` with `with_value` for the given phi user.
     ///
-    /// # Panics
+    /// # Note
     ///
-    /// If the given phi value is not associated to a phi instruction.
-    fn phi_value_to_instr(&self, phi_value: Value) -> Instr {
-        match self.ctx.value_assoc[phi_value] {
-            ValueAssoc::Instr(instr, 0) => {
-                assert!(self.ctx.instrs[instr].is_phi());
-                instr
-            }
-            ValueAssoc::Instr(instr, n) => {
-                panic!(
-                    "found {} to be the {}-th output of a phi instruction {} which is illegal",
-                    phi_value, n, instr,
-                )
-            }
-            _ => panic!("unexpected non-instruction value"),
+    /// - This also updates phi users on the fly if replacements took place.
+    /// - Returns `true` if an actual replacement took place.
+    fn replace_phi_user_values(
+        &mut self,
+        user: Value,
+        replace_value: Value,
+        with_value: Value,
+    ) -> bool {
+        let got_replaced = self.ctx.value_incomplete_phi[user]
+            .replace_value(replace_value, with_value);
+        if got_replaced {
+            // Register the phi as user if there was an actual replacement.
+            self.ctx.value_phi_users[with_value].insert(user);
         }
+        got_replaced
     }
 
     /// Reads the last assigned value of the variable within the scope of the current basic block.
@@ -612,6 +827,29 @@ impl<'a> FunctionBuilder<'a> {
         self.read_var_in_block(var, current)
     }
 
+    /// Returns the variables that are associated to the function inputs.
+    ///
+    /// # Note
+    ///
+    /// The input variables are automatically declared upon construction of the
+    /// function builder from the function signature and always precede all
+    /// other local variables declared via [`FunctionBuilder::declare_variables`].
+    pub fn input_vars(
+        &self,
+    ) -> impl ExactSizeIterator<Item = Variable> + DoubleEndedIterator {
+        let len_inputs = self
+            .res
+            .get_func_type(self.func)
+            .map(|func_type| func_type.inputs().len())
+            .unwrap_or_default() as u32;
+        (0..len_inputs).map(|n| Variable::from_raw(RawIdx::from_u32(n)))
+    }
+
+    /// Returns the variable associated to the nth function input if any.
+    pub fn input_var(&self, n: u32) -> Option<Variable> {
+        self.input_vars().nth(n as usize)
+    }
+
     /// Returns the type of the variable.
     ///
     /// # Errors
@@ -639,25 +877,78 @@ impl<'a> FunctionBuilder<'a> {
     ///
     /// # Errors
     ///
-    /// If not all basic blocks in the function are sealed and filled.
+    /// - If not all basic blocks in the function are sealed and filled.
+    /// - If the built function violates the invariants checked by
+    ///   [`FunctionBody::verify`].
     pub fn finalize(mut self) -> Result<FunctionBody, Error> {
         self.ensure_construction_in_order(FunctionBuilderState::Body)?;
         self.ensure_all_blocks_sealed()?;
         self.ensure_all_blocks_filled()?;
+        self.finalize_verified()
+    }
+
+    /// Finalizes construction of the built function sealing all unsealed blocks.
+    ///
+    /// Once all basic blocks are filled no further branches can be added and
+    /// therefore the predecessors of all basic blocks are known. Unlike
+    /// [`FunctionBuilder::finalize`] this seals all remaining unsealed blocks
+    /// instead of returning an error.
+    ///
+    /// Returns the built function and the basic blocks that had to be sealed.
+    ///
+    /// # Errors
+    ///
+    /// - If not all basic blocks in the function are filled.
+    /// - If the built function violates the invariants checked by
+    ///   [`FunctionBody::verify`].
+    pub fn finalize_auto_sealed(
+        mut self,
+    ) -> Result<(FunctionBody, Vec<Block>), Error> {
+        self.ensure_construction_in_order(FunctionBuilderState::Body)?;
+        self.ensure_all_blocks_filled()?;
+        let force_sealed = self.seal_all_blocks()?;
+        Ok((self.finalize_verified()?, force_sealed))
+    }
+
+    /// Builds the function body and verifies its invariants.
+    fn finalize_verified(self) -> Result<FunctionBody, Error> {
+        let func = self.func;
+        let res = self.res;
+        let body = self.finalize_unchecked();
+        if let Err(violations) = body.verify(func, res) {
+            return Err(
+                FunctionBuilderError::InvalidFunctionBody { func, violations }
+                    .into(),
+            )
+        }
+        Ok(body)
+    }
 
+    /// Builds the function body assuming all blocks are sealed and filled.
+    fn finalize_unchecked(mut self) -> FunctionBody {
         let mut body = FunctionBody {
             blocks: Default::default(),
+            block_params: Default::default(),
             block_instrs: Default::default(),
             values: Default::default(),
             value_type: Default::default(),
             value_assoc: Default::default(),
             instrs: Default::default(),
             instr_values: Default::default(),
+            instr_spans: Default::default(),
+            var_type: Default::default(),
+            var_defs: Default::default(),
+            removed_blocks: Default::default(),
         };
         let (replace_values, incomplete_phis) =
             self.initialize_values(&mut body);
-        self.initialize_instrs(&replace_values, incomplete_phis, &mut body);
-        Ok(body)
+        self.initialize_instrs(&replace_values, &mut body);
+        self.initialize_params(&replace_values, incomplete_phis, &mut body);
+        self.initialize_vars(&replace_values, &mut body);
+        if let Some(arena) = self.arena.take() {
+            arena.recycle(take(&mut self.ctx));
+        }
+        body
     }
 
     /// Ensures that all basic blocks are sealed and returns an `Error` if not.
@@ -710,7 +1001,7 @@ impl<'a> FunctionBuilder<'a> {
     ///
     /// Returns a mapping that stores all the SSA value replacements for all alive SSA values.
     /// Also returns all incomplete phi instructions with their values updated.
-    /// These two return values are going to be used in the `initialize_instrs` procedure.
+    /// These two return values are going to be used in the `initialize_params` procedure.
     fn initialize_values(
         &mut self,
         body: &mut FunctionBody,
@@ -721,6 +1012,7 @@ impl<'a> FunctionBuilder<'a> {
         // Replace all values and update references for all their associated data.
         for old_value in self.ctx.values.indices() {
             if !self.ctx.value_users[old_value].is_empty()
+                || !self.ctx.value_phi_users[old_value].is_empty()
                 || self.ctx.value_assoc[old_value].is_input()
             {
                 let new_value = body.values.alloc_some(1);
@@ -758,20 +1050,12 @@ impl<'a> FunctionBuilder<'a> {
     ///
     /// - This information can later be used to remove dead instructions from the function body.
     /// - An instruction is said to be alive if it is used at least once in any of the basic blocks.
-    /// - Since phi instructions are not part of the block instructions during function body construction
-    ///   we need to treat them specially.
     fn get_alive_instrs(&self) -> DefaultComponentBitVec<Instr> {
         let mut is_alive = <DefaultComponentBitVec<Instr>>::default();
         for block in self.ctx.blocks.indices() {
             for instr in self.ctx.block_instrs[block].iter().copied() {
                 is_alive.set(instr, true);
             }
-            for (_phi_var, &phi_instr) in &self.ctx.block_phis[block] {
-                let phi_value = self.phi_instr_to_value(phi_instr);
-                if !self.ctx.value_users[phi_value].is_empty() {
-                    is_alive.set(phi_instr, true);
-                }
-            }
         }
         is_alive
     }
@@ -784,7 +1068,6 @@ impl<'a> FunctionBuilder<'a> {
     fn initialize_instrs(
         &mut self,
         value_replace: &Replacer<Value>,
-        value_incomplete_phi: ComponentMap<Value, IncompletePhi>,
         body: &mut FunctionBody,
     ) {
         let is_instr_alive = self.get_alive_instrs();
@@ -798,12 +1081,15 @@ impl<'a> FunctionBuilder<'a> {
                     *old_value = new_value;
                     true
                 });
-                // Swap out updated instruction with a placeholder phi instruction.
+                // Swap out updated instruction with a placeholder trap instruction.
                 // The old instructions will be dropped so whatever we put in there does not matter.
-                let instruction =
-                    replace(instruction, Instruction::Phi(PhiInstr::default()));
+                let placeholder = TerminalInstr::Trap(TrapCode::Unreachable);
+                let instruction = replace(instruction, placeholder.into());
                 let new_instr = body.instrs.alloc(instruction);
                 instr_replace.insert(old_instr, new_instr);
+                if let Some(span) = self.ctx.instr_spans.get(old_instr) {
+                    body.instr_spans.insert(new_instr, *span);
+                }
                 // Replace all values associated to the output of all instructions.
                 for old_value in &self.ctx.instr_values[old_instr] {
                     let maybe_new_value = value_replace.try_get(*old_value);
@@ -814,19 +1100,6 @@ impl<'a> FunctionBuilder<'a> {
         // Simply copy over the same basic block structure.
         // We do not yet eliminate trivial or dead basic blocks.
         body.blocks = self.ctx.blocks.clone();
-        // Convert all incomplete phis into complete phis and add them to the
-        // start of each of their associated basic blocks.
-        for block in self.ctx.blocks.indices() {
-            for &phi_instr in self.ctx.block_phis[block].components() {
-                let phi_value = self.phi_instr_to_value(phi_instr);
-                let incomplete_phi = &value_incomplete_phi[phi_value];
-                let _ = replace(
-                    &mut body.instrs[phi_instr],
-                    PhiInstr::new(incomplete_phi.operands()).into(),
-                );
-                body.block_instrs[block].push(phi_instr);
-            }
-        }
         // Replace instruction references of block instructions.
         for block in self.ctx.blocks.indices() {
             for old_instr in self.ctx.block_instrs[block].iter().copied() {
@@ -844,6 +1117,88 @@ impl<'a> FunctionBuilder<'a> {
             }
         }
     }
+
+    /// Initializes the basic block parameters of the finalized constructed function body.
+    ///
+    /// Converts all alive incomplete phis into parameters of their basic blocks
+    /// and makes all branches to those basic blocks pass the phi operands for
+    /// the branching basic block as arguments.
+    ///
+    /// Dead phis have been eliminated by the `initialize_values` procedure.
+    fn initialize_params(
+        &self,
+        value_replace: &Replacer<Value>,
+        value_incomplete_phi: ComponentMap<Value, IncompletePhi>,
+        body: &mut FunctionBody,
+    ) {
+        for block in self.ctx.blocks.indices() {
+            // Order the parameters by their variables for determinism.
+            let mut phis = self.ctx.block_phis[block]
+                .iter()
+                .map(|(var, &value)| (var, value))
+                .collect::<Vec<_>>();
+            phis.sort_by_key(|(var, _)| *var);
+            for (_, old_value) in phis {
+                if let Some(new_value) = value_replace.try_get(old_value) {
+                    let n = body.block_params[block].len() as u32;
+                    body.block_params[block].push(new_value);
+                    body.value_assoc[new_value] = ValueAssoc::Param(block, n);
+                }
+            }
+        }
+        for block in self.ctx.blocks.indices() {
+            let terminal = match body.block_instrs[block].last() {
+                Some(&instr) => instr,
+                None => continue,
+            };
+            let FunctionBody {
+                instrs,
+                block_params,
+                ..
+            } = body;
+            let terminal = match &mut instrs[terminal] {
+                Instruction::Terminal(terminal) => terminal,
+                _ => continue,
+            };
+            for target in terminal.branch_targets_mut() {
+                let args = block_params[target.block()].iter().map(|param| {
+                    value_incomplete_phi[*param]
+                        .operand_for(block)
+                        .expect("encountered phi without operand for branch")
+                });
+                target.set_args(args);
+            }
+        }
+    }
+
+    /// Initializes the declared variables of the finalized constructed function body.
+    ///
+    /// Keeps the declared types of all variables as well as their final SSA value
+    /// definitions for every basic block. Definitions to SSA values that did not
+    /// survive finalization are dropped.
+    fn initialize_vars(
+        &self,
+        value_replace: &Replacer<Value>,
+        body: &mut FunctionBody,
+    ) {
+        for var in self.ctx.vars.vars() {
+            let (var_type, var_defs) = self
+                .ctx
+                .vars
+                .declaration(var)
+                .expect("encountered undeclared variable");
+            body.var_type.insert(var, var_type);
+            let mut block_defs = <ComponentMap<Block, Value>>::default();
+            if let Some(var_defs) = var_defs {
+                for (block, old_value) in var_defs.definitions().iter() {
+                    if let Some(new_value) = value_replace.try_get(old_value) {
+                        block_defs.insert(block, new_value);
+                    }
+                }
+            }
+            body.var_defs.insert(var, block_defs);
+        }
+    }
 }
 
 /// A replacement mapping.
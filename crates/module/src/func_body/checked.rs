@@ -0,0 +1,433 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checked integer arithmetic for function bodies.
+//!
+//! Some embedders require integer arithmetic to trap upon overflow
+//! regardless of the wrapping semantics of the source language. The rewrite
+//! checks integer additions, subtractions and multiplications for overflow
+//! and traps with [`TrapCode::IntegerOverflow`] if they overflow.
+
+use super::{FunctionBody, ValueAssoc};
+use crate::Error;
+use ir::{
+    instr::{
+        operands::{BinaryIntOp, CompareIntOp},
+        BinaryIntInstr,
+        CompareIntInstr,
+        ConstInstr,
+        IfThenElseInstr,
+        Instruction,
+        IntInstr,
+        SelectInstr,
+        TerminalInstr,
+        TrapCode,
+    },
+    primitive::{Block, Const, IntConst, IntType, Type, Value},
+};
+use std::collections::BTreeMap;
+
+/// The interpretation of integer operands for overflow checks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    /// The operands are interpreted as signed integers.
+    Signed,
+    /// The operands are interpreted as unsigned integers.
+    Unsigned,
+}
+
+/// Configures which integer arithmetic is checked for overflow.
+///
+/// By default no integer arithmetic is checked.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CheckedArithmetic {
+    /// The overflow checks per integer type.
+    checks: BTreeMap<IntType, Overflow>,
+}
+
+impl CheckedArithmetic {
+    /// Checks the arithmetic of the integer type for the given overflow.
+    pub fn set_check(&mut self, int_type: IntType, overflow: Overflow) {
+        self.checks.insert(int_type, overflow);
+    }
+
+    /// Returns the overflow checked for the integer type if any.
+    pub fn check(&self, int_type: IntType) -> Option<Overflow> {
+        self.checks.get(&int_type).copied()
+    }
+}
+
+impl FunctionBody {
+    /// Checks all configured integer additions, subtractions and
+    /// multiplications for overflow.
+    ///
+    /// Returns the number of checked instructions.
+    ///
+    /// # Note
+    ///
+    /// Every checked instruction ends its basic block with a conditional
+    /// branch to a shared basic block that traps with
+    /// [`TrapCode::IntegerOverflow`].
+    pub fn check_arithmetic(
+        &mut self,
+        checks: &CheckedArithmetic,
+    ) -> Result<usize, Error> {
+        let blocks = self.blocks().collect::<Vec<_>>();
+        let mut checker = Checker {
+            body: self,
+            checks,
+            trap_block: None,
+            len_checked: 0,
+        };
+        for block in blocks {
+            checker.check_block(block)?;
+        }
+        Ok(checker.len_checked)
+    }
+}
+
+/// Inserts overflow checks into a function body in place.
+#[derive(Debug)]
+struct Checker<'a> {
+    body: &'a mut FunctionBody,
+    checks: &'a CheckedArithmetic,
+    /// The basic block that traps upon overflow once it has been created.
+    trap_block: Option<Block>,
+    len_checked: usize,
+}
+
+/// The position before which new instructions are inserted.
+#[derive(Debug, Copy, Clone)]
+struct Cursor {
+    block: Block,
+    position: usize,
+}
+
+impl<'a> Checker<'a> {
+    /// Checks all integer arithmetic of the basic block.
+    ///
+    /// Checked instructions split the basic block so that the remaining
+    /// instructions are checked in the split off basic block.
+    fn check_block(&mut self, mut block: Block) -> Result<(), Error> {
+        let mut position = 0;
+        while position < self.body.block_instrs[block].len() {
+            let instr = self.body.block_instrs[block][position];
+            position += 1;
+            let instruction = match &self.body.instrs[instr] {
+                Instruction::Int(IntInstr::Binary(instruction))
+                    if matches!(
+                        instruction.op(),
+                        BinaryIntOp::Add | BinaryIntOp::Sub | BinaryIntOp::Mul
+                    ) =>
+                {
+                    instruction.clone()
+                }
+                _ => continue,
+            };
+            let overflow = match self.checks.check(instruction.ty()) {
+                Some(overflow) => overflow,
+                None => continue,
+            };
+            let result = self.body.instr_values[instr][0]
+                .expect("encountered integer instruction without result");
+            let mut cursor = Cursor { block, position };
+            let is_overflow = self.insert_is_overflow(
+                &mut cursor,
+                &instruction,
+                overflow,
+                result,
+            );
+            let next = self.body.block_instrs[block][cursor.position];
+            let continue_block = self.body.edit().split_block(next)?;
+            let trap_block = self.trap_block();
+            let br = self.body.block_instrs[block]
+                .last()
+                .copied()
+                .expect("encountered split basic block without terminal");
+            self.body.instrs[br] = TerminalInstr::Ite(IfThenElseInstr::new(
+                is_overflow,
+                trap_block,
+                continue_block,
+            ))
+            .into();
+            self.len_checked += 1;
+            block = continue_block;
+            position = 0;
+        }
+        Ok(())
+    }
+
+    /// Returns the basic block that traps upon overflow.
+    ///
+    /// Creates the basic block upon first use.
+    fn trap_block(&mut self) -> Block {
+        if let Some(block) = self.trap_block {
+            return block
+        }
+        let block = self.body.blocks.alloc_some(1);
+        let trap = TerminalInstr::Trap(TrapCode::IntegerOverflow);
+        let instr = self.body.instrs.alloc(trap.into());
+        self.body.block_instrs[block].push(instr);
+        self.trap_block = Some(block);
+        block
+    }
+
+    /// Inserts the instructions computing whether the instruction overflowed.
+    ///
+    /// Returns the boolean value that is `true` upon overflow.
+    fn insert_is_overflow(
+        &mut self,
+        cursor: &mut Cursor,
+        instruction: &BinaryIntInstr,
+        overflow: Overflow,
+        result: Value,
+    ) -> Value {
+        let ty = instruction.ty();
+        let lhs = instruction.lhs();
+        let rhs = instruction.rhs();
+        match (instruction.op(), overflow) {
+            (BinaryIntOp::Add, Overflow::Unsigned) => {
+                self.insert_icmp(cursor, CompareIntOp::Ult, ty, result, lhs)
+            }
+            (BinaryIntOp::Sub, Overflow::Unsigned) => {
+                self.insert_icmp(cursor, CompareIntOp::Ult, ty, lhs, rhs)
+            }
+            (BinaryIntOp::Add, Overflow::Signed) => {
+                // Overflows if both operands differ in sign from the result.
+                let lhs = self.insert_binary(
+                    cursor,
+                    BinaryIntOp::Xor,
+                    ty,
+                    result,
+                    lhs,
+                );
+                let rhs = self.insert_binary(
+                    cursor,
+                    BinaryIntOp::Xor,
+                    ty,
+                    result,
+                    rhs,
+                );
+                self.insert_is_negative(cursor, ty, lhs, rhs)
+            }
+            (BinaryIntOp::Sub, Overflow::Signed) => {
+                // Overflows if the operands have different signs and the
+                // result has a sign different from the left-hand side.
+                let operands =
+                    self.insert_binary(cursor, BinaryIntOp::Xor, ty, lhs, rhs);
+                let result = self.insert_binary(
+                    cursor,
+                    BinaryIntOp::Xor,
+                    ty,
+                    lhs,
+                    result,
+                );
+                self.insert_is_negative(cursor, ty, operands, result)
+            }
+            (BinaryIntOp::Mul, Overflow::Unsigned) => {
+                // Overflows if `lhs != 0 && result / lhs != rhs`.
+                let zero = self.insert_int(cursor, ty, 0);
+                let one = self.insert_int(cursor, ty, 1);
+                let lhs_is_zero =
+                    self.insert_icmp(cursor, CompareIntOp::Eq, ty, lhs, zero);
+                let divisor = self.insert_select(
+                    cursor,
+                    ty.into(),
+                    lhs_is_zero,
+                    one,
+                    lhs,
+                );
+                let quotient = self.insert_binary(
+                    cursor,
+                    BinaryIntOp::Udiv,
+                    ty,
+                    result,
+                    divisor,
+                );
+                let mismatch = self.insert_icmp(
+                    cursor,
+                    CompareIntOp::Ne,
+                    ty,
+                    quotient,
+                    rhs,
+                );
+                let no = self.insert_const(cursor, Const::Bool(false));
+                self.insert_select(
+                    cursor,
+                    Type::Bool,
+                    lhs_is_zero,
+                    no,
+                    mismatch,
+                )
+            }
+            (BinaryIntOp::Mul, Overflow::Signed) => {
+                // Overflows if `lhs == -1 && rhs == MIN` or otherwise if
+                // `lhs != 0 && result / lhs != rhs`. The divisor is adjusted
+                // so that the division itself never traps.
+                let zero = self.insert_int(cursor, ty, 0);
+                let one = self.insert_int(cursor, ty, 1);
+                let minus_one = self.insert_int(cursor, ty, u64::MAX);
+                let min =
+                    self.insert_int(cursor, ty, 1 << (ty.bit_width() - 1));
+                let lhs_is_zero =
+                    self.insert_icmp(cursor, CompareIntOp::Eq, ty, lhs, zero);
+                let lhs_is_minus_one = self.insert_icmp(
+                    cursor,
+                    CompareIntOp::Eq,
+                    ty,
+                    lhs,
+                    minus_one,
+                );
+                let divisor = self.insert_select(
+                    cursor,
+                    ty.into(),
+                    lhs_is_minus_one,
+                    one,
+                    lhs,
+                );
+                let divisor = self.insert_select(
+                    cursor,
+                    ty.into(),
+                    lhs_is_zero,
+                    one,
+                    divisor,
+                );
+                let quotient = self.insert_binary(
+                    cursor,
+                    BinaryIntOp::Sdiv,
+                    ty,
+                    result,
+                    divisor,
+                );
+                let mismatch = self.insert_icmp(
+                    cursor,
+                    CompareIntOp::Ne,
+                    ty,
+                    quotient,
+                    rhs,
+                );
+                let no = self.insert_const(cursor, Const::Bool(false));
+                let is_overflow = self.insert_select(
+                    cursor,
+                    Type::Bool,
+                    lhs_is_zero,
+                    no,
+                    mismatch,
+                );
+                let rhs_is_min =
+                    self.insert_icmp(cursor, CompareIntOp::Eq, ty, rhs, min);
+                self.insert_select(
+                    cursor,
+                    Type::Bool,
+                    lhs_is_minus_one,
+                    rhs_is_min,
+                    is_overflow,
+                )
+            }
+            _ => unreachable!("encountered unchecked integer operation"),
+        }
+    }
+
+    /// Inserts the instruction producing a single value of the given type.
+    fn insert(
+        &mut self,
+        cursor: &mut Cursor,
+        instruction: Instruction,
+        ty: Type,
+    ) -> Value {
+        let instr = self.body.instrs.alloc(instruction);
+        let value = self.body.values.alloc_some(1);
+        self.body.value_type.insert(value, ty);
+        self.body
+            .value_assoc
+            .insert(value, ValueAssoc::Instr(instr, 0));
+        self.body.instr_values[instr].push(Some(value));
+        self.body.block_instrs[cursor.block].insert(cursor.position, instr);
+        cursor.position += 1;
+        value
+    }
+
+    /// Inserts the constant value.
+    fn insert_const(&mut self, cursor: &mut Cursor, value: Const) -> Value {
+        self.insert(cursor, ConstInstr::new(value).into(), value.ty())
+    }
+
+    /// Inserts an integer constant of the given type.
+    fn insert_int(
+        &mut self,
+        cursor: &mut Cursor,
+        ty: IntType,
+        value: u64,
+    ) -> Value {
+        let value = match ty {
+            IntType::I8 => IntConst::I8(value as i8),
+            IntType::I16 => IntConst::I16(value as i16),
+            IntType::I32 => IntConst::I32(value as i32),
+            IntType::I64 => IntConst::I64(value as i64),
+        };
+        self.insert_const(cursor, value.into())
+    }
+
+    /// Inserts the binary integer instruction.
+    fn insert_binary(
+        &mut self,
+        cursor: &mut Cursor,
+        op: BinaryIntOp,
+        ty: IntType,
+        lhs: Value,
+        rhs: Value,
+    ) -> Value {
+        let instruction = BinaryIntInstr::new(op, ty, lhs, rhs);
+        self.insert(cursor, instruction.into(), ty.into())
+    }
+
+    /// Inserts the integer comparison.
+    fn insert_icmp(
+        &mut self,
+        cursor: &mut Cursor,
+        op: CompareIntOp,
+        ty: IntType,
+        lhs: Value,
+        rhs: Value,
+    ) -> Value {
+        let instruction = CompareIntInstr::new(op, ty, lhs, rhs);
+        self.insert(cursor, instruction.into(), Type::Bool)
+    }
+
+    /// Inserts the selection between both values of the given type.
+    fn insert_select(
+        &mut self,
+        cursor: &mut Cursor,
+        ty: Type,
+        condition: Value,
+        if_true: Value,
+        if_false: Value,
+    ) -> Value {
+        let instruction = SelectInstr::new(condition, ty, if_true, if_false);
+        self.insert(cursor, instruction.into(), ty)
+    }
+
+    /// Inserts the check whether the sign bit of `lhs & rhs` is set.
+    fn insert_is_negative(
+        &mut self,
+        cursor: &mut Cursor,
+        ty: IntType,
+        lhs: Value,
+        rhs: Value,
+    ) -> Value {
+        let both = self.insert_binary(cursor, BinaryIntOp::And, ty, lhs, rhs);
+        let zero = self.insert_int(cursor, ty, 0);
+        self.insert_icmp(cursor, CompareIntOp::Slt, ty, both, zero)
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Auditing of function bodies that handle secrets.
+//!
+//! Code handling secrets must not pass them to instructions whose execution
+//! time depends on their operand values since this leaks the secrets via
+//! timing. The check tracks secret values through the data flow of a
+//! function body and reports all secret operands of instructions that are
+//! not constant-time, e.g. conditional branches.
+
+use super::{FunctionBody, Instr};
+use entity::DefaultComponentBitVec;
+use ir::{
+    instr::{Instruction, TerminalInstr},
+    primitive::Value,
+    VisitValues,
+};
+
+/// A secret operand of an instruction that is not constant-time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SecretLeak {
+    /// The instruction that is not constant-time.
+    instr: Instr,
+    /// The secret operand of the instruction.
+    value: Value,
+}
+
+impl SecretLeak {
+    /// Returns the instruction that is not constant-time.
+    pub fn instr(&self) -> Instr {
+        self.instr
+    }
+
+    /// Returns the secret operand of the instruction.
+    pub fn value(&self) -> Value {
+        self.value
+    }
+}
+
+impl FunctionBody {
+    /// Returns all secret operands of instructions that are not constant-time.
+    ///
+    /// The `secrets` are the values marked as secret. All values computed
+    /// from secret values are secret as well.
+    ///
+    /// The leaks are returned in the order of the basic blocks and their
+    /// instructions.
+    ///
+    /// # Note
+    ///
+    /// Secrets are not tracked through linear memories and global variables
+    /// since their contents are unknown to the check.
+    pub fn check_constant_time<I>(&self, secrets: I) -> Vec<SecretLeak>
+    where
+        I: IntoIterator<Item = Value>,
+    {
+        let mut is_secret = DefaultComponentBitVec::<Value>::default();
+        for secret in secrets {
+            is_secret.set(secret, true);
+        }
+        let blocks = self.blocks().collect::<Vec<_>>();
+        // Propagate until a fixed point is reached since the parameters of
+        // loop headers may depend on values of later basic blocks.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in &blocks {
+                for &instr in &self.block_instrs[block] {
+                    let mut has_secret_operand = false;
+                    self.instrs[instr].visit_values(|value| {
+                        has_secret_operand = is_secret.get(value);
+                        !has_secret_operand
+                    });
+                    if !has_secret_operand {
+                        continue
+                    }
+                    for &value in self.instr_values(instr).iter().flatten() {
+                        changed |= !is_secret.replace(value, true);
+                    }
+                }
+                for target in self
+                    .terminal(block)
+                    .map(TerminalInstr::branch_targets)
+                    .unwrap_or_default()
+                {
+                    let params = self.block_params(target.block());
+                    for (&arg, &param) in target.args().iter().zip(params) {
+                        if is_secret.get(arg) {
+                            changed |= !is_secret.replace(param, true);
+                        }
+                    }
+                }
+            }
+        }
+        let mut leaks = Vec::new();
+        for &block in &blocks {
+            for &instr in &self.block_instrs[block] {
+                let instruction = &self.instrs[instr];
+                if instruction.descriptor().is_constant_time() {
+                    continue
+                }
+                let start = leaks.len();
+                let mut report = |value| {
+                    let leak = SecretLeak { instr, value };
+                    if is_secret.get(value) && !leaks[start..].contains(&leak) {
+                        leaks.push(leak);
+                    }
+                    true
+                };
+                // Branch arguments are only copied into the parameters of
+                // the target basic block and thus never leak.
+                use TerminalInstr::{BranchTable, Ite};
+                match instruction {
+                    Instruction::Terminal(Ite(ite)) => {
+                        report(ite.condition());
+                    }
+                    Instruction::Terminal(BranchTable(table)) => {
+                        report(table.case());
+                    }
+                    instruction => instruction.visit_values(report),
+                }
+            }
+        }
+        leaks
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Insertion of instructions into finalized function bodies.
+//!
+//! A cursor points to a position between two instructions of a basic block
+//! and inserts new instructions at that position. This allows passes to
+//! rewrite code in place instead of rebuilding entire function bodies.
+
+use super::{FunctionBody, FunctionEditorError, Instr, ValueAssoc};
+use crate::Error;
+use ir::{
+    instr::Instruction,
+    primitive::{Block, Type, Value},
+    VisitValues,
+};
+
+/// Inserts instructions at arbitrary positions of a finalized function body.
+///
+/// The cursor always points before an instruction of a basic block or to
+/// the end of a basic block. Inserted instructions are placed before the
+/// instruction the cursor points to and the cursor advances past them so
+/// that consecutively inserted instructions keep their order.
+#[derive(Debug)]
+pub struct FuncCursor<'a> {
+    body: &'a mut FunctionBody,
+    block: Block,
+    position: usize,
+}
+
+impl FunctionBody {
+    /// Returns a cursor pointing before the first instruction of the entry block.
+    pub fn cursor(&mut self) -> FuncCursor<'_> {
+        let block = self.entry_block();
+        FuncCursor {
+            body: self,
+            block,
+            position: 0,
+        }
+    }
+}
+
+impl<'a> FuncCursor<'a> {
+    /// Returns a shared reference to the function body under the cursor.
+    pub fn body(&self) -> &FunctionBody {
+        self.body
+    }
+
+    /// Returns the basic block the cursor points into.
+    pub fn current_block(&self) -> Block {
+        self.block
+    }
+
+    /// Returns the instruction the cursor points before if any.
+    ///
+    /// Returns `None` if the cursor points to the end of its basic block.
+    pub fn current_instr(&self) -> Option<Instr> {
+        self.body.block_instrs[self.block]
+            .get(self.position)
+            .copied()
+    }
+
+    /// Returns the instruction the cursor points before and advances past it.
+    ///
+    /// Returns `None` if the cursor points to the end of its basic block.
+    pub fn next_instr(&mut self) -> Option<Instr> {
+        let instr = self.current_instr()?;
+        self.position += 1;
+        Some(instr)
+    }
+
+    /// Moves the cursor before the first instruction of the basic block.
+    ///
+    /// # Errors
+    ///
+    /// If the basic block does not exist or has been removed.
+    pub fn goto_top(&mut self, block: Block) -> Result<(), Error> {
+        if !self.body.contains_block(block) {
+            return Err(FunctionEditorError::InvalidBasicBlock { block }.into())
+        }
+        self.block = block;
+        self.position = 0;
+        Ok(())
+    }
+
+    /// Moves the cursor before the instruction.
+    ///
+    /// # Errors
+    ///
+    /// If the instruction is not part of any basic block.
+    pub fn goto_before(&mut self, instr: Instr) -> Result<(), Error> {
+        let (block, position) = self
+            .body
+            .instr_position(instr)
+            .ok_or(FunctionEditorError::InvalidInstr { instr })?;
+        self.block = block;
+        self.position = position;
+        Ok(())
+    }
+
+    /// Moves the cursor after the instruction.
+    ///
+    /// # Note
+    ///
+    /// Moving after the terminal instruction of a basic block is possible
+    /// but inserting instructions at that position is not.
+    ///
+    /// # Errors
+    ///
+    /// If the instruction is not part of any basic block.
+    pub fn goto_after(&mut self, instr: Instr) -> Result<(), Error> {
+        self.goto_before(instr)?;
+        self.position += 1;
+        Ok(())
+    }
+
+    /// Inserts the instruction that produces a single value of the given type.
+    ///
+    /// Returns the value produced by the inserted instruction.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`FuncCursor::insert`].
+    pub fn insert_value(
+        &mut self,
+        instruction: Instruction,
+        output_type: Type,
+    ) -> Result<Value, Error> {
+        let (_, outputs) = self.insert(instruction, &[output_type])?;
+        Ok(outputs[0])
+    }
+
+    /// Inserts the instruction at the position of the cursor.
+    ///
+    /// The instruction is associated to a new SSA value for every type in
+    /// `output_types`. Returns the inserted instruction and its values.
+    ///
+    /// # Errors
+    ///
+    /// - If the instruction is a terminal instruction.
+    /// - If the cursor points after the terminal instruction of its block.
+    /// - If the instruction uses values that do not exist in the function.
+    pub fn insert(
+        &mut self,
+        instruction: Instruction,
+        output_types: &[Type],
+    ) -> Result<(Instr, Vec<Value>), Error> {
+        let block = self.block;
+        if instruction.is_terminal() {
+            return Err(FunctionEditorError::InsertTerminal { block }.into())
+        }
+        if self.position >= self.body.block_instrs[block].len() {
+            return Err(
+                FunctionEditorError::InsertAfterTerminal { block }.into()
+            )
+        }
+        let mut invalid_value = None;
+        instruction.visit_values(|value| {
+            if self.body.value_type(value).is_none() {
+                invalid_value = Some(value);
+            }
+            invalid_value.is_none()
+        });
+        if let Some(value) = invalid_value {
+            return Err(FunctionEditorError::InvalidValue { value }.into())
+        }
+        let instr = self.body.instrs.alloc(instruction);
+        let mut outputs = Vec::with_capacity(output_types.len());
+        for (n, &output_type) in output_types.iter().enumerate() {
+            let value = self.body.values.alloc_some(1);
+            self.body.value_type.insert(value, output_type);
+            self.body
+                .value_assoc
+                .insert(value, ValueAssoc::Instr(instr, n as u32));
+            self.body.instr_values[instr].push(Some(value));
+            outputs.push(value);
+        }
+        self.body.block_instrs[block].insert(self.position, instr);
+        self.position += 1;
+        Ok((instr, outputs))
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Configurable printing of function bodies.
+//!
+//! By default function bodies are printed in a terse format. For debugging
+//! purposes the printer can additionally annotate basic blocks with their
+//! predecessors, SSA values with the variables they define and instructions
+//! with their indices and source spans.
+
+use super::{FunctionBody, Variable};
+use crate::module::Indent;
+use core::fmt;
+use ir::primitive::Value;
+use std::collections::HashMap;
+
+/// Configures which information is printed for function bodies.
+///
+/// The default configuration prints the types of all defined SSA values
+/// and nothing else.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DisplayConfig {
+    /// Prints the predecessors of every basic block.
+    preds: bool,
+    /// Prints the type of every defined SSA value.
+    value_types: bool,
+    /// Prints the variables defined by SSA values.
+    var_origins: bool,
+    /// Prints the index of every instruction.
+    instr_indices: bool,
+    /// Prints the source span of every instruction that has one.
+    source_spans: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            preds: false,
+            value_types: true,
+            var_origins: false,
+            instr_indices: false,
+            source_spans: false,
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Creates the default display configuration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a display configuration that prints all available information.
+    pub fn verbose() -> Self {
+        Self {
+            preds: true,
+            value_types: true,
+            var_origins: true,
+            instr_indices: true,
+            source_spans: true,
+        }
+    }
+
+    /// Prints the predecessors of every basic block as comment.
+    pub fn set_show_preds(&mut self, enabled: bool) {
+        self.preds = enabled;
+    }
+
+    /// Prints the type of every SSA value upon its definition.
+    pub fn set_show_value_types(&mut self, enabled: bool) {
+        self.value_types = enabled;
+    }
+
+    /// Prints the variables that SSA values define as comment.
+    ///
+    /// # Note
+    ///
+    /// An SSA value defines a variable if it is the value of the variable
+    /// at the end of any basic block.
+    pub fn set_show_var_origins(&mut self, enabled: bool) {
+        self.var_origins = enabled;
+    }
+
+    /// Prints the index of every instruction before the instruction.
+    pub fn set_show_instr_indices(&mut self, enabled: bool) {
+        self.instr_indices = enabled;
+    }
+
+    /// Prints the source span of every instruction that has one as comment.
+    pub fn set_show_source_spans(&mut self, enabled: bool) {
+        self.source_spans = enabled;
+    }
+
+    /// Returns `true` if the predecessors of basic blocks are printed.
+    pub fn show_preds(&self) -> bool {
+        self.preds
+    }
+
+    /// Returns `true` if the types of SSA values are printed.
+    pub fn show_value_types(&self) -> bool {
+        self.value_types
+    }
+
+    /// Returns `true` if the variables defined by SSA values are printed.
+    pub fn show_var_origins(&self) -> bool {
+        self.var_origins
+    }
+
+    /// Returns `true` if the indices of instructions are printed.
+    pub fn show_instr_indices(&self) -> bool {
+        self.instr_indices
+    }
+
+    /// Returns `true` if the source spans of instructions are printed.
+    pub fn show_source_spans(&self) -> bool {
+        self.source_spans
+    }
+}
+
+/// Displays a function body using a display configuration.
+#[derive(Debug, Copy, Clone)]
+pub struct DisplayFunctionBody<'a> {
+    body: &'a FunctionBody,
+    config: DisplayConfig,
+}
+
+impl fmt::Display for DisplayFunctionBody<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.body
+            .display_with_indent(f, Default::default(), self.config)
+    }
+}
+
+/// Writes the items separated by commas.
+fn write_list<T>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result
+where
+    T: fmt::Display,
+{
+    if let Some((first, rest)) = items.split_first() {
+        write!(f, "{}", first)?;
+        for item in rest {
+            write!(f, ", {}", item)?;
+        }
+    }
+    Ok(())
+}
+
+impl FunctionBody {
+    /// Returns a wrapper that displays the function body using the configuration.
+    pub fn display_with(
+        &self,
+        config: DisplayConfig,
+    ) -> DisplayFunctionBody<'_> {
+        DisplayFunctionBody { body: self, config }
+    }
+
+    /// Returns the variables defined by every SSA value in ascending order.
+    fn var_origins(&self) -> HashMap<Value, Vec<Variable>> {
+        let mut origins = HashMap::<Value, Vec<Variable>>::new();
+        for var in self.vars() {
+            for (_, value) in self.var_definitions(var) {
+                let vars = origins.entry(value).or_default();
+                if !vars.contains(&var) {
+                    vars.push(var);
+                }
+            }
+        }
+        origins
+    }
+
+    /// Writes the definition of the SSA value and its type if configured.
+    fn write_value_def(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        value: Value,
+        config: DisplayConfig,
+    ) -> fmt::Result {
+        write!(f, "{}", value)?;
+        if config.show_value_types() {
+            write!(f, ": {}", self.value_type[value])?;
+        }
+        Ok(())
+    }
+
+    /// Writes a comment naming the variables defined by the SSA values if any.
+    fn write_origins<'a, T>(
+        f: &mut fmt::Formatter<'_>,
+        origins: &HashMap<Value, Vec<Variable>>,
+        values: T,
+        mut separator: &'static str,
+    ) -> fmt::Result
+    where
+        T: IntoIterator<Item = &'a Value>,
+    {
+        for value in values {
+            if let Some(vars) = origins.get(value) {
+                write!(f, "{}{} = ", separator, value)?;
+                write_list(f, vars)?;
+                separator = "; ";
+            }
+        }
+        Ok(())
+    }
+
+    /// Display the function body with the given indentation.
+    ///
+    /// # Note
+    ///
+    /// Indentation is important to properly indent the printed function body
+    /// in case the output is part of an entire function with signature.
+    pub(crate) fn display_with_indent(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        ident: Indent,
+        config: DisplayConfig,
+    ) -> fmt::Result {
+        let block_ident = ident;
+        let instr_ident = ident + Indent::single();
+        let origins = match config.show_var_origins() {
+            true => self.var_origins(),
+            false => HashMap::new(),
+        };
+        let mut passed_entry = false;
+        for block in self.blocks() {
+            if !passed_entry {
+                passed_entry = true;
+                write!(f, "{}block", block_ident)?;
+            } else {
+                write!(f, "{}block {}", block_ident, block)?;
+            }
+            let params = &self.block_params[block];
+            if let Some((&first, rest)) = params.split_first() {
+                write!(f, "(")?;
+                self.write_value_def(f, first, config)?;
+                for &param in rest {
+                    write!(f, ", ")?;
+                    self.write_value_def(f, param, config)?;
+                }
+                write!(f, ")")?;
+            }
+            write!(f, " {{")?;
+            let mut separator = " // ";
+            if config.show_preds() {
+                let preds = self.predecessors(block);
+                if !preds.is_empty() {
+                    write!(f, "{}preds: ", separator)?;
+                    write_list(f, &preds)?;
+                    separator = "; ";
+                }
+            }
+            Self::write_origins(f, &origins, params, separator)?;
+            writeln!(f)?;
+            for &instr in &self.block_instrs[block] {
+                let instr_data = &self.instrs[instr];
+                let instr_values = self.instr_values(instr);
+                let instr_values_tuples = instr_values.len() >= 2;
+                write!(f, "{}", instr_ident)?;
+                if config.show_instr_indices() {
+                    write!(f, "{}: ", instr)?;
+                }
+                match instr_values.split_first() {
+                    None => {
+                        write!(f, "{}", instr_data)?;
+                    }
+                    Some((&first, rest)) => {
+                        write!(f, "let ")?;
+                        if instr_values_tuples {
+                            write!(f, "(")?;
+                        }
+                        if let Some(first) = first {
+                            self.write_value_def(f, first, config)?;
+                        } else {
+                            write!(f, "_")?;
+                        }
+                        for &value in rest {
+                            if let Some(value) = value {
+                                write!(f, ", ")?;
+                                self.write_value_def(f, value, config)?;
+                            } else {
+                                write!(f, ", _")?;
+                            }
+                        }
+                        if instr_values_tuples {
+                            write!(f, ")")?;
+                        }
+                        write!(f, " = {}", instr_data)?;
+                    }
+                }
+                let mut separator = " // ";
+                if config.show_source_spans() {
+                    if let Some(span) = self.source_span(instr) {
+                        write!(f, "{}at {}", separator, span)?;
+                        separator = "; ";
+                    }
+                }
+                let values = instr_values.iter().flatten();
+                Self::write_origins(f, &origins, values, separator)?;
+                writeln!(f)?;
+            }
+            writeln!(f, "{}}}", block_ident)?;
+        }
+        Ok(())
+    }
+}
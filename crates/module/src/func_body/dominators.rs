@@ -0,0 +1,257 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dominator tree analysis of the control flow graph of functions.
+//!
+//! A basic block `a` dominates a basic block `b` if every path from the
+//! entry block to `b` passes through `a`. The immediate dominator of `b`
+//! is the strict dominator of `b` that is dominated by all other strict
+//! dominators of `b`.
+//!
+//! The dominator tree is computed using the algorithm from the paper
+//! "A Simple, Fast Dominance Algorithm" by Cooper, Harvey and Kennedy.
+
+use super::FunctionBody;
+use entity::ComponentMap;
+use ir::primitive::Block;
+
+/// The dominator tree of the reachable basic blocks of a function.
+///
+/// # Note
+///
+/// Unreachable basic blocks are not part of the dominator tree.
+/// They neither dominate nor are dominated by any basic block.
+#[derive(Debug, Clone)]
+pub struct DominatorTree {
+    /// The reachable basic blocks in reverse postorder.
+    rpo: Vec<Block>,
+    /// The position of every reachable basic block in the reverse postorder.
+    rpo_index: ComponentMap<Block, u32>,
+    /// The immediate dominator of every reachable basic block.
+    ///
+    /// The entry block is its own immediate dominator.
+    idom: ComponentMap<Block, Block>,
+}
+
+impl DominatorTree {
+    /// Computes the dominator tree of a control flow graph.
+    ///
+    /// The control flow graph is given by its `entry` block and the
+    /// `successors` of every basic block.
+    pub fn new<F, T>(entry: Block, mut successors: F) -> Self
+    where
+        F: FnMut(Block) -> T,
+        T: IntoIterator<Item = Block>,
+    {
+        let (rpo, preds) = reverse_postorder(entry, &mut successors);
+        let mut rpo_index = ComponentMap::default();
+        for (n, &block) in rpo.iter().enumerate() {
+            rpo_index.insert(block, n as u32);
+        }
+        let mut tree = Self {
+            rpo,
+            rpo_index,
+            idom: Default::default(),
+        };
+        tree.idom.insert(entry, entry);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for n in 1..tree.rpo.len() {
+                let block = tree.rpo[n];
+                let mut new_idom = None;
+                for &pred in &preds[block] {
+                    if !tree.idom.contains_key(pred) {
+                        // The predecessor has not yet been processed.
+                        continue
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(idom) => tree.intersect(pred, idom),
+                    });
+                }
+                // In reverse postorder the parent of a basic block in the
+                // depth-first search tree is processed before the block.
+                let new_idom = new_idom.expect(
+                    "encountered reachable block without processed predecessor",
+                );
+                if tree.idom.insert(block, new_idom) != Some(new_idom) {
+                    changed = true;
+                }
+            }
+        }
+        tree
+    }
+
+    /// Returns the closest common dominator of both basic blocks.
+    fn intersect(&self, mut lhs: Block, mut rhs: Block) -> Block {
+        while lhs != rhs {
+            while self.rpo_index[lhs] > self.rpo_index[rhs] {
+                lhs = self.idom[lhs];
+            }
+            while self.rpo_index[rhs] > self.rpo_index[lhs] {
+                rhs = self.idom[rhs];
+            }
+        }
+        lhs
+    }
+
+    /// Returns the entry block of the control flow graph.
+    pub fn entry(&self) -> Block {
+        self.rpo[0]
+    }
+
+    /// Returns the reachable basic blocks in reverse postorder.
+    ///
+    /// Every basic block comes after all of its dominators.
+    pub fn reverse_postorder(&self) -> &[Block] {
+        &self.rpo
+    }
+
+    /// Returns `true` if the basic block is reachable from the entry block.
+    pub fn is_reachable(&self, block: Block) -> bool {
+        self.rpo_index.contains_key(block)
+    }
+
+    /// Returns the immediate dominator of the basic block.
+    ///
+    /// Returns `None` for the entry block and unreachable basic blocks.
+    pub fn idom(&self, block: Block) -> Option<Block> {
+        let idom = *self.idom.get(block)?;
+        if idom == block {
+            return None
+        }
+        Some(idom)
+    }
+
+    /// Returns `true` if basic block `lhs` dominates basic block `rhs`.
+    ///
+    /// Every reachable basic block dominates itself.
+    pub fn dominates(&self, lhs: Block, rhs: Block) -> bool {
+        let lhs_index = match self.rpo_index.get(lhs) {
+            Some(&index) => index,
+            None => return false,
+        };
+        let mut rhs = rhs;
+        loop {
+            let rhs_index = match self.rpo_index.get(rhs) {
+                Some(&index) => index,
+                None => return false,
+            };
+            // Dominators precede the basic blocks they dominate in reverse
+            // postorder so walking up the dominator tree can stop here.
+            if rhs_index <= lhs_index {
+                return lhs == rhs
+            }
+            rhs = self.idom[rhs];
+        }
+    }
+}
+
+/// Returns the reachable basic blocks in reverse postorder and the
+/// predecessors of every reachable basic block.
+fn reverse_postorder<F, T>(
+    entry: Block,
+    successors: &mut F,
+) -> (Vec<Block>, ComponentMap<Block, Vec<Block>>)
+where
+    F: FnMut(Block) -> T,
+    T: IntoIterator<Item = Block>,
+{
+    let mut postorder = Vec::new();
+    let mut preds = <ComponentMap<Block, Vec<Block>>>::default();
+    preds.insert(entry, Vec::new());
+    // Every basic block on the stack is paired with its remaining successors.
+    let mut stack = vec![(entry, successors(entry).into_iter())];
+    while let Some((block, succs)) = stack.last_mut() {
+        let block = *block;
+        match succs.next() {
+            Some(succ) => {
+                if let Some(succ_preds) = preds.get_mut(succ) {
+                    succ_preds.push(block);
+                    continue
+                }
+                preds.insert(succ, vec![block]);
+                stack.push((succ, successors(succ).into_iter()));
+            }
+            None => {
+                postorder.push(block);
+                stack.pop();
+            }
+        }
+    }
+    postorder.reverse();
+    (postorder, preds)
+}
+
+impl FunctionBody {
+    /// Computes the dominator tree of the function body.
+    pub fn dominator_tree(&self) -> DominatorTree {
+        DominatorTree::new(self.entry_block(), |block| {
+            self.successors(block)
+                .into_iter()
+                .filter(move |&succ| self.contains_block(succ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::RawIdx;
+
+    fn block(n: u32) -> Block {
+        Block::from_raw(RawIdx::from_u32(n))
+    }
+
+    #[test]
+    fn dominator_tree_works() {
+        // A diamond followed by a loop and an unreachable basic block:
+        //
+        // 0 -> 1, 2; 1 -> 3; 2 -> 3; 3 -> 4; 4 -> 3, 5; 6 -> 5
+        let edges: &[&[u32]] = &[&[1, 2], &[3], &[3], &[4], &[3, 5], &[], &[5]];
+        let tree = DominatorTree::new(block(0), |b| {
+            edges[b.into_raw().into_u32() as usize]
+                .iter()
+                .copied()
+                .map(block)
+        });
+        assert_eq!(tree.entry(), block(0));
+        assert_eq!(tree.reverse_postorder().len(), 6);
+        assert!(tree.is_reachable(block(5)));
+        assert!(!tree.is_reachable(block(6)));
+        let idoms = (0..7).map(|n| tree.idom(block(n))).collect::<Vec<_>>();
+        assert_eq!(
+            idoms,
+            vec![
+                None,
+                Some(block(0)),
+                Some(block(0)),
+                Some(block(0)),
+                Some(block(3)),
+                Some(block(4)),
+                None,
+            ]
+        );
+        assert!(tree.dominates(block(0), block(5)));
+        assert!(tree.dominates(block(3), block(4)));
+        assert!(tree.dominates(block(4), block(4)));
+        assert!(!tree.dominates(block(4), block(3)));
+        assert!(!tree.dominates(block(1), block(3)));
+        assert!(!tree.dominates(block(2), block(5)));
+        assert!(!tree.dominates(block(6), block(5)));
+        assert!(!tree.dominates(block(0), block(6)));
+        assert!(!tree.dominates(block(6), block(6)));
+    }
+}
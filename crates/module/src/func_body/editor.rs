@@ -0,0 +1,464 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Editing of finalized function bodies.
+//!
+//! Allows transformation passes to rewrite instructions and restructure the
+//! control flow graph of a function body without having to rebuild it from
+//! scratch. All operations keep the terminal instructions and the arguments
+//! they pass to the parameters of their target basic blocks consistent with
+//! the changed control flow.
+
+use super::{FunctionBody, FunctionEditorError, Instr, ValueAssoc};
+use crate::Error;
+use ir::{
+    instr::{
+        BranchInstr,
+        BranchTarget,
+        Instruction,
+        TerminalInstr,
+    },
+    primitive::{Block, Value},
+    VisitValues,
+};
+
+/// Edits the instructions and control flow graph of a finalized function body.
+#[derive(Debug)]
+pub struct FunctionEditor<'a> {
+    body: &'a mut FunctionBody,
+}
+
+impl FunctionBody {
+    /// Returns an editor to restructure the function body.
+    pub fn edit(&mut self) -> FunctionEditor<'_> {
+        FunctionEditor { body: self }
+    }
+}
+
+impl<'a> FunctionEditor<'a> {
+    /// Returns a shared reference to the edited function body.
+    pub fn body(&self) -> &FunctionBody {
+        self.body
+    }
+
+    /// Returns `Ok` if the basic block exists and has not been removed.
+    fn ensure_block(&self, block: Block) -> Result<(), FunctionEditorError> {
+        if !self.body.contains_block(block) {
+            return Err(FunctionEditorError::InvalidBasicBlock { block })
+        }
+        Ok(())
+    }
+
+    /// Returns an exclusive reference to the terminal instruction of the basic block.
+    fn terminal_mut(&mut self, block: Block) -> Option<&mut TerminalInstr> {
+        let instr = self.body.block_instrs[block].last().copied()?;
+        match &mut self.body.instrs[instr] {
+            Instruction::Terminal(terminal) => Some(terminal),
+            _ => None,
+        }
+    }
+
+    /// Returns the instruction that defines the value if any.
+    ///
+    /// Returns `None` for values associated to function inputs or basic
+    /// block parameters.
+    fn defining_instr(&self, value: Value) -> Option<Instr> {
+        match self.body.value_assoc.get(value)? {
+            ValueAssoc::Instr(instr, _) => Some(*instr),
+            ValueAssoc::Input(_) | ValueAssoc::Param(..) => None,
+        }
+    }
+
+    /// Returns the first instruction that uses the value if any.
+    fn first_user(&self, value: Value) -> Option<Instr> {
+        self.body
+            .blocks()
+            .flat_map(|block| self.body.block_instrs(block).iter().copied())
+            .find(|&instr| {
+                let mut is_user = false;
+                self.body.instrs[instr].visit_values(|used| {
+                    is_user = used == value;
+                    !is_user
+                });
+                is_user
+            })
+    }
+
+    /// Returns `Ok` if the instruction only uses existing values and only
+    /// branches to existing basic blocks passing an argument per parameter.
+    fn ensure_valid_instr(
+        &self,
+        instruction: &Instruction,
+    ) -> Result<(), FunctionEditorError> {
+        let mut invalid_value = None;
+        instruction.visit_values(|value| {
+            if self.body.value_type(value).is_none() {
+                invalid_value = Some(value);
+            }
+            invalid_value.is_none()
+        });
+        if let Some(value) = invalid_value {
+            return Err(FunctionEditorError::InvalidValue { value })
+        }
+        let terminal = match instruction {
+            Instruction::Terminal(terminal) => terminal,
+            _ => return Ok(()),
+        };
+        for target in terminal.branch_targets() {
+            let block = target.block();
+            self.ensure_block(block)?;
+            let expected = self.body.block_params(block).len();
+            let found = target.args().len();
+            if expected != found {
+                return Err(FunctionEditorError::MismatchingBlockArgs {
+                    block,
+                    expected,
+                    found,
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the arguments of the first branch from `from` to `to` if any.
+    fn branch_args(&self, from: Block, to: Block) -> Option<&[Value]> {
+        self.body
+            .terminal(from)?
+            .branch_targets()
+            .into_iter()
+            .find(|target| target.block() == to)
+            .map(BranchTarget::args)
+    }
+
+    /// Replaces the instruction in place with the new instruction.
+    ///
+    /// The new instruction takes over the output values of the replaced
+    /// instruction so that all of their users remain valid.
+    ///
+    /// # Note
+    ///
+    /// The new instruction is expected to produce values of the same types
+    /// as the replaced instruction which is not checked. Replacing terminal
+    /// instructions changes the successors of their basic block. The new
+    /// instruction inherits the source span of the replaced instruction.
+    ///
+    /// # Errors
+    ///
+    /// - If the instruction is not part of any basic block.
+    /// - If exactly one of both instructions is a terminal instruction.
+    /// - If the new instruction uses values that do not exist.
+    /// - If the new instruction branches to basic blocks that do not exist
+    ///   or does not pass an argument for every of their parameters.
+    pub fn replace_instr(
+        &mut self,
+        instr: Instr,
+        instruction: Instruction,
+    ) -> Result<(), Error> {
+        self.body
+            .instr_position(instr)
+            .ok_or(FunctionEditorError::InvalidInstr { instr })?;
+        if self.body.instrs[instr].is_terminal() != instruction.is_terminal() {
+            return Err(
+                FunctionEditorError::IncompatibleReplacement { instr }.into()
+            )
+        }
+        self.ensure_valid_instr(&instruction)?;
+        self.body.instrs[instr] = instruction;
+        Ok(())
+    }
+
+    /// Removes the non-terminal instruction from its basic block.
+    ///
+    /// # Note
+    ///
+    /// The source span of the removed instruction and variable definitions
+    /// referring to its output values are removed as well.
+    ///
+    /// # Errors
+    ///
+    /// - If the instruction is not part of any basic block.
+    /// - If the instruction is a terminal instruction.
+    /// - If any output value of the instruction is still in use.
+    pub fn remove_instr(&mut self, instr: Instr) -> Result<(), Error> {
+        let (block, position) = self
+            .body
+            .instr_position(instr)
+            .ok_or(FunctionEditorError::InvalidInstr { instr })?;
+        if self.body.instrs[instr].is_terminal() {
+            return Err(FunctionEditorError::RemoveTerminal { instr }.into())
+        }
+        let outputs = self
+            .body
+            .instr_values(instr)
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        for &value in &outputs {
+            if let Some(user) = self.first_user(value) {
+                return Err(FunctionEditorError::RemoveUsedInstr {
+                    instr,
+                    value,
+                    user,
+                }
+                .into())
+            }
+        }
+        self.body.block_instrs[block].remove(position);
+        self.body.instr_spans.remove(instr);
+        for defs in self.body.var_defs.components_mut() {
+            let removed = defs
+                .iter()
+                .filter(|(_, value)| outputs.contains(value))
+                .map(|(block, _)| block)
+                .collect::<Vec<_>>();
+            for block in removed {
+                defs.remove(block);
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits the basic block containing the instruction right before it.
+    ///
+    /// The instruction and all instructions following it are moved into a
+    /// new basic block which is returned. The original basic block then
+    /// unconditionally branches to the new basic block.
+    ///
+    /// # Note
+    ///
+    /// - The terminal instruction including its branch arguments is moved
+    ///   to the new basic block.
+    /// - The variable definitions at the end of the original basic block
+    ///   are moved to the new basic block.
+    ///
+    /// # Errors
+    ///
+    /// - If the instruction is not part of any basic block.
+    pub fn split_block(&mut self, instr: Instr) -> Result<Block, Error> {
+        let (block, position) = self
+            .body
+            .instr_position(instr)
+            .ok_or(FunctionEditorError::InvalidInstr { instr })?;
+        let new_block = self.body.blocks.alloc_some(1);
+        let tail = self.body.block_instrs[block]
+            .drain(position..)
+            .collect::<Vec<_>>();
+        self.body.block_instrs[new_block].extend(tail);
+        let br = self.body.instrs.alloc(BranchInstr::new(new_block).into());
+        self.body.block_instrs[block].push(br);
+        for defs in self.body.var_defs.components_mut() {
+            if let Some(value) = defs.remove(block) {
+                defs.insert(new_block, value);
+            }
+        }
+        Ok(new_block)
+    }
+
+    /// Inserts a new basic block on the edge from `from` to `to`.
+    ///
+    /// All branches from `from` to `to` are redirected to the new basic
+    /// block which unconditionally branches to `to`. Returns the new basic
+    /// block.
+    ///
+    /// # Note
+    ///
+    /// - The new basic block has no parameters and passes the arguments
+    ///   previously passed by `from` on to `to`.
+    /// - The variable definitions at the end of `from` are also valid at
+    ///   the end of the new basic block.
+    ///
+    /// # Errors
+    ///
+    /// - If any of the basic blocks does not exist.
+    /// - If `from` does not branch to `to`.
+    /// - If `from` branches to `to` multiple times passing different
+    ///   arguments.
+    pub fn insert_block_between(
+        &mut self,
+        from: Block,
+        to: Block,
+    ) -> Result<Block, Error> {
+        self.ensure_block(from)?;
+        self.ensure_block(to)?;
+        let terminal = self
+            .body
+            .terminal(from)
+            .expect("encountered basic block without terminal instruction");
+        let mut targets = terminal
+            .branch_targets()
+            .into_iter()
+            .filter(|target| target.block() == to);
+        let args = match targets.next() {
+            Some(target) => target.args().to_vec(),
+            None => {
+                return Err(FunctionEditorError::MissingEdge { from, to }.into())
+            }
+        };
+        for target in targets {
+            let mismatch = args
+                .iter()
+                .zip(target.args())
+                .position(|(lhs, rhs)| lhs != rhs);
+            if let Some(index) = mismatch {
+                return Err(FunctionEditorError::UnresolvableBlockArg {
+                    block: to,
+                    pred: from,
+                    param: self.body.block_params(to)[index],
+                }
+                .into())
+            }
+        }
+        let new_block = self.body.blocks.alloc_some(1);
+        let br = BranchInstr::new(BranchTarget::new(to, args));
+        let br = self.body.instrs.alloc(br.into());
+        self.body.block_instrs[new_block].push(br);
+        self.terminal_mut(from)
+            .expect("encountered basic block without terminal instruction")
+            .replace_successor(to, new_block, &[]);
+        for defs in self.body.var_defs.components_mut() {
+            if let Some(&value) = defs.get(from) {
+                defs.insert(new_block, value);
+            }
+        }
+        Ok(new_block)
+    }
+
+    /// Removes the basic block and all of its instructions.
+    ///
+    /// # Errors
+    ///
+    /// - If the basic block does not exist.
+    /// - If the basic block is the entry block.
+    /// - If the basic block still has predecessors other than itself.
+    pub fn remove_block(&mut self, block: Block) -> Result<(), Error> {
+        self.ensure_block(block)?;
+        if block == self.body.entry_block() {
+            return Err(FunctionEditorError::RemoveEntryBlock { block }.into())
+        }
+        let preds = self
+            .body
+            .predecessors(block)
+            .into_iter()
+            .filter(|&pred| pred != block)
+            .collect::<Vec<_>>();
+        if !preds.is_empty() {
+            return Err(FunctionEditorError::RemoveReachableBlock {
+                block,
+                preds,
+            }
+            .into())
+        }
+        self.body.block_instrs[block].clear();
+        self.body.removed_blocks.set(block, true);
+        for defs in self.body.var_defs.components_mut() {
+            defs.remove(block);
+        }
+        Ok(())
+    }
+
+    /// Redirects all branches from `from` to `old_to` so that they branch to `new_to` instead.
+    ///
+    /// # Note
+    ///
+    /// The redirected branches pass arguments for the parameters of `new_to`.
+    /// If `from` already branches to `new_to` its existing arguments are used.
+    /// Otherwise if `old_to` branches to `new_to` its arguments are taken
+    /// whereas parameters of `old_to` are resolved to the arguments passed
+    /// by `from`. This covers the common case of bypassing `old_to`, e.g.
+    /// when removing empty basic blocks.
+    ///
+    /// # Errors
+    ///
+    /// - If any of the basic blocks does not exist.
+    /// - If `from` does not branch to `old_to`.
+    /// - If the argument for a parameter of `new_to` cannot be determined.
+    pub fn redirect_edge(
+        &mut self,
+        from: Block,
+        old_to: Block,
+        new_to: Block,
+    ) -> Result<(), Error> {
+        self.ensure_block(from)?;
+        self.ensure_block(old_to)?;
+        self.ensure_block(new_to)?;
+        if !self.body.successors(from).contains(&old_to) {
+            return Err(
+                FunctionEditorError::MissingEdge { from, to: old_to }.into()
+            )
+        }
+        if old_to == new_to {
+            return Ok(())
+        }
+        // Determine all new branch arguments before mutating anything.
+        let args = (0..self.body.block_params(new_to).len())
+            .map(|index| self.resolve_branch_arg(index, from, old_to, new_to))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.terminal_mut(from)
+            .expect("encountered basic block without terminal instruction")
+            .replace_successor(old_to, new_to, &args);
+        Ok(())
+    }
+
+    /// Returns the argument for the parameter at `index` of `new_to` after
+    /// redirecting the branch from `from` to `old_to` towards `new_to`.
+    fn resolve_branch_arg(
+        &self,
+        index: usize,
+        from: Block,
+        old_to: Block,
+        new_to: Block,
+    ) -> Result<Value, FunctionEditorError> {
+        let unresolvable = FunctionEditorError::UnresolvableBlockArg {
+            block: new_to,
+            pred: from,
+            param: self.body.block_params(new_to)[index],
+        };
+        // `from` might already branch to `new_to`.
+        let existing = self
+            .branch_args(from, new_to)
+            .map(|args| args[index]);
+        let value = match self.branch_args(old_to, new_to) {
+            Some(args) => args[index],
+            None => return existing.ok_or(unresolvable),
+        };
+        let resolved = match self.body.value_assoc.get(value) {
+            Some(ValueAssoc::Param(block, n)) if *block == old_to => {
+                // Parameters of the bypassed `old_to` resolve to the
+                // arguments passed to them by `from`.
+                match self.branch_args(from, old_to) {
+                    Some(args) => args[*n as usize],
+                    None => return Err(unresolvable),
+                }
+            }
+            _ => match self.defining_instr(value) {
+                // Values defined within the bypassed `old_to` are not
+                // available in `from`.
+                Some(defining_instr)
+                    if self
+                        .body
+                        .block_instrs(old_to)
+                        .contains(&defining_instr) =>
+                {
+                    return Err(unresolvable)
+                }
+                _ => value,
+            },
+        };
+        match existing {
+            Some(existing) if existing != resolved => Err(unresolvable),
+            _ => Ok(resolved),
+        }
+    }
+}
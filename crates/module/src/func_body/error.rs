@@ -12,10 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::FunctionBuilderState;
-use crate::{func_body::Variable, primitive::Instr};
+use super::{FunctionBuilderState, VerifierViolation};
+use crate::{
+    func_body::Variable,
+    primitive::{FunctionType, Instr},
+};
 use derive_more::{Display, Error};
-use ir::primitive::{Block, FloatType, IntType, Type, Value};
+use ir::{
+    primitive::{
+        Block,
+        Data,
+        FloatType,
+        Func,
+        Global,
+        IntType,
+        Mem,
+        Type,
+        Value,
+    },
+    Diagnostic,
+    ToDiagnostic,
+};
 
 /// Errors that might occur upon building up a Runwell IR function.
 #[derive(Debug, Display, Error, PartialEq, Eq)]
@@ -144,7 +161,7 @@ pub enum FunctionBuilderError {
     )]
     ReadBeforeWriteVariable { variable: Variable },
     #[display(
-        fmt = "there are still {} unsealed basic blocks upon finalizing construction: {:?}",
+        fmt = "there are still {} unsealed basic blocks upon finalizing construction: {:?} (consider `seal_all_blocks` or `finalize_auto_sealed`)",
         "unsealed.len()",
         unsealed
     )]
@@ -174,8 +191,25 @@ pub enum FunctionBuilderError {
         returned_types: Vec<Type>,
         expected_types: Vec<Type>,
     },
+    #[display(
+        fmt = "tried to call a function with arguments of types {:?} but its inputs are of types {:?}",
+        given_types,
+        expected_types
+    )]
+    UnmatchingCallArgumentTypes {
+        given_types: Vec<Type>,
+        expected_types: Vec<Type>,
+    },
     #[display(fmt = "encountered invalid instruction index {}", instr)]
     InvalidInstr { instr: Instr },
+    #[display(fmt = "encountered invalid read-only data index {}", data)]
+    InvalidReadOnlyData { data: Data },
+    #[display(fmt = "encountered invalid global variable index {}", global)]
+    InvalidGlobal { global: Global },
+    #[display(fmt = "tried to write to immutable global variable {}", global)]
+    ImmutableGlobalWrite { global: Global },
+    #[display(fmt = "encountered invalid linear memory index {}", mem)]
+    InvalidMemory { mem: Mem },
     #[display(
         fmt = "missing {} definition in {} with value {} to value {}",
         var,
@@ -189,6 +223,110 @@ pub enum FunctionBuilderError {
         replace_value: Value,
         with_value: Value,
     },
+    #[display(
+        fmt = "built function {} violates {} invariants upon finalizing construction: {:?}",
+        func,
+        "violations.len()",
+        violations
+    )]
+    InvalidFunctionBody {
+        func: Func,
+        violations: Vec<VerifierViolation>,
+    },
+    #[display(
+        fmt = "tried to build function {} with unmatching signature {}",
+        func,
+        signature
+    )]
+    UnmatchingFunctionSignature { func: Func, signature: FunctionType },
+}
+
+/// Errors that might occur upon editing a finalized Runwell IR function.
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum FunctionEditorError {
+    #[display(fmt = "encountered invalid basic block {}", block)]
+    InvalidBasicBlock { block: Block },
+    #[display(fmt = "encountered invalid instruction {}", instr)]
+    InvalidInstr { instr: Instr },
+    #[display(fmt = "encountered invalid value {}", value)]
+    InvalidValue { value: Value },
+    #[display(
+        fmt = "tried to insert a terminal instruction into basic block {}",
+        block
+    )]
+    InsertTerminal { block: Block },
+    #[display(
+        fmt = "tried to insert an instruction after the terminal instruction of basic block {}",
+        block
+    )]
+    InsertAfterTerminal { block: Block },
+    #[display(fmt = "tried to remove the entry block {}", block)]
+    RemoveEntryBlock { block: Block },
+    #[display(fmt = "tried to remove the terminal instruction {}", instr)]
+    RemoveTerminal { instr: Instr },
+    #[display(
+        fmt = "tried to remove instruction {} whose value {} is still used by {}",
+        instr,
+        value,
+        user
+    )]
+    RemoveUsedInstr {
+        instr: Instr,
+        value: Value,
+        user: Instr,
+    },
+    #[display(
+        fmt = "cannot replace instruction {} since only one of both instructions is terminal",
+        instr
+    )]
+    IncompatibleReplacement { instr: Instr },
+    #[display(
+        fmt = "branch to {} passes {} arguments but the basic block has {} parameters",
+        block,
+        found,
+        expected
+    )]
+    MismatchingBlockArgs {
+        block: Block,
+        expected: usize,
+        found: usize,
+    },
+    #[display(
+        fmt = "tried to remove basic block {} that still has predecessors {:?}",
+        block,
+        preds
+    )]
+    RemoveReachableBlock { block: Block, preds: Vec<Block> },
+    #[display(fmt = "there is no branch from {} to {}", from, to)]
+    MissingEdge { from: Block, to: Block },
+    #[display(
+        fmt = "cannot determine the argument from predecessor {} for parameter {} of {}",
+        pred,
+        param,
+        block
+    )]
+    UnresolvableBlockArg {
+        block: Block,
+        pred: Block,
+        param: Value,
+    },
+    #[display(
+        fmt = "cannot legalize instruction {} for the target constraints",
+        instr
+    )]
+    IllegalInstruction { instr: Instr },
+    #[display(fmt = "cannot transform interpreter-only function {}", func)]
+    InterpreterOnlyFunction { func: Func },
+    #[display(
+        fmt = "function {} cannot be used as instrumentation hook",
+        hook
+    )]
+    InvalidHook { hook: Func },
+    #[display(
+        fmt = "global variable {} cannot be used as shadow stack pointer",
+        global
+    )]
+    InvalidStackPointer { global: Global },
 }
 
 /// Kind of variable access for better error information.
@@ -203,3 +341,260 @@ pub enum VariableAccess {
     #[display(fmt = "replace {} with {}", from, to)]
     Replace { from: Value, to: Value },
 }
+
+impl ToDiagnostic for FunctionBuilderError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = |code| Diagnostic::new(code, self);
+        match self {
+            Self::InvalidPromotion { from_type, to_type } => {
+                diagnostic("builder.invalid_promotion")
+                    .with_parameter("from_type", from_type)
+                    .with_parameter("to_type", to_type)
+            }
+            Self::InvalidExtension { from_type, to_type } => {
+                diagnostic("builder.invalid_extension")
+                    .with_parameter("from_type", from_type)
+                    .with_parameter("to_type", to_type)
+            }
+            Self::InvalidDemotion { from_type, to_type } => {
+                diagnostic("builder.invalid_demotion")
+                    .with_parameter("from_type", from_type)
+                    .with_parameter("to_type", to_type)
+            }
+            Self::InvalidTruncation { from_type, to_type } => {
+                diagnostic("builder.invalid_truncation")
+                    .with_parameter("from_type", from_type)
+                    .with_parameter("to_type", to_type)
+            }
+            Self::IncorrectOrder {
+                last_state,
+                fail_state,
+            } => diagnostic("builder.incorrect_order")
+                .with_parameter("last_state", last_state)
+                .with_parameter("fail_state", fail_state),
+            Self::UnmatchingReinterpretBitwidths {
+                from_bitwidth,
+                to_bitwidth,
+                src,
+            } => diagnostic("builder.unmatching_reinterpret_bitwidths")
+                .with_parameter("from_bitwidth", from_bitwidth)
+                .with_parameter("to_bitwidth", to_bitwidth)
+                .with_parameter("src", src),
+            Self::PredecessorForSealedBlock {
+                sealed_block,
+                new_pred,
+            } => diagnostic("builder.predecessor_for_sealed_block")
+                .with_parameter("sealed_block", sealed_block)
+                .with_parameter("new_pred", new_pred),
+            Self::UnfilledPredecessor {
+                unfilled_pred,
+                block,
+            } => diagnostic("builder.unfilled_predecessor")
+                .with_parameter("unfilled_pred", unfilled_pred)
+                .with_parameter("block", block),
+            Self::NoCurrentBasicBlock => {
+                diagnostic("builder.no_current_basic_block")
+            }
+            Self::MissingBasicBlock { block } => {
+                diagnostic("builder.missing_basic_block")
+                    .with_parameter("block", block)
+            }
+            Self::BasicBlockIsAlreadySealed { block } => {
+                diagnostic("builder.basic_block_is_already_sealed")
+                    .with_parameter("block", block)
+            }
+            Self::BasicBlockIsAlreadyFilled { block } => {
+                diagnostic("builder.basic_block_is_already_filled")
+                    .with_parameter("block", block)
+            }
+            Self::TooManyVariableDeclarations => {
+                diagnostic("builder.too_many_variable_declarations")
+            }
+            Self::MissingDeclarationForVariable { variable, access } => {
+                diagnostic("builder.missing_declaration_for_variable")
+                    .with_parameter("variable", variable)
+                    .with_parameter("access", access)
+            }
+            Self::UnmatchingVariableType {
+                variable,
+                value,
+                declared_type,
+                value_type,
+            } => diagnostic("builder.unmatching_variable_type")
+                .with_parameter("variable", variable)
+                .with_parameter("value", value)
+                .with_parameter("declared_type", declared_type)
+                .with_parameter("value_type", value_type),
+            Self::UnmatchingValueType {
+                value,
+                value_type,
+                expected_type,
+            } => diagnostic("builder.unmatching_value_type")
+                .with_parameter("value", value)
+                .with_parameter("value_type", value_type)
+                .with_parameter("expected_type", expected_type),
+            Self::ReadBeforeWriteVariable { variable } => {
+                diagnostic("builder.read_before_write_variable")
+                    .with_parameter("variable", variable)
+            }
+            Self::UnsealedBlocksUponFinalize { unsealed } => {
+                diagnostic("builder.unsealed_blocks_upon_finalize")
+                    .with_parameter("unsealed", format!("{:?}", unsealed))
+            }
+            Self::UnfilledBlocksUponFinalize { unfilled } => {
+                diagnostic("builder.unfilled_blocks_upon_finalize")
+                    .with_parameter("unfilled", format!("{:?}", unfilled))
+            }
+            Self::BranchAlreadyExists { from, to } => {
+                diagnostic("builder.branch_already_exists")
+                    .with_parameter("from", from)
+                    .with_parameter("to", to)
+            }
+            Self::InvalidBasicBlock { block } => {
+                diagnostic("builder.invalid_basic_block")
+                    .with_parameter("block", block)
+            }
+            Self::UnreachablePhi { value } => {
+                diagnostic("builder.unreachable_phi")
+                    .with_parameter("value", value)
+            }
+            Self::UnmatchingFunctionReturnType {
+                returned_types,
+                expected_types,
+            } => diagnostic("builder.unmatching_function_return_type")
+                .with_parameter(
+                    "returned_types",
+                    format!("{:?}", returned_types),
+                )
+                .with_parameter(
+                    "expected_types",
+                    format!("{:?}", expected_types),
+                ),
+            Self::UnmatchingCallArgumentTypes {
+                given_types,
+                expected_types,
+            } => diagnostic("builder.unmatching_call_argument_types")
+                .with_parameter("given_types", format!("{:?}", given_types))
+                .with_parameter(
+                    "expected_types",
+                    format!("{:?}", expected_types),
+                ),
+            Self::InvalidInstr { instr } => diagnostic("builder.invalid_instr")
+                .with_parameter("instr", instr),
+            Self::InvalidReadOnlyData { data } => {
+                diagnostic("builder.invalid_read_only_data")
+                    .with_parameter("data", data)
+            }
+            Self::InvalidGlobal { global } => {
+                diagnostic("builder.invalid_global")
+                    .with_parameter("global", global)
+            }
+            Self::ImmutableGlobalWrite { global } => {
+                diagnostic("builder.immutable_global_write")
+                    .with_parameter("global", global)
+            }
+            Self::InvalidMemory { mem } => {
+                diagnostic("builder.invalid_memory").with_parameter("mem", mem)
+            }
+            Self::MissingVariableForReplacement {
+                var,
+                block,
+                replace_value,
+                with_value,
+            } => diagnostic("builder.missing_variable_for_replacement")
+                .with_parameter("var", var)
+                .with_parameter("block", block)
+                .with_parameter("replace_value", replace_value)
+                .with_parameter("with_value", with_value),
+            Self::InvalidFunctionBody { func, violations } => {
+                diagnostic("builder.invalid_function_body")
+                    .with_parameter("func", func)
+                    .with_parameter("violations", format!("{:?}", violations))
+            }
+            Self::UnmatchingFunctionSignature { func, signature } => {
+                diagnostic("builder.unmatching_function_signature")
+                    .with_parameter("func", func)
+                    .with_parameter("signature", signature)
+            }
+        }
+    }
+}
+
+impl ToDiagnostic for FunctionEditorError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = |code| Diagnostic::new(code, self);
+        match self {
+            Self::InvalidBasicBlock { block } => {
+                diagnostic("editor.invalid_basic_block")
+                    .with_parameter("block", block)
+            }
+            Self::InvalidInstr { instr } => diagnostic("editor.invalid_instr")
+                .with_parameter("instr", instr),
+            Self::InvalidValue { value } => diagnostic("editor.invalid_value")
+                .with_parameter("value", value),
+            Self::InsertTerminal { block } => {
+                diagnostic("editor.insert_terminal")
+                    .with_parameter("block", block)
+            }
+            Self::InsertAfterTerminal { block } => {
+                diagnostic("editor.insert_after_terminal")
+                    .with_parameter("block", block)
+            }
+            Self::RemoveEntryBlock { block } => {
+                diagnostic("editor.remove_entry_block")
+                    .with_parameter("block", block)
+            }
+            Self::RemoveTerminal { instr } => {
+                diagnostic("editor.remove_terminal")
+                    .with_parameter("instr", instr)
+            }
+            Self::RemoveUsedInstr { instr, value, user } => {
+                diagnostic("editor.remove_used_instr")
+                    .with_parameter("instr", instr)
+                    .with_parameter("value", value)
+                    .with_parameter("user", user)
+            }
+            Self::IncompatibleReplacement { instr } => {
+                diagnostic("editor.incompatible_replacement")
+                    .with_parameter("instr", instr)
+            }
+            Self::MismatchingBlockArgs {
+                block,
+                expected,
+                found,
+            } => diagnostic("editor.mismatching_block_args")
+                .with_parameter("block", block)
+                .with_parameter("expected", expected)
+                .with_parameter("found", found),
+            Self::RemoveReachableBlock { block, preds } => {
+                diagnostic("editor.remove_reachable_block")
+                    .with_parameter("block", block)
+                    .with_parameter("preds", format!("{:?}", preds))
+            }
+            Self::MissingEdge { from, to } => diagnostic("editor.missing_edge")
+                .with_parameter("from", from)
+                .with_parameter("to", to),
+            Self::UnresolvableBlockArg { block, pred, param } => {
+                diagnostic("editor.unresolvable_block_arg")
+                    .with_parameter("block", block)
+                    .with_parameter("pred", pred)
+                    .with_parameter("param", param)
+            }
+            Self::IllegalInstruction { instr } => {
+                diagnostic("editor.illegal_instruction")
+                    .with_parameter("instr", instr)
+            }
+            Self::InterpreterOnlyFunction { func } => {
+                diagnostic("editor.interpreter_only_function")
+                    .with_parameter("func", func)
+            }
+            Self::InvalidHook { hook } => {
+                diagnostic("editor.invalid_hook").with_parameter("hook", hook)
+            }
+            Self::InvalidStackPointer { global } => {
+                diagnostic("editor.invalid_stack_pointer")
+                    .with_parameter("global", global)
+            }
+        }
+    }
+}
@@ -25,7 +25,8 @@ use std::collections::{btree_map::Iter as BTreeIter, BTreeMap};
 ///
 /// This helper type is used only during function body construction.
 /// Upon finalization all remaining incomplete phi instructions are
-/// converted into proper phi instructions.
+/// converted into basic block parameters whose operands are passed as
+/// arguments by the branches of the predecessors.
 #[derive(Debug, Default)]
 pub struct IncompletePhi {
     operands: BTreeMap<Block, Value>,
@@ -63,6 +64,11 @@ impl IncompletePhi {
             .any(identity)
     }
 
+    /// Returns the operand for the given predecessor block if any.
+    pub fn operand_for(&self, block: Block) -> Option<Value> {
+        self.operands.get(&block).copied()
+    }
+
     /// Returns an iterator over the operands of the incomplete ϕ-instruction.
     pub fn operands(&self) -> Iter {
         Iter {
@@ -29,18 +29,26 @@ use ir::{
         BinaryFloatInstr,
         BinaryIntInstr,
         BranchInstr,
+        BranchTableInstr,
+        BranchTarget,
+        CallIndirectInstr,
         CallInstr,
         CompareFloatInstr,
         CompareIntInstr,
         ConstInstr,
+        DataAddrInstr,
         DemoteFloatInstr,
         ExtendIntInstr,
         FloatToIntInstr,
+        GlobalGetInstr,
+        GlobalSetInstr,
         HeapAddrInstr,
         IfThenElseInstr,
         Instruction,
         IntToFloatInstr,
         LoadInstr,
+        MemoryGrowInstr,
+        MemorySizeInstr,
         PromoteFloatInstr,
         ReinterpretInstr,
         ReturnInstr,
@@ -49,11 +57,25 @@ use ir::{
         StoreInstr,
         TailCallInstr,
         TerminalInstr,
+        TrapCode,
         TruncateIntInstr,
         UnaryFloatInstr,
         UnaryIntInstr,
     },
-    primitive::{Block, Const, FloatType, Func, IntType, Mem, Type, Value},
+    primitive::{
+        Block,
+        Const,
+        Data,
+        FloatType,
+        Func,
+        FuncType,
+        Global,
+        IntType,
+        Mem,
+        Table,
+        Type,
+        Value,
+    },
     ImmU32,
 };
 
@@ -127,6 +149,9 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         let block = self.builder.current_block()?;
         let instr = self.builder.ctx.instrs.alloc(instruction);
         self.builder.ctx.block_instrs[block].push(instr);
+        if let Some(span) = self.builder.ctx.source_span {
+            self.builder.ctx.instr_spans.insert(instr, span);
+        }
         for (n, output_type) in output_types.iter().copied().enumerate() {
             let value = self.builder.ctx.values.alloc_some(1);
             self.builder.ctx.instr_values[instr].push(value);
@@ -143,6 +168,13 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         Ok(instr)
     }
 
+    /// Calls the function with the given arguments.
+    ///
+    /// The call has an output value for every output of the called function.
+    ///
+    /// # Errors
+    ///
+    /// If the arguments do not match the inputs of the called function.
     pub fn call<P>(mut self, func: Func, params: P) -> Result<Instr, Error>
     where
         P: IntoIterator<Item = Value>,
@@ -155,16 +187,7 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
                 func
             )
             });
-        let param_types = instruction
-            .params()
-            .iter()
-            .copied()
-            .map(|val| self.builder.ctx.value_type[val]);
-        assert!(
-            // We might want to turn this into an error instead of panicking.
-            param_types.eq(func_type.inputs().iter().copied()),
-            "encountered mismatch between function parameter types and declaration types",
-        );
+        self.expect_argument_types(instruction.params(), func_type.inputs())?;
         let instr = self.append_multi_value_instr(
             instruction.into(),
             func_type.outputs(),
@@ -179,6 +202,62 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         Ok(instr)
     }
 
+    /// Calls the function at the `index` of the `table`.
+    ///
+    /// The called function is resolved at execution time and is expected
+    /// to be of the given function type.
+    ///
+    /// # Errors
+    ///
+    /// - If the `index` is not of type `i32`.
+    /// - If the arguments do not match the inputs of the function type.
+    pub fn call_indirect<P>(
+        mut self,
+        table: Table,
+        func_type: FuncType,
+        index: Value,
+        params: P,
+    ) -> Result<Instr, Error>
+    where
+        P: IntoIterator<Item = Value>,
+    {
+        self.expect_type(index, IntType::I32.into())?;
+        let instruction =
+            CallIndirectInstr::new(table, func_type, index, params);
+        let signature =
+            self.builder.res.get_type(func_type).unwrap_or_else(|| {
+                panic!(
+                    "encountered missing function type {} while building function {}",
+                    func_type, self.builder.func
+                )
+            });
+        self.expect_argument_types(instruction.params(), signature.inputs())?;
+        let instr = self.append_multi_value_instr(
+            instruction.into(),
+            signature.outputs(),
+        )?;
+        let call_instruction = match &self.builder.ctx.instrs[instr] {
+            Instruction::CallIndirect(call_instruction) => call_instruction,
+            _ => panic!("encountered unexpected instruction kind"),
+        };
+        let index = call_instruction.index();
+        for param in call_instruction.params().iter().copied() {
+            self.builder.ctx.value_users[param].insert(instr);
+        }
+        self.builder.ctx.value_users[index].insert(instr);
+        Ok(instr)
+    }
+
+    /// Calls the function and returns its results to the caller.
+    ///
+    /// The call replaces the frame of the current function instead of
+    /// pushing a new frame. Terminates the current basic block.
+    ///
+    /// # Errors
+    ///
+    /// - If the arguments do not match the inputs of the called function.
+    /// - If the output types of the called function do not match the output
+    ///   types of the current function.
     pub fn tail_call<P>(mut self, func: Func, params: P) -> Result<Instr, Error>
     where
         P: IntoIterator<Item = Value>,
@@ -191,17 +270,25 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
                 func
             )
             });
-        let param_types = instruction
-            .params()
-            .iter()
-            .copied()
-            .map(|val| self.builder.ctx.value_type[val]);
-        assert!(
-            param_types.eq(func_type.inputs().iter().copied()),
-            "encountered mismatch between function parameter types and declaration types",
-        );
-        // We have to query the type of the function `func` in the store.
-        // Currently we simply use `bool` as return type for all functions.
+        self.expect_argument_types(instruction.params(), func_type.inputs())?;
+        let expected_types = self
+            .builder
+            .res
+            .get_func_type(self.builder.func)
+            .unwrap_or_else(|| {
+                panic!(
+                    "encountered missing function type while building function {}",
+                    self.builder.func
+                )
+            })
+            .outputs();
+        if func_type.outputs() != expected_types {
+            return Err(FunctionBuilderError::UnmatchingFunctionReturnType {
+                returned_types: func_type.outputs().to_vec(),
+                expected_types: expected_types.to_vec(),
+            }
+            .into())
+        }
         let instr = self.append_instr(instruction)?;
         let call_instruction = match &self.builder.ctx.instrs[instr] {
             Instruction::Terminal(TerminalInstr::TailCall(
@@ -261,6 +348,41 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         Ok(())
     }
 
+    /// Returns `Ok` if the types of the arguments match the input types of a
+    /// called function.
+    ///
+    /// # Errors
+    ///
+    /// If the number or the types of the arguments do not match.
+    fn expect_argument_types(
+        &self,
+        args: &[Value],
+        input_types: &[Type],
+    ) -> Result<(), Error> {
+        let given_types =
+            args.iter().map(|&arg| self.builder.ctx.value_type[arg]);
+        if !given_types.clone().eq(input_types.iter().copied()) {
+            return Err(FunctionBuilderError::UnmatchingCallArgumentTypes {
+                given_types: given_types.collect(),
+                expected_types: input_types.to_vec(),
+            }
+            .into())
+        }
+        Ok(())
+    }
+
+    /// Returns `Ok` if the linear memory exists in the module.
+    ///
+    /// # Errors
+    ///
+    /// If the linear memory does not exist.
+    fn expect_memory(&self, mem: Mem) -> Result<(), Error> {
+        if self.builder.res.get_memory_decl(mem).is_none() {
+            return Err(FunctionBuilderError::InvalidMemory { mem }.into())
+        }
+        Ok(())
+    }
+
     /// Convenience function to construct unary integer instructions.
     fn iunary(
         mut self,
@@ -695,6 +817,13 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     /// # Note
     ///
     /// This is very similar to an if-then-else instruction that does not require jumps.
+    /// The `condition` is either of type `bool` or of type `i32` in which case
+    /// any non-zero value selects `if_true` as in Wasm.
+    ///
+    /// # Errors
+    ///
+    /// - If the `condition` is neither of type `bool` nor of type `i32`.
+    /// - If `if_true` or `if_false` are not of type `ty`.
     pub fn select(
         mut self,
         ty: Type,
@@ -702,6 +831,10 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         if_true: Value,
         if_false: Value,
     ) -> Result<Value, Error> {
+        let condition_type = self.builder.ctx.value_type[condition];
+        if condition_type != IntType::I32.into() {
+            self.expect_type(condition, Type::Bool)?;
+        }
         self.expect_type(if_true, ty)?;
         self.expect_type(if_false, ty)?;
         let instruction = SelectInstr::new(condition, ty, if_true, if_false);
@@ -721,7 +854,8 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     ///
     /// # Errors
     ///
-    /// If source and destination types have different bit widths.
+    /// - If source and destination types have different bit widths.
+    /// - If the source value is not of the source type.
     pub fn reinterpret(
         mut self,
         from_type: Type,
@@ -738,6 +872,7 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
             })
             .map_err(Into::into)
         }
+        self.expect_type(src, from_type)?;
         let instruction = ReinterpretInstr::new(from_type, to_type, src);
         let (value, instr) =
             self.append_value_instr(instruction.into(), to_type)?;
@@ -749,8 +884,9 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     ///
     /// # Errors
     ///
-    /// If the destination integer type does not have a bit-width greater than or equal
-    /// to the source type.
+    /// - If the destination integer type does not have a bit-width greater than or equal
+    ///   to the source type.
+    /// - If the source value is not of the source type.
     pub fn iextend(
         mut self,
         from_type: IntType,
@@ -765,6 +901,7 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
             })
             .map_err(Into::into)
         }
+        self.expect_type(src, from_type.into())?;
         let instruction = ExtendIntInstr::new(signed, from_type, to_type, src);
         let (value, instr) =
             self.append_value_instr(instruction.into(), to_type.into())?;
@@ -776,8 +913,9 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     ///
     /// # Errors
     ///
-    /// If the destination integer type does not have a bit-width greater than or equal
-    /// to the source type.
+    /// - If the source integer type does not have a bit-width greater than or equal
+    ///   to the destination type.
+    /// - If the source value is not of the source type.
     pub fn itruncate(
         mut self,
         from_type: IntType,
@@ -791,6 +929,7 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
             })
             .map_err(Into::into)
         }
+        self.expect_type(src, from_type.into())?;
         let instruction = TruncateIntInstr::new(from_type, to_type, src);
         let (value, instr) =
             self.append_value_instr(instruction.into(), to_type.into())?;
@@ -802,7 +941,8 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     ///
     /// # Errors
     ///
-    /// If the source type is bigger than the promoted-to type.
+    /// - If the source type is bigger than the promoted-to type.
+    /// - If the source value is not of the source type.
     pub fn promote(
         mut self,
         from_type: FloatType,
@@ -816,6 +956,7 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
             })
             .map_err(Into::into)
         }
+        self.expect_type(src, from_type.into())?;
         let instruction = PromoteFloatInstr::new(from_type, to_type, src);
         let (value, instr) =
             self.append_value_instr(instruction.into(), to_type.into())?;
@@ -823,11 +964,12 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         Ok(value)
     }
 
-    /// Demotes the source float to the other (bigger) float type.
+    /// Demotes the source float to the other (smaller) float type.
     ///
     /// # Errors
     ///
-    /// If the source type is smaller than the demoted-to type.
+    /// - If the source type is smaller than the demoted-to type.
+    /// - If the source value is not of the source type.
     pub fn demote(
         mut self,
         from_type: FloatType,
@@ -835,12 +977,13 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         src: Value,
     ) -> Result<Value, Error> {
         if from_type.bit_width() < to_type.bit_width() {
-            return Err(FunctionBuilderError::InvalidPromotion {
+            return Err(FunctionBuilderError::InvalidDemotion {
                 from_type,
                 to_type,
-            })
-            .map_err(Into::into)
+            }
+            .into())
         }
+        self.expect_type(src, from_type.into())?;
         let instruction = DemoteFloatInstr::new(from_type, to_type, src);
         let (value, instr) =
             self.append_value_instr(instruction.into(), to_type.into())?;
@@ -854,6 +997,10 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     ///   value shall be treated as if the integer is signed.
     /// - The `saturating` flag determines if the conversion may
     ///   trap upon failure or simply saturates to integer boundaries.
+    ///
+    /// # Errors
+    ///
+    /// If the source value is not of the source type.
     pub fn float_to_int(
         mut self,
         src_type: FloatType,
@@ -862,6 +1009,7 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         src: Value,
         saturating: bool,
     ) -> Result<Value, Error> {
+        self.expect_type(src, src_type.into())?;
         let instruction = FloatToIntInstr::new(
             src_type, dst_type, dst_signed, src, saturating,
         );
@@ -875,6 +1023,10 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     ///
     /// - The `signed` flag determines if the source integer
     ///   value shall be treated as if the integer is signed.
+    ///
+    /// # Errors
+    ///
+    /// If the source value is not of the source type.
     pub fn int_to_float(
         mut self,
         signed: bool,
@@ -882,6 +1034,7 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         dst_type: FloatType,
         src: Value,
     ) -> Result<Value, Error> {
+        self.expect_type(src, src_type.into())?;
         let instruction = IntToFloatInstr::new(signed, src_type, dst_type, src);
         let (value, instr) =
             self.append_value_instr(instruction.into(), dst_type.into())?;
@@ -893,12 +1046,18 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     ///
     /// The returned value can be used to load and store values from and to linear memory
     /// with an offset that is valid for the given size.
+    ///
+    /// # Errors
+    ///
+    /// - If the linear memory does not exist.
+    /// - If the byte position is not of type `i32`.
     pub fn heap_addr(
         mut self,
         mem: Mem,
         pos: Value,
         size: ImmU32,
     ) -> Result<Value, Error> {
+        self.expect_memory(mem)?;
         self.expect_type(pos, IntType::I32.into())?;
         let instruction = HeapAddrInstr::new(mem, pos, size);
         let (value, instr) =
@@ -907,7 +1066,66 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         Ok(value)
     }
 
+    /// Returns a pointer to the start of the read-only data object.
+    pub fn data_addr(mut self, data: Data) -> Result<Value, Error> {
+        if self.builder.res.get_read_only_data(data).is_none() {
+            return Err(
+                FunctionBuilderError::InvalidReadOnlyData { data }.into()
+            )
+        }
+        let instruction = DataAddrInstr::new(data);
+        let (value, _) =
+            self.append_value_instr(instruction.into(), Type::Ptr)?;
+        Ok(value)
+    }
+
+    /// Returns the current value of the global variable.
+    pub fn global_get(mut self, global: Global) -> Result<Value, Error> {
+        let ty = match self.builder.res.get_global_decl(global) {
+            Some(decl) => decl.ty(),
+            None => {
+                return Err(
+                    FunctionBuilderError::InvalidGlobal { global }.into()
+                )
+            }
+        };
+        let instruction = GlobalGetInstr::new(global, ty);
+        let (value, _) = self.append_value_instr(instruction.into(), ty)?;
+        Ok(value)
+    }
+
+    /// Sets the value of the mutable global variable.
+    pub fn global_set(
+        mut self,
+        global: Global,
+        new_value: Value,
+    ) -> Result<Instr, Error> {
+        let decl = match self.builder.res.get_global_decl(global) {
+            Some(decl) => decl,
+            None => {
+                return Err(
+                    FunctionBuilderError::InvalidGlobal { global }.into()
+                )
+            }
+        };
+        if !decl.is_mutable() {
+            return Err(
+                FunctionBuilderError::ImmutableGlobalWrite { global }.into()
+            )
+        }
+        let ty = decl.ty();
+        self.expect_type(new_value, ty)?;
+        let instruction = GlobalSetInstr::new(global, new_value, ty);
+        let instr = self.append_instr(instruction)?;
+        self.register_uses(instr, [new_value].iter().copied());
+        Ok(instr)
+    }
+
     /// Loads a value of the given type from the pointer with given offset.
+    ///
+    /// # Errors
+    ///
+    /// If the pointer is not of type `ptr`.
     pub fn load(
         mut self,
         ptr: Value,
@@ -922,6 +1140,11 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
     }
 
     /// Stores the given value of the given type to the pointer with given offset.
+    ///
+    /// # Errors
+    ///
+    /// - If the pointer is not of type `ptr`.
+    /// - If the stored value is not of the given type.
     pub fn store(
         mut self,
         ptr: Value,
@@ -930,12 +1153,49 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         ty: Type,
     ) -> Result<Instr, Error> {
         self.expect_type(ptr, Type::Ptr)?;
+        self.expect_type(stored_value, ty)?;
         let instruction = StoreInstr::new(ptr, offset, stored_value, ty);
         let instr = self.append_instr(instruction)?;
         self.register_uses(instr, [ptr, stored_value].iter().copied());
         Ok(instr)
     }
 
+    /// Returns the current size of the linear memory in pages.
+    ///
+    /// # Errors
+    ///
+    /// If the linear memory does not exist.
+    pub fn memory_size(mut self, mem: Mem) -> Result<Value, Error> {
+        self.expect_memory(mem)?;
+        let instruction = MemorySizeInstr::new(mem);
+        let (value, _) =
+            self.append_value_instr(instruction.into(), IntType::I32.into())?;
+        Ok(value)
+    }
+
+    /// Grows the linear memory by the given amount of new pages.
+    ///
+    /// Returns the previous size of the linear memory in pages or -1 if
+    /// the linear memory could not grow by the requested amount of pages.
+    ///
+    /// # Errors
+    ///
+    /// - If the linear memory does not exist.
+    /// - If the amount of new pages is not of type `i32`.
+    pub fn memory_grow(
+        mut self,
+        mem: Mem,
+        new_pages: Value,
+    ) -> Result<Value, Error> {
+        self.expect_memory(mem)?;
+        self.expect_type(new_pages, IntType::I32.into())?;
+        let instruction = MemoryGrowInstr::new(mem, new_pages);
+        let (value, instr) =
+            self.append_value_instr(instruction.into(), IntType::I32.into())?;
+        self.register_uses(instr, [new_pages].iter().copied());
+        Ok(value)
+    }
+
     /// Appends the instruction onto the current basic block.
     ///
     /// Fills the block in case the instruction is a terminal instruction.
@@ -948,6 +1208,9 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         let is_terminal = instruction.is_terminal();
         let instr = self.builder.ctx.instrs.alloc(instruction);
         self.builder.ctx.block_instrs[block].push(instr);
+        if let Some(span) = self.builder.ctx.source_span {
+            self.builder.ctx.instr_spans.insert(instr, span);
+        }
         if is_terminal {
             self.builder.ctx.block_filled.set(block, true);
         }
@@ -996,9 +1259,62 @@ impl<'a, 'b: 'a> InstructionBuilder<'a, 'b> {
         Ok(instr)
     }
 
-    /// Immediately traps or aborts execution.
-    pub fn trap(mut self) -> Result<Instr, Error> {
-        self.append_instr(TerminalInstr::Trap)
+    /// Jumps to the target at the index of the `case` value or to the
+    /// `default` target if the index is out of bounds.
+    ///
+    /// # Note
+    ///
+    /// Registers the current basic block as predecessor of every distinct
+    /// target including the default target and fills the current block.
+    ///
+    /// # Errors
+    ///
+    /// - If the `case` value is not of type `i32`.
+    /// - If one of the targets has already been sealed.
+    pub fn br_table<T>(
+        self,
+        case: Value,
+        targets: T,
+        default: Block,
+    ) -> Result<Instr, Error>
+    where
+        T: IntoIterator<Item = Block>,
+    {
+        self.branch_table(BranchTableInstr::new(case, default, targets))
+    }
+
+    /// Jumps to the target of the branching table that matches its case value.
+    ///
+    /// # Note
+    ///
+    /// Registers the current basic block as predecessor of every distinct
+    /// target of the branching table including its default target.
+    pub(super) fn branch_table(
+        mut self,
+        branch_table: BranchTableInstr,
+    ) -> Result<Instr, Error> {
+        let case = branch_table.case();
+        self.expect_type(case, IntType::I32.into())?;
+        let block = self.builder.current_block()?;
+        let mut targets = branch_table
+            .targets()
+            .iter()
+            .chain(Some(branch_table.default_target()))
+            .map(BranchTarget::block)
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets.dedup();
+        let instr = self.append_instr(branch_table)?;
+        for target in targets {
+            self.add_predecessor(target, block)?;
+        }
+        self.register_uses(instr, [case].iter().copied());
+        Ok(instr)
+    }
+
+    /// Immediately traps or aborts execution for the given reason.
+    pub fn trap(mut self, code: TrapCode) -> Result<Instr, Error> {
+        self.append_instr(TerminalInstr::Trap(code))
     }
 
     /// Conditionally jumps to either `then_target` or `else_target` depending on
@@ -0,0 +1,287 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Instrumentation of function bodies with calls to hook functions.
+//!
+//! Allows embedders to trace or meter the execution of functions without
+//! modifying the interpreter by calling designated hook functions upon
+//! function entry and exit as well as upon linear memory accesses.
+
+use super::{FunctionBody, Instr, ValueAssoc};
+use ir::{
+    instr::{
+        operands::BinaryIntOp,
+        BinaryIntInstr,
+        CallInstr,
+        ConstInstr,
+        Instruction,
+        TerminalInstr,
+    },
+    primitive::{Block, Func, IntConst, IntType, Type, Value},
+};
+
+/// The hook functions that instrumented function bodies call.
+///
+/// Usually hooks are imported functions so that the embedder can react to
+/// them. All hooks return nothing.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InstrumentationHooks {
+    /// The hook called upon function entry if any.
+    entry: Option<Func>,
+    /// The hook called before returning from a function if any.
+    exit: Option<Func>,
+    /// The hook called before accessing linear memory if any.
+    memory_access: Option<Func>,
+}
+
+impl InstrumentationHooks {
+    /// Calls `hook` upon entry of every instrumented function.
+    ///
+    /// The hook takes the index of the function as its only `i32` parameter.
+    pub fn set_entry_hook(&mut self, hook: Func) {
+        self.entry = Some(hook);
+    }
+
+    /// Calls `hook` before every instrumented function returns.
+    ///
+    /// The hook takes the index of the function as its only `i32` parameter.
+    ///
+    /// # Note
+    ///
+    /// Tail calls also exit the calling function and therefore call the
+    /// hook before the tail call. Traps do not call the hook.
+    pub fn set_exit_hook(&mut self, hook: Func) {
+        self.exit = Some(hook);
+    }
+
+    /// Calls `hook` before every load from or store to linear memory.
+    ///
+    /// The hook takes the accessed `i32` address, the number of accessed
+    /// bytes and `1` for stores or `0` for loads as `i32` parameters.
+    ///
+    /// # Note
+    ///
+    /// Only accesses through pointers returned by `heap_addr` instructions
+    /// are instrumented. Reads of read-only data are not.
+    pub fn set_memory_access_hook(&mut self, hook: Func) {
+        self.memory_access = Some(hook);
+    }
+
+    /// Returns the hook called upon function entry if any.
+    pub fn entry_hook(&self) -> Option<Func> {
+        self.entry
+    }
+
+    /// Returns the hook called before returning from a function if any.
+    pub fn exit_hook(&self) -> Option<Func> {
+        self.exit
+    }
+
+    /// Returns the hook called before accessing linear memory if any.
+    pub fn memory_access_hook(&self) -> Option<Func> {
+        self.memory_access
+    }
+
+    /// Returns an iterator over all hook functions.
+    pub fn hooks(&self) -> impl Iterator<Item = Func> {
+        self.entry
+            .into_iter()
+            .chain(self.exit)
+            .chain(self.memory_access)
+    }
+}
+
+impl FunctionBody {
+    /// Inserts calls to the hooks into the function body of `func`.
+    ///
+    /// Returns the number of inserted hook calls.
+    ///
+    /// # Note
+    ///
+    /// The function body itself does not know the function types of the
+    /// hooks. Use [`Module::instrument`] in order to instrument all internal
+    /// functions of a module with validated hooks.
+    ///
+    /// [`Module::instrument`]: crate::Module::instrument
+    pub fn instrument(
+        &mut self,
+        func: Func,
+        hooks: &InstrumentationHooks,
+    ) -> usize {
+        let mut len_inserted = 0;
+        if let Some(hook) = hooks.entry_hook() {
+            let entry = self.entry_block();
+            let mut position = 0;
+            let index = self.insert_func_index(entry, &mut position, func);
+            self.insert_call(entry, &mut position, hook, &[index]);
+            len_inserted += 1;
+        }
+        if let Some(hook) = hooks.exit_hook() {
+            let exits = self
+                .blocks()
+                .filter(|&block| {
+                    matches!(
+                        self.terminal(block),
+                        Some(TerminalInstr::Return(_))
+                            | Some(TerminalInstr::TailCall(_))
+                            | Some(TerminalInstr::TailCallIndirect(_))
+                    )
+                })
+                .collect::<Vec<_>>();
+            for block in exits {
+                let mut position = self.block_instrs[block].len() - 1;
+                let index = self.insert_func_index(block, &mut position, func);
+                self.insert_call(block, &mut position, hook, &[index]);
+                len_inserted += 1;
+            }
+        }
+        if let Some(hook) = hooks.memory_access_hook() {
+            let blocks = self.blocks().collect::<Vec<_>>();
+            for block in blocks {
+                let mut position = 0;
+                while position < self.block_instrs[block].len() {
+                    let instr = self.block_instrs[block][position];
+                    if self.insert_memory_access_call(
+                        block,
+                        &mut position,
+                        hook,
+                        instr,
+                    ) {
+                        len_inserted += 1;
+                    }
+                    position += 1;
+                }
+            }
+        }
+        len_inserted
+    }
+
+    /// Inserts a call to `hook` before the instruction at the position if
+    /// it loads from or stores to linear memory.
+    ///
+    /// Returns `true` if the call has been inserted.
+    fn insert_memory_access_call(
+        &mut self,
+        block: Block,
+        position: &mut usize,
+        hook: Func,
+        instr: Instr,
+    ) -> bool {
+        let (ptr, offset, ty, is_write) = match &self.instrs[instr] {
+            Instruction::Load(load) => {
+                (load.address(), load.offset(), load.ty(), false)
+            }
+            Instruction::Store(store) => {
+                (store.address(), store.offset(), store.ty(), true)
+            }
+            _ => return false,
+        };
+        let base = match self.heap_addr_base(ptr) {
+            Some(base) => base,
+            None => return false,
+        };
+        let offset = offset.into_u32();
+        let address = match offset {
+            0 => base,
+            offset => {
+                let offset = self.insert_i32(block, position, offset as i32);
+                let add = BinaryIntInstr::new(
+                    BinaryIntOp::Add,
+                    IntType::I32,
+                    base,
+                    offset,
+                );
+                self.insert_value(block, position, add.into(), IntType::I32)
+            }
+        };
+        let size = ty.bit_width().div_ceil(8) as i32;
+        let size = self.insert_i32(block, position, size);
+        let is_write = self.insert_i32(block, position, is_write as i32);
+        self.insert_call(block, position, hook, &[address, size, is_write]);
+        true
+    }
+
+    /// Returns the `i32` address of the pointer if it is returned by a
+    /// `heap_addr` instruction.
+    fn heap_addr_base(&self, ptr: Value) -> Option<Value> {
+        let instr = match self.value_assoc.get(ptr)? {
+            ValueAssoc::Instr(instr, _) => *instr,
+            ValueAssoc::Input(_) | ValueAssoc::Param(..) => return None,
+        };
+        match &self.instrs[instr] {
+            Instruction::HeapAddr(heap_addr) => Some(heap_addr.ptr()),
+            _ => None,
+        }
+    }
+
+    /// Inserts the index of `func` as `i32` constant.
+    fn insert_func_index(
+        &mut self,
+        block: Block,
+        position: &mut usize,
+        func: Func,
+    ) -> Value {
+        let index = func.into_raw().into_u32() as i32;
+        self.insert_i32(block, position, index)
+    }
+
+    /// Inserts the `i32` constant.
+    fn insert_i32(
+        &mut self,
+        block: Block,
+        position: &mut usize,
+        value: i32,
+    ) -> Value {
+        let constant = ConstInstr::new(IntConst::I32(value).into());
+        self.insert_value(block, position, constant.into(), IntType::I32)
+    }
+
+    /// Inserts the instruction producing a single value of the given type.
+    ///
+    /// The instruction is inserted before the instruction at the position
+    /// which is advanced past the inserted instruction.
+    fn insert_value(
+        &mut self,
+        block: Block,
+        position: &mut usize,
+        instruction: Instruction,
+        ty: IntType,
+    ) -> Value {
+        let instr = self.instrs.alloc(instruction);
+        let value = self.values.alloc_some(1);
+        self.value_type.insert(value, Type::from(ty));
+        self.value_assoc.insert(value, ValueAssoc::Instr(instr, 0));
+        self.instr_values[instr].push(Some(value));
+        self.block_instrs[block].insert(*position, instr);
+        *position += 1;
+        value
+    }
+
+    /// Inserts a call to `hook` with the parameters.
+    ///
+    /// The call is inserted before the instruction at the position which is
+    /// advanced past the inserted call.
+    fn insert_call(
+        &mut self,
+        block: Block,
+        position: &mut usize,
+        hook: Func,
+        params: &[Value],
+    ) {
+        let call = CallInstr::new(hook, params.iter().copied());
+        let instr = self.instrs.alloc(call.into());
+        self.block_instrs[block].insert(*position, instr);
+        *position += 1;
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Legalization of function bodies for code generation backends.
+//!
+//! Backends usually support only a subset of the Runwell IR. The legalization
+//! rewrites all constructs that are unsupported by a backend in terms of
+//! supported ones. This keeps the mid-end simple while still enabling
+//! multiple targets with different constraints.
+
+use super::{FunctionBody, FunctionEditorError, Instr, ValueAssoc};
+use crate::Error;
+use ir::{
+    instr::{
+        operands::{BinaryIntOp, CompareIntOp, ShiftIntOp, UnaryIntOp},
+        BinaryIntInstr, CompareIntInstr, ConstInstr, ExtendIntInstr,
+        Instruction, IntInstr, IntToFloatInstr, ShiftIntInstr,
+        TruncateIntInstr, UnaryIntInstr,
+    },
+    primitive::{Block, IntConst, IntType, Type, Value},
+};
+
+/// The constraints of a code generation backend.
+///
+/// By default all Runwell IR constructs are assumed to be supported.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TargetConstraints {
+    /// The narrowest integer type supported by integer operations if any.
+    min_int_type: Option<IntType>,
+}
+
+impl TargetConstraints {
+    /// Requires integer operations to operate on at least `int_type`.
+    ///
+    /// Narrower integer operations are widened by extending their operands
+    /// and truncating their results. Integer conversions, constants as well
+    /// as loads and stores are not affected.
+    pub fn set_min_int_type(&mut self, int_type: IntType) {
+        self.min_int_type = Some(int_type);
+    }
+
+    /// Returns the narrowest integer type supported by integer operations if any.
+    pub fn min_int_type(&self) -> Option<IntType> {
+        self.min_int_type
+    }
+}
+
+impl FunctionBody {
+    /// Rewrites all instructions that are unsupported under the constraints.
+    ///
+    /// Returns the number of rewritten instructions.
+    ///
+    /// # Errors
+    ///
+    /// If an unsupported instruction has no legalization, e.g. narrow
+    /// rotations or narrow signed divisions.
+    pub fn legalize(
+        &mut self,
+        constraints: &TargetConstraints,
+    ) -> Result<usize, Error> {
+        let blocks = self.blocks().collect::<Vec<_>>();
+        let mut legalizer = Legalizer {
+            body: self,
+            constraints,
+            len_rewritten: 0,
+        };
+        for block in blocks {
+            legalizer.legalize_block(block)?;
+        }
+        Ok(legalizer.len_rewritten)
+    }
+}
+
+/// Rewrites the instructions of a function body in place.
+#[derive(Debug)]
+struct Legalizer<'a> {
+    body: &'a mut FunctionBody,
+    constraints: &'a TargetConstraints,
+    len_rewritten: usize,
+}
+
+/// The position before which new instructions are inserted.
+#[derive(Debug, Copy, Clone)]
+struct Cursor {
+    block: Block,
+    position: usize,
+}
+
+impl<'a> Legalizer<'a> {
+    /// Legalizes all instructions of the basic block.
+    fn legalize_block(&mut self, block: Block) -> Result<(), Error> {
+        let mut cursor = Cursor { block, position: 0 };
+        while cursor.position < self.body.block_instrs[block].len() {
+            let instr = self.body.block_instrs[block][cursor.position];
+            if let Instruction::Int(instruction) = &self.body.instrs[instr] {
+                let instruction = instruction.clone();
+                if self.legalize_int(&mut cursor, instr, instruction)? {
+                    self.len_rewritten += 1;
+                }
+            }
+            cursor.position += 1;
+        }
+        Ok(())
+    }
+
+    /// Inserts the instruction producing a single value of the given type.
+    fn insert(
+        &mut self,
+        cursor: &mut Cursor,
+        instruction: Instruction,
+        ty: Type,
+    ) -> Value {
+        let instr = self.body.instrs.alloc(instruction);
+        let value = self.body.values.alloc_some(1);
+        self.body.value_type.insert(value, ty);
+        self.body
+            .value_assoc
+            .insert(value, ValueAssoc::Instr(instr, 0));
+        self.body.instr_values[instr].push(Some(value));
+        self.body.block_instrs[cursor.block].insert(cursor.position, instr);
+        cursor.position += 1;
+        value
+    }
+
+    /// Inserts an integer constant of the given type.
+    fn insert_const(
+        &mut self,
+        cursor: &mut Cursor,
+        ty: IntType,
+        value: u64,
+    ) -> Value {
+        let value = match ty {
+            IntType::I8 => IntConst::I8(value as i8),
+            IntType::I16 => IntConst::I16(value as i16),
+            IntType::I32 => IntConst::I32(value as i32),
+            IntType::I64 => IntConst::I64(value as i64),
+        };
+        self.insert(cursor, ConstInstr::new(value.into()).into(), ty.into())
+    }
+
+    /// Inserts the extension of the narrow value to the wide integer type.
+    fn insert_extend(
+        &mut self,
+        cursor: &mut Cursor,
+        signed: bool,
+        narrow: IntType,
+        wide: IntType,
+        value: Value,
+    ) -> Value {
+        let instruction = ExtendIntInstr::new(signed, narrow, wide, value);
+        self.insert(cursor, instruction.into(), wide.into())
+    }
+
+    /// Replaces the instruction in place while keeping its output values.
+    fn replace(&mut self, instr: Instr, instruction: Instruction) {
+        self.body.instrs[instr] = instruction;
+    }
+
+    /// Returns the integer type the narrow integer type has to be widened to if any.
+    fn widened(&self, ty: IntType) -> Option<IntType> {
+        self.constraints
+            .min_int_type()
+            .filter(|min| min.bit_width() > ty.bit_width())
+    }
+
+    /// Legalizes the integer instruction.
+    ///
+    /// Returns `true` if the instruction has been rewritten.
+    fn legalize_int(
+        &mut self,
+        cursor: &mut Cursor,
+        instr: Instr,
+        instruction: IntInstr,
+    ) -> Result<bool, Error> {
+        match instruction {
+            IntInstr::Binary(instruction) => {
+                let narrow = instruction.ty();
+                let wide = match self.widened(narrow) {
+                    Some(wide) => wide,
+                    None => return Ok(false),
+                };
+                let op = instruction.op();
+                if let BinaryIntOp::Sdiv | BinaryIntOp::Srem = op {
+                    // The widened operation would no longer trap on overflow.
+                    return Err(FunctionEditorError::IllegalInstruction {
+                        instr,
+                    }
+                    .into())
+                }
+                let lhs = instruction.lhs();
+                let rhs = instruction.rhs();
+                let lhs = self.insert_extend(cursor, false, narrow, wide, lhs);
+                let rhs = self.insert_extend(cursor, false, narrow, wide, rhs);
+                let result = self.insert(
+                    cursor,
+                    BinaryIntInstr::new(op, wide, lhs, rhs).into(),
+                    wide.into(),
+                );
+                self.replace(
+                    instr,
+                    TruncateIntInstr::new(wide, narrow, result).into(),
+                );
+            }
+            IntInstr::Compare(instruction) => {
+                let narrow = instruction.ty();
+                let wide = match self.widened(narrow) {
+                    Some(wide) => wide,
+                    None => return Ok(false),
+                };
+                let signed = matches!(
+                    instruction.op(),
+                    CompareIntOp::Slt
+                        | CompareIntOp::Sle
+                        | CompareIntOp::Sgt
+                        | CompareIntOp::Sge
+                );
+                let lhs = instruction.lhs();
+                let rhs = instruction.rhs();
+                let lhs = self.insert_extend(cursor, signed, narrow, wide, lhs);
+                let rhs = self.insert_extend(cursor, signed, narrow, wide, rhs);
+                self.replace(
+                    instr,
+                    CompareIntInstr::new(instruction.op(), wide, lhs, rhs)
+                        .into(),
+                );
+            }
+            IntInstr::Unary(instruction) => {
+                let narrow = instruction.ty();
+                let wide = match self.widened(narrow) {
+                    Some(wide) => wide,
+                    None => return Ok(false),
+                };
+                let src = instruction.src();
+                let mut src =
+                    self.insert_extend(cursor, false, narrow, wide, src);
+                if let UnaryIntOp::TrailingZeros = instruction.op() {
+                    // Bounds the count to the bit width of the narrow type.
+                    let bound = self.insert_const(
+                        cursor,
+                        wide,
+                        1 << narrow.bit_width(),
+                    );
+                    src = self.insert(
+                        cursor,
+                        BinaryIntInstr::new(BinaryIntOp::Or, wide, src, bound)
+                            .into(),
+                        wide.into(),
+                    );
+                }
+                let mut result = self.insert(
+                    cursor,
+                    UnaryIntInstr::new(instruction.op(), wide, src).into(),
+                    wide.into(),
+                );
+                if let UnaryIntOp::LeadingZeros = instruction.op() {
+                    // Discounts the leading zeros introduced by the extension.
+                    let delta = wide.bit_width() - narrow.bit_width();
+                    let delta = self.insert_const(cursor, wide, delta.into());
+                    result = self.insert(
+                        cursor,
+                        BinaryIntInstr::new(
+                            BinaryIntOp::Sub,
+                            wide,
+                            result,
+                            delta,
+                        )
+                        .into(),
+                        wide.into(),
+                    );
+                }
+                self.replace(
+                    instr,
+                    TruncateIntInstr::new(wide, narrow, result).into(),
+                );
+            }
+            IntInstr::Shift(instruction) => {
+                let narrow = instruction.ty();
+                let wide = match self.widened(narrow) {
+                    Some(wide) => wide,
+                    None => return Ok(false),
+                };
+                let signed = match instruction.op() {
+                    ShiftIntOp::Shl | ShiftIntOp::Ushr => false,
+                    ShiftIntOp::Sshr => true,
+                    ShiftIntOp::Rotl | ShiftIntOp::Rotr => {
+                        return Err(FunctionEditorError::IllegalInstruction {
+                            instr,
+                        }
+                        .into())
+                    }
+                };
+                let source = instruction.source();
+                let source =
+                    self.insert_extend(cursor, signed, narrow, wide, source);
+                // The shift amount has to be masked by the narrow bit width.
+                let mask = u64::from(narrow.bit_width() - 1);
+                let mask = self.insert_const(cursor, IntType::I32, mask);
+                let amount = self.insert(
+                    cursor,
+                    BinaryIntInstr::new(
+                        BinaryIntOp::And,
+                        IntType::I32,
+                        instruction.shift_amount(),
+                        mask,
+                    )
+                    .into(),
+                    IntType::I32.into(),
+                );
+                let result = self.insert(
+                    cursor,
+                    ShiftIntInstr::new(instruction.op(), wide, source, amount)
+                        .into(),
+                    wide.into(),
+                );
+                self.replace(
+                    instr,
+                    TruncateIntInstr::new(wide, narrow, result).into(),
+                );
+            }
+            IntInstr::IntToFloat(instruction) => {
+                let narrow = instruction.src_type();
+                let wide = match self.widened(narrow) {
+                    Some(wide) => wide,
+                    None => return Ok(false),
+                };
+                let signed = instruction.is_signed();
+                let src = instruction.src();
+                let src = self.insert_extend(cursor, signed, narrow, wide, src);
+                self.replace(
+                    instr,
+                    IntToFloatInstr::new(
+                        signed,
+                        wide,
+                        instruction.dst_type(),
+                        src,
+                    )
+                    .into(),
+                );
+            }
+            IntInstr::Extend(_) | IntInstr::Truncate(_) => return Ok(false),
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Side tables to attach arbitrary metadata to function body entities.
+//!
+//! Passes can use these side tables to annotate instructions, basic blocks
+//! or SSA values with additional information, e.g. gas costs, profile counts
+//! or alias classes, without having to modify the instruction types.
+
+use core::{
+    any::{type_name, Any, TypeId},
+    fmt,
+};
+use entity::{ComponentMap, Idx};
+use std::collections::HashMap;
+
+/// A kind of metadata that can be attached to function body entities.
+///
+/// The implementing type itself is used as key to register the kind
+/// of metadata with [`Metadata`]. Usually it is an empty marker type.
+pub trait MetadataKind: 'static {
+    /// The entity type the metadata is attached to.
+    ///
+    /// E.g. `Instruction`, `BlockEntity` or `ValueEntity`.
+    type Entity: 'static;
+    /// The type of the attached metadata.
+    type Value: Send + Sync + 'static;
+
+    /// `true` if the metadata stays valid when the function body is mutated.
+    ///
+    /// Metadata kinds that are not preserved are dropped upon
+    /// [`Metadata::invalidate`].
+    const PRESERVE_ON_MUTATION: bool = false;
+}
+
+/// The side table for a single kind of metadata.
+struct MetadataTable {
+    /// The name of the metadata kind for debugging purposes.
+    name: &'static str,
+    /// `true` if the metadata stays valid when the function body is mutated.
+    preserve: bool,
+    /// The type erased `ComponentMap<Idx<M::Entity>, M::Value>`.
+    components: Box<dyn Any + Send + Sync>,
+}
+
+/// A registry of side tables that store metadata for function body entities.
+///
+/// Every [`MetadataKind`] has its own side table that is created lazily
+/// upon its first insertion.
+#[derive(Default)]
+pub struct Metadata {
+    tables: HashMap<TypeId, MetadataTable, ahash::RandomState>,
+}
+
+impl fmt::Debug for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.tables.values().map(|table| table.name))
+            .finish()
+    }
+}
+
+impl Metadata {
+    /// Returns the side table of the metadata kind if any.
+    pub fn table<M>(&self) -> Option<&ComponentMap<Idx<M::Entity>, M::Value>>
+    where
+        M: MetadataKind,
+    {
+        self.tables.get(&TypeId::of::<M>()).map(|table| {
+            table
+                .components
+                .downcast_ref()
+                .expect("encountered metadata table with mismatching type")
+        })
+    }
+
+    /// Returns the side table of the metadata kind if any.
+    fn table_mut<M>(
+        &mut self,
+    ) -> Option<&mut ComponentMap<Idx<M::Entity>, M::Value>>
+    where
+        M: MetadataKind,
+    {
+        self.tables.get_mut(&TypeId::of::<M>()).map(|table| {
+            table
+                .components
+                .downcast_mut()
+                .expect("encountered metadata table with mismatching type")
+        })
+    }
+
+    /// Returns `true` if there is a side table for the metadata kind.
+    pub fn contains<M>(&self) -> bool
+    where
+        M: MetadataKind,
+    {
+        self.tables.contains_key(&TypeId::of::<M>())
+    }
+
+    /// Attaches the metadata to the entity.
+    ///
+    /// Returns the metadata of the same kind that was previously attached to the entity.
+    pub fn insert<M>(
+        &mut self,
+        entity: Idx<M::Entity>,
+        value: M::Value,
+    ) -> Option<M::Value>
+    where
+        M: MetadataKind,
+    {
+        self.tables
+            .entry(TypeId::of::<M>())
+            .or_insert_with(|| {
+                MetadataTable {
+                    name: type_name::<M>(),
+                    preserve: M::PRESERVE_ON_MUTATION,
+                    components: Box::new(<ComponentMap<
+                        Idx<M::Entity>,
+                        M::Value,
+                    >>::default()),
+                }
+            })
+            .components
+            .downcast_mut::<ComponentMap<Idx<M::Entity>, M::Value>>()
+            .expect("encountered metadata table with mismatching type")
+            .insert(entity, value)
+    }
+
+    /// Returns the metadata of the kind attached to the entity if any.
+    pub fn get<M>(&self, entity: Idx<M::Entity>) -> Option<&M::Value>
+    where
+        M: MetadataKind,
+    {
+        self.table::<M>()?.get(entity)
+    }
+
+    /// Returns the metadata of the kind attached to the entity if any.
+    pub fn get_mut<M>(
+        &mut self,
+        entity: Idx<M::Entity>,
+    ) -> Option<&mut M::Value>
+    where
+        M: MetadataKind,
+    {
+        self.table_mut::<M>()?.get_mut(entity)
+    }
+
+    /// Removes the metadata of the kind attached to the entity and returns it.
+    pub fn remove<M>(&mut self, entity: Idx<M::Entity>) -> Option<M::Value>
+    where
+        M: MetadataKind,
+    {
+        self.table_mut::<M>()?.remove(entity)
+    }
+
+    /// Removes the side table of the metadata kind.
+    pub fn clear<M>(&mut self)
+    where
+        M: MetadataKind,
+    {
+        self.tables.remove(&TypeId::of::<M>());
+    }
+
+    /// Invalidates all metadata that does not survive mutations of the function body.
+    ///
+    /// # Note
+    ///
+    /// This must be called whenever the function body the metadata refers
+    /// to has been mutated, e.g. after running a transformation pass.
+    /// Metadata kinds with [`MetadataKind::PRESERVE_ON_MUTATION`] are kept.
+    pub fn invalidate(&mut self) {
+        self.tables.retain(|_, table| table.preserve);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::Instr;
+    use entity::RawIdx;
+    use ir::{
+        instr::Instruction,
+        primitive::{Block, BlockEntity},
+    };
+
+    enum GasCost {}
+
+    impl MetadataKind for GasCost {
+        type Entity = Instruction;
+        type Value = u64;
+    }
+
+    enum ProfileCount {}
+
+    impl MetadataKind for ProfileCount {
+        type Entity = BlockEntity;
+        type Value = u32;
+        const PRESERVE_ON_MUTATION: bool = true;
+    }
+
+    #[test]
+    fn metadata_works() {
+        let instr = Instr::from_raw(RawIdx::from_u32(0));
+        let block = Block::from_raw(RawIdx::from_u32(0));
+        let mut metadata = Metadata::default();
+        assert!(!metadata.contains::<GasCost>());
+        assert_eq!(metadata.get::<GasCost>(instr), None);
+        assert_eq!(metadata.insert::<GasCost>(instr, 10), None);
+        assert_eq!(metadata.insert::<GasCost>(instr, 20), Some(10));
+        assert_eq!(metadata.insert::<ProfileCount>(block, 5), None);
+        *metadata.get_mut::<ProfileCount>(block).unwrap() += 1;
+        assert_eq!(metadata.get::<GasCost>(instr), Some(&20));
+        assert_eq!(metadata.get::<ProfileCount>(block), Some(&6));
+        assert_eq!(metadata.table::<GasCost>().unwrap().len(), 1);
+        metadata.invalidate();
+        assert!(!metadata.contains::<GasCost>());
+        assert_eq!(metadata.get::<ProfileCount>(block), Some(&6));
+        assert_eq!(metadata.remove::<ProfileCount>(block), Some(6));
+        metadata.clear::<ProfileCount>();
+        assert!(!metadata.contains::<ProfileCount>());
+    }
+}
@@ -13,26 +13,57 @@
 // limitations under the License.
 
 mod builder;
+mod checked;
+mod constant_time;
+mod cursor;
+mod display;
+mod dominators;
+mod editor;
 mod error;
 mod incomplete_phi;
 mod instruction;
+mod instrument;
+mod legalize;
+mod metadata;
+mod numbering;
+mod shadow_stack;
+mod source_span;
+mod stats;
+mod switch;
 mod variable;
+mod verifier;
 
 pub use self::{
     builder::{
         FunctionBuilder,
-        FunctionBuilderContext,
         FunctionBuilderState,
+        IrArena,
         ValueAssoc,
     },
-    error::{FunctionBuilderError, VariableAccess},
+    checked::{CheckedArithmetic, Overflow},
+    constant_time::SecretLeak,
+    cursor::FuncCursor,
+    display::{DisplayConfig, DisplayFunctionBody},
+    dominators::DominatorTree,
+    editor::FunctionEditor,
+    error::{FunctionBuilderError, FunctionEditorError, VariableAccess},
     instruction::{Instr, InstructionBuilder},
+    instrument::InstrumentationHooks,
+    legalize::TargetConstraints,
+    metadata::{Metadata, MetadataKind},
+    numbering::InstructionNumbering,
+    shadow_stack::ShadowStack,
+    source_span::SourceSpan,
+    stats::InstructionStats,
+    switch::SwitchLowering,
     variable::{Variable, VariableTranslator},
+    verifier::VerifierViolation,
 };
-use crate::module::Indent;
 use core::fmt;
 use entity::{
+    ComponentMap,
     ComponentVec,
+    DefaultComponentBitVec,
     DefaultComponentMap,
     DefaultComponentVec,
     EntityArena,
@@ -40,7 +71,7 @@ use entity::{
     RawIdx,
 };
 use ir::{
-    instr::Instruction,
+    instr::{Instruction, OperandKind, TerminalInstr},
     primitive::{Block, BlockEntity, Type, Value, ValueEntity},
 };
 use smallvec::SmallVec;
@@ -54,11 +85,11 @@ pub struct FunctionBody {
     values: PhantomEntityArena<ValueEntity>,
     /// Arena for all IR instructions.
     instrs: EntityArena<Instruction>,
-    /// Block instructions.
-    ///
-    /// # Note
+    /// Block parameters.
     ///
-    /// Also contains all the phi instructions at the block start.
+    /// Branches to a basic block pass an argument for each of its parameters.
+    block_params: DefaultComponentVec<Block, SmallVec<[Value; 2]>>,
+    /// Block instructions.
     block_instrs: DefaultComponentVec<Block, SmallVec<[Instr; 4]>>,
     /// Optional associated values for instructions.
     ///
@@ -66,6 +97,11 @@ pub struct FunctionBody {
     /// For example `store` is not in pure SSA form and therefore
     /// has no SSA value association.
     instr_values: DefaultComponentMap<Instr, SmallVec<[Option<Value>; 4]>>,
+    /// The source spans of instructions translated from a source language.
+    ///
+    /// Instructions that have been inserted afterwards, e.g. by a
+    /// [`FunctionEditor`], have no source span.
+    instr_spans: ComponentMap<Instr, SourceSpan>,
     /// Types for all values.
     value_type: ComponentVec<Value, Type>,
     /// The association of the SSA value.
@@ -73,6 +109,15 @@ pub struct FunctionBody {
     /// Every SSA value has an association to either an IR instruction
     /// or to an input parameter of the IR function under construction.
     value_assoc: ComponentVec<Value, ValueAssoc>,
+    /// The declared types of all variables including the function inputs.
+    var_type: ComponentVec<Variable, Type>,
+    /// The final SSA value definitions of all variables for every basic block.
+    ///
+    /// Used to map SSA values back to the variables of the source language,
+    /// e.g. for debugging purposes.
+    var_defs: ComponentVec<Variable, ComponentMap<Block, Value>>,
+    /// Basic blocks that have been removed via the [`FunctionEditor`].
+    removed_blocks: DefaultComponentBitVec<Block>,
 }
 
 impl FunctionBody {
@@ -81,6 +126,114 @@ impl FunctionBody {
         Block::from_raw(RawIdx::from_u32(0))
     }
 
+    /// Returns an iterator over all basic blocks of the function in order.
+    ///
+    /// # Note
+    ///
+    /// Basic blocks that have been removed via the [`FunctionEditor`] are skipped.
+    pub fn blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        self.blocks
+            .indices()
+            .filter(move |&block| !self.removed_blocks.get(block))
+    }
+
+    /// Returns `true` if the basic block exists and has not been removed.
+    pub fn contains_block(&self, block: Block) -> bool {
+        self.blocks.contains_key(block) && !self.removed_blocks.get(block)
+    }
+
+    /// Returns the parameters of the basic block in order.
+    pub fn block_params(&self, block: Block) -> &[Value] {
+        &self.block_params[block]
+    }
+
+    /// Returns the instructions of the basic block in order.
+    ///
+    /// # Note
+    ///
+    /// The terminal instruction is at the end of the basic block.
+    pub fn block_instrs(&self, block: Block) -> &[Instr] {
+        &self.block_instrs[block]
+    }
+
+    /// Returns the instruction if it exists.
+    pub fn instr(&self, instr: Instr) -> Option<&Instruction> {
+        self.instrs.get(instr)
+    }
+
+    /// Returns the source span the instruction originates from if any.
+    pub fn source_span(&self, instr: Instr) -> Option<SourceSpan> {
+        self.instr_spans.get(instr).copied()
+    }
+
+    /// Returns the basic block containing the instruction and its position within it.
+    pub(super) fn instr_position(
+        &self,
+        instr: Instr,
+    ) -> Option<(Block, usize)> {
+        self.blocks().find_map(|block| {
+            self.block_instrs[block]
+                .iter()
+                .position(|&other| other == instr)
+                .map(|position| (block, position))
+        })
+    }
+
+    /// Returns the terminal instruction of the basic block if any.
+    pub fn terminal(&self, block: Block) -> Option<&TerminalInstr> {
+        let instr = self.block_instrs[block].last().copied()?;
+        match &self.instrs[instr] {
+            Instruction::Terminal(terminal) => Some(terminal),
+            _ => None,
+        }
+    }
+
+    /// Returns the distinct successors of the basic block in ascending order.
+    pub fn successors(&self, block: Block) -> SmallVec<[Block; 4]> {
+        let mut successors = self
+            .terminal(block)
+            .map(TerminalInstr::successors)
+            .unwrap_or_default();
+        successors.sort();
+        successors.dedup();
+        successors
+    }
+
+    /// Returns the distinct predecessors of the basic block in ascending order.
+    ///
+    /// # Note
+    ///
+    /// The predecessors are not stored but computed on every call.
+    pub fn predecessors(&self, block: Block) -> Vec<Block> {
+        self.blocks()
+            .filter(|&pred| self.successors(pred).contains(&block))
+            .collect()
+    }
+
+    /// Returns `true` if the function body is pure.
+    ///
+    /// Pure function bodies neither access linear memories, tables or global
+    /// variables nor call other functions. Their results therefore only depend
+    /// on their inputs. Pure function bodies might still trap, e.g. on division
+    /// by zero.
+    pub fn is_pure(&self) -> bool {
+        self.blocks()
+            .flat_map(|block| self.block_instrs[block].iter())
+            .all(|&instr| {
+                let descriptor = self.instrs[instr].descriptor();
+                let effects = descriptor.effects();
+                !effects.reads_memory()
+                    && !effects.writes_memory()
+                    && !effects.reads_global()
+                    && !effects.writes_global()
+                    && !effects.calls()
+                    && !descriptor.operands().iter().any(|&kind| {
+                        kind == OperandKind::Memory
+                            || kind == OperandKind::Table
+                    })
+            })
+    }
+
     /// Returns the maximum SSA value used by the function.
     pub fn max_value(&self) -> Value {
         self.values
@@ -89,6 +242,49 @@ impl FunctionBody {
             .unwrap_or_else(|| Value::from_raw(RawIdx::from_u32(0)))
     }
 
+    /// Returns an iterator over all declared variables of the function.
+    ///
+    /// # Note
+    ///
+    /// This includes the variables that are associated to the function inputs.
+    pub fn vars(&self) -> impl Iterator<Item = Variable> + '_ {
+        self.var_type.iter().map(|(var, _)| var)
+    }
+
+    /// Returns the type of the SSA value if it is defined in the function body.
+    pub fn value_type(&self, value: Value) -> Option<Type> {
+        self.value_type.get(value).copied()
+    }
+
+    /// Returns the declared type of the variable if it has been declared.
+    pub fn var_type(&self, var: Variable) -> Option<Type> {
+        self.var_type.get(var).copied()
+    }
+
+    /// Returns the SSA value definition of the variable at the end of the block.
+    ///
+    /// Returns `None` if the variable has not been declared or if it has no
+    /// definition within the block.
+    pub fn var_definition(&self, var: Variable, block: Block) -> Option<Value> {
+        self.var_defs.get(var)?.get(block).copied()
+    }
+
+    /// Returns an iterator over all basic blocks and the respective SSA value
+    /// definitions of the variable.
+    ///
+    /// # Note
+    ///
+    /// The iteration order is unspecified.
+    pub fn var_definitions(
+        &self,
+        var: Variable,
+    ) -> impl Iterator<Item = (Block, Value)> + '_ {
+        self.var_defs
+            .get(var)
+            .into_iter()
+            .flat_map(|defs| defs.iter().map(|(block, value)| (block, *value)))
+    }
+
     /// Returns the slice over the output values of the instruction.
     fn instr_values(&self, instr: Instr) -> &[Option<Value>] {
         self.instr_values[instr].as_slice()
@@ -105,71 +301,10 @@ impl FunctionBody {
         let instr_values = self.instr_values(instr);
         Some((instr_values, instruction))
     }
-
-    /// Display the function body with the given indentation.
-    ///
-    /// # Note
-    ///
-    /// Indentation is important to properly indent the printed function body
-    /// in case the output is part of an entire function with signature.
-    pub(crate) fn display_with_indent(
-        &self,
-        f: &mut fmt::Formatter<'_>,
-        ident: Indent,
-    ) -> fmt::Result {
-        let block_ident = ident;
-        let instr_ident = ident + Indent::single();
-        let mut passed_entry = false;
-        for block in self.blocks.indices() {
-            if !passed_entry {
-                passed_entry = true;
-                writeln!(f, "{}block {{", block_ident)?;
-            } else {
-                writeln!(f, "{}block {} {{", block_ident, block)?;
-            }
-            for &instr in &self.block_instrs[block] {
-                let instr_data = &self.instrs[instr];
-                let instr_values = self.instr_values(instr);
-                let instr_values_tuples = instr_values.len() >= 2;
-                write!(f, "{}", instr_ident)?;
-                match instr_values.split_first() {
-                    None => {
-                        writeln!(f, "{}", instr_data)?;
-                    }
-                    Some((&first, rest)) => {
-                        write!(f, "let ")?;
-                        if instr_values_tuples {
-                            write!(f, "(")?;
-                        }
-                        if let Some(first) = first {
-                            let value_type = self.value_type[first];
-                            write!(f, "{}: {}", first, value_type)?;
-                        } else {
-                            write!(f, "_")?;
-                        }
-                        for &value in rest {
-                            if let Some(value) = value {
-                                let value_type = self.value_type[value];
-                                write!(f, ", {}: {}", value, value_type)?;
-                            } else {
-                                write!(f, ", _")?;
-                            }
-                        }
-                        if instr_values_tuples {
-                            write!(f, ")")?;
-                        }
-                        writeln!(f, " = {}", instr_data)?;
-                    }
-                }
-            }
-            writeln!(f, "{}}}", block_ident)?;
-        }
-        Ok(())
-    }
 }
 
 impl fmt::Display for FunctionBody {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.display_with_indent(f, Default::default())
+        write!(f, "{}", self.display_with(Default::default()))
     }
 }
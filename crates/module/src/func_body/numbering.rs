@@ -0,0 +1,221 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sequence numbers for instructions within their basic blocks.
+//!
+//! Allows analyses to answer "comes before" queries for instructions of the
+//! same basic block in constant time instead of scanning the instructions
+//! of the basic block.
+
+use super::{FunctionBody, Instr};
+use entity::{ComponentMap, DefaultComponentBitVec};
+use ir::primitive::Block;
+
+/// The gap between the sequence numbers of adjacent instructions after numbering.
+///
+/// The gaps allow to insert new instructions without renumbering.
+const SEQ_STRIDE: u32 = 16;
+
+/// The position of an instruction within its basic block.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Position {
+    /// The basic block that contains the instruction.
+    block: Block,
+    /// The sequence number of the instruction within its basic block.
+    seq: u32,
+}
+
+/// Sequence numbers for instructions within their basic blocks.
+///
+/// The sequence numbers of instructions of the same basic block are strictly
+/// increasing in the order of the instructions but are not necessarily dense.
+///
+/// # Note
+///
+/// Inserting instructions into a basic block uses the gaps between the
+/// sequence numbers of its neighbours. If there is no gap left the basic block
+/// is marked as dirty and must be renumbered via
+/// [`InstructionNumbering::renumber_block`] before it can be queried again.
+#[derive(Debug, Default, Clone)]
+pub struct InstructionNumbering {
+    /// The positions of all numbered instructions.
+    positions: ComponentMap<Instr, Position>,
+    /// Basic blocks that require renumbering before they can be queried.
+    dirty: DefaultComponentBitVec<Block>,
+}
+
+impl InstructionNumbering {
+    /// Returns the sequence number of the instruction within its basic block.
+    ///
+    /// Returns `None` if the instruction is not numbered or if its basic
+    /// block requires renumbering.
+    pub fn seq(&self, instr: Instr) -> Option<u32> {
+        let position = self.positions.get(instr)?;
+        if self.dirty.get(position.block) {
+            return None
+        }
+        Some(position.seq)
+    }
+
+    /// Returns the basic block that contains the numbered instruction.
+    pub fn block(&self, instr: Instr) -> Option<Block> {
+        self.positions.get(instr).map(|position| position.block)
+    }
+
+    /// Returns `true` if the basic block requires renumbering.
+    pub fn is_dirty(&self, block: Block) -> bool {
+        self.dirty.get(block)
+    }
+
+    /// Returns `true` if instruction `lhs` comes before instruction `rhs`.
+    ///
+    /// Returns `None` if any of the instructions is not numbered, if both
+    /// instructions are in different basic blocks or if their basic block
+    /// requires renumbering.
+    pub fn comes_before(&self, lhs: Instr, rhs: Instr) -> Option<bool> {
+        let lhs = self.positions.get(lhs)?;
+        let rhs = self.positions.get(rhs)?;
+        if lhs.block != rhs.block || self.dirty.get(lhs.block) {
+            return None
+        }
+        Some(lhs.seq < rhs.seq)
+    }
+
+    /// Numbers all instructions of the basic block in the given order.
+    ///
+    /// Also clears the dirty state of the basic block.
+    ///
+    /// # Note
+    ///
+    /// The given instructions must be all instructions of the basic block.
+    pub fn renumber_block<T>(&mut self, block: Block, instrs: T)
+    where
+        T: IntoIterator<Item = Instr>,
+    {
+        for (n, instr) in instrs.into_iter().enumerate() {
+            let seq = (n as u32 + 1) * SEQ_STRIDE;
+            self.positions.insert(instr, Position { block, seq });
+        }
+        self.dirty.set(block, false);
+    }
+
+    /// Numbers the new instruction that has been inserted between `prev` and `next`
+    /// into the basic block.
+    ///
+    /// `None` for `prev` or `next` means that the new instruction is the
+    /// first or last instruction of the basic block respectively.
+    ///
+    /// Returns `false` if there was no gap left between the sequence numbers of
+    /// `prev` and `next`. In this case the basic block is marked as dirty.
+    ///
+    /// # Panics
+    ///
+    /// If `prev` or `next` are not numbered instructions of the basic block.
+    pub fn insert(
+        &mut self,
+        block: Block,
+        prev: Option<Instr>,
+        next: Option<Instr>,
+        new_instr: Instr,
+    ) -> bool {
+        let seq_of = |instr: Instr| {
+            let position = self.positions.get(instr).unwrap_or_else(|| {
+                panic!("encountered unnumbered instruction {}", instr)
+            });
+            assert_eq!(
+                position.block, block,
+                "instruction {} is not part of basic block {}",
+                instr, block,
+            );
+            position.seq
+        };
+        let lower = prev.map(seq_of).map(|seq| seq + 1).unwrap_or(0);
+        let upper = next
+            .map(seq_of)
+            .unwrap_or_else(|| lower.saturating_add(2 * SEQ_STRIDE));
+        let fits = !self.dirty.get(block) && lower < upper;
+        let seq = if fits { lower + (upper - lower) / 2 } else { lower };
+        self.positions.insert(new_instr, Position { block, seq });
+        if !fits {
+            self.dirty.set(block, true);
+        }
+        fits
+    }
+
+    /// Removes the numbering of the instruction.
+    ///
+    /// The sequence numbers of the remaining instructions stay valid.
+    pub fn remove(&mut self, instr: Instr) {
+        self.positions.remove(instr);
+    }
+}
+
+impl FunctionBody {
+    /// Returns the sequence numbers of all instructions of the function body.
+    pub fn numbering(&self) -> InstructionNumbering {
+        let mut numbering = InstructionNumbering::default();
+        for block in self.blocks() {
+            let instrs = self.block_instrs[block].iter().copied();
+            numbering.renumber_block(block, instrs);
+        }
+        numbering
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::RawIdx;
+
+    fn instr(n: u32) -> Instr {
+        Instr::from_raw(RawIdx::from_u32(n))
+    }
+
+    #[test]
+    fn numbering_works() {
+        let block = Block::from_raw(RawIdx::from_u32(0));
+        let mut numbering = InstructionNumbering::default();
+        numbering.renumber_block(block, (0..3).map(instr));
+        assert_eq!(numbering.comes_before(instr(0), instr(2)), Some(true));
+        assert_eq!(numbering.comes_before(instr(2), instr(1)), Some(false));
+        assert_eq!(numbering.comes_before(instr(0), instr(3)), None);
+        assert_eq!(numbering.block(instr(1)), Some(block));
+
+        // Insert between the first and the second instruction.
+        assert!(numbering.insert(
+            block,
+            Some(instr(0)),
+            Some(instr(1)),
+            instr(3)
+        ));
+        assert_eq!(numbering.comes_before(instr(0), instr(3)), Some(true));
+        assert_eq!(numbering.comes_before(instr(3), instr(1)), Some(true));
+
+        // Insert at the front until the gap is exhausted.
+        let mut next = instr(0);
+        let mut n = 4;
+        while numbering.insert(block, None, Some(next), instr(n)) {
+            assert_eq!(numbering.comes_before(instr(n), next), Some(true));
+            next = instr(n);
+            n += 1;
+        }
+        assert!(numbering.is_dirty(block));
+        assert_eq!(numbering.seq(instr(0)), None);
+        assert_eq!(numbering.comes_before(instr(0), instr(1)), None);
+
+        numbering.renumber_block(block, vec![instr(1), instr(0)]);
+        assert!(!numbering.is_dirty(block));
+        assert_eq!(numbering.comes_before(instr(1), instr(0)), Some(true));
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Overflow checks for the shadow stack of LLVM generated code.
+//!
+//! LLVM keeps the stack of address-taken locals in linear memory and stores
+//! its stack pointer in a mutable `i32` global variable, conventionally named
+//! `__stack_pointer`. The stack grows downwards and nothing prevents deeply
+//! recursive code from silently overwriting the static data below it. The
+//! rewrite checks every write to the stack pointer against a lower bound and
+//! traps with [`TrapCode::StackOverflow`] if the bound is crossed.
+
+use super::{FunctionBody, ValueAssoc};
+use crate::Error;
+use ir::{
+    instr::{
+        operands::CompareIntOp,
+        CompareIntInstr,
+        ConstInstr,
+        IfThenElseInstr,
+        Instruction,
+        TerminalInstr,
+        TrapCode,
+    },
+    primitive::{Block, Global, IntConst, IntType, Type, Value},
+};
+
+/// The shadow stack whose stack pointer writes are checked for overflow.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ShadowStack {
+    /// The `i32` global variable holding the stack pointer.
+    stack_pointer: Global,
+    /// The lowest address the stack pointer may take.
+    lower_bound: u32,
+}
+
+impl ShadowStack {
+    /// Creates a new shadow stack for the stack pointer global variable.
+    ///
+    /// Usually the lower bound is the end of the static data of the module
+    /// which LLVM exports as `__data_end`.
+    pub fn new(stack_pointer: Global, lower_bound: u32) -> Self {
+        Self {
+            stack_pointer,
+            lower_bound,
+        }
+    }
+
+    /// Returns the global variable holding the stack pointer.
+    pub fn stack_pointer(&self) -> Global {
+        self.stack_pointer
+    }
+
+    /// Returns the lowest address the stack pointer may take.
+    pub fn lower_bound(&self) -> u32 {
+        self.lower_bound
+    }
+}
+
+impl FunctionBody {
+    /// Checks all writes to the stack pointer of the shadow stack for
+    /// overflow.
+    ///
+    /// Returns the number of checked writes.
+    ///
+    /// # Note
+    ///
+    /// Every checked write starts a new basic block that is only entered if
+    /// the new stack pointer does not fall below the lower bound. Otherwise
+    /// a shared basic block traps with [`TrapCode::StackOverflow`].
+    ///
+    /// The function body itself does not know the type of the stack pointer.
+    /// Use [`Module::check_shadow_stack`] in order to check all internal
+    /// functions of a module with a validated stack pointer.
+    ///
+    /// [`Module::check_shadow_stack`]: crate::Module::check_shadow_stack
+    pub fn check_shadow_stack(
+        &mut self,
+        stack: &ShadowStack,
+    ) -> Result<usize, Error> {
+        let blocks = self.blocks().collect::<Vec<_>>();
+        let mut trap_block = None;
+        let mut len_checked = 0;
+        for block in blocks {
+            let mut block = block;
+            let mut position = 0;
+            while position < self.block_instrs[block].len() {
+                let instr = self.block_instrs[block][position];
+                let new_value = match &self.instrs[instr] {
+                    Instruction::GlobalSet(global_set)
+                        if global_set.global() == stack.stack_pointer() =>
+                    {
+                        global_set.value()
+                    }
+                    _ => {
+                        position += 1;
+                        continue
+                    }
+                };
+                let bound = stack.lower_bound() as i32;
+                let bound = self.insert_checked_value(
+                    block,
+                    &mut position,
+                    ConstInstr::new(IntConst::I32(bound).into()).into(),
+                    IntType::I32.into(),
+                );
+                let is_overflow = self.insert_checked_value(
+                    block,
+                    &mut position,
+                    CompareIntInstr::new(
+                        CompareIntOp::Ult,
+                        IntType::I32,
+                        new_value,
+                        bound,
+                    )
+                    .into(),
+                    Type::Bool,
+                );
+                let continue_block = self.edit().split_block(instr)?;
+                let trap_block = *trap_block.get_or_insert_with(|| {
+                    let block = self.blocks.alloc_some(1);
+                    let trap = TerminalInstr::Trap(TrapCode::StackOverflow);
+                    let instr = self.instrs.alloc(trap.into());
+                    self.block_instrs[block].push(instr);
+                    block
+                });
+                let br = self.block_instrs[block]
+                    .last()
+                    .copied()
+                    .expect("encountered split basic block without terminal");
+                self.instrs[br] = TerminalInstr::Ite(IfThenElseInstr::new(
+                    is_overflow,
+                    trap_block,
+                    continue_block,
+                ))
+                .into();
+                len_checked += 1;
+                // Continue after the checked write in the split off block.
+                block = continue_block;
+                position = 1;
+            }
+        }
+        Ok(len_checked)
+    }
+
+    /// Inserts the instruction producing a single value of the given type.
+    ///
+    /// The instruction is inserted before the instruction at the position
+    /// which is advanced past the inserted instruction.
+    fn insert_checked_value(
+        &mut self,
+        block: Block,
+        position: &mut usize,
+        instruction: Instruction,
+        ty: Type,
+    ) -> Value {
+        let instr = self.instrs.alloc(instruction);
+        let value = self.values.alloc_some(1);
+        self.value_type.insert(value, ty);
+        self.value_assoc.insert(value, ValueAssoc::Instr(instr, 0));
+        self.instr_values[instr].push(Some(value));
+        self.block_instrs[block].insert(*position, instr);
+        *position += 1;
+        value
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use core::fmt;
+
+/// The range of bytes in the source input an instruction originates from.
+///
+/// For instructions translated from Wasm this is the range of bytes of the
+/// originating Wasm operator within the Wasm binary.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceSpan {
+    /// The inclusive start of the byte range.
+    start: usize,
+    /// The non-inclusive end of the byte range.
+    end: usize,
+}
+
+impl SourceSpan {
+    /// Creates a new source span for the given byte range.
+    ///
+    /// # Panics
+    ///
+    /// If `start` is greater than `end`.
+    pub fn new(start: usize, end: usize) -> Self {
+        assert!(
+            start <= end,
+            "encountered invalid source span {}..{}",
+            start,
+            end
+        );
+        Self { start, end }
+    }
+
+    /// Returns the inclusive start of the byte range.
+    ///
+    /// For Wasm operators this is their offset within the Wasm binary.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the non-inclusive end of the byte range.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+}
+
+impl fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
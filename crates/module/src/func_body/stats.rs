@@ -0,0 +1,151 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Statistics about the instructions and the memory consumption of function bodies.
+//!
+//! Used to find pathological translations and to guide work on more
+//! compact encodings of the Runwell IR.
+
+use super::{FunctionBody, Instr, ValueAssoc};
+use core::mem::{size_of, size_of_val};
+use ir::{
+    instr::Instruction,
+    primitive::Type,
+    VisitValues,
+};
+use std::collections::BTreeMap;
+
+/// Statistics about the instructions of one or multiple function bodies.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InstructionStats {
+    /// The number of basic blocks.
+    len_blocks: usize,
+    /// The number of SSA values.
+    len_values: usize,
+    /// The number of instructions.
+    len_instrs: usize,
+    /// The number of basic block parameters.
+    len_block_params: usize,
+    /// The total number of SSA value operands of all instructions.
+    len_operands: usize,
+    /// The number of instructions per instruction kind.
+    kinds: BTreeMap<&'static str, usize>,
+    /// The approximate number of bytes used by the function body entities.
+    memory_usage: usize,
+}
+
+impl InstructionStats {
+    /// Returns the number of basic blocks.
+    pub fn len_blocks(&self) -> usize {
+        self.len_blocks
+    }
+
+    /// Returns the number of SSA values.
+    pub fn len_values(&self) -> usize {
+        self.len_values
+    }
+
+    /// Returns the number of instructions.
+    pub fn len_instrs(&self) -> usize {
+        self.len_instrs
+    }
+
+    /// Returns the number of basic block parameters.
+    pub fn len_block_params(&self) -> usize {
+        self.len_block_params
+    }
+
+    /// Returns the total number of SSA value operands of all instructions.
+    pub fn len_operands(&self) -> usize {
+        self.len_operands
+    }
+
+    /// Returns the average number of SSA value operands per instruction.
+    pub fn average_operands(&self) -> f64 {
+        if self.len_instrs == 0 {
+            return 0.0
+        }
+        self.len_operands as f64 / self.len_instrs as f64
+    }
+
+    /// Returns the number of instructions of the given kind.
+    ///
+    /// The kind is the instruction name of its descriptor, e.g. `"iadd"`.
+    pub fn len_kind(&self, kind: &str) -> usize {
+        self.kinds.get(kind).copied().unwrap_or_default()
+    }
+
+    /// Returns an iterator over all instruction kinds and their number
+    /// of occurrences sorted by the instruction kind name.
+    pub fn kinds(&self) -> impl Iterator<Item = (&'static str, usize)> + '_ {
+        self.kinds.iter().map(|(&kind, &len)| (kind, len))
+    }
+
+    /// Returns the approximate number of bytes used by the function body entities.
+    ///
+    /// # Note
+    ///
+    /// This only accounts for the inline sizes of the stored entities and
+    /// does not account for heap allocations of instructions or the overhead
+    /// of the underlying data structures.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    /// Merges the statistics of `other` into `self`.
+    pub fn merge(&mut self, other: &Self) {
+        self.len_blocks += other.len_blocks;
+        self.len_values += other.len_values;
+        self.len_instrs += other.len_instrs;
+        self.len_block_params += other.len_block_params;
+        self.len_operands += other.len_operands;
+        self.memory_usage += other.memory_usage;
+        for (&kind, &len) in &other.kinds {
+            *self.kinds.entry(kind).or_default() += len;
+        }
+    }
+}
+
+impl FunctionBody {
+    /// Returns statistics about the instructions and the memory consumption
+    /// of the function body.
+    pub fn stats(&self) -> InstructionStats {
+        let mut stats = InstructionStats {
+            len_blocks: self.blocks().count(),
+            len_values: self.values.len(),
+            ..Default::default()
+        };
+        for block in self.blocks() {
+            stats.len_block_params += self.block_params(block).len();
+            for &instr in &self.block_instrs[block] {
+                let instruction = &self.instrs[instr];
+                stats.len_instrs += 1;
+                instruction.visit_values(|_| {
+                    stats.len_operands += 1;
+                    true
+                });
+                *stats
+                    .kinds
+                    .entry(instruction.descriptor().name())
+                    .or_default() += 1;
+                stats.memory_usage += size_of::<Instruction>()
+                    + size_of::<Instr>()
+                    + size_of_val(self.instr_values(instr));
+            }
+        }
+        stats.memory_usage += stats.len_values
+            * (size_of::<Type>() + size_of::<ValueAssoc>());
+        stats
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lowering of multi-way branches with arbitrary case keys.
+//!
+//! Multi-way branches, e.g. originating from large `match` statements, can be
+//! lowered into a jump table, a binary search tree of conditional branches or
+//! a chain of conditional branches. The best choice depends on the number of
+//! cases and on how densely the case keys are distributed.
+
+use super::{FunctionBuilder, Instr};
+use crate::Error;
+use ir::{
+    instr::{operands::CompareIntOp, BranchTableInstr},
+    primitive::{Block, IntConst, IntType, Value},
+};
+
+/// The maximum number of cases that are lowered into a chain of conditional branches.
+const MAX_IF_CHAIN_CASES: usize = 3;
+/// The maximum number of entries of a jump table.
+const MAX_JUMP_TABLE_LEN: u64 = 4096;
+
+/// The strategy to lower a multi-way branch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SwitchLowering {
+    /// Lowers into a dense `br_table`.
+    ///
+    /// Gaps between the case keys jump to the default target.
+    /// Case keys that do not start at zero are rebased via subtraction.
+    JumpTable,
+    /// Lowers into a sparse `br_table` without further lowering.
+    SparseTable,
+    /// Lowers into a balanced binary search tree of conditional branches.
+    BinarySearch,
+    /// Lowers into a chain of conditional branches.
+    IfChain,
+}
+
+impl SwitchLowering {
+    /// Chooses the lowering strategy based on the number of cases and their density.
+    ///
+    /// # Note
+    ///
+    /// The cases must be sorted by their keys.
+    pub fn choose(cases: &[(u32, Block)]) -> Self {
+        let (min, max) = match (cases.first(), cases.last()) {
+            (Some((min, _)), Some((max, _))) => (*min, *max),
+            _ => return Self::IfChain,
+        };
+        if cases.len() <= MAX_IF_CHAIN_CASES {
+            return Self::IfChain
+        }
+        let range = (max - min) as u64 + 1;
+        // Use a jump table if at least every second entry is a real case.
+        if range <= MAX_JUMP_TABLE_LEN && range <= 2 * cases.len() as u64 {
+            return Self::JumpTable
+        }
+        Self::BinarySearch
+    }
+}
+
+/// Sorts the cases by their keys and removes redundant cases.
+///
+/// Cases with duplicate keys are removed keeping the first one.
+/// Cases that jump to the default target are removed.
+fn normalize_cases(
+    default: Block,
+    cases: &[(u32, Block)],
+) -> Vec<(u32, Block)> {
+    let mut cases = cases.to_vec();
+    cases.sort_by_key(|(key, _)| *key);
+    cases.dedup_by_key(|(key, _)| *key);
+    cases.retain(|(_, target)| *target != default);
+    cases
+}
+
+impl<'a> FunctionBuilder<'a> {
+    /// Jumps to the target of the case whose key matches the `i32` case value
+    /// or to the default target if no case matches.
+    ///
+    /// The lowering strategy is chosen via [`SwitchLowering::choose`].
+    ///
+    /// # Note
+    ///
+    /// - If multiple cases share the same key the first one is used.
+    /// - This fills the current basic block and might create and fill new
+    ///   basic blocks. Therefore the caller has to switch to another basic
+    ///   block afterwards.
+    ///
+    /// # Errors
+    ///
+    /// - If the case value is not of type `i32`.
+    /// - If any of the targets has already been sealed.
+    pub fn switch(
+        &mut self,
+        case: Value,
+        default: Block,
+        cases: &[(u32, Block)],
+    ) -> Result<(), Error> {
+        let cases = normalize_cases(default, cases);
+        let lowering = SwitchLowering::choose(&cases);
+        self.lower_switch(lowering, case, default, &cases)
+    }
+
+    /// Jumps to the target of the case whose key matches the `i32` case value
+    /// or to the default target if no case matches using the given lowering strategy.
+    ///
+    /// # Note
+    ///
+    /// See [`FunctionBuilder::switch`] for details.
+    ///
+    /// # Errors
+    ///
+    /// See [`FunctionBuilder::switch`] for details.
+    pub fn switch_with(
+        &mut self,
+        lowering: SwitchLowering,
+        case: Value,
+        default: Block,
+        cases: &[(u32, Block)],
+    ) -> Result<(), Error> {
+        let cases = normalize_cases(default, cases);
+        self.lower_switch(lowering, case, default, &cases)
+    }
+
+    /// Lowers the normalized cases with the given lowering strategy.
+    fn lower_switch(
+        &mut self,
+        lowering: SwitchLowering,
+        case: Value,
+        default: Block,
+        cases: &[(u32, Block)],
+    ) -> Result<(), Error> {
+        match lowering {
+            SwitchLowering::JumpTable => {
+                self.lower_jump_table(case, default, cases)?;
+            }
+            SwitchLowering::SparseTable => {
+                let branch_table = BranchTableInstr::new_sparse(
+                    case,
+                    default,
+                    cases.iter().copied(),
+                );
+                self.ins()?.branch_table(branch_table)?;
+            }
+            SwitchLowering::BinarySearch => {
+                self.lower_binary_search(case, default, cases)?;
+            }
+            SwitchLowering::IfChain => {
+                self.lower_if_chain(case, default, cases)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Lowers the normalized cases into a dense branching table.
+    fn lower_jump_table(
+        &mut self,
+        case: Value,
+        default: Block,
+        cases: &[(u32, Block)],
+    ) -> Result<Instr, Error> {
+        let (min, max) = match (cases.first(), cases.last()) {
+            (Some((min, _)), Some((max, _))) => (*min, *max),
+            _ => return self.ins()?.br(default),
+        };
+        let index = if min == 0 {
+            case
+        } else {
+            // Case values below `min` wrap around and jump to the default target.
+            let min = self.ins()?.constant(IntConst::I32(min as i32))?;
+            self.ins()?.isub(IntType::I32, case, min)?
+        };
+        let mut targets = vec![default; (max - min) as usize + 1];
+        for (key, target) in cases {
+            targets[(key - min) as usize] = *target;
+        }
+        let branch_table = BranchTableInstr::new(index, default, targets);
+        self.ins()?.branch_table(branch_table)
+    }
+
+    /// Lowers the normalized cases into a balanced binary search tree.
+    fn lower_binary_search(
+        &mut self,
+        case: Value,
+        default: Block,
+        cases: &[(u32, Block)],
+    ) -> Result<(), Error> {
+        if cases.len() <= MAX_IF_CHAIN_CASES {
+            return self.lower_if_chain(case, default, cases)
+        }
+        let (lhs, rhs) = cases.split_at(cases.len() / 2);
+        let pivot = self.ins()?.constant(IntConst::I32(rhs[0].0 as i32))?;
+        let condition =
+            self.ins()?.icmp(IntType::I32, CompareIntOp::Ult, case, pivot)?;
+        let lhs_block = self.create_block()?;
+        let rhs_block = self.create_block()?;
+        self.ins()?.if_then_else(condition, lhs_block, rhs_block)?;
+        self.switch_to_block(lhs_block)?;
+        self.seal_block(lhs_block)?;
+        self.lower_binary_search(case, default, lhs)?;
+        self.switch_to_block(rhs_block)?;
+        self.seal_block(rhs_block)?;
+        self.lower_binary_search(case, default, rhs)?;
+        Ok(())
+    }
+
+    /// Lowers the normalized cases into a chain of conditional branches.
+    fn lower_if_chain(
+        &mut self,
+        case: Value,
+        default: Block,
+        cases: &[(u32, Block)],
+    ) -> Result<(), Error> {
+        let (last, init) = match cases.split_last() {
+            Some(split) => split,
+            None => {
+                self.ins()?.br(default)?;
+                return Ok(())
+            }
+        };
+        for (key, target) in init {
+            let key = self.ins()?.constant(IntConst::I32(*key as i32))?;
+            let condition =
+                self.ins()?.icmp(IntType::I32, CompareIntOp::Eq, case, key)?;
+            let next = self.create_block()?;
+            self.ins()?.if_then_else(condition, *target, next)?;
+            self.switch_to_block(next)?;
+            self.seal_block(next)?;
+        }
+        let (key, target) = last;
+        let key = self.ins()?.constant(IntConst::I32(*key as i32))?;
+        let condition =
+            self.ins()?.icmp(IntType::I32, CompareIntOp::Eq, case, key)?;
+        self.ins()?.if_then_else(condition, *target, default)?;
+        Ok(())
+    }
+}
@@ -220,9 +220,25 @@ impl<'a> VariableDefinitionPerBlock<'a> {
     pub fn for_block(self, block: Block) -> Option<Value> {
         self.defs.get(block).copied()
     }
+
+    /// Returns an iterator over all basic blocks and their variable definitions.
+    ///
+    /// # Note
+    ///
+    /// The iteration order is unspecified.
+    pub fn iter(self) -> impl Iterator<Item = (Block, Value)> + 'a {
+        self.defs.iter().map(|(block, value)| (block, *value))
+    }
 }
 
 impl VariableTranslator {
+    /// Clears the variable translator for reusing its memory.
+    pub fn clear(&mut self) {
+        self.vars.clear();
+        self.var_to_type.var_to_type.clear();
+        self.var_to_defs.clear();
+    }
+
     /// Ensures that the variable has been declared.
     ///
     /// # Errors
@@ -350,59 +366,45 @@ impl VariableTranslator {
         Ok(())
     }
 
-    /// Replaces the variable `var` of `block` with the new `with_value` in case it
-    /// is currently assigned to `replace_value`.
-    ///
-    /// Also ensures that the type of the variable stays correct.
+    /// Replaces all definitions of all variables that are assigned to
+    /// `replace_value` with the new `with_value`.
     ///
-    /// # Errors
+    /// # Note
     ///
-    /// - If the variable has not been declared before.
-    /// - If the type of the new value does not match the type of the variable declaration.
-    pub fn replace_var(
-        &mut self,
-        var: Variable,
-        block: Block,
-        replace_value: Value,
-        with_value: Value,
-        value_type: Type,
-    ) -> Result<(), Error> {
-        self.ensure_declared(
-            var,
-            VariableAccess::Replace {
-                from: replace_value,
-                to: with_value,
-            },
-        )?;
-        match self.var_to_defs.entry(var) {
-            Entry::Occupied(occupied) => {
-                let declared_type = occupied.get().ty;
-                Self::ensure_types_match(
-                    var,
-                    with_value,
-                    declared_type,
-                    value_type,
-                )?;
-                let previous = occupied.get().block_defs[block];
-                if previous != replace_value {
-                    // The previous value is not equal so we bail out early.
-                    return Ok(())
+    /// A value might define multiple variables in multiple basic blocks.
+    /// For example a removed trivial phi instruction might have been
+    /// assigned to another variable or cached as definition in the basic
+    /// blocks between its own basic block and where it has been read.
+    pub fn replace_value(&mut self, replace_value: Value, with_value: Value) {
+        for defs in self.var_to_defs.components_mut() {
+            for def in defs.block_defs.components_mut() {
+                if *def == replace_value {
+                    *def = with_value;
                 }
-                occupied.into_mut().block_defs.insert(block, with_value);
-            }
-            Entry::Vacant(_vacant) => {
-                return Err(
-                    FunctionBuilderError::MissingVariableForReplacement {
-                        var,
-                        block,
-                        replace_value,
-                        with_value,
-                    },
-                )
-                .map_err(Into::into)
             }
         }
-        Ok(())
+    }
+
+    /// Returns an iterator over all declared variables.
+    pub fn vars(&self) -> entity::primary::Indices<VariableEntity> {
+        self.vars.indices()
+    }
+
+    /// Returns the declared type and the definitions per basic block of
+    /// the variable without initializing its entry.
+    ///
+    /// Returns `None` if the variable has not been declared.
+    pub(super) fn declaration(
+        &self,
+        var: Variable,
+    ) -> Option<(Type, Option<&VariableDefinitions>)> {
+        if !self.vars.contains_key(var) {
+            return None
+        }
+        match self.var_to_defs.get(var) {
+            Some(defs) => Some((defs.ty, Some(defs))),
+            None => Some((self.var_to_type.get_var_type(var), None)),
+        }
     }
 
     /// Returns all definitions per basic block of the variable.
@@ -0,0 +1,595 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of the invariants of function bodies.
+//!
+//! The function builder upholds the invariants of the Runwell IR by
+//! construction. However, function bodies that have been transformed
+//! afterwards might violate them which otherwise only surfaces as a panic
+//! or an obscure error during interpretation. The verifier instead reports
+//! all violations at once.
+
+use super::{DominatorTree, FunctionBody, Instr, ValueAssoc};
+use crate::ModuleResources;
+use derive_more::Display;
+use ir::{
+    instr::{FloatInstr, Instruction, IntInstr, TerminalInstr},
+    primitive::{Block, Func, FuncType, Global, IntType, Type, Value},
+    Diagnostic,
+    ToDiagnostic,
+    VisitValues,
+};
+use std::collections::HashMap;
+
+/// A single violation of the invariants of a function body.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum VerifierViolation {
+    #[display(
+        fmt = "basic block {} does not end with a terminal instruction",
+        block
+    )]
+    MissingTerminal { block: Block },
+    #[display(
+        fmt = "terminal instruction {} is not at the end of basic block {}",
+        instr,
+        block
+    )]
+    MisplacedTerminal { block: Block, instr: Instr },
+    #[display(
+        fmt = "instruction {} branches to invalid basic block {}",
+        instr,
+        target
+    )]
+    InvalidBranchTarget { instr: Instr, target: Block },
+    #[display(
+        fmt = "instruction {} passes {} arguments to basic block {} with {} parameters",
+        instr,
+        found,
+        target,
+        expected
+    )]
+    UnmatchingBlockArgs {
+        instr: Instr,
+        target: Block,
+        expected: usize,
+        found: usize,
+    },
+    #[display(fmt = "instruction {} uses undefined value {}", instr, value)]
+    UndefinedValue { instr: Instr, value: Value },
+    #[display(
+        fmt = "instruction {} uses value {} whose definition does not dominate it",
+        instr,
+        value
+    )]
+    NonDominatingUse { instr: Instr, value: Value },
+    #[display(
+        fmt = "instruction {} uses value {} of type {} where type {} is expected",
+        instr,
+        value,
+        found,
+        expected
+    )]
+    UnmatchingOperandType {
+        instr: Instr,
+        value: Value,
+        expected: Type,
+        found: Type,
+    },
+    #[display(
+        fmt = "instruction {} has {} operands where {} are expected",
+        instr,
+        found,
+        expected
+    )]
+    UnmatchingOperandCount {
+        instr: Instr,
+        expected: usize,
+        found: usize,
+    },
+    #[display(
+        fmt = "instruction {} refers to invalid function {}",
+        instr,
+        func
+    )]
+    InvalidFunc { instr: Instr, func: Func },
+    #[display(
+        fmt = "instruction {} refers to invalid function type {}",
+        instr,
+        func_type
+    )]
+    InvalidFuncType { instr: Instr, func_type: FuncType },
+    #[display(
+        fmt = "instruction {} refers to invalid global variable {}",
+        instr,
+        global
+    )]
+    InvalidGlobal { instr: Instr, global: Global },
+}
+
+impl ToDiagnostic for VerifierViolation {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = |code| Diagnostic::new(code, self);
+        match self {
+            Self::MissingTerminal { block } => {
+                diagnostic("verifier.missing_terminal")
+                    .with_parameter("block", block)
+            }
+            Self::MisplacedTerminal { block, instr } => {
+                diagnostic("verifier.misplaced_terminal")
+                    .with_parameter("block", block)
+                    .with_parameter("instr", instr)
+            }
+            Self::InvalidBranchTarget { instr, target } => {
+                diagnostic("verifier.invalid_branch_target")
+                    .with_parameter("instr", instr)
+                    .with_parameter("target", target)
+            }
+            Self::UnmatchingBlockArgs {
+                instr,
+                target,
+                expected,
+                found,
+            } => diagnostic("verifier.unmatching_block_args")
+                .with_parameter("instr", instr)
+                .with_parameter("target", target)
+                .with_parameter("expected", expected)
+                .with_parameter("found", found),
+            Self::UndefinedValue { instr, value } => {
+                diagnostic("verifier.undefined_value")
+                    .with_parameter("instr", instr)
+                    .with_parameter("value", value)
+            }
+            Self::NonDominatingUse { instr, value } => {
+                diagnostic("verifier.non_dominating_use")
+                    .with_parameter("instr", instr)
+                    .with_parameter("value", value)
+            }
+            Self::UnmatchingOperandType {
+                instr,
+                value,
+                expected,
+                found,
+            } => diagnostic("verifier.unmatching_operand_type")
+                .with_parameter("instr", instr)
+                .with_parameter("value", value)
+                .with_parameter("expected", expected)
+                .with_parameter("found", found),
+            Self::UnmatchingOperandCount {
+                instr,
+                expected,
+                found,
+            } => diagnostic("verifier.unmatching_operand_count")
+                .with_parameter("instr", instr)
+                .with_parameter("expected", expected)
+                .with_parameter("found", found),
+            Self::InvalidFunc { instr, func } => {
+                diagnostic("verifier.invalid_func")
+                    .with_parameter("instr", instr)
+                    .with_parameter("func", func)
+            }
+            Self::InvalidFuncType { instr, func_type } => {
+                diagnostic("verifier.invalid_func_type")
+                    .with_parameter("instr", instr)
+                    .with_parameter("func_type", func_type)
+            }
+            Self::InvalidGlobal { instr, global } => {
+                diagnostic("verifier.invalid_global")
+                    .with_parameter("instr", instr)
+                    .with_parameter("global", global)
+            }
+        }
+    }
+}
+
+impl FunctionBody {
+    /// Verifies the invariants of the function body of `func`.
+    ///
+    /// Checks that
+    ///
+    /// - every basic block ends with its only terminal instruction,
+    /// - all branches target existing basic blocks and pass an argument
+    ///   for every parameter of their target,
+    /// - all used values are defined and their definitions dominate their
+    ///   uses and
+    /// - all operands are of the types expected by their instructions.
+    ///
+    /// # Note
+    ///
+    /// Uses within unreachable basic blocks are not checked for dominance.
+    ///
+    /// # Errors
+    ///
+    /// Returns all encountered violations.
+    pub fn verify(
+        &self,
+        func: Func,
+        res: &ModuleResources,
+    ) -> Result<(), Vec<VerifierViolation>> {
+        let mut verifier = Verifier {
+            body: self,
+            func,
+            res,
+            positions: HashMap::new(),
+            dominators: self.dominator_tree(),
+            violations: Vec::new(),
+        };
+        verifier.compute_positions();
+        for block in self.blocks() {
+            verifier.verify_block(block);
+        }
+        if !verifier.violations.is_empty() {
+            return Err(verifier.violations)
+        }
+        Ok(())
+    }
+}
+
+/// Verifies a single function body and collects all violations.
+#[derive(Debug)]
+struct Verifier<'a> {
+    body: &'a FunctionBody,
+    func: Func,
+    res: &'a ModuleResources,
+    /// The basic block and position of every instruction within it.
+    positions: HashMap<Instr, (Block, usize)>,
+    /// The dominator tree of the reachable basic blocks.
+    dominators: DominatorTree,
+    violations: Vec<VerifierViolation>,
+}
+
+impl<'a> Verifier<'a> {
+    /// Computes the positions of all instructions within their basic blocks.
+    fn compute_positions(&mut self) {
+        for block in self.body.blocks() {
+            for (n, &instr) in self.body.block_instrs(block).iter().enumerate()
+            {
+                self.positions.insert(instr, (block, n));
+            }
+        }
+    }
+
+    /// Verifies all instructions of the basic block.
+    fn verify_block(&mut self, block: Block) {
+        let instrs = self.body.block_instrs(block);
+        match instrs.last() {
+            Some(&last) if self.body.instrs[last].is_terminal() => (),
+            _ => self
+                .violations
+                .push(VerifierViolation::MissingTerminal { block }),
+        }
+        for (n, &instr) in instrs.iter().enumerate() {
+            let instruction = &self.body.instrs[instr];
+            if instruction.is_terminal() && n != instrs.len() - 1 {
+                self.violations.push(VerifierViolation::MisplacedTerminal {
+                    block,
+                    instr,
+                });
+            }
+            self.verify_uses(block, n, instr);
+            self.verify_operand_types(instr);
+        }
+    }
+
+    /// Verifies that all values used by the instruction dominate it.
+    fn verify_uses(&mut self, block: Block, position: usize, instr: Instr) {
+        let mut used = Vec::new();
+        self.body.instrs[instr].visit_values(|value| {
+            used.push(value);
+            true
+        });
+        let is_reachable = self.dominators.is_reachable(block);
+        for value in used {
+            let is_dominating = match self.body.value_assoc.get(value) {
+                Some(ValueAssoc::Input(_)) => true,
+                Some(ValueAssoc::Param(def_block, _)) => {
+                    self.body.contains_block(*def_block)
+                        && (!is_reachable
+                            || self.dominators.dominates(*def_block, block))
+                }
+                Some(ValueAssoc::Instr(def_instr, _)) => {
+                    match self.positions.get(def_instr) {
+                        Some(&(def_block, def_position))
+                            if def_block == block =>
+                        {
+                            def_position < position
+                        }
+                        Some(&(def_block, _)) => {
+                            !is_reachable
+                                || self.dominators.dominates(def_block, block)
+                        }
+                        None => {
+                            self.violations.push(
+                                VerifierViolation::UndefinedValue {
+                                    instr,
+                                    value,
+                                },
+                            );
+                            continue
+                        }
+                    }
+                }
+                None => {
+                    self.violations.push(VerifierViolation::UndefinedValue {
+                        instr,
+                        value,
+                    });
+                    continue
+                }
+            };
+            if !is_dominating {
+                self.violations
+                    .push(VerifierViolation::NonDominatingUse { instr, value });
+            }
+        }
+    }
+
+    /// Reports a violation if the value is defined but not of the expected type.
+    fn expect_type(&mut self, instr: Instr, value: Value, expected: Type) {
+        if let Some(found) = self.body.value_type(value) {
+            if found != expected {
+                self.violations.push(
+                    VerifierViolation::UnmatchingOperandType {
+                        instr,
+                        value,
+                        expected,
+                        found,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Reports violations if the values do not match the expected types.
+    fn expect_types(
+        &mut self,
+        instr: Instr,
+        values: &[Value],
+        expected: &[Type],
+    ) {
+        if values.len() != expected.len() {
+            self.violations
+                .push(VerifierViolation::UnmatchingOperandCount {
+                    instr,
+                    expected: expected.len(),
+                    found: values.len(),
+                });
+            return
+        }
+        for (&value, &expected) in values.iter().zip(expected) {
+            self.expect_type(instr, value, expected);
+        }
+    }
+
+    /// Reports violations if the arguments do not match the inputs of the function.
+    fn expect_call(&mut self, instr: Instr, func: Func, params: &[Value]) {
+        match self.res.get_func_type(func) {
+            Some(func_type) => {
+                self.expect_types(instr, params, func_type.inputs())
+            }
+            None => self
+                .violations
+                .push(VerifierViolation::InvalidFunc { instr, func }),
+        }
+    }
+
+    /// Reports violations if the arguments do not match the function type.
+    fn expect_call_indirect(
+        &mut self,
+        instr: Instr,
+        func_type: FuncType,
+        index: Value,
+        params: &[Value],
+    ) {
+        self.expect_type(instr, index, IntType::I32.into());
+        match self.res.get_type(func_type) {
+            Some(signature) => {
+                self.expect_types(instr, params, signature.inputs())
+            }
+            None => self
+                .violations
+                .push(VerifierViolation::InvalidFuncType { instr, func_type }),
+        }
+    }
+
+    /// Verifies that all operands of the instruction are of the expected types.
+    fn verify_operand_types(&mut self, instr: Instr) {
+        let i32_type = Type::from(IntType::I32);
+        match &self.body.instrs[instr] {
+            Instruction::Call(call) => {
+                self.expect_call(instr, call.func(), call.params())
+            }
+            Instruction::CallIndirect(call) => self.expect_call_indirect(
+                instr,
+                call.func_type(),
+                call.index(),
+                call.params(),
+            ),
+            Instruction::Const(_)
+            | Instruction::MemorySize(_)
+            | Instruction::DataAddr(_)
+            | Instruction::GlobalGet(_) => (),
+            Instruction::MemoryGrow(grow) => {
+                self.expect_type(instr, grow.new_pages(), i32_type)
+            }
+            Instruction::HeapAddr(heap_addr) => {
+                self.expect_type(instr, heap_addr.ptr(), i32_type)
+            }
+            Instruction::GlobalSet(global_set) => {
+                let global = global_set.global();
+                match self.res.get_global_decl(global) {
+                    Some(decl) => {
+                        self.expect_type(instr, global_set.value(), decl.ty())
+                    }
+                    None => {
+                        self.violations.push(VerifierViolation::InvalidGlobal {
+                            instr,
+                            global,
+                        })
+                    }
+                }
+            }
+            Instruction::Load(load) => {
+                self.expect_type(instr, load.address(), Type::Ptr)
+            }
+            Instruction::Store(store) => {
+                self.expect_type(instr, store.address(), Type::Ptr);
+                self.expect_type(instr, store.value(), store.ty());
+            }
+            Instruction::Select(select) => {
+                let condition = select.condition();
+                if self.body.value_type(condition) != Some(i32_type) {
+                    self.expect_type(instr, condition, Type::Bool);
+                }
+                self.expect_type(instr, select.true_value(), select.ty());
+                self.expect_type(instr, select.false_value(), select.ty());
+            }
+            Instruction::Reinterpret(reinterpret) => self.expect_type(
+                instr,
+                reinterpret.src(),
+                reinterpret.src_type(),
+            ),
+            Instruction::Int(int) => self.verify_int_operand_types(instr, int),
+            Instruction::Float(float) => {
+                self.verify_float_operand_types(instr, float)
+            }
+            Instruction::Terminal(terminal) => {
+                self.verify_terminal(instr, terminal)
+            }
+        }
+    }
+
+    /// Verifies that all operands of the integer instruction are of the expected types.
+    fn verify_int_operand_types(&mut self, instr: Instr, int: &IntInstr) {
+        match int {
+            IntInstr::Binary(binary) => {
+                self.expect_type(instr, binary.lhs(), binary.ty().into());
+                self.expect_type(instr, binary.rhs(), binary.ty().into());
+            }
+            IntInstr::Unary(unary) => {
+                self.expect_type(instr, unary.src(), unary.ty().into())
+            }
+            IntInstr::Compare(compare) => {
+                self.expect_type(instr, compare.lhs(), compare.ty().into());
+                self.expect_type(instr, compare.rhs(), compare.ty().into());
+            }
+            IntInstr::Extend(extend) => {
+                self.expect_type(instr, extend.src(), extend.src_type().into())
+            }
+            IntInstr::IntToFloat(convert) => self.expect_type(
+                instr,
+                convert.src(),
+                convert.src_type().into(),
+            ),
+            IntInstr::Truncate(truncate) => self.expect_type(
+                instr,
+                truncate.src(),
+                truncate.src_type().into(),
+            ),
+            IntInstr::Shift(shift) => {
+                self.expect_type(instr, shift.source(), shift.ty().into());
+                self.expect_type(
+                    instr,
+                    shift.shift_amount(),
+                    IntType::I32.into(),
+                );
+            }
+        }
+    }
+
+    /// Verifies that all operands of the float instruction are of the expected types.
+    fn verify_float_operand_types(&mut self, instr: Instr, float: &FloatInstr) {
+        match float {
+            FloatInstr::Unary(unary) => {
+                self.expect_type(instr, unary.src(), unary.ty().into())
+            }
+            FloatInstr::Binary(binary) => {
+                self.expect_type(instr, binary.lhs(), binary.ty().into());
+                self.expect_type(instr, binary.rhs(), binary.ty().into());
+            }
+            FloatInstr::Compare(compare) => {
+                self.expect_type(instr, compare.lhs(), compare.ty().into());
+                self.expect_type(instr, compare.rhs(), compare.ty().into());
+            }
+            FloatInstr::Demote(demote) => {
+                self.expect_type(instr, demote.src(), demote.src_type().into())
+            }
+            FloatInstr::Promote(promote) => self.expect_type(
+                instr,
+                promote.src(),
+                promote.src_type().into(),
+            ),
+            FloatInstr::FloatToInt(convert) => self.expect_type(
+                instr,
+                convert.src(),
+                convert.src_type().into(),
+            ),
+        }
+    }
+
+    /// Verifies the branch targets and the operand types of the terminal instruction.
+    fn verify_terminal(&mut self, instr: Instr, terminal: &TerminalInstr) {
+        for target in terminal.branch_targets() {
+            let block = target.block();
+            if !self.body.contains_block(block) {
+                self.violations
+                    .push(VerifierViolation::InvalidBranchTarget {
+                        instr,
+                        target: block,
+                    });
+                continue
+            }
+            let params = self.body.block_params(block);
+            if params.len() != target.args().len() {
+                self.violations
+                    .push(VerifierViolation::UnmatchingBlockArgs {
+                        instr,
+                        target: block,
+                        expected: params.len(),
+                        found: target.args().len(),
+                    });
+                continue
+            }
+            for (&arg, &param) in target.args().iter().zip(params) {
+                let param_type = self.body.value_type[param];
+                self.expect_type(instr, arg, param_type);
+            }
+        }
+        match terminal {
+            TerminalInstr::Trap(_) | TerminalInstr::Br(_) => (),
+            TerminalInstr::Return(ret) => {
+                let outputs = self
+                    .res
+                    .get_func_type(self.func)
+                    .map(|func_type| func_type.outputs().to_vec())
+                    .unwrap_or_default();
+                self.expect_types(instr, ret.return_values(), &outputs)
+            }
+            TerminalInstr::Ite(ite) => {
+                self.expect_type(instr, ite.condition(), Type::Bool)
+            }
+            TerminalInstr::TailCall(call) => {
+                self.expect_call(instr, call.func(), call.params())
+            }
+            TerminalInstr::TailCallIndirect(call) => self.expect_call_indirect(
+                instr,
+                call.func_type(),
+                call.index(),
+                call.params(),
+            ),
+            TerminalInstr::BranchTable(table) => {
+                self.expect_type(instr, table.case(), IntType::I32.into())
+            }
+        }
+    }
+}
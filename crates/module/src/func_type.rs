@@ -16,7 +16,7 @@ use core::fmt;
 use ir::primitive::Type;
 
 /// A function type.
-#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct FunctionType {
     /// The amount of inputs.
     len_inputs: usize,
@@ -12,10 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{module::Indent, primitive::FunctionType, FunctionBody};
+use crate::{
+    module::Indent,
+    primitive::{FunctionType, ImportName, Variable},
+    DisplayConfig,
+    FunctionBody,
+};
 use core::fmt;
 use entity::RawIdx;
-use ir::primitive::{Func, Type, Value};
+use ir::primitive::{Block, Func, Type, Value};
 
 /// A Runwell function.
 ///
@@ -74,11 +79,52 @@ impl<'a> Function<'a> {
         &self.func_body
     }
 
-    /// Displays the function using the given indentation.
+    /// Returns an iterator over all declared variables of the function.
+    ///
+    /// # Note
+    ///
+    /// This includes the variables that are associated to the function inputs.
+    #[inline]
+    pub fn vars(&self) -> impl Iterator<Item = Variable> + 'a {
+        self.func_body.vars()
+    }
+
+    /// Returns the declared type of the variable if it has been declared.
+    #[inline]
+    pub fn var_type(&self, var: Variable) -> Option<Type> {
+        self.func_body.var_type(var)
+    }
+
+    /// Returns the SSA value definition of the variable at the end of the block.
+    #[inline]
+    pub fn var_definition(&self, var: Variable, block: Block) -> Option<Value> {
+        self.func_body.var_definition(var, block)
+    }
+
+    /// Returns an iterator over all basic blocks and the respective SSA value
+    /// definitions of the variable.
+    #[inline]
+    pub fn var_definitions(
+        &self,
+        var: Variable,
+    ) -> impl Iterator<Item = (Block, Value)> + 'a {
+        self.func_body.var_definitions(var)
+    }
+
+    /// Returns a wrapper that displays the function using the configuration.
+    pub fn display_with(&self, config: DisplayConfig) -> DisplayFunction<'a> {
+        DisplayFunction {
+            function: *self,
+            config,
+        }
+    }
+
+    /// Displays the function using the given indentation and configuration.
     pub(crate) fn display_with_indent(
         &self,
         f: &mut fmt::Formatter,
         indent: Indent,
+        config: DisplayConfig,
     ) -> Result<(), fmt::Error> {
         let inputs = self
             .inputs()
@@ -112,8 +158,11 @@ impl<'a> Function<'a> {
             }
         }
         writeln!(f, " {{")?;
-        self.body()
-            .display_with_indent(f, indent + Indent::single())?;
+        self.body().display_with_indent(
+            f,
+            indent + Indent::single(),
+            config,
+        )?;
         writeln!(f, "{}}}", indent)?;
         Ok(())
     }
@@ -121,6 +170,121 @@ impl<'a> Function<'a> {
 
 impl<'a> fmt::Display for Function<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.display_with_indent(f, Default::default())
+        self.display_with_indent(f, Default::default(), Default::default())
+    }
+}
+
+/// Displays a function using a display configuration.
+#[derive(Debug, Copy, Clone)]
+pub struct DisplayFunction<'a> {
+    function: Function<'a>,
+    config: DisplayConfig,
+}
+
+impl<'a> fmt::Display for DisplayFunction<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.function
+            .display_with_indent(f, Default::default(), self.config)
+    }
+}
+
+/// A Runwell function that is imported by its module.
+///
+/// Includes the function's unique index, signature and import name.
+#[derive(Debug, Copy, Clone)]
+pub struct ImportedFunction<'a> {
+    func: Func,
+    func_type: &'a FunctionType,
+    import_name: &'a ImportName,
+}
+
+impl<'a> ImportedFunction<'a> {
+    /// Creates a new imported function.
+    pub(super) fn new(
+        func: Func,
+        func_type: &'a FunctionType,
+        import_name: &'a ImportName,
+    ) -> Self {
+        Self {
+            func,
+            func_type,
+            import_name,
+        }
+    }
+
+    /// Returns the function's unique index.
+    #[inline]
+    pub fn idx(&self) -> Func {
+        self.func
+    }
+
+    /// Returns the function's signature.
+    #[inline]
+    pub fn ty(&self) -> &'a FunctionType {
+        self.func_type
+    }
+
+    /// Returns the function's input types.
+    #[inline]
+    pub fn inputs(&self) -> &'a [Type] {
+        self.func_type.inputs()
+    }
+
+    /// Returns the function's output types.
+    #[inline]
+    pub fn outputs(&self) -> &'a [Type] {
+        self.func_type.outputs()
+    }
+
+    /// Returns the name under which the function is imported.
+    #[inline]
+    pub fn import_name(&self) -> &'a ImportName {
+        self.import_name
+    }
+}
+
+/// A function of a module that is either imported or internal.
+///
+/// Imported and internal functions share the same function index space
+/// so that the index of either can be used to refer to it.
+#[derive(Debug, Copy, Clone)]
+pub enum ModuleFunction<'a> {
+    /// A function that is imported by the module.
+    Imported(ImportedFunction<'a>),
+    /// A function that is defined by the module with its body.
+    Internal(Function<'a>),
+}
+
+impl<'a> ModuleFunction<'a> {
+    /// Returns the function's unique index.
+    pub fn idx(&self) -> Func {
+        match self {
+            Self::Imported(function) => function.idx(),
+            Self::Internal(function) => function.idx(),
+        }
+    }
+
+    /// Returns the function's signature.
+    pub fn ty(&self) -> &'a FunctionType {
+        match self {
+            Self::Imported(function) => function.ty(),
+            Self::Internal(function) => function.ty(),
+        }
+    }
+
+    /// Returns the import name if the function is imported.
+    pub fn import_name(&self) -> Option<&'a ImportName> {
+        match self {
+            Self::Imported(function) => Some(function.import_name()),
+            Self::Internal(_) => None,
+        }
+    }
+
+    /// Returns the function body if the function is internal.
+    pub fn body(&self) -> Option<&'a FunctionBody> {
+        match self {
+            Self::Imported(_) => None,
+            Self::Internal(function) => Some(function.body()),
+        }
     }
 }
@@ -12,8 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use core::fmt;
-use entity::{DisplayHook, Idx};
+pub use ir::primitive::{Global, GlobalVariableEntity};
 use ir::primitive::Type;
 
 /// A global variable declaration.
@@ -39,16 +38,3 @@ impl GlobalVariable {
         self.is_mutable
     }
 }
-
-/// A global variable entity.
-#[derive(Debug, Default)]
-pub struct GlobalVariableEntity;
-
-/// The unique index of a global variable.
-pub type Global = Idx<GlobalVariableEntity>;
-
-impl DisplayHook for GlobalVariableEntity {
-    fn fmt(idx: Global, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "global({})", idx.into_raw())
-    }
-}
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 /// A module and field name for an imported entity.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ImportName {
     module_name: String,
     field_name: String,
@@ -15,27 +15,68 @@
 #![forbid(unsafe_code)]
 
 mod error;
+pub mod filecheck;
 mod func_body;
 mod func_type;
 mod function;
 mod global_var;
 mod import_name;
 mod init_expr;
+mod limits;
 mod linear_memory;
 mod module;
+mod producers;
 mod table;
 
 pub use self::{
     error::{Error, ErrorKind},
-    func_body::{FunctionBody, FunctionBuilderError},
-    function::Function,
-    module::{Module, ModuleResources},
+    func_body::{
+        CheckedArithmetic,
+        DisplayConfig,
+        DisplayFunctionBody,
+        DominatorTree,
+        FuncCursor,
+        FunctionBody,
+        FunctionBuilderError,
+        FunctionEditor,
+        FunctionEditorError,
+        InstructionNumbering,
+        InstructionStats,
+        InstrumentationHooks,
+        Metadata,
+        MetadataKind,
+        Overflow,
+        SecretLeak,
+        ShadowStack,
+        TargetConstraints,
+        VerifierViolation,
+    },
+    function::{
+        DisplayFunction,
+        Function,
+        ImportedFunction,
+        ModuleFunction,
+    },
+    limits::ResourceLimits,
+    module::{
+        Module,
+        ModulePolicy,
+        ModuleResources,
+        ModuleStats,
+        PolicyViolation,
+        StartFuncPolicy,
+    },
 };
 
 /// Module section builder types.
 pub mod builder {
     pub use super::{
-        func_body::{FunctionBuilder, InstructionBuilder},
+        func_body::{
+            FunctionBuilder,
+            InstructionBuilder,
+            IrArena,
+            SwitchLowering,
+        },
         func_type::FunctionTypeBuilder,
         module::{
             ModuleBuilder,
@@ -46,6 +87,7 @@ pub mod builder {
             ModuleImportsBuilder,
             ModuleMemoriesBuilder,
             ModuleMemoryDataBuilder,
+            ModuleReadOnlyDataBuilder,
             ModuleTableElementsBuilder,
             ModuleTablesBuilder,
             ModuleTypesBuilder,
@@ -56,12 +98,21 @@ pub mod builder {
 /// Primitive Wasm types that implement conversion to Runwell types.
 pub mod primitive {
     pub use super::{
-        func_body::{Instr, Variable},
+        func_body::{Instr, SourceSpan, Variable},
         func_type::FunctionType,
         global_var::{Global, GlobalVariable, GlobalVariableEntity},
         import_name::ImportName,
         init_expr::InitExpr,
         linear_memory::{DataSegmentIter, LinearMemoryDecl, LinearMemoryInit},
+        module::GlobalInit,
+        producers::{
+            Producers,
+            ProducersField,
+            ProducersValue,
+            LANGUAGE_FIELD,
+            PROCESSED_BY_FIELD,
+            SDK_FIELD,
+        },
         table::{ElementSegmentIter, TableDecl, TableInit},
     };
 }
@@ -0,0 +1,71 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Limits on the sizes of linear memories and tables.
+
+/// The maximum number of pages of a linear memory with 32-bit addresses.
+const WASM_MAX_MEMORY_PAGES: u32 = 65536;
+
+/// Limits on the sizes of linear memories and tables.
+///
+/// The limits apply uniformly to the declared initial sizes of linear
+/// memories and tables, including imported ones, as well as to their
+/// growth at runtime.
+///
+/// By default only the limits imposed by Wasm itself apply.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// The maximum number of pages of a linear memory.
+    max_memory_pages: u32,
+    /// The maximum number of elements of a table.
+    max_table_size: u32,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_memory_pages: WASM_MAX_MEMORY_PAGES,
+            max_table_size: u32::MAX,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Creates the default resource limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of pages of a linear memory.
+    ///
+    /// Limits beyond the 65536 pages addressable by Wasm are clamped.
+    pub fn set_max_memory_pages(&mut self, max_memory_pages: u32) {
+        self.max_memory_pages = max_memory_pages.min(WASM_MAX_MEMORY_PAGES);
+    }
+
+    /// Returns the maximum number of pages of a linear memory.
+    pub fn max_memory_pages(&self) -> u32 {
+        self.max_memory_pages
+    }
+
+    /// Sets the maximum number of elements of a table.
+    pub fn set_max_table_size(&mut self, max_table_size: u32) {
+        self.max_table_size = max_table_size;
+    }
+
+    /// Returns the maximum number of elements of a table.
+    pub fn max_table_size(&self) -> u32 {
+        self.max_table_size
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::module::res::ModuleResources;
+use ir::primitive::Data;
+
+/// Constructs module-level read-only data objects.
+///
+/// Read-only data objects are byte blobs with symbolic indices that
+/// functions can reference via the `data_addr` instruction, e.g. for
+/// string literals or lookup tables.
+#[derive(Debug)]
+pub struct ModuleReadOnlyDataBuilder<'a> {
+    res: &'a mut ModuleResources,
+}
+
+impl<'a> ModuleReadOnlyDataBuilder<'a> {
+    /// Creates a new module read-only data builder.
+    pub(super) fn new(res: &'a mut ModuleResources) -> Self {
+        Self { res }
+    }
+
+    /// Reserves space for `additional` read-only data objects.
+    pub fn reserve(&mut self, additional: u32) {
+        self.res.read_only_data.reserve_exact(additional);
+    }
+
+    /// Pushes a new read-only data object to the module and returns an index to it.
+    pub fn push_data<T>(&mut self, bytes: T) -> Data
+    where
+        T: IntoIterator<Item = u8>,
+    {
+        let idx = self.res.data_entities.alloc_some(1);
+        self.res
+            .read_only_data
+            .insert(idx, bytes.into_iter().collect());
+        idx
+    }
+}
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use crate::{module::res::ModuleResources, FunctionBody};
-use entity::ComponentVec;
+use entity::{ComponentMap, ComponentVec};
 use ir::primitive::{Func, FuncType};
 
 /// Constructs module function declarations for internally defined functions.
@@ -38,7 +38,7 @@ impl<'a> ModuleFunctionsBuilder<'a> {
         &mut self,
         func_type: FuncType,
     ) -> Result<Func, String> {
-        self.res.ensure_func_type_exists(func_type)?;
+        let func_type = self.res.canonicalize_func_type(func_type)?;
         let idx = self.res.function_entities.alloc_some(1);
         self.res.function_decls.insert(idx, func_type);
         Ok(idx)
@@ -50,6 +50,7 @@ impl<'a> ModuleFunctionsBuilder<'a> {
 pub struct ModuleFunctionBodiesBuilder<'a> {
     res: &'a ModuleResources,
     bodies: &'a mut ComponentVec<Func, FunctionBody>,
+    interpreter_only: &'a mut ComponentMap<Func, Box<[u8]>>,
 }
 
 impl<'a> ModuleFunctionBodiesBuilder<'a> {
@@ -57,8 +58,13 @@ impl<'a> ModuleFunctionBodiesBuilder<'a> {
     pub(super) fn new(
         res: &'a ModuleResources,
         bodies: &'a mut ComponentVec<Func, FunctionBody>,
+        interpreter_only: &'a mut ComponentMap<Func, Box<[u8]>>,
     ) -> Self {
-        Self { res, bodies }
+        Self {
+            res,
+            bodies,
+            interpreter_only,
+        }
     }
 
     /// Reserves space for `additional` function bodies.
@@ -87,4 +93,24 @@ impl<'a> ModuleFunctionBodiesBuilder<'a> {
         self.bodies.insert(func, body);
         Ok(())
     }
+
+    /// Registers the function body for the given function as interpreter-only.
+    ///
+    /// # Note
+    ///
+    /// Used for functions whose translation into Runwell IR has been aborted,
+    /// e.g. because it exceeded its translation budget.
+    /// The `body` is a placeholder with the signature of the function whereas
+    /// the `source` holds its binary encoded original function body that is
+    /// to be executed directly by a baseline interpreter.
+    pub fn push_interpreter_only_body(
+        &mut self,
+        func: Func,
+        body: FunctionBody,
+        source: Vec<u8>,
+    ) -> Result<(), String> {
+        self.push_body(func, body)?;
+        self.interpreter_only.insert(func, source.into_boxed_slice());
+        Ok(())
+    }
 }
@@ -39,7 +39,7 @@ impl<'a> ModuleImportsBuilder<'a> {
         name: ImportName,
         func_type: FuncType,
     ) -> Result<Func, String> {
-        self.res.ensure_func_type_exists(func_type)?;
+        let func_type = self.res.canonicalize_func_type(func_type)?;
         let idx = self.res.function_entities.alloc_some(1);
         self.res.function_decls.insert(idx, func_type);
         self.res.function_import.insert(idx, name);
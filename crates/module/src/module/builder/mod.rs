@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod data;
 mod export;
 mod function;
 mod global;
@@ -21,7 +22,8 @@ mod table;
 mod types;
 
 pub use self::{
-    export::{ExportKind, ExportName, ModuleExportsBuilder},
+    data::ModuleReadOnlyDataBuilder,
+    export::ModuleExportsBuilder,
     function::{ModuleFunctionBodiesBuilder, ModuleFunctionsBuilder},
     global::{GlobalInit, ModuleGlobalsBuilder},
     import::ModuleImportsBuilder,
@@ -32,8 +34,8 @@ pub use self::{
 use super::Module;
 
 use super::res::ModuleResources;
-use crate::FunctionBody;
-use entity::ComponentVec;
+use crate::{primitive::Producers, FunctionBody};
+use entity::{ComponentMap, ComponentVec};
 use ir::primitive::Func;
 
 /// A module builder to incrementally build up a Runwell module.
@@ -45,6 +47,8 @@ pub struct ModuleBuilder {
     res: ModuleResources,
     /// The bodies (implementations) of the internal functions.
     bodies: ComponentVec<Func, FunctionBody>,
+    /// The original source bodies of interpreter-only internal functions.
+    interpreter_only: ComponentMap<Func, Box<[u8]>>,
 }
 
 /// The Runwell module sections.
@@ -61,8 +65,9 @@ pub enum ModuleSection {
     Globals = 6,
     Exports = 7,
     TableElements = 8,
-    FunctionBodies = 9,
-    MemoryData = 10,
+    ReadOnlyData = 9,
+    FunctionBodies = 10,
+    MemoryData = 11,
 }
 
 impl ModuleBuilder {
@@ -72,6 +77,7 @@ impl ModuleBuilder {
             section: None,
             res: Default::default(),
             bodies: Default::default(),
+            interpreter_only: Default::default(),
         }
     }
 
@@ -156,6 +162,26 @@ impl ModuleBuilder {
         Ok(())
     }
 
+    /// Sets the toolchain metadata of the module.
+    ///
+    /// # Note
+    ///
+    /// Unlike other module sections the metadata can be set at any point
+    /// during module construction since the Wasm `producers` custom section
+    /// may appear anywhere in a Wasm binary.
+    pub fn set_producers(
+        &mut self,
+        producers: Producers,
+    ) -> Result<(), String> {
+        if self.res.producers.is_some() {
+            return Err(String::from(
+                "tried to set producers while the module already has producers",
+            ))
+        }
+        self.res.producers = Some(producers);
+        Ok(())
+    }
+
     /// Returns a module table elements builder.
     pub fn table_element_section(
         &mut self,
@@ -164,6 +190,14 @@ impl ModuleBuilder {
         Ok(ModuleTableElementsBuilder::new(&mut self.res))
     }
 
+    /// Returns a module read-only data builder.
+    pub fn read_only_data_section(
+        &mut self,
+    ) -> Result<ModuleReadOnlyDataBuilder, String> {
+        self.ensure_section_in_order(ModuleSection::ReadOnlyData)?;
+        Ok(ModuleReadOnlyDataBuilder::new(&mut self.res))
+    }
+
     /// Returns a module memory data builder.
     pub fn memory_data_section(
         &mut self,
@@ -177,9 +211,15 @@ impl ModuleBuilder {
         &mut self,
     ) -> Result<(&ModuleResources, ModuleFunctionBodiesBuilder), String> {
         self.ensure_section_in_order(ModuleSection::FunctionBodies)?;
-        let Self { res, bodies, .. } = self;
+        let Self {
+            res,
+            bodies,
+            interpreter_only,
+            ..
+        } = self;
         let res = &*res;
-        let builder = ModuleFunctionBodiesBuilder::new(res, bodies);
+        let builder =
+            ModuleFunctionBodiesBuilder::new(res, bodies, interpreter_only);
         Ok((res, builder))
     }
 
@@ -201,9 +241,12 @@ impl ModuleBuilder {
         }
         self.res.shrink_to_fit();
         self.bodies.shrink_to_fit();
+        self.interpreter_only.shrink_to_fit();
         Ok(Module {
             res: self.res,
             bodies: self.bodies,
+            interpreter_only: self.interpreter_only,
+            passes: Vec::new(),
         })
     }
 }
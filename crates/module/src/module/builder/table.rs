@@ -60,6 +60,11 @@ impl<'a> ModuleTableElementsBuilder<'a> {
     }
 
     /// Pushes a new table initializer to the module.
+    ///
+    /// # Errors
+    ///
+    /// - If the table does not exist.
+    /// - If any of the functions does not exist in the function space.
     pub fn push_element<T>(
         &mut self,
         idx: Table,
@@ -75,6 +80,15 @@ impl<'a> ModuleTableElementsBuilder<'a> {
                 idx,
             ))
         }
+        let funcs = funcs.into_iter().collect::<Vec<_>>();
+        for (index, &func) in funcs.iter().enumerate() {
+            if self.res.get_raw_func_type(func).is_none() {
+                return Err(format!(
+                    "encountered invalid function {} at index {} of an element segment for table {}",
+                    func, index, idx,
+                ))
+            }
+        }
         self.res.table_inits[idx].push_element(offset, funcs);
         Ok(())
     }
@@ -29,11 +29,24 @@ impl<'a> ModuleTypesBuilder<'a> {
     /// Reserves space for `additional` function types.
     pub fn reserve(&mut self, additional: u32) {
         self.res.types.reserve_exact(additional);
+        self.res.canonical_types.reserve_exact(additional);
     }
 
     /// Pushes a new function type to the module and returns an index to it.
+    ///
+    /// # Note
+    ///
+    /// Structurally identical function types are interned so that they share
+    /// the same canonical function type. The returned index still refers to
+    /// the pushed function type.
     pub fn push_type(&mut self, func_type: FunctionType) -> FuncType {
         let idx = self.res.type_entities.alloc_some(1);
+        let canonical = *self
+            .res
+            .interned_types
+            .entry(func_type.clone())
+            .or_insert(idx);
+        self.res.canonical_types.insert(idx, canonical);
         self.res.types[idx] = func_type;
         idx
     }
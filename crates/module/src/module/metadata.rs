@@ -0,0 +1,378 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine-readable metadata of modules for external tooling.
+//!
+//! The metadata is a JSON document that describes the functions of a module
+//! with their signatures, the functions they call and the entities they use
+//! as well as the imports that are required to instantiate the module.
+//! Auditing and visualization tools can consume it without linking against
+//! Runwell.
+
+use super::Module;
+use crate::{
+    primitive::{GlobalInit, ImportName},
+    FunctionBody,
+};
+use core::fmt;
+use entity::Idx;
+use ir::{
+    instr::{Instruction, TerminalInstr},
+    primitive::{Func, FuncType, Global, Mem, Table, Type},
+};
+use std::collections::BTreeSet;
+
+impl Module {
+    /// Returns a JSON document describing the module for external tooling.
+    ///
+    /// The document lists the functions with their signatures, call edges
+    /// and used linear memories, tables and global variables, as well as
+    /// the linear memories, tables, global variables and imports of the
+    /// module.
+    ///
+    /// # Note
+    ///
+    /// Interpreter-only functions have no Runwell IR body and therefore
+    /// report neither call edges nor used entities.
+    pub fn export_metadata(&self) -> String {
+        ModuleMetadata { module: self }.to_string()
+    }
+}
+
+/// The entities used by the body of a single function.
+#[derive(Debug, Default)]
+struct FunctionUses {
+    /// The directly called functions.
+    calls: BTreeSet<Func>,
+    /// The tables and function types of indirect calls.
+    indirect_calls: BTreeSet<(Table, FuncType)>,
+    /// The accessed linear memories.
+    memories: BTreeSet<Mem>,
+    /// The read global variables.
+    globals_read: BTreeSet<Global>,
+    /// The written global variables.
+    globals_written: BTreeSet<Global>,
+}
+
+impl FunctionUses {
+    /// Collects the entities used by the function body.
+    fn new(body: &FunctionBody) -> Self {
+        let mut uses = Self::default();
+        let instrs = body
+            .blocks()
+            .flat_map(|block| body.block_instrs(block).iter().copied())
+            .filter_map(|instr| body.instr(instr));
+        for instruction in instrs {
+            match instruction {
+                Instruction::Call(instr) => {
+                    uses.calls.insert(instr.func());
+                }
+                Instruction::CallIndirect(instr) => {
+                    uses.indirect_calls
+                        .insert((instr.table(), instr.func_type()));
+                }
+                Instruction::Terminal(TerminalInstr::TailCall(instr)) => {
+                    uses.calls.insert(instr.func());
+                }
+                Instruction::Terminal(TerminalInstr::TailCallIndirect(
+                    instr,
+                )) => {
+                    uses.indirect_calls
+                        .insert((instr.table(), instr.func_type()));
+                }
+                Instruction::HeapAddr(instr) => {
+                    uses.memories.insert(instr.heap());
+                }
+                Instruction::MemorySize(instr) => {
+                    uses.memories.insert(instr.memory());
+                }
+                Instruction::MemoryGrow(instr) => {
+                    uses.memories.insert(instr.memory());
+                }
+                Instruction::GlobalGet(instr) => {
+                    uses.globals_read.insert(instr.global());
+                }
+                Instruction::GlobalSet(instr) => {
+                    uses.globals_written.insert(instr.global());
+                }
+                _ => (),
+            }
+        }
+        uses
+    }
+
+    /// Returns the tables used by indirect calls.
+    fn tables(&self) -> BTreeSet<Table> {
+        self.indirect_calls
+            .iter()
+            .map(|&(table, _)| table)
+            .collect()
+    }
+}
+
+/// Writes the metadata of the module as JSON document.
+struct ModuleMetadata<'a> {
+    module: &'a Module,
+}
+
+impl fmt::Display for ModuleMetadata<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let res = &self.module.res;
+        write!(f, "{{\"functions\":[")?;
+        for (n, func) in res.function_entities.indices().enumerate() {
+            if n > 0 {
+                write!(f, ",")?;
+            }
+            self.write_function(f, func)?;
+        }
+        write!(f, "],\"memories\":[")?;
+        for (n, memory) in self.module.memories().enumerate() {
+            if n > 0 {
+                write!(f, ",")?;
+            }
+            let decl = &res.memory_decls[memory];
+            write!(f, "{{\"index\":{},", raw(memory))?;
+            write!(f, "\"initial_pages\":{},", decl.initial_pages())?;
+            write!(f, "\"maximum_pages\":")?;
+            write_optional(f, decl.maximum_pages())?;
+            write!(f, ",\"import\":")?;
+            write_import(f, res.memory_import.get(memory))?;
+            write!(f, ",\"export\":")?;
+            write_export(f, res.memory_export.get(memory))?;
+            write!(f, "}}")?;
+        }
+        write!(f, "],\"tables\":[")?;
+        for (n, table) in self.module.tables().enumerate() {
+            if n > 0 {
+                write!(f, ",")?;
+            }
+            let decl = &res.table_decls[table];
+            write!(f, "{{\"index\":{},", raw(table))?;
+            write!(f, "\"initial_size\":{},", decl.initial_size())?;
+            write!(f, "\"maximum_size\":")?;
+            write_optional(f, decl.maximum_size())?;
+            write!(f, ",\"import\":")?;
+            write_import(f, res.table_import.get(table))?;
+            write!(f, ",\"export\":")?;
+            write_export(f, res.table_export.get(table))?;
+            write!(f, "}}")?;
+        }
+        write!(f, "],\"globals\":[")?;
+        for (n, global) in self.module.globals().enumerate() {
+            if n > 0 {
+                write!(f, ",")?;
+            }
+            let decl = &res.global_decls[global];
+            let import = match &res.global_inits[global] {
+                GlobalInit::Import(name) => Some(name),
+                GlobalInit::Define(_) => None,
+            };
+            write!(f, "{{\"index\":{},", raw(global))?;
+            write!(f, "\"type\":\"{}\",", decl.ty())?;
+            write!(f, "\"mutable\":{},", decl.is_mutable())?;
+            write!(f, "\"import\":")?;
+            write_import(f, import)?;
+            write!(f, ",\"export\":")?;
+            write_export(f, res.global_export.get(global))?;
+            write!(f, "}}")?;
+        }
+        write!(f, "],\"imports\":[")?;
+        self.write_imports(f)?;
+        write!(f, "],\"start\":")?;
+        write_optional(f, res.start_func.map(raw))?;
+        write!(f, "}}")
+    }
+}
+
+impl ModuleMetadata<'_> {
+    /// Writes the signature, call edges and used entities of the function.
+    fn write_function(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        func: Func,
+    ) -> fmt::Result {
+        let res = &self.module.res;
+        let func_type = res.function_decls[func];
+        let signature = &res.types[func_type];
+        let is_interpreter_only = self.module.is_interpreter_only(func);
+        let uses = match self.module.bodies.get(func) {
+            Some(body) if !is_interpreter_only => FunctionUses::new(body),
+            _ => FunctionUses::default(),
+        };
+        write!(f, "{{\"index\":{},", raw(func))?;
+        write!(f, "\"type\":{},", raw(func_type))?;
+        write!(f, "\"inputs\":")?;
+        write_types(f, signature.inputs())?;
+        write!(f, ",\"outputs\":")?;
+        write_types(f, signature.outputs())?;
+        write!(f, ",\"import\":")?;
+        write_import(f, res.function_import.get(func))?;
+        write!(f, ",\"export\":")?;
+        write_export(f, res.function_export.get(func))?;
+        write!(f, ",\"interpreter_only\":{},", is_interpreter_only)?;
+        write!(f, "\"calls\":")?;
+        write_indices(f, uses.calls.iter().copied())?;
+        write!(f, ",\"indirect_calls\":[")?;
+        for (n, (table, func_type)) in uses.indirect_calls.iter().enumerate() {
+            if n > 0 {
+                write!(f, ",")?;
+            }
+            write!(
+                f,
+                "{{\"table\":{},\"type\":{}}}",
+                raw(*table),
+                raw(*func_type)
+            )?;
+        }
+        write!(f, "],\"memories\":")?;
+        write_indices(f, uses.memories.iter().copied())?;
+        write!(f, ",\"tables\":")?;
+        write_indices(f, uses.tables().into_iter())?;
+        write!(f, ",\"globals_read\":")?;
+        write_indices(f, uses.globals_read.iter().copied())?;
+        write!(f, ",\"globals_written\":")?;
+        write_indices(f, uses.globals_written.iter().copied())?;
+        write!(f, "}}")
+    }
+
+    /// Writes the imports required to instantiate the module.
+    fn write_imports(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let res = &self.module.res;
+        let functions = res
+            .function_import
+            .iter()
+            .map(|(func, name)| ("function", raw(func), name));
+        let memories = res
+            .memory_import
+            .iter()
+            .map(|(memory, name)| ("memory", raw(memory), name));
+        let tables = res
+            .table_import
+            .iter()
+            .map(|(table, name)| ("table", raw(table), name));
+        let globals =
+            res.global_inits
+                .iter()
+                .filter_map(|(global, init)| match init {
+                    GlobalInit::Import(name) => {
+                        Some(("global", raw(global), name))
+                    }
+                    GlobalInit::Define(_) => None,
+                });
+        let imports = functions.chain(memories).chain(tables).chain(globals);
+        for (n, (kind, index, name)) in imports.enumerate() {
+            if n > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{{\"kind\":\"{}\",\"index\":{},", kind, index)?;
+            write!(f, "\"module\":")?;
+            write_str(f, name.module_name())?;
+            write!(f, ",\"field\":")?;
+            write_str(f, name.field_name())?;
+            write!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the raw index of the entity.
+fn raw<T>(idx: Idx<T>) -> u32 {
+    idx.into_raw().into_u32()
+}
+
+/// Writes the string as JSON string literal.
+fn write_str(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Writes the value or `null` if there is none.
+fn write_optional<T>(
+    f: &mut fmt::Formatter<'_>,
+    value: Option<T>,
+) -> fmt::Result
+where
+    T: fmt::Display,
+{
+    match value {
+        Some(value) => write!(f, "{}", value),
+        None => write!(f, "null"),
+    }
+}
+
+/// Writes the module and field name of the import or `null` if there is none.
+fn write_import(
+    f: &mut fmt::Formatter<'_>,
+    name: Option<&ImportName>,
+) -> fmt::Result {
+    match name {
+        Some(name) => {
+            write!(f, "{{\"module\":")?;
+            write_str(f, name.module_name())?;
+            write!(f, ",\"field\":")?;
+            write_str(f, name.field_name())?;
+            write!(f, "}}")
+        }
+        None => write!(f, "null"),
+    }
+}
+
+/// Writes the export name or `null` if there is none.
+fn write_export(
+    f: &mut fmt::Formatter<'_>,
+    name: Option<&String>,
+) -> fmt::Result {
+    match name {
+        Some(name) => write_str(f, name),
+        None => write!(f, "null"),
+    }
+}
+
+/// Writes the types as JSON array of strings.
+fn write_types(f: &mut fmt::Formatter<'_>, types: &[Type]) -> fmt::Result {
+    write!(f, "[")?;
+    for (n, ty) in types.iter().enumerate() {
+        if n > 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "\"{}\"", ty)?;
+    }
+    write!(f, "]")
+}
+
+/// Writes the raw indices of the entities as JSON array.
+fn write_indices<T, I>(f: &mut fmt::Formatter<'_>, indices: I) -> fmt::Result
+where
+    I: Iterator<Item = Idx<T>>,
+{
+    write!(f, "[")?;
+    for (n, idx) in indices.enumerate() {
+        if n > 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "{}", raw(idx))?;
+    }
+    write!(f, "]")
+}
@@ -14,13 +14,13 @@
 
 mod builder;
 mod indent;
+mod metadata;
+mod policy;
 mod res;
 
 pub(crate) use self::indent::Indent;
 pub use self::{
     builder::{
-        ExportKind,
-        ExportName,
         GlobalInit,
         ModuleBuilder,
         ModuleExportsBuilder,
@@ -30,24 +30,63 @@ pub use self::{
         ModuleImportsBuilder,
         ModuleMemoriesBuilder,
         ModuleMemoryDataBuilder,
+        ModuleReadOnlyDataBuilder,
         ModuleTableElementsBuilder,
         ModuleTablesBuilder,
         ModuleTypesBuilder,
     },
+    policy::{ModulePolicy, PolicyViolation, StartFuncPolicy},
     res::ModuleResources,
 };
-use crate::{Function, FunctionBody};
+use crate::{
+    primitive::{
+        FunctionType,
+        Global,
+        GlobalVariable,
+        ImportName,
+        LinearMemoryDecl,
+        Producers,
+        TableDecl,
+        TableInit,
+    },
+    CheckedArithmetic,
+    Error,
+    Function,
+    FunctionBody,
+    FunctionEditorError,
+    ImportedFunction,
+    InstructionStats,
+    InstrumentationHooks,
+    ModuleFunction,
+    ShadowStack,
+    VerifierViolation,
+};
 use core::fmt;
-use entity::ComponentVec;
-use ir::primitive::Func;
+use entity::{ComponentMap, ComponentVec};
+use ir::primitive::{Data, Func, FuncType, IntType, Mem, Table, Type};
 
 /// A constructed and validated Runwell module.
+///
+/// # Thread Safety
+///
+/// A `Module` is immutable after construction and is `Send` and `Sync`.
+/// Therefore a single module can be shared, e.g. via `Arc<Module>`, between
+/// many threads that evaluate its functions concurrently.
+/// All mutable execution state is owned by the respective evaluation context.
 #[derive(Debug)]
 pub struct Module {
     /// The internal resources of the constructed module.
     pub(crate) res: ModuleResources,
     /// The bodies (implementations) of the internal functions.
     pub(crate) bodies: ComponentVec<Func, FunctionBody>,
+    /// The original source bodies of interpreter-only internal functions.
+    ///
+    /// # Note
+    ///
+    /// The Runwell IR bodies of these functions are mere placeholders.
+    pub(crate) interpreter_only: ComponentMap<Func, Box<[u8]>>,
+    /// The names of the transformations applied to the module in order.
+    pub(crate) passes: Vec<&'static str>,
 }
 
 impl Module {
@@ -57,24 +96,406 @@ impl Module {
     }
 
     /// Returns the function signature and body for the given function index if any.
+    ///
+    /// Returns `None` for imported functions since they have no body.
     pub fn get_function(&self, func: Func) -> Option<Function> {
-        self.res.get_func_type(func).map(|func_type| {
-            let func_body = &self.bodies[func];
-            Function::new(func, func_type, func_body)
-        })
+        let func_type = self.res.get_func_type(func)?;
+        let func_body = self.bodies.get(func)?;
+        Some(Function::new(func, func_type, func_body))
+    }
+
+    /// Returns an iterator over all internal functions in ascending order.
+    pub fn internal_functions(&self) -> impl Iterator<Item = Function> + '_ {
+        self.bodies
+            .iter()
+            .filter_map(move |(func, _)| self.get_function(func))
+    }
+
+    /// Returns the imported or internal function for the given function index if any.
+    pub fn get_module_function(&self, func: Func) -> Option<ModuleFunction> {
+        let func_type = self.res.get_func_type(func)?;
+        match self.res.function_import.get(func) {
+            Some(import_name) => {
+                let function =
+                    ImportedFunction::new(func, func_type, import_name);
+                Some(ModuleFunction::Imported(function))
+            }
+            None => self.get_function(func).map(ModuleFunction::Internal),
+        }
+    }
+
+    /// Returns an iterator over all imported and internal functions in ascending order.
+    ///
+    /// The yielded functions carry the same indices that are used to refer
+    /// to them, e.g. by call instructions.
+    pub fn functions(&self) -> impl Iterator<Item = ModuleFunction> + '_ {
+        self.res
+            .function_entities
+            .indices()
+            .filter_map(move |func| self.get_module_function(func))
+    }
+
+    /// Returns the import name of the function if it is imported.
+    pub fn get_function_import(&self, func: Func) -> Option<&ImportName> {
+        self.res.function_import.get(func)
+    }
+
+    /// Returns the function type at the given type index if any.
+    pub fn get_type(&self, func_type: FuncType) -> Option<&FunctionType> {
+        self.res.get_type(func_type)
+    }
+
+    /// Returns the function type index of the function if any.
+    pub fn get_raw_func_type(&self, func: Func) -> Option<FuncType> {
+        self.res.get_raw_func_type(func)
+    }
+
+    /// Returns `true` if both function types exist and are structurally identical.
+    pub fn func_types_match(&self, lhs: FuncType, rhs: FuncType) -> bool {
+        self.res.func_types_match(lhs, rhs)
+    }
+
+    /// Returns the function that is exported under the given name if any.
+    pub fn get_exported_function(&self, name: &str) -> Option<Func> {
+        self.res
+            .function_export
+            .iter()
+            .find(|(_, export_name)| export_name.as_str() == name)
+            .map(|(func, _)| func)
+    }
+
+    /// Returns an iterator over all imported and internal global variables.
+    pub fn globals(&self) -> impl Iterator<Item = Global> + '_ {
+        self.res.global_entities.indices()
+    }
+
+    /// Returns the declaration and initializer of the global variable if any.
+    pub fn get_global(
+        &self,
+        global: Global,
+    ) -> Option<(&GlobalVariable, &GlobalInit)> {
+        let decl = self.res.global_decls.get(global)?;
+        let init = self.res.global_inits.get(global)?;
+        Some((decl, init))
+    }
+
+    /// Returns the global variable that is imported under the given name if any.
+    pub fn get_imported_global(&self, name: &ImportName) -> Option<Global> {
+        self.res
+            .global_inits
+            .iter()
+            .find(|(_, init)| match init {
+                GlobalInit::Import(import_name) => import_name == name,
+                GlobalInit::Define(_) => false,
+            })
+            .map(|(global, _)| global)
+    }
+
+    /// Returns the global variable that is exported under the given name if any.
+    pub fn get_exported_global(&self, name: &str) -> Option<Global> {
+        self.res
+            .global_export
+            .iter()
+            .find(|(_, export_name)| export_name.as_str() == name)
+            .map(|(global, _)| global)
+    }
+
+    /// Returns an iterator over all imported and internal linear memories.
+    pub fn memories(&self) -> impl Iterator<Item = Mem> + '_ {
+        self.res.memory_entities.indices()
+    }
+
+    /// Returns the declaration of the linear memory if any.
+    pub fn get_memory(&self, memory: Mem) -> Option<&LinearMemoryDecl> {
+        self.res.memory_decls.get(memory)
+    }
+
+    /// Returns the import name of the linear memory if it is imported.
+    pub fn get_memory_import(&self, memory: Mem) -> Option<&ImportName> {
+        self.res.memory_import.get(memory)
+    }
+
+    /// Returns an iterator over all imported and internal tables.
+    pub fn tables(&self) -> impl Iterator<Item = Table> + '_ {
+        self.res.table_entities.indices()
+    }
+
+    /// Returns the declaration of the table if any.
+    pub fn get_table(&self, table: Table) -> Option<&TableDecl> {
+        self.res.table_decls.get(table)
+    }
+
+    /// Returns the import name of the table if it is imported.
+    pub fn get_table_import(&self, table: Table) -> Option<&ImportName> {
+        self.res.table_import.get(table)
+    }
+
+    /// Returns the element segments that initialize the table if any.
+    pub fn get_table_init(&self, table: Table) -> Option<&TableInit> {
+        if !self.res.table_entities.contains_key(table) {
+            return None
+        }
+        Some(&self.res.table_inits[table])
+    }
+
+    /// Returns an iterator over all read-only data objects.
+    pub fn read_only_data(&self) -> impl Iterator<Item = Data> + '_ {
+        self.res.data_entities.indices()
+    }
+
+    /// Returns the contents of the read-only data object if any.
+    pub fn get_read_only_data(&self, data: Data) -> Option<&[u8]> {
+        self.res.get_read_only_data(data)
+    }
+
+    /// Returns `true` if the internal function is interpreter-only.
+    ///
+    /// The Runwell IR body of an interpreter-only function is a placeholder
+    /// and its original source body has to be executed instead.
+    pub fn is_interpreter_only(&self, func: Func) -> bool {
+        self.interpreter_only.contains_key(func)
+    }
+
+    /// Returns `true` if the function is an internal function with a pure body.
+    ///
+    /// Imported and interpreter-only functions are never considered pure.
+    ///
+    /// See [`FunctionBody::is_pure`] for the definition of pure function bodies.
+    pub fn is_pure(&self, func: Func) -> bool {
+        !self.is_interpreter_only(func)
+            && self.bodies.get(func).is_some_and(FunctionBody::is_pure)
+    }
+
+    /// Returns the original source body of the interpreter-only function if any.
+    pub fn get_interpreter_only_source(&self, func: Func) -> Option<&[u8]> {
+        self.interpreter_only.get(func).map(|source| &source[..])
+    }
+
+    /// Returns the toolchain metadata of the module if any.
+    pub fn producers(&self) -> Option<&Producers> {
+        self.res.producers.as_ref()
+    }
+
+    /// Returns the names of the transformations applied to the module
+    /// after its construction in the order of their application.
+    pub fn passes(&self) -> &[&'static str] {
+        &self.passes
+    }
+
+    /// Returns statistics about the instructions of all internal functions.
+    pub fn stats(&self) -> ModuleStats {
+        let mut stats = ModuleStats::default();
+        for (func, body) in &self.bodies {
+            let func_stats = body.stats();
+            stats.total.merge(&func_stats);
+            stats.funcs.push((func, func_stats));
+        }
+        stats
+    }
+
+    /// Verifies the invariants of all internal function bodies.
+    ///
+    /// # Note
+    ///
+    /// The bodies of interpreter-only functions are mere placeholders and
+    /// therefore not verified.
+    ///
+    /// # Errors
+    ///
+    /// Returns all encountered violations together with their function.
+    pub fn verify(&self) -> Result<(), Vec<(Func, VerifierViolation)>> {
+        let mut violations = Vec::new();
+        for (func, body) in &self.bodies {
+            if self.interpreter_only.contains_key(func) {
+                continue
+            }
+            if let Err(found) = body.verify(func, &self.res) {
+                violations.extend(
+                    found.into_iter().map(|violation| (func, violation)),
+                );
+            }
+        }
+        if !violations.is_empty() {
+            return Err(violations)
+        }
+        Ok(())
+    }
+
+    /// Instruments all internal functions to call the hooks upon entry and exit.
+    ///
+    /// Returns the number of inserted hook calls.
+    ///
+    /// # Note
+    ///
+    /// The hooks themselves as well as interpreter-only functions are not
+    /// instrumented.
+    ///
+    /// # Errors
+    ///
+    /// If a hook does not exist or does not match the function type that
+    /// is documented for it in [`InstrumentationHooks`].
+    pub fn instrument(
+        &mut self,
+        hooks: &InstrumentationHooks,
+    ) -> Result<usize, Error> {
+        let func_index = [Type::from(IntType::I32)];
+        let memory_access = [Type::from(IntType::I32); 3];
+        let expected_inputs = hooks
+            .entry_hook()
+            .into_iter()
+            .chain(hooks.exit_hook())
+            .map(|hook| (hook, &func_index[..]))
+            .chain(
+                hooks
+                    .memory_access_hook()
+                    .map(|hook| (hook, &memory_access[..])),
+            );
+        for (hook, inputs) in expected_inputs {
+            let is_valid = self.res.get_func_type(hook).is_some_and(|ty| {
+                ty.inputs() == inputs && ty.outputs().is_empty()
+            });
+            if !is_valid {
+                return Err(FunctionEditorError::InvalidHook { hook }.into())
+            }
+        }
+        let mut len_inserted = 0;
+        for (func, body) in &mut self.bodies {
+            if self.interpreter_only.contains_key(func)
+                || hooks.hooks().any(|hook| hook == func)
+            {
+                continue
+            }
+            len_inserted += body.instrument(func, hooks);
+        }
+        self.passes.push("instrument");
+        Ok(len_inserted)
+    }
+
+    /// Checks the configured integer arithmetic of all internal functions
+    /// for overflow so that it traps upon overflow.
+    ///
+    /// Returns the number of checked instructions.
+    ///
+    /// # Errors
+    ///
+    /// If the module has interpreter-only functions since their arithmetic
+    /// cannot be checked. In this case no function is transformed.
+    pub fn check_arithmetic(
+        &mut self,
+        checks: &CheckedArithmetic,
+    ) -> Result<usize, Error> {
+        if let Some((func, _)) = self.interpreter_only.iter().next() {
+            return Err(
+                FunctionEditorError::InterpreterOnlyFunction { func }.into()
+            )
+        }
+        let mut len_checked = 0;
+        for body in self.bodies.components_mut() {
+            len_checked += body.check_arithmetic(checks)?;
+        }
+        self.passes.push("check-arithmetic");
+        Ok(len_checked)
+    }
+
+    /// Checks all writes to the shadow stack pointer of all internal
+    /// functions so that they trap upon overflow of the shadow stack.
+    ///
+    /// Returns the number of checked writes.
+    ///
+    /// # Errors
+    ///
+    /// - If the stack pointer is not a mutable `i32` global variable.
+    /// - If the module has interpreter-only functions since their writes
+    ///   cannot be checked. In this case no function is transformed.
+    pub fn check_shadow_stack(
+        &mut self,
+        stack: &ShadowStack,
+    ) -> Result<usize, Error> {
+        let global = stack.stack_pointer();
+        let is_valid = self.res.get_global_decl(global).is_some_and(|decl| {
+            decl.is_mutable() && decl.ty() == Type::from(IntType::I32)
+        });
+        if !is_valid {
+            return Err(
+                FunctionEditorError::InvalidStackPointer { global }.into()
+            )
+        }
+        if let Some((func, _)) = self.interpreter_only.iter().next() {
+            return Err(
+                FunctionEditorError::InterpreterOnlyFunction { func }.into()
+            )
+        }
+        let mut len_checked = 0;
+        for body in self.bodies.components_mut() {
+            len_checked += body.check_shadow_stack(stack)?;
+        }
+        self.passes.push("check-shadow-stack");
+        Ok(len_checked)
+    }
+}
+
+/// Statistics about the instructions of all internal functions of a module.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ModuleStats {
+    /// The statistics for every internal function.
+    funcs: Vec<(Func, InstructionStats)>,
+    /// The accumulated statistics of all internal functions.
+    total: InstructionStats,
+}
+
+impl ModuleStats {
+    /// Returns the statistics of the function if it is an internal function.
+    pub fn get(&self, func: Func) -> Option<&InstructionStats> {
+        self.funcs
+            .iter()
+            .find(|(f, _)| *f == func)
+            .map(|(_, stats)| stats)
+    }
+
+    /// Returns an iterator over the statistics of all internal functions.
+    pub fn funcs(
+        &self,
+    ) -> impl Iterator<Item = (Func, &InstructionStats)> + '_ {
+        self.funcs.iter().map(|(func, stats)| (*func, stats))
+    }
+
+    /// Returns the accumulated statistics of all internal functions.
+    pub fn total(&self) -> &InstructionStats {
+        &self.total
     }
 }
 
 impl fmt::Display for Module {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "mod {{")?;
-        for func in self.res.function_entities.indices() {
-            let function = self
-                .get_function(func)
-                .expect("encountered missing function");
-            function.display_with_indent(f, Indent::single())?;
+        for (data, bytes) in &self.res.read_only_data {
+            write!(f, "{}{} = \"", Indent::single(), data)?;
+            for byte in bytes.iter().copied() {
+                write!(f, "{}", core::ascii::escape_default(byte))?;
+            }
+            writeln!(f, "\"")?;
+        }
+        for function in self.internal_functions() {
+            function.display_with_indent(
+                f,
+                Indent::single(),
+                Default::default(),
+            )?;
         }
         writeln!(f, "}}")?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Module>();
+        assert_send_sync::<ModuleResources>();
+        assert_send_sync::<FunctionBody>();
+    }
+}
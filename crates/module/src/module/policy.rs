@@ -0,0 +1,263 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Configurable deployment policies for constructed modules.
+//!
+//! Embedders commonly want to reject modules before deployment that do not
+//! match their expected ABI, e.g. modules that are missing an exported
+//! `call` function with a specific signature or modules that declare
+//! overly large linear memories.
+
+use super::Module;
+use crate::{primitive::FunctionType, ResourceLimits};
+use derive_more::Display;
+use ir::primitive::{Func, Mem, Table};
+
+/// The policy regarding the start function of a module.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum StartFuncPolicy {
+    /// The module may or may not have a start function.
+    #[default]
+    Allow,
+    /// The module is required to have a start function.
+    Require,
+    /// The module must not have a start function.
+    Forbid,
+}
+
+/// A single violation of a module policy.
+#[derive(Debug, Display, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    #[display(fmt = "module is required to have a start function")]
+    MissingStartFunc,
+    #[display(fmt = "module must not have a start function but has {}", func)]
+    ForbiddenStartFunc { func: Func },
+    #[display(fmt = "module is missing the exported function {:?}", name)]
+    MissingExport { name: String },
+    #[display(
+        fmt = "exported function {:?} has signature {} but {} is expected",
+        name,
+        found,
+        expected
+    )]
+    UnmatchingExportSignature {
+        name: String,
+        expected: FunctionType,
+        found: FunctionType,
+    },
+    #[display(fmt = "module must not export the function {:?}", name)]
+    ForbiddenExport { name: String },
+    #[display(
+        fmt = "linear memory {} declares {} pages exceeding the limit of {} pages",
+        memory,
+        pages,
+        limit
+    )]
+    MemoryLimitExceeded { memory: Mem, pages: u32, limit: u32 },
+    #[display(
+        fmt = "table {} declares {} elements exceeding the limit of {} elements",
+        table,
+        size,
+        limit
+    )]
+    TableLimitExceeded { table: Table, size: u32, limit: u32 },
+}
+
+/// A configurable policy that modules can be checked against.
+///
+/// The default policy accepts all modules within the limits of Wasm.
+#[derive(Debug, Default, Clone)]
+pub struct ModulePolicy {
+    /// The policy regarding the start function.
+    start_func: StartFuncPolicy,
+    /// The required exported functions and their expected signatures.
+    required_exports: Vec<(String, FunctionType)>,
+    /// If `true` only the required exported functions may be exported.
+    forbid_other_exports: bool,
+    /// The limits on the sizes of linear memories and tables.
+    limits: ResourceLimits,
+}
+
+impl ModulePolicy {
+    /// Sets the policy regarding the start function.
+    pub fn set_start_func(&mut self, policy: StartFuncPolicy) {
+        self.start_func = policy;
+    }
+
+    /// Requires the module to export a function with the given name and signature.
+    pub fn require_export(&mut self, name: &str, func_type: FunctionType) {
+        self.required_exports.push((name.to_string(), func_type));
+    }
+
+    /// Forbids the module to export functions that are not required.
+    pub fn forbid_other_exports(&mut self) {
+        self.forbid_other_exports = true;
+    }
+
+    /// Sets the maximum number of pages of any linear memory.
+    ///
+    /// # Note
+    ///
+    /// Checks against the maximum pages of a linear memory if declared
+    /// and otherwise against its initial pages.
+    pub fn set_max_memory_pages(&mut self, max_pages: u32) {
+        self.limits.set_max_memory_pages(max_pages);
+    }
+
+    /// Sets the maximum number of elements of any table.
+    ///
+    /// # Note
+    ///
+    /// Checks against the maximum size of a table if declared and
+    /// otherwise against its initial size.
+    pub fn set_max_table_size(&mut self, max_size: u32) {
+        self.limits.set_max_table_size(max_size);
+    }
+
+    /// Sets the limits on the sizes of linear memories and tables.
+    pub fn set_resource_limits(&mut self, limits: ResourceLimits) {
+        self.limits = limits;
+    }
+
+    /// Returns the limits on the sizes of linear memories and tables.
+    ///
+    /// Embedders usually enforce the same limits at runtime by passing them
+    /// to the execution configuration of the interpreter.
+    pub fn resource_limits(&self) -> &ResourceLimits {
+        &self.limits
+    }
+
+    /// Checks the module against the policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns all encountered policy violations.
+    pub fn check(&self, module: &Module) -> Result<(), Vec<PolicyViolation>> {
+        let mut violations = Vec::new();
+        self.check_start_func(module, &mut violations);
+        self.check_exports(module, &mut violations);
+        self.check_limits(module, &mut violations);
+        if !violations.is_empty() {
+            return Err(violations)
+        }
+        Ok(())
+    }
+
+    /// Checks the start function of the module against the policy.
+    fn check_start_func(
+        &self,
+        module: &Module,
+        violations: &mut Vec<PolicyViolation>,
+    ) {
+        match (self.start_func, module.res.start_func) {
+            (StartFuncPolicy::Require, None) => {
+                violations.push(PolicyViolation::MissingStartFunc)
+            }
+            (StartFuncPolicy::Forbid, Some(func)) => {
+                violations.push(PolicyViolation::ForbiddenStartFunc { func })
+            }
+            _ => (),
+        }
+    }
+
+    /// Checks the exported functions of the module against the policy.
+    fn check_exports(
+        &self,
+        module: &Module,
+        violations: &mut Vec<PolicyViolation>,
+    ) {
+        let res = &module.res;
+        for (name, expected) in &self.required_exports {
+            let exported = res
+                .function_export
+                .iter()
+                .find(|(_, export_name)| *export_name == name)
+                .map(|(func, _)| func);
+            match exported {
+                None => {
+                    violations.push(PolicyViolation::MissingExport {
+                        name: name.clone(),
+                    })
+                }
+                Some(func) => {
+                    let found = res
+                        .get_func_type(func)
+                        .expect("encountered exported function without type");
+                    if found != expected {
+                        violations.push(
+                            PolicyViolation::UnmatchingExportSignature {
+                                name: name.clone(),
+                                expected: expected.clone(),
+                                found: found.clone(),
+                            },
+                        )
+                    }
+                }
+            }
+        }
+        if self.forbid_other_exports {
+            let mut forbidden = res
+                .function_export
+                .components()
+                .filter(|export_name| {
+                    !self
+                        .required_exports
+                        .iter()
+                        .any(|(name, _)| name == *export_name)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            // Sort for deterministic reports since exports are stored in a hash map.
+            forbidden.sort();
+            violations.extend(
+                forbidden
+                    .into_iter()
+                    .map(|name| PolicyViolation::ForbiddenExport { name }),
+            );
+        }
+    }
+
+    /// Checks the linear memory and table limits of the module against the policy.
+    fn check_limits(
+        &self,
+        module: &Module,
+        violations: &mut Vec<PolicyViolation>,
+    ) {
+        let res = &module.res;
+        let limit = self.limits.max_memory_pages();
+        for (memory, decl) in &res.memory_decls {
+            let pages =
+                decl.maximum_pages().unwrap_or_else(|| decl.initial_pages());
+            if pages > limit {
+                violations.push(PolicyViolation::MemoryLimitExceeded {
+                    memory,
+                    pages,
+                    limit,
+                })
+            }
+        }
+        let limit = self.limits.max_table_size();
+        for (table, decl) in &res.table_decls {
+            let size =
+                decl.maximum_size().unwrap_or_else(|| decl.initial_size());
+            if size > limit {
+                violations.push(PolicyViolation::TableLimitExceeded {
+                    table,
+                    size,
+                    limit,
+                })
+            }
+        }
+    }
+}
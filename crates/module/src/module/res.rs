@@ -18,6 +18,7 @@ use crate::{
     global_var::{Global, GlobalVariable, GlobalVariableEntity},
     linear_memory::{LinearMemoryDecl, LinearMemoryInit},
     primitive::ImportName,
+    producers::Producers,
     table::{TableDecl, TableInit},
 };
 use entity::{
@@ -27,15 +28,18 @@ use entity::{
     PhantomEntityArena,
 };
 use ir::primitive::{
+    Data,
     Func,
     FuncType,
     FuncTypeEntity,
     FunctionEntity,
     LinearMemoryEntity,
     Mem,
+    ReadOnlyDataEntity,
     Table,
     TableEntity,
 };
+use std::collections::HashMap;
 
 /// Module builder resource to incrementally build up a Runwell module.
 #[derive(Debug, Default)]
@@ -52,15 +56,29 @@ pub struct ModuleResources {
     pub(super) memory_entities: PhantomEntityArena<LinearMemoryEntity>,
     /// Table entities.
     pub(super) table_entities: PhantomEntityArena<TableEntity>,
+    /// Read-only data entities.
+    pub(super) data_entities: PhantomEntityArena<ReadOnlyDataEntity>,
 
     /// Registered function types.
     pub(super) types: DefaultComponentVec<FuncType, FunctionType>,
+    /// The canonical function type of every registered function type.
+    ///
+    /// # Note
+    ///
+    /// Structurally identical function types share the same canonical
+    /// function type which is the first registered of them. Therefore
+    /// function types can be compared by comparing their canonical indices.
+    pub(super) canonical_types: ComponentVec<FuncType, FuncType>,
+    /// Maps function types to their canonical function type.
+    pub(super) interned_types:
+        HashMap<FunctionType, FuncType, ahash::RandomState>,
     /// Function declarations.
     ///
     /// # Note
     ///
     /// In their basic form they just declare the function type or signature.
     /// Note that function bodies (implementations) are stored elsewhere.
+    /// The declared function types are always canonical.
     pub(super) function_decls: ComponentVec<Func, FuncType>,
     /// Stores the import name in case the function is imported.
     ///
@@ -126,6 +144,10 @@ pub struct ModuleResources {
     ///
     /// A table cannot be imported and exported at the same time.
     pub(super) table_export: ComponentMap<Table, String>,
+    /// The contents of all read-only data objects.
+    pub(super) read_only_data: ComponentVec<Data, Box<[u8]>>,
+    /// The toolchain metadata of the module.
+    pub(super) producers: Option<Producers>,
 }
 
 impl ModuleResources {
@@ -143,6 +165,38 @@ impl ModuleResources {
         Ok(())
     }
 
+    /// Returns the canonical function type of the given function type.
+    ///
+    /// # Errors
+    ///
+    /// If the function type does not exist in the module resources.
+    pub(super) fn canonicalize_func_type(
+        &self,
+        func_type: FuncType,
+    ) -> Result<FuncType, String> {
+        self.ensure_func_type_exists(func_type)?;
+        Ok(self.canonical_types[func_type])
+    }
+
+    /// Returns the canonical function type of the given function type if it exists.
+    ///
+    /// Structurally identical function types share the same canonical function type.
+    pub fn get_canonical_type(&self, func_type: FuncType) -> Option<FuncType> {
+        self.canonical_types.get(func_type).copied()
+    }
+
+    /// Returns `true` if both function types exist and are structurally identical.
+    ///
+    /// # Note
+    ///
+    /// This only compares the canonical indices of the function types.
+    pub fn func_types_match(&self, lhs: FuncType, rhs: FuncType) -> bool {
+        match (self.get_canonical_type(lhs), self.get_canonical_type(rhs)) {
+            (Some(lhs), Some(rhs)) => lhs == rhs,
+            _ => false,
+        }
+    }
+
     /// Returns the function type at the given index if any.
     pub fn get_type(&self, func_type: FuncType) -> Option<&FunctionType> {
         if !self.type_entities.contains_key(func_type) {
@@ -158,16 +212,33 @@ impl ModuleResources {
             .map(|&func_type| &self.types[func_type])
     }
 
-    /// Returns the function type of the function if the function exists in the module.
+    /// Returns the canonical function type of the function if the function exists in the module.
     pub fn get_raw_func_type(&self, func: Func) -> Option<FuncType> {
         self.function_decls.get(func).copied()
     }
 
+    /// Returns the contents of the read-only data object if it exists.
+    pub fn get_read_only_data(&self, data: Data) -> Option<&[u8]> {
+        self.read_only_data.get(data).map(|bytes| &bytes[..])
+    }
+
+    /// Returns the declaration of the global variable if it exists.
+    pub fn get_global_decl(&self, global: Global) -> Option<&GlobalVariable> {
+        self.global_decls.get(global)
+    }
+
+    /// Returns the declaration of the linear memory if it exists.
+    pub fn get_memory_decl(&self, mem: Mem) -> Option<&LinearMemoryDecl> {
+        self.memory_decls.get(mem)
+    }
+
     /// Shrinks all data structures to fit their minimum space needed.
     ///
     /// This may costly reallocate some data structures.
     pub(super) fn shrink_to_fit(&mut self) {
         self.types.shrink_to_fit();
+        self.canonical_types.shrink_to_fit();
+        self.interned_types.shrink_to_fit();
         self.function_decls.shrink_to_fit();
         self.function_import.shrink_to_fit();
         self.function_export.shrink_to_fit();
@@ -182,5 +253,6 @@ impl ModuleResources {
         self.table_inits.shrink_to_fit();
         self.table_import.shrink_to_fit();
         self.table_export.shrink_to_fit();
+        self.read_only_data.shrink_to_fit();
     }
 }
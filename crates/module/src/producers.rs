@@ -0,0 +1,206 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Toolchain metadata of a module as stored in the Wasm `producers` custom section.
+//!
+//! See the [tool conventions] for the definition of the section.
+//!
+//! [tool conventions]: https://github.com/WebAssembly/tool-conventions/blob/master/ProducersSection.md
+
+/// The name of the `producers` field listing the source languages.
+pub const LANGUAGE_FIELD: &str = "language";
+/// The name of the `producers` field listing the tools that processed the module.
+pub const PROCESSED_BY_FIELD: &str = "processed-by";
+/// The name of the `producers` field listing the used SDKs.
+pub const SDK_FIELD: &str = "sdk";
+
+/// The toolchain metadata of a module.
+///
+/// Stores all fields in the order in which they have been pushed so that
+/// the metadata is preserved exactly upon re-encoding.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Producers {
+    fields: Vec<ProducersField>,
+}
+
+/// A single field of the producers metadata, e.g. `language`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducersField {
+    name: String,
+    values: Vec<ProducersValue>,
+}
+
+/// A named and versioned producer of a producers field, e.g. `rustc 1.50.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducersValue {
+    name: String,
+    version: String,
+}
+
+impl ProducersField {
+    /// Returns the name of the field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the producers of the field.
+    pub fn values(&self) -> &[ProducersValue] {
+        &self.values
+    }
+}
+
+impl ProducersValue {
+    /// Returns the name of the producer.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the version of the producer.
+    ///
+    /// # Note
+    ///
+    /// The version might be empty.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+}
+
+impl Producers {
+    /// Adds a producer with its version to the field with the given name.
+    ///
+    /// Creates the field if it does not exist, yet.
+    pub fn push(&mut self, field: &str, name: &str, version: &str) {
+        let value = ProducersValue {
+            name: name.to_string(),
+            version: version.to_string(),
+        };
+        match self.fields.iter_mut().find(|f| f.name == field) {
+            Some(field) => field.values.push(value),
+            None => {
+                self.fields.push(ProducersField {
+                    name: field.to_string(),
+                    values: vec![value],
+                })
+            }
+        }
+    }
+
+    /// Returns `true` if there are no producers fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns all fields in the order of their first appearance.
+    pub fn fields(&self) -> &[ProducersField] {
+        &self.fields
+    }
+
+    /// Returns the producers of the field with the given name.
+    ///
+    /// Returns an empty slice if there is no such field.
+    pub fn field(&self, name: &str) -> &[ProducersValue] {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(ProducersField::values)
+            .unwrap_or_default()
+    }
+
+    /// Returns the source languages of the module.
+    pub fn language(&self) -> &[ProducersValue] {
+        self.field(LANGUAGE_FIELD)
+    }
+
+    /// Returns the tools that processed the module.
+    pub fn processed_by(&self) -> &[ProducersValue] {
+        self.field(PROCESSED_BY_FIELD)
+    }
+
+    /// Returns the SDKs that were used to produce the module.
+    pub fn sdk(&self) -> &[ProducersValue] {
+        self.field(SDK_FIELD)
+    }
+
+    /// Encodes the producers as contents of the Wasm `producers` custom section.
+    ///
+    /// # Note
+    ///
+    /// The encoded bytes exclude the custom section header and name.
+    pub fn encode(&self, buffer: &mut Vec<u8>) {
+        encode_u32(self.fields.len() as u32, buffer);
+        for field in &self.fields {
+            encode_str(&field.name, buffer);
+            encode_u32(field.values.len() as u32, buffer);
+            for value in &field.values {
+                encode_str(&value.name, buffer);
+                encode_str(&value.version, buffer);
+            }
+        }
+    }
+}
+
+/// Encodes the `u32` value using unsigned LEB128 encoding.
+fn encode_u32(mut value: u32, buffer: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            break
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+/// Encodes the string as Wasm name with its length prefix.
+fn encode_str(string: &str, buffer: &mut Vec<u8>) {
+    encode_u32(string.len() as u32, buffer);
+    buffer.extend_from_slice(string.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_and_query_works() {
+        let mut producers = Producers::default();
+        assert!(producers.is_empty());
+        producers.push(LANGUAGE_FIELD, "Rust", "");
+        producers.push(PROCESSED_BY_FIELD, "rustc", "1.50.0");
+        producers.push(PROCESSED_BY_FIELD, "wasm-opt", "99");
+        assert!(!producers.is_empty());
+        assert_eq!(producers.fields().len(), 2);
+        assert_eq!(producers.language().len(), 1);
+        assert_eq!(producers.language()[0].name(), "Rust");
+        assert_eq!(producers.language()[0].version(), "");
+        assert_eq!(producers.processed_by().len(), 2);
+        assert_eq!(producers.processed_by()[1].name(), "wasm-opt");
+        assert_eq!(producers.processed_by()[1].version(), "99");
+        assert!(producers.sdk().is_empty());
+    }
+
+    #[test]
+    fn encode_works() {
+        let mut producers = Producers::default();
+        producers.push(LANGUAGE_FIELD, "C", "11");
+        let mut buffer = Vec::new();
+        producers.encode(&mut buffer);
+        let mut expected = vec![1, 8];
+        expected.extend_from_slice(b"language");
+        expected.push(1);
+        expected.extend_from_slice(&[1, b'C', 2, b'1', b'1']);
+        assert_eq!(buffer, expected);
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The Runwell WebAssembly virtual machine.
+//!
+//! Re-exports the APIs of all Runwell crates under stable paths so that
+//! downstream users do not have to depend on the individual crates:
+//!
+//! - [`entity`]: Entity component data structures used throughout Runwell.
+//! - [`ir`]: The Runwell IR on the instruction level.
+//! - [`module`]: Modules, function bodies and their builders.
+//! - [`interp`]: The Runwell IR interpreter.
+//! - [`wasm`]: Translation of WebAssembly (Wasm) into Runwell IR.
+
+#![forbid(unsafe_code)]
+
+pub use entity;
+pub use interpreter as interp;
+pub use ir;
+pub use module;
+pub use wasm;
@@ -0,0 +1,54 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use runwell::{
+    entity::RawIdx,
+    interp::EvaluationContext,
+    ir::primitive::Func,
+    module::{builder::ModuleBuilder, Module},
+};
+
+/// Parses the Wasm text format input into a Runwell module.
+fn parse_wat(wat: &str) -> Module {
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut buffer = Vec::new();
+    runwell::wasm::parse(&mut &wasm[..], &mut buffer).unwrap()
+}
+
+#[test]
+fn facade_exposes_all_apis() {
+    let module = parse_wat(
+        r#"
+        (module
+            (func (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add
+            )
+        )
+        "#,
+    );
+    let func = Func::from_raw(RawIdx::from_u32(0));
+    let function = module.get_function(func).unwrap();
+    assert_eq!(function.inputs().len(), 2);
+    let mut ctx = EvaluationContext::new(&module);
+    let mut results = Vec::new();
+    ctx.evaluate_function(func, [1, 2].iter().copied(), |result| {
+        results.push(result)
+    })
+    .unwrap();
+    assert_eq!(results, vec![3]);
+    // The builder types are available under the module path as well.
+    let _builder: ModuleBuilder = Module::build();
+}
@@ -0,0 +1,127 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Budgets to limit the resources spent translating a single function.
+
+use derive_more::{Display, Error};
+
+/// Per-function limits for the translation of Wasm function bodies.
+///
+/// Functions exceeding any of the limits are not translated into Runwell IR
+/// but registered as interpreter-only functions instead so that pathological
+/// functions do not stall the translation of the whole module.
+///
+/// All limits are measured in deterministic units instead of wall-clock
+/// time so that the same functions bail out on every machine and the
+/// compiled module stays reproducible.
+///
+/// By default no limits are imposed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TranslationBudget {
+    /// The maximum number of local variable declarations.
+    max_locals: Option<u32>,
+    /// The maximum number of Wasm operators.
+    max_operators: Option<u32>,
+    /// The maximum number of constructed Runwell instructions.
+    max_instructions: Option<usize>,
+    /// The maximum number of translation steps.
+    max_steps: Option<u64>,
+}
+
+/// The reason why a function exceeded its translation budget.
+#[derive(Debug, Display, Error, Copy, Clone, PartialEq, Eq)]
+pub enum BudgetExceeded {
+    #[display(fmt = "function declares more than {} local variables", limit)]
+    Locals { limit: u32 },
+    #[display(fmt = "function has more than {} operators", limit)]
+    Operators { limit: u32 },
+    #[display(fmt = "function translates to more than {} instructions", limit)]
+    Instructions { limit: usize },
+    #[display(fmt = "function translation took more than {} steps", limit)]
+    Steps { limit: u64 },
+}
+
+impl TranslationBudget {
+    /// Sets the maximum number of local variable declarations of a function.
+    pub fn set_max_locals(&mut self, max_locals: u32) {
+        self.max_locals = Some(max_locals);
+    }
+
+    /// Sets the maximum number of Wasm operators of a function.
+    pub fn set_max_operators(&mut self, max_operators: u32) {
+        self.max_operators = Some(max_operators);
+    }
+
+    /// Sets the maximum number of Runwell instructions a function may translate to.
+    pub fn set_max_instructions(&mut self, max_instructions: usize) {
+        self.max_instructions = Some(max_instructions);
+    }
+
+    /// Sets the maximum number of steps the translation of a function may take.
+    ///
+    /// Every translated Wasm operator, every constructed Runwell instruction
+    /// and every created SSA value counts as one step. This bounds the time
+    /// spent translating a function, including the time spent resolving
+    /// variables into SSA form, independent of the machine.
+    pub fn set_max_steps(&mut self, max_steps: u64) {
+        self.max_steps = Some(max_steps);
+    }
+
+    /// Checks the number of local variable declarations against the budget.
+    pub(crate) fn check_locals(
+        &self,
+        count_locals: u32,
+    ) -> Result<(), BudgetExceeded> {
+        match self.max_locals {
+            Some(limit) if count_locals > limit => {
+                Err(BudgetExceeded::Locals { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the progress of the translation against the budget.
+    ///
+    /// # Note
+    ///
+    /// - `count_operators` is the number of translated Wasm operators.
+    /// - `count_instrs` is the number of constructed Runwell instructions.
+    /// - `count_values` is the number of created SSA values.
+    pub(crate) fn check_progress(
+        &self,
+        count_operators: u32,
+        count_instrs: usize,
+        count_values: usize,
+    ) -> Result<(), BudgetExceeded> {
+        if let Some(limit) = self.max_operators {
+            if count_operators > limit {
+                return Err(BudgetExceeded::Operators { limit })
+            }
+        }
+        if let Some(limit) = self.max_instructions {
+            if count_instrs > limit {
+                return Err(BudgetExceeded::Instructions { limit })
+            }
+        }
+        if let Some(limit) = self.max_steps {
+            let steps = u64::from(count_operators)
+                + count_instrs as u64
+                + count_values as u64;
+            if steps > limit {
+                return Err(BudgetExceeded::Steps { limit })
+            }
+        }
+        Ok(())
+    }
+}
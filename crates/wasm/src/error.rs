@@ -25,6 +25,7 @@ use super::{
 };
 use core::fmt::Display;
 use derive_more::{Display, Error, From};
+use ir::{Diagnostic, ToDiagnostic};
 
 /// An error that occurred while parsing a Wasm input and building up the module for it.
 #[derive(Debug, Error)]
@@ -34,7 +35,10 @@ pub struct Error {
     /// Additional context to the error.
     context: String,
     /// The kind of the error holding valuable information to the user.
-    kind: ErrorKind,
+    ///
+    /// Boxed since some error kinds are large which would otherwise bloat
+    /// every `Result` returned by the Wasm frontend.
+    kind: Box<ErrorKind>,
 }
 
 impl Display for Error {
@@ -78,6 +82,23 @@ impl Error {
     }
 }
 
+impl ToDiagnostic for Error {
+    /// Returns the diagnostic of the error kind.
+    ///
+    /// The span and context of the error are provided as the `span` and
+    /// `context` parameters if any.
+    fn to_diagnostic(&self) -> Diagnostic {
+        let mut diagnostic = self.kind.to_diagnostic();
+        if let Some(span) = &self.span {
+            diagnostic = diagnostic.with_parameter("span", span);
+        }
+        if !self.context.is_empty() {
+            diagnostic = diagnostic.with_parameter("context", &self.context);
+        }
+        diagnostic
+    }
+}
+
 /// Span denoting the range of bytes in a Wasm binary input that is associated to some error.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Span {
@@ -110,7 +131,7 @@ where
         Self {
             span: None,
             context: String::new(),
-            kind: error.into(),
+            kind: Box::new(error.into()),
         }
     }
 }
@@ -131,3 +152,27 @@ pub enum ErrorKind {
     Translate(TranslateError),
     Wasmparser(wasmparser::BinaryReaderError),
 }
+
+impl ToDiagnostic for ErrorKind {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::Primitive(error) => error.to_diagnostic(),
+            Self::Import(error) => error.to_diagnostic(),
+            Self::Export(error) => error.to_diagnostic(),
+            Self::InitExpr(error) => error.to_diagnostic(),
+            Self::Memory(error) => error.to_diagnostic(),
+            Self::Table(error) => error.to_diagnostic(),
+            Self::Read(error) => error.to_diagnostic(),
+            Self::Section(error) => error.to_diagnostic(),
+            Self::Module { message } => Diagnostic::new("wasm.module", self)
+                .with_parameter("message", message),
+            Self::Ir(error) => error.to_diagnostic(),
+            Self::Translate(error) => error.to_diagnostic(),
+            Self::Wasmparser(error) => {
+                Diagnostic::new("wasm.invalid_binary", self)
+                    .with_parameter("offset", error.offset())
+                    .with_parameter("message", error.message())
+            }
+        }
+    }
+}
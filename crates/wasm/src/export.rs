@@ -15,7 +15,11 @@
 use core::convert::TryFrom;
 use derive_more::{Display, Error};
 use entity::RawIdx;
-use ir::primitive::{Func, Mem, Table};
+use ir::{
+    primitive::{Func, Mem, Table},
+    Diagnostic,
+    ToDiagnostic,
+};
 use module::primitive::Global;
 
 /// An error upon parsing, validating or operating on Wasm exports.
@@ -33,6 +37,17 @@ pub enum UnsupportedExportKind {
     Type,
 }
 
+impl ToDiagnostic for ExportError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::UnsupportedExportKind { kind } => {
+                Diagnostic::new("wasm.unsupported_export_kind", self)
+                    .with_parameter("kind", kind)
+            }
+        }
+    }
+}
+
 /// Supported kinds of exports.
 pub enum ExportKind {
     /// An exported function.
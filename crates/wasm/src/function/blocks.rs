@@ -15,12 +15,12 @@
 #![allow(dead_code)]
 
 use crate::{Error, TranslateError};
-use core::convert::TryFrom;
+use core::{convert::TryFrom, ops::Range};
 use entity::RawIdx;
 use ir::primitive::{Block, FuncType, Type};
-use module::{builder::FunctionBuilder, ModuleResources};
+use module::{builder::FunctionBuilder, primitive::Variable, ModuleResources};
 
-/// A stack of Wasm `Block` and `Loop` definitions to branch/continue to.
+/// A stack of Wasm `Block`, `Loop` and `If` definitions to branch/continue to.
 #[derive(Debug, Default)]
 pub struct Blocks {
     blocks: Vec<WasmBlock>,
@@ -42,7 +42,7 @@ impl Blocks {
         self.blocks.len()
     }
 
-    /// Pushes the Wasm `Block` or `Loop` onto the stack of blocks.
+    /// Pushes the Wasm `Block`, `Loop` or `If` onto the stack of blocks.
     pub fn push_block(&mut self, block: WasmBlock) {
         self.blocks.push(block)
     }
@@ -65,7 +65,7 @@ impl Blocks {
         self.blocks
             .iter()
             .rev()
-            .nth_back(n as usize)
+            .nth(n as usize)
             .copied()
             .ok_or_else(|| {
                 let len = self.blocks.len();
@@ -86,13 +86,12 @@ impl Blocks {
         builder: &mut FunctionBuilder,
     ) -> Result<Block, Error> {
         let len_blocks = self.blocks.len();
-        let wasm_block =
-            self.blocks.iter_mut().rev().nth_back(n as usize).ok_or(
-                TranslateError::RelativeDepthExceedsBlockStack {
-                    n,
-                    len: len_blocks,
-                },
-            )?;
+        let wasm_block = self.blocks.iter_mut().rev().nth(n as usize).ok_or(
+            TranslateError::RelativeDepthExceedsBlockStack {
+                n,
+                len: len_blocks,
+            },
+        )?;
         match wasm_block.block() {
             Some(block) => Ok(block),
             None => {
@@ -120,55 +119,158 @@ impl Blocks {
     }
 }
 
-/// A Wasm `Block` or `Loop` for Wasm operators to branch to.
+/// The kind of a Wasm block.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WasmBlockKind {
+    /// The implicit block of the entire Wasm function body.
+    ///
+    /// Branches to it return from the function.
+    Function,
+    /// A Wasm `Block` whose branches continue after its end.
+    Block,
+    /// A Wasm `Loop` whose branches continue at its header.
+    Loop,
+    /// A Wasm `If` whose branches continue after its end.
+    If {
+        /// The Runwell basic block of the else branch.
+        ///
+        /// This is `None` once the Wasm `Else` operator has been translated.
+        else_block: Option<Block>,
+    },
+}
+
+/// A Wasm `Block`, `Loop` or `If` for Wasm operators to branch to.
+///
+/// # Note
+///
+/// The values carried by branches to a Wasm block are written into variables
+/// so that they are merged by the SSA construction of the function builder.
+/// The variables of a Wasm block hold its inputs followed by its outputs.
 #[derive(Debug, Copy, Clone)]
 pub struct WasmBlock {
-    /// The unique Runwell basic block index of the Wasm block.
+    /// The kind of the Wasm block.
+    kind: WasmBlockKind,
+    /// The Runwell basic block that branches to the Wasm block jump to.
+    ///
+    /// This is the loop header for Wasm `Loop` blocks and the basic block
+    /// following the end of the Wasm block otherwise.
     block: Option<Block>,
     /// The type of the Wasm block.
     ty: WasmBlockType,
+    /// The height of the value stack without the inputs of the Wasm block.
+    len_stack: usize,
+    /// The first variable holding the inputs and outputs of the Wasm block.
+    first_var: u32,
 }
 
 impl WasmBlock {
     /// Creates a new Wasm block with the given block type.
     pub fn new<B>(
+        kind: WasmBlockKind,
         block: B,
         block_type: wasmparser::TypeOrFuncType,
+        len_stack: usize,
+        first_var: u32,
     ) -> Result<Self, Error>
     where
         B: Into<Option<Block>>,
     {
         Ok(Self {
+            kind,
             block: block.into(),
             ty: WasmBlockType::try_from(block_type)?,
+            len_stack,
+            first_var,
         })
     }
 
-    /// Creates a new Wasm block with the given function type.
-    pub fn with_func_type<B>(block: B, func_type: FuncType) -> Self
-    where
-        B: Into<Option<Block>>,
-    {
+    /// Creates the Wasm block of the entire function with the given function type.
+    pub fn with_func_type(func_type: FuncType, first_var: u32) -> Self {
         Self {
-            block: block.into(),
+            kind: WasmBlockKind::Function,
+            block: None,
             ty: WasmBlockType::FuncType(func_type),
+            len_stack: 0,
+            first_var,
         }
     }
 
+    /// Returns the kind of the Wasm block.
+    pub fn kind(&self) -> WasmBlockKind {
+        self.kind
+    }
+
+    /// Marks the else branch of a Wasm `If` block as translated.
+    pub fn enter_else(&mut self) {
+        debug_assert!(matches!(self.kind, WasmBlockKind::If { .. }));
+        self.kind = WasmBlockKind::If { else_block: None };
+    }
+
     /// Returns the associated Runwell block index.
     pub fn block(&self) -> Option<Block> {
         self.block
     }
 
+    /// Returns the height of the value stack without the inputs of the Wasm block.
+    pub fn len_stack(&self) -> usize {
+        self.len_stack
+    }
+
     /// Returns a slice over the input types of the Wasm block.
+    ///
+    /// # Note
+    ///
+    /// The inputs of the function are its local variables and not
+    /// on the value stack. Therefore they are not part of the inputs
+    /// of the Wasm block of the entire function.
     pub fn inputs<'a, 'b, 'c>(&'a self, res: &'b ModuleResources) -> &'c [Type]
     where
         'a: 'c,
         'b: 'c,
     {
+        if let WasmBlockKind::Function = self.kind {
+            return &[]
+        }
         self.ty.inputs(res)
     }
 
+    /// Returns the variables holding the inputs of the Wasm block.
+    pub fn input_vars(
+        &self,
+        res: &ModuleResources,
+    ) -> impl ExactSizeIterator<Item = Variable> {
+        let len_inputs = self.inputs(res).len() as u32;
+        vars(self.first_var..self.first_var + len_inputs)
+    }
+
+    /// Returns the variables holding the outputs of the Wasm block.
+    pub fn output_vars(
+        &self,
+        res: &ModuleResources,
+    ) -> impl ExactSizeIterator<Item = Variable> {
+        let first_output = self.first_var + self.inputs(res).len() as u32;
+        let len_outputs = self.outputs(res).len() as u32;
+        vars(first_output..first_output + len_outputs)
+    }
+
+    /// Returns the variables holding the values carried by branches to the Wasm block.
+    ///
+    /// These are the inputs for Wasm `Loop` blocks and the outputs otherwise.
+    pub fn branch_vars(
+        &self,
+        res: &ModuleResources,
+    ) -> impl ExactSizeIterator<Item = Variable> {
+        let len_inputs = self.inputs(res).len() as u32;
+        let first_output = self.first_var + len_inputs;
+        match self.kind {
+            WasmBlockKind::Loop => vars(self.first_var..first_output),
+            _ => {
+                let len_outputs = self.outputs(res).len() as u32;
+                vars(first_output..first_output + len_outputs)
+            }
+        }
+    }
+
     /// Returns a slice over the output types of the Wasm block.
     pub fn outputs<'a, 'b, 'c>(&'a self, res: &'b ModuleResources) -> &'c [Type]
     where
@@ -179,9 +281,14 @@ impl WasmBlock {
     }
 }
 
+/// Returns the variables with indices in the given range.
+fn vars(range: Range<u32>) -> impl ExactSizeIterator<Item = Variable> {
+    range.map(|n| Variable::from_raw(RawIdx::from_u32(n)))
+}
+
 /// The type of a Wasm block.
 #[derive(Debug, Copy, Clone)]
-enum WasmBlockType {
+pub enum WasmBlockType {
     /// Block has no inputs and no outputs.
     Empty,
     /// Block just returns the inner type and has no inputs.
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use derive_more::{Display, Error};
+use ir::{Diagnostic, ToDiagnostic};
 
 /// An error that occurred while translating from Wasm to Runwell IR.
 #[derive(Debug, Display, Error, PartialEq, Eq)]
@@ -56,3 +57,30 @@ impl TranslateError {
         }
     }
 }
+
+impl ToDiagnostic for TranslateError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = |code| Diagnostic::new(code, self);
+        match self {
+            Self::UnsupportedOperator { offset } => {
+                diagnostic("wasm.unsupported_operator")
+                    .with_parameter("offset", offset)
+            }
+            Self::UnimplementedOperator { display } => {
+                diagnostic("wasm.unimplemented_operator")
+                    .with_parameter("operator", display)
+            }
+            Self::MissingStackValue { expected, found } => {
+                diagnostic("wasm.missing_stack_value")
+                    .with_parameter("expected", expected)
+                    .with_parameter("found", found)
+            }
+            Self::MissingWasmBlock => diagnostic("wasm.missing_wasm_block"),
+            Self::RelativeDepthExceedsBlockStack { n, len } => {
+                diagnostic("wasm.relative_depth_exceeds_block_stack")
+                    .with_parameter("n", n)
+                    .with_parameter("len", len)
+            }
+        }
+    }
+}
@@ -19,37 +19,74 @@
 mod blocks;
 mod error;
 mod operator;
+mod single;
 mod stack;
 
-pub use self::error::TranslateError;
+pub use self::{error::TranslateError, single::translate_single_function};
 use self::{
-    blocks::{Blocks, WasmBlock},
+    blocks::{Blocks, WasmBlock, WasmBlockType},
     stack::ValueStack,
 };
-use crate::{Error, Type};
+use crate::{BudgetExceeded, Error, TranslationBudget, Type};
 use core::{convert::TryFrom as _, fmt};
-use ir::primitive::Func;
-use module::{builder::FunctionBuilder, FunctionBody, ModuleResources};
+use entity::RawIdx;
+use ir::{
+    instr::TrapCode,
+    primitive::{Const, Func, FuncType},
+};
+use module::{
+    builder::{FunctionBuilder, IrArena},
+    primitive::{SourceSpan, Variable},
+    FunctionBody,
+    ModuleResources,
+};
 use wasmparser::{BinaryReader, FuncValidator, Range, ValidatorResources};
 
+/// The outcome of the translation of a Wasm function body.
+#[derive(Debug)]
+pub enum Translation {
+    /// The translated Runwell function body.
+    Body(Box<FunctionBody>),
+    /// The translation has been aborted since it exceeded its budget.
+    ///
+    /// The Wasm function body has still been validated entirely.
+    Bailout(BudgetExceeded),
+}
+
 /// Translate a Wasm function body into a Runwell function body.
 ///
 /// # Note
 ///
 /// - The `buffer` contains the binary encoded Wasm function body.
 /// - The Wasm function body is parsed and validated during construction.
+/// - The translation is aborted if it exceeds the `budget`.
+/// - The intermediate construction state is allocated from the `arena`.
 pub fn translate_function_body(
     range: Range,
-    buffer: Vec<u8>,
+    buffer: &[u8],
     validator: FuncValidator<ValidatorResources>,
     func: Func,
     res: &ModuleResources,
+    budget: &TranslationBudget,
+    arena: &mut IrArena,
+) -> Result<Translation, Error> {
+    let wasm_body = wasmparser::FunctionBody::new(range.start, buffer);
+    FunctionBodyTranslator::new(wasm_body, validator, func, res, budget, arena)?
+        .translate()
+}
+
+/// Creates the placeholder Runwell function body of an interpreter-only function.
+///
+/// The placeholder body simply traps.
+pub fn interpreter_only_body(
+    func: Func,
+    res: &ModuleResources,
 ) -> Result<FunctionBody, Error> {
-    let wasm_body = wasmparser::FunctionBody::new(range.start, &buffer[..]);
-    let function_body =
-        FunctionBodyTranslator::new(wasm_body, validator, func, res)
-            .translate()?;
-    Ok(function_body)
+    let mut builder = FunctionBody::build(func, res);
+    builder.body()?;
+    builder.ins()?.trap(TrapCode::Unreachable)?;
+    let body = builder.finalize()?;
+    Ok(body)
 }
 
 /// Translates Wasm function bodies into Runwell function bodies.
@@ -60,6 +97,8 @@ struct FunctionBodyTranslator<'a, 'b> {
     validator: FuncValidator<ValidatorResources>,
     /// The unique function index associated to the translated function body.
     func: Func,
+    /// The function type of the translated function body.
+    func_type: FuncType,
     /// The immutable module resources required to translate the function body.
     res: &'b ModuleResources,
     /// The Runwell function body builder.
@@ -68,6 +107,17 @@ struct FunctionBodyTranslator<'a, 'b> {
     stack: ValueStack,
     /// The emulated Wasm stack of control blocks.
     blocks: Blocks,
+    /// The variable holding the first input or output of the next Wasm block.
+    next_block_var: u32,
+    /// Is `false` while translating unreachable Wasm operators.
+    ///
+    /// This is the case after unconditional branches, returns and traps
+    /// until the end of the enclosing Wasm block.
+    reachable: bool,
+    /// The nesting depth of Wasm blocks within unreachable Wasm operators.
+    unreachable_depth: u32,
+    /// The limits of the translation.
+    budget: &'b TranslationBudget,
 }
 
 impl<'a, 'b> fmt::Debug for FunctionBodyTranslator<'a, 'b> {
@@ -79,6 +129,7 @@ impl<'a, 'b> fmt::Debug for FunctionBodyTranslator<'a, 'b> {
             .field("builder", &self.builder)
             .field("stack", &self.stack)
             .field("blocks", &self.blocks)
+            .field("reachable", &self.reachable)
             .finish()
     }
 }
@@ -90,74 +141,179 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         validator: FuncValidator<ValidatorResources>,
         func: Func,
         res: &'b ModuleResources,
-    ) -> Self {
+        budget: &'b TranslationBudget,
+        arena: &'b mut IrArena,
+    ) -> Result<Self, Error> {
         let mut reader = wasm_body.get_binary_reader();
         let _body_size = reader
             .read_var_u32()
             .expect("expect function size in bytes");
-        Self {
+        let func_type = res.get_raw_func_type(func).unwrap_or_else(|| {
+            panic!("expected function type for {} due to validation", func)
+        });
+        let signature = res
+            .get_type(func_type)
+            .expect("encountered missing function type for existing function");
+        let builder =
+            FunctionBody::with_signature_in(func, res, signature, arena)?;
+        Ok(Self {
             reader,
             validator,
             func,
+            func_type,
             res,
-            builder: FunctionBody::build(func, res),
+            builder,
             stack: Default::default(),
             blocks: Default::default(),
-        }
+            next_block_var: 0,
+            reachable: true,
+            unreachable_depth: 0,
+            budget,
+        })
     }
 
     /// Translates the Wasm function body into an equivalent Runwell function body.
-    fn translate(mut self) -> Result<FunctionBody, Error> {
-        self.translate_local_variables()?;
+    fn translate(mut self) -> Result<Translation, Error> {
+        if let Err(exceeded) = self.translate_local_variables()? {
+            return self.bailout(exceeded)
+        }
+        self.declare_block_variables()?;
         self.initialize_entry_block()?;
-        self.translate_operators()?;
+        if let Err(exceeded) = self.translate_operators()? {
+            return self.bailout(exceeded)
+        }
         let body = self.builder.finalize()?;
-        Ok(body)
+        Ok(Translation::Body(Box::new(body)))
+    }
+
+    /// Aborts the translation and validates the remaining Wasm operators.
+    fn bailout(
+        mut self,
+        exceeded: BudgetExceeded,
+    ) -> Result<Translation, Error> {
+        while !self.reader.eof() {
+            let offset = self.reader.original_position();
+            let op = self.reader.read_operator()?;
+            self.validator.op(offset, &op)?;
+        }
+        let offset = self.reader.original_position();
+        self.validator.finish(offset)?;
+        Ok(Translation::Bailout(exceeded))
     }
 
     /// Parses, validates and translates the Wasm local variables into
     /// Runwell variable declarations.
-    fn translate_local_variables(&mut self) -> Result<(), Error> {
+    ///
+    /// Returns `Ok(Err(_))` if the local variables exceed the budget.
+    /// In this case all local variables are still validated.
+    fn translate_local_variables(
+        &mut self,
+    ) -> Result<Result<(), BudgetExceeded>, Error> {
+        let mut budget = Ok(());
+        let mut total_locals = 0_u32;
         let count_locals = self.reader.read_var_u32()?;
         for _ in 0..count_locals {
             let offset = self.reader.original_position();
             let count = self.reader.read_var_u32()?;
             let ty = self.reader.read_type()?;
             self.validator.define_locals(offset, count, ty)?;
-            let ty = Type::try_from(ty)?.into_inner();
-            self.builder.declare_variables(count, ty)?;
+            total_locals = total_locals.saturating_add(count);
+            if budget.is_ok() {
+                budget = self.budget.check_locals(total_locals);
+            }
+            if budget.is_ok() {
+                let ty = Type::try_from(ty)?.into_inner();
+                self.builder.declare_variables(count, ty)?;
+            }
+        }
+        let len_inputs = self.builder.input_vars().len() as u32;
+        self.next_block_var = len_inputs.saturating_add(total_locals);
+        Ok(budget)
+    }
+
+    /// Declares the variables holding the inputs and outputs of all Wasm blocks.
+    ///
+    /// The variables of the Wasm block of the entire function come first
+    /// followed by the variables of all Wasm blocks in the order in which
+    /// they appear in the Wasm function body.
+    fn declare_block_variables(&mut self) -> Result<(), Error> {
+        let entry_block_type = WasmBlockType::FuncType(self.func_type);
+        for &ty in entry_block_type.outputs(self.res) {
+            self.builder.declare_variables(1, ty)?;
+        }
+        // The operators are validated upon translation so that we simply
+        // stop declaring variables at malformed operators.
+        let mut reader = self.reader.clone();
+        while !reader.eof() {
+            let ty = match reader.read_operator() {
+                Ok(wasmparser::Operator::Block { ty })
+                | Ok(wasmparser::Operator::Loop { ty })
+                | Ok(wasmparser::Operator::If { ty }) => ty,
+                Ok(_) => continue,
+                Err(_) => break,
+            };
+            let block_type = match WasmBlockType::try_from(ty) {
+                Ok(block_type) => block_type,
+                Err(_) => break,
+            };
+            let inputs = block_type.inputs(self.res);
+            let outputs = block_type.outputs(self.res);
+            for &ty in inputs.iter().chain(outputs) {
+                self.builder.declare_variables(1, ty)?;
+            }
         }
         Ok(())
     }
 
     /// Initializes the stack of blocks to contain the Runwell entry block.
+    ///
+    /// Also initializes the Wasm local variables to zero.
     fn initialize_entry_block(&mut self) -> Result<(), Error> {
-        let entry_block_type =
-            self.res.get_raw_func_type(self.func).unwrap_or_else(|| {
-                panic!(
-                    "expected function type for {} due to validation",
-                    self.func
-                )
-            });
-        let entry_block = WasmBlock::with_func_type(
-            self.builder.current_block()?,
-            entry_block_type,
-        );
+        let len_inputs = self.builder.input_vars().len() as u32;
+        for n in len_inputs..self.next_block_var {
+            let var = Variable::from_raw(RawIdx::from_u32(n));
+            let ty = self.builder.var_type(var)?;
+            let zero = self.builder.ins()?.constant(Const::from_bits64(ty, 0))?;
+            self.builder.write_var(var, zero)?;
+        }
+        let entry_block =
+            WasmBlock::with_func_type(self.func_type, self.next_block_var);
+        self.next_block_var += entry_block.outputs(self.res).len() as u32;
         self.blocks.push_block(entry_block);
         Ok(())
     }
 
     /// Parses, validates and translates the Wasm operands into Runwell
     /// function body instructions and basic blocks.
-    fn translate_operators(&mut self) -> Result<(), Error> {
+    ///
+    /// Every translated instruction remembers the byte range of the Wasm
+    /// operator it originates from as its source span.
+    ///
+    /// Returns `Ok(Err(_))` if the translation exceeds the budget.
+    /// In this case the remaining Wasm operators are not yet validated.
+    fn translate_operators(
+        &mut self,
+    ) -> Result<Result<(), BudgetExceeded>, Error> {
+        let mut count_operators = 0_u32;
         while !self.reader.eof() {
             let offset = self.reader.original_position();
             let op = self.reader.read_operator()?;
             self.validator.op(offset, &op)?;
+            let end = self.reader.original_position();
+            self.builder
+                .set_source_span(Some(SourceSpan::new(offset, end)));
             self.translate_operator(offset, op)?;
+            count_operators += 1;
+            if let Err(exceeded) = self.budget.check_progress(
+                count_operators,
+                self.builder.len_instrs(),
+                self.builder.len_values(),
+            ) {
+                return Ok(Err(exceeded))
+            }
         }
         let offset = self.reader.original_position();
         self.validator.finish(offset)?;
-        Ok(())
+        Ok(Ok(()))
     }
 }
@@ -12,16 +12,96 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::super::{blocks::WasmBlock, FunctionBodyTranslator};
-use crate::{Error, TranslateError};
+use super::super::{
+    blocks::{WasmBlock, WasmBlockKind, WasmBlockType},
+    FunctionBodyTranslator,
+};
+use crate::Error;
+use core::convert::TryFrom as _;
+use ir::{
+    instr::{operands::CompareIntOp, TrapCode},
+    primitive::{Block, IntConst, IntType, Value},
+};
+use module::primitive::Variable;
 
 impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
+    /// Creates a new Wasm block of the given kind and type.
+    ///
+    /// The inputs of the Wasm block are expected on top of the value stack.
+    fn create_wasm_block(
+        &mut self,
+        kind: WasmBlockKind,
+        block: Option<Block>,
+        ty: wasmparser::TypeOrFuncType,
+    ) -> Result<WasmBlock, Error> {
+        let block_type = WasmBlockType::try_from(ty)?;
+        let len_inputs = block_type.inputs(self.res).len();
+        let len_outputs = block_type.outputs(self.res).len();
+        let len_stack = self.stack.len() - len_inputs;
+        let wasm_block =
+            WasmBlock::new(kind, block, ty, len_stack, self.next_block_var)?;
+        self.next_block_var += (len_inputs + len_outputs) as u32;
+        Ok(wasm_block)
+    }
+
+    /// Writes the values on top of the value stack into the variables.
+    ///
+    /// The values are not popped from the value stack.
+    fn write_stack_to_vars<T>(&mut self, vars: T) -> Result<(), Error>
+    where
+        T: ExactSizeIterator<Item = Variable>,
+    {
+        let values = self.stack.peek_n(vars.len())?;
+        for (var, entry) in vars.zip(values) {
+            self.builder.write_var(var, entry.value)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the variables and pushes their values onto the value stack.
+    fn push_vars_to_stack<T>(&mut self, vars: T) -> Result<(), Error>
+    where
+        T: Iterator<Item = Variable>,
+    {
+        for var in vars {
+            let value = self.builder.read_var(var)?;
+            let ty = self.builder.var_type(var)?;
+            self.stack.push(value, ty);
+        }
+        Ok(())
+    }
+
+    /// Pops the `i32` condition of a Wasm branch and converts it into a `bool` value.
+    fn pop_condition(&mut self) -> Result<Value, Error> {
+        let condition = self.stack.pop1()?;
+        assert_eq!(condition.ty, IntType::I32.into());
+        let zero = self.builder.ins()?.constant(IntConst::I32(0))?;
+        let result = self.builder.ins()?.icmp(
+            IntType::I32,
+            CompareIntOp::Ne,
+            condition.value,
+            zero,
+        )?;
+        Ok(result)
+    }
+
+    /// Prepares a branch to the nth Wasm block from the back where 0th is the last.
+    ///
+    /// Writes the values carried by the branch into the variables of the
+    /// Wasm block and returns the Runwell basic block to jump to.
+    fn branch_target(&mut self, relative_depth: u32) -> Result<Block, Error> {
+        let wasm_block = self.blocks.nth_back(relative_depth)?;
+        self.write_stack_to_vars(wasm_block.branch_vars(self.res))?;
+        self.blocks.break_to(relative_depth, &mut self.builder)
+    }
+
     /// Translate a Wasm `Block` control operator.
     pub(super) fn translate_block(
         &mut self,
         ty: wasmparser::TypeOrFuncType,
     ) -> Result<(), Error> {
-        let wasm_block = WasmBlock::new(None, ty)?;
+        let wasm_block =
+            self.create_wasm_block(WasmBlockKind::Block, None, ty)?;
         self.blocks.push_block(wasm_block);
         Ok(())
     }
@@ -32,10 +112,16 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         ty: wasmparser::TypeOrFuncType,
     ) -> Result<(), Error> {
         let loop_header = self.builder.create_block()?;
-        let wasm_block = WasmBlock::new(loop_header, ty)?;
-        self.blocks.push_block(wasm_block);
+        let wasm_block =
+            self.create_wasm_block(WasmBlockKind::Loop, Some(loop_header), ty)?;
+        self.write_stack_to_vars(wasm_block.input_vars(self.res))?;
         self.builder.ins()?.br(loop_header)?;
         self.builder.switch_to_block(loop_header)?;
+        // The loop header is sealed at the end of the loop since
+        // only then all of its predecessors are known.
+        self.stack.truncate(wasm_block.len_stack());
+        self.push_vars_to_stack(wasm_block.input_vars(self.res))?;
+        self.blocks.push_block(wasm_block);
         Ok(())
     }
 
@@ -44,52 +130,111 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         &mut self,
         ty: wasmparser::TypeOrFuncType,
     ) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::If { ty },
-        ))
-        .map_err(Into::into)
+        let condition = self.pop_condition()?;
+        let then_block = self.builder.create_block()?;
+        let else_block = self.builder.create_block()?;
+        let kind = WasmBlockKind::If {
+            else_block: Some(else_block),
+        };
+        let wasm_block = self.create_wasm_block(kind, None, ty)?;
+        // The else branch reads its inputs from the variables.
+        self.write_stack_to_vars(wasm_block.input_vars(self.res))?;
+        self.builder
+            .ins()?
+            .if_then_else(condition, then_block, else_block)?;
+        self.builder.seal_block(then_block)?;
+        self.builder.seal_block(else_block)?;
+        self.builder.switch_to_block(then_block)?;
+        self.blocks.push_block(wasm_block);
+        Ok(())
     }
 
     /// Translate a Wasm `Else` control operator.
     pub(super) fn translate_else(&mut self) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::Else,
-        ))
-        .map_err(Into::into)
+        if self.reachable {
+            let end_block = self.branch_target(0)?;
+            self.builder.ins()?.br(end_block)?;
+        }
+        let mut wasm_block = self.blocks.pop_block()?;
+        let else_block = match wasm_block.kind() {
+            WasmBlockKind::If {
+                else_block: Some(else_block),
+            } => else_block,
+            _ => panic!("encountered Else without If due to validation"),
+        };
+        wasm_block.enter_else();
+        self.builder.switch_to_block(else_block)?;
+        self.reachable = true;
+        self.stack.truncate(wasm_block.len_stack());
+        self.push_vars_to_stack(wasm_block.input_vars(self.res))?;
+        self.blocks.push_block(wasm_block);
+        Ok(())
     }
 
     /// Translate a Wasm `End` control operator.
     pub(super) fn translate_end(&mut self) -> Result<(), Error> {
-        let block = self.blocks.pop_block()?;
-        if let Some(runwell_block) = block.block() {
-            let _ = self.builder.seal_block(runwell_block).unwrap_or(());
+        let wasm_block = self.blocks.current()?;
+        match wasm_block.kind() {
+            WasmBlockKind::Loop => {
+                // The results of the loop simply stay on the value stack.
+                self.blocks.pop_block()?;
+                if let Some(loop_header) = wasm_block.block() {
+                    self.builder.seal_block(loop_header)?;
+                }
+                if !self.reachable {
+                    self.stack.truncate(wasm_block.len_stack());
+                }
+                return Ok(())
+            }
+            WasmBlockKind::Function if wasm_block.block().is_none() => {
+                // There are no branches to the end of the function.
+                self.blocks.pop_block()?;
+                if self.reachable {
+                    self.translate_return()?;
+                }
+                return Ok(())
+            }
+            WasmBlockKind::If {
+                else_block: Some(else_block),
+            } => {
+                // Without a Wasm `Else` operator the inputs are passed
+                // through as the outputs of the else branch.
+                if self.reachable {
+                    let end_block = self.branch_target(0)?;
+                    self.builder.ins()?.br(end_block)?;
+                }
+                self.builder.switch_to_block(else_block)?;
+                self.reachable = true;
+                self.stack.truncate(wasm_block.len_stack());
+                self.push_vars_to_stack(wasm_block.input_vars(self.res))?;
+            }
+            WasmBlockKind::Block
+            | WasmBlockKind::Function
+            | WasmBlockKind::If { else_block: None } => (),
+        }
+        if self.reachable {
+            let end_block = self.branch_target(0)?;
+            self.builder.ins()?.br(end_block)?;
+        }
+        let wasm_block = self.blocks.pop_block()?;
+        self.stack.truncate(wasm_block.len_stack());
+        match wasm_block.block() {
+            Some(end_block) => {
+                self.builder.switch_to_block(end_block)?;
+                self.builder.seal_block(end_block)?;
+                self.reachable = true;
+                self.push_vars_to_stack(wasm_block.output_vars(self.res))?;
+            }
+            None => {
+                // The end of the Wasm block is unreachable since
+                // there are no branches to it.
+                self.reachable = false;
+            }
         }
-        if self.blocks.is_empty() {
-            // The popped block was the entry block and thus the
-            // `End` operator represents the end of the Wasm function.
-            // Therefore we need to insert a Runwell return statement
-            // returning all values that are still on the stack and
-            // check if they are matching the functions return types.
-            let outputs = self
-                .res
-                .get_func_type(self.func)
-                .unwrap_or_else(|| {
-                    panic!(
-                        "expected function type for {} due to validation",
-                        self.func
-                    )
-                })
-                .outputs();
-            let output_values = self.stack.peek_n(outputs.len())?;
-            for (req_type, entry) in
-                outputs.iter().copied().zip(output_values.clone())
-            {
-                assert_eq!(req_type, entry.ty);
+        if let WasmBlockKind::Function = wasm_block.kind() {
+            if self.reachable {
+                self.translate_return()?;
             }
-            self.builder
-                .ins()?
-                .return_values(output_values.map(|entry| entry.value))?;
-            self.stack.pop_n(outputs.len())?;
         }
         Ok(())
     }
@@ -99,10 +244,10 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         &mut self,
         relative_depth: u32,
     ) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::Br { relative_depth },
-        ))
-        .map_err(Into::into)
+        let target = self.branch_target(relative_depth)?;
+        self.builder.ins()?.br(target)?;
+        self.reachable = false;
+        Ok(())
     }
 
     /// Translate a Wasm `BrIf` control operator.
@@ -110,10 +255,15 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         &mut self,
         relative_depth: u32,
     ) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::BrIf { relative_depth },
-        ))
-        .map_err(Into::into)
+        let condition = self.pop_condition()?;
+        let target = self.branch_target(relative_depth)?;
+        let next_block = self.builder.create_block()?;
+        self.builder
+            .ins()?
+            .if_then_else(condition, target, next_block)?;
+        self.builder.seal_block(next_block)?;
+        self.builder.switch_to_block(next_block)?;
+        Ok(())
     }
 
     /// Translate a Wasm `BrTable` control operator.
@@ -121,17 +271,82 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         &mut self,
         table: wasmparser::BrTable,
     ) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::BrTable { table },
-        ))
-        .map_err(Into::into)
+        let case = self.stack.pop1()?;
+        let mut targets = Vec::new();
+        let mut default = None;
+        for target in table.targets() {
+            let (relative_depth, is_default) = target?;
+            let target = self.branch_target(relative_depth)?;
+            if is_default {
+                default = Some(target);
+            } else {
+                targets.push(target);
+            }
+        }
+        let default =
+            default.expect("encountered branch table without default target");
+        self.builder.ins()?.br_table(case.value, targets, default)?;
+        self.reachable = false;
+        Ok(())
     }
 
     /// Translate a Wasm `Return` control operator.
     pub(super) fn translate_return(&mut self) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::Return,
-        ))
-        .map_err(Into::into)
+        let outputs = self
+            .res
+            .get_func_type(self.func)
+            .unwrap_or_else(|| {
+                panic!(
+                    "expected function type for {} due to validation",
+                    self.func
+                )
+            })
+            .outputs();
+        let output_values = self.stack.peek_n(outputs.len())?;
+        for (req_type, entry) in
+            outputs.iter().copied().zip(output_values.clone())
+        {
+            assert_eq!(req_type, entry.ty);
+        }
+        self.builder
+            .ins()?
+            .return_values(output_values.map(|entry| entry.value))?;
+        self.stack.pop_n(outputs.len())?;
+        self.reachable = false;
+        Ok(())
+    }
+
+    /// Translate a Wasm `Unreachable` control operator.
+    pub(super) fn translate_unreachable(&mut self) -> Result<(), Error> {
+        self.builder.ins()?.trap(TrapCode::Unreachable)?;
+        self.reachable = false;
+        Ok(())
+    }
+
+    /// Translate a Wasm operator that is unreachable.
+    ///
+    /// Unreachable operators are validated but not translated. Only the Wasm
+    /// `Else` and `End` operators of the enclosing Wasm block are translated
+    /// since they might end the unreachable code.
+    pub(super) fn translate_unreachable_operator(
+        &mut self,
+        op: wasmparser::Operator,
+    ) -> Result<(), Error> {
+        use wasmparser::Operator as Op;
+        match op {
+            Op::Block { ty } | Op::Loop { ty } | Op::If { ty } => {
+                // Skip the variables declared for the unreachable Wasm block.
+                let block_type = WasmBlockType::try_from(ty)?;
+                let len_vars = block_type.inputs(self.res).len()
+                    + block_type.outputs(self.res).len();
+                self.next_block_var += len_vars as u32;
+                self.unreachable_depth += 1;
+            }
+            Op::Else if self.unreachable_depth == 0 => self.translate_else()?,
+            Op::End if self.unreachable_depth == 0 => self.translate_end()?,
+            Op::End => self.unreachable_depth -= 1,
+            _ => (),
+        }
+        Ok(())
     }
 }
@@ -124,6 +124,8 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
     }
 
     /// Translates a Wasm integer compare to zero (`Eqz`) operator.
+    ///
+    /// The operator is translated into an equality comparison with zero.
     pub(super) fn translate_eqz_op(
         &mut self,
         int_type: IntType,
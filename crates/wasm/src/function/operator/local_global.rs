@@ -13,9 +13,9 @@
 // limitations under the License.
 
 use super::super::FunctionBodyTranslator;
-use crate::{Error, TranslateError};
+use crate::Error;
 use entity::RawIdx;
-use module::primitive::Variable;
+use module::primitive::{Global, Variable};
 
 impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
     /// Translates Wasm `local_get` operator.
@@ -59,10 +59,14 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         &mut self,
         global_index: u32,
     ) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::GlobalGet { global_index },
-        ))
-        .map_err(Into::into)
+        let global = Global::from_raw(RawIdx::from_u32(global_index));
+        let decl = self.res.get_global_decl(global).unwrap_or_else(|| {
+            panic!("global variable {} must exist due to validation", global)
+        });
+        let result_type = decl.ty();
+        let result = self.builder.ins()?.global_get(global)?;
+        self.stack.push(result, result_type);
+        Ok(())
     }
 
     /// Translates Wasm `global_set` operator.
@@ -70,9 +74,9 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         &mut self,
         global_index: u32,
     ) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::GlobalSet { global_index },
-        ))
-        .map_err(Into::into)
+        let global = Global::from_raw(RawIdx::from_u32(global_index));
+        let source = self.stack.pop1()?;
+        self.builder.ins()?.global_set(global, source.value)?;
+        Ok(())
     }
 }
@@ -13,9 +13,10 @@
 // limitations under the License.
 
 use super::super::FunctionBodyTranslator;
-use crate::{function::stack::ValueEntry, Error, TranslateError};
+use crate::{function::stack::ValueEntry, Error};
 use entity::RawIdx;
 use ir::{
+    instr::TrapCode,
     primitive::{self as runwell, IntType, Mem, Value},
     ImmU32,
 };
@@ -49,7 +50,7 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
                 // and an immediately following `trap` behind it.
                 let ptr =
                     self.builder.ins()?.heap_addr(mem, pos, ImmU32::from(0))?;
-                self.builder.ins()?.trap()?;
+                self.builder.ins()?.trap(TrapCode::MemoryOutOfBounds)?;
                 ptr
             }
         };
@@ -152,23 +153,25 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
     pub(super) fn translate_memory_grow(
         &mut self,
         mem: u32,
-        mem_byte: u8,
+        _mem_byte: u8,
     ) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::MemoryGrow { mem, mem_byte },
-        ))
-        .map_err(Into::into)
+        let new_pages = self.stack.pop1()?;
+        assert_eq!(new_pages.ty, IntType::I32.into());
+        let mem = Mem::from_raw(RawIdx::from_u32(mem));
+        let result = self.builder.ins()?.memory_grow(mem, new_pages.value)?;
+        self.stack.push(result, IntType::I32.into());
+        Ok(())
     }
 
     /// Translates the Wasm memory size operator.
     pub(super) fn translate_memory_size(
         &mut self,
         mem: u32,
-        mem_byte: u8,
+        _mem_byte: u8,
     ) -> Result<(), Error> {
-        Err(TranslateError::unimplemented_operator(
-            wasmparser::Operator::MemorySize { mem, mem_byte },
-        ))
-        .map_err(Into::into)
+        let mem = Mem::from_raw(RawIdx::from_u32(mem));
+        let result = self.builder.ins()?.memory_size(mem)?;
+        self.stack.push(result, IntType::I32.into());
+        Ok(())
     }
 }
@@ -50,10 +50,11 @@ impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
         use ir::primitive::IntType::{I16, I32, I64, I8};
         use UnaryFloatOp as FloatUnop;
         use UnaryIntOp::*;
+        if !self.reachable {
+            return self.translate_unreachable_operator(op)
+        }
         match op {
-            Op::Unreachable => {
-                self.builder.ins()?.trap()?;
-            }
+            Op::Unreachable => self.translate_unreachable()?,
             Op::Nop => { /* Deliberately do nothing. */ }
             Op::Block { ty } => self.translate_block(ty)?,
             Op::Loop { ty } => self.translate_loop(ty)?,
@@ -18,12 +18,15 @@ use ir::primitive::{IntConst, IntType, Value};
 
 impl<'a, 'b> FunctionBodyTranslator<'a, 'b> {
     /// Translates a Runwell `bool` result into an equivalent Wasm `i32` result.
+    ///
+    /// As in Wasm `true` is materialized as `1` and `false` as `0` so that
+    /// the result can flow into integer arithmetic.
     pub(super) fn translate_bool_to_i32(
         &mut self,
         bool_result: Value,
     ) -> Result<(), Error> {
-        let const_true = self.builder.ins()?.constant(IntConst::I32(0))?;
-        let const_false = self.builder.ins()?.constant(IntConst::I32(1))?;
+        let const_true = self.builder.ins()?.constant(IntConst::I32(1))?;
+        let const_false = self.builder.ins()?.constant(IntConst::I32(0))?;
         let bool_to_i32 = self.builder.ins()?.select(
             IntType::I32.into(),
             bool_result,
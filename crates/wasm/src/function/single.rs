@@ -0,0 +1,173 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translation of single standalone Wasm function bodies.
+//!
+//! Useful for unit tests, REPL usage and the compilation of dynamically
+//! generated snippets where parsing an entire Wasm module is overkill.
+
+use super::{translate_function_body, Translation};
+use crate::{Error, PrimitiveError, TranslationBudget};
+use entity::RawIdx;
+use ir::primitive::{FloatType, Func, IntType, Type};
+use module::{
+    builder::IrArena,
+    primitive::FunctionType,
+    FunctionBody,
+    Module,
+};
+use wasmparser::{Parser, Payload, Range, Validator};
+
+/// The binary encoded Wasm section identifiers used by the translation.
+const TYPE_SECTION_ID: u8 = 1;
+const FUNCTION_SECTION_ID: u8 = 3;
+const CODE_SECTION_ID: u8 = 10;
+
+/// Translates a single standalone Wasm function body into a Runwell function body.
+///
+/// # Note
+///
+/// - The `body` contains the binary encoded Wasm function body as found in
+///   an entry of the Wasm code section without its size prefix.
+///   So it contains the local variable declarations followed by the
+///   operators of the function body.
+/// - The function is translated as the only function of an otherwise empty
+///   module. Therefore it may only call itself and must not access globals,
+///   linear memories or tables.
+/// - The function body is validated during translation.
+///
+/// # Errors
+///
+/// - If the function type has inputs or outputs of types unsupported by Wasm.
+/// - If the Wasm function body is invalid or unsupported.
+pub fn translate_single_function(
+    func_type: &FunctionType,
+    body: &[u8],
+) -> Result<FunctionBody, Error> {
+    let (wasm, entry) = encode_single_function_module(func_type, body)?;
+    let mut builder = Module::build();
+    let func_type = builder.type_section()?.push_type(func_type.clone());
+    let func = builder.function_section()?.push_function(func_type)?;
+    debug_assert_eq!(func, Func::from_raw(RawIdx::from_u32(0)));
+    let (res, _) = builder.code_section()?;
+    let mut validator = Validator::new();
+    for payload in Parser::new(0).parse_all(&wasm) {
+        match payload? {
+            Payload::Version { num, range } => {
+                validator.version(num, &range)?;
+            }
+            Payload::TypeSection(reader) => {
+                validator.type_section(&reader)?;
+            }
+            Payload::FunctionSection(reader) => {
+                validator.function_section(&reader)?;
+            }
+            Payload::CodeSectionStart { count, range, .. } => {
+                validator.code_section_start(count, &range)?;
+            }
+            Payload::CodeSectionEntry(_) => {
+                let fn_validator = validator.code_section_entry()?;
+                let translation = translate_function_body(
+                    entry,
+                    &wasm[entry.start..entry.end],
+                    fn_validator,
+                    func,
+                    res,
+                    &TranslationBudget::default(),
+                    &mut IrArena::new(),
+                )?;
+                match translation {
+                    Translation::Body(body) => return Ok(*body),
+                    Translation::Bailout(exceeded) => {
+                        unreachable!(
+                            "exceeded unlimited translation budget: {}",
+                            exceeded
+                        )
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    unreachable!("encountered missing code section entry in encoded module")
+}
+
+/// Encodes a Wasm module consisting of just the given function.
+///
+/// Returns the binary encoded Wasm module and the range of the code section
+/// entry of the function within it.
+fn encode_single_function_module(
+    func_type: &FunctionType,
+    body: &[u8],
+) -> Result<(Vec<u8>, Range), Error> {
+    let mut types = vec![0x01, 0x60];
+    encode_u32(&mut types, func_type.inputs().len() as u32);
+    for input in func_type.inputs() {
+        types.push(encode_type(*input)?);
+    }
+    encode_u32(&mut types, func_type.outputs().len() as u32);
+    for output in func_type.outputs() {
+        types.push(encode_type(*output)?);
+    }
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    encode_section(&mut wasm, TYPE_SECTION_ID, &types);
+    encode_section(&mut wasm, FUNCTION_SECTION_ID, &[0x01, 0x00]);
+    let mut entry = Vec::new();
+    encode_u32(&mut entry, body.len() as u32);
+    entry.extend_from_slice(body);
+    let mut code = vec![0x01];
+    code.extend_from_slice(&entry);
+    encode_section(&mut wasm, CODE_SECTION_ID, &code);
+    let range = Range {
+        start: wasm.len() - entry.len(),
+        end: wasm.len(),
+    };
+    Ok((wasm, range))
+}
+
+/// Appends the section with the given identifier and contents to the Wasm binary.
+fn encode_section(wasm: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    wasm.push(id);
+    encode_u32(wasm, contents.len() as u32);
+    wasm.extend_from_slice(contents);
+}
+
+/// Appends the LEB128 encoded value to the buffer.
+fn encode_u32(buffer: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            break
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+/// Returns the binary encoded Wasm value type for the Runwell type.
+fn encode_type(ty: Type) -> Result<u8, Error> {
+    let encoded = match ty {
+        Type::Int(IntType::I32) => 0x7F,
+        Type::Int(IntType::I64) => 0x7E,
+        Type::Float(FloatType::F32) => 0x7D,
+        Type::Float(FloatType::F64) => 0x7C,
+        unsupported => {
+            return Err(
+                PrimitiveError::UnsupportedRunwellType { unsupported }.into()
+            )
+        }
+    };
+    Ok(encoded)
+}
@@ -30,6 +30,18 @@ pub struct ValueEntry {
 }
 
 impl ValueStack {
+    /// Returns the number of values on the stack.
+    pub fn len(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Shortens the stack to the first `len` values.
+    ///
+    /// Does nothing if the stack holds less than `len` values.
+    pub fn truncate(&mut self, len: usize) {
+        self.stack.truncate(len)
+    }
+
     /// Pushes another value onto the stack.
     pub fn push(&mut self, value: Value, ty: Type) {
         self.stack.push(ValueEntry { value, ty });
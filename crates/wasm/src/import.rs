@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use derive_more::{Display, Error};
+use ir::{Diagnostic, ToDiagnostic};
 
 /// An error upon parsing, validating or operating on Wasm imports.
 #[derive(Debug, Error, Display, PartialEq, Eq, Hash)]
@@ -23,6 +24,16 @@ pub enum ImportError {
     UnsupportedEventImport,
 }
 
+impl ToDiagnostic for ImportError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let code = match self {
+            Self::UnsupportedModuleImport => "wasm.unsupported_module_import",
+            Self::UnsupportedEventImport => "wasm.unsupported_event_import",
+        };
+        Diagnostic::new(code, self)
+    }
+}
+
 /// The module and field name of an imported Wasm entity.
 #[derive(Debug)]
 pub struct ImportName<'a> {
@@ -16,7 +16,7 @@ use super::{Const, Error};
 use core::convert::TryFrom;
 use derive_more::{Display, Error};
 use entity::RawIdx;
-use ir::primitive::IntConst;
+use ir::{primitive::IntConst, Diagnostic, ToDiagnostic};
 use module::primitive::Global;
 use wasmparser::Operator;
 
@@ -37,6 +37,18 @@ pub enum InitExprError {
     MalformedExpression,
 }
 
+impl ToDiagnostic for InitExprError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let code = match self {
+            Self::UnsupportedOperator => "wasm.init_expr_unsupported_operator",
+            Self::UnsupportedV128 => "wasm.init_expr_unsupported_v128",
+            Self::UnsupportedRefType => "wasm.init_expr_unsupported_ref_type",
+            Self::MalformedExpression => "wasm.init_expr_malformed",
+        };
+        Diagnostic::new(code, self)
+    }
+}
+
 /// A parsed and validated Wasm constant initializer expression.
 #[derive(Debug)]
 pub struct InitExpr {
@@ -14,6 +14,7 @@
 
 #![forbid(unsafe_code)]
 
+mod budget;
 mod error;
 mod export;
 mod func_type;
@@ -24,22 +25,27 @@ mod init_expr;
 mod memory;
 mod primitive;
 mod read;
+mod report;
 mod section;
 mod table;
 
 pub use self::{
+    budget::{BudgetExceeded, TranslationBudget},
     error::{Error, ErrorKind},
     export::{Export, ExportError, ExportItem, ExportKind},
     func_type::FunctionType,
-    function::TranslateError,
+    function::{translate_single_function, TranslateError},
     global::GlobalVariable,
     import::{ImportError, ImportName},
     init_expr::{InitExpr, InitExprError},
     memory::{LinearMemoryDecl, MemoryDataInit, MemoryError},
     primitive::{Const, PrimitiveError, Type},
     read::{Read, ReadError},
+    report::CompilationReport,
     section::{
         parse,
+        parse_with_budget,
+        parse_with_limits,
         SectionError,
         UnexpectedWasmPayload,
         UnsupportedTypeDef,
@@ -16,7 +16,8 @@ use super::{Error, InitExpr};
 use core::convert::TryFrom;
 use derive_more::Display;
 use entity::RawIdx;
-use ir::primitive::Mem;
+use ir::{primitive::Mem, Diagnostic, ToDiagnostic};
+use module::ResourceLimits;
 
 /// An error that can occur upon parsing and validating linear memory.
 #[derive(Debug, Display)]
@@ -30,10 +31,39 @@ pub enum MemoryError {
     UnsupportedSharedMemory(wasmparser::MemoryType),
     #[display(fmt = "encountered unsupported passive data kind")]
     UnsupportedPassiveData,
+    #[display(
+        fmt = "linear memory of {} initial pages exceeds the limit of {} pages",
+        pages,
+        limit
+    )]
+    MemoryLimitExceeded { pages: u32, limit: u32 },
 }
 
 impl std::error::Error for MemoryError {}
 
+impl ToDiagnostic for MemoryError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::Unsupported64BitMemory(memory_type) => {
+                Diagnostic::new("wasm.unsupported_64bit_memory", self)
+                    .with_parameter("memory_type", format!("{:?}", memory_type))
+            }
+            Self::UnsupportedSharedMemory(memory_type) => {
+                Diagnostic::new("wasm.unsupported_shared_memory", self)
+                    .with_parameter("memory_type", format!("{:?}", memory_type))
+            }
+            Self::UnsupportedPassiveData => {
+                Diagnostic::new("wasm.unsupported_passive_data", self)
+            }
+            Self::MemoryLimitExceeded { pages, limit } => {
+                Diagnostic::new("wasm.memory_limit_exceeded", self)
+                    .with_parameter("pages", pages)
+                    .with_parameter("limit", limit)
+            }
+        }
+    }
+}
+
 /// A Wasm linear memory declaration.
 #[derive(Debug)]
 pub struct LinearMemoryDecl {
@@ -45,6 +75,20 @@ impl LinearMemoryDecl {
     pub fn into_inner(self) -> module::primitive::LinearMemoryDecl {
         self.inner
     }
+
+    /// Checks the initial pages of the linear memory against the limits.
+    ///
+    /// # Errors
+    ///
+    /// If the linear memory declares more initial pages than the limits allow.
+    pub fn check_limits(&self, limits: &ResourceLimits) -> Result<(), Error> {
+        let pages = self.inner.initial_pages();
+        let limit = limits.max_memory_pages();
+        if pages > limit {
+            return Err(MemoryError::MemoryLimitExceeded { pages, limit }.into())
+        }
+        Ok(())
+    }
 }
 
 impl TryFrom<wasmparser::MemoryType> for LinearMemoryDecl {
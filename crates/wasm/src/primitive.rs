@@ -15,13 +15,33 @@
 use super::Error;
 use core::convert::TryFrom;
 use derive_more::{Display, Error, From};
-use ir::primitive as runwell;
+use ir::{primitive as runwell, Diagnostic, ToDiagnostic};
 
 /// An error that occurred while operating on Wasm primitives.
 #[derive(Debug, Display, Error, From, PartialEq, Eq, Hash)]
 pub enum PrimitiveError {
     #[display(fmt = "encountered unsupported Wasm type: {:?}", unsupported)]
     UnsupportedWasmType { unsupported: wasmparser::Type },
+    #[display(
+        fmt = "encountered Runwell type unsupported by Wasm: {}",
+        unsupported
+    )]
+    UnsupportedRunwellType { unsupported: runwell::Type },
+}
+
+impl ToDiagnostic for PrimitiveError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::UnsupportedWasmType { unsupported } => {
+                Diagnostic::new("wasm.unsupported_wasm_type", self)
+                    .with_parameter("unsupported", format!("{:?}", unsupported))
+            }
+            Self::UnsupportedRunwellType { unsupported } => {
+                Diagnostic::new("wasm.unsupported_runwell_type", self)
+                    .with_parameter("unsupported", unsupported)
+            }
+        }
+    }
 }
 
 /// A Wasm translated Runwell type.
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use derive_more::{Display, Error};
+use ir::{Diagnostic, ToDiagnostic};
 
 /// Errors returned by [`Read::read`].
 #[derive(Debug, Display, Error, PartialEq, Eq)]
@@ -25,6 +26,16 @@ pub enum ReadError {
     UnknownError,
 }
 
+impl ToDiagnostic for ReadError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        let code = match self {
+            Self::EndOfStream => "wasm.end_of_stream",
+            Self::UnknownError => "wasm.unknown_read_error",
+        };
+        Diagnostic::new(code, self)
+    }
+}
+
 /// The Read trait allows for reading bytes from a source.
 ///
 /// # Note
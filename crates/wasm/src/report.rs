@@ -0,0 +1,172 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reports that attest the reproducibility of a compilation.
+
+use crate::TranslationBudget;
+use core::fmt::{self, Write as _};
+use module::Module;
+
+/// The FNV-1a 64-bit offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A manifest about the inputs and outputs of the compilation of a module.
+///
+/// Embedders can store and compare reports across nodes in order to attest
+/// that all of them compiled identical code from identical inputs.
+///
+/// # Note
+///
+/// The hashes are computed using the 64-bit FNV-1a hash function which is
+/// stable across platforms but not cryptographically secure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilationReport {
+    /// The hash of the Wasm input bytes.
+    input_hash: u64,
+    /// The version of the engine that compiled the module.
+    engine_version: &'static str,
+    /// The enabled feature flags of the engine.
+    features: Vec<&'static str>,
+    /// The budget that was used to translate the Wasm functions.
+    budget: TranslationBudget,
+    /// The transformations applied to the module after its translation.
+    passes: Vec<&'static str>,
+    /// The hash of the compiled module.
+    output_hash: u64,
+}
+
+impl CompilationReport {
+    /// Creates a report for the module compiled from the Wasm `input` bytes
+    /// using the translation `budget`.
+    ///
+    /// # Note
+    ///
+    /// The report must be created after all transformations have been
+    /// applied to the module so that they are reflected by the report.
+    /// The output hash covers the Runwell IR of all internal functions and
+    /// the source bodies of interpreter-only functions.
+    pub fn new(
+        input: &[u8],
+        budget: &TranslationBudget,
+        module: &Module,
+    ) -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "std") {
+            features.push("std");
+        }
+        Self {
+            input_hash: Fnv1a::default().hash(input).finish(),
+            engine_version: env!("CARGO_PKG_VERSION"),
+            features,
+            budget: budget.clone(),
+            passes: module.passes().to_vec(),
+            output_hash: output_hash(module),
+        }
+    }
+
+    /// Returns the hash of the Wasm input bytes.
+    pub fn input_hash(&self) -> u64 {
+        self.input_hash
+    }
+
+    /// Returns the version of the engine that compiled the module.
+    pub fn engine_version(&self) -> &'static str {
+        self.engine_version
+    }
+
+    /// Returns the enabled feature flags of the engine.
+    pub fn features(&self) -> &[&'static str] {
+        &self.features
+    }
+
+    /// Returns the budget that was used to translate the Wasm functions.
+    pub fn budget(&self) -> &TranslationBudget {
+        &self.budget
+    }
+
+    /// Returns the transformations applied to the module in order.
+    pub fn passes(&self) -> &[&'static str] {
+        &self.passes
+    }
+
+    /// Returns the hash of the compiled module.
+    pub fn output_hash(&self) -> u64 {
+        self.output_hash
+    }
+}
+
+impl fmt::Display for CompilationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "input-hash = {:016x}", self.input_hash)?;
+        writeln!(f, "engine-version = {}", self.engine_version)?;
+        writeln!(f, "features = [{}]", self.features.join(", "))?;
+        writeln!(f, "budget = {:?}", self.budget)?;
+        writeln!(f, "passes = [{}]", self.passes.join(", "))?;
+        writeln!(f, "output-hash = {:016x}", self.output_hash)?;
+        Ok(())
+    }
+}
+
+/// Returns the hash of the compiled module.
+fn output_hash(module: &Module) -> u64 {
+    let mut hasher = Fnv1a::default();
+    write!(hasher, "{}", module).expect("hashing cannot fail");
+    for function in module.internal_functions() {
+        if let Some(source) = module.get_interpreter_only_source(function.idx())
+        {
+            hasher.hash(source);
+        }
+    }
+    hasher.finish()
+}
+
+/// Computes 64-bit FNV-1a hashes.
+#[derive(Debug, Copy, Clone)]
+struct Fnv1a {
+    state: u64,
+}
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl Fnv1a {
+    /// Feeds the bytes into the hash.
+    fn hash(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in bytes.iter().copied() {
+            self.state ^= u64::from(byte);
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+        self
+    }
+
+    /// Returns the hash of all bytes fed so far.
+    fn finish(&self) -> u64 {
+        self.state
+    }
+}
+
+impl fmt::Write for Fnv1a {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.hash(s.as_bytes());
+        Ok(())
+    }
+}
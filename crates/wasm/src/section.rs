@@ -24,16 +24,26 @@ use super::{
     TableDecl,
 };
 use crate::{
-    function::translate_function_body,
+    function::{interpreter_only_body, translate_function_body, Translation},
     table::Element,
     FunctionType,
     InitExpr,
+    TranslationBudget,
 };
 use core::convert::TryFrom;
 use derive_more::{Display, Error};
 use entity::RawIdx;
-use ir::primitive::{Func, FuncType};
-use module::{builder::ModuleBuilder, Module};
+use ir::{
+    primitive::{Func, FuncType},
+    Diagnostic,
+    ToDiagnostic,
+};
+use module::{
+    builder::{IrArena, ModuleBuilder},
+    primitive::Producers,
+    Module,
+    ResourceLimits,
+};
 use wasmparser::{
     Chunk,
     DataSectionReader,
@@ -46,6 +56,7 @@ use wasmparser::{
     MemorySectionReader,
     Parser,
     Payload,
+    ProducersSectionReader,
     TableSectionReader,
     TypeSectionReader,
     Validator,
@@ -153,6 +164,44 @@ pub enum UnsupportedTypeDef {
     Module,
 }
 
+impl ToDiagnostic for SectionError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::Unsupported(section) => {
+                let name = match section {
+                    UnsupportedWasmSection::DataCount => "data_count",
+                    UnsupportedWasmSection::Module => "module",
+                    UnsupportedWasmSection::Instance => "instance",
+                    UnsupportedWasmSection::Alias => "alias",
+                    UnsupportedWasmSection::Event => "event",
+                    UnsupportedWasmSection::Unknown => "unknown",
+                };
+                Diagnostic::new("wasm.unsupported_section", self)
+                    .with_parameter("section", name)
+            }
+            Self::Unexpected(payload) => {
+                Diagnostic::new("wasm.unexpected_payload", self)
+                    .with_parameter(
+                        "encountered",
+                        format!("{:?}", payload.encountered),
+                    )
+                    .with_parameter(
+                        "expected",
+                        format!("{:?}", payload.expected),
+                    )
+            }
+            Self::UnsupportedTypeDef(type_def) => {
+                let name = match type_def {
+                    UnsupportedTypeDef::Instance => "instance",
+                    UnsupportedTypeDef::Module => "module",
+                };
+                Diagnostic::new("wasm.unsupported_type_def", self)
+                    .with_parameter("type_def", name)
+            }
+        }
+    }
+}
+
 fn pull_more_data<R>(
     hint: u64,
     buffer: &mut Vec<u8>,
@@ -187,13 +236,68 @@ where
 /// - If the given Wasm does not validate.
 /// - If the given Wasm does not parse properly.
 /// - If unsupported Wasm definitions or proposals are encountered.
-pub fn parse<R>(mut reader: R, buffer: &mut Vec<u8>) -> Result<Module, Error>
+pub fn parse<R>(reader: R, buffer: &mut Vec<u8>) -> Result<Module, Error>
+where
+    R: Read,
+{
+    parse_with_budget(reader, buffer, Default::default())
+}
+
+/// Parses the binary WebAssembly (Wasm) bytes given through `reader`.
+///
+/// Returns the fully parsed and validated Wasm module.
+///
+/// # Note
+///
+/// - Reuses the allocation from the `buffer` bytes vector.
+/// - Functions whose translation exceeds the `budget` are registered as
+///   interpreter-only functions instead of failing the translation.
+///
+/// # Errors
+///
+/// - If the given Wasm does not validate.
+/// - If the given Wasm does not parse properly.
+/// - If unsupported Wasm definitions or proposals are encountered.
+pub fn parse_with_budget<R>(
+    reader: R,
+    buffer: &mut Vec<u8>,
+    budget: TranslationBudget,
+) -> Result<Module, Error>
+where
+    R: Read,
+{
+    parse_with_limits(reader, buffer, budget, Default::default())
+}
+
+/// Parses the binary WebAssembly (Wasm) bytes given through `reader`.
+///
+/// Returns the fully parsed and validated Wasm module.
+///
+/// # Note
+///
+/// - Reuses the allocation from the `buffer` bytes vector.
+/// - Functions whose translation exceeds the `budget` are registered as
+///   interpreter-only functions instead of failing the translation.
+///
+/// # Errors
+///
+/// - If the given Wasm does not validate.
+/// - If the given Wasm does not parse properly.
+/// - If unsupported Wasm definitions or proposals are encountered.
+/// - If a declared or imported linear memory or table exceeds the `limits`
+///   with its initial size.
+pub fn parse_with_limits<R>(
+    mut reader: R,
+    buffer: &mut Vec<u8>,
+    budget: TranslationBudget,
+    limits: ResourceLimits,
+) -> Result<Module, Error>
 where
     R: Read,
 {
     let mut parser = Parser::new(0);
     let mut eof = false;
-    let mut context = ParseContext::default();
+    let mut context = ParseContext::new(budget, limits);
     buffer.clear();
     loop {
         match parser.parse(&buffer, eof)? {
@@ -226,18 +330,35 @@ pub struct ParseContext {
     builder: ModuleBuilder,
     /// The Wasm validator and its internal state.
     validator: Validator,
+    /// The per-function translation budget.
+    budget: TranslationBudget,
+    /// The limits on the sizes of linear memories and tables.
+    limits: ResourceLimits,
+    /// The number of imported functions.
+    ///
+    /// Function bodies of the code section are indexed after them.
+    len_imported_funcs: u32,
 }
 
 impl Default for ParseContext {
     fn default() -> Self {
+        Self::new(Default::default(), Default::default())
+    }
+}
+
+impl ParseContext {
+    /// Creates a new parsing context using the per-function translation budget
+    /// and the limits on the sizes of linear memories and tables.
+    pub fn new(budget: TranslationBudget, limits: ResourceLimits) -> Self {
         Self {
             builder: Module::build(),
-            validator: Default::default(),
+            validator: Validator::new(),
+            budget,
+            limits,
+            len_imported_funcs: 0,
         }
     }
-}
 
-impl ParseContext {
     /// Finishes building the Wasm module and returns the Wasm module built so far.
     pub fn finish(self) -> Result<Module, Error> {
         self.builder.finalize().map_err(Into::into)
@@ -324,11 +445,18 @@ impl ParseContext {
                 .map_err(Into::into)
             }
 
+            Payload::CustomSection {
+                name: "producers",
+                data_offset,
+                data,
+            } => {
+                self.parse_producers_section(data, data_offset)?;
+            }
             Payload::CustomSection {
                 name: _,
                 data_offset: _,
                 data: _,
-            } => { /* custom sections are ignored */ }
+            } => { /* other custom sections are ignored */ }
             Payload::UnknownSection {
                 id: _,
                 contents: _,
@@ -409,9 +537,11 @@ impl ParseContext {
                         import_name.into(),
                         FuncType::from_raw(RawIdx::from_u32(fn_sig_id)),
                     )?;
+                    self.len_imported_funcs += 1;
                 }
                 ImportSectionEntryType::Table(table_type) => {
                     let table_decl = TableDecl::try_from(table_type)?;
+                    table_decl.check_limits(&self.limits)?;
                     builder.import_table(
                         import_name.into(),
                         table_decl.into_inner(),
@@ -419,6 +549,7 @@ impl ParseContext {
                 }
                 ImportSectionEntryType::Memory(memory_type) => {
                     let memory_decl = LinearMemoryDecl::try_from(memory_type)?;
+                    memory_decl.check_limits(&self.limits)?;
                     builder.import_memory(
                         import_name.into(),
                         memory_decl.into_inner(),
@@ -472,6 +603,7 @@ impl ParseContext {
         for table_type in reader {
             let table_type = table_type?;
             let table_decl = TableDecl::try_from(table_type)?;
+            table_decl.check_limits(&self.limits)?;
             builder.push_table(table_decl.into_inner())?;
         }
         Ok(())
@@ -488,6 +620,7 @@ impl ParseContext {
         for memory_type in reader {
             let memory_type = memory_type?;
             let memory_decl = LinearMemoryDecl::try_from(memory_type)?;
+            memory_decl.check_limits(&self.limits)?;
             builder.push_memory(memory_decl.into_inner())?;
         }
         Ok(())
@@ -606,6 +739,9 @@ impl ParseContext {
             .code_section_start(total_count_bodies, &range)?;
         let (module_view, mut fn_builder) = self.builder.code_section()?;
         fn_builder.reserve(total_count_bodies);
+        // All function bodies of the code section reuse the same memory
+        // for their intermediate construction state.
+        let mut arena = IrArena::new();
         let mut buffer = <Vec<u8>>::new();
         let mut eof = false;
         let mut count_bodies = 0;
@@ -624,19 +760,39 @@ impl ParseContext {
                     consumed,
                     payload: Payload::CodeSectionEntry(function_body),
                 } => {
-                    let range = function_body.get_binary_reader().range();
+                    // The consumed bytes include the size prefix of the
+                    // function body which is part of the translated buffer.
+                    let range = wasmparser::Range {
+                        start: function_body.range().end - consumed,
+                        end: function_body.range().end,
+                    };
                     let fn_validator = self.validator.code_section_entry()?;
-                    let func = Func::from_raw(RawIdx::from_u32(count_bodies));
+                    let func = Func::from_raw(RawIdx::from_u32(
+                        self.len_imported_funcs + count_bodies,
+                    ));
                     let new_buffer = buffer.drain(consumed..).collect();
                     let fn_buffer = core::mem::replace(&mut buffer, new_buffer);
-                    let func_body = translate_function_body(
+                    let translation = translate_function_body(
                         range,
-                        fn_buffer,
+                        &fn_buffer,
                         fn_validator,
                         func,
                         module_view,
+                        &self.budget,
+                        &mut arena,
                     )?;
-                    fn_builder.push_body(func, func_body)?;
+                    match translation {
+                        Translation::Body(func_body) => {
+                            fn_builder.push_body(func, *func_body)?;
+                        }
+                        Translation::Bailout(_) => {
+                            let func_body =
+                                interpreter_only_body(func, module_view)?;
+                            fn_builder.push_interpreter_only_body(
+                                func, func_body, fn_buffer,
+                            )?;
+                        }
+                    }
                     count_bodies += 1;
                     // Cut away the parts from the intermediate buffer that have already been parsed.
                     // buffer.drain(..consumed); // <- Not required since we are currently swapping out the buffer.
@@ -665,6 +821,30 @@ impl ParseContext {
         Ok(())
     }
 
+    /// Parses the Wasm `producers` custom section and feeds its contents into the `module`.
+    ///
+    /// # Errors
+    ///
+    /// - If the `producers` custom section is malformed.
+    /// - If the module has multiple `producers` custom sections.
+    fn parse_producers_section(
+        &mut self,
+        data: &[u8],
+        data_offset: usize,
+    ) -> Result<(), Error> {
+        let reader = ProducersSectionReader::new(data, data_offset)?;
+        let mut producers = Producers::default();
+        for field in reader {
+            let field = field?;
+            for value in field.get_producer_field_values_reader()? {
+                let value = value?;
+                producers.push(field.name, value.name, value.version);
+            }
+        }
+        self.builder.set_producers(producers)?;
+        Ok(())
+    }
+
     fn parse_data_section(
         &mut self,
         reader: DataSectionReader,
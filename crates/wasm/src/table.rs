@@ -16,23 +16,71 @@ use super::{Error, InitExpr};
 use core::convert::TryFrom;
 use derive_more::Display;
 use entity::RawIdx;
-use ir::primitive::{Func, Table};
+use ir::{
+    primitive::{Func, Table},
+    Diagnostic,
+    ToDiagnostic,
+};
+use module::ResourceLimits;
 
 /// An error that might occur while parsing or validating tables or table elements.
 #[derive(Debug, Display, PartialEq, Eq)]
 pub enum TableError {
     #[display(fmt = "encountered invalid table element type: {:?}", _0)]
     InvalidTableElementType(wasmparser::Type),
+    #[display(fmt = "encountered invalid element segment type: {:?}", _0)]
+    InvalidElementSegmentType(wasmparser::Type),
     #[display(fmt = "encountered unsupported null element item")]
     UnsupportedNullElementItem,
     #[display(fmt = "encountered unsupported passive table element")]
     UnsupportedPassiveElement,
     #[display(fmt = "encountered unsupported declared table element")]
     UnsupportedDeclaredElement,
+    #[display(
+        fmt = "table of {} initial elements exceeds the limit of {} elements",
+        size,
+        limit
+    )]
+    TableLimitExceeded { size: u32, limit: u32 },
 }
 
 impl std::error::Error for TableError {}
 
+impl ToDiagnostic for TableError {
+    fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            Self::InvalidTableElementType(element_type) => {
+                Diagnostic::new("wasm.invalid_table_element_type", self)
+                    .with_parameter(
+                        "element_type",
+                        format!("{:?}", element_type),
+                    )
+            }
+            Self::InvalidElementSegmentType(element_type) => {
+                Diagnostic::new("wasm.invalid_element_segment_type", self)
+                    .with_parameter(
+                        "element_type",
+                        format!("{:?}", element_type),
+                    )
+            }
+            Self::UnsupportedNullElementItem => {
+                Diagnostic::new("wasm.unsupported_null_element_item", self)
+            }
+            Self::UnsupportedPassiveElement => {
+                Diagnostic::new("wasm.unsupported_passive_element", self)
+            }
+            Self::UnsupportedDeclaredElement => {
+                Diagnostic::new("wasm.unsupported_declared_element", self)
+            }
+            Self::TableLimitExceeded { size, limit } => {
+                Diagnostic::new("wasm.table_limit_exceeded", self)
+                    .with_parameter("size", size)
+                    .with_parameter("limit", limit)
+            }
+        }
+    }
+}
+
 /// A Wasm table declaration.
 #[derive(Debug)]
 pub struct TableDecl {
@@ -44,6 +92,20 @@ impl TableDecl {
     pub fn into_inner(self) -> module::primitive::TableDecl {
         self.inner
     }
+
+    /// Checks the initial size of the table against the limits.
+    ///
+    /// # Errors
+    ///
+    /// If the table declares more initial elements than the limits allow.
+    pub fn check_limits(&self, limits: &ResourceLimits) -> Result<(), Error> {
+        let size = self.inner.initial_size();
+        let limit = limits.max_table_size();
+        if size > limit {
+            return Err(TableError::TableLimitExceeded { size, limit }.into())
+        }
+        Ok(())
+    }
 }
 
 impl TryFrom<wasmparser::TableType> for TableDecl {
@@ -105,6 +167,11 @@ impl<'a> TryFrom<wasmparser::Element<'a>> for Element<'a> {
 
     fn try_from(element: wasmparser::Element<'a>) -> Result<Self, Self::Error> {
         use wasmparser::ElementKind;
+        // All tables store function references so that only element
+        // segments of function references are compatible with them.
+        if element.ty != wasmparser::Type::FuncRef {
+            return Err(TableError::InvalidElementSegmentType(element.ty).into())
+        }
         match element.kind {
             ElementKind::Passive => {
                 Err(TableError::UnsupportedPassiveElement).map_err(Into::into)
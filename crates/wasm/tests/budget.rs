@@ -0,0 +1,113 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use entity::RawIdx;
+use ir::primitive::Func;
+use runwell_wasm::{parse_with_budget, TranslationBudget};
+
+/// Parses the Wasm text format input using the given translation budget.
+fn parse_wat(
+    wat: &str,
+    budget: TranslationBudget,
+) -> Result<module::Module, runwell_wasm::Error> {
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut buffer = Vec::new();
+    parse_with_budget(&mut &wasm[..], &mut buffer, budget)
+}
+
+#[test]
+fn functions_exceeding_budget_are_interpreter_only() {
+    let wat = r#"
+        (module
+            (func (param i32) (result i32)
+                local.get 0
+            )
+            (func (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add
+                i32.const 2
+                i32.mul
+            )
+            (func (param i32) (result i32) (local i64 i64 i64 i64)
+                local.get 0
+            )
+        )
+    "#;
+    let mut budget = TranslationBudget::default();
+    budget.set_max_operators(3);
+    budget.set_max_locals(2);
+    let module = parse_wat(wat, budget).unwrap();
+    let func = |n| Func::from_raw(RawIdx::from_u32(n));
+    assert!(!module.is_interpreter_only(func(0)));
+    assert!(module.get_interpreter_only_source(func(0)).is_none());
+    assert!(module.is_interpreter_only(func(1)));
+    assert!(module.get_interpreter_only_source(func(1)).is_some());
+    assert!(module.is_interpreter_only(func(2)));
+    // Without budget all functions are translated.
+    let module = parse_wat(wat, TranslationBudget::default()).unwrap();
+    assert!((0..3).all(|n| !module.is_interpreter_only(func(n))));
+}
+
+#[test]
+fn functions_exceeding_budget_are_still_validated() {
+    let wat = r#"
+        (module
+            (func (result i32)
+                i32.const 1
+                i32.const 2
+                i32.const 3
+                i32.add
+                i64.add
+            )
+        )
+    "#;
+    let mut budget = TranslationBudget::default();
+    budget.set_max_operators(1);
+    assert!(parse_wat(wat, budget).is_err());
+}
+
+#[test]
+fn step_budget_bails_out_deterministically() {
+    let wat = r#"
+        (module
+            (func (param i32) (result i32)
+                local.get 0
+            )
+            (func (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add
+                i32.const 2
+                i32.add
+                i32.const 3
+                i32.add
+                i32.const 4
+                i32.add
+                i32.const 5
+                i32.add
+                i32.const 6
+                i32.add
+            )
+        )
+    "#;
+    let mut budget = TranslationBudget::default();
+    budget.set_max_steps(20);
+    let first = parse_wat(wat, budget.clone()).unwrap();
+    let second = parse_wat(wat, budget).unwrap();
+    let func = |n| Func::from_raw(RawIdx::from_u32(n));
+    assert!(!first.is_interpreter_only(func(0)));
+    assert!(first.is_interpreter_only(func(1)));
+    assert_eq!(first.to_string(), second.to_string());
+}
@@ -0,0 +1,68 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use module::ModuleFunction;
+use runwell_wasm::parse;
+
+#[test]
+fn functions_iterates_imported_and_internal_functions() {
+    let wat = r#"
+        (module
+            (import "env" "log" (func $log (param i32)))
+            (import "env" "now" (func $now (result i64)))
+            (func (export "run") (param i32) (result i32)
+                local.get 0
+                call $log
+                local.get 0
+            )
+        )
+    "#;
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut buffer = Vec::new();
+    let module = parse(&mut &wasm[..], &mut buffer).unwrap();
+    let functions = module.functions().collect::<Vec<_>>();
+    assert_eq!(functions.len(), 3);
+    for (n, function) in functions.iter().enumerate() {
+        assert_eq!(function.idx().into_raw().into_u32(), n as u32);
+    }
+    let import_names = functions
+        .iter()
+        .map(|function| {
+            function
+                .import_name()
+                .map(|name| (name.module_name(), name.field_name()))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        import_names,
+        vec![Some(("env", "log")), Some(("env", "now")), None]
+    );
+    match functions[2] {
+        ModuleFunction::Internal(function) => {
+            assert_eq!(function.inputs().len(), 1);
+            assert_eq!(function.outputs().len(), 1);
+        }
+        ModuleFunction::Imported(_) => panic!("expected an internal function"),
+    }
+    let imported = functions[0].idx();
+    assert!(functions[0].body().is_none());
+    assert!(module.get_function(imported).is_none());
+    assert_eq!(
+        module
+            .get_function_import(imported)
+            .map(|name| name.field_name()),
+        Some("log")
+    );
+    assert_eq!(module.internal_functions().count(), 1);
+}
@@ -0,0 +1,55 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ir::ToDiagnostic;
+use module::ResourceLimits;
+use runwell_wasm::parse_with_limits;
+
+/// Parses the Wasm text format input using the given resource limits.
+fn parse_wat(
+    wat: &str,
+    limits: ResourceLimits,
+) -> Result<module::Module, runwell_wasm::Error> {
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut buffer = Vec::new();
+    parse_with_limits(&mut &wasm[..], &mut buffer, Default::default(), limits)
+}
+
+#[test]
+fn declared_sizes_are_checked_against_limits() {
+    let limits = |max_memory_pages, max_table_size| {
+        let mut limits = ResourceLimits::new();
+        limits.set_max_memory_pages(max_memory_pages);
+        limits.set_max_table_size(max_table_size);
+        limits
+    };
+    let check = |wat: &str, limits, code: &str, limit: &str| {
+        let diagnostic = parse_wat(wat, limits).unwrap_err().to_diagnostic();
+        assert_eq!(diagnostic.code(), code);
+        assert_eq!(diagnostic.parameter("limit"), Some(limit));
+    };
+    let memory = r#"(module (memory 2 10))"#;
+    let imported_memory = r#"(module (import "env" "mem" (memory 2)))"#;
+    let table = r#"(module (table 8 funcref))"#;
+    let imported_table = r#"(module (import "env" "tab" (table 8 funcref)))"#;
+    for wat in &[memory, imported_memory, table, imported_table] {
+        assert!(parse_wat(wat, limits(2, 8)).is_ok());
+    }
+    // Only the initial sizes are checked since the maximum sizes are
+    // enforced upon growth.
+    check(memory, limits(1, 8), "wasm.memory_limit_exceeded", "1");
+    check(imported_memory, limits(1, 8), "wasm.memory_limit_exceeded", "1");
+    check(table, limits(2, 7), "wasm.table_limit_exceeded", "7");
+    check(imported_table, limits(2, 7), "wasm.table_limit_exceeded", "7");
+}
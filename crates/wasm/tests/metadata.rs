@@ -0,0 +1,62 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use runwell_wasm::parse;
+
+#[test]
+fn module_metadata_can_be_exported() {
+    let wat = r#"
+        (module
+            (import "env" "log" (func $log (param i32)))
+            (memory 1 2)
+            (global $counter (mut i32) (i32.const 0))
+            (func (export "run") (param i32) (result i32)
+                local.get 0
+                call $log
+                global.get $counter
+                i32.const 1
+                i32.add
+                global.set $counter
+                local.get 0
+                i32.load
+            )
+        )
+    "#;
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut buffer = Vec::new();
+    let module = parse(&mut &wasm[..], &mut buffer).unwrap();
+    let metadata = module.export_metadata();
+    assert!(metadata.starts_with(concat!(
+        r#"{"functions":[{"index":0,"type":0,"inputs":["i32"],"outputs":[],"#,
+        r#""import":{"module":"env","field":"log"},"export":null,"#,
+    )));
+    assert!(metadata.contains(concat!(
+        r#""inputs":["i32"],"outputs":["i32"],"import":null,"#,
+        r#""export":"run","interpreter_only":false,"calls":[0],"#,
+        r#""indirect_calls":[],"memories":[0],"tables":[],"#,
+        r#""globals_read":[0],"globals_written":[0]}"#,
+    )));
+    assert!(metadata.contains(concat!(
+        r#""memories":[{"index":0,"initial_pages":1,"maximum_pages":2,"#,
+        r#""import":null,"export":null}]"#,
+    )));
+    assert!(metadata.contains(concat!(
+        r#""globals":[{"index":0,"type":"i32","mutable":true,"#,
+        r#""import":null,"export":null}]"#,
+    )));
+    assert!(metadata.ends_with(concat!(
+        r#""imports":[{"kind":"function","index":0,"module":"env","#,
+        r#""field":"log"}],"start":null}"#,
+    )));
+}
@@ -0,0 +1,74 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use module::CheckedArithmetic;
+use runwell_wasm::{parse_with_budget, CompilationReport, TranslationBudget};
+
+/// Compiles the Wasm text format input and returns its compilation report.
+fn compile(wat: &str, budget: TranslationBudget) -> CompilationReport {
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut buffer = Vec::new();
+    let module =
+        parse_with_budget(&mut &wasm[..], &mut buffer, budget.clone()).unwrap();
+    CompilationReport::new(&wasm, &budget, &module)
+}
+
+#[test]
+fn compilation_reports_are_reproducible() {
+    let wat = r#"
+        (module
+            (func (param i32) (result i32)
+                local.get 0
+                i32.const 1
+                i32.add
+            )
+        )
+    "#;
+    let report = compile(wat, TranslationBudget::default());
+    assert_eq!(report, compile(wat, TranslationBudget::default()));
+    assert_eq!(report.engine_version(), env!("CARGO_PKG_VERSION"));
+    assert!(report.passes().is_empty());
+    // Different inputs yield different input and output hashes.
+    let other = compile(&wat.replace("i32.add", "i32.sub"), Default::default());
+    assert_ne!(report.input_hash(), other.input_hash());
+    assert_ne!(report.output_hash(), other.output_hash());
+    // Interpreter-only functions change the output but not the input hash.
+    let mut budget = TranslationBudget::default();
+    budget.set_max_operators(0);
+    let baseline = compile(wat, budget);
+    assert_eq!(report.input_hash(), baseline.input_hash());
+    assert_ne!(report.output_hash(), baseline.output_hash());
+}
+
+#[test]
+fn compilation_reports_record_passes() {
+    let wat = r#"
+        (module
+            (func (param i32) (result i32)
+                local.get 0
+            )
+        )
+    "#;
+    let wasm = wat::parse_str(wat).unwrap();
+    let mut buffer = Vec::new();
+    let budget = TranslationBudget::default();
+    let mut module =
+        parse_with_budget(&mut &wasm[..], &mut buffer, budget.clone()).unwrap();
+    module
+        .check_arithmetic(&CheckedArithmetic::default())
+        .unwrap();
+    let report = CompilationReport::new(&wasm, &budget, &module);
+    assert_eq!(report.passes(), &["check-arithmetic"]);
+    assert!(report.to_string().contains("passes = [check-arithmetic]"));
+}
@@ -0,0 +1,67 @@
+// Copyright 2021 Robin Freyler
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ir::primitive::{IntType, Type};
+use module::primitive::FunctionType;
+use runwell_wasm::translate_single_function;
+
+/// Returns the function type with the given inputs and outputs.
+fn func_type(inputs: &[Type], outputs: &[Type]) -> FunctionType {
+    let mut builder = FunctionType::build();
+    for input in inputs {
+        builder.push_input(*input);
+    }
+    for output in outputs {
+        builder.push_output(*output);
+    }
+    builder.finalize()
+}
+
+#[test]
+fn translate_single_function_works() {
+    let i32 = Type::from(IntType::I32);
+    let func_type = func_type(&[i32, i32], &[i32]);
+    // No locals, `local.get 0`, `local.get 1`, `i32.add`, `end`
+    let body = [0x00, 0x20, 0x00, 0x20, 0x01, 0x6A, 0x0B];
+    let func_body = translate_single_function(&func_type, &body).unwrap();
+    assert!(func_body.to_string().contains("iadd<i32>"));
+}
+
+#[test]
+fn translate_single_recursive_function_works() {
+    let i32 = Type::from(IntType::I32);
+    let func_type = func_type(&[i32], &[i32]);
+    // No locals, `local.get 0`, `call 0`, `end`
+    let body = [0x00, 0x20, 0x00, 0x10, 0x00, 0x0B];
+    let func_body = translate_single_function(&func_type, &body).unwrap();
+    assert!(func_body.to_string().contains("call func0"));
+}
+
+#[test]
+fn translate_single_function_validates() {
+    let i32 = Type::from(IntType::I32);
+    let i64 = Type::from(IntType::I64);
+    // No locals, `local.get 0`, `end` with mismatching result type.
+    let body = [0x00, 0x20, 0x00, 0x0B];
+    assert!(
+        translate_single_function(&func_type(&[i32], &[i64]), &body).is_err()
+    );
+    // Global, memory and table accesses are unavailable.
+    let body = [0x00, 0x23, 0x00, 0x0B];
+    assert!(translate_single_function(&func_type(&[], &[i32]), &body).is_err());
+    // Runwell types without Wasm equivalent are unsupported.
+    let body = [0x00, 0x0B];
+    assert!(translate_single_function(&func_type(&[Type::Bool], &[]), &body)
+        .is_err());
+}